@@ -6,4 +6,19 @@ fn main() {
         .input("src/core/handlers/onnx/protos/onnx.proto")
         .cargo_out_dir("onnx-protos")
         .run_from_script();
+
+    // Generate the tensorflow protobuf files
+    protobuf_codegen::Codegen::new()
+        .pure()
+        .includes(["src"])
+        .input("src/core/handlers/tensorflow/protos/tensorflow.proto")
+        .cargo_out_dir("tensorflow-protos")
+        .run_from_script();
+
+    // Generate the gRPC service of the serve command, with a bundled protoc
+    #[cfg(feature = "grpc")]
+    {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+        tonic_prost_build::compile_protos("src/cli/serve/protos/tensor_man.proto").unwrap();
+    }
 }
@@ -0,0 +1,81 @@
+use crate::core::{
+    badge::{Badge, BadgeFormat},
+    signing::{Failure, Verdict},
+};
+
+use super::{signing, BadgeArgs, VerifyArgs};
+
+// the fingerprint of the signer if the model is verified, the cause of the failure otherwise
+fn message_of(verdict: &Verdict) -> String {
+    let message = match verdict.failure {
+        None if verdict.exit_code == 0 => {
+            return match verdict.signers.iter().find(|signer| signer.trusted) {
+                Some(signer) => format!(
+                    "signed by {} ✓",
+                    signer.fingerprint.chars().take(16).collect::<String>()
+                ),
+                None => "signed ✓".to_string(),
+            };
+        }
+        Some(Failure::MissingSignature) => "unsigned",
+        Some(Failure::KeyMismatch) => "untrusted signature",
+        Some(Failure::InvalidSignature) => "invalid signature",
+        Some(Failure::ModifiedFile | Failure::MissingFile) => "modified",
+        Some(Failure::Expired) => "expired",
+        Some(Failure::PolicyViolation) => "policy violation",
+        None => "verification failed",
+    };
+    format!("{} ✗", message)
+}
+
+pub(crate) fn badge(args: BadgeArgs) -> anyhow::Result<()> {
+    // the format given, or the one of the output file extension, or SVG
+    let format = match (&args.badge_format, &args.output) {
+        (Some(format), _) => format.clone(),
+        (None, Some(output)) => output
+            .extension()
+            .and_then(|ext| BadgeFormat::of_extension(&ext.to_string_lossy()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "can't determine the badge format of {}, use --badge-format",
+                    output.display()
+                )
+            })?,
+        (None, None) => BadgeFormat::Svg,
+    };
+
+    let file_path = args
+        .file_path
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("can't read {}: {}", args.file_path.display(), e))?;
+    let mut verify_args = VerifyArgs::new(file_path, args.key_path.clone());
+    verify_args.format = args.format.clone();
+    verify_args.policy = args.policy.clone();
+    verify_args.keyring = args.keyring.clone();
+    verify_args.signature = args.signature.clone();
+
+    // the badge tells why the verification failed, it doesn't fail the command
+    let (verdict, result) = signing::verdict_of(verify_args);
+    if let Err(e) = &result {
+        tracing::warn!("{}", e);
+    }
+    let badge = Badge {
+        label: args.label.clone(),
+        message: message_of(&verdict),
+        passing: result.is_ok(),
+    };
+
+    let rendered = badge.render(&format);
+    if let Some(output) = &args.output {
+        std::fs::write(output, rendered)?;
+        tracing::info!(
+            "Badge \"{}\" written to {}",
+            badge.message,
+            output.display()
+        );
+    } else {
+        print!("{}", rendered);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::core::{
+    checksums,
+    handlers::{self, replace_file, safetensors, Scope},
+};
+
+use super::CanonicalizeArgs;
+
+pub(crate) fn canonicalize(args: CanonicalizeArgs) -> anyhow::Result<()> {
+    if safetensors::is_safetensors_index(&args.file_path) {
+        anyhow::bail!("canonicalize each of the files referenced by the index instead");
+    }
+    let handler = handlers::handler_for(args.format, &args.file_path, Scope::Inspection)?;
+    let signed = handler
+        .embedded_signature(&args.file_path)
+        .is_ok_and(|embedded| embedded.manifest.is_some());
+
+    let mut content = handler.canonical_content(&args.file_path)?;
+    let output = args.output.unwrap_or(args.file_path.clone());
+    if output.exists() {
+        replace_file(&output, &mut content)?;
+    } else {
+        std::io::copy(&mut content, &mut std::fs::File::create(&output)?)?;
+    }
+
+    println!(
+        "{} canonicalized to {}\n\nsha256: {}",
+        args.file_path.display(),
+        output.display(),
+        checksums::sha256_of(&output)?
+    );
+    if signed {
+        tracing::warn!("the embedded signature has been removed, sign the canonical file again");
+    }
+
+    Ok(())
+}
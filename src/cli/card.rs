@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+
+use crate::core::{
+    card::{CardSignature, CardSigner, ModelCard},
+    handlers::{self, Scope},
+    signing::{Envelope, Manifest},
+    FileType, Inspection,
+};
+
+use super::{
+    signing::{embedded_signature_for, envelope_path, signature_path},
+    CardArgs, DetailLevel,
+};
+
+// the model itself, or the supported files of a folder that is not a model
+pub(super) fn model_paths(format: Option<FileType>, path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() || handlers::handler_for(format.clone(), path, Scope::Inspection).is_ok() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = vec![];
+    for entry in glob(&path.join("**/*").to_string_lossy())? {
+        let entry = entry?;
+        // the shards referenced by an index are listed themselves, and split GGUF models
+        // through their first part
+        if entry.is_file()
+            && !handlers::safetensors::is_safetensors_index(&entry)
+            && handlers::gguf::split_paths(&entry).is_none_or(|parts| parts[0] == entry)
+            && handlers::handler_for(format.clone(), &entry, Scope::Inspection).is_ok()
+        {
+            paths.push(entry);
+        }
+    }
+    if paths.is_empty() {
+        anyhow::bail!("no supported files found in {}", path.display());
+    }
+    Ok(paths)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+fn signers_of(manifest: &Manifest) -> Vec<CardSigner> {
+    manifest
+        .signatures
+        .iter()
+        .map(|signer| CardSigner {
+            fingerprint: signer.public_key[..16].to_string(),
+            algorithm: format!("{:?}", signer.algorithm),
+            signed_at: signer.signed_at.clone(),
+        })
+        .collect()
+}
+
+// looks for a signature manifest or a DSSE envelope next to the model, or a signature embedded
+// in it, without verifying them
+pub(super) fn signature_of(
+    format: Option<FileType>,
+    file_path: &Path,
+    base_path: &Path,
+) -> anyhow::Result<Option<CardSignature>> {
+    let path = signature_path(file_path, None);
+    if path.is_file() {
+        let manifest = Manifest::from_signature_str(base_path, &std::fs::read_to_string(&path)?)?;
+        return Ok(Some(CardSignature {
+            source: format!("in `{}`", file_name(&path)),
+            signers: signers_of(&manifest),
+        }));
+    }
+
+    let path = envelope_path(file_path, None);
+    if path.is_file() {
+        let envelope: Envelope = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        let signed_at = envelope.statement()?.predicate.signed_at;
+        return Ok(Some(CardSignature {
+            source: format!("a DSSE envelope in `{}`", file_name(&path)),
+            signers: envelope
+                .signatures
+                .iter()
+                .map(|signature| CardSigner {
+                    fingerprint: signature.keyid.chars().take(16).collect(),
+                    algorithm: "-".to_string(),
+                    signed_at: signed_at.clone(),
+                })
+                .collect(),
+        }));
+    }
+
+    if file_path.is_file() {
+        if let Some(manifest) =
+            embedded_signature_for(format, file_path).and_then(|embedded| embedded.manifest)
+        {
+            let manifest = Manifest::from_signature_str(base_path, &manifest)?;
+            return Ok(Some(CardSignature {
+                source: "embedded in the model metadata".to_string(),
+                signers: signers_of(&manifest),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+// LICENSE, LICENCE and COPYING files of the model folder
+fn license_files(base_path: &Path) -> anyhow::Result<Vec<String>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(base_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let upper = name.to_ascii_uppercase();
+        if entry.path().is_file()
+            && ["LICENSE", "LICENCE", "COPYING"]
+                .iter()
+                .any(|prefix| upper.starts_with(prefix))
+        {
+            files.push(name);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// the folder of a model file, or the model folder itself
+pub(super) fn base_path_of(file_path: &Path) -> PathBuf {
+    if file_path.is_file() {
+        file_path.parent().unwrap_or(Path::new(".")).to_path_buf()
+    } else {
+        file_path.to_path_buf()
+    }
+}
+
+// the name given, or the general.name metadata, or the name of the model file or folder
+pub(super) fn model_name(
+    name: Option<String>,
+    file_path: &Path,
+    inspections: &[Inspection],
+) -> String {
+    name.or_else(|| {
+        inspections
+            .iter()
+            .find_map(|inspection| inspection.metadata.get("general.name").cloned())
+    })
+    .unwrap_or_else(|| {
+        let path = file_path.canonicalize().unwrap_or(file_path.to_path_buf());
+        path.file_stem()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    })
+}
+
+pub(crate) fn card(args: CardArgs) -> anyhow::Result<()> {
+    let base_path = base_path_of(&args.file_path);
+
+    let mut card = ModelCard::default();
+    for path in model_paths(args.format.clone(), &args.file_path)? {
+        let handler = handlers::handler_for(args.format.clone(), &path, Scope::Inspection)?;
+        card.inspections
+            .push(handler.inspect(&path, DetailLevel::Full, None)?);
+    }
+
+    let config_path = base_path.join("config.json");
+    if config_path.is_file() {
+        card.config = serde_json::from_str(&std::fs::read_to_string(&config_path)?).ok();
+    }
+    card.license_files = license_files(&base_path)?;
+    card.signature = signature_of(args.format.clone(), &args.file_path, &base_path)?;
+    card.name = model_name(args.name.clone(), &args.file_path, &card.inspections);
+
+    let markdown = card.render();
+    if let Some(output) = &args.output {
+        std::fs::write(output, markdown)?;
+        tracing::info!("Model card written to {}", output.display());
+    } else {
+        print!("{}", markdown);
+    }
+
+    Ok(())
+}
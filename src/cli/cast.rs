@@ -0,0 +1,38 @@
+use crate::core::handlers::safetensors::{self, CastFilter};
+
+use super::CastArgs;
+
+pub(crate) fn cast(args: CastArgs) -> anyhow::Result<()> {
+    if !args
+        .file_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("safetensors"))
+    {
+        anyhow::bail!("only safetensors files can be cast");
+    }
+    let before = std::fs::metadata(&args.file_path)?.len();
+    let filter = CastFilter {
+        include: args.include,
+        exclude: args.exclude,
+    };
+    let tensors = safetensors::cast(&args.file_path, &args.output, args.dtype, &filter)?;
+
+    let cast: Vec<_> = tensors.iter().filter(|tensor| tensor.is_cast()).collect();
+    if args.verbose {
+        for tensor in &cast {
+            println!("  {:<60} {} -> {}", tensor.name, tensor.from, tensor.to);
+        }
+        println!();
+    }
+    println!(
+        "cast {} of {} tensors to {:?}, {} -> {}, saved to {}",
+        cast.len(),
+        tensors.len(),
+        args.dtype,
+        humansize::format_size(before, humansize::DECIMAL),
+        humansize::format_size(std::fs::metadata(&args.output)?.len(), humansize::DECIMAL),
+        args.output.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use crate::core::{
+    config_check::{self, ConfigCheck},
+    handlers::{self, Scope},
+};
+
+use super::{
+    card::{base_path_of, model_paths},
+    CheckConfigArgs, DetailLevel,
+};
+
+fn print_check(check: &ConfigCheck) {
+    let status = match (&check.actual, check.is_ok()) {
+        (None, _) => "no matching tensor".to_string(),
+        (Some(_), true) => "ok".to_string(),
+        (Some(actual), false) => format!("MISMATCH, found {}", actual),
+    };
+    println!(
+        "  {:<30} {:>8}  {}{}",
+        check.key,
+        check.expected,
+        status,
+        check
+            .tensor
+            .as_ref()
+            .map(|tensor| format!(" ({})", tensor))
+            .unwrap_or_default()
+    );
+}
+
+pub(crate) fn check_config(args: CheckConfigArgs) -> anyhow::Result<()> {
+    let base_path = base_path_of(&args.file_path);
+    let config_path = base_path.join("config.json");
+    let config: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&config_path)
+            .map_err(|e| anyhow::anyhow!("can't read {}: {}", config_path.display(), e))?,
+    )?;
+
+    let mut tensors = vec![];
+    for path in model_paths(None, &base_path)? {
+        let handler = handlers::handler_for(None, &path, Scope::Inspection)?;
+        if handler.file_type().is_safetensors() {
+            let inspection = handler.inspect(&path, DetailLevel::Full, None)?;
+            tensors.extend(inspection.tensors.unwrap_or_default());
+        }
+    }
+    if tensors.is_empty() {
+        anyhow::bail!("no safetensors weights found in {}", base_path.display());
+    }
+
+    let checks = config_check::check(&config, &tensors);
+    if checks.is_empty() {
+        anyhow::bail!(
+            "{} has none of the hyperparameters that can be checked",
+            config_path.display()
+        );
+    }
+    tracing::info!(
+        "checking {} against {} tensors",
+        config_path.display(),
+        tensors.len()
+    );
+    for check in &checks {
+        print_check(check);
+    }
+
+    if let Some(json_path) = &args.to_json {
+        std::fs::write(json_path, serde_json::to_string_pretty(&checks)?)?;
+        tracing::info!("saved to {:?}", json_path);
+    }
+
+    let mismatches = checks.iter().filter(|check| !check.is_ok()).count();
+    if mismatches > 0 {
+        anyhow::bail!(
+            "{} of {} hyperparameters of config.json don't match the weights",
+            mismatches,
+            checks.len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use crate::core::{
+    handlers::Scope,
+    sarif::{self, Finding, Rule},
+    stats::NonFinite,
+};
+
+use super::CheckNanArgs;
+
+const RULES: &[Rule] = &[Rule {
+    id: "non-finite-values",
+    description: "A tensor contains NaN or Inf values.",
+    level: "error",
+}];
+
+pub(crate) fn check_nan(args: CheckNanArgs) -> anyhow::Result<()> {
+    let sarif_path = args.sarif.clone();
+    let uri = sarif::artifact_uri(&args.file_path);
+
+    let result = scan(args);
+    if let Some(sarif_path) = sarif_path {
+        let (findings, error) = match &result {
+            Ok((found, _)) => (
+                found
+                    .iter()
+                    .map(|tensor| Finding {
+                        rule_id: RULES[0].id,
+                        message: format!(
+                            "tensor {} contains {} NaN and {} Inf values (first at element {})",
+                            tensor.id, tensor.nan_count, tensor.inf_count, tensor.first_index
+                        ),
+                        uri: uri.clone(),
+                        byte_offset: tensor.first_offset,
+                    })
+                    .collect(),
+                None,
+            ),
+            Err(e) => (vec![], Some(e.to_string())),
+        };
+        sarif::save(&sarif_path, RULES, &findings, error)?;
+    }
+
+    let (found, num_tensors) = result?;
+    if found.is_empty() {
+        println!("no NaN/Inf values found in {} tensors", num_tensors);
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "found NaN/Inf values in {} of {} tensors",
+            found.len(),
+            num_tensors
+        ))
+    }
+}
+
+// returns the tensors with NaN/Inf values and the number of tensors scanned
+fn scan(args: CheckNanArgs) -> anyhow::Result<(Vec<NonFinite>, usize)> {
+    let handler =
+        crate::core::handlers::handler_for(args.format, &args.file_path, Scope::Inspection)?;
+
+    tracing::info!(
+        "Scanning {:?} (format={}{}) for NaN/Inf values ...",
+        args.file_path,
+        handler.file_type(),
+        args.filter
+            .as_ref()
+            .map(|f| format!(" filter_by={:?}", f))
+            .unwrap_or("".to_string())
+    );
+
+    let tensors = handler.tensor_data(&args.file_path, args.filter)?;
+    let found = crate::core::stats::find_non_finite(&tensors);
+
+    for tensor in &found {
+        println!(
+            "  {}: {} NaN, {} Inf (first at element {}{})",
+            tensor.id,
+            tensor.nan_count,
+            tensor.inf_count,
+            tensor.first_index,
+            tensor
+                .first_offset
+                .map(|offset| format!(", offset 0x{:x}", offset))
+                .unwrap_or_default()
+        );
+    }
+
+    Ok((found, tensors.len()))
+}
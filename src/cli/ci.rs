@@ -0,0 +1,339 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::core::{
+    scan::{self, Safety},
+    validate,
+};
+
+use super::{signing, CiArgs, VerifyArgs};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Error => write!(f, "error"),
+            Level::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+// something wrong with a model, reported as an annotation on GitHub
+#[derive(Debug)]
+struct Problem {
+    path: PathBuf,
+    level: Level,
+    // rule or cause of the failure
+    title: String,
+    message: String,
+}
+
+impl Problem {
+    fn error(path: &Path, title: &str, message: impl ToString) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            level: Level::Error,
+            title: title.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    // the workflow command creating the annotation, with its special characters escaped
+    fn annotation(&self) -> String {
+        let escape = |value: &str| {
+            value
+                .replace('%', "%25")
+                .replace('\r', "%0D")
+                .replace('\n', "%0A")
+        };
+        let escape_property = |value: &str| escape(value).replace(':', "%3A").replace(',', "%2C");
+        format!(
+            "::{} file={},title={}::{}",
+            self.level,
+            escape_property(&self.path.to_string_lossy()),
+            escape_property(&self.title),
+            escape(&self.message)
+        )
+    }
+}
+
+// the files changed since the revision, relative to the current folder
+fn changed_since(revision: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let git = |args: &[&str]| -> anyhow::Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .output()
+            .map_err(|e| anyhow::anyhow!("can't run git: {}", e))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+
+    let top_level = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim());
+    let current_dir = std::env::current_dir()?;
+    // deleted files have nothing left to check
+    Ok(git(&["diff", "--name-only", "--diff-filter=d", revision])?
+        .lines()
+        .map(|name| {
+            let path = top_level.join(name);
+            path.strip_prefix(&current_dir)
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect())
+}
+
+// the model files among the paths, those of the folders included
+fn model_paths(paths: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut models = BTreeSet::new();
+    for path in paths {
+        if path.is_dir() {
+            let pattern = path.join("**").join("*");
+            models.extend(
+                glob::glob(&pattern.to_string_lossy())?
+                    .filter_map(Result::ok)
+                    .filter(|path| path.is_file() && scan::is_scannable(path)),
+            );
+        } else if path.is_file() && scan::is_scannable(path) {
+            models.insert(path.clone());
+        }
+    }
+    Ok(models.into_iter().collect())
+}
+
+// the file or folder whose signature covers the model: the file itself if it has its own
+// signature, embedded or not, else the closest folder with one
+fn signed_path(path: &Path) -> PathBuf {
+    if signing::signature_path(path, None).exists()
+        || signing::embedded_signature_for(None, path).is_some()
+    {
+        return path.to_path_buf();
+    }
+
+    path.canonicalize()
+        .ok()
+        .and_then(|canonical| {
+            canonical
+                .ancestors()
+                .skip(1)
+                .find(|folder| signing::signature_path(folder, None).exists())
+                .map(Path::to_path_buf)
+        })
+        // verifying the file tells the signature is missing
+        .unwrap_or(path.to_path_buf())
+}
+
+fn scan_problems(path: &Path) -> Vec<Problem> {
+    match scan::scan(path, false, false) {
+        Ok(scan) => scan
+            .issues
+            .into_iter()
+            .filter(|issue| issue.safety != Safety::Safe)
+            .map(|issue| Problem {
+                path: path.to_path_buf(),
+                level: if issue.safety == Safety::Dangerous {
+                    Level::Error
+                } else {
+                    Level::Warning
+                },
+                title: issue.rule_id.to_string(),
+                message: if issue.location.is_empty() {
+                    issue.message
+                } else {
+                    format!("{} ({})", issue.message, issue.location)
+                },
+            })
+            .collect(),
+        Err(e) => vec![Problem::error(path, "invalid-file", e)],
+    }
+}
+
+fn validate_problems(path: &Path) -> Vec<Problem> {
+    if !validate::is_validatable(path) {
+        return vec![];
+    }
+    match validate::validate(path) {
+        Ok(validation) => validation
+            .violations
+            .into_iter()
+            .map(|violation| Problem::error(path, violation.rule_id, violation.message))
+            .collect(),
+        Err(e) => vec![Problem::error(path, "invalid-file", e)],
+    }
+}
+
+fn verify_problems(args: &CiArgs, path: &Path) -> Vec<Problem> {
+    // verified by its canonical path, the folder of a bare file name being empty
+    let canonical = path.canonicalize().unwrap_or(path.to_path_buf());
+    let mut verify_args = VerifyArgs::new(canonical, args.key_path.clone());
+    verify_args.policy = args.policy.clone();
+    verify_args.keyring = args.keyring.clone();
+
+    // the signers are only logged at debug level, stdout is left to the problems and the summary
+    let (verdict, result) = signing::verdict_of(verify_args);
+    match result {
+        Ok(()) => vec![],
+        Err(e) => {
+            let title = verdict
+                .failure
+                .and_then(|failure| serde_json::to_value(failure).ok())
+                .and_then(|value| value.as_str().map(str::to_string))
+                .unwrap_or("verification-failed".to_string());
+            vec![Problem::error(path, &title, e)]
+        }
+    }
+}
+
+pub(crate) fn ci(args: CiArgs) -> anyhow::Result<()> {
+    let mut paths = args.file_paths.clone();
+    if let Some(revision) = &args.changed_since {
+        paths.extend(changed_since(revision)?);
+    }
+    let models = model_paths(&paths)?;
+    if models.is_empty() {
+        tracing::info!("No model files to check");
+        return Ok(());
+    }
+
+    let github = args.github || std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true");
+    let mut problems = vec![];
+    for path in &models {
+        tracing::info!("Checking {} ...", path.display());
+        problems.extend(scan_problems(path));
+        problems.extend(validate_problems(path));
+    }
+    if !args.no_verify {
+        // models of a signed folder are verified along with the whole folder
+        let signed: BTreeSet<PathBuf> = models.iter().map(|path| signed_path(path)).collect();
+        for path in &signed {
+            tracing::info!("Verifying {} ...", path.display());
+            problems.extend(verify_problems(&args, path));
+        }
+    }
+
+    for problem in &problems {
+        if github {
+            println!("{}", problem.annotation());
+        } else {
+            println!(
+                "{}: {}: [{}] {}",
+                problem.path.display(),
+                problem.level,
+                problem.title,
+                problem.message
+            );
+        }
+    }
+
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.level == Level::Error)
+        .count();
+    println!(
+        "\n{} model files checked: {} errors, {} warnings",
+        models.len(),
+        errors,
+        problems.len() - errors
+    );
+
+    if errors > 0 {
+        anyhow::bail!("{} errors found in the model files", errors);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation() {
+        let problem = Problem::error(
+            Path::new("models/a,b.gguf"),
+            "modified_file",
+            "100% modified:\nweights",
+        );
+        assert_eq!(
+            problem.annotation(),
+            "::error file=models/a%2Cb.gguf,title=modified_file::100%25 modified:%0Aweights"
+        );
+    }
+
+    #[test]
+    fn test_model_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("models/llama")).unwrap();
+        for name in [
+            "README.md",
+            "models/llama/model.gguf",
+            "models/llama/config.json",
+            "models/model.safetensors",
+        ] {
+            std::fs::write(root.join(name), b"").unwrap();
+        }
+        std::fs::write(root.join("models/tensor-man.signature"), b"{}").unwrap();
+
+        let models = model_paths(&[root.join("README.md"), root.join("models")]).unwrap();
+        assert_eq!(
+            models,
+            vec![
+                root.join("models/llama/model.gguf"),
+                root.join("models/model.safetensors")
+            ]
+        );
+
+        // both are covered by the signature of the folder
+        for model in &models {
+            assert_eq!(signed_path(model), root.join("models"));
+        }
+    }
+
+    #[test]
+    fn test_verify_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let (private_key, public_key) = (
+            dir.path().join("private.key"),
+            dir.path().join("public.key"),
+        );
+        crate::core::signing::create_key(
+            &private_key,
+            &public_key,
+            crate::core::signing::SigningAlgorithm::Ed25519,
+        )
+        .unwrap();
+        let model = dir.path().join("model.gguf");
+        std::fs::write(&model, b"weights").unwrap();
+        signing::sign(crate::cli::SignArgs::new(model.clone(), private_key)).unwrap();
+
+        let args = CiArgs {
+            file_paths: vec![model.clone()],
+            changed_since: None,
+            policy: None,
+            key_path: vec![public_key],
+            keyring: None,
+            no_verify: false,
+            github: false,
+        };
+        assert!(verify_problems(&args, &model).is_empty());
+
+        std::fs::write(&model, b"tampered").unwrap();
+        let problems = verify_problems(&args, &model);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].title, "modified_file");
+    }
+}
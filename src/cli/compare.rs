@@ -0,0 +1,84 @@
+use crate::core::handlers::Scope;
+
+use super::CompareArgs;
+
+pub(crate) fn compare(args: CompareArgs) -> anyhow::Result<()> {
+    let handler_a = crate::core::handlers::handler_for(
+        args.format.clone(),
+        &args.file_path_a,
+        Scope::Inspection,
+    )?;
+    let handler_b =
+        crate::core::handlers::handler_for(args.format, &args.file_path_b, Scope::Inspection)?;
+
+    tracing::info!(
+        "Comparing {:?} (format={}) with {:?} (format={}) rtol={} atol={}{} ...",
+        args.file_path_a,
+        handler_a.file_type(),
+        args.file_path_b,
+        handler_b.file_type(),
+        args.rtol,
+        args.atol,
+        args.filter
+            .as_ref()
+            .map(|f| format!(" filter_by={:?}", f))
+            .unwrap_or("".to_string())
+    );
+
+    let tensors_a = handler_a.tensor_data(&args.file_path_a, args.filter.clone())?;
+    let tensors_b = handler_b.tensor_data(&args.file_path_b, args.filter)?;
+
+    let comparison = crate::core::compare::compare(&tensors_a, &tensors_b, args.rtol, args.atol);
+
+    for tensor in &comparison.tensors {
+        let status = if tensor.is_match() { "ok" } else { "MISMATCH" };
+        if tensor.num_elements.0 != tensor.num_elements.1 {
+            println!(
+                "  {}: {} (number of elements {} vs {})",
+                tensor.id, status, tensor.num_elements.0, tensor.num_elements.1
+            );
+        } else {
+            println!(
+                "  {}: {} (max abs diff={:e} max rel diff={:e} mismatches={}/{})",
+                tensor.id,
+                status,
+                tensor.max_abs_diff,
+                tensor.max_rel_diff,
+                tensor.mismatches,
+                tensor.num_elements.0
+            );
+        }
+    }
+
+    if !comparison.only_in_a.is_empty() {
+        println!("\nonly in {:?}:\n", args.file_path_a);
+        for id in &comparison.only_in_a {
+            println!("  {}", id);
+        }
+    }
+    if !comparison.only_in_b.is_empty() {
+        println!("\nonly in {:?}:\n", args.file_path_b);
+        for id in &comparison.only_in_b {
+            println!("  {}", id);
+        }
+    }
+
+    if let Some(json_file_path) = &args.to_json {
+        let json_str = serde_json::to_string_pretty(&comparison)?;
+        std::fs::write(json_file_path, json_str)?;
+
+        tracing::info!("saved to {:?}", json_file_path);
+    }
+
+    let mismatching = comparison.mismatching().count();
+    if mismatching == 0 {
+        println!("\n{} tensors within tolerance", comparison.tensors.len());
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} of {} tensors are not within tolerance",
+            mismatching,
+            comparison.tensors.len()
+        ))
+    }
+}
@@ -0,0 +1,30 @@
+use crate::core::handlers::pytorch;
+
+use super::{ConvertArgs, SignArgs};
+
+pub(crate) fn convert(args: ConvertArgs) -> anyhow::Result<()> {
+    let (converted, dropped) = pytorch::convert(&args.file_path, &args.output)?;
+
+    if !dropped.is_empty() {
+        println!("dropped {} objects that are not tensors:", dropped.len());
+        for object in &dropped {
+            println!("  {:<60} {}", object.name, object.kind);
+        }
+        println!();
+    }
+    println!(
+        "converted {} tensors, saved to {}",
+        converted,
+        args.output.display()
+    );
+
+    if let Some(key_path) = args.key_path {
+        super::sign(SignArgs {
+            embed: args.embed,
+            print: true,
+            ..SignArgs::new(args.output.canonicalize()?, key_path)
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use crate::core::{diff::Diff, handlers::Scope, TensorDescriptor};
+
+use super::{DetailLevel, DiffArgs};
+
+fn format_delta(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!(
+        "{}{} ({}{})",
+        sign,
+        humansize::format_size(delta.unsigned_abs(), humansize::DECIMAL),
+        sign,
+        delta.unsigned_abs()
+    )
+}
+
+fn format_tensor(tensor: &TensorDescriptor) -> String {
+    format!(
+        "{} {:?} {} ({})",
+        tensor.id.as_ref().unwrap_or(&"<no tensor id>".to_string()),
+        tensor.shape,
+        tensor.dtype,
+        humansize::format_size(tensor.size, humansize::DECIMAL)
+    )
+}
+
+fn print_diff(diff: &Diff) {
+    if diff.is_empty() {
+        println!("no differences");
+        return;
+    }
+
+    if let Some((old, new)) = &diff.file_type_change {
+        println!("file type:     {} -> {}", old, new);
+    }
+    if let Some((old, new)) = &diff.version_change {
+        println!("version:       {} -> {}", old, new);
+    }
+    println!("file size:     {}", format_delta(diff.file_size_delta));
+    println!("data size:     {}", format_delta(diff.data_size_delta));
+    println!("total tensors: {:+}", diff.num_tensors_delta);
+
+    if !diff.renamed.is_empty() {
+        println!("\nrenamed:\n");
+        for rename in &diff.renamed {
+            println!("  {} -> {}", rename.from, rename.to);
+        }
+    }
+
+    if !diff.changed.is_empty() {
+        println!("\nchanged:\n");
+        for change in &diff.changed {
+            println!("  {}", change.id);
+            if change.old_dtype != change.new_dtype {
+                println!("    dtype: {} -> {}", change.old_dtype, change.new_dtype);
+            }
+            if change.old_shape != change.new_shape {
+                println!(
+                    "    shape: {:?} -> {:?}",
+                    change.old_shape, change.new_shape
+                );
+            }
+            println!(
+                "    size: {} -> {}",
+                humansize::format_size(change.old_size, humansize::DECIMAL),
+                humansize::format_size(change.new_size, humansize::DECIMAL)
+            );
+        }
+    }
+
+    if !diff.added.is_empty() {
+        println!("\nadded:\n");
+        for tensor in &diff.added {
+            println!("  + {}", format_tensor(tensor));
+        }
+    }
+
+    if !diff.removed.is_empty() {
+        println!("\nremoved:\n");
+        for tensor in &diff.removed {
+            println!("  - {}", format_tensor(tensor));
+        }
+    }
+
+    if !diff.metadata.is_empty() {
+        println!("\nmetadata:\n");
+        for change in &diff.metadata {
+            match (&change.old_value, &change.new_value) {
+                (None, Some(new)) => println!("  + {}: {}", change.key, new),
+                (Some(old), None) => println!("  - {}: {}", change.key, old),
+                (Some(old), Some(new)) => println!("  ~ {}: {} -> {}", change.key, old, new),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+pub(crate) fn diff(args: DiffArgs) -> anyhow::Result<()> {
+    let old_handler = crate::core::handlers::handler_for(
+        args.format.clone(),
+        &args.old_file_path,
+        Scope::Inspection,
+    )?;
+    let new_handler =
+        crate::core::handlers::handler_for(args.format, &args.new_file_path, Scope::Inspection)?;
+
+    tracing::info!(
+        "Comparing {:?} (format={}) with {:?} (format={})",
+        args.old_file_path,
+        old_handler.file_type(),
+        args.new_file_path,
+        new_handler.file_type(),
+    );
+
+    let old = old_handler.inspect(&args.old_file_path, DetailLevel::Full, None)?;
+    let new = new_handler.inspect(&args.new_file_path, DetailLevel::Full, None)?;
+
+    let diff = crate::core::diff::diff(&old, &new);
+
+    print_diff(&diff);
+
+    if let Some(json_file_path) = &args.to_json {
+        let json_str = serde_json::to_string_pretty(&diff)?;
+        std::fs::write(json_file_path, json_str)?;
+
+        tracing::info!("saved to {:?}", json_file_path);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::core::{
+    card::format_count,
+    estimate::{weights_by_dtype, Attention, Estimate, KvCache},
+    handlers::{self, Scope},
+};
+
+use super::{
+    card::{base_path_of, model_paths},
+    DetailLevel, EstimateArgs,
+};
+
+fn size(bytes: u64) -> String {
+    humansize::format_size(bytes, humansize::DECIMAL)
+}
+
+pub(crate) fn estimate(args: EstimateArgs) -> anyhow::Result<()> {
+    let mut inspections = vec![];
+    for path in model_paths(args.format.clone(), &args.file_path)? {
+        let handler = handlers::handler_for(args.format.clone(), &path, Scope::Inspection)?;
+        inspections.push(handler.inspect(&path, DetailLevel::Full, None)?);
+    }
+
+    // GGUF metadata, or the Hugging Face config.json next to the model
+    let attention = inspections
+        .iter()
+        .find_map(|inspection| Attention::from_gguf(&inspection.metadata))
+        .or_else(|| {
+            let config =
+                std::fs::read_to_string(base_path_of(&args.file_path).join("config.json")).ok()?;
+            Attention::from_config(&serde_json::from_str(&config).ok()?)
+        });
+
+    let kv_cache = match attention {
+        Some(attention) => {
+            let context = args.context.or(attention.context_length).ok_or_else(|| {
+                anyhow::anyhow!("the context length of the model is unknown, set --context")
+            })?;
+            Some(KvCache {
+                size: attention.kv_cache_size(context, args.batch, args.kv_type),
+                attention,
+                context,
+                batch: args.batch,
+                cache_type: args.kv_type,
+            })
+        }
+        None => None,
+    };
+    let estimate = Estimate::new(weights_by_dtype(&inspections), kv_cache);
+
+    println!("weights:\n");
+    for weights in &estimate.weights {
+        println!(
+            "  {:<10} {:>8} parameters {:>10}",
+            weights.dtype,
+            format_count(weights.parameters),
+            size(weights.size)
+        );
+    }
+    println!("  {:<10} {:>30}\n", "total", size(estimate.weights_size));
+
+    match &estimate.kv_cache {
+        Some(cache) => println!(
+            "kv cache:   {} ({} layers, {} kv heads of {}+{}, {} tokens x {} sequences, {:?})",
+            size(cache.size),
+            cache.attention.layers,
+            cache.attention.kv_heads,
+            cache.attention.key_length,
+            cache.attention.value_length,
+            cache.context,
+            cache.batch,
+            cache.cache_type
+        ),
+        None => println!(
+            "kv cache:   unknown, the model has no GGUF attention metadata nor config.json"
+        ),
+    }
+    println!(
+        "total:      {} ({} bytes), plus the runtime buffers",
+        size(estimate.total),
+        estimate.total
+    );
+
+    if let Some(json_path) = &args.to_json {
+        std::fs::write(json_path, serde_json::to_string_pretty(&estimate)?)?;
+        tracing::info!("saved to {:?}", json_path);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::core::{
+    extract::{ExtractFormat, Extracted},
+    handlers::{self, gguf, Scope},
+};
+
+use super::{DetailLevel, ExtractArgs};
+
+pub(crate) fn extract(args: ExtractArgs) -> anyhow::Result<()> {
+    let output_format = match &args.output_format {
+        Some(format) => format.clone(),
+        None => ExtractFormat::of_extension(&args.output).ok_or_else(|| {
+            anyhow::anyhow!(
+                "can't tell the format of {}, set --output-format",
+                args.output.display()
+            )
+        })?,
+    };
+
+    let handler = handlers::handler_for(args.format.clone(), &args.file_path, Scope::Inspection)?;
+    let inspection = handler.inspect(
+        &args.file_path,
+        DetailLevel::Full,
+        Some(args.tensor.clone()),
+    )?;
+    let tensors = inspection.tensors.unwrap_or_default();
+    let Some(descriptor) = tensors
+        .iter()
+        .find(|tensor| tensor.id.as_deref() == Some(args.tensor.as_str()))
+    else {
+        let similar: Vec<&str> = tensors
+            .iter()
+            .filter_map(|t| t.id.as_deref())
+            .take(5)
+            .collect();
+        if similar.is_empty() {
+            anyhow::bail!("tensor {} not found", args.tensor);
+        }
+        anyhow::bail!(
+            "tensor {} not found, did you mean {}?",
+            args.tensor,
+            similar.join(", ")
+        );
+    };
+
+    let is_gguf = handler.file_type().is_gguf();
+    let extracted = if is_gguf && args.raw {
+        let data = gguf::raw_tensor_data(&args.file_path, &args.tensor)?;
+        let size = data.bytes().len();
+        Extracted::new(&data, vec![size])?
+    } else {
+        let data = handler
+            .tensor_data(&args.file_path, Some(args.tensor.clone()))?
+            .into_iter()
+            .find(|data| data.id == args.tensor)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} values of tensor {} can't be decoded{}",
+                    descriptor.dtype,
+                    args.tensor,
+                    if is_gguf {
+                        ", use --raw to extract them as stored"
+                    } else {
+                        ""
+                    }
+                )
+            })?;
+        // GGUF dimensions are listed from the fastest varying one
+        let mut shape = descriptor.shape.clone();
+        if is_gguf {
+            shape.reverse();
+        }
+        Extracted::new(&data, shape)?
+    };
+
+    let data = match output_format {
+        ExtractFormat::Npy => extracted.to_npy(),
+        ExtractFormat::Safetensors => extracted.to_safetensors(&args.tensor)?,
+    };
+    std::fs::write(&args.output, data)?;
+    println!(
+        "{} ({}, shape {:?}) saved to {}",
+        args.tensor,
+        descriptor.dtype,
+        extracted.shape,
+        args.output.display()
+    );
+
+    Ok(())
+}
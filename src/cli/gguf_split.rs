@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use crate::core::handlers::gguf::{self, Split, SplitLimit};
+
+use super::{GgufMergeArgs, GgufSplitArgs, SignArgs};
+
+fn sign(file_path: PathBuf, key_path: PathBuf) -> anyhow::Result<()> {
+    super::sign(SignArgs {
+        print: true,
+        ..SignArgs::new(file_path.canonicalize()?, key_path)
+    })
+}
+
+fn print_signed(split: &Split) {
+    if split.signed {
+        tracing::warn!("the embedded signature has been removed, sign the model again");
+    }
+}
+
+pub(crate) fn gguf_split(args: GgufSplitArgs) -> anyhow::Result<()> {
+    let prefix = args
+        .output
+        .unwrap_or_else(|| args.file_path.with_extension(""));
+    let limit = match args.max_size {
+        Some(size) => SplitLimit::Size(size),
+        None => SplitLimit::Tensors(args.max_tensors),
+    };
+    let split = gguf::split(&args.file_path, &prefix, limit)?;
+
+    println!(
+        "split {} tensors into {} parts:",
+        split.tensors,
+        split.parts.len()
+    );
+    for part in &split.parts {
+        println!(
+            "  {:<60} {}",
+            part.display(),
+            humansize::format_size(std::fs::metadata(part)?.len(), humansize::DECIMAL)
+        );
+    }
+
+    match args.key_path {
+        // the signature of the first part covers all of them
+        Some(key_path) => sign(split.parts[0].clone(), key_path),
+        None => {
+            print_signed(&split);
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn gguf_merge(args: GgufMergeArgs) -> anyhow::Result<()> {
+    let split = gguf::merge(&args.file_path, &args.output)?;
+
+    println!(
+        "merged {} tensors from {} parts, {} saved to {}",
+        split.tensors,
+        split.parts.len(),
+        humansize::format_size(std::fs::metadata(&args.output)?.len(), humansize::DECIMAL),
+        args.output.display()
+    );
+
+    match args.key_path {
+        Some(key_path) => sign(args.output, key_path),
+        None => {
+            print_signed(&split);
+            Ok(())
+        }
+    }
+}
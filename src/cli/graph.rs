@@ -3,12 +3,29 @@ use crate::core::handlers::Scope;
 use super::GraphArgs;
 
 pub(crate) fn graph(args: GraphArgs) -> anyhow::Result<()> {
-    println!(
+    let handler =
+        crate::core::handlers::handler_for(args.format, &args.file_path, Scope::Inspection)?;
+
+    // DOT rendering doesn't scale to models with thousands of nodes, the page does
+    if let Some(html) = &args.html {
+        tracing::info!(
+            "Generating HTML graph for {} to {} ...",
+            args.file_path.display(),
+            html.display()
+        );
+
+        let graph = handler.graph(&args.file_path)?;
+        std::fs::write(html, graph.render_html())
+            .map_err(|e| anyhow::anyhow!("can't write {}: {}", html.display(), e))?;
+        tracing::info!("{} nodes written to {}", graph.nodes.len(), html.display());
+        return Ok(());
+    }
+
+    tracing::info!(
         "Generating DOT graph for {} to {} ...",
         args.file_path.display(),
         args.output.display()
     );
 
-    crate::core::handlers::handler_for(args.format, &args.file_path, Scope::Inspection)?
-        .create_graph(&args.file_path, &args.output)
+    handler.create_graph(&args.file_path, &args.output)
 }
@@ -0,0 +1,158 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use rayon::prelude::*;
+
+use crate::core::{
+    checksums::{self, Entry},
+    compression,
+    signing::{Failure, FileStatus, FileVerdict, Verdict},
+};
+
+use super::{signing::get_paths_of_interest, HashArgs, VerifyArgs};
+
+// the folder checksum paths are relative to
+fn base_path_of(file_path: &Path) -> anyhow::Result<PathBuf> {
+    let file_path = file_path.canonicalize()?;
+    if file_path.is_file() {
+        Ok(file_path.parent().unwrap().to_path_buf())
+    } else {
+        Ok(file_path)
+    }
+}
+
+fn relative_path(base_path: &Path, path: &Path) -> anyhow::Result<String> {
+    path.strip_prefix(base_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|_| anyhow!("{} is outside of {}", path.display(), base_path.display()))
+}
+
+// computes in parallel the checksums of the files, sorted by path, of the decompressed content
+// of compressed files if decompress is set
+fn entries_of(base_path: &Path, paths: &[PathBuf], decompress: bool) -> anyhow::Result<Vec<Entry>> {
+    let mut entries = paths
+        .par_iter()
+        .map(|path| {
+            let checksum = if decompress {
+                checksums::sha256_of_reader(&mut compression::open(path)?)
+                    .map_err(|e| anyhow!("can't decompress {}: {}", path.display(), e))?
+            } else {
+                checksums::sha256_of(path)?
+            };
+            Ok(Entry {
+                checksum,
+                path: relative_path(base_path, path)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+pub(crate) fn hash(args: HashArgs) -> anyhow::Result<()> {
+    let base_path = base_path_of(&args.file_path)?;
+    let mut paths = get_paths_of_interest(args.format, &args.file_path, args.ignore)?;
+    // don't hash the checksums file itself
+    if let Some(output) = &args.output {
+        if output.exists() {
+            let output = output.canonicalize()?;
+            paths.retain(|path| path != &output);
+        }
+    }
+
+    let lines: String = entries_of(&base_path, &paths, args.decompress)?
+        .iter()
+        .map(|entry| format!("{}\n", entry.to_line()))
+        .collect();
+
+    if let Some(output) = args.output {
+        std::fs::write(&output, lines)?;
+        tracing::info!("Checksums written to {}", output.display());
+    } else {
+        print!("{}", lines);
+    }
+
+    Ok(())
+}
+
+pub(super) fn verify_checksums(
+    args: &VerifyArgs,
+    checksums_path: &Path,
+    verdict: &mut Verdict,
+) -> anyhow::Result<()> {
+    tracing::info!("Verifying checksums: {}", checksums_path.display());
+
+    let entries = checksums::parse(&std::fs::read_to_string(checksums_path)?)?;
+    let expected: BTreeMap<&str, &str> = entries
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry.checksum.as_str()))
+        .collect();
+
+    let base_path = base_path_of(&args.file_path)?;
+    let mut paths =
+        get_paths_of_interest(args.format.clone(), &args.file_path, args.ignore.clone())?;
+    let checksums_path = checksums_path.canonicalize()?;
+    paths.retain(|path| path != &checksums_path);
+    verdict.signature = Some(checksums_path.display().to_string());
+
+    let mut failed = 0;
+    for entry in entries_of(&base_path, &paths, args.decompress)? {
+        let status = match expected.get(entry.path.as_str()) {
+            Some(checksum) if *checksum == entry.checksum => {
+                super::output(args.print, format_args!("  {}: OK", entry.path));
+                FileStatus::Ok
+            }
+            Some(_) => {
+                super::output(args.print, format_args!("  {}: FAILED", entry.path));
+                failed += 1;
+                FileStatus::Modified
+            }
+            None => {
+                super::output(
+                    args.print,
+                    format_args!("  {}: missing checksum", entry.path),
+                );
+                failed += 1;
+                FileStatus::Extra
+            }
+        };
+        verdict.files.push(FileVerdict {
+            path: entry.path,
+            status,
+        });
+    }
+
+    // when verifying a folder, all the listed files must be there
+    if args.file_path.is_dir() {
+        for path in expected.keys() {
+            if !base_path.join(path).exists() {
+                super::output(args.print, format_args!("  {}: missing file", path));
+                failed += 1;
+                verdict.files.push(FileVerdict {
+                    path: path.to_string(),
+                    status: FileStatus::Missing,
+                });
+            }
+        }
+    }
+
+    if failed > 0 {
+        let failure = if verdict
+            .files
+            .iter()
+            .any(|file| file.status == FileStatus::Modified)
+        {
+            Failure::ModifiedFile
+        } else {
+            Failure::MissingFile
+        };
+        return Err(failure.error(format!("{} files failed verification", failed)));
+    }
+
+    super::output(args.print, format_args!("Checksums verified"));
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::core::{handlers::Scope, stats::Histogram};
+
+use super::HistArgs;
+
+// width in characters of the largest bar
+const BAR_WIDTH: usize = 50;
+
+pub(crate) fn hist(args: HistArgs) -> anyhow::Result<()> {
+    let handler =
+        crate::core::handlers::handler_for(args.format, &args.file_path, Scope::Inspection)?;
+
+    tracing::info!(
+        "Histogram of {} in {:?} (format={}, bins={})",
+        args.tensor,
+        args.file_path,
+        handler.file_type(),
+        args.bins
+    );
+
+    let tensor = handler
+        .tensor_data(&args.file_path, Some(args.tensor.clone()))?
+        .into_iter()
+        .find(|t| t.id == args.tensor)
+        .ok_or_else(|| anyhow::anyhow!("tensor {} not found or not supported", args.tensor))?;
+
+    let histogram = Histogram::from_data(&tensor, args.bins)?;
+    let max_count = histogram.bins.iter().map(|b| b.count).max().unwrap_or(0);
+
+    for bin in &histogram.bins {
+        let bar = (bin.count * BAR_WIDTH).checked_div(max_count).unwrap_or(0);
+        println!(
+            "  [{:>12.6}, {:>12.6}) | {:<width$} {}",
+            bin.start,
+            bin.end,
+            "#".repeat(bar),
+            bin.count,
+            width = BAR_WIDTH
+        );
+    }
+
+    if histogram.nan_count > 0 || histogram.inf_count > 0 {
+        println!(
+            "\n  {} NaN and {} Inf values not included",
+            histogram.nan_count, histogram.inf_count
+        );
+    }
+
+    if let Some(json_file_path) = &args.to_json {
+        let json_str = serde_json::to_string_pretty(&histogram)?;
+        std::fs::write(json_file_path, json_str)?;
+
+        tracing::info!("saved to {:?}", json_file_path);
+    }
+
+    Ok(())
+}
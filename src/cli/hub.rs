@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::hub::{cache_path, files_to_download, Hub, HubUri};
+
+/// A Hugging Face Hub model downloaded to the local cache.
+pub(super) struct HubModel {
+    pub uri: HubUri,
+    // local folder of the repository snapshot
+    pub root: PathBuf,
+    // local path of the model, the file or the whole snapshot
+    pub path: PathBuf,
+}
+
+/// Downloads the files of a hf:// model, returns None for local paths.
+pub(super) fn download(file_path: &Path, ignore: Option<&str>) -> anyhow::Result<Option<HubModel>> {
+    if !HubUri::is_hub_uri(file_path) {
+        return Ok(None);
+    }
+
+    let uri = HubUri::parse(&file_path.to_string_lossy())?;
+    let hub = Hub::new();
+    let snapshot = hub.snapshot(&uri)?;
+    tracing::info!(
+        "Resolved {}@{} to commit {}",
+        uri.repo,
+        uri.revision,
+        snapshot.commit
+    );
+
+    let root = cache_path(&uri, &snapshot.commit)?;
+    let files = files_to_download(&uri, &snapshot, ignore);
+    if let Some(path) = &uri.path {
+        if !files.iter().any(|file| &file.rfilename == path) {
+            anyhow::bail!("{} not found in {}", path, uri.repo);
+        }
+    }
+
+    for file in files {
+        let output = root.join(&file.rfilename);
+        // snapshots never change, complete files are downloaded only once
+        if let Ok(metadata) = output.metadata() {
+            if file.size.is_none_or(|size| size == metadata.len()) {
+                continue;
+            }
+        }
+
+        tracing::info!("Downloading {} ...", file.rfilename);
+        hub.download(&uri, &snapshot.commit, &file.rfilename, &output)?;
+    }
+
+    let path = match &uri.path {
+        Some(path) => root.join(path),
+        None => root.clone(),
+    };
+    Ok(Some(HubModel { uri, root, path }))
+}
+
+/// Commits a signature of the model to its repository, at the same path relative to the root.
+pub(super) fn upload(model: &HubModel, path: &Path) -> anyhow::Result<()> {
+    let relative = path
+        .canonicalize()?
+        .strip_prefix(model.root.canonicalize()?)
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "{} is not in the {} snapshot, can't upload it",
+                path.display(),
+                model.uri.repo
+            )
+        })?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    tracing::info!("Uploading {} to {} ...", relative, model.uri.repo);
+    Hub::new().upload(&model.uri, &relative, &std::fs::read(path)?)?;
+    tracing::info!("Uploaded {}", relative);
+
+    Ok(())
+}
@@ -1,17 +1,270 @@
-use crate::core::handlers::Scope;
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 
-use super::InspectArgs;
+use glob::glob;
+use notify::Watcher;
+use serde::Serialize;
+
+use crate::core::{
+    adapter::{self, Adapter},
+    archive::{self, ArchiveType, Member},
+    compression::{self, Compression},
+    filter::{self, TensorFilter},
+    handlers::{gguf, safetensors, Handler, Scope},
+    layers,
+    layers::LayerGroup,
+    license,
+    operators::{Operators, DEFAULT_DOMAIN},
+    pipe::{self, PipedFile},
+    remote::{self, RemoteFile},
+    repository::{self, ModelVersion},
+    storage::{ObjectRange, ObjectUri},
+    validate, Inspection, Shard, TensorDescriptor, ValueInfo,
+};
+
+use super::{DetailLevel, InspectArgs, OutputFormat};
+
+// time without changes after which the watched files are inspected again
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+// TOML documents are tables, the inspections of multiple files are saved under a key
+#[derive(Serialize)]
+struct Inspections<'a> {
+    inspections: Vec<&'a Inspection>,
+}
+
+// Hugging Face config.json next to the model, if any
+fn config_of(file_path: &Path) -> Option<serde_json::Value> {
+    let folder = if file_path.is_dir() {
+        file_path
+    } else {
+        file_path.parent()?
+    };
+    let data = std::fs::read_to_string(folder.join("config.json")).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+// PEFT adapter_config.json next to the model, if any
+fn adapter_config_of(file_path: &Path) -> Option<serde_json::Value> {
+    let folder = if file_path.is_dir() {
+        file_path
+    } else {
+        file_path.parent()?
+    };
+    let data = std::fs::read_to_string(folder.join("adapter_config.json")).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+// all the tensors of the file, even if the inspection lists none or some of them
+fn all_tensors(
+    handler: &dyn Handler,
+    file_path: &Path,
+    inspection: &Inspection,
+    args: &InspectArgs,
+) -> anyhow::Result<Vec<TensorDescriptor>> {
+    if inspection.tensors.is_some() && args.filter.is_none() {
+        return Ok(inspection.tensors.clone().unwrap_or_default());
+    }
+    Ok(handler
+        .inspect(file_path, DetailLevel::Full, None)?
+        .tensors
+        .unwrap_or_default())
+}
+
+// the LoRA adapter the file is, if any, checked against the base model if set
+fn adapter_of(
+    handler: &dyn Handler,
+    file_path: &Path,
+    inspection: &Inspection,
+    args: &InspectArgs,
+    verbose: bool,
+) -> anyhow::Result<Option<Adapter>> {
+    let peft_config = adapter_config_of(file_path);
+    let maybe_adapter = if inspection.file_type.is_gguf() {
+        inspection.metadata.get("general.type").map(String::as_str) == Some("adapter")
+    } else {
+        peft_config.is_some()
+    };
+    if !maybe_adapter && args.base.is_none() {
+        return Ok(None);
+    }
+
+    let full = Inspection {
+        tensors: Some(all_tensors(handler, file_path, inspection, args)?),
+        ..inspection.clone()
+    };
+    let found = adapter::detect(&full, peft_config.as_ref());
+    let Some(base) = &args.base else {
+        return Ok(found);
+    };
+    if found.is_none() {
+        anyhow::bail!("{} is not a LoRA adapter", file_path.display());
+    }
+
+    let mut base_tensors = vec![];
+    for path in super::card::model_paths(args.format.clone(), base)? {
+        let handler =
+            crate::core::handlers::handler_for(args.format.clone(), &path, Scope::Inspection)?;
+        base_tensors.extend(
+            handler
+                .inspect(&path, DetailLevel::Full, None)?
+                .tensors
+                .unwrap_or_default(),
+        );
+    }
+    let gguf = full.file_type.is_gguf();
+    let pairs = adapter::lora_pairs(full.tensors.as_deref().unwrap_or_default(), gguf);
+    let problems = adapter::check_base(&pairs, &base_tensors, gguf);
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "{} of {} tensors updated by the adapter are not compatible with {}:\n  {}",
+            problems.len(),
+            pairs.len(),
+            base.display(),
+            problems.join("\n  ")
+        );
+    }
+    if verbose {
+        tracing::info!(
+            "compatible with {}: {} tensors updated",
+            base.display(),
+            pairs.len()
+        );
+    }
+
+    Ok(found)
+}
+
+fn tensor_filter_of(args: &InspectArgs) -> TensorFilter {
+    TensorFilter {
+        regex: args.filter_regex.clone(),
+        dtypes: args.dtype.clone(),
+        min_size: args.min_size,
+        max_size: args.max_size,
+        shape: args.shape.clone(),
+    }
+}
+
+// returns the inspection of a single file, hub, remote and object store files included
+pub(super) fn inspect_file(
+    args: &InspectArgs,
+    file_path: &Path,
+    verbose: bool,
+) -> anyhow::Result<Inspection> {
+    let mut file_path = file_path.to_path_buf();
+    let mut detail = args.detail.clone();
+
+    if let Some(model) = super::hub::download(&file_path, None)? {
+        if model.uri.path.is_none() {
+            anyhow::bail!(
+                "a file of the repository is required, for instance hf://{}/model.safetensors",
+                model.uri.repo
+            );
+        }
+        file_path = model.path;
+    }
+
+    if ArchiveType::of(&file_path).is_some() && file_path.is_file() {
+        return inspect_archive(args, &file_path, verbose);
+    }
+
+    // remote files are inspected on a local copy of their header, the tensor data is left zeroed
+    let remote_url = (remote::is_url(&file_path) || ObjectUri::is_object_uri(&file_path))
+        .then(|| file_path.to_string_lossy().to_string());
+    let mut temp_dir = None;
+    if let Some(url) = &remote_url {
+        if args.stats || args.preview.is_some() {
+            anyhow::bail!(
+                "statistics and previews require the tensor data, download the file to compute them"
+            );
+        }
+        if args.shapes {
+            anyhow::bail!(
+                "shape inference requires the values of the constants, download the file to infer them"
+            );
+        }
+        file_path = temp_dir
+            .insert(tempfile::tempdir()?)
+            .path()
+            .join(remote::file_name_of(url).unwrap_or("model"));
+    }
+
+    // the standard input is inspected like remote files, its header being buffered while read
+    let stdin = pipe::is_stdin(&file_path);
+    let mut piped: Option<PipedFile<Box<dyn Read>>> = None;
+    if stdin {
+        if args.stats || args.preview.is_some() {
+            anyhow::bail!(
+                "statistics and previews require the tensor data, save the file to compute them"
+            );
+        }
+        if args.shapes {
+            anyhow::bail!(
+                "shape inference requires the values of the constants, save the file to infer them"
+            );
+        }
+        let file = piped.insert(PipedFile::new(Box::new(std::io::stdin().lock())));
+        let extension = match file.sniff_extension()? {
+            Some(extension) => extension,
+            None if args.format.is_some() => "bin",
+            None => anyhow::bail!("can't detect the format of the standard input, set --format"),
+        };
+        file_path = temp_dir
+            .insert(tempfile::tempdir()?)
+            .path()
+            .join(format!("stdin.{}", extension));
+    }
+
+    // compressed files are decompressed while read, only the header unless the tensor data is
+    // needed, and inspected as the file they contain
+    let compressed_path = Compression::of(&file_path)
+        .filter(|_| remote_url.is_none() && !stdin)
+        .map(|_| file_path.clone());
+    if let Some(compressed_path) = &compressed_path {
+        let mut decoder = compression::open(compressed_path)?;
+        file_path = temp_dir.insert(tempfile::tempdir()?).path().join(
+            compression::decompressed_path(compressed_path)
+                .file_name()
+                .unwrap_or_default(),
+        );
+        if args.stats || args.preview.is_some() {
+            std::io::copy(&mut decoder, &mut std::fs::File::create(&file_path)?).map_err(|e| {
+                anyhow::anyhow!("can't decompress {}: {}", compressed_path.display(), e)
+            })?;
+        } else {
+            piped = Some(PipedFile::new(decoder));
+        }
+    }
 
-pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
     let handler =
-        crate::core::handlers::handler_for(args.format, &args.file_path, Scope::Inspection)?;
+        crate::core::handlers::handler_for(args.format.clone(), &file_path, Scope::Inspection)?;
 
-    if !args.quiet {
-        println!(
-            "Inspecting {:?} (format={}, detail={:?}{}):\n",
-            args.file_path,
+    let tensor_filter = tensor_filter_of(args);
+    if args.stats
+        || args.preview.is_some()
+        || args.group_by_layer
+        || !tensor_filter.is_empty()
+        || args.sort_by.is_some()
+        || args.top.is_some()
+    {
+        // statistics are reported per tensor, and filters select among the listed tensors
+        detail = DetailLevel::Full;
+    }
+
+    if verbose {
+        tracing::info!(
+            "Inspecting {:?} (format={}, detail={:?}{})",
+            remote_url
+                .as_ref()
+                .map(PathBuf::from)
+                .or_else(|| stdin.then(|| PathBuf::from("-")))
+                .or(compressed_path.clone())
+                .unwrap_or(file_path.clone()),
             handler.file_type(),
-            args.detail,
+            detail,
             args.filter
                 .as_ref()
                 .map(|f| format!(" filter_by={:?}", f))
@@ -19,9 +272,790 @@ pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
         );
     }
 
-    let inspection = handler.inspect(&args.file_path, args.detail, args.filter)?;
+    if let Some(url) = &remote_url {
+        let mut file = if ObjectUri::is_object_uri(&PathBuf::from(url)) {
+            RemoteFile::new(Box::new(ObjectRange::new(&ObjectUri::parse(url)?)?))?
+        } else {
+            RemoteFile::open(url)?
+        };
+        handler.scan_header(&mut file)?;
+        file.save(&file_path)?;
+
+        if verbose {
+            tracing::info!(
+                "downloaded {} of {}",
+                humansize::format_size(file.downloaded(), humansize::DECIMAL),
+                humansize::format_size(file.size(), humansize::DECIMAL)
+            );
+        }
+    }
+
+    if let Some(mut file) = piped.take() {
+        handler.scan_header(&mut file)?;
+        let buffered = file.buffered();
+        let size = file.save(&file_path)?;
+
+        if verbose {
+            tracing::info!(
+                "buffered {} of {}",
+                humansize::format_size(buffered, humansize::DECIMAL),
+                humansize::format_size(size, humansize::DECIMAL)
+            );
+        }
+    }
+
+    let mut inspection = handler.inspect(&file_path, detail, args.filter.clone())?;
+    inspection.license = license::detect(&inspection.metadata, config_of(&file_path).as_ref());
+    inspection.adapter = adapter_of(handler.as_ref(), &file_path, &inspection, args, verbose)?;
+    if let (Some(opset), Some(operators)) = (args.target_opset, inspection.operators.as_mut()) {
+        operators.unavailable = operators.unavailable_in(opset);
+    }
+    if args.shapes {
+        inspection.intermediates = Some(handler.intermediate_shapes(&file_path)?);
+    }
+    if let Some(url) = remote_url {
+        inspection.file_path = PathBuf::from(url);
+    } else if stdin {
+        inspection.file_path = PathBuf::from("-");
+    } else if let Some(compressed_path) = compressed_path {
+        inspection.file_path = compressed_path.canonicalize()?;
+    }
+
+    if args.stats || args.preview.is_some() {
+        let tensors = handler.tensor_data(&file_path, args.filter.clone())?;
+        let mut stats = if args.stats {
+            crate::core::stats::compute(&tensors)
+        } else {
+            Default::default()
+        };
+        let mut previews: HashMap<&str, Vec<f64>> = args
+            .preview
+            .map(|count| {
+                tensors
+                    .iter()
+                    .map(|data| (data.id.as_str(), data.values().take(count).collect()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for tensor in inspection.tensors.iter_mut().flatten() {
+            if let Some(id) = &tensor.id {
+                tensor.stats = stats.remove(id);
+                tensor.preview = previews.remove(id.as_str());
+            }
+        }
+    }
+    tensor_filter.apply(&mut inspection);
+    if !args.full_metadata {
+        // arrays such as the vocabulary are as large as the rest of the inspection
+        inspection.typed_metadata = None;
+    }
+    filter::sort_tensors(&mut inspection, args.sort_by.as_ref(), args.desc, args.top);
+    if args.group_by_layer {
+        inspection.layers = inspection.tensors.as_deref().map(layers::group_by_layer);
+    }
+
+    Ok(inspection)
+}
+
+// inspects the models among the files of the archive, extracted to a temporary folder, as a whole
+fn inspect_archive(
+    args: &InspectArgs,
+    archive_path: &Path,
+    verbose: bool,
+) -> anyhow::Result<Inspection> {
+    if verbose {
+        tracing::info!("Inspecting {:?} (archive)", archive_path);
+    }
+
+    let temp_dir = tempfile::tempdir()?;
+    let members: Vec<Member> = archive::extract(archive_path, temp_dir.path())?
+        .into_iter()
+        .map(|(name, path)| {
+            Ok(Member {
+                name,
+                size: path.metadata()?.len(),
+                file_type: None,
+                num_tensors: 0,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let inspection = Inspection {
+        file_path: archive_path.canonicalize()?,
+        file_size: archive_path.metadata()?.len(),
+        ..Default::default()
+    };
+    inspect_members(args, temp_dir.path(), members, inspection)
+}
+
+// inspects the models among the files of the folder of a model repository version as a whole
+fn inspect_version(args: &InspectArgs, version: &ModelVersion) -> anyhow::Result<Inspection> {
+    if version.path.is_file() {
+        return inspect_file(args, &version.path, false);
+    }
+
+    let mut members = vec![];
+    for path in glob(&version.path.join("**/*").to_string_lossy())? {
+        let path = path?;
+        if path.is_file() {
+            members.push(Member {
+                name: path
+                    .strip_prefix(&version.path)?
+                    .to_string_lossy()
+                    .to_string(),
+                size: path.metadata()?.len(),
+                file_type: None,
+                num_tensors: 0,
+            });
+        }
+    }
+
+    let inspection = Inspection {
+        file_path: version.path.clone(),
+        file_size: members.iter().map(|member| member.size).sum(),
+        // the settings of the Triton configuration, which the models don't override
+        metadata: version.config(),
+        ..Default::default()
+    };
+    inspect_members(args, &version.path, members, inspection)
+}
+
+// aggregates the models among the members extracted to the folder into the inspection
+fn inspect_members(
+    args: &InspectArgs,
+    folder: &Path,
+    mut members: Vec<Member>,
+    mut inspection: Inspection,
+) -> anyhow::Result<Inspection> {
+    // models are selected and grouped as in folders, a folder without any is reported as is
+    let folder_args = InspectArgs {
+        file_paths: vec![folder.to_path_buf()],
+        ..args.clone()
+    };
+    let mut shapes = std::collections::HashSet::new();
+    let mut dtypes = std::collections::HashSet::new();
+    for (index, path) in batch_paths(&folder_args)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let name = path.strip_prefix(folder)?.to_string_lossy().to_string();
+        let model = inspect_file(args, path, false)
+            .map_err(|e| anyhow::anyhow!("can't inspect {}: {}", name, e))?;
+
+        // the archive has the format of its models, if they all have the same
+        if index == 0 {
+            inspection.file_type = model.file_type.clone();
+            inspection.version = model.version.clone();
+        } else if inspection.file_type != model.file_type {
+            inspection.file_type = Default::default();
+            inspection.version = String::new();
+        }
+        inspection.header_size += model.header_size;
+        inspection.num_tensors += model.num_tensors;
+        inspection.data_size += model.data_size;
+        shapes.extend(model.unique_shapes.iter().cloned());
+        dtypes.extend(model.unique_dtypes.iter().cloned());
+        for (key, value) in &model.metadata {
+            inspection
+                .metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        inspection.license = inspection.license.or(model.license);
+        if let Some(tensors) = model.tensors {
+            inspection
+                .tensors
+                .get_or_insert_with(Vec::new)
+                .extend(tensors);
+        }
+
+        // models made of several files, or folders, get their own row
+        match members.iter_mut().find(|member| member.name == name) {
+            Some(member) => {
+                member.file_type = Some(model.file_type);
+                member.num_tensors = model.num_tensors;
+            }
+            None => members.push(Member {
+                name,
+                size: model.file_size,
+                file_type: Some(model.file_type),
+                num_tensors: model.num_tensors,
+            }),
+        }
+    }
+
+    inspection.unique_shapes = shapes.into_iter().collect();
+    inspection.unique_shapes.sort();
+    inspection.unique_dtypes = dtypes.into_iter().collect();
+    inspection.unique_dtypes.sort();
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    inspection.members = Some(members);
+    filter::sort_tensors(&mut inspection, args.sort_by.as_ref(), args.desc, args.top);
+    if args.group_by_layer {
+        inspection.layers = inspection.tensors.as_deref().map(layers::group_by_layer);
+    }
+
+    Ok(inspection)
+}
+
+// the Triton or TorchServe model repository to inspect, if a single one is given
+fn repository_of(args: &InspectArgs) -> Option<&Path> {
+    match args.file_paths.as_slice() {
+        [path] if repository::RepositoryType::of(path).is_some() => Some(path),
+        _ => None,
+    }
+}
+
+fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+// folders that are not models themselves, and glob patterns, select all the supported files
+// they contain
+fn is_batch(args: &InspectArgs) -> bool {
+    args.file_paths.len() > 1
+        || args.file_paths.iter().any(|path| {
+            is_glob(path)
+                || (path.is_dir()
+                    && crate::core::handlers::handler_for(
+                        args.format.clone(),
+                        path,
+                        Scope::Inspection,
+                    )
+                    .is_err())
+        })
+}
+
+fn batch_paths(args: &InspectArgs) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for path in &args.file_paths {
+        let pattern = if is_glob(path) {
+            path.to_string_lossy().to_string()
+        } else if path.is_dir()
+            && crate::core::handlers::handler_for(args.format.clone(), path, Scope::Inspection)
+                .is_err()
+        {
+            path.join("**/*").to_string_lossy().to_string()
+        } else {
+            // explicit files are always inspected, and reported if not supported
+            paths.push(path.clone());
+            continue;
+        };
+
+        let mut matched = vec![];
+        for entry in glob(&pattern)? {
+            let entry = entry?;
+            if (entry.is_file() || entry.is_dir())
+                && crate::core::handlers::handler_for(
+                    args.format.clone(),
+                    &entry,
+                    Scope::Inspection,
+                )
+                .is_ok()
+            {
+                matched.push(entry);
+            }
+        }
+        // models made of folders are inspected once, not for each of their files
+        let folders: Vec<PathBuf> = matched.iter().filter(|e| e.is_dir()).cloned().collect();
+        matched.retain(|entry| {
+            !entry
+                .ancestors()
+                .skip(1)
+                .any(|parent| folders.iter().any(|folder| folder == parent))
+        });
+        // sharded models are inspected once through their index, not for each shard
+        let shards: Vec<PathBuf> = matched
+            .iter()
+            .filter(|entry| safetensors::is_safetensors_index(entry))
+            .filter_map(|index| safetensors::shard_paths(index).ok())
+            .flatten()
+            .collect();
+        matched.retain(|entry| !shards.contains(entry));
+        // and split GGUF models through their first part
+        matched.retain(|entry| gguf::split_paths(entry).is_none_or(|parts| parts[0] == *entry));
+        if matched.is_empty() {
+            anyhow::bail!("no supported files found in {}", path.display());
+        }
+        paths.extend(matched);
+    }
+
+    Ok(paths)
+}
+
+fn print_summary(rows: &[(PathBuf, anyhow::Result<Inspection>)]) {
+    let width = rows
+        .iter()
+        .map(|(path, _)| path.to_string_lossy().chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{:<width$}  {:<12} {:<8} {:>8} {:>10}  DTYPES",
+        "FILE", "FORMAT", "VERSION", "TENSORS", "SIZE"
+    );
+    for (path, result) in rows {
+        match result {
+            Ok(inspection) => println!(
+                "{:<width$}  {:<12} {:<8} {:>8} {:>10}  {}",
+                path.display(),
+                inspection.file_type.to_string(),
+                inspection.version,
+                inspection.num_tensors,
+                humansize::format_size(inspection.file_size, humansize::DECIMAL),
+                inspection.unique_dtypes.join(", ")
+            ),
+            Err(e) => println!("{:<width$}  error: {}", path.display(), e),
+        }
+    }
+}
+
+fn print_layers(layers: &[LayerGroup]) {
+    let width = layers
+        .iter()
+        .map(|layer| layer.name.chars().count())
+        .chain(std::iter::once(5))
+        .max()
+        .unwrap_or_default();
+
+    println!("\nlayers:\n");
+    println!(
+        "  {:<width$}  {:>8} {:>14} {:>10}",
+        "LAYER", "TENSORS", "PARAMETERS", "SIZE"
+    );
+    for layer in layers {
+        println!(
+            "  {:<width$}  {:>8} {:>14} {:>10}",
+            layer.name,
+            layer.tensors,
+            layer.parameters,
+            humansize::format_size(layer.size, humansize::DECIMAL)
+        );
+    }
+    println!();
+}
+
+fn print_values(title: &str, values: &[ValueInfo]) {
+    let width = values
+        .iter()
+        .map(|value| value.name.chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or_default();
+
+    println!("\n{}:\n", title);
+    for value in values {
+        println!("  {:<width$}  {}", value.name, value.shape());
+    }
+}
+
+fn print_operators(operators: &Operators, target_opset: Option<i64>) {
+    let names: Vec<String> = operators.counts.iter().map(|count| count.name()).collect();
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .chain(std::iter::once(8))
+        .max()
+        .unwrap_or_default();
+
+    println!("\noperators:\n");
+    for (domain, version) in &operators.opsets {
+        println!("  opset {}: {}", domain, version);
+    }
+    println!();
+    println!("  {:<width$}  {:>8}", "OPERATOR", "NODES");
+    for (name, count) in names.iter().zip(&operators.counts) {
+        println!("  {:<width$}  {:>8}", name, count.count);
+    }
+
+    if let Some(opset) = target_opset {
+        if operators.unavailable.is_empty() {
+            println!(
+                "\n  all the {} operators are available in opset {}",
+                DEFAULT_DOMAIN, opset
+            );
+        } else {
+            println!("\nunavailable in opset {}:\n", opset);
+            for reason in &operators.unavailable {
+                println!("  {}", reason);
+            }
+        }
+    }
+}
+
+fn print_shards(shards: &[Shard]) {
+    let names: Vec<String> = shards
+        .iter()
+        .map(|shard| {
+            shard
+                .file_path
+                .file_name()
+                .unwrap_or(shard.file_path.as_os_str())
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or_default();
+
+    println!("\nshards:\n");
+    println!(
+        "  {:<width$}  {:>8} {:>10}  DTYPES",
+        "FILE", "TENSORS", "SIZE"
+    );
+    for (name, shard) in names.iter().zip(shards) {
+        println!(
+            "  {:<width$}  {:>8} {:>10}  {}",
+            name,
+            shard.num_tensors,
+            humansize::format_size(shard.file_size, humansize::DECIMAL),
+            shard.unique_dtypes.join(", ")
+        );
+    }
+}
+
+fn print_members(members: &[Member]) {
+    let width = members
+        .iter()
+        .map(|member| member.name.chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or_default();
+
+    println!("\nmembers:\n");
+    println!(
+        "  {:<width$}  {:<12} {:>8} {:>10}",
+        "FILE", "FORMAT", "TENSORS", "SIZE"
+    );
+    for member in members {
+        println!(
+            "  {:<width$}  {:<12} {:>8} {:>10}",
+            member.name,
+            member
+                .file_type
+                .as_ref()
+                .map(|file_type| file_type.to_string())
+                .unwrap_or("-".to_string()),
+            member.num_tensors,
+            humansize::format_size(member.size, humansize::DECIMAL)
+        );
+    }
+}
+
+// the values as the shortest decimal of their 32 bits float, integers in full
+fn format_preview(values: &[f64]) -> String {
+    let values: Vec<String> = values
+        .iter()
+        .map(|value| {
+            if value.fract() == 0.0 && value.abs() < 1e15 {
+                format!("{}", *value as i64)
+            } else {
+                format!("{}", *value as f32)
+            }
+        })
+        .collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn format_of_extension(path: &Path) -> anyhow::Result<OutputFormat> {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("json") => Ok(OutputFormat::Json),
+        Some("yaml" | "yml") => Ok(OutputFormat::Yaml),
+        Some("toml") => Ok(OutputFormat::Toml),
+        _ => Err(anyhow::anyhow!(
+            "can't tell the output format of {}, set --output-format",
+            path.display()
+        )),
+    }
+}
+
+// format and file the inspections are saved to, printed if only the format is set
+fn output_of(args: &InspectArgs) -> anyhow::Result<Option<(OutputFormat, Option<PathBuf>)>> {
+    if let Some(path) = &args.to_json {
+        return Ok(Some((OutputFormat::Json, Some(path.clone()))));
+    }
+    if let Some(path) = &args.to_file {
+        let format = match &args.output_format {
+            Some(format) => format.clone(),
+            None => format_of_extension(path)?,
+        };
+        return Ok(Some((format, Some(path.clone()))));
+    }
+    Ok(args.output_format.clone().map(|format| (format, None)))
+}
+
+fn serialize<T: Serialize>(value: &T, format: &OutputFormat) -> anyhow::Result<String> {
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::Yaml => serde_yaml_ng::to_string(value)?,
+        OutputFormat::Toml => toml::to_string_pretty(value)?,
+    })
+}
+
+// saves the serialized inspections to the file, or prints them
+fn save(data: String, path: Option<&Path>, quiet: bool) -> anyhow::Result<()> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, data)?;
+            if !quiet {
+                tracing::info!("saved to {:?}", path);
+            }
+        }
+        None => print!("{}", data),
+    }
+    Ok(())
+}
+
+// the inspection of a file, or its error, as a single JSON line
+fn jsonl_line(path: &Path, result: &anyhow::Result<Inspection>) -> anyhow::Result<String> {
+    Ok(match result {
+        Ok(inspection) => serde_json::to_string(inspection)?,
+        Err(e) => serde_json::to_string(&serde_json::json!({
+            "file_path": path,
+            "error": e.to_string(),
+        }))?,
+    })
+}
+
+fn print_jsonl(path: &Path, result: &anyhow::Result<Inspection>) -> anyhow::Result<()> {
+    let line = jsonl_line(path, result)?;
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", line)?;
+    // consumers read each line as soon as the file is inspected
+    stdout.flush()?;
+    Ok(())
+}
+
+// inspects each file and prints one line per file
+fn inspect_batch(args: InspectArgs) -> anyhow::Result<()> {
+    let output = output_of(&args)?;
+    let mut rows: Vec<(PathBuf, anyhow::Result<Inspection>)> = vec![];
+    if let Some(path) = repository_of(&args) {
+        // each version of each model of the repository is a row
+        let (_, versions) = repository::model_versions(path)?;
+        for version in versions {
+            // TorchServe archives without a manifest have no version
+            let name = PathBuf::from(&version.model).join(&version.version);
+            let name = name.components().collect::<PathBuf>();
+            let inspection = inspect_version(&args, &version);
+            if args.jsonl {
+                print_jsonl(&name, &inspection)?;
+            }
+            rows.push((name, inspection));
+        }
+    }
+    let paths = if rows.is_empty() {
+        batch_paths(&args)?
+    } else {
+        vec![]
+    };
+    for path in paths {
+        let inspection = inspect_file(&args, &path, false);
+        if args.jsonl {
+            print_jsonl(&path, &inspection)?;
+        }
+        rows.push((path, inspection));
+    }
 
-    if !args.quiet {
+    // the serialized inspections replace the summary on the standard output
+    let quiet = args.no_print || args.jsonl || matches!(output, Some((_, None)));
+    if !quiet {
+        print_summary(&rows);
+    }
+
+    let failed = rows.iter().filter(|(_, result)| result.is_err()).count();
+    let total = rows.len();
+    let unlicensed = rows
+        .iter()
+        .filter(|(_, result)| matches!(result, Ok(inspection) if inspection.license.is_none()))
+        .count();
+    let incompatible = rows
+        .iter()
+        .filter(|(_, result)| {
+            matches!(result, Ok(inspection) if inspection
+                .operators
+                .as_ref()
+                .is_some_and(|operators| !operators.unavailable.is_empty()))
+        })
+        .count();
+
+    if let Some((format, path)) = output {
+        let inspections: Vec<&Inspection> = rows
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok())
+            .collect();
+        let data = if format == OutputFormat::Toml {
+            serialize(&Inspections { inspections }, &format)?
+        } else {
+            serialize(&inspections, &format)?
+        };
+        save(data, path.as_deref(), quiet)?;
+    }
+
+    if failed > 0 {
+        anyhow::bail!("failed to inspect {} of {} files", failed, total);
+    }
+    if args.require_license && unlicensed > 0 {
+        anyhow::bail!("{} of {} files don't declare a license", unlicensed, total);
+    }
+    if let Some(opset) = args.target_opset.filter(|_| incompatible > 0) {
+        anyhow::bail!(
+            "{} of {} files use operators unavailable in opset {}",
+            incompatible,
+            total,
+            opset
+        );
+    }
+
+    Ok(())
+}
+
+// local paths to watch, the folders of files since editors and export scripts often replace
+// them, along with the files themselves (None for folders)
+fn watch_targets(args: &InspectArgs) -> anyhow::Result<Vec<(PathBuf, Option<PathBuf>)>> {
+    let mut targets = vec![];
+    for path in &args.file_paths {
+        if is_glob(path)
+            || remote::is_url(path)
+            || ObjectUri::is_object_uri(path)
+            || pipe::is_stdin(path)
+            || path.to_string_lossy().starts_with("hf://")
+        {
+            anyhow::bail!(
+                "--watch requires local files or folders, not {}",
+                path.display()
+            );
+        }
+        let path = path
+            .canonicalize()
+            .map_err(|e| anyhow::anyhow!("can't watch {}: {}", path.display(), e))?;
+        if path.is_dir() {
+            targets.push((path, None));
+        } else {
+            let folder = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+            targets.push((folder, Some(path)));
+        }
+    }
+    Ok(targets)
+}
+
+// changes to the watched files, or to anything in the watched folders
+fn is_relevant(event: &notify::Event, targets: &[(PathBuf, Option<PathBuf>)]) -> bool {
+    if matches!(event.kind, notify::EventKind::Access(_)) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        targets.iter().any(|(folder, file)| match file {
+            Some(file) => path == file,
+            None => path.starts_with(folder),
+        })
+    })
+}
+
+// inspects and validates the files, errors are printed since the files may be being written
+fn inspect_and_validate(args: &InspectArgs) {
+    if let Err(e) = inspect(args.clone()) {
+        tracing::error!("{}", e);
+    }
+
+    let Ok(paths) = batch_paths(args) else {
+        return;
+    };
+    for path in paths.iter().filter(|path| validate::is_validatable(path)) {
+        match validate::validate(path) {
+            Ok(validation) if validation.violations.is_empty() => {
+                println!(
+                    "
+{}: valid",
+                    path.display()
+                )
+            }
+            Ok(validation) => {
+                println!(
+                    "
+{}: {} violations",
+                    path.display(),
+                    validation.violations.len()
+                );
+                for violation in &validation.violations {
+                    println!("  {:<22} {}", violation.rule_id, violation.message);
+                }
+            }
+            Err(e) => tracing::warn!("{}: can't validate: {}", path.display(), e),
+        }
+    }
+}
+
+fn watch(mut args: InspectArgs) -> anyhow::Result<()> {
+    args.watch = false;
+    let targets = watch_targets(&args)?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for (folder, file) in &targets {
+        let mode = if file.is_some() {
+            notify::RecursiveMode::NonRecursive
+        } else {
+            notify::RecursiveMode::Recursive
+        };
+        watcher.watch(folder, mode)?;
+    }
+
+    inspect_and_validate(&args);
+    println!(
+        "
+watching for changes, press Ctrl-C to stop ..."
+    );
+
+    loop {
+        let event = rx.recv()??;
+        if !is_relevant(&event, &targets) {
+            continue;
+        }
+        // files are usually written in several steps, wait for them to settle
+        while let Ok(event) = rx.recv_timeout(WATCH_DEBOUNCE) {
+            event?;
+        }
+
+        println!(
+            "
+[{}] {} changed
+",
+            chrono::Local::now().format("%H:%M:%S"),
+            event.paths[0].display()
+        );
+        inspect_and_validate(&args);
+        println!(
+            "
+watching for changes, press Ctrl-C to stop ..."
+        );
+    }
+}
+
+pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
+    if args.watch {
+        return watch(args);
+    }
+    if is_batch(&args) || args.jsonl {
+        return inspect_batch(args);
+    }
+
+    let output = output_of(&args)?;
+    let quiet = args.no_print || matches!(output, Some((_, None)));
+    let inspection = inspect_file(&args, &args.file_paths[0], !quiet)?;
+
+    if !quiet {
         println!("file type:     {}", inspection.file_type);
         println!("version:       {}", inspection.version);
         println!(
@@ -57,6 +1091,41 @@ pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
                 .collect::<Vec<_>>()
                 .join(", ")
         );
+        match &inspection.license {
+            Some(license) => println!("license:       {} ({})", license.name, license.source),
+            None => println!("license:       none found"),
+        }
+
+        if let Some(shards) = &inspection.shards {
+            print_shards(shards);
+        }
+
+        if let Some(members) = &inspection.members {
+            print_members(members);
+        }
+
+        if let Some(profile) = &inspection.profile {
+            println!("\nprofile:\n");
+            for (label, value) in profile.rows() {
+                println!("  {:<16} {}", format!("{}:", label), value);
+            }
+        }
+
+        if let Some(adapter) = &inspection.adapter {
+            println!("\nadapter:\n");
+            println!("  format:         LoRA ({})", adapter.format);
+            if let Some(rank) = adapter.rank {
+                println!("  rank:           {}", rank);
+            }
+            if let Some(alpha) = adapter.alpha {
+                println!("  alpha:          {}", alpha);
+            }
+            println!("  target modules: {}", adapter.target_modules.join(", "));
+            println!("  tensor pairs:   {}", adapter.pairs);
+            if let Some(base_model) = &adapter.base_model {
+                println!("  base model:     {}", base_model);
+            }
+        }
 
         if !inspection.metadata.is_empty() {
             println!("\nmetadata:\n");
@@ -65,7 +1134,32 @@ pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
             }
         }
 
-        if let Some(tensors) = &inspection.tensors {
+        if let Some(inputs) = inspection
+            .inputs
+            .as_ref()
+            .filter(|values| !values.is_empty())
+        {
+            print_values("inputs", inputs);
+        }
+        if let Some(outputs) = inspection
+            .outputs
+            .as_ref()
+            .filter(|values| !values.is_empty())
+        {
+            print_values("outputs", outputs);
+        }
+
+        if let Some(intermediates) = &inspection.intermediates {
+            print_values("shapes", intermediates);
+        }
+
+        if let Some(operators) = &inspection.operators {
+            print_operators(operators, args.target_opset);
+        }
+
+        if let Some(layers) = &inspection.layers {
+            print_layers(layers);
+        } else if let Some(tensors) = &inspection.tensors {
             println!("\ntensors:\n");
 
             for tensor_info in tensors {
@@ -84,6 +1178,23 @@ pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
                     tensor_info.size
                 );
 
+                if let Some(stats) = &tensor_info.stats {
+                    println!(
+                        "    stats: min={} max={} mean={} std={} zeros={:.2}% nan={} inf={}",
+                        stats.min,
+                        stats.max,
+                        stats.mean,
+                        stats.std,
+                        stats.zero_fraction * 100.0,
+                        stats.nan_count,
+                        stats.inf_count
+                    );
+                }
+
+                if let Some(preview) = &tensor_info.preview {
+                    println!("    preview: {}", format_preview(preview));
+                }
+
                 if !tensor_info.metadata.is_empty() {
                     println!("    metadata:");
                     for (meta_key, meta_value) in &tensor_info.metadata {
@@ -96,14 +1207,168 @@ pub(crate) fn inspect(args: InspectArgs) -> anyhow::Result<()> {
         }
     }
 
-    if let Some(json_file_path) = &args.to_json {
-        let json_str = serde_json::to_string_pretty(&inspection)?;
-        std::fs::write(json_file_path, json_str)?;
+    if let Some((format, path)) = output {
+        save(serialize(&inspection, &format)?, path.as_deref(), quiet)?;
+    }
 
-        if !args.quiet {
-            println!("\nsaved to {:?}", json_file_path);
+    if args.require_license && inspection.license.is_none() {
+        anyhow::bail!("{} doesn't declare a license", args.file_paths[0].display());
+    }
+    if let (Some(opset), Some(operators)) = (args.target_opset, &inspection.operators) {
+        if !operators.unavailable.is_empty() {
+            anyhow::bail!(
+                "{} operators of {} are unavailable in opset {}",
+                operators.unavailable.len(),
+                args.file_paths[0].display(),
+                opset
+            );
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_for(file_paths: Vec<PathBuf>) -> InspectArgs {
+        let mut args = InspectArgs::new(PathBuf::new());
+        args.file_paths = file_paths;
+        args.no_print = true;
+        args
+    }
+
+    #[test]
+    fn test_batch_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in ["a.safetensors", "sub/b.gguf", "notes.txt"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        let single = args_for(vec![dir.path().join("a.safetensors")]);
+        assert!(!is_batch(&single));
+
+        let folder = args_for(vec![dir.path().to_path_buf()]);
+        assert!(is_batch(&folder));
+        assert_eq!(
+            batch_paths(&folder).unwrap(),
+            vec![
+                dir.path().join("a.safetensors"),
+                dir.path().join("sub/b.gguf")
+            ]
+        );
+
+        let pattern = args_for(vec![dir.path().join("**/*.gguf")]);
+        assert!(is_batch(&pattern));
+        assert_eq!(
+            batch_paths(&pattern).unwrap(),
+            vec![dir.path().join("sub/b.gguf")]
+        );
+
+        assert!(batch_paths(&args_for(vec![dir.path().join("*.onnx")])).is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let inspection = Inspection {
+            file_path: PathBuf::from("model.gguf"),
+            num_tensors: 1,
+            unique_shapes: vec![vec![2, 3]],
+            metadata: [("general.name".to_string(), "tiny".to_string())].into(),
+            tensors: Some(vec![crate::core::TensorDescriptor {
+                id: Some("w".to_string()),
+                shape: vec![2, 3],
+                dtype: "F32".to_string(),
+                size: 24,
+                metadata: Default::default(),
+                stats: None,
+                preview: None,
+            }]),
+            ..Default::default()
+        };
+
+        for format in [OutputFormat::Json, OutputFormat::Yaml, OutputFormat::Toml] {
+            let data = serialize(&inspection, &format).unwrap();
+            let parsed: Inspection = match format {
+                OutputFormat::Json => serde_json::from_str(&data).unwrap(),
+                OutputFormat::Yaml => serde_yaml_ng::from_str(&data).unwrap(),
+                OutputFormat::Toml => toml::from_str(&data).unwrap(),
+            };
+            assert_eq!(parsed.metadata["general.name"], "tiny");
+            assert_eq!(parsed.tensors.unwrap()[0].shape, vec![2, 3]);
+        }
+
+        // multiple inspections are an array, under a key for TOML
+        let inspections = vec![&inspection, &inspection];
+        assert!(serialize(&Inspections { inspections }, &OutputFormat::Toml)
+            .unwrap()
+            .starts_with("[[inspections]]"));
+
+        assert_eq!(
+            format_of_extension(Path::new("out.YML")).unwrap(),
+            OutputFormat::Yaml
+        );
+        assert!(format_of_extension(Path::new("out.txt")).is_err());
+    }
+
+    #[test]
+    fn test_jsonl_line() {
+        let inspection = Inspection {
+            file_path: PathBuf::from("model.gguf"),
+            num_tensors: 2,
+            ..Default::default()
+        };
+        let line = jsonl_line(Path::new("model.gguf"), &Ok(inspection)).unwrap();
+        assert!(!line.contains('\n'));
+        let parsed: Inspection = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.num_tensors, 2);
+
+        let line = jsonl_line(
+            Path::new("broken.gguf"),
+            &Err(anyhow::anyhow!("invalid header")),
+        )
+        .unwrap();
+        assert_eq!(
+            line,
+            r#"{"error":"invalid header","file_path":"broken.gguf"}"#
+        );
+    }
+
+    #[test]
+    fn test_is_relevant() {
+        let targets = vec![
+            (
+                PathBuf::from("/models"),
+                Some(PathBuf::from("/models/model.gguf")),
+            ),
+            (PathBuf::from("/exports"), None),
+        ];
+        let event = |kind, path: &str| notify::Event::new(kind).add_path(PathBuf::from(path));
+        let modify = notify::EventKind::Modify(notify::event::ModifyKind::Any);
+
+        assert!(is_relevant(&event(modify, "/models/model.gguf"), &targets));
+        assert!(is_relevant(
+            &event(modify, "/exports/onnx/model.onnx"),
+            &targets
+        ));
+        assert!(!is_relevant(&event(modify, "/models/other.gguf"), &targets));
+        assert!(!is_relevant(
+            &event(
+                notify::EventKind::Access(notify::event::AccessKind::Any),
+                "/models/model.gguf"
+            ),
+            &targets
+        ));
+    }
+
+    #[test]
+    fn test_format_preview() {
+        assert_eq!(
+            format_preview(&[0.1f32 as f64, -2.0, f64::NAN, f64::INFINITY, 3e20]),
+            "[0.1, -2, NaN, inf, 300000000000000000000]"
+        );
+        assert_eq!(format_preview(&[]), "[]");
+    }
+}
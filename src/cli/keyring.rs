@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use crate::core::signing::{load_public_key, public_key_to_pem, Keyring};
+
+use super::{KeyArgs, KeyCommand};
+
+pub(crate) fn key(args: KeyArgs) -> anyhow::Result<()> {
+    let mut keyring = Keyring::open_or_default(args.keyring.as_deref())?;
+    let path = keyring.path().to_path_buf();
+
+    match args.command {
+        KeyCommand::Add(args) => {
+            let key = keyring.add(load_public_key(&args.key_path)?, args.name, args.trust)?;
+            println!(
+                "Added {:?} key {}{} to {}",
+                key.algorithm,
+                &key.fingerprint[..16],
+                if key.trusted { " (trusted)" } else { "" },
+                path.display()
+            );
+        }
+        KeyCommand::List => {
+            if keyring.keys().is_empty() {
+                println!("no keys in {}", path.display());
+            }
+
+            for key in keyring.keys() {
+                println!(
+                    "{} {:<12} {:<10} {}{}",
+                    &key.fingerprint[..16],
+                    format!("{:?}", key.algorithm),
+                    if key.trusted { "trusted" } else { "untrusted" },
+                    key.added_at,
+                    key.name
+                        .as_ref()
+                        .map(|name| format!(" {}", name))
+                        .unwrap_or_default()
+                );
+            }
+        }
+        KeyCommand::Remove(args) => {
+            let key = keyring.remove(&args.id)?;
+            println!("Removed key {}", &key.fingerprint[..16]);
+        }
+        KeyCommand::Trust(args) => {
+            let key = keyring.set_trusted(&args.id, !args.distrust)?;
+            println!(
+                "Key {} is {}",
+                &key.fingerprint[..16],
+                if key.trusted {
+                    "trusted"
+                } else {
+                    "no longer trusted"
+                }
+            );
+        }
+        KeyCommand::Export(args) => {
+            let public_key = if Path::new(&args.key).exists() {
+                load_public_key(Path::new(&args.key))?
+            } else {
+                keyring.public_key(&args.key)?
+            };
+            let pem = public_key_to_pem(&public_key)?;
+            match args.output {
+                Some(output) => {
+                    std::fs::write(&output, pem)?;
+                    tracing::info!("Public key written to {}", output.display());
+                }
+                None => print!("{}", pem),
+            }
+        }
+    }
+
+    Ok(())
+}
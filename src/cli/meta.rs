@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::core::{
+    handlers::{self, safetensors, Scope, EMBEDDED_SIGNATURE_KEY},
+    Metadata,
+};
+
+use super::{MetaArgs, MetaCommand};
+
+// KEY=VALUE entries of the metadata to set
+pub(crate) fn parse_entry(entry: &str) -> Result<(String, String), String> {
+    let (key, value) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("invalid entry '{}', use KEY=VALUE", entry))?;
+    if key.is_empty() {
+        return Err(format!("invalid entry '{}', the key is empty", entry));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn check_format(file_path: &Path) -> anyhow::Result<()> {
+    let handler = handlers::handler_for(None, file_path, Scope::Inspection)?;
+    if !handler.file_type().is_safetensors() || safetensors::is_safetensors_index(file_path) {
+        anyhow::bail!("only the metadata of safetensors files can be edited");
+    }
+    Ok(())
+}
+
+fn check_key(key: &str) -> anyhow::Result<()> {
+    if key == EMBEDDED_SIGNATURE_KEY {
+        anyhow::bail!("{} is managed by tman sign", key);
+    }
+    Ok(())
+}
+
+pub(crate) fn meta(args: MetaArgs) -> anyhow::Result<()> {
+    let (file_path, (metadata, signed)) = match args.command {
+        MetaCommand::Set(args) => {
+            check_format(&args.file_path)?;
+            for (key, _) in &args.entries {
+                check_key(key)?;
+            }
+            let edited = safetensors::edit_metadata(&args.file_path, |metadata| {
+                metadata.extend(args.entries.iter().cloned())
+            })?;
+            (args.file_path, edited)
+        }
+        MetaCommand::Remove(args) => {
+            check_format(&args.file_path)?;
+            for key in &args.keys {
+                check_key(key)?;
+            }
+            let edited = safetensors::edit_metadata(&args.file_path, |metadata| {
+                for key in &args.keys {
+                    if metadata.remove(key).is_none() {
+                        tracing::warn!("{} has no {} metadata", args.file_path.display(), key);
+                    }
+                }
+            })?;
+            (args.file_path, edited)
+        }
+        MetaCommand::Clear(args) => {
+            check_format(&args.file_path)?;
+            let edited = safetensors::edit_metadata(&args.file_path, Metadata::clear)?;
+            (args.file_path, edited)
+        }
+    };
+
+    if metadata.is_empty() {
+        println!("{} has no metadata", file_path.display());
+    } else {
+        println!("{} metadata:\n", file_path.display());
+        for (key, value) in &metadata {
+            println!("  {}: {}", key, value);
+        }
+    }
+    if signed {
+        println!(
+            "\nthe embedded signature no longer matches and has been removed, sign the file again"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entry() {
+        assert_eq!(
+            parse_entry("source=hf://org/repo?a=b"),
+            Ok(("source".to_string(), "hf://org/repo?a=b".to_string()))
+        );
+        assert_eq!(
+            parse_entry("empty="),
+            Ok(("empty".to_string(), String::new()))
+        );
+        assert!(parse_entry("novalue").is_err());
+        assert!(parse_entry("=value").is_err());
+    }
+}
@@ -2,19 +2,178 @@ use std::path::PathBuf;
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
+mod badge;
+mod canonicalize;
+mod card;
+mod cast;
+mod check_config;
+mod check_nan;
+mod ci;
+mod compare;
+mod convert;
+mod diff;
+mod estimate;
+mod extract;
+mod gguf_split;
 mod graph;
+mod hash;
+mod hist;
+mod hub;
 mod inspect;
+mod keyring;
+mod meta;
+mod pack;
+mod registry;
+mod report;
+mod scan;
+mod schema;
+mod serve;
 mod signing;
+mod storage;
+mod validate;
 
+pub(crate) use badge::*;
+pub(crate) use canonicalize::*;
+pub(crate) use card::*;
+pub(crate) use cast::*;
+pub(crate) use check_config::*;
+pub(crate) use check_nan::*;
+pub(crate) use ci::*;
+pub(crate) use compare::*;
+pub(crate) use convert::*;
+pub(crate) use diff::*;
+pub(crate) use estimate::*;
+pub(crate) use extract::*;
+pub(crate) use gguf_split::*;
 pub(crate) use graph::*;
+pub(crate) use hash::*;
+pub(crate) use hist::*;
 pub(crate) use inspect::*;
+pub(crate) use keyring::*;
+pub(crate) use meta::*;
+pub(crate) use pack::*;
+pub(crate) use registry::*;
+pub(crate) use report::*;
+pub(crate) use scan::*;
+pub(crate) use schema::*;
+pub(crate) use serve::*;
 pub(crate) use signing::*;
+pub(crate) use validate::*;
 
-use crate::core::FileType;
+use crate::core::{
+    badge::BadgeFormat,
+    estimate::KvCacheType,
+    extract::ExtractFormat,
+    filter::{parse_size, ShapePattern, SortKey},
+    handlers::safetensors::CastType,
+    report::ReportFormat,
+    schema::SchemaKind,
+    signing::SigningAlgorithm,
+    FileType,
+};
+
+pub use crate::core::DetailLevel;
+
+/// Parses the command line and runs the command, returns the exit code of the process.
+pub fn run() -> i32 {
+    let args = Arguments::parse();
+    init_logging(args.verbose, args.quiet, args.log_json);
+
+    let ret = match args.command {
+        Command::Inspect(args) => inspect(args),
+        Command::CreateKey(args) => create_key(args),
+        Command::Sign(args) => sign(args),
+        Command::Verify(args) => verify(*args),
+        Command::Key(args) => key(args),
+        Command::Revoke(args) => revoke(args),
+        Command::PushSignature(args) => push_signature(args),
+        Command::PullSignature(args) => pull_signature(args),
+        Command::Hash(args) => hash(args),
+        Command::Graph(args) => graph(args),
+        Command::Diff(args) => diff(args),
+        Command::CheckNan(args) => check_nan(args),
+        Command::Scan(args) => scan(args),
+        Command::Validate(args) => validate(args),
+        Command::Ci(args) => ci(args),
+        Command::Hist(args) => hist(args),
+        Command::Compare(args) => compare(args),
+        Command::Card(args) => card(args),
+        Command::Report(args) => report(args),
+        Command::Badge(args) => badge(args),
+        Command::Schema(args) => schema(args),
+        Command::Estimate(args) => estimate(args),
+        Command::CheckConfig(args) => check_config(args),
+        Command::Extract(args) => extract(args),
+        Command::Meta(args) => meta(args),
+        Command::Canonicalize(args) => canonicalize(args),
+        Command::Cast(args) => cast(args),
+        Command::Convert(args) => convert(args),
+        Command::Pack(args) => pack(args),
+        Command::Unpack(args) => unpack(args),
+        Command::GgufSplit(args) => gguf_split(args),
+        Command::GgufMerge(args) => gguf_merge(args),
+        Command::Serve(args) => serve(args),
+        Command::Version => {
+            println!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+    };
+
+    match ret {
+        Ok(()) => 0,
+        Err(e) => {
+            tracing::error!("{}", e);
+            // verification failures have their own exit code
+            crate::core::signing::Failure::of(&e)
+                .map(|failure| failure.exit_code())
+                .unwrap_or(1)
+        }
+    }
+}
+
+// log messages go to stderr, leaving stdout to the results
+fn init_logging(verbose: u8, quiet: bool, json: bool) {
+    let level = match verbose {
+        _ if quiet => tracing::Level::WARN,
+        0 => tracing::Level::INFO,
+        1 => tracing::Level::DEBUG,
+        _ => tracing::Level::TRACE,
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr);
+    // fails if the library user already set a subscriber
+    let _ = if json {
+        builder.json().try_init()
+    } else if verbose == 0 {
+        builder.without_time().with_target(false).try_init()
+    } else {
+        builder.try_init()
+    };
+}
+
+// prints a line of the results of a command run by tman, the library only logs it at debug level
+// to leave the output of the application alone
+fn output(print: bool, line: std::fmt::Arguments) {
+    if print {
+        println!("{}", line);
+    } else {
+        tracing::debug!("{}", line);
+    }
+}
 
 #[derive(Debug, Parser)]
 #[clap(name = "tensor-man", version, about)]
 pub(crate) struct Arguments {
+    /// Log debug messages, and trace messages such as the checksum of each file if repeated.
+    #[clap(long, short = 'v', action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Only log warnings and errors, without progress bars.
+    #[clap(long, short = 'q', global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Log as JSON lines instead of text.
+    #[clap(long, global = true)]
+    log_json: bool,
     #[clap(subcommand)]
     pub command: Command,
 }
@@ -28,25 +187,84 @@ pub(crate) enum Command {
     /// Sign the model with the provided key and generate a signature file.
     Sign(SignArgs),
     /// Verify model signature.
-    Verify(VerifyArgs),
-    /// Generate a DOT representation of the graph of the model.
+    Verify(Box<VerifyArgs>),
+    /// Manage the local keyring of trusted public keys.
+    Key(KeyArgs),
+    /// Add a public key to a signed revocation list.
+    Revoke(RevokeArgs),
+    /// Push a signature to an OCI registry, as an artifact referring to the model.
+    PushSignature(PushSignatureArgs),
+    /// Pull the most recent signature of a model from an OCI registry.
+    PullSignature(PullSignatureArgs),
+    /// Compute the SHA-256 checksums of the model files in the sha256sum format.
+    Hash(HashArgs),
+    /// Generate a DOT representation of the graph of the model, or of its module hierarchy.
     Graph(GraphArgs),
+    /// Compare two models and print the differences.
+    Diff(DiffArgs),
+    /// Scan the tensors data for NaN and Inf values.
+    CheckNan(CheckNanArgs),
+    /// Statically scan PyTorch, pickle, ONNX, GGUF and safetensors models for code execution, appended data and other dangerous constructs, without loading them.
+    Scan(ScanArgs),
+    /// Strictly validate the structure of safetensors, GGUF and ONNX files: overlapping, misplaced or out of bounds data, duplicate tensors, dangling node inputs and type mismatches.
+    Validate(ValidateArgs),
+    /// Scan, validate and verify the signatures of model files, for instance those changed by a commit, with a single pass or fail for pre-commit hooks and pipelines.
+    Ci(CiArgs),
+    /// Print the histogram of the values of a tensor.
+    Hist(HistArgs),
+    /// Numerically compare the tensors of two models.
+    Compare(CompareArgs),
+    /// Generate a Markdown model card from the inspection of the model: architecture, parameters, data types, quantization, license and signature status.
+    Card(CardArgs),
+    /// Generate a self-contained HTML or Markdown report of the model, with its metadata, tensors, shape and data type charts and signature status.
+    Report(ReportArgs),
+    /// Verify a model and render a shields.io style badge of the result, for the README of the model.
+    Badge(BadgeArgs),
+    /// Print the JSON Schema of the inspection or signature manifest files.
+    Schema(SchemaArgs),
+    /// Estimate the memory needed to load the model: its weights by data type and the KV cache for the context length and batch size.
+    Estimate(EstimateArgs),
+    /// Check the hidden size, layers, vocabulary size, attention heads and intermediate size of the Hugging Face config.json against the shapes of the safetensors weights next to it.
+    CheckConfig(CheckConfigArgs),
+    /// Extract a single tensor, such as an embedding table or the LM head, to a .npy or .safetensors file.
+    Extract(ExtractArgs),
+    /// Set, remove or clear the __metadata__ entries of a safetensors file in place, the tensor data being left untouched.
+    Meta(MetaArgs),
+    /// Rewrite a safetensors or ONNX file in a canonical byte form, so that identical models exported independently have the same hashes and signatures.
+    Canonicalize(CanonicalizeArgs),
+    /// Cast the floating point tensors of a safetensors file to another data type.
+    Cast(CastArgs),
+    /// Convert the state dict of a PyTorch file to safetensors without running its pickle.
+    Convert(ConvertArgs),
+    /// Move the external data of an ONNX model into the model itself.
+    Pack(PackArgs),
+    /// Move the large initializers of an ONNX model to an external data file.
+    Unpack(UnpackArgs),
+    /// Split a GGUF model into parts in the format of llama.cpp.
+    GgufSplit(GgufSplitArgs),
+    /// Merge the parts of a GGUF model split by llama.cpp into a single file.
+    GgufMerge(GgufMergeArgs),
+    /// Serve inspection and verification over a REST API.
+    Serve(ServeArgs),
     /// Print version and exit.
     Version,
 }
 
-#[derive(Debug, Clone, ValueEnum)]
-pub(crate) enum DetailLevel {
-    /// Print metadata and high level information only.
-    Brief,
-    /// Print the metadata and detailed tensor information.
-    Full,
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    Json,
+    Yaml,
+    Toml,
 }
 
-#[derive(Debug, Args)]
+#[derive(Debug, Clone, Args)]
 pub(crate) struct InspectArgs {
-    // File to inspect.
-    file_path: PathBuf,
+    // File to inspect, hf://org/repo[@revision]/path of a file on the Hugging Face Hub, or the
+    // http(s)://, s3://, gs:// or az:// URL of a SafeTensors, GGUF or ONNX file of which only
+    // the header is downloaded, or - to read it from the standard input. Multiple paths, folders
+    // and glob patterns print a summary table.
+    #[clap(required = true)]
+    file_paths: Vec<PathBuf>,
     /// Override the file format detection by file extension.
     #[clap(long)]
     format: Option<FileType>,
@@ -56,12 +274,106 @@ pub(crate) struct InspectArgs {
     /// If the detail level is set to full, filter the tensors by this substring.
     #[clap(long, short = 'F')]
     filter: Option<String>,
-    /// Suppress inspection output.
+    /// Only list the tensors whose name matches this regular expression, implies full detail level.
+    #[clap(long)]
+    filter_regex: Option<regex::Regex>,
+    /// Only list the tensors of these data types, such as BF16 or Q4_K, implies full detail level.
+    #[clap(long, value_delimiter = ',')]
+    dtype: Vec<String>,
+    /// Only list the tensors at least this large, such as 100MB or 1GiB, implies full detail level.
+    #[clap(long, value_parser = parse_size)]
+    min_size: Option<usize>,
+    /// Only list the tensors at most this large, implies full detail level.
+    #[clap(long, value_parser = parse_size)]
+    max_size: Option<usize>,
+    /// Only list the tensors of this shape, such as 4096,4096 or *,4096, implies full detail level.
+    #[clap(long)]
+    shape: Option<ShapePattern>,
+    /// Sort the listed tensors, implies full detail level.
+    #[clap(long)]
+    sort_by: Option<SortKey>,
+    /// Sort the listed tensors in descending order.
+    #[clap(long)]
+    desc: bool,
+    /// Only list the first N tensors, after sorting, implies full detail level.
+    #[clap(long)]
+    top: Option<usize>,
+    /// Group the tensors by layer, such as model.layers.17, and print the tensors, parameters and size of each layer instead of each tensor, implies full detail level.
+    #[clap(long)]
+    group_by_layer: bool,
+    /// Compute per tensor value statistics (min, max, mean, std, zeros and NaN/Inf counts), implies full detail level.
+    #[clap(long)]
+    stats: bool,
+    /// Print the first N values of each listed tensor, decoded per data type, implies full detail level.
+    #[clap(long)]
+    preview: Option<usize>,
+    /// Don't print the inspection, for instance when only saving it.
     #[clap(long, short = 'Q')]
-    quiet: bool,
-    /// Save as JSON to the specified file.
+    no_print: bool,
+    /// Save as JSON to the specified file, an array of inspections for multiple files.
     #[clap(long, short = 'J')]
     to_json: Option<PathBuf>,
+    /// Serialize the inspection in this format, printed instead of the summary unless --to-file is set.
+    #[clap(long)]
+    output_format: Option<OutputFormat>,
+    /// Save the inspection to the specified file, in the output format or the one of its extension (.json, .yaml, .yml or .toml).
+    #[clap(long, conflicts_with = "to_json")]
+    to_file: Option<PathBuf>,
+    /// Print each inspection as a JSON line as soon as its file is inspected, errors included, instead of the summary.
+    #[clap(long, conflicts_with_all = ["to_json", "output_format", "to_file"])]
+    jsonl: bool,
+    /// Include the GGUF metadata with their native types and arrays in full, such as the tokenizer vocabulary, in the serialized inspection.
+    #[clap(long)]
+    full_metadata: bool,
+    /// Check that the LoRA adapter being inspected is compatible with the tensors of this base model, a file or folder.
+    #[clap(long)]
+    base: Option<PathBuf>,
+    /// Fail if the model doesn't declare a license in its metadata or config.json, or any of the files for multiple files.
+    #[clap(long)]
+    require_license: bool,
+    /// List the tensors computed by the graph of ONNX models with their shapes, inferred from the inputs and initializers when the model doesn't declare them.
+    #[clap(long)]
+    shapes: bool,
+    /// Fail if the operators of ONNX models aren't all available in this opset of the default ai.onnx domain, or any of the files for multiple files.
+    #[clap(long)]
+    target_opset: Option<i64>,
+    /// Keep watching the files or folders and inspect them again, along with the validation of safetensors, GGUF and ONNX files, whenever they change.
+    #[clap(long, conflicts_with = "jsonl")]
+    watch: bool,
+}
+
+impl InspectArgs {
+    /// Arguments to inspect the file, with the default options.
+    pub(crate) fn new(file_path: PathBuf) -> Self {
+        Self {
+            file_paths: vec![file_path],
+            format: None,
+            detail: DetailLevel::Brief,
+            filter: None,
+            filter_regex: None,
+            dtype: vec![],
+            min_size: None,
+            max_size: None,
+            shape: None,
+            sort_by: None,
+            desc: false,
+            top: None,
+            group_by_layer: false,
+            full_metadata: false,
+            base: None,
+            stats: false,
+            preview: None,
+            no_print: false,
+            to_json: None,
+            output_format: None,
+            to_file: None,
+            jsonl: false,
+            watch: false,
+            require_license: false,
+            shapes: false,
+            target_opset: None,
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -72,11 +384,15 @@ pub(crate) struct CreateKeyArgs {
     /// Output path for public key file.
     #[clap(long, default_value = "./public.key")]
     public_key: PathBuf,
+    /// Signing algorithm.
+    #[clap(long, value_enum, default_value_t = SigningAlgorithm::Ed25519)]
+    algorithm: SigningAlgorithm,
 }
 
 #[derive(Debug, Args)]
 pub(crate) struct SignArgs {
-    // File to sign.
+    // File or folder to sign, hf://org/repo[@revision][/path] of a model on the Hugging Face Hub,
+    // or s3://, gs:// or az:// URI of an object or prefix of an object store.
     file_path: PathBuf,
     /// Override the file format detection by file extension.
     #[clap(long)]
@@ -90,24 +406,284 @@ pub(crate) struct SignArgs {
     /// Ignore files and folders matching this pattern.
     #[clap(long, short = 'I')]
     ignore: Option<String>,
+    /// Hash the files in chunks of this size in bytes, allows to verify byte ranges individually.
+    #[clap(long)]
+    chunk_size: Option<u64>,
+    /// Embed the signature in the file metadata instead of writing a separate signature file (safetensors, GGUF and ONNX only).
+    #[clap(long, conflicts_with_all = ["output", "chunk_size"])]
+    embed: bool,
+    /// Request a RFC 3161 trusted timestamp of the signature from this TSA (http:// only).
+    #[clap(long)]
+    tsa_url: Option<String>,
+    /// Add a signature to the existing signature manifest instead of creating a new one.
+    #[clap(long, conflicts_with = "chunk_size")]
+    append: bool,
+    /// Make the signature expire after this duration, for instance 90d, 12h or 4w.
+    #[clap(long)]
+    expires_in: Option<String>,
+    /// Include the full public key in the signature, so that it can be verified with --trust-first-use.
+    #[clap(long)]
+    include_public_key: bool,
+    /// Sign the SHA-256 of the files in a cosign compatible DSSE envelope instead of a signature manifest.
+    #[clap(long, conflicts_with_all = ["embed", "chunk_size", "tsa_url", "expires_in", "include_public_key"])]
+    dsse: bool,
+    /// Commit the signature to the Hugging Face Hub repository of the model, requires HF_TOKEN, or store it next to the objects of an object store.
+    #[clap(long)]
+    upload: bool,
+    /// Sign each version of each model of a Triton or TorchServe model repository with its own signature manifest.
+    #[clap(long, conflicts_with_all = ["output", "embed", "dsse", "upload"])]
+    repository: bool,
+    // whether the signatures are printed, only by tman
+    #[clap(skip = true)]
+    print: bool,
+}
+
+impl SignArgs {
+    /// Arguments to sign the file or folder with the private key, with the default options.
+    pub(crate) fn new(file_path: PathBuf, key_path: PathBuf) -> Self {
+        Self {
+            file_path,
+            format: None,
+            key_path,
+            output: None,
+            ignore: None,
+            chunk_size: None,
+            embed: false,
+            tsa_url: None,
+            append: false,
+            expires_in: None,
+            include_public_key: false,
+            dsse: false,
+            upload: false,
+            repository: false,
+            print: false,
+        }
+    }
 }
 
 #[derive(Debug, Args)]
 pub(crate) struct VerifyArgs {
-    // File to verify.
+    // File or folder to verify, hf://org/repo[@revision][/path] of a model on the Hugging Face Hub,
+    // or s3://, gs:// or az:// URI of an object or prefix of an object store.
     file_path: PathBuf,
     /// Override the file format detection by file extension.
     #[clap(long)]
     format: Option<FileType>,
-    /// Public key file.
+    /// Public key file, can be repeated to verify a manifest signed by multiple parties.
+    /// If not set the trusted keys of the keyring are used.
     #[clap(long, short = 'K')]
-    key_path: PathBuf,
-    /// Signature file. If not set the file name will be used as base name.
+    key_path: Vec<PathBuf>,
+    /// Minimum number of valid signatures made with different public keys.
+    #[clap(long, default_value_t = 1)]
+    require_signers: usize,
+    /// YAML or JSON policy file with the trusted public keys, the threshold of signatures required and optional requirements on the algorithms, the age of the signatures and the signed files.
+    #[clap(long, conflicts_with_all = ["key_path", "require_signers"])]
+    policy: Option<PathBuf>,
+    /// Keyring folder to load the trusted keys from, defaults to the user configuration folder.
+    #[clap(long, conflicts_with_all = ["key_path", "policy"])]
+    keyring: Option<PathBuf>,
+    /// Verify with the public keys included in the signature the first time the model is verified, and pin them in the keyring for the next times.
+    #[clap(long, conflicts_with_all = ["key_path", "policy"])]
+    trust_first_use: bool,
+    /// Verify the signature manifest of each version of each model of a Triton or TorchServe model repository.
+    #[clap(long, conflicts_with_all = ["signature", "range", "trust_first_use", "json", "sarif"])]
+    repository: bool,
+    /// Verify against a sha256sum style checksums file instead of a signature.
+    #[clap(long, conflicts_with_all = ["key_path", "policy", "keyring", "trust_first_use", "signature", "range", "repository"])]
+    checksums: Option<PathBuf>,
+    /// Verify the checksums of the decompressed content of .gz, .zst and .xz files.
+    #[clap(long, requires = "checksums")]
+    decompress: bool,
+    /// Signed revocation list, signatures made with the revoked keys are refused.
+    #[clap(long)]
+    revocations: Option<PathBuf>,
+    /// Public key of the revocation list issuer, if not set the list must be signed by one of the verifying keys.
+    #[clap(long, requires = "revocations")]
+    revocations_key: Option<PathBuf>,
+    /// Signature manifest or DSSE envelope file. If not set the signature embedded in the file is used if present, otherwise the file name will be used as base name.
     #[clap(long, short = 'S')]
     signature: Option<PathBuf>,
     /// Ignore files and folders matching this pattern.
     #[clap(long, short = 'I')]
     ignore: Option<String>,
+    /// Only verify this byte range (START:END) of the file, requires a file signed with --chunk-size.
+    #[clap(long)]
+    range: Option<String>,
+    /// Check every file instead of stopping at the first mismatch, and list the files that are OK, modified, missing or extra.
+    #[clap(long, conflicts_with_all = ["checksums", "range"])]
+    report: bool,
+    /// Save the verdict (status, cause of the failure, files and signers) as JSON to the specified file, whether the verification succeeds or not.
+    #[clap(long)]
+    json: Option<PathBuf>,
+    /// Save the failures as a SARIF log to the specified file, for GitHub code scanning and other SARIF consumers.
+    #[clap(long)]
+    sarif: Option<PathBuf>,
+    /// Certificate (PEM or DER) of the TSA the signature timestamp must be signed by.
+    #[clap(long)]
+    tsa_cert: Option<PathBuf>,
+    /// Require the signature to be timestamped before this RFC 3339 date, for instance a key compromise date.
+    #[clap(long, requires = "tsa_cert")]
+    signed_before: Option<String>,
+    /// Refuse signatures older than this duration, for instance 90d, 12h or 4w.
+    #[clap(long)]
+    max_age: Option<String>,
+    // identifies remote models regardless of the revision, for the keys pinned on first use
+    #[clap(skip)]
+    model_id: Option<String>,
+    // whether the signers and the results are printed, only by tman
+    #[clap(skip = true)]
+    print: bool,
+}
+
+impl VerifyArgs {
+    /// Arguments to verify the file or folder against its signature with the public keys, with
+    /// the default options.
+    pub(crate) fn new(file_path: PathBuf, key_path: Vec<PathBuf>) -> Self {
+        Self {
+            file_path,
+            format: None,
+            key_path,
+            require_signers: 1,
+            policy: None,
+            keyring: None,
+            trust_first_use: false,
+            repository: false,
+            checksums: None,
+            decompress: false,
+            revocations: None,
+            revocations_key: None,
+            signature: None,
+            ignore: None,
+            range: None,
+            report: false,
+            json: None,
+            sarif: None,
+            tsa_cert: None,
+            signed_before: None,
+            max_age: None,
+            model_id: None,
+            print: false,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct HashArgs {
+    // File or folder to hash.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Ignore files and folders matching this pattern.
+    #[clap(long, short = 'I')]
+    ignore: Option<String>,
+    /// Write the checksums to this file instead of printing them.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+    /// Hash the decompressed content of .gz, .zst and .xz files instead of the files themselves.
+    #[clap(long)]
+    decompress: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RevokeArgs {
+    // Public key file or fingerprint of the key to revoke.
+    key: PathBuf,
+    /// Private key of the issuer of the revocation list.
+    #[clap(long, short = 'K')]
+    key_path: PathBuf,
+    /// Reason of the revocation.
+    #[clap(long, short = 'R')]
+    reason: String,
+    /// RFC 3339 date the key has been revoked at, defaults to now.
+    #[clap(long)]
+    revoked_at: Option<String>,
+    /// Revocation list file, updated if it already exists.
+    #[clap(long, short = 'O', default_value = "./revocations.json")]
+    output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct PushSignatureArgs {
+    // Reference of the model in the registry, for instance ghcr.io/org/model:v1.
+    reference: String,
+    /// Signature manifest or DSSE envelope file.
+    #[clap(long, short = 'S')]
+    signature: PathBuf,
+    /// Connect to the registry over plain HTTP instead of HTTPS.
+    #[clap(long)]
+    plain_http: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct PullSignatureArgs {
+    // Reference of the model in the registry, for instance ghcr.io/org/model:v1.
+    reference: String,
+    /// Output signature file. If not set the file name the signature has been pushed with is used.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+    /// Connect to the registry over plain HTTP instead of HTTPS.
+    #[clap(long)]
+    plain_http: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct KeyArgs {
+    /// Keyring folder, defaults to the user configuration folder.
+    #[clap(long, global = true)]
+    keyring: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: KeyCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum KeyCommand {
+    /// Import a public key in the keyring.
+    Add(KeyAddArgs),
+    /// List the keys in the keyring.
+    List,
+    /// Remove a key from the keyring.
+    Remove(KeyIdArgs),
+    /// Mark a key as trusted for verification.
+    Trust(KeyTrustArgs),
+    /// Print a public key in the PEM format used by cosign and openssl.
+    Export(KeyExportArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct KeyAddArgs {
+    // Public key file, raw, OpenSSH, minisign or PEM.
+    key_path: PathBuf,
+    /// Human readable name of the key.
+    #[clap(long, short = 'N')]
+    name: Option<String>,
+    /// Also mark the key as trusted.
+    #[clap(long)]
+    trust: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct KeyIdArgs {
+    // Name or fingerprint prefix of the key.
+    id: String,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct KeyTrustArgs {
+    // Name or fingerprint prefix of the key.
+    id: String,
+    /// Stop trusting the key instead.
+    #[clap(long)]
+    distrust: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct KeyExportArgs {
+    // Public key file, or name or fingerprint prefix of a key in the keyring.
+    key: String,
+    /// Write the PEM public key to this file instead of printing it.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
@@ -117,7 +693,448 @@ pub(crate) struct GraphArgs {
     /// Output DOT file.
     #[clap(long, short = 'O', default_value = "graph.dot")]
     output: PathBuf,
+    /// Write an interactive HTML page of the graph to this file instead of the DOT file.
+    #[clap(long)]
+    html: Option<PathBuf>,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct DiffArgs {
+    // Original file.
+    old_file_path: PathBuf,
+    // File to compare with the original one.
+    new_file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Save as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CheckNanArgs {
+    // File to scan.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Only scan the tensors whose name contains this substring.
+    #[clap(long, short = 'F')]
+    filter: Option<String>,
+    /// Save the tensors with NaN/Inf values as a SARIF log to the specified file.
+    #[clap(long)]
+    sarif: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ScanArgs {
+    // File or folder to scan.
+    file_path: PathBuf,
+    /// Save the results as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+    /// Save the suspicious and dangerous imports as a SARIF log to the specified file.
+    #[clap(long)]
+    sarif: Option<PathBuf>,
+    /// Also scan the tensor data of safetensors, GGUF and ONNX models for regions with an entropy inconsistent with weights, such as embedded encrypted archives.
+    #[clap(long)]
+    entropy: bool,
+    /// Experimental: also test the least significant mantissa bits of the floating point tensors for patterns left by data hidden in them.
+    #[clap(long)]
+    lsb: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ValidateArgs {
+    // File or folder to validate.
+    file_path: PathBuf,
+    /// Save the violations as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+    /// Save the violations as a SARIF log to the specified file.
+    #[clap(long)]
+    sarif: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CiArgs {
+    // Model files or folders to check, other files are ignored.
+    #[clap(required_unless_present = "changed_since")]
+    file_paths: Vec<PathBuf>,
+    /// Also check the model files changed since this git revision, for instance origin/main.
+    #[clap(long)]
+    changed_since: Option<String>,
+    /// YAML or JSON policy file to verify the signatures with.
+    #[clap(long)]
+    policy: Option<PathBuf>,
+    /// Public key to verify the signatures with, can be repeated. If not set the trusted keys of the keyring are used.
+    #[clap(long, short = 'K', conflicts_with = "policy")]
+    key_path: Vec<PathBuf>,
+    /// Keyring folder to load the trusted keys from, defaults to the user configuration folder.
+    #[clap(long, conflicts_with_all = ["key_path", "policy"])]
+    keyring: Option<PathBuf>,
+    /// Only scan and validate the models, without verifying their signatures.
+    #[clap(long, conflicts_with_all = ["key_path", "policy", "keyring"])]
+    no_verify: bool,
+    /// Print the problems as GitHub Actions annotations, the default when run by GitHub Actions.
+    #[clap(long)]
+    github: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct HistArgs {
+    // File to inspect.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Name of the tensor.
+    #[clap(long, short = 'T')]
+    tensor: String,
+    /// Number of bins.
+    #[clap(long, short = 'B', default_value_t = 20)]
+    bins: usize,
+    /// Save as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CompareArgs {
+    // First file to compare.
+    file_path_a: PathBuf,
+    // Second file to compare.
+    file_path_b: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Only compare the tensors whose name contains this substring.
+    #[clap(long, short = 'F')]
+    filter: Option<String>,
+    /// Relative tolerance.
+    #[clap(long, default_value_t = 1e-5)]
+    rtol: f64,
+    /// Absolute tolerance.
+    #[clap(long, default_value_t = 1e-8)]
+    atol: f64,
+    /// Save as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CardArgs {
+    // Model file or folder.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Name of the model, defaults to its general.name metadata or its file name.
+    #[clap(long)]
+    name: Option<String>,
+    /// Write the card to this file instead of printing it, for instance README.md.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ReportArgs {
+    // Model file or folder.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Name of the model, defaults to its general.name metadata or its file name.
+    #[clap(long)]
+    name: Option<String>,
+    /// Write the report to this file instead of printing it, as HTML for .html and .htm and Markdown for .md.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+    /// Report format, overriding the one of the output file extension.
+    #[clap(long)]
+    report_format: Option<ReportFormat>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct BadgeArgs {
+    // Model file or folder to verify.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Public key to verify the model with, can be repeated. If not set the trusted keys of the keyring are used.
+    #[clap(long, short = 'K')]
+    key_path: Vec<PathBuf>,
+    /// YAML or JSON policy file to verify the model with.
+    #[clap(long, conflicts_with = "key_path")]
+    policy: Option<PathBuf>,
+    /// Keyring folder to load the trusted keys from, defaults to the user configuration folder.
+    #[clap(long, conflicts_with_all = ["key_path", "policy"])]
+    keyring: Option<PathBuf>,
+    /// Signature manifest or DSSE envelope file, defaults to the one next to the model or embedded in it.
+    #[clap(long, short = 'S')]
+    signature: Option<PathBuf>,
+    /// Text of the left half of the badge.
+    #[clap(long, default_value = "tman")]
+    label: String,
+    /// Write the badge to this file instead of printing it, as SVG for .svg and shields.io endpoint JSON for .json.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+    /// Badge format, overriding the one of the output file extension.
+    #[clap(long)]
+    badge_format: Option<BadgeFormat>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SchemaArgs {
+    /// Files to print the schema of.
+    #[clap(default_value = "inspection")]
+    kind: SchemaKind,
+    /// Write the schema to this file instead of printing it.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct EstimateArgs {
+    // Model file or folder.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Context length in tokens, defaults to the one the model has been trained with.
+    #[clap(long)]
+    context: Option<usize>,
+    /// Number of sequences processed in parallel.
+    #[clap(long, default_value_t = 1)]
+    batch: usize,
+    /// Element type of the KV cache.
+    #[clap(long, default_value = "f16")]
+    kv_type: KvCacheType,
+    /// Save as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CheckConfigArgs {
+    // Model folder, or one of its files.
+    file_path: PathBuf,
+    /// Save as JSON to the specified file.
+    #[clap(long, short = 'J')]
+    to_json: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ExtractArgs {
+    // Model file.
+    file_path: PathBuf,
+    /// Override the file format detection by file extension.
+    #[clap(long)]
+    format: Option<FileType>,
+    /// Name of the tensor to extract.
+    #[clap(long, short = 'T')]
+    tensor: String,
+    /// Output file.
+    #[clap(long, short = 'O')]
+    output: PathBuf,
+    /// Output format, by default the one of the output file extension (.npy or .safetensors).
+    #[clap(long)]
+    output_format: Option<ExtractFormat>,
+    /// Extract the data of GGUF tensors as stored, quantization blocks included, as a 1D array of bytes instead of dequantizing them.
+    #[clap(long)]
+    raw: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MetaArgs {
+    #[clap(subcommand)]
+    command: MetaCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum MetaCommand {
+    /// Add or replace metadata entries.
+    Set(MetaSetArgs),
+    /// Remove metadata entries.
+    Remove(MetaRemoveArgs),
+    /// Remove all the metadata.
+    Clear(MetaClearArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MetaSetArgs {
+    // Safetensors file.
+    file_path: PathBuf,
+    /// Entries to set, as KEY=VALUE.
+    #[clap(required = true, value_parser = parse_entry)]
+    entries: Vec<(String, String)>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MetaRemoveArgs {
+    // Safetensors file.
+    file_path: PathBuf,
+    /// Keys of the entries to remove.
+    #[clap(required = true)]
+    keys: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MetaClearArgs {
+    // Safetensors file.
+    file_path: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CanonicalizeArgs {
+    // Safetensors or ONNX file.
+    file_path: PathBuf,
     /// Override the file format detection by file extension.
     #[clap(long)]
     format: Option<FileType>,
+    /// Write the canonical file here instead of rewriting the file in place.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CastArgs {
+    // Safetensors file.
+    file_path: PathBuf,
+    /// Output file, can be the input file itself.
+    #[clap(long, short = 'O')]
+    output: PathBuf,
+    /// Data type to cast the floating point tensors to.
+    #[clap(long)]
+    dtype: CastType,
+    /// Only cast the tensors whose name matches this regular expression.
+    #[clap(long)]
+    include: Option<regex::Regex>,
+    /// Don't cast the tensors whose name matches this regular expression, such as norm layers.
+    #[clap(long)]
+    exclude: Option<regex::Regex>,
+    /// Print each tensor that has been cast.
+    #[clap(long, short = 'V')]
+    verbose: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ConvertArgs {
+    // PyTorch file.
+    file_path: PathBuf,
+    /// Output safetensors file.
+    #[clap(long, short = 'O')]
+    output: PathBuf,
+    /// Sign the output with this private key.
+    #[clap(long, short = 'K')]
+    key_path: Option<PathBuf>,
+    /// Embed the signature in the output instead of writing a separate signature file.
+    #[clap(long, requires = "key_path")]
+    embed: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct PackArgs {
+    // ONNX file.
+    file_path: PathBuf,
+    /// Write the packed model here instead of rewriting the file in place.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct UnpackArgs {
+    // ONNX file.
+    file_path: PathBuf,
+    /// Write the unpacked model here instead of rewriting the file in place.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+    /// Move the initializers at least this large, such as 1MB or 100KiB.
+    #[clap(long, value_parser = parse_size, default_value = "1MB")]
+    threshold: usize,
+    /// Name of the external data file, relative to the folder of the output. Defaults to the output file name followed by .data.
+    #[clap(long)]
+    location: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct GgufSplitArgs {
+    // GGUF file.
+    file_path: PathBuf,
+    /// Prefix of the parts, followed by -00001-of-0000N.gguf. Defaults to the input file without extension.
+    #[clap(long, short = 'O')]
+    output: Option<PathBuf>,
+    /// Maximum number of tensors per part.
+    #[clap(long, default_value_t = 128)]
+    max_tensors: usize,
+    /// Maximum size of the tensor data per part, such as 2GB or 500MiB.
+    #[clap(long, value_parser = parse_size, conflicts_with = "max_tensors")]
+    max_size: Option<usize>,
+    /// Sign the parts with this private key.
+    #[clap(long, short = 'K')]
+    key_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct GgufMergeArgs {
+    // Any part of the split GGUF model.
+    file_path: PathBuf,
+    /// Output GGUF file.
+    #[clap(long, short = 'O')]
+    output: PathBuf,
+    /// Sign the merged model with this private key.
+    #[clap(long, short = 'K')]
+    key_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub(crate) struct ServeArgs {
+    /// Address to listen on.
+    #[clap(long, short = 'L', default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// Also serve the gRPC service on this address, for instance 127.0.0.1:50051.
+    #[cfg(feature = "grpc")]
+    #[clap(long)]
+    grpc_listen: Option<String>,
+    /// Folder of the models that can be inspected and verified, paths of the requests are relative to it.
+    #[clap(long, default_value = ".")]
+    root: PathBuf,
+    /// Also accept requests for Hugging Face Hub and object store models, which are downloaded with the credentials and disk of the server.
+    #[clap(long)]
+    allow_remote: bool,
+    /// Number of requests handled at the same time, the others waiting for a worker.
+    #[clap(long, default_value_t = 4)]
+    workers: usize,
+    /// Public key to verify the models with, can be repeated. If not set the trusted keys of the keyring are used.
+    #[clap(long, short = 'K')]
+    key_path: Vec<PathBuf>,
+    /// YAML or JSON policy file to verify the models with.
+    #[clap(long, conflicts_with = "key_path")]
+    policy: Option<PathBuf>,
+    /// Keyring folder to load the trusted keys from, defaults to the user configuration folder.
+    #[clap(long, conflicts_with_all = ["key_path", "policy"])]
+    keyring: Option<PathBuf>,
+}
+
+#[cfg(test)]
+impl ServeArgs {
+    /// Arguments to serve the folder, with the default options.
+    pub(crate) fn new(root: PathBuf) -> Self {
+        Self {
+            listen: String::new(),
+            #[cfg(feature = "grpc")]
+            grpc_listen: None,
+            root,
+            allow_remote: false,
+            workers: 4,
+            key_path: vec![],
+            policy: None,
+            keyring: None,
+        }
+    }
 }
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::handlers::onnx::{self, Relocation};
+
+use super::{PackArgs, UnpackArgs};
+
+fn check_format(file_path: &Path) -> anyhow::Result<()> {
+    if !file_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("onnx"))
+    {
+        anyhow::bail!("only ONNX files can be packed and unpacked");
+    }
+    Ok(())
+}
+
+// prints the external data files the rewritten model no longer reads
+fn print_unreferenced(
+    input: &Path,
+    output: &Path,
+    relocation: &Relocation,
+    data_path: Option<&Path>,
+) {
+    let same_file = |a: &Path, b: &Path| {
+        a.canonicalize()
+            .ok()
+            .is_some_and(|a| b.canonicalize().is_ok_and(|b| a == b))
+    };
+    if !same_file(input, output) {
+        return;
+    }
+    for data_file in &relocation.data_files {
+        if data_path.is_none_or(|data_path| !same_file(data_file, data_path)) {
+            tracing::info!(
+                "{} is no longer referenced by the model and can be deleted",
+                data_file.display()
+            );
+        }
+    }
+}
+
+fn print_size(relocation: &Relocation, output: &Path) -> anyhow::Result<()> {
+    println!(
+        "{}, saved to {}",
+        humansize::format_size(std::fs::metadata(output)?.len(), humansize::DECIMAL),
+        output.display()
+    );
+    if relocation.signed {
+        tracing::warn!("the embedded signature has been removed, sign the model again");
+    }
+    Ok(())
+}
+
+pub(crate) fn pack(args: PackArgs) -> anyhow::Result<()> {
+    check_format(&args.file_path)?;
+    let output = args.output.unwrap_or(args.file_path.clone());
+    let relocation = onnx::pack(&args.file_path, &output)?;
+
+    print!(
+        "moved the external data of {} tensors into the model, ",
+        relocation.tensors.len()
+    );
+    print_size(&relocation, &output)?;
+    print_unreferenced(&args.file_path, &output, &relocation, None);
+
+    Ok(())
+}
+
+pub(crate) fn unpack(args: UnpackArgs) -> anyhow::Result<()> {
+    check_format(&args.file_path)?;
+    let output = args.output.unwrap_or(args.file_path.clone());
+    let location = args.location.unwrap_or_else(|| {
+        format!(
+            "{}.data",
+            output.file_name().unwrap_or_default().to_string_lossy()
+        )
+    });
+    let relocation = onnx::unpack(&args.file_path, &output, args.threshold, &location)?;
+
+    let data_path: PathBuf = output.with_file_name(&location);
+    if relocation.tensors.is_empty() {
+        print!(
+            "no tensor of at least {}, ",
+            humansize::format_size(args.threshold, humansize::DECIMAL)
+        );
+    } else {
+        print!(
+            "moved the data of {} tensors to {}, ",
+            relocation.tensors.len(),
+            data_path.display()
+        );
+    }
+    print_size(&relocation, &output)?;
+    print_unreferenced(&args.file_path, &output, &relocation, Some(&data_path));
+
+    Ok(())
+}
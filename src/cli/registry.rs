@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::{
+    registry::{Client, Reference, DSSE_MEDIA_TYPE, MANIFEST_MEDIA_TYPE},
+    signing::{Envelope, Manifest},
+};
+
+use super::{PullSignatureArgs, PushSignatureArgs};
+
+pub(crate) fn push_signature(args: PushSignatureArgs) -> anyhow::Result<()> {
+    let reference = Reference::parse(&args.reference)?;
+    let data = std::fs::read_to_string(&args.signature)?;
+
+    // make sure we're pushing a signature
+    let media_type = if Envelope::is_envelope(&data) {
+        serde_json::from_str::<Envelope>(&data)?.statement()?;
+        DSSE_MEDIA_TYPE
+    } else {
+        Manifest::from_signature_str(Path::new("."), &data).map_err(|e| {
+            anyhow::anyhow!("{} is not a signature: {}", args.signature.display(), e)
+        })?;
+        MANIFEST_MEDIA_TYPE
+    };
+    let title = args
+        .signature
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut client = Client::new(&reference, args.plain_http);
+    let subject = client.resolve(&reference)?;
+    tracing::info!("Model {} resolved to {}", reference, subject.digest);
+
+    let artifact = client.push_signature(&subject, media_type, &title, data.as_bytes())?;
+    println!(
+        "Signature {} pushed as {}@{}",
+        args.signature.display(),
+        reference.repository,
+        artifact.digest
+    );
+
+    Ok(())
+}
+
+pub(crate) fn pull_signature(args: PullSignatureArgs) -> anyhow::Result<()> {
+    let reference = Reference::parse(&args.reference)?;
+
+    let mut client = Client::new(&reference, args.plain_http);
+    let subject = client.resolve(&reference)?;
+    tracing::info!("Model {} resolved to {}", reference, subject.digest);
+
+    let mut signatures = client.signatures(&subject)?;
+    if signatures.is_empty() {
+        anyhow::bail!("no signatures found for {}", reference);
+    }
+    // RFC 3339 dates of the same format sort chronologically, pull the most recent one
+    signatures.sort_by(|a, b| a.created().cmp(&b.created()));
+    let artifact = signatures.last().unwrap();
+    tracing::info!(
+        "Found {} signature(s), pulling {} created at {}",
+        signatures.len(),
+        artifact.digest,
+        artifact.created().unwrap_or("unknown date")
+    );
+
+    let signature = client.pull_signature(artifact)?;
+    let output = match args.output {
+        Some(output) => output,
+        None => match signature
+            .title
+            .as_deref()
+            .map(Path::new)
+            .and_then(Path::file_name)
+        {
+            Some(name) => PathBuf::from(name),
+            None if signature.media_type == DSSE_MEDIA_TYPE => {
+                PathBuf::from("tensor-man.intoto.json")
+            }
+            None => PathBuf::from("tensor-man.signature"),
+        },
+    };
+    std::fs::write(&output, &signature.data)?;
+
+    tracing::info!("Signature written to {}", output.display());
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::core::{
+    handlers::{self, Scope},
+    report::{Report, ReportFormat},
+};
+
+use super::{
+    card::{base_path_of, model_name, model_paths, signature_of},
+    DetailLevel, ReportArgs,
+};
+
+pub(crate) fn report(args: ReportArgs) -> anyhow::Result<()> {
+    // the format given, or the one of the output file extension, or Markdown
+    let format = match (&args.report_format, &args.output) {
+        (Some(format), _) => format.clone(),
+        (None, Some(output)) => output
+            .extension()
+            .and_then(|ext| ReportFormat::of_extension(&ext.to_string_lossy()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "can't determine the report format of {}, use --report-format",
+                    output.display()
+                )
+            })?,
+        (None, None) => ReportFormat::Markdown,
+    };
+
+    let mut report = Report::default();
+    for path in model_paths(args.format.clone(), &args.file_path)? {
+        let handler = handlers::handler_for(args.format.clone(), &path, Scope::Inspection)?;
+        report
+            .inspections
+            .push(handler.inspect(&path, DetailLevel::Full, None)?);
+    }
+    report.signature = signature_of(
+        args.format.clone(),
+        &args.file_path,
+        &base_path_of(&args.file_path),
+    )?;
+    report.name = model_name(args.name.clone(), &args.file_path, &report.inspections);
+
+    let rendered = report.render(&format);
+    if let Some(output) = &args.output {
+        std::fs::write(output, rendered)?;
+        tracing::info!("Report written to {}", output.display());
+    } else {
+        print!("{}", rendered);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use crate::core::{
+    sarif::{self, Finding},
+    scan::{self, Issue, Safety, Scan, RULES},
+};
+
+use super::ScanArgs;
+
+// the files to scan, recursively if the path is a folder
+fn paths_to_scan(args: &ScanArgs) -> anyhow::Result<Vec<PathBuf>> {
+    if !args.file_path.is_dir() {
+        return Ok(vec![args.file_path.clone()]);
+    }
+
+    let pattern = args.file_path.join("**").join("*");
+    let mut paths: Vec<PathBuf> = glob::glob(&pattern.to_string_lossy())?
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file() && scan::is_scannable(path))
+        .collect();
+    paths.sort();
+
+    Ok(paths)
+}
+
+// the message of the issue with its location
+fn describe(issue: &Issue) -> String {
+    if issue.location.is_empty() {
+        issue.message.clone()
+    } else {
+        format!("{} ({})", issue.message, issue.location)
+    }
+}
+
+fn findings_of(scan: &Scan) -> Vec<Finding> {
+    scan.issues
+        .iter()
+        .map(|issue| Finding {
+            rule_id: issue.rule_id,
+            message: describe(issue),
+            uri: sarif::artifact_uri(&scan.file_path),
+            byte_offset: issue.offset,
+        })
+        .collect()
+}
+
+pub(crate) fn scan(args: ScanArgs) -> anyhow::Result<()> {
+    let paths = paths_to_scan(&args)?;
+    if paths.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no scannable files found in {}",
+            args.file_path.display()
+        ));
+    }
+
+    let mut scans = vec![];
+    let mut findings = vec![];
+    let mut failed = 0;
+
+    for path in &paths {
+        match scan::scan(path, args.entropy, args.lsb) {
+            Ok(scan) => {
+                println!("{}: {}", path.display(), scan.verdict);
+                for issue in &scan.issues {
+                    println!(
+                        "  {:<10} {}",
+                        issue.safety.to_string().to_uppercase(),
+                        describe(issue)
+                    );
+                }
+                findings.extend(findings_of(&scan));
+                scans.push(scan);
+            }
+            Err(e) => {
+                println!("{}: {}", path.display(), e);
+                failed += 1;
+                findings.push(Finding {
+                    rule_id: "invalid-file",
+                    message: e.to_string(),
+                    uri: sarif::artifact_uri(path),
+                    byte_offset: None,
+                });
+            }
+        }
+    }
+
+    if let Some(json_path) = &args.to_json {
+        let json_str = serde_json::to_string_pretty(&scans)?;
+        std::fs::write(json_path, json_str)?;
+
+        tracing::info!("saved to {:?}", json_path);
+    }
+    if let Some(sarif_path) = &args.sarif {
+        sarif::save(sarif_path, RULES, &findings, None)?;
+    }
+
+    let dangerous = scans
+        .iter()
+        .filter(|scan| scan.verdict == Safety::Dangerous)
+        .count();
+    let suspicious = scans
+        .iter()
+        .filter(|scan| scan.verdict == Safety::Suspicious)
+        .count();
+    println!(
+        "\n{} files scanned: {} safe, {} suspicious, {} dangerous, {} invalid",
+        paths.len(),
+        scans.len() - dangerous - suspicious,
+        suspicious,
+        dangerous,
+        failed
+    );
+
+    if dangerous + failed > 0 {
+        Err(anyhow::anyhow!(
+            "{} of {} files are dangerous or can't be parsed",
+            dangerous + failed,
+            paths.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use crate::core::schema::json_schema;
+
+use super::SchemaArgs;
+
+pub(crate) fn schema(args: SchemaArgs) -> anyhow::Result<()> {
+    let schema = serde_json::to_string_pretty(&json_schema(&args.kind))?;
+    if let Some(output) = &args.output {
+        std::fs::write(output, schema)?;
+        tracing::info!("Schema written to {}", output.display());
+    } else {
+        println!("{}", schema);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::{path::PathBuf, sync::Arc};
+
+use serde::Serialize;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::core::{signing::Verdict, Inspection};
+
+use super::ServeArgs;
+
+mod protos {
+    tonic::include_proto!("tensor_man.v1");
+}
+
+use protos::{
+    tensor_man_server::{TensorMan, TensorManServer},
+    FileVerdict, HealthRequest, HealthResponse, InspectRequest, InspectResponse, SignerVerdict,
+    Tensor, VerifyRequest, VerifyResponse,
+};
+
+// name of a serialized enum, such as modified_file
+fn name_of(value: impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn inspect_response(inspection: Inspection) -> anyhow::Result<InspectResponse> {
+    Ok(InspectResponse {
+        inspection_json: serde_json::to_string(&inspection)?,
+        file_path: inspection.file_path.display().to_string(),
+        file_type: inspection.file_type.to_string(),
+        file_size: inspection.file_size,
+        header_size: inspection.header_size as u64,
+        version: inspection.version,
+        num_tensors: inspection.num_tensors as u64,
+        data_size: inspection.data_size as u64,
+        unique_dtypes: inspection.unique_dtypes,
+        metadata: inspection.metadata.into_iter().collect(),
+        tensors: inspection
+            .tensors
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tensor| Tensor {
+                id: tensor.id.unwrap_or_default(),
+                shape: tensor.shape.iter().map(|dim| *dim as u64).collect(),
+                dtype: tensor.dtype,
+                size: tensor.size as u64,
+                metadata: tensor.metadata.into_iter().collect(),
+            })
+            .collect(),
+    })
+}
+
+fn verify_response(verdict: Verdict) -> VerifyResponse {
+    VerifyResponse {
+        verified: verdict.exit_code == 0,
+        failure: verdict.failure.map(name_of),
+        exit_code: verdict.exit_code,
+        error: verdict.error,
+        model: verdict.model,
+        signature: verdict.signature,
+        files: verdict
+            .files
+            .into_iter()
+            .map(|file| FileVerdict {
+                path: file.path,
+                status: name_of(file.status),
+            })
+            .collect(),
+        signers: verdict
+            .signers
+            .into_iter()
+            .map(|signer| SignerVerdict {
+                fingerprint: signer.fingerprint,
+                algorithm: name_of(signer.algorithm),
+                trusted: signer.trusted,
+                signed_at: signer.signed_at,
+                expires_at: signer.expires_at,
+                timestamped_at: signer.timestamped_at,
+            })
+            .collect(),
+        policy_violations: verdict.policy_violations,
+        verified_at: verdict.verified_at,
+    }
+}
+
+// runs the inspection or the verification on the blocking threads of the runtime
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, Status> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+struct Service {
+    args: Arc<ServeArgs>,
+    root: Arc<PathBuf>,
+}
+
+#[tonic::async_trait]
+impl TensorMan for Service {
+    async fn health(
+        &self,
+        _request: Request<HealthRequest>,
+    ) -> Result<Response<HealthResponse>, Status> {
+        Ok(Response::new(HealthResponse {
+            status: "ok".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }))
+    }
+
+    async fn inspect(
+        &self,
+        request: Request<InspectRequest>,
+    ) -> Result<Response<InspectResponse>, Status> {
+        let request = request.into_inner();
+        let request = super::InspectRequest {
+            path: request.path,
+            format: request.format,
+            detail: request.detail,
+            filter: request.filter,
+        };
+        let (args, root) = (self.args.clone(), self.root.clone());
+        let inspection =
+            blocking(move || super::inspect(&args, &root, request).and_then(inspect_response))
+                .await?;
+        Ok(Response::new(inspection))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<VerifyRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let request = request.into_inner();
+        let request = super::VerifyRequest {
+            path: request.path,
+            format: request.format,
+            signature: request.signature,
+            ignore: request.ignore,
+        };
+        let (args, root) = (self.args.clone(), self.root.clone());
+        let verdict = blocking(move || super::verify(&args, &root, request)).await?;
+        Ok(Response::new(verify_response(verdict)))
+    }
+}
+
+/// Serves the gRPC service on the listener until it fails.
+pub(super) fn serve(
+    listener: std::net::TcpListener,
+    args: ServeArgs,
+    root: PathBuf,
+) -> anyhow::Result<()> {
+    listener.set_nonblocking(true)?;
+    // inspections and verifications run on at most as many blocking threads as REST workers
+    let workers = args.workers.max(1);
+    let service = Service {
+        args: Arc::new(args),
+        root: Arc::new(root),
+    };
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .max_blocking_threads(workers)
+        .build()?
+        .block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            Server::builder()
+                .add_service(TensorManServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await?;
+            Ok(())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{protos::tensor_man_client::TensorManClient, *};
+
+    #[test]
+    fn test_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let header = br#"{"weight":{"dtype":"F32","shape":[2,2],"data_offsets":[0,16]}}"#;
+        let mut content = (header.len() as u64).to_le_bytes().to_vec();
+        content.extend_from_slice(header);
+        content.extend_from_slice(&[0; 16]);
+        std::fs::write(root.join("model.safetensors"), content).unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let args = ServeArgs::new(root.clone());
+        std::thread::spawn(move || serve(listener, args, root));
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let mut client = TensorManClient::connect(address).await.unwrap();
+                let health = client.health(HealthRequest {}).await.unwrap().into_inner();
+                assert_eq!(health.status, "ok");
+
+                let inspection = client
+                    .inspect(InspectRequest {
+                        path: "model.safetensors".to_string(),
+                        detail: Some("full".to_string()),
+                        ..Default::default()
+                    })
+                    .await
+                    .unwrap()
+                    .into_inner();
+                assert_eq!(inspection.file_type, "SafeTensors");
+                assert_eq!(inspection.num_tensors, 1);
+                assert_eq!(inspection.tensors[0].shape, vec![2, 2]);
+
+                // requests can't leave the served folder
+                let status = client
+                    .inspect(InspectRequest {
+                        path: "../model.safetensors".to_string(),
+                        ..Default::default()
+                    })
+                    .await
+                    .unwrap_err();
+                assert_eq!(status.code(), tonic::Code::InvalidArgument);
+            });
+    }
+}
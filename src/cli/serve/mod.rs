@@ -0,0 +1,230 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::core::{hub::HubUri, signing::Verdict, storage::ObjectUri, FileType, Inspection};
+
+use super::{DetailLevel, InspectArgs, ServeArgs, VerifyArgs};
+
+#[cfg(feature = "grpc")]
+mod grpc;
+
+// requests reference the models, they are never uploaded
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct InspectRequest {
+    path: String,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    detail: Option<String>,
+    #[serde(default)]
+    filter: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyRequest {
+    path: String,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    ignore: Option<String>,
+}
+
+// status code and JSON body of a response
+type Reply = (u16, serde_json::Value);
+
+fn error(status: u16, message: impl ToString) -> Reply {
+    (status, json!({ "error": message.to_string() }))
+}
+
+fn body_of<T: DeserializeOwned>(request: &mut Request) -> anyhow::Result<T> {
+    let mut body = vec![];
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE + 1)
+        .read_to_end(&mut body)?;
+    if body.len() as u64 > MAX_BODY_SIZE {
+        anyhow::bail!("request larger than {} bytes", MAX_BODY_SIZE);
+    }
+    Ok(serde_json::from_slice(&body)?)
+}
+
+// parses a value the way the command line does, for instance safetensors or full
+fn value_of<T: ValueEnum>(value: Option<&str>) -> anyhow::Result<Option<T>> {
+    value
+        .map(|value| T::from_str(value, true).map_err(|e| anyhow::anyhow!(e)))
+        .transpose()
+}
+
+// the path of the model of a request, Hub and object store models as is if allowed and local
+// paths resolved in the root folder, which they can't leave
+fn resolve(args: &ServeArgs, root: &Path, path: &str) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(path);
+    if HubUri::is_hub_uri(&path) || ObjectUri::is_object_uri(&path) {
+        if !args.allow_remote {
+            anyhow::bail!(
+                "remote models are not served, start the server with --allow-remote to allow them"
+            );
+        }
+        return Ok(path);
+    }
+
+    let resolved = root
+        .join(&path)
+        .canonicalize()
+        .map_err(|_| anyhow::anyhow!("{} not found", path.display()))?;
+    if !resolved.starts_with(root) {
+        anyhow::bail!("{} is outside of the served folder", path.display());
+    }
+    Ok(resolved)
+}
+
+fn inspect(args: &ServeArgs, root: &Path, request: InspectRequest) -> anyhow::Result<Inspection> {
+    let mut inspect_args = InspectArgs::new(resolve(args, root, &request.path)?);
+    inspect_args.format = value_of::<FileType>(request.format.as_deref())?;
+    inspect_args.detail = value_of(request.detail.as_deref())?.unwrap_or(DetailLevel::Brief);
+    inspect_args.filter = request.filter;
+
+    super::inspect_file(&inspect_args, &inspect_args.file_paths[0], false)
+}
+
+// the verdict of the model, which tells why the verification failed if it did
+fn verify(args: &ServeArgs, root: &Path, request: VerifyRequest) -> anyhow::Result<Verdict> {
+    let mut verify_args =
+        VerifyArgs::new(resolve(args, root, &request.path)?, args.key_path.clone());
+    verify_args.policy = args.policy.clone();
+    verify_args.keyring = args.keyring.clone();
+    verify_args.format = value_of::<FileType>(request.format.as_deref())?;
+    verify_args.signature = request
+        .signature
+        .map(|signature| resolve(args, root, &signature))
+        .transpose()?;
+    verify_args.ignore = request.ignore;
+
+    Ok(super::verdict_of(verify_args).0)
+}
+
+fn route(args: &ServeArgs, root: &Path, request: &mut Request) -> Reply {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    match (request.method(), path.as_str()) {
+        (Method::Get, "/healthz") => (
+            200,
+            json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }),
+        ),
+        (Method::Post, "/inspect") => match body_of(request) {
+            Ok(body) => inspect(args, root, body)
+                .and_then(|inspection| Ok((200, serde_json::to_value(inspection)?)))
+                .unwrap_or_else(|e| error(422, e)),
+            Err(e) => error(400, e),
+        },
+        (Method::Post, "/verify") => match body_of(request) {
+            Ok(body) => verify(args, root, body)
+                .and_then(|verdict| {
+                    let status = if verdict.exit_code == 0 { 200 } else { 422 };
+                    Ok((status, serde_json::to_value(verdict)?))
+                })
+                .unwrap_or_else(|e| error(422, e)),
+            Err(e) => error(400, e),
+        },
+        (_, "/healthz" | "/inspect" | "/verify") => error(405, "method not allowed"),
+        _ => error(404, "not found"),
+    }
+}
+
+fn handle(args: &ServeArgs, root: &Path, mut request: Request) {
+    let (status, body) = route(args, root, &mut request);
+    tracing::info!("{} {} {}", request.method(), request.url(), status);
+
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    if let Err(e) = request.respond(response) {
+        tracing::warn!("can't respond: {}", e);
+    }
+}
+
+pub(crate) fn serve(args: ServeArgs) -> anyhow::Result<()> {
+    let root = args
+        .root
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("can't serve {}: {}", args.root.display(), e))?;
+    let server = Server::http(&args.listen)
+        .map_err(|e| anyhow::anyhow!("can't listen on {}: {}", args.listen, e))?;
+
+    tracing::info!("Serving {} on http://{} ...", root.display(), args.listen);
+
+    #[cfg(feature = "grpc")]
+    if let Some(address) = &args.grpc_listen {
+        let listener = std::net::TcpListener::bind(address)
+            .map_err(|e| anyhow::anyhow!("can't listen on {}: {}", address, e))?;
+        tracing::info!("Serving the gRPC service on {} ...", address);
+        let (args, root) = (args.clone(), root.clone());
+        std::thread::spawn(move || {
+            if let Err(e) = grpc::serve(listener, args, root) {
+                tracing::error!("gRPC service stopped: {}", e);
+            }
+        });
+    }
+
+    // requests are handled by a fixed number of workers, verifications taking as long as hashing
+    // the model, the others are queued
+    let (args, root, server) = (&args, root.as_path(), &server);
+    std::thread::scope(|scope| {
+        for _ in 0..args.workers.max(1) {
+            scope.spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(args, root, request);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("models")).unwrap();
+        std::fs::write(root.join("models/model.safetensors"), b"").unwrap();
+        let mut args = ServeArgs::new(root.clone());
+
+        assert_eq!(
+            resolve(&args, &root, "models/model.safetensors").unwrap(),
+            root.join("models/model.safetensors")
+        );
+        assert!(resolve(&args, &root, "models/missing.gguf").is_err());
+        // requests can't reach files outside of the root folder
+        assert!(resolve(&args, &root.join("models"), "../models/../../").is_err());
+        assert!(resolve(&args, &root.join("models"), root.to_str().unwrap()).is_err());
+
+        // nor remote models, unless allowed
+        assert!(resolve(&args, &root, "hf://org/repo/model.safetensors").is_err());
+        assert!(resolve(&args, &root, "s3://bucket/model.safetensors").is_err());
+        args.allow_remote = true;
+        assert_eq!(
+            resolve(&args, &root, "hf://org/repo/model.safetensors").unwrap(),
+            PathBuf::from("hf://org/repo/model.safetensors")
+        );
+    }
+}
@@ -1,17 +1,29 @@
 use std::{
     collections::HashSet,
+    ops::Range,
     path::{Path, PathBuf},
 };
 
 use anyhow::anyhow;
 use glob::glob;
 
-use crate::core::{handlers::Scope, signing::Manifest, FileType};
+use crate::core::{
+    archive::ArchiveType,
+    data::TensorData,
+    handlers::{EmbeddedSignature, Scope},
+    repository::{self, ModelVersion},
+    sarif,
+    signing::{
+        fingerprint, parse_duration, Envelope, Failure, FileStatus, FileVerdict, Keyring, Manifest,
+        Policy, RevocationList, SignerVerdict, SigningKey, Verdict, VerifyingKey, RULES,
+    },
+    FileType,
+};
 
-use super::{CreateKeyArgs, SignArgs, VerifyArgs};
+use super::{CreateKeyArgs, RevokeArgs, SignArgs, VerifyArgs};
 
 pub(crate) fn create_key(args: CreateKeyArgs) -> anyhow::Result<()> {
-    crate::core::signing::create_key(&args.private_key, &args.public_key)
+    crate::core::signing::create_key(&args.private_key, &args.public_key, args.algorithm)
 }
 
 fn get_paths_for(format: Option<FileType>, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
@@ -36,7 +48,7 @@ fn get_paths_for(format: Option<FileType>, file_path: &Path) -> anyhow::Result<V
     }
 }
 
-fn get_paths_of_interest(
+pub(super) fn get_paths_of_interest(
     format: Option<FileType>,
     file_path: &Path,
     ignore: Option<String>,
@@ -55,7 +67,7 @@ fn get_paths_of_interest(
                         unique.extend(get_paths_for(format.clone(), &path)?);
                     }
                 }
-                Err(e) => println!("{:?}", e),
+                Err(e) => tracing::warn!("{:?}", e),
             }
         }
 
@@ -78,75 +90,1058 @@ fn get_paths_of_interest(
     Ok(paths)
 }
 
-fn signature_path(file_path: &Path, signature_path: Option<PathBuf>) -> PathBuf {
-    if let Some(path) = signature_path {
-        if path.exists() {
-            path.canonicalize().unwrap()
+// returns the tensors of the file if the format supports reading them
+// the files of the version of the repository model to sign or verify, like the files of a folder
+fn paths_of_version(
+    format: Option<FileType>,
+    version: &ModelVersion,
+    ignore: Option<String>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for file in version.files()? {
+        paths.extend(get_paths_for(format.clone(), &file)?);
+    }
+    if let Some(ignore_pattern) = ignore {
+        paths.retain(|path| !path.to_string_lossy().contains(&ignore_pattern));
+    }
+    paths.sort();
+    paths.dedup();
+
+    if paths.is_empty() {
+        return Err(anyhow!("no compatible paths found"));
+    }
+
+    Ok(paths)
+}
+
+fn tensor_data_for(format: Option<FileType>, file_path: &Path) -> Option<Vec<TensorData>> {
+    crate::core::handlers::handler_for(format, file_path, Scope::Inspection)
+        .and_then(|handler| handler.tensor_data(file_path, None))
+        .ok()
+        .filter(|tensors| !tensors.is_empty())
+}
+
+// returns the signature embedded in the file, if the format supports it and the file has one
+pub(super) fn embedded_signature_for(
+    format: Option<FileType>,
+    file_path: &Path,
+) -> Option<EmbeddedSignature> {
+    crate::core::handlers::handler_for(format, file_path, Scope::Signing)
+        .and_then(|handler| handler.embedded_signature(file_path))
+        .ok()
+        .filter(|embedded| embedded.manifest.is_some())
+}
+
+// prints which tensors and archive members have been modified, if the signed manifest can be
+// trusted
+fn report_tampered_tensors(
+    args: &VerifyArgs,
+    paths: &[PathBuf],
+    manifest: &Manifest,
+    signature: &Manifest,
+) -> anyhow::Result<()> {
+    // only trust the tensor checksums if the manifest itself has not been tampered with
+    if !signature.tensor_checksums.is_empty() && manifest.is_authentic(signature) {
+        for path in paths {
+            let Some(tensors) = tensor_data_for(args.format.clone(), path) else {
+                continue;
+            };
+            for id in signature.tampered_tensors(path, &tensors)? {
+                super::output(
+                    args.print,
+                    format_args!("  {}: tensor {} has been modified", path.display(), id),
+                );
+            }
+        }
+    }
+    if !signature.member_checksums.is_empty() && manifest.is_authentic(signature) {
+        for path in paths {
+            for name in signature.tampered_members(path)? {
+                super::output(
+                    args.print,
+                    format_args!("  {}: member {} has been modified", path.display(), name),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// records the status of each file in the verdict
+fn set_files(verdict: &mut Verdict, manifest: &Manifest, signature: &Manifest) {
+    verdict.files = manifest
+        .file_report(signature)
+        .into_iter()
+        .map(|(path, status)| FileVerdict { path, status })
+        .collect();
+}
+
+// records the signatures of the manifest in the verdict, and whether they can be trusted
+fn set_signers(
+    verdict: &mut Verdict,
+    args: &VerifyArgs,
+    manifest: &Manifest,
+    signature: &Manifest,
+) -> anyhow::Result<()> {
+    let tsa_certificate = args.tsa_cert.as_ref().map(std::fs::read).transpose()?;
+    let trusted = manifest.trusted_signers(signature);
+
+    verdict.signers = signature
+        .signatures
+        .iter()
+        .map(|signer| SignerVerdict {
+            fingerprint: signer.public_key.clone(),
+            algorithm: signer.algorithm,
+            trusted: trusted
+                .iter()
+                .any(|other| other.public_key == signer.public_key),
+            signed_at: signer.signed_at.clone(),
+            expires_at: signer.expires_at.clone(),
+            timestamped_at: signer
+                .timestamp(tsa_certificate.as_deref())
+                .ok()
+                .flatten()
+                .map(|time| time.to_rfc3339()),
+            authenticated_at: signature
+                .authenticated_date(signer, tsa_certificate.as_deref())
+                .ok()
+                .flatten()
+                .map(|time| time.to_rfc3339()),
+        })
+        .collect();
+
+    Ok(())
+}
+
+// prints the status of each file and a summary, fails if any file doesn't match the signature
+fn print_report(print: bool, manifest: &Manifest, signature: &Manifest) -> anyhow::Result<()> {
+    let report = manifest.file_report(signature);
+    for (path, status) in &report {
+        super::output(print, format_args!("  {}: {}", path, status));
+    }
+
+    let count = |status: FileStatus| report.iter().filter(|(_, s)| *s == status).count();
+    let failed = report.len() - count(FileStatus::Ok);
+    super::output(
+        print,
+        format_args!(
+            "\n{} OK, {} modified, {} missing, {} extra",
+            count(FileStatus::Ok),
+            count(FileStatus::Modified),
+            count(FileStatus::Missing),
+            count(FileStatus::Extra)
+        ),
+    );
+
+    if failed > 0 {
+        // tells tampered files apart from a tampered manifest
+        if manifest.is_authentic(signature) {
+            super::output(
+                print,
+                format_args!(
+                    "the signature manifest is authentic, the files changed since they were signed"
+                ),
+            );
+        }
+        let failure = if count(FileStatus::Modified) > 0 {
+            Failure::ModifiedFile
         } else {
-            path
+            Failure::MissingFile
+        };
+        return Err(failure.error(format!(
+            "{} of {} files failed verification",
+            failed,
+            report.len()
+        )));
+    }
+
+    Ok(())
+}
+
+// loads the public keys to verify against, and the keys that have been revoked, the signature
+// manifest is not set for DSSE envelopes
+fn verifier_for(
+    args: &VerifyArgs,
+    base_path: &Path,
+    signature: Option<&Manifest>,
+) -> anyhow::Result<Manifest> {
+    let mut manifest = trusted_keys_for(args, base_path, signature)?;
+
+    if let Some(revocations_path) = &args.revocations {
+        tracing::info!(
+            "Loading revocation list from {}",
+            revocations_path.display()
+        );
+
+        let issuer = args
+            .revocations_key
+            .as_ref()
+            .map(|path| crate::core::signing::load_public_key(path))
+            .transpose()?;
+        manifest.set_revocations(RevocationList::from_path(revocations_path)?, issuer)?;
+
+        for (signer, revocation) in signature
+            .map(|signature| manifest.revoked_signers(signature))
+            .unwrap_or_default()
+        {
+            tracing::warn!(
+                "Refusing signature by {}, revoked at {}: {}",
+                &signer.public_key[..16],
+                revocation.revoked_at,
+                revocation.reason
+            );
         }
+    }
+
+    Ok(manifest)
+}
+
+fn trusted_keys_for(
+    args: &VerifyArgs,
+    base_path: &Path,
+    signature: Option<&Manifest>,
+) -> anyhow::Result<Manifest> {
+    if args.trust_first_use {
+        let Some(signature) = signature else {
+            return Err(anyhow!(
+                "DSSE envelopes can't be verified with --trust-first-use"
+            ));
+        };
+        return first_use_verifier(args, base_path, signature);
+    }
+
+    if let Some(policy_path) = &args.policy {
+        tracing::info!("Loading policy from {}", policy_path.display());
+
+        let policy = Policy::from_path(policy_path)?;
+        return Manifest::from_policy(base_path, &policy);
+    }
+
+    let mut manifest = if args.key_path.is_empty() {
+        let keyring = Keyring::open_or_default(args.keyring.as_deref())?;
+        tracing::info!("Loading trusted keys from {}", keyring.path().display());
+
+        let public_keys = keyring.trusted_keys()?;
+        if public_keys.is_empty() {
+            anyhow::bail!(
+                "no public key given and no trusted keys in {}",
+                keyring.path().display()
+            );
+        }
+        Manifest::from_public_keys(base_path, public_keys)?
+    } else {
+        Manifest::from_public_key_paths(base_path, &args.key_path)?
+    };
+    manifest.set_required_signers(args.require_signers)?;
+    Ok(manifest)
+}
+
+// identifies the model in the keys pinned on first use
+fn pinned_model_id(args: &VerifyArgs) -> anyhow::Result<String> {
+    match &args.model_id {
+        Some(model_id) => Ok(model_id.clone()),
+        None => Ok(args.file_path.canonicalize()?.to_string_lossy().to_string()),
+    }
+}
+
+// verifies with the keys pinned for this model, or with the ones included in the signature
+// if the model is verified for the first time
+fn first_use_verifier(
+    args: &VerifyArgs,
+    base_path: &Path,
+    signature: &Manifest,
+) -> anyhow::Result<Manifest> {
+    let keyring = Keyring::open_or_default(args.keyring.as_deref())?;
+    let model = pinned_model_id(args)?;
+
+    let public_keys = if let Some(public_keys) = keyring.pinned_keys(&model)? {
+        tracing::info!("Using the keys pinned in {}", keyring.path().display());
+        public_keys
+    } else {
+        tracing::info!("First use, trusting the public keys included in the signature");
+
+        let mut public_keys = signature
+            .signatures
+            .iter()
+            .filter_map(|signer| signer.included_public_key().transpose())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if public_keys.is_empty() {
+            return Err(anyhow!(
+                "the signature doesn't include any public key, sign with --include-public-key"
+            ));
+        }
+        public_keys.sort();
+        public_keys.dedup();
+        public_keys
+    };
+
+    let mut manifest = Manifest::from_public_keys(base_path, public_keys)?;
+    manifest.set_required_signers(args.require_signers)?;
+    Ok(manifest)
+}
+
+// pins the keys of the valid signatures if the model has been verified for the first time
+fn pin_on_first_use(
+    args: &VerifyArgs,
+    manifest: &Manifest,
+    signature: &Manifest,
+) -> anyhow::Result<()> {
+    if !args.trust_first_use {
+        return Ok(());
+    }
+
+    let mut keyring = Keyring::open_or_default(args.keyring.as_deref())?;
+    let model = pinned_model_id(args)?;
+    if keyring.pinned_keys(&model)?.is_some() {
+        return Ok(());
+    }
+
+    let mut public_keys = vec![];
+    for signer in manifest.trusted_signers(signature) {
+        if let Some(public_key) = signer.included_public_key()? {
+            tracing::info!("Pinning key {} for {}", &signer.public_key[..16], model);
+            public_keys.push(public_key);
+        }
+    }
+    keyring.pin(&model, &public_keys)
+}
+
+// reports the valid signatures made with our keys and verifies their trusted timestamps, if any
+fn check_signers(
+    args: &VerifyArgs,
+    manifest: &Manifest,
+    signature: &Manifest,
+) -> anyhow::Result<()> {
+    let tsa_certificate = args.tsa_cert.as_ref().map(std::fs::read).transpose()?;
+    let signed_before = args
+        .signed_before
+        .as_ref()
+        .map(|date| chrono::DateTime::parse_from_rfc3339(date))
+        .transpose()?;
+    let max_age = args.max_age.as_deref().map(parse_duration).transpose()?;
+
+    for signer in manifest.trusted_signers(signature) {
+        super::output(
+            args.print,
+            format_args!(
+                "Signed by {} ({:?}) at {}",
+                &signer.public_key[..16],
+                signer.algorithm,
+                signer.signed_at
+            ),
+        );
+        if let Some(expires_at) = signer.expiration()? {
+            super::output(
+                args.print,
+                format_args!("  expires at {}", expires_at.to_rfc3339()),
+            );
+        }
+
+        if let Some(max_age) = max_age {
+            // the unsigned signing date could have been changed
+            let Some(signed_at) =
+                signature.authenticated_date(signer, tsa_certificate.as_deref())?
+            else {
+                return Err(Failure::Expired.error(format!(
+                    "the signing date of {} can't be trusted, sign with --expires-in or --tsa-url",
+                    &signer.public_key[..16]
+                )));
+            };
+            if chrono::Utc::now() - signed_at > max_age {
+                return Err(Failure::Expired.error(format!(
+                    "signature by {} made at {} is older than {}",
+                    &signer.public_key[..16],
+                    signed_at.to_rfc3339(),
+                    args.max_age.as_deref().unwrap_or_default()
+                )));
+            }
+        }
+
+        let timestamp = signer.timestamp(tsa_certificate.as_deref())?;
+        if let Some(time) = timestamp {
+            super::output(
+                args.print,
+                format_args!(
+                    "  timestamped at {}{}",
+                    time.to_rfc3339(),
+                    if tsa_certificate.is_none() {
+                        " (TSA certificate not pinned)"
+                    } else {
+                        ""
+                    }
+                ),
+            );
+        }
+
+        if let Some(signed_before) = signed_before {
+            match timestamp {
+                Some(time) if time < signed_before => {}
+                Some(time) => {
+                    return Err(Failure::Expired.error(format!(
+                        "signature timestamped at {}, not before {}",
+                        time.to_rfc3339(),
+                        signed_before.to_rfc3339()
+                    )))
+                }
+                None => {
+                    return Err(Failure::Expired.error("the signature has no trusted timestamp"))
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// evaluates the verification policy, if any, once the signatures have been verified
+fn check_policy(
+    args: &VerifyArgs,
+    base_path: &Path,
+    hash_algorithm: &str,
+    verdict: &mut Verdict,
+) -> anyhow::Result<()> {
+    let Some(policy_path) = &args.policy else {
+        return Ok(());
+    };
+
+    let policy = Policy::from_path(policy_path)?;
+    verdict.policy_violations = policy.evaluate(verdict, base_path, hash_algorithm)?;
+    for violation in &verdict.policy_violations {
+        tracing::warn!("Policy violation: {}", violation);
+    }
+    if !verdict.policy_violations.is_empty() {
+        return Err(Failure::PolicyViolation.error(format!(
+            "{} policy violations",
+            verdict.policy_violations.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_range(range: &str) -> anyhow::Result<Range<u64>> {
+    let (start, end) = range
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid range '{}', expected START:END", range))?;
+    Ok(start.trim().parse()?..end.trim().parse()?)
+}
+
+// sets the options of the signatures we create
+fn set_signing_options(args: &SignArgs, manifest: &mut Manifest) -> anyhow::Result<()> {
+    if let Some(expires_in) = &args.expires_in {
+        manifest.set_expiration(chrono::Utc::now() + parse_duration(expires_in)?);
+    }
+    manifest.set_include_public_key(args.include_public_key);
+    Ok(())
+}
+
+pub(crate) fn signature_path(file_path: &Path, signature_path: Option<PathBuf>) -> PathBuf {
+    let path = if let Some(path) = signature_path {
+        path
     } else if file_path.is_file() {
-        file_path
-            .with_extension("signature")
-            .canonicalize()
-            .unwrap()
+        file_path.with_extension("signature")
     } else {
         file_path.join("tensor-man.signature")
+    };
+    // canonical if it exists, like the paths of the files it is compared with
+    path.canonicalize().unwrap_or(path)
+}
+
+pub(super) fn envelope_path(file_path: &Path, envelope_path: Option<PathBuf>) -> PathBuf {
+    let path = if let Some(path) = envelope_path {
+        path
+    } else if file_path.is_file() {
+        file_path.with_extension("intoto.json")
+    } else {
+        file_path.join("tensor-man.intoto.json")
+    };
+    // canonical if it exists, like the paths of the files it is compared with
+    path.canonicalize().unwrap_or(path)
+}
+
+fn sign_envelope(
+    args: &SignArgs,
+    base_path: &Path,
+    signing_key: SigningKey,
+    mut paths: Vec<PathBuf>,
+) -> anyhow::Result<PathBuf> {
+    let envelope_path = envelope_path(&args.file_path, args.output.clone());
+    // the envelope itself is not signed
+    if let Ok(canonical) = envelope_path.canonicalize() {
+        paths.retain(|path| path != &canonical);
+    }
+    paths.sort();
+
+    let manifest = Manifest::from_signing_key(base_path, signing_key)?;
+    let envelope = if args.append {
+        tracing::info!("Appending to envelope: {}", envelope_path.display());
+
+        let mut envelope = Envelope::from_path(&envelope_path)?;
+        manifest.countersign_envelope(&paths, &mut envelope)?;
+        envelope
+    } else {
+        manifest.sign_envelope(&paths)?
+    };
+    super::output(
+        args.print,
+        format_args!("Signature: {}", envelope.signatures.last().unwrap().sig),
+    );
+
+    std::fs::write(&envelope_path, serde_json::to_string(&envelope)?)?;
+
+    tracing::info!("Envelope written to {}", envelope_path.display());
+
+    Ok(envelope_path)
+}
+
+// creates the manifest to sign the files with
+fn new_manifest(
+    args: &SignArgs,
+    base_path: &Path,
+    signing_key: SigningKey,
+    paths: &[PathBuf],
+) -> anyhow::Result<Manifest> {
+    let mut manifest = Manifest::from_signing_key(base_path, signing_key)?;
+    set_signing_options(args, &mut manifest)?;
+    if let Some(chunk_size) = args.chunk_size {
+        manifest.set_chunk_size(chunk_size)?;
     }
+
+    // record per tensor checksums for the formats supporting it
+    for path in paths {
+        if let Some(tensors) = tensor_data_for(args.format.clone(), path) {
+            manifest.add_tensor_checksums(path, &tensors)?;
+        }
+        // and per member checksums for archives
+        if ArchiveType::of(path).is_some() {
+            manifest.add_member_checksums(path)?;
+        }
+    }
+
+    Ok(manifest)
 }
 
-pub(crate) fn sign(args: SignArgs) -> anyhow::Result<()> {
-    // load the private key for signing
+fn sign_embedded(
+    args: &SignArgs,
+    base_path: &Path,
+    signing_key: SigningKey,
+    paths: &[PathBuf],
+) -> anyhow::Result<PathBuf> {
+    let [path] = paths else {
+        return Err(anyhow!("signatures can only be embedded in a single file"));
+    };
+    let handler = crate::core::handlers::handler_for(args.format.clone(), path, Scope::Signing)?;
+    let embedded = handler.embedded_signature(path)?;
+
+    let mut manifest;
+    let signature = if args.append {
+        let Some(signed) = embedded.manifest else {
+            return Err(anyhow!(
+                "{} has no embedded signature to append to",
+                path.display()
+            ));
+        };
+        manifest = Manifest::from_signature_str(base_path, &signed)?;
+        manifest.set_signing_key(signing_key);
+        set_signing_options(args, &mut manifest)?;
+        manifest.countersign_embedded(path, embedded.content)?
+    } else {
+        manifest = new_manifest(args, base_path, signing_key, paths)?;
+        manifest.sign_embedded(path, embedded.content)?
+    };
+    super::output(args.print, format_args!("Signature: {}", signature));
+
+    if let Some(tsa_url) = &args.tsa_url {
+        tracing::info!("Requesting timestamp from {} ...", tsa_url);
+        manifest.add_timestamp(tsa_url)?;
+    }
+
+    handler.embed_signature(path, &serde_json::to_string(&manifest)?)?;
+
+    tracing::info!("Signature embedded in {}", path.display());
+
+    Ok(path.clone())
+}
+
+pub(crate) fn revoke(args: RevokeArgs) -> anyhow::Result<()> {
+    // load the private key of the issuer of the list
     let signing_key = crate::core::signing::load_key(&args.key_path)?;
+
+    let mut revocations = if args.output.exists() {
+        tracing::info!("Updating revocation list: {}", args.output.display());
+
+        let revocations = RevocationList::from_path(&args.output)?;
+        // only the issuer can update the list
+        revocations.verify(&[VerifyingKey::new(signing_key.public_key().to_vec())?])?;
+        revocations
+    } else {
+        RevocationList::default()
+    };
+
+    // the key to revoke can be given as a public key file or as its fingerprint
+    let fingerprint = if args.key.exists() {
+        fingerprint(&crate::core::signing::load_public_key(&args.key)?)
+    } else {
+        let fingerprint = args.key.to_string_lossy().to_lowercase();
+        if fingerprint.len() != 128 || hex::decode(&fingerprint).is_err() {
+            return Err(anyhow!(
+                "{} is neither a public key file nor a key fingerprint",
+                args.key.display()
+            ));
+        }
+        fingerprint
+    };
+
+    revocations.revoke(
+        fingerprint.clone(),
+        args.revoked_at
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        args.reason,
+    )?;
+    revocations.sign(&signing_key)?;
+    revocations.to_file(&args.output)?;
+
+    println!(
+        "Key {} revoked, revocation list written to {}",
+        &fingerprint[..16],
+        args.output.display()
+    );
+
+    Ok(())
+}
+
+pub(crate) fn sign(mut args: SignArgs) -> anyhow::Result<()> {
+    let hub_model = super::hub::download(&args.file_path, args.ignore.as_deref())?;
+    let stored_model = super::storage::download(&args.file_path, args.ignore.as_deref())?;
+    match (&hub_model, &stored_model) {
+        (Some(model), _) if args.embed => {
+            return Err(anyhow!(
+                "signatures can't be embedded in the files of {}",
+                model.uri.repo
+            ))
+        }
+        (_, Some(model)) if args.embed => {
+            return Err(anyhow!(
+                "signatures can't be embedded in the objects of {}",
+                model.uri
+            ))
+        }
+        (Some(model), _) => args.file_path = model.path.clone(),
+        // the objects are downloaded to a temporary folder
+        (_, Some(model)) if !args.upload && args.output.is_none() => {
+            return Err(anyhow!(
+                "use --upload to store the signature next to the objects of {} or -O to save it locally",
+                model.uri
+            ))
+        }
+        (_, Some(model)) => args.file_path = model.path.clone(),
+        (None, None) if args.upload => {
+            return Err(anyhow!(
+                "--upload requires a hf://org/repo model of the Hugging Face Hub or a s3://, gs:// or az:// URI"
+            ))
+        }
+        (None, None) => {}
+    }
+
+    if args.repository {
+        return sign_repository(&args);
+    }
+
+    let signature_path = sign_files(&args)?;
+
+    match (hub_model, stored_model) {
+        (Some(model), _) if args.upload => super::hub::upload(&model, &signature_path),
+        (_, Some(model)) if args.upload => super::storage::upload(&model, &signature_path),
+        _ => Ok(()),
+    }
+}
+
+// signs the files, returns the path of the signature
+fn sign_files(args: &SignArgs) -> anyhow::Result<PathBuf> {
     // get the paths to sign
-    let mut paths_to_sign = get_paths_of_interest(args.format, &args.file_path, args.ignore)?;
+    let paths_to_sign =
+        get_paths_of_interest(args.format.clone(), &args.file_path, args.ignore.clone())?;
     let base_path = if args.file_path.is_file() {
         args.file_path.parent().unwrap().to_path_buf()
     } else {
         args.file_path.to_path_buf()
     };
-    // create the manifest
-    let mut manifest = Manifest::from_signing_key(&base_path, signing_key)?;
 
-    // sign
-    let signature = manifest.sign(&mut paths_to_sign)?;
-    println!("Signature: {}", signature);
+    if args.embed || args.dsse {
+        // load the private key for signing
+        let signing_key = crate::core::signing::load_key(&args.key_path)?;
+        return if args.embed {
+            sign_embedded(args, &base_path, signing_key, &paths_to_sign)
+        } else {
+            sign_envelope(args, &base_path, signing_key, paths_to_sign)
+        };
+    }
 
-    // write manifest to file
-    let signature_path = signature_path(&args.file_path, args.output);
+    let signature_path = signature_path(&args.file_path, args.output.clone());
+    sign_paths(args, &base_path, paths_to_sign, signature_path)
+}
+
+// signs each version of each model of the repository with its own manifest
+fn sign_repository(args: &SignArgs) -> anyhow::Result<()> {
+    let (repository_type, versions) = repository::model_versions(&args.file_path)?;
+    tracing::info!(
+        "Signing {} model versions of {:?} repository",
+        versions.len(),
+        repository_type
+    );
+
+    for version in versions {
+        super::output(
+            args.print,
+            format_args!("\n{} {}:", version.model, version.version),
+        );
+        let paths_to_sign = paths_of_version(args.format.clone(), &version, args.ignore.clone())?;
+        sign_paths(
+            args,
+            &version.base_path,
+            paths_to_sign,
+            signature_path(&version.path, None),
+        )?;
+    }
+
+    Ok(())
+}
 
+// writes the signature manifest of the files, returns its path
+fn sign_paths(
+    args: &SignArgs,
+    base_path: &Path,
+    mut paths_to_sign: Vec<PathBuf>,
+    signature_path: PathBuf,
+) -> anyhow::Result<PathBuf> {
+    // load the private key for signing
+    let signing_key = crate::core::signing::load_key(&args.key_path)?;
+    // a previous signature in the model folder is not signed
+    paths_to_sign.retain(|p| p != &signature_path);
+
+    let mut manifest = if args.append {
+        // add our signature to the existing manifest
+        tracing::info!("Appending to signature: {}", signature_path.display());
+
+        let mut manifest = Manifest::from_signature_path(base_path, &signature_path)?;
+        manifest.set_signing_key(signing_key);
+        set_signing_options(args, &mut manifest)?;
+        manifest.countersign(&mut paths_to_sign)?;
+        manifest
+    } else {
+        let mut manifest = new_manifest(args, base_path, signing_key, &paths_to_sign)?;
+        manifest.sign(&mut paths_to_sign)?;
+        manifest
+    };
+    super::output(
+        args.print,
+        format_args!(
+            "Signature: {}",
+            manifest.signatures.last().unwrap().signature
+        ),
+    );
+
+    if let Some(tsa_url) = &args.tsa_url {
+        tracing::info!("Requesting timestamp from {} ...", tsa_url);
+        manifest.add_timestamp(tsa_url)?;
+    }
+
+    // write manifest to file
     std::fs::write(&signature_path, serde_json::to_string(&manifest)?)?;
 
-    println!("Manifest written to {}", signature_path.display());
+    tracing::info!("Manifest written to {}", signature_path.display());
 
-    Ok(())
+    Ok(signature_path)
+}
+
+/// Verifies the model, returning the verdict whatever the result.
+pub(crate) fn verdict_of(args: VerifyArgs) -> (Verdict, anyhow::Result<()>) {
+    let mut verdict = Verdict {
+        model: args.file_path.display().to_string(),
+        ..Default::default()
+    };
+
+    let result = verify_model(args, &mut verdict);
+    verdict.set_result(&result);
+    (verdict, result)
 }
 
 pub(crate) fn verify(args: VerifyArgs) -> anyhow::Result<()> {
+    let json_path = args.json.clone();
+    let sarif_path = args.sarif.clone();
+
+    let (verdict, result) = verdict_of(args);
+    if let Some(json_path) = json_path {
+        std::fs::write(&json_path, serde_json::to_string_pretty(&verdict)?)?;
+        tracing::info!("Verdict written to {}", json_path.display());
+    }
+    if let Some(sarif_path) = sarif_path {
+        sarif::save(
+            &sarif_path,
+            RULES,
+            &verdict.findings(),
+            verdict.execution_error(),
+        )?;
+    }
+
+    result
+}
+
+fn verify_model(mut args: VerifyArgs, verdict: &mut Verdict) -> anyhow::Result<()> {
+    if let Some(model) = super::hub::download(&args.file_path, args.ignore.as_deref())? {
+        args.model_id = Some(model.uri.model_id());
+        args.file_path = model.path;
+    }
+    // the temporary folder is removed when dropped
+    let stored_model = super::storage::download(&args.file_path, args.ignore.as_deref())?;
+    if let Some(model) = &stored_model {
+        args.model_id = Some(model.model_id());
+        args.file_path = model.path.clone();
+    }
+
     let base_path = if args.file_path.is_file() {
         args.file_path.parent().unwrap().to_path_buf()
     } else {
         args.file_path.to_path_buf()
     };
 
-    // load signature file to verify
-    let signature_path = signature_path(&args.file_path, args.signature);
+    if let Some(checksums_path) = &args.checksums {
+        return super::hash::verify_checksums(&args, checksums_path, verdict);
+    }
+
+    // files signed with --embed carry their own signature
+    if args.signature.is_none() && args.file_path.is_file() {
+        if let Some(embedded) = embedded_signature_for(args.format.clone(), &args.file_path) {
+            return verify_embedded(args, &base_path, embedded, verdict);
+        }
+    }
 
-    println!("Verifying signature: {}", signature_path.display());
+    if args.repository {
+        return verify_repository(&args, verdict);
+    }
 
-    let signature = Manifest::from_signature_path(&base_path, &signature_path)?;
+    // load signature file to verify
+    let signature_path = signature_path(&args.file_path, args.signature.clone());
+    let data = read_signature(&signature_path, verdict)?;
+    if Envelope::is_envelope(&data) {
+        return verify_envelope(&args, &base_path, &signature_path, &data, verdict);
+    }
+    let signature = Manifest::from_signature_str(&base_path, &data)?;
 
     // load the public key to verify against
-    let mut manifest = Manifest::from_public_key_path(&base_path, &args.key_path)?;
+    let manifest = verifier_for(&args, &base_path, Some(&signature))?;
+
+    if let Some(range) = &args.range {
+        if !args.file_path.is_file() {
+            return Err(anyhow!(
+                "a byte range can only be verified for a single file"
+            ));
+        }
+        let range = parse_range(range)?;
+        let result = manifest.verify_range(&args.file_path, range.clone(), &signature);
+        set_signers(verdict, &args, &manifest, &signature)?;
+        result?;
+        check_signers(&args, &manifest, &signature)?;
+        check_policy(&args, &base_path, signature.hash_algorithm(), verdict)?;
+
+        super::output(
+            args.print,
+            format_args!("Bytes {}..{} verified", range.start, range.end),
+        );
+
+        return Ok(());
+    }
     // get the paths to verify
-    let mut paths_to_verify = get_paths_of_interest(args.format, &args.file_path, args.ignore)?;
+    let paths_to_verify =
+        get_paths_of_interest(args.format.clone(), &args.file_path, args.ignore.clone())?;
+
+    verify_paths(
+        &args,
+        &base_path,
+        &signature_path,
+        manifest,
+        signature,
+        paths_to_verify,
+        verdict,
+    )
+}
+
+// loads the signature to verify, recording it in the verdict
+fn read_signature(signature_path: &Path, verdict: &mut Verdict) -> anyhow::Result<String> {
+    tracing::info!("Verifying signature: {}", signature_path.display());
+
+    if !signature_path.exists() {
+        return Err(Failure::MissingSignature
+            .error(format!("signature {} not found", signature_path.display())));
+    }
+    verdict.signature = Some(signature_path.display().to_string());
+    Ok(std::fs::read_to_string(signature_path)?)
+}
+
+// verifies each version of each model of the repository against its own manifest, stopping at
+// the first one that fails
+fn verify_repository(args: &VerifyArgs, verdict: &mut Verdict) -> anyhow::Result<()> {
+    let (_, versions) = repository::model_versions(&args.file_path)?;
+    let total = versions.len();
+    for version in versions {
+        super::output(
+            args.print,
+            format_args!("\n{} {}:", version.model, version.version),
+        );
+        let signature_path = signature_path(&version.path, None);
+        let data = read_signature(&signature_path, verdict)?;
+        let signature = Manifest::from_signature_str(&version.base_path, &data)?;
+        let manifest = verifier_for(args, &version.base_path, Some(&signature))?;
+        let paths_to_verify = paths_of_version(args.format.clone(), &version, args.ignore.clone())?;
+        verify_paths(
+            args,
+            &version.base_path,
+            &signature_path,
+            manifest,
+            signature,
+            paths_to_verify,
+            verdict,
+        )?;
+    }
+
+    super::output(
+        args.print,
+        format_args!("\n{} model versions verified", total),
+    );
+
+    Ok(())
+}
+
+// computes the checksums of the files and verifies them against the signature
+fn verify_paths(
+    args: &VerifyArgs,
+    base_path: &Path,
+    signature_path: &Path,
+    mut manifest: Manifest,
+    signature: Manifest,
+    mut paths_to_verify: Vec<PathBuf>,
+    verdict: &mut Verdict,
+) -> anyhow::Result<()> {
     // remove the signature file from the list
-    paths_to_verify.retain(|p| p != &signature_path);
+    paths_to_verify.retain(|p| p != signature_path);
 
     // this will compute the checksums and verify the signature
-    manifest.verify(&mut paths_to_verify, &signature)?;
+    let result = manifest.verify(&mut paths_to_verify, &signature);
+    set_files(verdict, &manifest, &signature);
+    set_signers(verdict, args, &manifest, &signature)?;
+    let report = if args.report {
+        print_report(args.print, &manifest, &signature)
+    } else {
+        Ok(())
+    };
+    if let Err(e) = result {
+        report_tampered_tensors(args, &paths_to_verify, &manifest, &signature)?;
+        report?;
+        return Err(e);
+    }
+    check_signers(args, &manifest, &signature)?;
+    check_policy(args, base_path, signature.hash_algorithm(), verdict)?;
+    pin_on_first_use(args, &manifest, &signature)?;
 
-    println!("Signature verified");
+    super::output(args.print, format_args!("Signature verified"));
+
+    Ok(())
+}
+
+fn verify_envelope(
+    args: &VerifyArgs,
+    base_path: &Path,
+    envelope_path: &Path,
+    data: &str,
+    verdict: &mut Verdict,
+) -> anyhow::Result<()> {
+    if args.range.is_some() || args.max_age.is_some() || args.signed_before.is_some() {
+        return Err(anyhow!(
+            "byte ranges and signature dates can't be verified with a DSSE envelope"
+        ));
+    }
+    if args.report {
+        return Err(anyhow!("a report can't be generated for a DSSE envelope"));
+    }
+
+    let envelope: Envelope = serde_json::from_str(data)?;
+    let manifest = verifier_for(args, base_path, None)?;
+
+    let mut paths_to_verify =
+        get_paths_of_interest(args.format.clone(), &args.file_path, args.ignore.clone())?;
+    paths_to_verify.retain(|p| p != envelope_path);
+    paths_to_verify.sort();
+
+    let signed_at = envelope.statement()?.predicate.signed_at;
+    for key in manifest.verify_envelope(&paths_to_verify, &envelope)? {
+        super::output(
+            args.print,
+            format_args!(
+                "Signed by {} ({:?}) at {}",
+                &fingerprint(key.as_bytes())[..16],
+                key.algorithm(),
+                signed_at
+            ),
+        );
+        verdict.signers.push(SignerVerdict {
+            fingerprint: fingerprint(key.as_bytes()),
+            algorithm: key.algorithm(),
+            trusted: true,
+            signed_at: signed_at.clone(),
+            expires_at: None,
+            timestamped_at: None,
+            authenticated_at: None,
+        });
+    }
+    for path in &paths_to_verify {
+        verdict.files.push(FileVerdict {
+            path: path
+                .strip_prefix(base_path)
+                .unwrap_or(path)
+                .display()
+                .to_string(),
+            status: FileStatus::Ok,
+        });
+    }
+    check_policy(args, base_path, "SHA256", verdict)?;
+
+    super::output(args.print, format_args!("Signature verified"));
+
+    Ok(())
+}
+
+fn verify_embedded(
+    args: VerifyArgs,
+    base_path: &Path,
+    embedded: EmbeddedSignature,
+    verdict: &mut Verdict,
+) -> anyhow::Result<()> {
+    tracing::info!("Verifying embedded signature: {}", args.file_path.display());
+
+    if args.range.is_some() {
+        return Err(anyhow!(
+            "byte ranges can't be verified with an embedded signature"
+        ));
+    }
+
+    let signature =
+        Manifest::from_signature_str(base_path, &embedded.manifest.unwrap_or_default())?;
+    let mut manifest = verifier_for(&args, base_path, Some(&signature))?;
+
+    verdict.signature = Some(args.file_path.display().to_string());
+    let result = manifest.verify_embedded(&args.file_path, embedded.content, &signature);
+    set_files(verdict, &manifest, &signature);
+    set_signers(verdict, &args, &manifest, &signature)?;
+    if let Err(e) = result {
+        let paths = [args.file_path.canonicalize()?];
+        report_tampered_tensors(&args, &paths, &manifest, &signature)?;
+        return Err(e);
+    }
+    check_signers(&args, &manifest, &signature)?;
+    check_policy(&args, base_path, signature.hash_algorithm(), verdict)?;
+    pin_on_first_use(&args, &manifest, &signature)?;
+
+    super::output(args.print, format_args!("Signature verified"));
 
     Ok(())
 }
@@ -157,6 +1152,66 @@ mod tests {
     use std::fs::File;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_verify_max_age() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (private_key, public_key) = (
+            temp_dir.path().join("private.key"),
+            temp_dir.path().join("public.key"),
+        );
+        crate::core::signing::create_key(
+            &private_key,
+            &public_key,
+            crate::core::signing::SigningAlgorithm::Ed25519,
+        )?;
+        let file_path = temp_dir.path().join("model.bin");
+        std::fs::write(&file_path, "test")?;
+
+        // the signing date of 3.0 manifests is signed, even without an expiration
+        sign(SignArgs::new(file_path.clone(), private_key))?;
+        let mut args = VerifyArgs::new(file_path, vec![public_key]);
+        args.max_age = Some("90d".to_string());
+        verify(args)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verdict_of_signers() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let (private_key, public_key) = (
+            temp_dir.path().join("private.key"),
+            temp_dir.path().join("public.key"),
+        );
+        crate::core::signing::create_key(
+            &private_key,
+            &public_key,
+            crate::core::signing::SigningAlgorithm::Ed25519,
+        )?;
+        let file_path = temp_dir.path().join("model.bin");
+        std::fs::write(&file_path, "test")?;
+
+        sign(SignArgs::new(file_path.clone(), private_key))?;
+        let (verdict, result) = verdict_of(VerifyArgs::new(file_path, vec![public_key.clone()]));
+        result?;
+        assert_eq!(verdict.signers.len(), 1);
+        assert!(verdict.signers[0].trusted);
+        assert_eq!(
+            verdict.signers[0].fingerprint,
+            fingerprint(&crate::core::signing::load_public_key(&public_key)?)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("0:1024").unwrap(), 0..1024);
+        assert_eq!(parse_range(" 10 : 20 ").unwrap(), 10..20);
+        assert!(parse_range("10-20").is_err());
+        assert!(parse_range("a:1").is_err());
+    }
+
     #[test]
     fn test_get_paths_single_file() -> anyhow::Result<()> {
         let temp_dir = TempDir::new()?;
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::{
+    hub::is_safe_path,
+    storage::{storage_for, ObjectUri, Storage},
+};
+
+/// Objects of an object store downloaded to a temporary folder.
+pub(super) struct StoredModel {
+    pub uri: ObjectUri,
+    // local path of the model, the object or the whole prefix
+    pub path: PathBuf,
+    storage: Box<dyn Storage>,
+    // prefix of the objects, mapped to the root folder
+    prefix: String,
+    root: tempfile::TempDir,
+}
+
+impl StoredModel {
+    /// Identifies the model regardless of where it's been downloaded to.
+    pub fn model_id(&self) -> String {
+        self.uri
+            .with_key(self.uri.key.trim_end_matches('/'))
+            .to_string()
+    }
+}
+
+// the object and the signatures that can be stored next to it
+fn related_keys(key: &str) -> [String; 3] {
+    let path = Path::new(key);
+    [
+        key.to_string(),
+        path.with_extension("signature")
+            .to_string_lossy()
+            .to_string(),
+        path.with_extension("intoto.json")
+            .to_string_lossy()
+            .to_string(),
+    ]
+}
+
+/// Streams the objects of a s3://, gs:// or az:// model to a temporary folder, returns None
+/// for local paths. A key naming an object selects it along with its signatures, any other
+/// key is the prefix of the objects of the model.
+pub(super) fn download(
+    file_path: &Path,
+    ignore: Option<&str>,
+) -> anyhow::Result<Option<StoredModel>> {
+    if !ObjectUri::is_object_uri(file_path) {
+        return Ok(None);
+    }
+
+    let uri = ObjectUri::parse(&file_path.to_string_lossy())?;
+    let storage = storage_for(&uri)?;
+
+    let key = uri.key.trim_end_matches('/');
+    let candidates = if key.is_empty() {
+        vec![]
+    } else {
+        storage.list(&Path::new(key).with_extension("").to_string_lossy())?
+    };
+    let is_object = candidates.iter().any(|object| object.key == key);
+    let (prefix, objects) = if is_object {
+        let related = related_keys(key);
+        let prefix = key
+            .rsplit_once('/')
+            .map(|(parent, _)| format!("{}/", parent))
+            .unwrap_or_default();
+        let objects = candidates
+            .into_iter()
+            .filter(|object| related.contains(&object.key))
+            .collect::<Vec<_>>();
+        (prefix, objects)
+    } else {
+        let prefix = if key.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", key)
+        };
+        let objects = storage
+            .list(&prefix)?
+            .into_iter()
+            // folder placeholders
+            .filter(|object| !object.key.ends_with('/'))
+            .filter(|object| !ignore.is_some_and(|ignore| object.key.contains(ignore)))
+            .collect::<Vec<_>>();
+        (prefix, objects)
+    };
+    if objects.is_empty() {
+        anyhow::bail!("no objects found at {}", uri);
+    }
+
+    let root = tempfile::tempdir()?;
+    for object in &objects {
+        let relative = &object.key[prefix.len()..];
+        if !is_safe_path(relative) {
+            anyhow::bail!("invalid object key '{}'", object.key);
+        }
+        let output = root.path().join(relative);
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        tracing::info!("Downloading {} ...", uri.with_key(&object.key));
+        let mut reader = storage.reader(&object.key)?;
+        let mut writer = std::fs::File::create(&output)?;
+        if std::io::copy(&mut reader, &mut writer)? != object.size {
+            anyhow::bail!("{} changed while downloading it", uri.with_key(&object.key));
+        }
+    }
+
+    let path = if is_object {
+        root.path().join(&key[prefix.len()..])
+    } else {
+        root.path().to_path_buf()
+    };
+    Ok(Some(StoredModel {
+        uri,
+        path,
+        storage,
+        prefix,
+        root,
+    }))
+}
+
+/// Stores a signature of the model next to its objects, at the same path relative to the root.
+pub(super) fn upload(model: &StoredModel, path: &Path) -> anyhow::Result<()> {
+    let relative = path
+        .canonicalize()?
+        .strip_prefix(model.root.path().canonicalize()?)
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "{} is not in the folder of {}, can't upload it",
+                path.display(),
+                model.uri
+            )
+        })?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let uri = model.uri.with_key(&format!("{}{}", model.prefix, relative));
+
+    tracing::info!("Uploading {} ...", uri);
+    model.storage.write(&uri.key, &std::fs::read(path)?)?;
+    tracing::info!("Uploaded {}", uri);
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use crate::core::{
+    sarif::{self, Finding},
+    validate::{self, RULES},
+};
+
+use super::ValidateArgs;
+
+// the files to validate, recursively if the path is a folder
+fn paths_to_validate(args: &ValidateArgs) -> anyhow::Result<Vec<PathBuf>> {
+    if !args.file_path.is_dir() {
+        return Ok(vec![args.file_path.clone()]);
+    }
+
+    let pattern = args.file_path.join("**").join("*");
+    let mut paths: Vec<PathBuf> = glob::glob(&pattern.to_string_lossy())?
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file() && validate::is_validatable(path))
+        .collect();
+    paths.sort();
+
+    Ok(paths)
+}
+
+pub(crate) fn validate(args: ValidateArgs) -> anyhow::Result<()> {
+    let paths = paths_to_validate(&args)?;
+    if paths.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no safetensors, GGUF or ONNX files found in {}",
+            args.file_path.display()
+        ));
+    }
+
+    let mut validations = vec![];
+    for path in &paths {
+        let validation = validate::validate(path)?;
+        if validation.violations.is_empty() {
+            println!("{}: valid", path.display());
+        } else {
+            println!(
+                "{}: {} violations",
+                path.display(),
+                validation.violations.len()
+            );
+        }
+        for violation in &validation.violations {
+            println!("  {:<22} {}", violation.rule_id, violation.message);
+        }
+
+        validations.push(validation);
+    }
+
+    if let Some(json_path) = &args.to_json {
+        let json_str = serde_json::to_string_pretty(&validations)?;
+        std::fs::write(json_path, json_str)?;
+
+        tracing::info!("saved to {:?}", json_path);
+    }
+    if let Some(sarif_path) = &args.sarif {
+        let findings: Vec<_> = validations
+            .iter()
+            .flat_map(|validation| {
+                validation.violations.iter().map(|violation| Finding {
+                    rule_id: violation.rule_id,
+                    message: violation.message.clone(),
+                    uri: sarif::artifact_uri(&validation.file_path),
+                    byte_offset: violation.offset,
+                })
+            })
+            .collect();
+        sarif::save(sarif_path, RULES, &findings, None)?;
+    }
+
+    let invalid = validations
+        .iter()
+        .filter(|validation| !validation.violations.is_empty())
+        .count();
+    if invalid > 0 {
+        Err(anyhow::anyhow!(
+            "{} of {} files are not valid",
+            invalid,
+            paths.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Inspection, TensorDescriptor};
+
+// prefix PEFT adds to the names of the base model modules
+const PEFT_PREFIX: &str = "base_model.model.";
+
+// shapes of the A and B matrices updating a tensor
+type PairShapes = (Option<Vec<usize>>, Option<Vec<usize>>);
+
+/// A LoRA adapter, in the PEFT or in the llama.cpp GGUF format.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct Adapter {
+    /// PEFT or GGUF.
+    pub format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f64>,
+    pub target_modules: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_model: Option<String>,
+    /// Number of pairs of low rank matrices.
+    pub pairs: usize,
+}
+
+/// The two low rank matrices updating a tensor of the base model.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LoraPair {
+    // name of the tensor of the base model
+    pub base: String,
+    // module of the tensor, such as q_proj or attn_q
+    pub module: String,
+    pub a: Vec<usize>,
+    pub b: Vec<usize>,
+}
+
+/// Pairs the lora_A and lora_B (PEFT) or lora_a and lora_b (GGUF) tensors by base tensor.
+pub(crate) fn lora_pairs(tensors: &[TensorDescriptor], gguf: bool) -> Vec<LoraPair> {
+    let mut halves: BTreeMap<String, PairShapes> = BTreeMap::new();
+    for tensor in tensors {
+        let name = tensor.id.as_deref().unwrap_or_default();
+        let split = if gguf {
+            name.strip_suffix(".lora_a")
+                .map(|base| (base.to_string(), true))
+                .or(name
+                    .strip_suffix(".lora_b")
+                    .map(|base| (base.to_string(), false)))
+        } else {
+            let name = name.strip_prefix(PEFT_PREFIX).unwrap_or(name);
+            name.strip_suffix(".lora_A.weight")
+                .map(|module| (format!("{}.weight", module), true))
+                .or(name
+                    .strip_suffix(".lora_B.weight")
+                    .map(|module| (format!("{}.weight", module), false)))
+        };
+        let Some((base, is_a)) = split else {
+            continue;
+        };
+        let half = halves.entry(base).or_default();
+        if is_a {
+            half.0 = Some(tensor.shape.clone());
+        } else {
+            half.1 = Some(tensor.shape.clone());
+        }
+    }
+
+    halves
+        .into_iter()
+        .filter_map(|(base, (a, b))| {
+            let module = base
+                .trim_end_matches(".weight")
+                .rsplit('.')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            Some(LoraPair {
+                module,
+                a: a?,
+                b: b?,
+                base,
+            })
+        })
+        .collect()
+}
+
+fn string_list(value: Option<&Value>) -> Option<Vec<String>> {
+    match value? {
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect(),
+        ),
+        // a regular expression matching the module names
+        Value::String(pattern) => Some(vec![pattern.clone()]),
+        _ => None,
+    }
+}
+
+/// Recognizes a LoRA adapter from the full detail inspection of a file: a safetensors file with a
+/// PEFT adapter_config.json next to it, or a GGUF file of the adapter type.
+pub(crate) fn detect(inspection: &Inspection, peft_config: Option<&Value>) -> Option<Adapter> {
+    let tensors = inspection.tensors.as_deref().unwrap_or_default();
+    let gguf = inspection.file_type.is_gguf();
+    if gguf {
+        if inspection.metadata.get("general.type").map(String::as_str) != Some("adapter")
+            || inspection.metadata.get("adapter.type").map(String::as_str) != Some("lora")
+        {
+            return None;
+        }
+    } else if !peft_config
+        .and_then(|config| config.get("peft_type"))
+        .and_then(Value::as_str)
+        .is_some_and(|peft_type| peft_type.eq_ignore_ascii_case("lora"))
+    {
+        return None;
+    }
+
+    let pairs = lora_pairs(tensors, gguf);
+    let modules: BTreeSet<String> = pairs.iter().map(|pair| pair.module.clone()).collect();
+    // the inner dimension of A, [r, in] for PEFT and [in, r] in the GGUF dimension order
+    let tensor_rank = pairs
+        .first()
+        .and_then(|pair| pair.a.get(if gguf { 1 } else { 0 }).copied());
+
+    let adapter = if gguf {
+        Adapter {
+            format: "GGUF".to_string(),
+            rank: tensor_rank,
+            alpha: inspection
+                .metadata
+                .get("adapter.lora.alpha")
+                .and_then(|alpha| alpha.parse().ok()),
+            target_modules: modules.into_iter().collect(),
+            base_model: inspection
+                .metadata
+                .get("general.base_model.0.name")
+                .cloned(),
+            pairs: pairs.len(),
+        }
+    } else {
+        let config = peft_config?;
+        Adapter {
+            format: "PEFT".to_string(),
+            rank: config
+                .get("r")
+                .and_then(Value::as_u64)
+                .map(|rank| rank as usize)
+                .or(tensor_rank),
+            alpha: config.get("lora_alpha").and_then(Value::as_f64),
+            target_modules: string_list(config.get("target_modules"))
+                .unwrap_or_else(|| modules.into_iter().collect()),
+            base_model: config
+                .get("base_model_name_or_path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            pairs: pairs.len(),
+        }
+    };
+    Some(adapter)
+}
+
+/// Checks that each pair of the adapter updates a tensor of the base model of the right shape,
+/// returning the incompatibilities found.
+pub(crate) fn check_base(pairs: &[LoraPair], base: &[TensorDescriptor], gguf: bool) -> Vec<String> {
+    let base: BTreeMap<&str, &Vec<usize>> = base
+        .iter()
+        .filter_map(|tensor| tensor.id.as_deref().map(|id| (id, &tensor.shape)))
+        .collect();
+
+    let mut problems = vec![];
+    for pair in pairs {
+        let Some(shape) = base.get(pair.base.as_str()) else {
+            problems.push(format!("{} not found in the base model", pair.base));
+            continue;
+        };
+        // the product B x A must have the shape of the base tensor, [out, in] for PEFT, and
+        // [in, out] in the GGUF dimension order
+        let (inputs, outputs) = if gguf {
+            (pair.a.first(), pair.b.get(1))
+        } else {
+            (pair.a.get(1), pair.b.first())
+        };
+        let expected = if gguf {
+            (shape.first(), shape.get(1))
+        } else {
+            (shape.get(1), shape.first())
+        };
+        if (inputs, outputs) != expected || shape.len() != 2 {
+            problems.push(format!(
+                "{} has shape {:?}, the adapter updates it with A {:?} and B {:?}",
+                pair.base, shape, pair.a, pair.b
+            ));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::FileType;
+
+    fn tensor(id: &str, shape: Vec<usize>) -> TensorDescriptor {
+        TensorDescriptor {
+            id: Some(id.to_string()),
+            shape,
+            dtype: "F32".to_string(),
+            size: 0,
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        }
+    }
+
+    #[test]
+    fn test_peft() {
+        let prefix = "base_model.model.model.layers.0.self_attn";
+        let inspection = Inspection {
+            file_type: FileType::SafeTensors,
+            tensors: Some(vec![
+                tensor(&format!("{}.q_proj.lora_A.weight", prefix), vec![8, 64]),
+                tensor(&format!("{}.q_proj.lora_B.weight", prefix), vec![64, 8]),
+                tensor(&format!("{}.k_proj.lora_A.weight", prefix), vec![8, 64]),
+                tensor(&format!("{}.k_proj.lora_B.weight", prefix), vec![16, 8]),
+            ]),
+            ..Default::default()
+        };
+        let config = serde_json::json!({
+            "peft_type": "LORA",
+            "r": 8,
+            "lora_alpha": 16,
+            "target_modules": ["q_proj", "k_proj"],
+            "base_model_name_or_path": "org/tiny"
+        });
+
+        assert_eq!(detect(&inspection, None), None);
+        let adapter = detect(&inspection, Some(&config)).unwrap();
+        assert_eq!(adapter.rank, Some(8));
+        assert_eq!(adapter.alpha, Some(16.0));
+        assert_eq!(adapter.target_modules, vec!["q_proj", "k_proj"]);
+        assert_eq!(adapter.pairs, 2);
+
+        let pairs = lora_pairs(inspection.tensors.as_ref().unwrap(), false);
+        assert_eq!(pairs[0].base, "model.layers.0.self_attn.k_proj.weight");
+        let base = vec![
+            tensor("model.layers.0.self_attn.q_proj.weight", vec![64, 64]),
+            tensor("model.layers.0.self_attn.k_proj.weight", vec![16, 64]),
+        ];
+        assert!(check_base(&pairs, &base, false).is_empty());
+        // a base model of another size
+        let base = vec![tensor(
+            "model.layers.0.self_attn.q_proj.weight",
+            vec![32, 32],
+        )];
+        assert_eq!(
+            check_base(&pairs, &base, false),
+            vec![
+                "model.layers.0.self_attn.k_proj.weight not found in the base model",
+                "model.layers.0.self_attn.q_proj.weight has shape [32, 32], the adapter updates it with A [8, 64] and B [64, 8]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gguf() {
+        let inspection = Inspection {
+            file_type: FileType::GGUF,
+            metadata: [
+                ("general.type", "adapter"),
+                ("adapter.type", "lora"),
+                ("adapter.lora.alpha", "32"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+            tensors: Some(vec![
+                tensor("blk.0.attn_q.weight.lora_a", vec![64, 16]),
+                tensor("blk.0.attn_q.weight.lora_b", vec![16, 128]),
+            ]),
+            ..Default::default()
+        };
+
+        let adapter = detect(&inspection, None).unwrap();
+        assert_eq!(adapter.format, "GGUF");
+        assert_eq!(adapter.rank, Some(16));
+        assert_eq!(adapter.alpha, Some(32.0));
+        assert_eq!(adapter.target_modules, vec!["attn_q"]);
+
+        let pairs = lora_pairs(inspection.tensors.as_ref().unwrap(), true);
+        assert!(check_base(
+            &pairs,
+            &[tensor("blk.0.attn_q.weight", vec![64, 128])],
+            true
+        )
+        .is_empty());
+        assert_eq!(
+            check_base(
+                &pairs,
+                &[tensor("blk.0.attn_q.weight", vec![128, 64])],
+                true
+            )
+            .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    compression::{self, Compression},
+    FileType,
+};
+
+/// Format of a model bundle, such as a TorchServe .mar or an NGC archive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ArchiveType {
+    /// Tarball, compressed or not.
+    Tar,
+    Zip,
+}
+
+impl ArchiveType {
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        // tarballs can be compressed with any of the supported compressions
+        let inner = compression::decompressed_path(Path::new(&name));
+        match inner.extension()?.to_str()? {
+            "tar" | "tgz" => Some(Self::Tar),
+            // TorchServe model archives are zip files
+            "zip" | "mar" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// A file of an archive, along with the format and the tensors of the model it is.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct Member {
+    pub name: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<FileType>,
+    pub num_tensors: usize,
+}
+
+// the path of the member relative to the extraction folder, None if it would be outside of it
+fn relative_path_of(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Calls visit with the name and the content of each file of the archive, in archive order.
+/// Folders, links and other special members are skipped.
+pub(crate) fn for_each_member(
+    path: &Path,
+    mut visit: impl FnMut(&str, &mut dyn Read) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    match ArchiveType::of(path) {
+        Some(ArchiveType::Tar) => {
+            let file = std::fs::File::open(path)?;
+            let reader = if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("tgz"))
+            {
+                Compression::Gzip.decoder(file)?
+            } else {
+                compression::open(path)?
+            };
+            let mut archive = tar::Archive::new(reader);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path()?.to_string_lossy().to_string();
+                visit(&name, &mut entry)?;
+            }
+        }
+        Some(ArchiveType::Zip) => {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+            for index in 0..archive.len() {
+                let mut file = archive.by_index(index)?;
+                if !file.is_file() {
+                    continue;
+                }
+                let name = file.name().to_string();
+                visit(&name, &mut file)?;
+            }
+        }
+        None => anyhow::bail!("{} is not a supported archive", path.display()),
+    }
+    Ok(())
+}
+
+/// Extracts the files of the archive to the folder, returning their normalized names and paths. Archives
+/// with members that would be extracted outside of the folder are refused.
+pub(crate) fn extract(path: &Path, folder: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut members = vec![];
+    for_each_member(path, |name, content| {
+        let relative_path = relative_path_of(name)
+            .ok_or_else(|| anyhow::anyhow!("member {} is outside of the archive folder", name))?;
+        let target = folder.join(&relative_path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::io::copy(content, &mut std::fs::File::create(&target)?)?;
+        members.push((relative_path.to_string_lossy().to_string(), target));
+        Ok(())
+    })?;
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn build_tar(path: &Path, members: &[(&str, &[u8])]) {
+        let file = std::fs::File::create(path).unwrap();
+        let writer: Box<dyn Write> = if path.extension().is_some_and(|ext| ext == "gz") {
+            Box::new(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::fast(),
+            ))
+        } else {
+            Box::new(file)
+        };
+        let mut builder = tar::Builder::new(writer);
+        for (name, content) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            // set directly, append_data refuses the names going up the tree
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            builder.append(&header, *content).unwrap();
+        }
+        builder.into_inner().unwrap().flush().unwrap();
+    }
+
+    #[test]
+    fn test_archive_type() {
+        for (name, expected) in [
+            ("model.tar", Some(ArchiveType::Tar)),
+            ("model.TAR.GZ", Some(ArchiveType::Tar)),
+            ("model.tgz", Some(ArchiveType::Tar)),
+            ("model.tar.zst", Some(ArchiveType::Tar)),
+            ("model.zip", Some(ArchiveType::Zip)),
+            ("resnet-18.mar", Some(ArchiveType::Zip)),
+            ("model.safetensors.gz", None),
+            ("model.gguf", None),
+        ] {
+            assert_eq!(ArchiveType::of(Path::new(name)), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        let dir = tempfile::tempdir().unwrap();
+        let tarball = dir.path().join("bundle.tar.gz");
+        build_tar(
+            &tarball,
+            &[("./model/weights.bin", b"weights"), ("config.json", b"{}")],
+        );
+        let zip_path = dir.path().join("bundle.mar");
+        let options = zip::write::SimpleFileOptions::default();
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        zip.add_directory("model/", options).unwrap();
+        zip.start_file("model/weights.bin", options).unwrap();
+        zip.write_all(b"weights").unwrap();
+        zip.start_file("config.json", options).unwrap();
+        zip.write_all(b"{}").unwrap();
+        zip.finish().unwrap();
+
+        for archive in [tarball, zip_path] {
+            let folder = tempfile::tempdir().unwrap();
+            let members = extract(&archive, folder.path()).unwrap();
+            assert_eq!(members.len(), 2);
+            assert_eq!(members[0].0, "model/weights.bin");
+            assert_eq!(members[1].0, "config.json");
+            assert_eq!(
+                std::fs::read(folder.path().join("model/weights.bin")).unwrap(),
+                b"weights"
+            );
+        }
+
+        // members can't be extracted outside of the folder
+        let evil = dir.path().join("evil.tar");
+        build_tar(&evil, &[("../evil.txt", b"evil")]);
+        let folder = dir.path().join("out");
+        assert!(extract(&evil, &folder).is_err());
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+}
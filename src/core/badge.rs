@@ -0,0 +1,136 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::report::escape_html;
+
+// colors of shields.io, by name for the endpoint badges and as rendered in the SVG ones
+const PASSING_COLOR: (&str, &str) = ("brightgreen", "#4c1");
+const FAILING_COLOR: (&str, &str) = ("red", "#e05d44");
+const LABEL_COLOR: &str = "#555";
+// horizontal padding of each half of the badge
+const PADDING: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub(crate) enum BadgeFormat {
+    Svg,
+    /// shields.io endpoint JSON, for https://img.shields.io/endpoint?url=...
+    Json,
+}
+
+impl BadgeFormat {
+    /// The format of a badge file, by its extension.
+    pub(crate) fn of_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "svg" => Some(Self::Svg),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+// https://shields.io/badges/endpoint-badge
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint<'a> {
+    schema_version: u8,
+    label: &'a str,
+    message: &'a str,
+    color: &'a str,
+}
+
+/// A shields.io style badge, green if passing and red otherwise.
+#[derive(Debug)]
+pub(crate) struct Badge {
+    pub label: String,
+    pub message: String,
+    pub passing: bool,
+}
+
+// approximate width in pixels of the text in the 11px Verdana of the badges
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' => 3,
+            'f' | 'r' | 't' | 'I' | ' ' | '(' | ')' | '[' | ']' | '-' => 5,
+            'm' | 'w' | 'M' | 'W' => 10,
+            c if c.is_ascii_uppercase() => 8,
+            _ => 7,
+        })
+        .sum()
+}
+
+impl Badge {
+    fn color(&self) -> (&'static str, &'static str) {
+        if self.passing {
+            PASSING_COLOR
+        } else {
+            FAILING_COLOR
+        }
+    }
+
+    pub(crate) fn render(&self, format: &BadgeFormat) -> String {
+        match format {
+            BadgeFormat::Svg => self.to_svg(),
+            BadgeFormat::Json => self.to_json(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let endpoint = Endpoint {
+            schema_version: 1,
+            label: &self.label,
+            message: &self.message,
+            color: self.color().0,
+        };
+        serde_json::to_string_pretty(&endpoint).unwrap()
+    }
+
+    // the flat style of shields.io
+    fn to_svg(&self) -> String {
+        let label_width = text_width(&self.label) + PADDING;
+        let message_width = text_width(&self.message) + PADDING;
+        let width = label_width + message_width;
+        let label = escape_html(&self.label);
+        let message = escape_html(&self.message);
+        let text = |x: usize, value: &str| {
+            format!(
+                r##"<text x="{x}" y="15" fill="#010101" fill-opacity=".3">{value}</text><text x="{x}" y="14">{value}</text>"##
+            )
+        };
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="{LABEL_COLOR}"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">{label_text}{message_text}</g></svg>
+"##,
+            color = self.color().1,
+            label_text = text(label_width / 2, &label),
+            message_text = text(label_width + message_width / 2, &message),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let badge = Badge {
+            label: "tman".to_string(),
+            message: "signed by <fbd0200f> ✓".to_string(),
+            passing: true,
+        };
+
+        let json: serde_json::Value =
+            serde_json::from_str(&badge.render(&BadgeFormat::Json)).unwrap();
+        assert_eq!(json["schemaVersion"], 1);
+        assert_eq!(json["message"], "signed by <fbd0200f> ✓");
+        assert_eq!(json["color"], "brightgreen");
+
+        let svg = badge.render(&BadgeFormat::Svg);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r##"fill="#4c1""##));
+        // the message is escaped, and the badge wide enough for it
+        assert!(svg.contains("signed by &lt;fbd0200f&gt; ✓"));
+        assert!(text_width("signed by fbd0200f ✓") > text_width("unsigned ✗"));
+    }
+}
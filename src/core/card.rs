@@ -0,0 +1,430 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use serde_json::Value;
+
+use super::{handlers::EMBEDDED_SIGNATURE_KEY, license, Inspection};
+
+// keys of a Hugging Face config.json describing the architecture
+const CONFIG_KEYS: &[&str] = &[
+    "architectures",
+    "model_type",
+    "hidden_size",
+    "intermediate_size",
+    "num_hidden_layers",
+    "num_attention_heads",
+    "num_key_value_heads",
+    "vocab_size",
+    "max_position_embeddings",
+    "torch_dtype",
+];
+// longer metadata values, such as vocabularies and chat templates, don't belong to the card
+const MAX_VALUE_SIZE: usize = 80;
+
+/// A signature of the model, as found next to it or embedded in it.
+#[derive(Debug, Clone)]
+pub(crate) struct CardSigner {
+    pub fingerprint: String,
+    pub algorithm: String,
+    pub signed_at: String,
+}
+
+/// Where the signature of the model has been found and who signed it, not verified.
+#[derive(Debug, Clone)]
+pub(crate) struct CardSignature {
+    pub source: String,
+    pub signers: Vec<CardSigner>,
+}
+
+/// Data a model card is rendered from.
+#[derive(Debug, Default)]
+pub(crate) struct ModelCard {
+    pub name: String,
+    // full detail inspections of the model files
+    pub inspections: Vec<Inspection>,
+    // Hugging Face config.json next to the model, if any
+    pub config: Option<serde_json::Map<String, Value>>,
+    // LICENSE files next to the model
+    pub license_files: Vec<String>,
+    pub signature: Option<CardSignature>,
+}
+
+// escapes a value for a Markdown table cell
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn config_value(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(config_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => value.to_string(),
+    }
+}
+
+/// Formats a number of parameters as 1.24B, 350M or 12K.
+pub(crate) fn format_count(count: usize) -> String {
+    for (unit, scale) in [("T", 1e12), ("B", 1e9), ("M", 1e6), ("K", 1e3)] {
+        if count as f64 >= scale {
+            let value = format!("{:.2}", count as f64 / scale);
+            let value = value.trim_end_matches('0').trim_end_matches('.');
+            return format!("{}{}", value, unit);
+        }
+    }
+    count.to_string()
+}
+
+// quantized and 8 bits or less element types, as named by the different formats
+fn is_quantized(dtype: &str) -> bool {
+    let dtype = dtype.to_ascii_uppercase();
+    ["Q", "IQ", "TQ"].iter().any(|prefix| {
+        dtype
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+    }) || ["I8", "U8", "INT8", "UINT8", "INT4", "UINT4"].contains(&dtype.as_str())
+        || dtype.contains("F8")
+        || dtype.contains("FLOAT8")
+        || dtype.contains("FP4")
+        || dtype.contains("FLOAT4")
+}
+
+fn is_license_key(key: &str) -> bool {
+    key.to_ascii_lowercase().contains("license")
+}
+
+impl ModelCard {
+    fn parameters(&self) -> usize {
+        self.inspections
+            .iter()
+            .flat_map(|inspection| inspection.tensors.iter().flatten())
+            .map(|tensor| tensor.shape.iter().product::<usize>())
+            .sum()
+    }
+
+    fn dtypes(&self) -> Vec<String> {
+        let mut dtypes: Vec<String> = self
+            .inspections
+            .iter()
+            .flat_map(|inspection| inspection.unique_dtypes.iter().cloned())
+            .collect();
+        dtypes.sort();
+        dtypes.dedup();
+        dtypes
+    }
+
+    // metadata of the model files, the first shard wins
+    fn metadata(&self) -> BTreeMap<&str, &str> {
+        let mut metadata = BTreeMap::new();
+        for inspection in &self.inspections {
+            for (key, value) in &inspection.metadata {
+                metadata.entry(key.as_str()).or_insert(value.as_str());
+            }
+        }
+        metadata.remove(EMBEDDED_SIGNATURE_KEY);
+        metadata
+    }
+
+    fn architecture(&self) -> Vec<(String, String)> {
+        let mut rows: Vec<(String, String)> = self
+            .metadata()
+            .into_iter()
+            .filter(|(key, value)| {
+                !is_license_key(key)
+                    && !key.starts_with("tokenizer.")
+                    && !value.is_empty()
+                    && value.len() <= MAX_VALUE_SIZE
+            })
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        if let Some(config) = &self.config {
+            for key in CONFIG_KEYS {
+                if let Some(value) = config.get(*key) {
+                    rows.push((format!("config.json {}", key), config_value(value)));
+                }
+            }
+        }
+        rows
+    }
+
+    fn quantization(&self) -> Option<String> {
+        let mut methods: Vec<String> = self
+            .dtypes()
+            .into_iter()
+            .filter(|dtype| is_quantized(dtype))
+            .collect();
+
+        if let Some(config) = self
+            .config
+            .as_ref()
+            .and_then(|config| config.get("quantization_config"))
+        {
+            let method = config
+                .get("quant_method")
+                .map(config_value)
+                .unwrap_or("unknown method".to_string());
+            match config.get("bits") {
+                Some(bits) => methods.push(format!("{} ({} bits)", method, bits)),
+                None => methods.push(method),
+            }
+        }
+
+        (!methods.is_empty()).then(|| methods.join(", "))
+    }
+
+    fn licenses(&self) -> Vec<(String, String)> {
+        let mut licenses: Vec<(String, String)> = self
+            .metadata()
+            .into_iter()
+            .filter(|(key, value)| is_license_key(key) && !value.is_empty())
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        if let Some(Value::String(license)) = self
+            .config
+            .as_ref()
+            .and_then(|config| config.get("license"))
+        {
+            licenses.push(("config.json license".to_string(), license.clone()));
+        }
+        licenses
+    }
+
+    fn write_details(&self, card: &mut String) -> std::fmt::Result {
+        let mut formats: Vec<String> = self
+            .inspections
+            .iter()
+            .map(|inspection| inspection.file_type.to_string())
+            .collect();
+        formats.dedup();
+        let file_size: u64 = self.inspections.iter().map(|i| i.file_size).sum();
+        let parameters = self.parameters();
+        let dtypes = self.dtypes();
+
+        writeln!(card, "## Model Details\n")?;
+        writeln!(card, "| | |\n|---|---|")?;
+        writeln!(card, "| Format | {} |", formats.join(", "))?;
+        writeln!(
+            card,
+            "| Files | {} ({}) |",
+            self.inspections.len(),
+            humansize::format_size(file_size, humansize::DECIMAL)
+        )?;
+        writeln!(
+            card,
+            "| Parameters | {} ({}) |",
+            format_count(parameters),
+            parameters
+        )?;
+        writeln!(
+            card,
+            "| Tensors | {} |",
+            self.inspections
+                .iter()
+                .map(|inspection| inspection.num_tensors)
+                .sum::<usize>()
+        )?;
+        writeln!(card, "| Data types | {} |", dtypes.join(", "))?;
+        writeln!(
+            card,
+            "| Quantization | {} |",
+            self.quantization().unwrap_or("none".to_string())
+        )?;
+
+        let architecture = self.architecture();
+        if !architecture.is_empty() {
+            writeln!(card, "\n## Architecture\n")?;
+            writeln!(card, "| Key | Value |\n|---|---|")?;
+            for (key, value) in architecture {
+                writeln!(card, "| `{}` | {} |", cell(&key), cell(&value))?;
+            }
+        }
+
+        writeln!(card, "\n## Files\n")?;
+        writeln!(
+            card,
+            "| File | Format | Size | Tensors |\n|---|---|---|---|"
+        )?;
+        for inspection in &self.inspections {
+            writeln!(
+                card,
+                "| `{}` | {} | {} | {} |",
+                cell(
+                    &inspection
+                        .file_path
+                        .file_name()
+                        .unwrap_or(inspection.file_path.as_os_str())
+                        .to_string_lossy()
+                ),
+                inspection.file_type,
+                humansize::format_size(inspection.file_size, humansize::DECIMAL),
+                inspection.num_tensors
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_license(&self, card: &mut String) -> std::fmt::Result {
+        let licenses = self.licenses();
+
+        writeln!(card, "\n## License\n")?;
+        if licenses.is_empty() && self.license_files.is_empty() {
+            writeln!(
+                card,
+                "No license found in the metadata of the model, add one before publishing it."
+            )?;
+        }
+        for (key, value) in &licenses {
+            writeln!(card, "- {} (`{}`)", cell(value), key)?;
+        }
+        for file in &self.license_files {
+            writeln!(card, "- See [{}]({})", file, file)?;
+        }
+        Ok(())
+    }
+
+    fn write_signature(&self, card: &mut String) -> std::fmt::Result {
+        writeln!(card, "\n## Signature\n")?;
+        let Some(signature) = &self.signature else {
+            return writeln!(
+                card,
+                "The model is not signed, sign it with `tman sign` so that its integrity can be verified."
+            );
+        };
+
+        writeln!(
+            card,
+            "Signed with tensor-man, the signature is {}. Verify it with `tman verify` and the public keys of the publisher.\n",
+            signature.source
+        )?;
+        writeln!(card, "| Signer | Algorithm | Signed at |\n|---|---|---|")?;
+        for signer in &signature.signers {
+            writeln!(
+                card,
+                "| `{}` | {} | {} |",
+                cell(&signer.fingerprint),
+                signer.algorithm,
+                cell(&signer.signed_at)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Renders the card as Markdown, with a Hugging Face metadata header.
+    pub(crate) fn render(&self) -> String {
+        let mut card = String::new();
+        let license = self
+            .licenses()
+            .into_iter()
+            .map(|(_, value)| license::normalize(&value))
+            .find(|name| !name.is_empty());
+
+        card.push_str("---\n");
+        if let Some(license) = license {
+            card.push_str(&format!("license: {}\n", license));
+        }
+        card.push_str("tags:\n");
+        let mut tags: Vec<String> = self
+            .inspections
+            .iter()
+            .map(|inspection| inspection.file_type.to_string().to_ascii_lowercase())
+            .collect();
+        tags.dedup();
+        for tag in tags {
+            card.push_str(&format!("- {}\n", tag));
+        }
+        card.push_str("---\n\n");
+
+        card.push_str(&format!("# {}\n\n", self.name));
+        card.push_str("<!-- Describe the model, how it has been trained, its intended uses and limitations. -->\n\n");
+
+        // writing to a String can't fail
+        _ = self.write_details(&mut card);
+        _ = self.write_license(&mut card);
+        _ = self.write_signature(&mut card);
+        card
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileType, TensorDescriptor};
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(12_000), "12K");
+        assert_eq!(format_count(350_000_000), "350M");
+        assert_eq!(format_count(1_235_814_400), "1.24B");
+        assert!(is_quantized("Q4_K"));
+        assert!(is_quantized("IQ2_XS"));
+        assert!(is_quantized("F8_E4M3"));
+        assert!(!is_quantized("BF16"));
+        assert!(!is_quantized("QWEN"));
+    }
+
+    #[test]
+    fn test_render() {
+        let tensor = |shape: Vec<usize>| TensorDescriptor {
+            id: Some("w".to_string()),
+            shape,
+            dtype: "Q4_K".to_string(),
+            size: 0,
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        let card = ModelCard {
+            name: "tiny-llama".to_string(),
+            inspections: vec![Inspection {
+                file_path: "/models/tiny-llama.gguf".into(),
+                file_type: FileType::GGUF,
+                file_size: 1_000_000,
+                num_tensors: 2,
+                unique_dtypes: vec!["Q4_K".to_string(), "F32".to_string()],
+                metadata: [
+                    ("general.architecture", "llama"),
+                    ("general.license", "Apache-2.0"),
+                    ("llama.block_count", "22"),
+                    ("tokenizer.chat_template", "{{ messages }}"),
+                    (EMBEDDED_SIGNATURE_KEY, "{}"),
+                ]
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+                tensors: Some(vec![tensor(vec![1000, 2000]), tensor(vec![500])]),
+                ..Default::default()
+            }],
+            signature: Some(CardSignature {
+                source: "embedded in the model metadata".to_string(),
+                signers: vec![CardSigner {
+                    fingerprint: "37decd85fd37c6d1".to_string(),
+                    algorithm: "Ed25519".to_string(),
+                    signed_at: "2024-10-16T13:54:17+00:00".to_string(),
+                }],
+            }),
+            ..Default::default()
+        };
+
+        let card = card.render();
+        for line in [
+            "license: apache-2.0\ntags:\n- gguf\n---",
+            "# tiny-llama",
+            "| Parameters | 2M (2000500) |",
+            "| Data types | F32, Q4_K |",
+            "| Quantization | Q4_K |",
+            "| `general.architecture` | llama |",
+            "| `llama.block_count` | 22 |",
+            "| `tiny-llama.gguf` | GGUF | 1 MB | 2 |",
+            "- Apache-2.0 (`general.license`)",
+            "| `37decd85fd37c6d1` | Ed25519 | 2024-10-16T13:54:17+00:00 |",
+        ] {
+            assert!(card.contains(line), "{} not in\n{}", line, card);
+        }
+        assert!(!card.contains("chat_template"));
+        assert!(!card.contains(EMBEDDED_SIGNATURE_KEY));
+    }
+}
@@ -0,0 +1,114 @@
+use std::{io::Read, path::Path};
+
+use ring::digest::{Context, SHA256};
+
+/// A line of a `sha256sum` style checksums file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entry {
+    pub checksum: String,
+    pub path: String,
+}
+
+impl Entry {
+    /// Formats the entry as `sha256sum` does in text mode.
+    pub fn to_line(&self) -> String {
+        format!("{}  {}", self.checksum, self.path)
+    }
+}
+
+/// Computes the hex-encoded SHA-256 of the file.
+pub(crate) fn sha256_of(path: &Path) -> anyhow::Result<String> {
+    sha256_of_reader(&mut std::fs::File::open(path)?)
+}
+
+/// Computes the hex-encoded SHA-256 of the content read.
+pub(crate) fn sha256_of_reader(file: &mut impl Read) -> anyhow::Result<String> {
+    let mut context = Context::new(&SHA256);
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+    Ok(hex::encode(context.finish()))
+}
+
+fn is_sha256(checksum: &str) -> bool {
+    checksum.len() == 64 && checksum.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Parses a checksums file in the `sha256sum` format, text (`HASH  PATH`) or binary
+/// (`HASH *PATH`) mode, or in the BSD one (`SHA256 (PATH) = HASH`) of `sha256sum --tag`.
+pub(crate) fn parse(data: &str) -> anyhow::Result<Vec<Entry>> {
+    let mut entries = vec![];
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let entry = if let Some(tagged) = line.strip_prefix("SHA256 (") {
+            tagged.rsplit_once(") = ").map(|(path, checksum)| Entry {
+                checksum: checksum.to_lowercase(),
+                path: path.to_string(),
+            })
+        } else {
+            line.split_once(' ').and_then(|(checksum, path)| {
+                path.strip_prefix([' ', '*']).map(|path| Entry {
+                    checksum: checksum.to_lowercase(),
+                    path: path.to_string(),
+                })
+            })
+        };
+
+        match entry {
+            Some(entry) if is_sha256(&entry.checksum) && !entry.path.is_empty() => {
+                entries.push(entry)
+            }
+            _ => anyhow::bail!("invalid SHA-256 checksum line {}: {}", index + 1, line),
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_of() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("abc.txt");
+        std::fs::write(&path, "abc").unwrap();
+
+        assert_eq!(
+            sha256_of(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let checksum = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let data = format!(
+            "# comment\n{checksum}  model.safetensors\n{} *bin/weights 1.bin\r\n\nSHA256 (config.json) = {checksum}\n",
+            checksum.to_uppercase()
+        );
+
+        let entries = parse(&data).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].to_line(),
+            format!("{checksum}  model.safetensors")
+        );
+        assert_eq!(entries[1].path, "bin/weights 1.bin");
+        assert_eq!(entries[1].checksum, checksum);
+        assert_eq!(entries[2].path, "config.json");
+
+        assert!(parse("abc  model.safetensors").is_err());
+        assert!(parse(&format!("{checksum}model.safetensors")).is_err());
+        assert!(parse(&format!("{checksum}  ")).is_err());
+    }
+}
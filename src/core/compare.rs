@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::data::TensorData;
+
+/// Numerical differences between two tensors with the same name.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub(crate) struct TensorComparison {
+    pub id: String,
+    /// Number of elements of each tensor, if they differ the values are not compared.
+    pub num_elements: (usize, usize),
+    pub max_abs_diff: f64,
+    pub max_rel_diff: f64,
+    /// Number of elements outside of the tolerance.
+    pub mismatches: usize,
+}
+
+impl TensorComparison {
+    pub fn is_match(&self) -> bool {
+        self.num_elements.0 == self.num_elements.1 && self.mismatches == 0
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub(crate) struct Comparison {
+    pub rtol: f64,
+    pub atol: f64,
+    pub tensors: Vec<TensorComparison>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+}
+
+impl Comparison {
+    pub fn mismatching(&self) -> impl Iterator<Item = &TensorComparison> {
+        self.tensors.iter().filter(|t| !t.is_match())
+    }
+}
+
+/// Compares two tensors element by element, values are considered equal if
+/// |a - b| <= atol + rtol * |b| (like numpy.allclose), NaNs are only equal to NaNs.
+pub(crate) fn compare_tensor(
+    a: &TensorData,
+    b: &TensorData,
+    rtol: f64,
+    atol: f64,
+) -> TensorComparison {
+    let mut comparison = TensorComparison {
+        id: a.id.clone(),
+        num_elements: (a.values().count(), b.values().count()),
+        ..Default::default()
+    };
+
+    if comparison.num_elements.0 != comparison.num_elements.1 {
+        return comparison;
+    }
+
+    for (x, y) in a.values().zip(b.values()) {
+        if x.is_nan() || y.is_nan() {
+            if x.is_nan() != y.is_nan() {
+                comparison.mismatches += 1;
+            }
+            continue;
+        }
+        if x == y {
+            // also covers infinite values with the same sign
+            continue;
+        }
+
+        let abs_diff = (x - y).abs();
+        let rel_diff = if y != 0.0 {
+            abs_diff / y.abs()
+        } else {
+            f64::INFINITY
+        };
+
+        comparison.max_abs_diff = comparison.max_abs_diff.max(abs_diff);
+        comparison.max_rel_diff = comparison.max_rel_diff.max(rel_diff);
+        if x.is_infinite() || y.is_infinite() || abs_diff > atol + rtol * y.abs() {
+            comparison.mismatches += 1;
+        }
+    }
+
+    comparison
+}
+
+/// Matches tensors by name and compares them in parallel.
+pub(crate) fn compare(a: &[TensorData], b: &[TensorData], rtol: f64, atol: f64) -> Comparison {
+    let a_by_id: BTreeMap<&str, &TensorData> = a.iter().map(|t| (t.id.as_str(), t)).collect();
+    let b_by_id: BTreeMap<&str, &TensorData> = b.iter().map(|t| (t.id.as_str(), t)).collect();
+
+    let pairs: Vec<_> = a_by_id
+        .iter()
+        .filter_map(|(id, a)| b_by_id.get(id).map(|b| (*a, *b)))
+        .collect();
+
+    Comparison {
+        rtol,
+        atol,
+        tensors: pairs
+            .par_iter()
+            .map(|(a, b)| compare_tensor(a, b, rtol, atol))
+            .collect(),
+        only_in_a: a_by_id
+            .keys()
+            .filter(|id| !b_by_id.contains_key(*id))
+            .map(|id| id.to_string())
+            .collect(),
+        only_in_b: b_by_id
+            .keys()
+            .filter(|id| !a_by_id.contains_key(*id))
+            .map(|id| id.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data::{ElementType, Encoding};
+
+    fn f32_tensor(id: &str, values: &[f32]) -> TensorData {
+        TensorData::owned(
+            id.to_string(),
+            Encoding::Plain(ElementType::F32),
+            values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_compare_tensor() {
+        let a = f32_tensor("t", &[1.0, 2.0, f32::NAN, f32::INFINITY]);
+        let b = f32_tensor("t", &[1.0, 2.5, f32::NAN, f32::INFINITY]);
+
+        let comparison = compare_tensor(&a, &b, 0.0, 0.0);
+        assert_eq!(comparison.max_abs_diff, 0.5);
+        assert_eq!(comparison.max_rel_diff, 0.2);
+        assert_eq!(comparison.mismatches, 1);
+        assert!(!comparison.is_match());
+
+        assert!(compare_tensor(&a, &b, 0.25, 0.0).is_match());
+        assert!(compare_tensor(&a, &b, 0.0, 0.5).is_match());
+
+        let c = f32_tensor("t", &[1.0, 2.0, 0.0, f32::INFINITY]);
+        assert_eq!(compare_tensor(&a, &c, 1.0, 1.0).mismatches, 1);
+
+        // infinite values only match the same infinite value
+        let d = f32_tensor("t", &[1.0, 2.0, f32::NAN, 1.0]);
+        assert_eq!(compare_tensor(&d, &a, 1.0, 1.0).mismatches, 1);
+    }
+
+    #[test]
+    fn test_compare_across_dtypes() {
+        // 1.0 and -2.0 as f16
+        let a = TensorData::owned(
+            "t".to_string(),
+            Encoding::Plain(ElementType::F16),
+            vec![0x00, 0x3c, 0x00, 0xc0],
+        );
+        let b = f32_tensor("t", &[1.0, -2.0]);
+        assert!(compare_tensor(&a, &b, 0.0, 0.0).is_match());
+
+        let c = f32_tensor("t", &[1.0]);
+        let comparison = compare_tensor(&a, &c, 1.0, 1.0);
+        assert_eq!(comparison.num_elements, (2, 1));
+        assert!(!comparison.is_match());
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = vec![f32_tensor("x", &[1.0]), f32_tensor("only_a", &[0.0])];
+        let b = vec![f32_tensor("only_b", &[0.0]), f32_tensor("x", &[1.5])];
+
+        let comparison = compare(&a, &b, 1e-5, 1e-8);
+        assert_eq!(comparison.tensors.len(), 1);
+        assert_eq!(comparison.tensors[0].id, "x");
+        assert_eq!(comparison.mismatching().count(), 1);
+        assert_eq!(comparison.only_in_a, vec!["only_a"]);
+        assert_eq!(comparison.only_in_b, vec!["only_b"]);
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+/// Compression of an archived model file, detected from its extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// Wraps the reader of the compressed content into a reader of the decompressed one.
+    pub(crate) fn decoder<'a>(self, reader: impl Read + 'a) -> anyhow::Result<Box<dyn Read + 'a>> {
+        let reader = BufReader::new(reader);
+        Ok(match self {
+            // concatenated members are decompressed like gzip does
+            Self::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(
+                ruzstd::decoding::StreamingDecoder::new(reader)
+                    .map_err(|e| anyhow::anyhow!("invalid zstd stream: {}", e))?,
+            ),
+            Self::Xz => Box::new(lzma_rust2::XzReader::new(reader, true)),
+        })
+    }
+}
+
+/// Returns the path of the file once decompressed, without the compression extension.
+pub(crate) fn decompressed_path(path: &Path) -> PathBuf {
+    match Compression::of(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Opens the file, decompressing its content on the fly if it is compressed.
+pub(crate) fn open(path: &Path) -> anyhow::Result<Box<dyn Read>> {
+    let file = std::fs::File::open(path)?;
+    match Compression::of(path) {
+        Some(compression) => compression.decoder(file),
+        None => Ok(Box::new(file)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        let content: Vec<u8> = (0..100_000u32).map(|value| (value % 251) as u8).collect();
+
+        let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::fast());
+        gzip.write_all(&content).unwrap();
+        let zstd = ruzstd::encoding::compress_to_vec(
+            content.as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        let mut xz = lzma_rust2::XzWriter::new(vec![], Default::default()).unwrap();
+        xz.write_all(&content).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        for (name, compressed) in [
+            ("model.safetensors.gz", gzip.finish().unwrap()),
+            ("model.safetensors.zst", zstd),
+            ("model.safetensors.XZ", xz.finish().unwrap()),
+            ("model.safetensors", content.clone()),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, compressed).unwrap();
+            assert_eq!(
+                decompressed_path(&path),
+                dir.path().join("model.safetensors")
+            );
+
+            let mut decompressed = vec![];
+            open(&path).unwrap().read_to_end(&mut decompressed).unwrap();
+            assert_eq!(decompressed, content, "{}", name);
+        }
+
+        let path = dir.path().join("model.gguf.zst");
+        std::fs::write(&path, b"not zstd").unwrap();
+        assert!(open(&path).is_err());
+    }
+}
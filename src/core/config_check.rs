@@ -0,0 +1,207 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::TensorDescriptor;
+
+// names of the token embeddings, by model family
+const EMBEDDING_SUFFIXES: &[&str] = &[
+    "embed_tokens.weight",
+    "wte.weight",
+    "word_embeddings.weight",
+    "tok_embeddings.weight",
+];
+
+/// A hyperparameter of config.json checked against the shape of the tensors.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ConfigCheck {
+    pub key: String,
+    pub expected: usize,
+    // the first tensor that doesn't match, or the first that does
+    pub tensor: Option<String>,
+    pub actual: Option<usize>,
+}
+
+impl ConfigCheck {
+    pub(crate) fn is_ok(&self) -> bool {
+        self.actual.is_none_or(|actual| actual == self.expected)
+    }
+}
+
+// the hyperparameter of the language model config, nested in multimodal configs
+fn hyperparameter(config: &Value, key: &str) -> Option<usize> {
+    config
+        .get("text_config")
+        .and_then(|text_config| text_config.get(key))
+        .or_else(|| config.get(key))
+        .and_then(Value::as_u64)
+        .map(|value| value as usize)
+}
+
+// compares the dimension of each tensor matching to the expected value
+fn check_dimension(
+    key: &str,
+    expected: Option<usize>,
+    tensors: &[TensorDescriptor],
+    matches: impl Fn(&str) -> bool,
+    dimension: usize,
+) -> Option<ConfigCheck> {
+    let expected = expected?;
+    let mut check = ConfigCheck {
+        key: key.to_string(),
+        expected,
+        tensor: None,
+        actual: None,
+    };
+    for tensor in tensors {
+        let name = tensor.id.as_deref().unwrap_or_default();
+        let Some(actual) = tensor.shape.get(dimension).filter(|_| matches(name)) else {
+            continue;
+        };
+        if check.actual.is_none() || *actual != expected {
+            check.tensor = Some(name.to_string());
+            check.actual = Some(*actual);
+        }
+        if *actual != expected {
+            break;
+        }
+    }
+    Some(check)
+}
+
+/// Checks the hidden size, number of layers, vocabulary size, attention heads and
+/// intermediate size of a Hugging Face config.json against the shapes of the safetensors
+/// weights, stored as [out, in] like PyTorch.
+pub(crate) fn check(config: &Value, tensors: &[TensorDescriptor]) -> Vec<ConfigCheck> {
+    let hidden_size = hyperparameter(config, "hidden_size");
+    let heads = hyperparameter(config, "num_attention_heads");
+    let head_dim = hyperparameter(config, "head_dim").or(hidden_size
+        .zip(heads)
+        .map(|(size, heads)| size / heads.max(1)));
+    let kv_heads = hyperparameter(config, "num_key_value_heads").or(heads);
+    let is_embedding = |name: &str| {
+        EMBEDDING_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+    };
+    // dense MLPs, the experts of MoE models have their own intermediate size
+    let is_mlp = |name: &str| {
+        !name.contains("expert")
+            && (name.ends_with("mlp.up_proj.weight") || name.ends_with("mlp.gate_proj.weight"))
+    };
+
+    let mut checks: Vec<ConfigCheck> = [
+        check_dimension(
+            "vocab_size",
+            hyperparameter(config, "vocab_size"),
+            tensors,
+            |name| is_embedding(name) || name.ends_with("lm_head.weight"),
+            0,
+        ),
+        check_dimension("hidden_size", hidden_size, tensors, is_embedding, 1),
+        check_dimension(
+            "num_attention_heads * head_dim",
+            heads.zip(head_dim).map(|(heads, dim)| heads * dim),
+            tensors,
+            |name| name.ends_with("q_proj.weight"),
+            0,
+        ),
+        check_dimension(
+            "num_key_value_heads * head_dim",
+            kv_heads.zip(head_dim).map(|(heads, dim)| heads * dim),
+            tensors,
+            |name| name.ends_with("k_proj.weight") || name.ends_with("v_proj.weight"),
+            0,
+        ),
+        check_dimension(
+            "intermediate_size",
+            hyperparameter(config, "intermediate_size"),
+            tensors,
+            is_mlp,
+            0,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if let Some(layers) = hyperparameter(config, "num_hidden_layers") {
+        let regex = Regex::new(r"(?:^|\.)(?:layers|h|blocks|layer)\.(\d+)\.").unwrap();
+        let indexes: BTreeSet<usize> = tensors
+            .iter()
+            .filter_map(|tensor| regex.captures(tensor.id.as_deref().unwrap_or_default()))
+            .filter_map(|captures| captures[1].parse().ok())
+            .collect();
+        checks.push(ConfigCheck {
+            key: "num_hidden_layers".to_string(),
+            expected: layers,
+            tensor: None,
+            actual: (!indexes.is_empty()).then_some(indexes.len()),
+        });
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let tensor = |id: &str, shape: Vec<usize>| TensorDescriptor {
+            id: Some(id.to_string()),
+            shape,
+            dtype: "BF16".to_string(),
+            size: 0,
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        let mut tensors = vec![tensor("model.embed_tokens.weight", vec![1000, 64])];
+        for layer in 0..2 {
+            let name = |suffix: &str| format!("model.layers.{}.{}", layer, suffix);
+            tensors.push(tensor(&name("self_attn.q_proj.weight"), vec![64, 64]));
+            tensors.push(tensor(&name("self_attn.k_proj.weight"), vec![32, 64]));
+            tensors.push(tensor(&name("self_attn.v_proj.weight"), vec![32, 64]));
+            tensors.push(tensor(&name("mlp.up_proj.weight"), vec![256, 64]));
+        }
+        tensors.push(tensor("lm_head.weight", vec![1000, 64]));
+
+        let config = serde_json::json!({
+            "hidden_size": 64,
+            "num_attention_heads": 4,
+            "num_key_value_heads": 2,
+            "num_hidden_layers": 2,
+            "vocab_size": 1000,
+            "intermediate_size": 256
+        });
+        let checks = check(&config, &tensors);
+        assert_eq!(checks.len(), 6);
+        assert!(checks.iter().all(ConfigCheck::is_ok), "{:?}", checks);
+
+        // config of another model size
+        let config = serde_json::json!({
+            "text_config": {"hidden_size": 64, "num_attention_heads": 4, "num_hidden_layers": 4},
+            "vocab_size": 1000
+        });
+        let failed: Vec<(String, Option<String>, Option<usize>)> = check(&config, &tensors)
+            .into_iter()
+            .filter(|check| !check.is_ok())
+            .map(|check| (check.key, check.tensor, check.actual))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                (
+                    "num_key_value_heads * head_dim".to_string(),
+                    Some("model.layers.0.self_attn.k_proj.weight".to_string()),
+                    Some(32)
+                ),
+                ("num_hidden_layers".to_string(), None, Some(2)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::{ops::Range, sync::Arc};
+
+// number of values in a Q4_0 and Q8_0 block
+const QK: usize = 32;
+
+/// Plain (non quantized) element types we can decode values of.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElementType {
+    F64,
+    F32,
+    F16,
+    BF16,
+    I64,
+    I32,
+    I16,
+    I8,
+    U64,
+    U32,
+    U16,
+    U8,
+    Bool,
+}
+
+impl ElementType {
+    pub fn size(&self) -> usize {
+        match self {
+            ElementType::F64 | ElementType::I64 | ElementType::U64 => 8,
+            ElementType::F32 | ElementType::I32 | ElementType::U32 => 4,
+            ElementType::F16 | ElementType::BF16 | ElementType::I16 | ElementType::U16 => 2,
+            ElementType::I8 | ElementType::U8 | ElementType::Bool => 1,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            ElementType::F64 | ElementType::F32 | ElementType::F16 | ElementType::BF16
+        )
+    }
+
+    /// Reads a single little endian value, `bytes` must be exactly `size()` long.
+    pub fn read(&self, bytes: &[u8]) -> f64 {
+        match self {
+            ElementType::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+            ElementType::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::F16 => f16_to_f32(u16::from_le_bytes(bytes.try_into().unwrap())) as f64,
+            ElementType::BF16 => bf16_to_f32(u16::from_le_bytes(bytes.try_into().unwrap())) as f64,
+            ElementType::I64 => i64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::I32 => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::I16 => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::I8 => bytes[0] as i8 as f64,
+            ElementType::U64 => u64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::U32 => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::U16 => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::U8 | ElementType::Bool => bytes[0] as f64,
+        }
+    }
+}
+
+/// How the values of a tensor are laid out in its buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Plain(ElementType),
+    /// GGML blocks of a f16 scale followed by 32 signed 8 bits values.
+    Q8_0,
+    /// GGML blocks of a f16 scale followed by 32 packed unsigned 4 bits values, offset by 8.
+    Q4_0,
+}
+
+impl Encoding {
+    /// Returns the number of bytes needed to store the given number of elements.
+    pub fn data_size(&self, elements: usize) -> usize {
+        match self {
+            Encoding::Plain(element_type) => elements * element_type.size(),
+            Encoding::Q8_0 => elements.div_ceil(QK) * (2 + QK),
+            Encoding::Q4_0 => elements.div_ceil(QK) * (2 + QK / 2),
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        match self {
+            Encoding::Plain(element_type) => element_type.is_float(),
+            Encoding::Q8_0 | Encoding::Q4_0 => true,
+        }
+    }
+
+    /// Returns the offset, relative to the tensor data, of the element or of the block containing it.
+    pub fn element_offset(&self, index: usize) -> usize {
+        match self {
+            Encoding::Plain(element_type) => index * element_type.size(),
+            Encoding::Q8_0 => (index / QK) * (2 + QK),
+            Encoding::Q4_0 => (index / QK) * (2 + QK / 2),
+        }
+    }
+}
+
+/// Raw data of a single tensor, either mapped from the model file or decoded in memory.
+#[derive(Clone)]
+pub struct TensorData {
+    pub id: String,
+    pub encoding: Encoding,
+    /// Offset of the data within the file storing it, if stored contiguously.
+    pub file_offset: Option<u64>,
+    buffer: Arc<dyn AsRef<[u8]> + Send + Sync>,
+    range: Range<usize>,
+}
+
+impl std::fmt::Debug for TensorData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TensorData")
+            .field("id", &self.id)
+            .field("encoding", &self.encoding)
+            .field("file_offset", &self.file_offset)
+            .field("range", &self.range)
+            .finish()
+    }
+}
+
+impl TensorData {
+    /// Creates a tensor referencing a region of a shared buffer, fails if out of bounds.
+    pub fn new(
+        id: String,
+        encoding: Encoding,
+        buffer: Arc<dyn AsRef<[u8]> + Send + Sync>,
+        range: Range<usize>,
+        file_offset: Option<u64>,
+    ) -> anyhow::Result<Self> {
+        if range.start > range.end || range.end > (*buffer).as_ref().len() {
+            anyhow::bail!(
+                "data of tensor {} is out of bounds ({}..{})",
+                id,
+                range.start,
+                range.end
+            );
+        }
+        Ok(Self {
+            id,
+            encoding,
+            file_offset,
+            buffer,
+            range,
+        })
+    }
+
+    /// Creates a tensor owning its data.
+    pub fn owned(id: String, encoding: Encoding, data: Vec<u8>) -> Self {
+        let range = 0..data.len();
+        Self {
+            id,
+            encoding,
+            file_offset: None,
+            buffer: Arc::new(data),
+            range,
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &(*self.buffer).as_ref()[self.range.clone()]
+    }
+
+    pub fn values(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        let bytes = self.bytes();
+        match self.encoding {
+            Encoding::Plain(element_type) => Box::new(
+                bytes
+                    .chunks_exact(element_type.size())
+                    .map(move |chunk| element_type.read(chunk)),
+            ),
+            Encoding::Q8_0 => Box::new(dequantize_q8_0(bytes)),
+            Encoding::Q4_0 => Box::new(dequantize_q4_0(bytes)),
+        }
+    }
+
+    /// Returns the offset within the file of the element, or of the block containing it.
+    pub fn element_file_offset(&self, index: usize) -> Option<u64> {
+        self.file_offset
+            .map(|offset| offset + self.encoding.element_offset(index) as u64)
+    }
+}
+
+fn dequantize_q8_0(data: &[u8]) -> impl Iterator<Item = f64> + '_ {
+    data.chunks_exact(2 + QK).flat_map(|block| {
+        let scale = f16_to_f32(u16::from_le_bytes([block[0], block[1]])) as f64;
+        block[2..].iter().map(move |q| *q as i8 as f64 * scale)
+    })
+}
+
+fn dequantize_q4_0(data: &[u8]) -> impl Iterator<Item = f64> + '_ {
+    data.chunks_exact(2 + QK / 2).flat_map(|block| {
+        let scale = f16_to_f32(u16::from_le_bytes([block[0], block[1]])) as f64;
+        let quants = &block[2..];
+        // low nibbles hold the first half of the block, high nibbles the second
+        quants
+            .iter()
+            .map(|q| (q & 0x0f) as i32)
+            .chain(quants.iter().map(|q| (q >> 4) as i32))
+            .map(move |q| (q - 8) as f64 * scale)
+    })
+}
+
+/// Converts an IEEE 754 half precision value to single precision.
+pub(crate) fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) as u32) << 31;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // subnormal, normalize it
+            let shift = mantissa.leading_zeros() - 21;
+            let mantissa = (mantissa << shift) & 0x3ff;
+            sign | ((113 - shift) << 23) | (mantissa << 13)
+        }
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+/// Converts a bfloat16 value to single precision.
+pub(crate) fn bf16_to_f32(bf16: u16) -> f32 {
+    f32::from_bits((bf16 as u32) << 16)
+}
+
+/// Converts a single precision value to half precision, rounding to the nearest even value.
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // infinity, or a quiet NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    let (mantissa, shift) = if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // subnormal, with the implicit leading bit
+        (mantissa | 0x80_0000, (14 - exponent) as u32)
+    } else {
+        (mantissa, 13)
+    };
+    let half = ((exponent.max(0) as u32) << 10) | (mantissa >> shift);
+    // the bits shifted out of the mantissa decide the rounding
+    let remainder = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round_up = remainder > halfway || (remainder == halfway && half & 1 == 1);
+    // a carry into the exponent is the next representable value, or infinity
+    sign | (half + round_up as u32) as u16
+}
+
+/// Converts a single precision value to bfloat16, rounding to the nearest even value.
+pub(crate) fn f32_to_bf16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    if value.is_nan() {
+        return ((bits >> 16) | 0x40) as u16;
+    }
+    let round = 0x7fff + ((bits >> 16) & 1);
+    ((bits + round) >> 16) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_values() {
+        let data: Vec<u8> = [-1.0f32, 1.0, 2.5]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let tensor = TensorData::owned("t".to_string(), Encoding::Plain(ElementType::F32), data);
+
+        assert_eq!(tensor.values().collect::<Vec<_>>(), vec![-1.0, 1.0, 2.5]);
+        assert_eq!(tensor.element_file_offset(1), None);
+    }
+
+    #[test]
+    fn test_mapped_region() {
+        let buffer: Arc<dyn AsRef<[u8]> + Send + Sync> = Arc::new(vec![0u8, 1, 2, 3, 4, 5]);
+
+        let tensor = TensorData::new(
+            "t".to_string(),
+            Encoding::Plain(ElementType::U16),
+            buffer.clone(),
+            2..6,
+            Some(102),
+        )
+        .unwrap();
+        assert_eq!(tensor.bytes(), &[2, 3, 4, 5]);
+        assert_eq!(tensor.values().count(), 2);
+        assert_eq!(tensor.element_file_offset(1), Some(104));
+
+        assert!(TensorData::new(
+            "t".to_string(),
+            Encoding::Plain(ElementType::U8),
+            buffer,
+            4..10,
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_dequantize_q8_0() {
+        // scale 0.5 and values 0..32
+        let mut block = 0x3800u16.to_le_bytes().to_vec();
+        block.extend((0..32).map(|i| i as u8));
+
+        let tensor = TensorData::owned("t".to_string(), Encoding::Q8_0, block);
+        let values: Vec<f64> = tensor.values().collect();
+        assert_eq!(values.len(), 32);
+        assert_eq!(values[0], 0.0);
+        assert_eq!(values[31], 15.5);
+        assert_eq!(Encoding::Q8_0.data_size(64), 68);
+        assert_eq!(Encoding::Q8_0.element_offset(33), 34);
+    }
+
+    #[test]
+    fn test_dequantize_q4_0() {
+        let mut block = 0x3c00u16.to_le_bytes().to_vec();
+        // low nibble 8 (0), high nibble 15 (7)
+        block.extend(std::iter::repeat_n(0xf8u8, 16));
+
+        let tensor = TensorData::owned("t".to_string(), Encoding::Q4_0, block);
+        let values: Vec<f64> = tensor.values().collect();
+        assert_eq!(values.len(), 32);
+        assert!(values[..16].iter().all(|v| *v == 0.0));
+        assert!(values[16..].iter().all(|v| *v == 7.0));
+    }
+
+    #[test]
+    fn test_half_conversions() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 5.960_464_5e-8);
+        assert!(f16_to_f32(0x7c00).is_infinite());
+        assert!(f16_to_f32(0x7e00).is_nan());
+
+        assert_eq!(bf16_to_f32(0x3f80), 1.0);
+        assert_eq!(bf16_to_f32(0xc040), -3.0);
+
+        for half in [
+            0x3c00, 0xc000, 0x7bff, 0x0001, 0x03ff, 0x0400, 0x7c00, 0xfc00, 0x3555,
+        ] {
+            assert_eq!(f32_to_f16(f16_to_f32(half)), half);
+        }
+        assert_eq!(f32_to_f16(65520.0), 0x7c00);
+        assert_eq!(f32_to_f16(1e-9), 0);
+        // halfway between 1 and the next half value, rounded to the even one
+        assert_eq!(f32_to_f16(1.0 + 1.0 / 2048.0), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 / 2048.0), 0x3c02);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+
+        assert_eq!(f32_to_bf16(1.0), 0x3f80);
+        assert_eq!(f32_to_bf16(-3.0), 0xc040);
+        assert_eq!(f32_to_bf16(f32::from_bits(0x3f80_8000)), 0x3f80);
+        assert_eq!(f32_to_bf16(f32::from_bits(0x3f81_8000)), 0x3f82);
+        assert!(bf16_to_f32(f32_to_bf16(f32::NAN)).is_nan());
+    }
+}
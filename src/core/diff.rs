@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Inspection, Shape, TensorDescriptor};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TensorRename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TensorChange {
+    pub id: String,
+    pub old_shape: Shape,
+    pub new_shape: Shape,
+    pub old_dtype: String,
+    pub new_dtype: String,
+    pub old_size: usize,
+    pub new_size: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct MetadataChange {
+    pub key: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub(crate) struct Diff {
+    pub file_size_delta: i64,
+    pub data_size_delta: i64,
+    pub num_tensors_delta: i64,
+    pub version_change: Option<(String, String)>,
+    pub file_type_change: Option<(String, String)>,
+    pub added: Vec<TensorDescriptor>,
+    pub removed: Vec<TensorDescriptor>,
+    pub renamed: Vec<TensorRename>,
+    pub changed: Vec<TensorChange>,
+    pub metadata: Vec<MetadataChange>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.file_size_delta == 0
+            && self.data_size_delta == 0
+            && self.num_tensors_delta == 0
+            && self.version_change.is_none()
+            && self.file_type_change.is_none()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+            && self.metadata.is_empty()
+    }
+}
+
+fn tensors_by_id(inspection: &Inspection) -> BTreeMap<String, &TensorDescriptor> {
+    inspection
+        .tensors
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        // tensors without a name are matched by position
+        .map(|(idx, t)| (t.id.clone().unwrap_or_else(|| format!("#{}", idx)), t))
+        .collect()
+}
+
+/// Used to match renamed tensors, only unambiguous matches are considered renames.
+fn signature_of(tensor: &TensorDescriptor) -> (Shape, String, usize) {
+    (tensor.shape.clone(), tensor.dtype.clone(), tensor.size)
+}
+
+fn signature_counts<'a>(
+    tensors: impl Iterator<Item = &'a TensorDescriptor>,
+) -> HashMap<(Shape, String, usize), usize> {
+    let mut counts = HashMap::new();
+    for tensor in tensors {
+        *counts.entry(signature_of(tensor)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Compares two inspections created with full detail level.
+pub(crate) fn diff(old: &Inspection, new: &Inspection) -> Diff {
+    let mut diff = Diff {
+        file_size_delta: new.file_size as i64 - old.file_size as i64,
+        data_size_delta: new.data_size as i64 - old.data_size as i64,
+        num_tensors_delta: new.num_tensors as i64 - old.num_tensors as i64,
+        ..Default::default()
+    };
+
+    if old.version != new.version {
+        diff.version_change = Some((old.version.clone(), new.version.clone()));
+    }
+    if old.file_type.to_string() != new.file_type.to_string() {
+        diff.file_type_change = Some((old.file_type.to_string(), new.file_type.to_string()));
+    }
+
+    let old_tensors = tensors_by_id(old);
+    let new_tensors = tensors_by_id(new);
+
+    let mut removed = vec![];
+    for (id, old_tensor) in &old_tensors {
+        match new_tensors.get(id) {
+            Some(new_tensor) => {
+                if old_tensor.shape != new_tensor.shape
+                    || old_tensor.dtype != new_tensor.dtype
+                    || old_tensor.size != new_tensor.size
+                {
+                    diff.changed.push(TensorChange {
+                        id: id.clone(),
+                        old_shape: old_tensor.shape.clone(),
+                        new_shape: new_tensor.shape.clone(),
+                        old_dtype: old_tensor.dtype.clone(),
+                        new_dtype: new_tensor.dtype.clone(),
+                        old_size: old_tensor.size,
+                        new_size: new_tensor.size,
+                    });
+                }
+            }
+            None => removed.push((id, *old_tensor)),
+        }
+    }
+
+    let added: Vec<_> = new_tensors
+        .iter()
+        .filter(|(id, _)| !old_tensors.contains_key(*id))
+        .map(|(id, t)| (id, *t))
+        .collect();
+
+    let removed_counts = signature_counts(removed.iter().map(|(_, t)| *t));
+    let added_counts = signature_counts(added.iter().map(|(_, t)| *t));
+    let is_unique = |tensor: &TensorDescriptor| {
+        let signature = signature_of(tensor);
+        removed_counts.get(&signature) == Some(&1) && added_counts.get(&signature) == Some(&1)
+    };
+
+    for (id, tensor) in &removed {
+        let renamed_to = if is_unique(tensor) {
+            added
+                .iter()
+                .find(|(_, t)| signature_of(t) == signature_of(tensor))
+        } else {
+            None
+        };
+
+        match renamed_to {
+            Some((new_id, _)) => diff.renamed.push(TensorRename {
+                from: id.to_string(),
+                to: new_id.to_string(),
+            }),
+            None => diff.removed.push((*tensor).clone()),
+        }
+    }
+
+    for (id, tensor) in &added {
+        if !diff.renamed.iter().any(|r| &r.to == *id) {
+            diff.added.push((*tensor).clone());
+        }
+    }
+
+    for (key, old_value) in &old.metadata {
+        match new.metadata.get(key) {
+            Some(new_value) if new_value == old_value => {}
+            new_value => diff.metadata.push(MetadataChange {
+                key: key.clone(),
+                old_value: Some(old_value.clone()),
+                new_value: new_value.cloned(),
+            }),
+        }
+    }
+    for (key, new_value) in &new.metadata {
+        if !old.metadata.contains_key(key) {
+            diff.metadata.push(MetadataChange {
+                key: key.clone(),
+                old_value: None,
+                new_value: Some(new_value.clone()),
+            });
+        }
+    }
+    diff.metadata.sort_by(|a, b| a.key.cmp(&b.key));
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Metadata;
+
+    fn tensor(id: &str, shape: Vec<usize>, dtype: &str) -> TensorDescriptor {
+        TensorDescriptor {
+            id: Some(id.to_string()),
+            size: shape.iter().product::<usize>() * 4,
+            shape,
+            dtype: dtype.to_string(),
+            metadata: Metadata::new(),
+            stats: None,
+            preview: None,
+        }
+    }
+
+    fn inspection(tensors: Vec<TensorDescriptor>, metadata: &[(&str, &str)]) -> Inspection {
+        Inspection {
+            num_tensors: tensors.len(),
+            data_size: tensors.iter().map(|t| t.size).sum(),
+            file_size: 1000,
+            tensors: Some(tensors),
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_identical() {
+        let a = inspection(vec![tensor("a", vec![2, 2], "F32")], &[("k", "v")]);
+        let diff = diff(&a, &a.clone());
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_tensor_changes() {
+        let old = inspection(
+            vec![
+                tensor("embed", vec![10, 4], "F32"),
+                tensor("lm_head", vec![4, 10], "F32"),
+                tensor("norm", vec![4], "F32"),
+                tensor("dropped", vec![3, 3], "F32"),
+            ],
+            &[],
+        );
+        let new = inspection(
+            vec![
+                tensor("embed", vec![12, 4], "F32"),
+                tensor("output", vec![4, 10], "F32"),
+                tensor("norm", vec![4], "F16"),
+                tensor("lora_a", vec![4, 2], "F32"),
+            ],
+            &[],
+        );
+
+        let diff = diff(&old, &new);
+
+        assert_eq!(diff.renamed.len(), 1);
+        assert_eq!(diff.renamed[0].from, "lm_head");
+        assert_eq!(diff.renamed[0].to, "output");
+
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(diff.changed[0].id, "embed");
+        assert_eq!(diff.changed[0].new_shape, vec![12, 4]);
+        assert_eq!(diff.changed[1].id, "norm");
+        assert_eq!(diff.changed[1].new_dtype, "F16");
+
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].id.as_deref(), Some("dropped"));
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id.as_deref(), Some("lora_a"));
+
+        assert_eq!(diff.num_tensors_delta, 0);
+        assert_eq!(diff.data_size_delta, 32 - 36 + 32);
+    }
+
+    #[test]
+    fn test_ambiguous_renames() {
+        // two removed tensors with the same signature can't be matched to a single added one
+        let old = inspection(
+            vec![tensor("a", vec![2], "F32"), tensor("b", vec![2], "F32")],
+            &[],
+        );
+        let new = inspection(vec![tensor("c", vec![2], "F32")], &[]);
+
+        let diff = diff(&old, &new);
+        assert!(diff.renamed.is_empty());
+        assert_eq!(diff.removed.len(), 2);
+        assert_eq!(diff.added.len(), 1);
+    }
+
+    #[test]
+    fn test_metadata_changes() {
+        let old = inspection(vec![], &[("kept", "1"), ("changed", "a"), ("removed", "x")]);
+        let new = inspection(vec![], &[("kept", "1"), ("changed", "b"), ("added", "y")]);
+
+        let diff = diff(&old, &new);
+        let keys: Vec<_> = diff.metadata.iter().map(|m| m.key.as_str()).collect();
+        assert_eq!(keys, vec!["added", "changed", "removed"]);
+
+        assert_eq!(diff.metadata[0].old_value, None);
+        assert_eq!(diff.metadata[1].new_value.as_deref(), Some("b"));
+        assert_eq!(diff.metadata[2].new_value, None);
+    }
+}
@@ -1,8 +1,9 @@
 use std::path::Path;
 
 use blake2::{Blake2b512, Digest};
+use serde::de::DeserializeOwned;
 
-use crate::{cli::DetailLevel, core::Inspection};
+use crate::core::{progress::Spinner, DetailLevel, Inspection};
 
 pub(crate) struct Inspector {
     image_id: String,
@@ -30,7 +31,7 @@ impl Inspector {
 
     fn build_if_needed(&self) -> anyhow::Result<()> {
         if !super::image_exists(&self.image_id) {
-            println!("building image '{}'", &self.image_id);
+            tracing::info!("building image '{}'", &self.image_id);
 
             // extract the image assets in a temporary directory
             let tmp_dir = tempfile::tempdir()?;
@@ -42,7 +43,8 @@ impl Inspector {
             std::fs::write(base_path.join("script_main.py"), &self.script)?;
             std::fs::write(base_path.join("requirements.txt"), &self.requirements)?;
 
-            // build the image
+            // build the image, this can take a few minutes the first time
+            let _spinner = Spinner::new("building image");
             super::build_image(&self.image_id, &dockerfile_path.display().to_string())?;
         }
         Ok(())
@@ -55,6 +57,26 @@ impl Inspector {
         detail: DetailLevel,
         filter: Option<String>,
     ) -> anyhow::Result<Inspection> {
+        let mut args = vec![];
+        if let Some(filter) = filter {
+            args.push(format!("--filter={filter}"));
+        }
+
+        if matches!(detail, DetailLevel::Full) {
+            args.push("--detailed".to_string());
+        }
+
+        self.run_script(file_path, additional_files, args, "inspecting in container")
+    }
+
+    /// Runs the script on the file with the arguments, parsing the JSON it prints.
+    pub fn run_script<T: DeserializeOwned>(
+        &self,
+        file_path: &Path,
+        additional_files: Vec<String>,
+        script_args: Vec<String>,
+        message: &str,
+    ) -> anyhow::Result<T> {
         if !super::docker_exists() {
             anyhow::bail!("docker is not installed or not running");
         }
@@ -65,13 +87,7 @@ impl Inspector {
         let file_name = file_path.file_name().unwrap().to_str().unwrap();
 
         let mut args = vec![format!("/{}", &file_name)];
-        if let Some(filter) = filter {
-            args.push(format!("--filter={filter}"));
-        }
-
-        if matches!(detail, DetailLevel::Full) {
-            args.push("--detailed".to_string());
-        }
+        args.extend(script_args);
 
         let mut volumes = vec![(file_path.display().to_string(), format!("/{}", &file_name))];
         for additional_file in additional_files {
@@ -84,13 +100,15 @@ impl Inspector {
             }
         }
 
-        let (stdout, stderr) = super::run(&self.image_id, args, volumes)?;
+        let (stdout, stderr) = {
+            let _spinner = Spinner::new(message);
+            super::run(&self.image_id, args, volumes)?
+        };
 
         if !stderr.is_empty() {
             anyhow::bail!("docker container error: {}", stderr);
         }
 
-        let inspection: Inspection = serde_json::from_str(&stdout)?;
-        Ok(inspection)
+        Ok(serde_json::from_str(&stdout)?)
     }
 }
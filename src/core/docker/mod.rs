@@ -9,6 +9,7 @@ mod inspection;
 pub(crate) use inspection::*;
 
 fn run_command(command: &str, args: &[&str]) -> anyhow::Result<(String, String)> {
+    tracing::debug!("running `{} {}`", command, args.join(" "));
     let output = Command::new(command).args(args).output()?;
 
     let stdout = str::from_utf8(&output.stdout)?.to_string();
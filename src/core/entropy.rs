@@ -0,0 +1,178 @@
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::data::{Encoding, TensorData};
+
+// size of the sliding window, moving by half of it
+const WINDOW_SIZE: usize = 64 * 1024;
+// fewer samples underestimate the entropy of random data
+const MIN_SAMPLES: usize = 4096;
+// bits per byte of the most significant bytes of floating point weights, holding the sign and the
+// exponent: a few exponents are much more likely than the others, unlike in encrypted data
+const MAX_EXPONENT_ENTROPY: f64 = 7.5;
+// bits per byte of integer and quantized weights, compressed and encrypted data are close to 8
+const MAX_BYTE_ENTROPY: f64 = 7.9;
+
+/// A region of a tensor whose entropy is inconsistent with weight data.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct HighEntropy {
+    pub id: String,
+    /// Offset of the region relative to the tensor data.
+    pub start: usize,
+    pub size: usize,
+    /// Highest entropy of the windows of the region, in bits per byte.
+    pub entropy: f64,
+    /// File offset of the region, if the tensor is stored contiguously.
+    pub file_offset: Option<u64>,
+}
+
+/// Shannon entropy of the bytes, in bits per byte.
+pub(crate) fn entropy(bytes: impl Iterator<Item = u8>) -> f64 {
+    let mut counts = [0usize; 256];
+    let mut total = 0;
+    for byte in bytes {
+        counts[byte as usize] += 1;
+        total += 1;
+    }
+
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// entropy of the window and whether it's too high for the encoding of the tensor
+fn check_window(window: &[u8], encoding: Encoding) -> Option<f64> {
+    match encoding {
+        Encoding::Plain(element_type) if element_type.is_float() => {
+            let size = element_type.size();
+            if window.len() / size < MIN_SAMPLES {
+                return None;
+            }
+            // little endian, the sign and exponent are in the last byte
+            let entropy = entropy(window.chunks_exact(size).map(|value| value[size - 1]));
+            (entropy > MAX_EXPONENT_ENTROPY).then_some(entropy)
+        }
+        _ => {
+            if window.len() < MIN_SAMPLES {
+                return None;
+            }
+            let entropy = entropy(window.iter().copied());
+            (entropy > MAX_BYTE_ENTROPY).then_some(entropy)
+        }
+    }
+}
+
+fn scan_tensor(tensor: &TensorData) -> Vec<HighEntropy> {
+    let bytes = tensor.bytes();
+    let mut regions: Vec<HighEntropy> = vec![];
+
+    let mut start = 0;
+    loop {
+        let end = (start + WINDOW_SIZE).min(bytes.len());
+        if let Some(entropy) = check_window(&bytes[start..end], tensor.encoding) {
+            match regions.last_mut() {
+                // overlapping windows extend the region
+                Some(region) if region.start + region.size >= start => {
+                    region.size = end - region.start;
+                    region.entropy = region.entropy.max(entropy);
+                }
+                _ => regions.push(HighEntropy {
+                    id: tensor.id.clone(),
+                    start,
+                    size: end - start,
+                    entropy,
+                    file_offset: tensor.file_offset.map(|offset| offset + start as u64),
+                }),
+            }
+        }
+
+        if end == bytes.len() {
+            break;
+        }
+        start += WINDOW_SIZE / 2;
+    }
+
+    regions
+}
+
+/// Scans the data of the tensors in parallel with a sliding window and returns the regions whose
+/// entropy is too high for weights, as embedded encrypted or compressed archives have.
+pub(crate) fn find_high_entropy(tensors: &[TensorData]) -> Vec<HighEntropy> {
+    let mut found: Vec<HighEntropy> = tensors.par_iter().flat_map(scan_tensor).collect();
+
+    found.sort_by(|a, b| a.id.cmp(&b.id).then(a.start.cmp(&b.start)));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data::ElementType;
+
+    // deterministic pseudo random bytes
+    fn random_bytes(size: usize) -> Vec<u8> {
+        let mut state = 0x2545f4914f6cdd1du64;
+        (0..size)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    // weights spread like the ones of a trained layer
+    fn weights(count: usize) -> Vec<f32> {
+        (0..count)
+            .map(|i| ((i as f32 * 0.7).sin() * (i as f32 * 1.3).cos()) * 0.05)
+            .collect()
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy([7u8; 100].into_iter()), 0.0);
+        assert_eq!(entropy([0u8, 1, 2, 3].into_iter()), 2.0);
+        assert!(entropy(random_bytes(1 << 20).into_iter()) > 7.99);
+    }
+
+    #[test]
+    fn test_find_high_entropy() {
+        // a payload in the middle of float weights
+        let mut data: Vec<u8> = weights(128 * 1024)
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        data[200_000..300_000].copy_from_slice(&random_bytes(100_000));
+        let float = TensorData::owned("float".to_string(), Encoding::Plain(ElementType::F32), data);
+        // quantized weights and random bytes
+        let quantized = TensorData::owned(
+            "quantized".to_string(),
+            Encoding::Plain(ElementType::I8),
+            weights(256 * 1024)
+                .iter()
+                .map(|value| (value * 2000.0) as i8 as u8)
+                .collect(),
+        );
+        let archive = TensorData::owned(
+            "archive".to_string(),
+            Encoding::Plain(ElementType::U8),
+            random_bytes(WINDOW_SIZE),
+        );
+
+        let found = find_high_entropy(&[float, quantized, archive]);
+        let regions: Vec<_> = found
+            .iter()
+            .map(|region| (region.id.as_str(), region.start, region.size))
+            .collect();
+        assert_eq!(
+            regions,
+            vec![("archive", 0, WINDOW_SIZE), ("float", 196608, 98304)]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{Inspection, Metadata};
+
+/// Element type of the KV cache.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ValueEnum)]
+pub(crate) enum KvCacheType {
+    F32,
+    F16,
+    #[value(name = "q8_0")]
+    Q8_0,
+    #[value(name = "q4_0")]
+    Q4_0,
+}
+
+impl KvCacheType {
+    // bits per element, block scales included
+    fn bits(&self) -> f64 {
+        match self {
+            KvCacheType::F32 => 32.0,
+            KvCacheType::F16 => 16.0,
+            KvCacheType::Q8_0 => 8.5,
+            KvCacheType::Q4_0 => 4.5,
+        }
+    }
+}
+
+/// Attention parameters of a transformer the KV cache size depends on.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Attention {
+    pub layers: usize,
+    pub kv_heads: usize,
+    // size of the keys and values of each head
+    pub key_length: usize,
+    pub value_length: usize,
+    // context length the model has been trained with, if known
+    pub context_length: Option<usize>,
+}
+
+// the largest of the values of an integer or of the first values of an array, as GGUF
+// metadata is formatted
+fn integer(value: &str) -> Option<usize> {
+    value
+        .split(',')
+        .filter_map(|value| value.trim().parse::<usize>().ok())
+        .max()
+}
+
+impl Attention {
+    /// Reads the attention parameters from the GGUF metadata of the model.
+    pub(crate) fn from_gguf(metadata: &Metadata) -> Option<Self> {
+        let arch = metadata.get("general.architecture")?;
+        let get = |key: &str| {
+            metadata
+                .get(&format!("{}.{}", arch, key))
+                .and_then(|value| integer(value))
+        };
+
+        let heads = get("attention.head_count")?;
+        let head_size = get("embedding_length").map(|size| size / heads.max(1));
+        Some(Self {
+            layers: get("block_count")?,
+            kv_heads: get("attention.head_count_kv").unwrap_or(heads),
+            key_length: get("attention.key_length").or(head_size)?,
+            value_length: get("attention.value_length").or(head_size)?,
+            context_length: get("context_length"),
+        })
+    }
+
+    /// Reads the attention parameters from the Hugging Face config.json of the model.
+    pub(crate) fn from_config(config: &Value) -> Option<Self> {
+        // multimodal models nest the one of the language model
+        let config = config.get("text_config").unwrap_or(config);
+        let get = |key: &str| config.get(key).and_then(Value::as_u64).map(|v| v as usize);
+
+        let heads = get("num_attention_heads")?;
+        let head_size = get("head_dim").or(get("hidden_size").map(|size| size / heads.max(1)))?;
+        Some(Self {
+            layers: get("num_hidden_layers")?,
+            kv_heads: get("num_key_value_heads").unwrap_or(heads),
+            key_length: head_size,
+            value_length: head_size,
+            context_length: get("max_position_embeddings"),
+        })
+    }
+
+    /// Bytes of the keys and values of every layer for the tokens of each sequence.
+    pub(crate) fn kv_cache_size(&self, tokens: usize, sequences: usize, cache: KvCacheType) -> u64 {
+        let elements = self.layers
+            * self.kv_heads
+            * (self.key_length + self.value_length)
+            * tokens
+            * sequences;
+        (elements as f64 * cache.bits() / 8.0).ceil() as u64
+    }
+}
+
+/// Parameters and bytes of the weights of a data type.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct DtypeWeights {
+    pub dtype: String,
+    pub tensors: usize,
+    pub parameters: usize,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct KvCache {
+    pub attention: Attention,
+    pub context: usize,
+    pub batch: usize,
+    pub cache_type: KvCacheType,
+    pub size: u64,
+}
+
+/// Memory needed to load a model and run it, runtime buffers excluded.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Estimate {
+    pub weights: Vec<DtypeWeights>,
+    pub weights_size: u64,
+    pub kv_cache: Option<KvCache>,
+    pub total: u64,
+}
+
+/// Sums the weights of the full detail inspections by data type, largest first.
+pub(crate) fn weights_by_dtype(inspections: &[Inspection]) -> Vec<DtypeWeights> {
+    let mut weights: BTreeMap<&str, DtypeWeights> = BTreeMap::new();
+    for tensor in inspections
+        .iter()
+        .flat_map(|inspection| inspection.tensors.iter().flatten())
+    {
+        let entry = weights
+            .entry(tensor.dtype.as_str())
+            .or_insert_with(|| DtypeWeights {
+                dtype: tensor.dtype.clone(),
+                tensors: 0,
+                parameters: 0,
+                size: 0,
+            });
+        entry.tensors += 1;
+        entry.parameters += tensor.shape.iter().product::<usize>();
+        entry.size += tensor.size as u64;
+    }
+
+    let mut weights: Vec<DtypeWeights> = weights.into_values().collect();
+    weights.sort_by_key(|weights| std::cmp::Reverse(weights.size));
+    weights
+}
+
+impl Estimate {
+    pub(crate) fn new(weights: Vec<DtypeWeights>, kv_cache: Option<KvCache>) -> Self {
+        let weights_size = weights.iter().map(|weights| weights.size).sum();
+        let total = weights_size + kv_cache.as_ref().map(|cache| cache.size).unwrap_or(0);
+        Self {
+            weights,
+            weights_size,
+            kv_cache,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attention() {
+        // Llama 3.1 8B
+        let metadata: Metadata = [
+            ("general.architecture", "llama"),
+            ("llama.block_count", "32"),
+            ("llama.attention.head_count", "32"),
+            ("llama.attention.head_count_kv", "8"),
+            ("llama.embedding_length", "4096"),
+            ("llama.context_length", "131072"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let attention = Attention::from_gguf(&metadata).unwrap();
+        assert_eq!(
+            attention,
+            Attention {
+                layers: 32,
+                kv_heads: 8,
+                key_length: 128,
+                value_length: 128,
+                context_length: Some(131072),
+            }
+        );
+        assert_eq!(
+            attention.kv_cache_size(8192, 1, KvCacheType::F16),
+            1024 * 1024 * 1024
+        );
+        assert_eq!(
+            attention.kv_cache_size(8192, 2, KvCacheType::Q8_0),
+            1024 * 1024 * 1024 * 17 / 16
+        );
+
+        let config = serde_json::json!({
+            "num_hidden_layers": 32,
+            "num_attention_heads": 32,
+            "num_key_value_heads": 8,
+            "hidden_size": 4096,
+            "max_position_embeddings": 131072
+        });
+        assert_eq!(Attention::from_config(&config), Some(attention));
+        assert_eq!(Attention::from_config(&serde_json::json!({})), None);
+        assert_eq!(integer("8, 8, 4, ..."), Some(8));
+    }
+}
@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use super::data::{bf16_to_f32, ElementType, Encoding, TensorData};
+
+/// File formats a single tensor can be extracted to.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub(crate) enum ExtractFormat {
+    /// NumPy array, BF16 values converted to F32.
+    Npy,
+    /// SafeTensors file with the tensor alone.
+    Safetensors,
+}
+
+impl ExtractFormat {
+    pub(crate) fn of_extension(path: &Path) -> Option<Self> {
+        match path
+            .extension()?
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "npy" => Some(Self::Npy),
+            "safetensors" => Some(Self::Safetensors),
+            _ => None,
+        }
+    }
+}
+
+/// The values of a tensor in a plain element type, in row major order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Extracted {
+    pub element_type: ElementType,
+    pub shape: Vec<usize>,
+    pub data: Vec<u8>,
+}
+
+impl Extracted {
+    /// Decodes the tensor, quantized values being dequantized to F32.
+    pub(crate) fn new(tensor: &TensorData, shape: Vec<usize>) -> anyhow::Result<Self> {
+        let (element_type, data) = match tensor.encoding {
+            Encoding::Plain(element_type) => (element_type, tensor.bytes().to_vec()),
+            Encoding::Q8_0 | Encoding::Q4_0 => (
+                ElementType::F32,
+                tensor
+                    .values()
+                    .flat_map(|value| (value as f32).to_le_bytes())
+                    .collect(),
+            ),
+        };
+
+        let expected = shape.iter().product::<usize>() * element_type.size();
+        if data.len() != expected {
+            anyhow::bail!(
+                "tensor {} has {} bytes of data, {} expected for shape {:?}",
+                tensor.id,
+                data.len(),
+                expected,
+                shape
+            );
+        }
+        Ok(Self {
+            element_type,
+            shape,
+            data,
+        })
+    }
+
+    /// Serializes the tensor as a NumPy .npy file, version 1.0.
+    pub(crate) fn to_npy(&self) -> Vec<u8> {
+        // NumPy has no bfloat16
+        let (descr, data) = match self.element_type {
+            ElementType::BF16 => (
+                "<f4",
+                self.data
+                    .chunks_exact(2)
+                    .flat_map(|chunk| {
+                        bf16_to_f32(u16::from_le_bytes([chunk[0], chunk[1]])).to_le_bytes()
+                    })
+                    .collect(),
+            ),
+            element_type => (npy_descr(element_type), self.data.clone()),
+        };
+        let shape = match self.shape.as_slice() {
+            [dim] => format!("({},)", dim),
+            dims => format!(
+                "({})",
+                dims.iter()
+                    .map(|dim| dim.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape
+        );
+        // magic, version and header length take 10 bytes, the header ends with a newline and
+        // the data is aligned to 64 bytes
+        let padding = (10 + header.len() + 1).next_multiple_of(64) - (10 + header.len() + 1);
+        header.push_str(&" ".repeat(padding));
+        header.push('\n');
+
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend((header.len() as u16).to_le_bytes());
+        npy.extend(header.as_bytes());
+        npy.extend(data);
+        npy
+    }
+
+    /// Serializes the tensor as a SafeTensors file with the given name.
+    pub(crate) fn to_safetensors(&self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let header = serde_json::json!({
+            name: {
+                "dtype": safetensors_dtype(self.element_type),
+                "shape": self.shape,
+                "data_offsets": [0, self.data.len()],
+            }
+        });
+        let mut json = serde_json::to_vec(&header)?;
+        // padded with spaces to keep the data aligned
+        json.resize(json.len().next_multiple_of(8), b' ');
+
+        let mut file = (json.len() as u64).to_le_bytes().to_vec();
+        file.extend(json);
+        file.extend(&self.data);
+        Ok(file)
+    }
+}
+
+fn npy_descr(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::F64 => "<f8",
+        ElementType::F32 | ElementType::BF16 => "<f4",
+        ElementType::F16 => "<f2",
+        ElementType::I64 => "<i8",
+        ElementType::I32 => "<i4",
+        ElementType::I16 => "<i2",
+        ElementType::I8 => "|i1",
+        ElementType::U64 => "<u8",
+        ElementType::U32 => "<u4",
+        ElementType::U16 => "<u2",
+        ElementType::U8 => "|u1",
+        ElementType::Bool => "|b1",
+    }
+}
+
+fn safetensors_dtype(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::F64 => "F64",
+        ElementType::F32 => "F32",
+        ElementType::F16 => "F16",
+        ElementType::BF16 => "BF16",
+        ElementType::I64 => "I64",
+        ElementType::I32 => "I32",
+        ElementType::I16 => "I16",
+        ElementType::I8 => "I8",
+        ElementType::U64 => "U64",
+        ElementType::U32 => "U32",
+        ElementType::U16 => "U16",
+        ElementType::U8 => "U8",
+        ElementType::Bool => "BOOL",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        // a Q8_0 block with a scale of 0.5
+        let mut block = 0x3800u16.to_le_bytes().to_vec();
+        block.extend((0..32).map(|i| i as u8));
+        let tensor = TensorData::owned("q".to_string(), Encoding::Q8_0, block);
+        let extracted = Extracted::new(&tensor, vec![4, 8]).unwrap();
+        assert_eq!(extracted.element_type, ElementType::F32);
+        assert_eq!(extracted.data[4..8], 0.5f32.to_le_bytes());
+        assert!(Extracted::new(&tensor, vec![4, 4]).is_err());
+
+        let npy = extracted.to_npy();
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (4, 8), }"));
+        assert_eq!(npy.len(), 10 + header_len + 32 * 4);
+
+        let file = extracted.to_safetensors("q").unwrap();
+        let loaded = safetensors::SafeTensors::deserialize(&file).unwrap();
+        let view = loaded.tensor("q").unwrap();
+        assert_eq!(view.shape(), &[4, 8]);
+        assert_eq!(view.data(), extracted.data.as_slice());
+
+        assert_eq!(
+            ExtractFormat::of_extension(Path::new("embed.NPY")),
+            Some(ExtractFormat::Npy)
+        );
+        assert_eq!(ExtractFormat::of_extension(Path::new("embed.bin")), None);
+    }
+}
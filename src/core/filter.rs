@@ -0,0 +1,313 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use clap::ValueEnum;
+use regex::Regex;
+
+use super::{Inspection, TensorDescriptor};
+
+// size units, decimal and binary
+const SIZE_UNITS: &[(&str, f64)] = &[
+    ("", 1.0),
+    ("b", 1.0),
+    ("kb", 1e3),
+    ("mb", 1e6),
+    ("gb", 1e9),
+    ("tb", 1e12),
+    ("kib", 1024.0),
+    ("mib", 1024.0 * 1024.0),
+    ("gib", 1024.0 * 1024.0 * 1024.0),
+    ("tib", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+];
+
+/// Parses a size in bytes such as 4096, 100MB or 1.5GiB.
+pub(crate) fn parse_size(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", value))?;
+    let unit = unit.trim().to_ascii_lowercase();
+    SIZE_UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .map(|(_, scale)| (number * scale).round() as usize)
+        .ok_or_else(|| {
+            format!(
+                "invalid size unit '{}', use B, KB, MB, GB, KiB, MiB or GiB",
+                unit
+            )
+        })
+}
+
+/// A tensor shape, dimensions separated by commas or x, * matching any size.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ShapePattern(Vec<Option<usize>>);
+
+impl FromStr for ShapePattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().trim_start_matches('[').trim_end_matches(']');
+        if value.trim().is_empty() {
+            // scalars
+            return Ok(Self(vec![]));
+        }
+        value
+            .split([',', 'x'])
+            .map(|dim| match dim.trim() {
+                "*" => Ok(None),
+                dim => dim
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("invalid dimension '{}' in shape", dim)),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl ShapePattern {
+    pub(crate) fn matches(&self, shape: &[usize]) -> bool {
+        self.0.len() == shape.len()
+            && self
+                .0
+                .iter()
+                .zip(shape)
+                .all(|(expected, dim)| expected.is_none_or(|expected| expected == *dim))
+    }
+}
+
+/// Selects the tensors listed by a full detail inspection, on top of the name substring filter
+/// the handlers apply.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TensorFilter {
+    pub regex: Option<Regex>,
+    // data types, case insensitive
+    pub dtypes: Vec<String>,
+    // bounds of the size of the tensor data, in bytes
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    pub shape: Option<ShapePattern>,
+}
+
+impl TensorFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.regex.is_none()
+            && self.dtypes.is_empty()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.shape.is_none()
+    }
+
+    pub(crate) fn matches(&self, tensor: &TensorDescriptor) -> bool {
+        self.regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(tensor.id.as_deref().unwrap_or_default()))
+            && (self.dtypes.is_empty()
+                || self
+                    .dtypes
+                    .iter()
+                    .any(|dtype| dtype.eq_ignore_ascii_case(&tensor.dtype)))
+            && self.min_size.is_none_or(|min| tensor.size >= min)
+            && self.max_size.is_none_or(|max| tensor.size <= max)
+            && self
+                .shape
+                .as_ref()
+                .is_none_or(|shape| shape.matches(&tensor.shape))
+    }
+
+    /// Removes the tensors not matching from the inspection.
+    pub(crate) fn apply(&self, inspection: &mut Inspection) {
+        if let Some(tensors) = inspection.tensors.as_mut() {
+            tensors.retain(|tensor| self.matches(tensor));
+        }
+    }
+}
+
+/// Key the listed tensors are sorted by.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub(crate) enum SortKey {
+    Name,
+    /// Size of the tensor data.
+    Size,
+    /// Number of elements, then dimensions.
+    Shape,
+    Dtype,
+}
+
+fn compare(key: &SortKey, a: &TensorDescriptor, b: &TensorDescriptor) -> Ordering {
+    match key {
+        SortKey::Name => Ordering::Equal,
+        SortKey::Size => a.size.cmp(&b.size),
+        SortKey::Shape => a
+            .shape
+            .iter()
+            .product::<usize>()
+            .cmp(&b.shape.iter().product::<usize>())
+            .then_with(|| a.shape.cmp(&b.shape)),
+        SortKey::Dtype => a.dtype.cmp(&b.dtype),
+    }
+    // ties are sorted by name
+    .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Sorts the listed tensors of the inspection, in descending order if set, and keeps the first
+/// `top` ones.
+pub(crate) fn sort_tensors(
+    inspection: &mut Inspection,
+    key: Option<&SortKey>,
+    descending: bool,
+    top: Option<usize>,
+) {
+    let Some(tensors) = inspection.tensors.as_mut() else {
+        return;
+    };
+    if let Some(key) = key {
+        tensors.sort_by(|a, b| compare(key, a, b));
+    }
+    if descending {
+        tensors.reverse();
+    }
+    if let Some(top) = top {
+        tensors.truncate(top);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("100MB"), Ok(100_000_000));
+        assert_eq!(parse_size("1.5 GiB"), Ok(1_610_612_736));
+        assert_eq!(parse_size("2kb"), Ok(2000));
+        assert!(parse_size("10 parsecs").is_err());
+        assert!(parse_size("MB").is_err());
+
+        assert_eq!(
+            "4096,*".parse::<ShapePattern>(),
+            Ok(ShapePattern(vec![Some(4096), None]))
+        );
+        assert_eq!(
+            "[32x128x*]".parse::<ShapePattern>(),
+            Ok(ShapePattern(vec![Some(32), Some(128), None]))
+        );
+        assert!("4096,a".parse::<ShapePattern>().is_err());
+    }
+
+    #[test]
+    fn test_filter() {
+        let tensor = |id: &str, dtype: &str, shape: Vec<usize>, size: usize| TensorDescriptor {
+            id: Some(id.to_string()),
+            shape,
+            dtype: dtype.to_string(),
+            size,
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        let mut inspection = Inspection {
+            tensors: Some(vec![
+                tensor(
+                    "model.embed_tokens.weight",
+                    "BF16",
+                    vec![128256, 4096],
+                    1_050_673_152,
+                ),
+                tensor(
+                    "model.layers.0.self_attn.q_proj.weight",
+                    "BF16",
+                    vec![4096, 4096],
+                    33_554_432,
+                ),
+                tensor(
+                    "model.layers.0.input_layernorm.weight",
+                    "BF16",
+                    vec![4096],
+                    8192,
+                ),
+                tensor(
+                    "model.layers.0.mlp.up_proj.weight",
+                    "F32",
+                    vec![14336, 4096],
+                    234_881_024,
+                ),
+            ]),
+            ..Default::default()
+        };
+        let ids = |inspection: &Inspection| -> Vec<String> {
+            inspection
+                .tensors
+                .iter()
+                .flatten()
+                .filter_map(|tensor| tensor.id.clone())
+                .collect()
+        };
+
+        let filter = TensorFilter {
+            dtypes: vec!["bf16".to_string()],
+            min_size: Some(parse_size("100MB").unwrap()),
+            ..Default::default()
+        };
+        let mut filtered = inspection.clone();
+        filter.apply(&mut filtered);
+        assert_eq!(ids(&filtered), vec!["model.embed_tokens.weight"]);
+
+        let filter = TensorFilter {
+            regex: Some(Regex::new(r"layers\.\d+\..*_proj").unwrap()),
+            shape: Some("*,4096".parse().unwrap()),
+            max_size: Some(100_000_000),
+            ..Default::default()
+        };
+        assert!(!filter.is_empty());
+        filter.apply(&mut inspection);
+        assert_eq!(
+            ids(&inspection),
+            vec!["model.layers.0.self_attn.q_proj.weight"]
+        );
+    }
+
+    #[test]
+    fn test_sort_tensors() {
+        let tensor = |id: &str, dtype: &str, shape: Vec<usize>| TensorDescriptor {
+            id: Some(id.to_string()),
+            size: shape.iter().product::<usize>() * 2,
+            shape,
+            dtype: dtype.to_string(),
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        let inspection = Inspection {
+            tensors: Some(vec![
+                tensor("b", "F16", vec![8, 2]),
+                tensor("c", "BF16", vec![4]),
+                tensor("a", "F16", vec![2, 8]),
+                tensor("d", "BF16", vec![64]),
+            ]),
+            ..Default::default()
+        };
+        let sorted = |key: Option<SortKey>, descending: bool, top: Option<usize>| {
+            let mut inspection = inspection.clone();
+            sort_tensors(&mut inspection, key.as_ref(), descending, top);
+            inspection
+                .tensors
+                .unwrap()
+                .into_iter()
+                .filter_map(|tensor| tensor.id)
+                .collect::<Vec<_>>()
+                .join("")
+        };
+
+        assert_eq!(sorted(Some(SortKey::Name), false, None), "abcd");
+        assert_eq!(sorted(Some(SortKey::Size), true, Some(2)), "db");
+        assert_eq!(sorted(Some(SortKey::Shape), false, None), "cabd");
+        assert_eq!(sorted(Some(SortKey::Dtype), false, None), "cdab");
+        assert_eq!(sorted(None, false, Some(1)), "b");
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use super::{card::format_count, layers::natural_cmp, report::escape_html, TensorDescriptor};
+
+// the page rendering the graph, with the title and the graph data to fill in
+const HTML_TEMPLATE: &str = include_str!("graph.html");
+
+/// A tensor consumed or produced by a node of the graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphTensor {
+    pub name: String,
+    // element type and dimensions, such as FLOAT[1,3,224,224], if known
+    pub shape: Option<String>,
+}
+
+/// An operator of the graph, or one of its inputs or outputs.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub op_type: String,
+    // attribute names and their values, formatted
+    pub attributes: Vec<(String, String)>,
+    pub inputs: Vec<GraphTensor>,
+    pub outputs: Vec<GraphTensor>,
+}
+
+/// The computation graph of a model, its nodes in execution order.
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub name: String,
+    pub nodes: Vec<GraphNode>,
+}
+
+// what the page gets to lay out the graph
+#[derive(Serialize)]
+struct Layout<'a> {
+    name: &'a str,
+    nodes: &'a [GraphNode],
+    // pairs of producer and consumer node indexes
+    edges: Vec<(usize, usize)>,
+    // depth of each node, the length of the longest path reaching it
+    ranks: Vec<usize>,
+}
+
+impl Graph {
+    // nodes are linked by the tensors one produces and the other consumes
+    fn edges(&self) -> Vec<(usize, usize)> {
+        let producers: HashMap<&str, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, node)| {
+                node.outputs
+                    .iter()
+                    .map(move |output| (output.name.as_str(), index))
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        let mut edges = vec![];
+        for (index, node) in self.nodes.iter().enumerate() {
+            for input in &node.inputs {
+                if let Some(&producer) = producers.get(input.name.as_str()) {
+                    if producer != index && seen.insert((producer, index)) {
+                        edges.push((producer, index));
+                    }
+                }
+            }
+        }
+        edges
+    }
+
+    // ranks in topological order, nodes in cycles are left where their other inputs put them
+    fn ranks(&self, edges: &[(usize, usize)]) -> Vec<usize> {
+        let mut successors = vec![vec![]; self.nodes.len()];
+        let mut pending = vec![0; self.nodes.len()];
+        for &(from, to) in edges {
+            successors[from].push(to);
+            pending[to] += 1;
+        }
+
+        let mut ranks = vec![0; self.nodes.len()];
+        let mut queue: VecDeque<usize> = (0..self.nodes.len())
+            .filter(|&index| pending[index] == 0)
+            .collect();
+        while let Some(index) = queue.pop_front() {
+            for &next in &successors[index] {
+                ranks[next] = ranks[next].max(ranks[index] + 1);
+                pending[next] -= 1;
+                if pending[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+        ranks
+    }
+
+    /// Renders the graph as DOT, the nodes labeled with their operator, name and attributes and
+    /// the edges with the shapes of their tensors, if known.
+    pub(crate) fn to_dot(&self) -> anyhow::Result<String> {
+        // make sure the name is quoted
+        let mut graph =
+            dot_graph::Graph::new(&format!("{:?}", self.name), dot_graph::Kind::Digraph);
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut lines = vec![node.op_type.clone(), node.name.clone()];
+            lines.extend(
+                node.attributes
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value)),
+            );
+            graph.add_node(
+                dot_graph::Node::new(&format!("n{}", index))
+                    .label(&dot_label(&lines))
+                    .shape(Some("box")),
+            );
+        }
+        for (from, to) in self.edges() {
+            let inputs = &self.nodes[to].inputs;
+            let shape = self.nodes[from]
+                .outputs
+                .iter()
+                .filter(|output| inputs.iter().any(|input| input.name == output.name))
+                .find_map(|output| output.shape.as_deref())
+                .unwrap_or_default();
+            graph.add_edge(dot_graph::Edge::new(
+                &format!("n{}", from),
+                &format!("n{}", to),
+                &dot_label(&[shape]),
+            ));
+        }
+        Ok(graph.to_dot_string()?)
+    }
+
+    /// Renders the graph as a self-contained HTML page, laid out and drawn by its script, with
+    /// zoom, search and tooltips with the attributes and tensor shapes of the nodes.
+    pub(crate) fn render_html(&self) -> String {
+        let edges = self.edges();
+        let layout = Layout {
+            name: &self.name,
+            nodes: &self.nodes,
+            ranks: self.ranks(&edges),
+            edges,
+        };
+        // a closing tag in a string would end the script early
+        let data = serde_json::to_string(&layout)
+            .unwrap()
+            .replace("</", "<\\/");
+
+        HTML_TEMPLATE
+            .replace("{{title}}", &escape_html(&self.name))
+            .replace("{{graph}}", &data)
+    }
+}
+
+/// The lines of a DOT label, with its quotes and backslashes escaped.
+pub(crate) fn dot_label<S: AsRef<str>>(lines: &[S]) -> String {
+    lines
+        .iter()
+        .map(|line| line.as_ref().replace('\\', "\\\\").replace('"', "\\\""))
+        .collect::<Vec<_>>()
+        .join("\\n")
+}
+
+// a module of the hierarchy given by the dotted tensor names, with the totals of its tensors
+#[derive(Default)]
+struct Module<'a> {
+    tensors: usize,
+    parameters: usize,
+    size: usize,
+    children: BTreeMap<&'a str, Module<'a>>,
+    // the tensor named after the module, the leaves of the tree
+    tensor: Option<&'a TensorDescriptor>,
+}
+
+impl<'a> Module<'a> {
+    fn add(&mut self, tensor: &'a TensorDescriptor) {
+        let parameters = tensor.shape.iter().product::<usize>();
+        let mut module = self;
+        for component in tensor.id.as_deref().unwrap_or_default().split('.') {
+            module.tensors += 1;
+            module.parameters += parameters;
+            module.size += tensor.size;
+            module = module.children.entry(component).or_default();
+        }
+        module.tensors += 1;
+        module.parameters += parameters;
+        module.size += tensor.size;
+        module.tensor = Some(tensor);
+    }
+
+    fn label(&self, name: &str) -> String {
+        let size = humansize::format_size(self.size, humansize::DECIMAL);
+        match self.tensor {
+            Some(tensor) if self.children.is_empty() => dot_label(&[
+                name.to_string(),
+                format!("{:?} {}", tensor.shape, tensor.dtype),
+                size,
+            ]),
+            _ => dot_label(&[
+                name.to_string(),
+                format!(
+                    "{} tensor{}, {} params, {}",
+                    self.tensors,
+                    if self.tensors == 1 { "" } else { "s" },
+                    format_count(self.parameters),
+                    size
+                ),
+            ]),
+        }
+    }
+
+    // adds the node of the module and the ones of its children, returning its node name
+    fn add_to(&self, graph: &mut dot_graph::Graph, name: &str, count: &mut usize) -> String {
+        let node_name = format!("n{}", count);
+        *count += 1;
+        let shape = if self.children.is_empty() {
+            "ellipse"
+        } else {
+            "box"
+        };
+        graph.add_node(
+            dot_graph::Node::new(&node_name)
+                .label(&self.label(name))
+                .shape(Some(shape)),
+        );
+
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_by(|(a, _), (b, _)| natural_cmp(a, b));
+        for (child_name, child) in children {
+            let child_node = child.add_to(graph, child_name, count);
+            graph.add_edge(dot_graph::Edge::new(&node_name, &child_node, ""));
+        }
+        node_name
+    }
+}
+
+/// A DOT graph of the module hierarchy given by the dotted tensor names, from the model down
+/// to the layers, their blocks and weights, with the number of parameters and size of each.
+pub(crate) fn module_tree_dot(name: &str, tensors: &[TensorDescriptor]) -> anyhow::Result<String> {
+    let mut root = Module::default();
+    for tensor in tensors {
+        root.add(tensor);
+    }
+
+    // make sure the name is quoted
+    let mut graph = dot_graph::Graph::new(&format!("{:?}", name), dot_graph::Kind::Digraph);
+    root.add_to(&mut graph, name, &mut 0);
+    Ok(graph.to_dot_string()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, inputs: &[&str], outputs: &[&str]) -> GraphNode {
+        let tensors = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| GraphTensor {
+                    name: name.to_string(),
+                    shape: None,
+                })
+                .collect()
+        };
+        GraphNode {
+            name: name.to_string(),
+            op_type: "Op".to_string(),
+            attributes: vec![],
+            inputs: tensors(inputs),
+            outputs: tensors(outputs),
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let graph = Graph {
+            name: "</script>".to_string(),
+            nodes: vec![
+                node("input", &[], &["x"]),
+                node("conv", &["x", "weight"], &["y"]),
+                node("relu", &["y"], &["z"]),
+                // skip connection
+                node("add", &["x", "z", "z"], &["sum"]),
+                node("output", &["sum"], &[]),
+            ],
+        };
+
+        let edges = graph.edges();
+        assert_eq!(edges, vec![(0, 1), (1, 2), (0, 3), (2, 3), (3, 4)]);
+        assert_eq!(graph.ranks(&edges), vec![0, 1, 2, 3, 4]);
+
+        let html = graph.render_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>&lt;/script&gt;</title>"));
+        assert!(html.contains(r#""name":"<\/script>""#));
+        assert!(!html.contains("{{graph}}"));
+    }
+
+    #[test]
+    fn test_module_tree_dot() {
+        let tensor = |id: &str, shape: Vec<usize>| TensorDescriptor {
+            id: Some(id.to_string()),
+            size: shape.iter().product::<usize>() * 2,
+            shape,
+            dtype: "BF16".to_string(),
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        let dot = module_tree_dot(
+            "model",
+            &[
+                tensor("model.layers.10.mlp.up_proj.weight", vec![8, 4]),
+                tensor("model.layers.2.mlp.up_proj.weight", vec![8, 4]),
+                tensor("model.norm.weight", vec![4]),
+                tensor("lm_head.weight", vec![100, 4]),
+            ],
+        )
+        .unwrap();
+
+        let labels: Vec<_> = dot
+            .lines()
+            .filter(|line| !line.contains(" -> "))
+            .filter_map(|line| line.split_once("[label=\"")?.1.split_once('"'))
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "model\\n4 tensors, 468 params, 936 B",
+                "lm_head\\n1 tensor, 400 params, 800 B",
+                "weight\\n[100, 4] BF16\\n800 B",
+                "model\\n3 tensors, 68 params, 136 B",
+                "layers\\n2 tensors, 64 params, 128 B",
+                // in numeric order
+                "2\\n1 tensor, 32 params, 64 B",
+                "mlp\\n1 tensor, 32 params, 64 B",
+                "up_proj\\n1 tensor, 32 params, 64 B",
+                "weight\\n[8, 4] BF16\\n64 B",
+                "10\\n1 tensor, 32 params, 64 B",
+                "mlp\\n1 tensor, 32 params, 64 B",
+                "up_proj\\n1 tensor, 32 params, 64 B",
+                "weight\\n[8, 4] BF16\\n64 B",
+                "norm\\n1 tensor, 4 params, 8 B",
+                "weight\\n[4] BF16\\n8 B",
+            ]
+        );
+        assert!(dot.contains(r#""n0" -> "n1"[label=""]"#));
+        assert!(dot.contains(r#""n0" -> "n3"[label=""]"#));
+    }
+}
@@ -0,0 +1,159 @@
+// Minimal, bounds checked, read-only access to FlatBuffers tables.
+//
+// https://flatbuffers.dev/internals/
+
+#[derive(Clone, Copy)]
+pub(crate) struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> anyhow::Result<u16> {
+    buf.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| anyhow::anyhow!("out of bounds read at offset {}", pos))
+}
+
+pub(crate) fn read_u32(buf: &[u8], pos: usize) -> anyhow::Result<u32> {
+    buf.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow::anyhow!("out of bounds read at offset {}", pos))
+}
+
+pub(crate) fn read_u64(buf: &[u8], pos: usize) -> anyhow::Result<u64> {
+    buf.get(pos..pos + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow::anyhow!("out of bounds read at offset {}", pos))
+}
+
+fn follow(buf: &[u8], pos: usize) -> anyhow::Result<usize> {
+    pos.checked_add(read_u32(buf, pos)? as usize)
+        .filter(|target| *target < buf.len())
+        .ok_or_else(|| anyhow::anyhow!("invalid offset at {}", pos))
+}
+
+impl<'a> Table<'a> {
+    pub fn root(buf: &'a [u8]) -> anyhow::Result<Self> {
+        Self::at(buf, follow(buf, 0)?)
+    }
+
+    fn at(buf: &'a [u8], pos: usize) -> anyhow::Result<Self> {
+        // make sure the vtable is reachable
+        let table = Self { buf, pos };
+        table.vtable()?;
+        Ok(table)
+    }
+
+    fn vtable(&self) -> anyhow::Result<usize> {
+        let soffset = read_u32(self.buf, self.pos)? as i32 as i64;
+        let vtable = self.pos as i64 - soffset;
+        if vtable < 0 || vtable as usize >= self.buf.len() {
+            anyhow::bail!("invalid vtable offset for table at {}", self.pos);
+        }
+        Ok(vtable as usize)
+    }
+
+    /// Returns the absolute position of the given field, if present.
+    fn field(&self, slot: usize) -> anyhow::Result<Option<usize>> {
+        let vtable = self.vtable()?;
+        let vtable_size = read_u16(self.buf, vtable)? as usize;
+        let entry = 4 + slot * 2;
+        if entry + 2 > vtable_size {
+            return Ok(None);
+        }
+        match read_u16(self.buf, vtable + entry)? {
+            0 => Ok(None),
+            offset => Ok(Some(self.pos + offset as usize)),
+        }
+    }
+
+    pub fn u8(&self, slot: usize, default: u8) -> anyhow::Result<u8> {
+        match self.field(slot)? {
+            Some(pos) => self
+                .buf
+                .get(pos)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("out of bounds read at offset {}", pos)),
+            None => Ok(default),
+        }
+    }
+
+    pub fn u32(&self, slot: usize, default: u32) -> anyhow::Result<u32> {
+        match self.field(slot)? {
+            Some(pos) => read_u32(self.buf, pos),
+            None => Ok(default),
+        }
+    }
+
+    pub fn u64(&self, slot: usize, default: u64) -> anyhow::Result<u64> {
+        match self.field(slot)? {
+            Some(pos) => read_u64(self.buf, pos),
+            None => Ok(default),
+        }
+    }
+
+    pub fn table(&self, slot: usize) -> anyhow::Result<Option<Table<'a>>> {
+        match self.field(slot)? {
+            Some(pos) => Ok(Some(Self::at(self.buf, follow(self.buf, pos)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn string(&self, slot: usize) -> anyhow::Result<Option<String>> {
+        match self.field(slot)? {
+            Some(pos) => {
+                let start = follow(self.buf, pos)?;
+                let len = read_u32(self.buf, start)? as usize;
+                let bytes = self
+                    .buf
+                    .get(start + 4..start + 4 + len)
+                    .ok_or_else(|| anyhow::anyhow!("string at {} is out of bounds", start))?;
+                Ok(Some(String::from_utf8_lossy(bytes).to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the position of the first element and the number of elements of a vector field.
+    fn vector(&self, slot: usize, element_size: usize) -> anyhow::Result<Option<(usize, usize)>> {
+        match self.field(slot)? {
+            Some(pos) => {
+                let start = follow(self.buf, pos)?;
+                let len = read_u32(self.buf, start)? as usize;
+                let end = len
+                    .checked_mul(element_size)
+                    .and_then(|size| size.checked_add(start + 4))
+                    .ok_or_else(|| anyhow::anyhow!("vector at {} is too large", start))?;
+                if end > self.buf.len() {
+                    anyhow::bail!("vector at {} is out of bounds", start);
+                }
+                Ok(Some((start + 4, len)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn vector_len(&self, slot: usize, element_size: usize) -> anyhow::Result<usize> {
+        Ok(self.vector(slot, element_size)?.map_or(0, |(_, len)| len))
+    }
+
+    pub fn tables(&self, slot: usize) -> anyhow::Result<Vec<Table<'a>>> {
+        let mut tables = vec![];
+        if let Some((start, len)) = self.vector(slot, 4)? {
+            for i in 0..len {
+                tables.push(Self::at(self.buf, follow(self.buf, start + i * 4)?)?);
+            }
+        }
+        Ok(tables)
+    }
+
+    pub fn i32s(&self, slot: usize) -> anyhow::Result<Vec<i32>> {
+        let mut values = vec![];
+        if let Some((start, len)) = self.vector(slot, 4)? {
+            for i in 0..len {
+                values.push(read_u32(self.buf, start + i * 4)? as i32);
+            }
+        }
+        Ok(values)
+    }
+}
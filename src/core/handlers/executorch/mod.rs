@@ -0,0 +1,509 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+
+mod flatbuffer;
+
+use flatbuffer::{read_u32, read_u64, Table};
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+// https://github.com/pytorch/executorch/blob/main/schema/program.fbs
+const PROGRAM_IDENTIFIER: &[u8; 2] = b"ET";
+// https://github.com/pytorch/executorch/blob/main/exir/_serialize/_program.py
+const EXTENDED_HEADER_MAGIC: &[u8; 4] = b"eh00";
+
+// field slots of the flatbuffer tables we read
+const PROGRAM_VERSION: usize = 0;
+const PROGRAM_EXECUTION_PLAN: usize = 1;
+const PROGRAM_BACKEND_DELEGATE_DATA: usize = 3;
+const PROGRAM_SEGMENTS: usize = 4;
+const PLAN_NAME: usize = 0;
+const PLAN_VALUES: usize = 2;
+const PLAN_INPUTS: usize = 3;
+const PLAN_OUTPUTS: usize = 4;
+const PLAN_OPERATORS: usize = 6;
+const PLAN_DELEGATES: usize = 7;
+const EVALUE_VAL_TYPE: usize = 0;
+const EVALUE_VAL: usize = 1;
+const TENSOR_SCALAR_TYPE: usize = 0;
+const TENSOR_SIZES: usize = 2;
+const TENSOR_DATA_BUFFER_IDX: usize = 5;
+const TENSOR_ALLOCATION_INFO: usize = 6;
+const DELEGATE_ID: usize = 0;
+const DELEGATE_PROCESSED: usize = 1;
+const DATA_REFERENCE_LOCATION: usize = 0;
+const DATA_REFERENCE_INDEX: usize = 1;
+const INLINE_DATA: usize = 0;
+const SEGMENT_SIZE: usize = 1;
+const OPERATOR_NAME: usize = 0;
+
+// KernelTypes union tag of the Tensor variant
+const KERNEL_TYPE_TENSOR: u8 = 5;
+// DataLocation::SEGMENT
+const LOCATION_SEGMENT: u8 = 1;
+
+#[inline]
+fn scalar_type_bits(scalar_type: u8) -> usize {
+    match scalar_type {
+        0 => 8,    // BYTE
+        1 => 8,    // CHAR
+        2 => 16,   // SHORT
+        3 => 32,   // INT
+        4 => 64,   // LONG
+        5 => 16,   // HALF
+        6 => 32,   // FLOAT
+        7 => 64,   // DOUBLE
+        8 => 32,   // COMPLEX32
+        9 => 64,   // COMPLEX64
+        10 => 128, // COMPLEX128
+        11 => 8,   // BOOL
+        12 => 8,   // QINT8
+        13 => 8,   // QUINT8
+        14 => 32,  // QINT32
+        15 => 16,  // BFLOAT16
+        16 => 8,   // QUINT4X2
+        17 => 8,   // QUINT2X4
+        22 => 16,  // BITS16
+        23 => 8,   // FLOAT8E5M2
+        24 => 8,   // FLOAT8E4M3FN
+        25 => 8,   // FLOAT8E5M2FNUZ
+        26 => 8,   // FLOAT8E4M3FNUZ
+        27 => 16,  // UINT16
+        28 => 32,  // UINT32
+        29 => 64,  // UINT64
+        _ => 0,
+    }
+}
+
+#[inline]
+fn scalar_type_string(scalar_type: u8) -> &'static str {
+    match scalar_type {
+        0 => "BYTE",
+        1 => "CHAR",
+        2 => "SHORT",
+        3 => "INT",
+        4 => "LONG",
+        5 => "HALF",
+        6 => "FLOAT",
+        7 => "DOUBLE",
+        8 => "COMPLEX32",
+        9 => "COMPLEX64",
+        10 => "COMPLEX128",
+        11 => "BOOL",
+        12 => "QINT8",
+        13 => "QUINT8",
+        14 => "QINT32",
+        15 => "BFLOAT16",
+        16 => "QUINT4X2",
+        17 => "QUINT2X4",
+        22 => "BITS16",
+        23 => "FLOAT8E5M2",
+        24 => "FLOAT8E4M3FN",
+        25 => "FLOAT8E5M2FNUZ",
+        26 => "FLOAT8E4M3FNUZ",
+        27 => "UINT16",
+        28 => "UINT32",
+        29 => "UINT64",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Optional header following the flatbuffer identifier describing where segment data starts.
+#[derive(Debug, Default)]
+struct ExtendedHeader {
+    program_size: u64,
+    segment_base_offset: u64,
+}
+
+impl ExtendedHeader {
+    fn parse(buf: &[u8]) -> anyhow::Result<Option<Self>> {
+        if buf.get(8..12) != Some(EXTENDED_HEADER_MAGIC.as_slice()) {
+            return Ok(None);
+        }
+        // the length field covers the whole header, including magic and length
+        let _length = read_u32(buf, 12)?;
+        Ok(Some(Self {
+            program_size: read_u64(buf, 16)?,
+            segment_base_offset: read_u64(buf, 24)?,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct Delegate {
+    method: String,
+    backend: String,
+    size: usize,
+}
+
+#[derive(Debug, Default)]
+struct Program {
+    version: u32,
+    methods: Vec<String>,
+    operators: BTreeSet<String>,
+    delegates: Vec<Delegate>,
+    constants: Vec<TensorDescriptor>,
+    num_segments: usize,
+}
+
+fn parse_program(buf: &[u8]) -> anyhow::Result<Program> {
+    if buf.get(4..6) != Some(PROGRAM_IDENTIFIER.as_slice()) {
+        anyhow::bail!("not an ExecuTorch program: invalid file identifier");
+    }
+
+    let root = Table::root(buf)?;
+    let mut program = Program {
+        version: root.u32(PROGRAM_VERSION, 0)?,
+        ..Default::default()
+    };
+
+    let segments = root.tables(PROGRAM_SEGMENTS)?;
+    let inline_data = root.tables(PROGRAM_BACKEND_DELEGATE_DATA)?;
+    program.num_segments = segments.len();
+
+    for plan in root.tables(PROGRAM_EXECUTION_PLAN)? {
+        let method = plan.string(PLAN_NAME)?.unwrap_or_default();
+
+        for operator in plan.tables(PLAN_OPERATORS)? {
+            if let Some(name) = operator.string(OPERATOR_NAME)? {
+                program.operators.insert(name);
+            }
+        }
+
+        for delegate in plan.tables(PLAN_DELEGATES)? {
+            let backend = delegate.string(DELEGATE_ID)?.unwrap_or_default();
+            let size = match delegate.table(DELEGATE_PROCESSED)? {
+                Some(reference) => {
+                    let index = reference.u32(DATA_REFERENCE_INDEX, 0)? as usize;
+                    if reference.u8(DATA_REFERENCE_LOCATION, 0)? == LOCATION_SEGMENT {
+                        match segments.get(index) {
+                            Some(segment) => segment.u64(SEGMENT_SIZE, 0)? as usize,
+                            None => 0,
+                        }
+                    } else {
+                        match inline_data.get(index) {
+                            Some(data) => data.vector_len(INLINE_DATA, 1)?,
+                            None => 0,
+                        }
+                    }
+                }
+                None => 0,
+            };
+
+            program.delegates.push(Delegate {
+                method: method.clone(),
+                backend,
+                size,
+            });
+        }
+
+        let inputs = plan.i32s(PLAN_INPUTS)?;
+        let outputs = plan.i32s(PLAN_OUTPUTS)?;
+
+        for (value_idx, value) in plan.tables(PLAN_VALUES)?.iter().enumerate() {
+            if value.u8(EVALUE_VAL_TYPE, 0)? != KERNEL_TYPE_TENSOR {
+                continue;
+            }
+            let Some(tensor) = value.table(EVALUE_VAL)? else {
+                continue;
+            };
+
+            // constant tensors reference a data buffer and have no memory planning information
+            let data_buffer_idx = tensor.u32(TENSOR_DATA_BUFFER_IDX, 0)?;
+            if data_buffer_idx == 0
+                || tensor.table(TENSOR_ALLOCATION_INFO)?.is_some()
+                || inputs.contains(&(value_idx as i32))
+                || outputs.contains(&(value_idx as i32))
+            {
+                continue;
+            }
+
+            let scalar_type = tensor.u8(TENSOR_SCALAR_TYPE, 0)?;
+            let shape: Vec<usize> = tensor
+                .i32s(TENSOR_SIZES)?
+                .iter()
+                .map(|d| (*d).max(0) as usize)
+                .collect();
+
+            let mut metadata = Metadata::new();
+            metadata.insert("method".to_string(), method.clone());
+            metadata.insert("data_buffer_idx".to_string(), data_buffer_idx.to_string());
+
+            program.constants.push(TensorDescriptor {
+                // tensors have no names in the serialized program
+                id: Some(format!("{}/value_{}", method, value_idx)),
+                size: (scalar_type_bits(scalar_type) * shape.iter().product::<usize>()) / 8,
+                shape,
+                dtype: scalar_type_string(scalar_type).to_string(),
+                metadata,
+                stats: None,
+                preview: None,
+            });
+        }
+
+        program.methods.push(method);
+    }
+
+    Ok(program)
+}
+
+pub(crate) struct ExecuTorchHandler;
+
+impl ExecuTorchHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for ExecuTorchHandler {
+    fn file_type(&self) -> FileType {
+        FileType::ExecuTorch
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        file_path
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or("")
+            .eq_ignore_ascii_case("pte")
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        // programs are self contained, segments are appended to the same file
+        Ok(vec![file_path.to_path_buf()])
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let mut inspection = Inspection::default();
+
+        let file = std::fs::File::open(file_path)?;
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
+
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.file_size = file.metadata()?.len();
+
+        let header = ExtendedHeader::parse(&buffer)?;
+        // the flatbuffer only spans the program, segments follow it
+        let program_buffer = match &header {
+            Some(header) if header.program_size > 0 => {
+                &buffer[..(header.program_size as usize).min(buffer.len())]
+            }
+            _ => &buffer[..],
+        };
+
+        let program = parse_program(program_buffer)?;
+
+        inspection.file_type = FileType::ExecuTorch;
+        inspection.version = format!("v{}", program.version);
+        inspection.header_size = match &header {
+            Some(header) if header.segment_base_offset > 0 => header.segment_base_offset as usize,
+            _ => program_buffer.len(),
+        };
+
+        inspection
+            .metadata
+            .insert("methods".to_string(), program.methods.join(", "));
+
+        if !program.operators.is_empty() {
+            inspection.metadata.insert(
+                "operators".to_string(),
+                program
+                    .operators
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+
+        if program.num_segments > 0 {
+            inspection
+                .metadata
+                .insert("segments".to_string(), program.num_segments.to_string());
+        }
+
+        if !program.delegates.is_empty() {
+            inspection.metadata.insert(
+                "backends".to_string(),
+                program
+                    .delegates
+                    .iter()
+                    .map(|d| d.backend.as_str())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+
+            for (idx, delegate) in program.delegates.iter().enumerate() {
+                inspection.metadata.insert(
+                    format!("delegate.{}.{}", delegate.method, idx),
+                    format!(
+                        "{} ({})",
+                        delegate.backend,
+                        humansize::format_size(delegate.size, humansize::DECIMAL)
+                    ),
+                );
+            }
+        }
+
+        let mut tensors = program.constants;
+
+        inspection.num_tensors = tensors.len();
+        // delegated constants live inside the backend payloads
+        inspection.data_size = tensors.iter().map(|t| t.size).sum::<usize>()
+            + program.delegates.iter().map(|d| d.size).sum::<usize>();
+
+        inspection.unique_shapes = tensors
+            .iter()
+            .map(|t| t.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = tensors
+            .iter()
+            .map(|t| t.dtype.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            tensors.retain(|t| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| t.id.as_ref().is_some_and(|id| id.contains(f)))
+            });
+            inspection.tensors = Some(tensors);
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flatbuffers::{FlatBufferBuilder, WIPOffset};
+
+    use super::*;
+
+    fn slot(index: usize) -> u16 {
+        (4 + index * 2) as u16
+    }
+
+    fn build_program() -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+
+        // constant float tensor [2, 3]
+        let sizes = fbb.create_vector(&[2i32, 3]);
+        let start = fbb.start_table();
+        fbb.push_slot::<u8>(slot(TENSOR_SCALAR_TYPE), 6, 0);
+        fbb.push_slot_always(slot(TENSOR_SIZES), sizes);
+        fbb.push_slot::<u32>(slot(TENSOR_DATA_BUFFER_IDX), 1, 0);
+        let tensor = fbb.end_table(start);
+
+        let start = fbb.start_table();
+        fbb.push_slot::<u8>(slot(EVALUE_VAL_TYPE), KERNEL_TYPE_TENSOR, 0);
+        fbb.push_slot_always(slot(EVALUE_VAL), tensor);
+        let value = fbb.end_table(start);
+        let values = fbb.create_vector(&[value]);
+
+        let op_name = fbb.create_string("aten::linear");
+        let start = fbb.start_table();
+        fbb.push_slot_always(slot(OPERATOR_NAME), op_name);
+        let operator = fbb.end_table(start);
+        let operators = fbb.create_vector(&[operator]);
+
+        let start = fbb.start_table();
+        fbb.push_slot::<u8>(slot(DATA_REFERENCE_LOCATION), LOCATION_SEGMENT, 0);
+        fbb.push_slot::<u32>(slot(DATA_REFERENCE_INDEX), 0, 0);
+        let reference = fbb.end_table(start);
+        let backend_id = fbb.create_string("XnnpackBackend");
+        let start = fbb.start_table();
+        fbb.push_slot_always(slot(DELEGATE_ID), backend_id);
+        fbb.push_slot_always(slot(DELEGATE_PROCESSED), reference);
+        let delegate = fbb.end_table(start);
+        let delegates = fbb.create_vector(&[delegate]);
+
+        let method = fbb.create_string("forward");
+        let start = fbb.start_table();
+        fbb.push_slot_always(slot(PLAN_NAME), method);
+        fbb.push_slot_always(slot(PLAN_VALUES), values);
+        fbb.push_slot_always(slot(PLAN_OPERATORS), operators);
+        fbb.push_slot_always(slot(PLAN_DELEGATES), delegates);
+        let plan = fbb.end_table(start);
+        let plans = fbb.create_vector(&[plan]);
+
+        let start = fbb.start_table();
+        fbb.push_slot::<u64>(slot(SEGMENT_SIZE), 4096, 0);
+        let segment = fbb.end_table(start);
+        let segments: WIPOffset<_> = fbb.create_vector(&[segment]);
+
+        let start = fbb.start_table();
+        fbb.push_slot::<u32>(slot(PROGRAM_VERSION), 0, 1);
+        fbb.push_slot_always(slot(PROGRAM_EXECUTION_PLAN), plans);
+        fbb.push_slot_always(slot(PROGRAM_SEGMENTS), segments);
+        let root = fbb.end_table(start);
+
+        fbb.finish(root, Some("ET12"));
+        fbb.finished_data().to_vec()
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let program = parse_program(&build_program()).unwrap();
+
+        assert_eq!(program.version, 0);
+        assert_eq!(program.methods, vec!["forward"]);
+        assert_eq!(program.num_segments, 1);
+        assert!(program.operators.contains("aten::linear"));
+
+        assert_eq!(program.delegates.len(), 1);
+        assert_eq!(program.delegates[0].backend, "XnnpackBackend");
+        assert_eq!(program.delegates[0].size, 4096);
+
+        assert_eq!(program.constants.len(), 1);
+        assert_eq!(program.constants[0].shape, vec![2, 3]);
+        assert_eq!(program.constants[0].dtype, "FLOAT");
+        assert_eq!(program.constants[0].size, 24);
+    }
+
+    #[test]
+    fn test_parse_invalid_program() {
+        assert!(parse_program(b"").is_err());
+        assert!(parse_program(b"\x08\x00\x00\x00NOPE").is_err());
+
+        // truncated flatbuffer
+        let program = build_program();
+        assert!(parse_program(&program[..program.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let handler = ExecuTorchHandler::new();
+
+        assert!(handler.is_handler_for(Path::new("model.pte"), &Scope::Inspection));
+        assert!(handler.is_handler_for(Path::new("path/to/MODEL.PTE"), &Scope::Signing));
+
+        assert!(!handler.is_handler_for(Path::new("model.pt"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
+    }
+}
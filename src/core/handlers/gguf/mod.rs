@@ -1,76 +1,475 @@
 use std::{
     collections::HashSet,
+    io::{Read, Seek},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use gguf::{GGMLType, GGUFTensorInfo};
+mod parser;
+mod profile;
+mod scan;
+mod split;
+mod validate;
+
 use rayon::prelude::*;
+use regex::Regex;
 
-use super::{Handler, Scope};
-use crate::{
-    cli::DetailLevel,
-    core::{FileType, Inspection, Metadata, TensorDescriptor},
+use super::{replace_file, EmbeddedSignature, Handler, ReadSeek, Scope, EMBEDDED_SIGNATURE_KEY};
+use crate::core::{
+    data::{ElementType, Encoding, TensorData},
+    DetailLevel, FileType, Inspection, Metadata, Shard, TensorDescriptor,
 };
 
-#[inline]
-fn data_type_bits(dtype: GGMLType) -> usize {
-    match dtype {
-        GGMLType::F32 => 32,
-        GGMLType::F16 => 16,
-        GGMLType::Q4_0 => 4,
-        GGMLType::Q4_1 => 4,
-        GGMLType::Q5_0 => 5,
-        GGMLType::Q5_1 => 5,
-        GGMLType::Q8_0 => 8,
-        GGMLType::Q8_1 => 8,
-        GGMLType::Q2K => 2,
-        GGMLType::Q3K => 3,
-        GGMLType::Q4K => 4,
-        GGMLType::Q5K => 5,
-        GGMLType::Q6K => 6,
-        GGMLType::Q8K => 8,
-        GGMLType::I8 => 8,
-        GGMLType::I16 => 16,
-        GGMLType::I32 => 32,
-        GGMLType::Count => 32, // Assuming Count is 32-bit, adjust if needed
-    }
-}
-
-fn build_tensor_descriptor(t_info: &GGUFTensorInfo) -> TensorDescriptor {
+pub(crate) use parser::{GGMLType, Header, MetadataEntry, MetadataValue, TensorInfo};
+pub(crate) use scan::scan;
+pub(crate) use split::{merge, split, Split, SplitLimit};
+pub(crate) use validate::validate;
+
+const DEFAULT_ALIGNMENT: usize = 32;
+const ALIGNMENT_KEY: &str = "general.alignment";
+const STRING_VALUE_TYPE: u32 = 8;
+const ARRAY_VALUE_TYPE: u32 = 9;
+
+/// Returns the parts of a model split by llama.cpp in files named like model-00001-of-00003.gguf,
+/// in order, or None if the file is not part of a split model.
+pub(crate) fn split_paths(file_path: &Path) -> Option<Vec<PathBuf>> {
+    let file_name = file_path.file_name()?.to_str()?;
+    let regex = Regex::new(r"^(.+)-(\d{5})-of-(\d{5})\.((?i)gguf)$").unwrap();
+    let captures = regex.captures(file_name)?;
+    let count: usize = captures[3].parse().ok()?;
+    if count < 2 {
+        return None;
+    }
+
+    Some(
+        (1..=count)
+            .map(|number| {
+                file_path.with_file_name(format!(
+                    "{}-{:05}-of-{:05}.{}",
+                    &captures[1], number, count, &captures[4]
+                ))
+            })
+            .collect(),
+    )
+}
+
+/// Returns the bytes of a tensor as stored in the file, or in any part of a split model,
+/// quantization blocks included, whatever its type.
+pub(crate) fn raw_tensor_data(file_path: &Path, tensor_id: &str) -> anyhow::Result<TensorData> {
+    let parts = split_paths(file_path).unwrap_or_else(|| vec![file_path.to_path_buf()]);
+    for part in parts {
+        let file = std::fs::File::open(&part)?;
+        let buffer = Arc::new(unsafe { memmap2::MmapOptions::new().map(&file)? });
+
+        let header = Header::read(&buffer)?;
+        let Some(t_info) = header
+            .tensors
+            .iter()
+            .find(|t_info| t_info.name == tensor_id)
+        else {
+            continue;
+        };
+
+        let range = header.data_range(t_info).ok_or_else(|| {
+            anyhow::anyhow!(
+                "tensor {} has unknown type {}",
+                t_info.name,
+                t_info.tensor_type
+            )
+        })?;
+        return TensorData::new(
+            t_info.name.clone(),
+            Encoding::Plain(ElementType::U8),
+            buffer.clone(),
+            range.clone(),
+            Some(range.start as u64),
+        );
+    }
+    Err(anyhow::anyhow!("tensor {} not found", tensor_id))
+}
+
+// the metadata value as JSON, arrays in full
+pub(crate) fn json_value(value: &MetadataValue) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        MetadataValue::Uint8(v) => Value::from(*v),
+        MetadataValue::Int8(v) => Value::from(*v),
+        MetadataValue::Uint16(v) => Value::from(*v),
+        MetadataValue::Int16(v) => Value::from(*v),
+        MetadataValue::Uint32(v) => Value::from(*v),
+        MetadataValue::Int32(v) => Value::from(*v),
+        MetadataValue::Uint64(v) => Value::from(*v),
+        MetadataValue::Int64(v) => Value::from(*v),
+        // through the shortest representation of the 32 bits value, 1e-5 and not 9.99999974e-6
+        MetadataValue::Float32(v) => Value::from(v.to_string().parse::<f64>().unwrap_or(f64::NAN)),
+        MetadataValue::Float64(v) => Value::from(*v),
+        MetadataValue::Bool(v) => Value::from(*v),
+        MetadataValue::String(v) => Value::from(v.as_str()),
+        MetadataValue::Array(values) => Value::Array(values.iter().map(json_value).collect()),
+    }
+}
+
+fn build_tensor_descriptor(t_info: &TensorInfo) -> TensorDescriptor {
     TensorDescriptor {
         id: Some(t_info.name.to_string()),
         shape: t_info.dimensions.iter().map(|d| *d as usize).collect(),
-        dtype: format!("{:?}", t_info.tensor_type),
-        size: if t_info.dimensions.is_empty() {
-            0
-        } else {
-            (data_type_bits(t_info.tensor_type)
-                * t_info
-                    .dimensions
-                    .iter()
-                    .map(|d| *d as usize)
-                    .product::<usize>())
-                / 8
-        },
+        dtype: t_info.tensor_type.to_string(),
+        size: t_info.data_size().unwrap_or(0) as usize,
         metadata: Metadata::new(),
+        stats: None,
+        preview: None,
+    }
+}
+
+fn push_string(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+// a single string entry is expected, any other would be left out of the signed content
+fn embedded_signature_of(header: &Header) -> anyhow::Result<Option<String>> {
+    let count = header
+        .metadata
+        .iter()
+        .filter(|entry| entry.key == EMBEDDED_SIGNATURE_KEY)
+        .count();
+    if count > 1 {
+        anyhow::bail!("multiple {} metadata entries", EMBEDDED_SIGNATURE_KEY);
+    }
+    match header.get(EMBEDDED_SIGNATURE_KEY) {
+        None => Ok(None),
+        Some(value) => value
+            .as_str()
+            .map(|signature| Some(signature.to_string()))
+            .ok_or_else(|| anyhow::anyhow!("{} is not a string", EMBEDDED_SIGNATURE_KEY)),
     }
 }
 
-fn format_parsing_error(error: &str) -> String {
-    // the GGUF library dumps the entire buffer in the error message, we don't want that.
-    if error.len() > 100 {
-        format!("failed to parse GGUF file: {}", &error[..100])
+/// Serializes everything preceding the tensor data, the embedded signature is replaced
+/// by the given one or removed if none.
+fn serialize_header(buffer: &[u8], header: &Header, signature: Option<&str>) -> Vec<u8> {
+    let mut metadata = vec![];
+    let mut metadata_count = 0u64;
+    for entry in &header.metadata {
+        if entry.key != EMBEDDED_SIGNATURE_KEY {
+            metadata.extend_from_slice(&buffer[entry.range.clone()]);
+            metadata_count += 1;
+        }
+    }
+    if let Some(signature) = signature {
+        push_string(&mut metadata, EMBEDDED_SIGNATURE_KEY);
+        metadata.extend_from_slice(&STRING_VALUE_TYPE.to_le_bytes());
+        push_string(&mut metadata, signature);
+        metadata_count += 1;
+    }
+
+    // magic, version and tensor count, followed by the metadata count, 32 bits in the first version
+    let mut serialized = if header.version == 1 {
+        let mut serialized = buffer[..12].to_vec();
+        serialized.extend_from_slice(&(metadata_count as u32).to_le_bytes());
+        serialized
     } else {
-        format!("failed to parse GGUF file: {}", error)
+        let mut serialized = buffer[..16].to_vec();
+        serialized.extend_from_slice(&metadata_count.to_le_bytes());
+        serialized
+    };
+    serialized.extend(metadata);
+    // tensor offsets are relative to the data section, so the tensor infos can be copied as they are
+    serialized.extend_from_slice(&buffer[header.tensor_infos.clone()]);
+    serialized.resize(serialized.len().next_multiple_of(header.alignment), 0);
+
+    serialized
+}
+
+fn encoding(dtype: GGMLType) -> Option<Encoding> {
+    match dtype {
+        GGMLType::F32 => Some(Encoding::Plain(ElementType::F32)),
+        GGMLType::F16 => Some(Encoding::Plain(ElementType::F16)),
+        GGMLType::BF16 => Some(Encoding::Plain(ElementType::BF16)),
+        GGMLType::F64 => Some(Encoding::Plain(ElementType::F64)),
+        GGMLType::I8 => Some(Encoding::Plain(ElementType::I8)),
+        GGMLType::I16 => Some(Encoding::Plain(ElementType::I16)),
+        GGMLType::I32 => Some(Encoding::Plain(ElementType::I32)),
+        GGMLType::I64 => Some(Encoding::Plain(ElementType::I64)),
+        GGMLType::Q8_0 => Some(Encoding::Q8_0),
+        GGMLType::Q4_0 => Some(Encoding::Q4_0),
+        // other quantization schemes are not supported
+        _ => None,
     }
 }
 
+fn read_u32(reader: &mut dyn Read) -> anyhow::Result<u32> {
+    let mut value = [0u8; 4];
+    reader.read_exact(&mut value)?;
+    Ok(u32::from_le_bytes(value))
+}
+
+fn read_u64(reader: &mut dyn Read) -> anyhow::Result<u64> {
+    let mut value = [0u8; 8];
+    reader.read_exact(&mut value)?;
+    Ok(u64::from_le_bytes(value))
+}
+
+// sizes and counts are 32 bits in the first version
+fn read_size(reader: &mut dyn Read, wide: bool) -> anyhow::Result<u64> {
+    if wide {
+        read_u64(reader)
+    } else {
+        read_u32(reader).map(u64::from)
+    }
+}
+
+// reads through the given number of bytes
+fn scan_bytes(reader: &mut dyn Read, size: u64) -> anyhow::Result<()> {
+    if std::io::copy(&mut reader.take(size), &mut std::io::sink())? != size {
+        anyhow::bail!("unexpected end of GGUF header");
+    }
+    Ok(())
+}
+
+// size of the metadata value types that have one
+fn fixed_value_size(value_type: u32) -> Option<u64> {
+    match value_type {
+        // uint8, int8, bool
+        0 | 1 | 7 => Some(1),
+        // uint16, int16
+        2 | 3 => Some(2),
+        // uint32, int32, float32
+        4..=6 => Some(4),
+        // uint64, int64, float64
+        10..=12 => Some(8),
+        _ => None,
+    }
+}
+
+fn scan_value(
+    reader: &mut dyn Read,
+    value_type: u32,
+    wide: bool,
+    depth: usize,
+) -> anyhow::Result<()> {
+    if let Some(size) = fixed_value_size(value_type) {
+        return scan_bytes(reader, size);
+    }
+
+    match value_type {
+        STRING_VALUE_TYPE => {
+            let size = read_size(reader, wide)?;
+            scan_bytes(reader, size)
+        }
+        ARRAY_VALUE_TYPE if depth >= parser::MAX_ARRAY_DEPTH => {
+            anyhow::bail!("GGUF arrays nested too deeply")
+        }
+        ARRAY_VALUE_TYPE => {
+            let element_type = read_u32(reader)?;
+            let count = read_size(reader, wide)?;
+            match fixed_value_size(element_type) {
+                Some(size) => scan_bytes(
+                    reader,
+                    size.checked_mul(count)
+                        .ok_or_else(|| anyhow::anyhow!("invalid GGUF array size"))?,
+                ),
+                None => {
+                    (0..count).try_for_each(|_| scan_value(reader, element_type, wide, depth + 1))
+                }
+            }
+        }
+        _ => anyhow::bail!("invalid GGUF metadata value type {}", value_type),
+    }
+}
+
+// the tensors of a single GGUF file
+fn file_tensor_data(file_path: &Path, filter: Option<String>) -> anyhow::Result<Vec<TensorData>> {
+    let file = std::fs::File::open(file_path)?;
+    let buffer = Arc::new(unsafe { memmap2::MmapOptions::new().map(&file)? });
+
+    let header = Header::read(&buffer)?;
+
+    header
+        .tensors
+        .iter()
+        .filter(|t_info| filter.as_ref().is_none_or(|f| t_info.name.contains(f)))
+        .filter_map(|t_info| encoding(t_info.tensor_type).map(|encoding| (t_info, encoding)))
+        .map(|(t_info, encoding)| {
+            let range = header.data_range(t_info).ok_or_else(|| {
+                anyhow::anyhow!("data of tensor {} is out of bounds", t_info.name)
+            })?;
+            TensorData::new(
+                t_info.name.clone(),
+                encoding,
+                buffer.clone(),
+                range.clone(),
+                Some(range.start as u64),
+            )
+        })
+        .collect()
+}
+
+// summary of a part of a split model
+fn shard_of(part: &Inspection, dtypes: &[String]) -> Shard {
+    let mut unique_dtypes = dtypes.to_vec();
+    unique_dtypes.sort();
+    Shard {
+        file_path: part.file_path.clone(),
+        file_size: part.file_size,
+        num_tensors: part.num_tensors,
+        data_size: part.data_size,
+        unique_dtypes,
+    }
+}
+
+/// Inspects the GGUF file read in memory, only its header is parsed.
+pub(crate) fn inspect_buffer(
+    buffer: &[u8],
+    detail: DetailLevel,
+    filter: Option<String>,
+) -> anyhow::Result<Inspection> {
+    let mut inspection = Inspection {
+        file_size: buffer.len() as u64,
+        ..Default::default()
+    };
+
+    let header = Header::read(buffer)?;
+
+    inspection.file_type = FileType::GGUF;
+    inspection.version = format!("{}", header.version);
+    // metadata, tensor infos and the padding up to the data section
+    inspection.header_size = header.data_offset;
+    inspection.num_tensors = header.tensors.len();
+    inspection.unique_shapes = header
+        .tensors
+        .par_iter()
+        .map(|t| t.dimensions.iter().map(|d| *d as usize).collect::<Vec<_>>())
+        .filter(|shape| !shape.is_empty())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    // sort shapes by volume
+    inspection.unique_shapes.sort_by(|a, b| {
+        let size_a: usize = a.iter().product();
+        let size_b: usize = b.iter().product();
+        size_a.cmp(&size_b)
+    });
+
+    inspection.unique_dtypes = header
+        .tensors
+        .par_iter()
+        .map(|t| t.tensor_type.to_string())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    // quantized tensors are stored in blocks, their scales and minimums included
+    inspection.data_size = header
+        .tensors
+        .par_iter()
+        .map(|t| t.data_size().unwrap_or(0) as usize)
+        .sum::<usize>();
+
+    for entry in &header.metadata {
+        inspection
+            .metadata
+            .insert(entry.key.clone(), entry.value.to_string());
+    }
+    inspection.typed_metadata = Some(
+        header
+            .metadata
+            .iter()
+            .map(|entry| (entry.key.clone(), json_value(&entry.value)))
+            .collect(),
+    );
+    inspection.profile = profile::profile(&header.metadata);
+
+    if matches!(detail, DetailLevel::Full) {
+        inspection.tensors = Some(
+            header
+                .tensors
+                .par_iter()
+                .filter(|t_info| filter.as_ref().is_none_or(|f| t_info.name.contains(f)))
+                .map(build_tensor_descriptor)
+                .collect(),
+        );
+    }
+
+    Ok(inspection)
+}
+
 pub(crate) struct GGUFHandler {}
 
 impl GGUFHandler {
     pub(crate) fn new() -> Self {
         Self {}
     }
+
+    fn inspect_split(
+        &self,
+        parts: &[PathBuf],
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let parts = parts
+            .par_iter()
+            .map(|part| {
+                self.inspect_file(part, detail.clone(), filter.clone())
+                    .map_err(|e| anyhow::anyhow!("can't inspect {}: {}", part.display(), e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // the first part has the metadata of the model, the others only the split keys
+        let mut parts = parts.into_iter();
+        let mut inspection = parts.next().ok_or_else(|| anyhow::anyhow!("no parts"))?;
+        let mut shapes: HashSet<Vec<usize>> = inspection.unique_shapes.drain(..).collect();
+        let first_dtypes = std::mem::take(&mut inspection.unique_dtypes);
+        let mut summaries = vec![shard_of(&inspection, &first_dtypes)];
+        let mut dtypes: HashSet<String> = first_dtypes.into_iter().collect();
+
+        for part in parts {
+            summaries.push(shard_of(&part, &part.unique_dtypes));
+            inspection.file_size += part.file_size;
+            inspection.header_size += part.header_size;
+            inspection.num_tensors += part.num_tensors;
+            inspection.data_size += part.data_size;
+            shapes.extend(part.unique_shapes);
+            dtypes.extend(part.unique_dtypes);
+            for (key, value) in part.metadata {
+                inspection.metadata.entry(key).or_insert(value);
+            }
+            if let Some(tensors) = part.tensors {
+                inspection
+                    .tensors
+                    .get_or_insert_with(Vec::new)
+                    .extend(tensors);
+            }
+        }
+
+        inspection.unique_shapes = shapes.into_iter().collect();
+        // sort shapes by volume
+        inspection
+            .unique_shapes
+            .sort_by_key(|shape| (shape.iter().product::<usize>(), shape.clone()));
+        inspection.unique_dtypes = dtypes.into_iter().collect();
+        inspection.unique_dtypes.sort();
+        inspection.shards = Some(summaries);
+
+        Ok(inspection)
+    }
+
+    fn inspect_file(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let file = std::fs::File::open(file_path)?;
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
+
+        let mut inspection = inspect_buffer(&buffer, detail, filter)?;
+        inspection.file_path = file_path.canonicalize()?;
+
+        Ok(inspection)
+    }
 }
 
 impl Handler for GGUFHandler {
@@ -84,94 +483,374 @@ impl Handler for GGUFHandler {
             .unwrap_or_default()
             .to_str()
             .unwrap_or("")
-            .to_ascii_lowercase()
-            == "gguf"
+            .eq_ignore_ascii_case("gguf")
     }
 
     fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
-        // GGUF are self contained
-        Ok(vec![file_path.to_path_buf()])
+        let Some(parts) = split_paths(file_path) else {
+            // GGUF are self contained
+            return Ok(vec![file_path.to_path_buf()]);
+        };
+        // a signature covers every part of a split model
+        if let Some(missing) = parts.iter().find(|part| !part.is_file()) {
+            anyhow::bail!("{} of the split model is missing", missing.display());
+        }
+        Ok(parts)
     }
 
     fn inspect(
         &self,
         file_path: &Path,
-        detail: crate::cli::DetailLevel,
+        detail: crate::core::DetailLevel,
         filter: Option<String>,
     ) -> anyhow::Result<crate::core::Inspection> {
-        let mut inspection = Inspection::default();
+        match split_paths(file_path) {
+            Some(parts) => self.inspect_split(&parts, detail, filter),
+            None => self.inspect_file(file_path, detail, filter),
+        }
+    }
 
-        let file = std::fs::File::open(file_path)?;
-        let buffer = unsafe {
-            memmap2::MmapOptions::new()
-                .map(&file)
-                .unwrap_or_else(|_| panic!("failed to map file {}", file_path.display()))
-        };
+    fn scan_header(&self, reader: &mut dyn ReadSeek) -> anyhow::Result<()> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != b"GGUF" {
+            anyhow::bail!("not a GGUF file");
+        }
+        let version = read_u32(reader)?;
+        parser::check_version(version)?;
+        let wide = version > 1;
+        let tensor_count = read_size(reader, wide)?;
+        let metadata_count = read_size(reader, wide)?;
 
-        inspection.file_path = file_path.canonicalize()?;
-        inspection.file_size = file.metadata()?.len();
+        for _ in 0..metadata_count {
+            // key, value type and value
+            let key_size = read_size(reader, wide)?;
+            scan_bytes(reader, key_size)?;
+            let value_type = read_u32(reader)?;
+            scan_value(reader, value_type, wide, 0)?;
+        }
+        for _ in 0..tensor_count {
+            // name, dimensions, type and offset
+            let name_size = read_size(reader, wide)?;
+            scan_bytes(reader, name_size)?;
+            let dimensions = read_u32(reader)? as u64;
+            let dimension_size = if wide { 8 } else { 4 };
+            scan_bytes(reader, dimension_size * dimensions + 4 + 8)?;
+        }
 
-        let gguf = gguf::GGUFFile::read(&buffer)
-            .map_err(|e| anyhow::anyhow!(format_parsing_error(&e.to_string())))?
-            .unwrap_or_else(|| panic!("failed to read GGUF file {}", file_path.display()));
+        Ok(())
+    }
 
-        inspection.file_type = FileType::GGUF;
-        inspection.version = format!("{}", gguf.header.version);
-        inspection.num_tensors = gguf.header.tensor_count as usize;
-        inspection.unique_shapes = gguf
-            .tensors
-            .par_iter()
-            .map(|t| t.dimensions.iter().map(|d| *d as usize).collect::<Vec<_>>())
-            .filter(|shape| !shape.is_empty())
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
+    fn tensor_data(
+        &self,
+        file_path: &Path,
+        filter: Option<String>,
+    ) -> anyhow::Result<Vec<TensorData>> {
+        if let Some(parts) = split_paths(file_path) {
+            let mut tensors = vec![];
+            for part in parts {
+                tensors.extend(file_tensor_data(&part, filter.clone())?);
+            }
+            return Ok(tensors);
+        }
+        file_tensor_data(file_path, filter)
+    }
 
-        // sort shapes by volume
-        inspection.unique_shapes.sort_by(|a, b| {
-            let size_a: usize = a.iter().product();
-            let size_b: usize = b.iter().product();
-            size_a.cmp(&size_b)
-        });
+    fn embedded_signature(&self, file_path: &Path) -> anyhow::Result<EmbeddedSignature> {
+        let mut file = std::fs::File::open(file_path)?;
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
 
-        inspection.unique_dtypes = gguf
-            .tensors
-            .par_iter()
-            .map(|t| format!("{:?}", t.tensor_type))
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
+        let header = Header::read(&buffer)?;
+        let manifest = embedded_signature_of(&header)?;
+        // the padding is serialized as zeros, anything else would not be signed
+        if buffer[header.tensor_infos.end..header.data_offset.min(buffer.len())]
+            .iter()
+            .any(|byte| *byte != 0)
+        {
+            anyhow::bail!("the padding before the tensor data is not zeroed");
+        }
 
-        inspection.data_size = gguf
-            .tensors
-            .par_iter()
-            .map(|t| {
-                if t.dimensions.is_empty() {
-                    0
-                } else {
-                    data_type_bits(t.tensor_type)
-                        * t.dimensions.iter().map(|d| *d as usize).product::<usize>()
-                }
-            })
-            .sum::<usize>()
-            / 8;
+        // the header without the signature followed by the untouched tensor data
+        let serialized = serialize_header(&buffer, &header, None);
+        file.seek(std::io::SeekFrom::Start(header.data_offset as u64))?;
 
-        for meta in &gguf.header.metadata {
-            inspection
-                .metadata
-                .insert(meta.key.clone(), format!("{:?}", meta.value));
-        }
+        Ok(EmbeddedSignature {
+            content: Box::new(std::io::Cursor::new(serialized).chain(file)),
+            manifest,
+        })
+    }
+
+    fn embed_signature(&self, file_path: &Path, manifest: &str) -> anyhow::Result<()> {
+        let mut file = std::fs::File::open(file_path)?;
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
 
-        if matches!(detail, DetailLevel::Full) {
-            inspection.tensors = Some(
-                gguf.tensors
-                    .par_iter()
-                    .filter(|t_info| filter.as_ref().map_or(true, |f| t_info.name.contains(f)))
-                    .map(build_tensor_descriptor)
-                    .collect(),
+        let header = Header::read(&buffer)?;
+        if header.version == 1 {
+            // strings would need 32 bits lengths
+            anyhow::bail!(
+                "signatures can't be embedded in GGUF version 1 files, convert the model again"
             );
         }
 
-        Ok(inspection)
+        let serialized = serialize_header(&buffer, &header, Some(manifest));
+        file.seek(std::io::SeekFrom::Start(header.data_offset as u64))?;
+
+        replace_file(file_path, &mut std::io::Cursor::new(serialized).chain(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stats;
+
+    pub(super) fn build_gguf() -> Vec<u8> {
+        let mut data = b"GGUF".to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        // tensor count, metadata count
+        data.extend_from_slice(&2u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+
+        push_string(&mut data, "general.name");
+        data.extend_from_slice(&8u32.to_le_bytes());
+        push_string(&mut data, "test");
+
+        for (name, elements, tensor_type, offset) in
+            [("weights", 8u64, 0u32, 0u64), ("quantized", 32, 8, 32)]
+        {
+            push_string(&mut data, name);
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(&elements.to_le_bytes());
+            data.extend_from_slice(&tensor_type.to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        data.resize(
+            data.len().div_ceil(DEFAULT_ALIGNMENT) * DEFAULT_ALIGNMENT,
+            0,
+        );
+
+        // first tensor: 8 F32 values
+        for value in [1.0f32, -1.0, 0.0, 0.0, 2.0, -2.0, f32::NAN, 0.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // second tensor: one Q8_0 block with scale 0.5 and values 0..32
+        data.extend_from_slice(&0x3800u16.to_le_bytes());
+        data.extend((0..32).map(|i| i as u8));
+
+        data
+    }
+
+    #[test]
+    fn test_json_value() {
+        assert_eq!(
+            json_value(&MetadataValue::Float32(1e-5)),
+            serde_json::json!(1e-5)
+        );
+        assert_eq!(
+            json_value(&MetadataValue::Array(
+                (0..4).map(MetadataValue::Int32).collect()
+            )),
+            serde_json::json!([0, 1, 2, 3])
+        );
+        assert_eq!(
+            json_value(&MetadataValue::Bool(true)),
+            serde_json::json!(true)
+        );
+    }
+
+    #[test]
+    fn test_scan_header() {
+        let data = build_gguf();
+        let data_offset = data.len() - 32 - 34;
+
+        let scanned = super::super::scanned(&GGUFHandler::new(), &data);
+        assert!(scanned[data_offset..].iter().all(|b| *b == 0));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, scanned).unwrap();
+        let inspection = GGUFHandler::new()
+            .inspect(&file_path, DetailLevel::Full, None)
+            .unwrap();
+        assert_eq!(inspection.num_tensors, 2);
+        assert_eq!(inspection.metadata.get("general.name").unwrap(), "test");
+    }
+
+    #[test]
+    fn test_inspect_sizes() {
+        let data = build_gguf();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, &data).unwrap();
+
+        let inspection = GGUFHandler::new()
+            .inspect(&file_path, DetailLevel::Full, None)
+            .unwrap();
+        // 8 F32 values and a Q8_0 block of a 2 bytes scale and 32 bytes
+        assert_eq!(inspection.data_size, 32 + 34);
+        assert_eq!(inspection.header_size + inspection.data_size, data.len());
+        let sizes: Vec<usize> = inspection
+            .tensors
+            .unwrap()
+            .iter()
+            .map(|tensor| tensor.size)
+            .collect();
+        assert_eq!(sizes, vec![32, 34]);
+    }
+
+    #[test]
+    fn test_tensor_data() {
+        let data = build_gguf();
+        let data_offset = data.len() as u64 - 32 - 34;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, data).unwrap();
+
+        let tensors = GGUFHandler::new().tensor_data(&file_path, None).unwrap();
+        assert_eq!(tensors.len(), 2);
+        assert_eq!(tensors[1].file_offset, Some(data_offset + 32));
+        let stats = stats::compute(&tensors);
+
+        let weights = stats.get("weights").unwrap();
+        assert_eq!(weights.count, 8);
+        assert_eq!(weights.nan_count, 1);
+        assert_eq!(weights.zero_fraction, 0.375);
+        assert!(weights.mean.abs() < 1e-12);
+
+        let quantized = stats.get("quantized").unwrap();
+        assert_eq!(quantized.count, 32);
+        assert_eq!(quantized.min, 0.0);
+        assert_eq!(quantized.max, 15.5);
+        assert_eq!(quantized.mean, 7.75);
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split_paths(Path::new("model.gguf")), None);
+        assert_eq!(split_paths(Path::new("model-00001-of-00001.gguf")), None);
+        assert_eq!(
+            split_paths(Path::new("dir/Llama-3-Q4_K_M-00002-of-00002.GGUF")),
+            Some(vec![
+                PathBuf::from("dir/Llama-3-Q4_K_M-00001-of-00002.GGUF"),
+                PathBuf::from("dir/Llama-3-Q4_K_M-00002-of-00002.GGUF"),
+            ])
+        );
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first = temp_dir.path().join("model-00001-of-00002.gguf");
+        let second = temp_dir.path().join("model-00002-of-00002.gguf");
+        std::fs::write(&first, build_gguf()).unwrap();
+        let handler = GGUFHandler::new();
+        assert!(handler.paths_to_sign(&first).is_err());
+
+        std::fs::write(&second, build_gguf()).unwrap();
+        assert_eq!(
+            handler.paths_to_sign(&second).unwrap(),
+            vec![first.clone(), second.clone()]
+        );
+        let inspection = handler.inspect(&second, DetailLevel::Full, None).unwrap();
+        assert_eq!(inspection.num_tensors, 4);
+        assert_eq!(inspection.tensors.unwrap().len(), 4);
+        assert_eq!(inspection.file_size, 2 * build_gguf().len() as u64);
+        assert_eq!(inspection.metadata.get("general.name").unwrap(), "test");
+        assert_eq!(inspection.shards.unwrap().len(), 2);
+        assert_eq!(handler.tensor_data(&first, None).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_embed_signature() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, build_gguf()).unwrap();
+
+        let handler = GGUFHandler::new();
+        let read_content = |embedded: EmbeddedSignature| {
+            let mut content = embedded.content;
+            let mut data = vec![];
+            content.read_to_end(&mut data).unwrap();
+            (embedded.manifest, data)
+        };
+
+        let (manifest, unsigned) = read_content(handler.embedded_signature(&file_path).unwrap());
+        assert!(manifest.is_none());
+        assert_eq!(unsigned, build_gguf());
+
+        handler.embed_signature(&file_path, "{}").unwrap();
+        // the file is still valid and so are its tensors
+        let tensors = handler.tensor_data(&file_path, None).unwrap();
+        assert_eq!(tensors.len(), 2);
+        assert_eq!(tensors[1].values().last(), Some(15.5));
+
+        // the signed content doesn't change when the signature is embedded or replaced
+        let (manifest, signed) = read_content(handler.embedded_signature(&file_path).unwrap());
+        assert_eq!(manifest.as_deref(), Some("{}"));
+        assert_eq!(signed, unsigned);
+
+        handler.embed_signature(&file_path, "[]").unwrap();
+        let (manifest, signed) = read_content(handler.embedded_signature(&file_path).unwrap());
+        assert_eq!(manifest.as_deref(), Some("[]"));
+        assert_eq!(signed, unsigned);
+    }
+
+    #[test]
+    fn test_embedded_signature_padding() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, build_gguf()).unwrap();
+
+        let handler = GGUFHandler::new();
+        handler.embed_signature(&file_path, "{}").unwrap();
+        let mut data = std::fs::read(&file_path).unwrap();
+        let header = Header::read(&data).unwrap();
+        assert!(header.data_offset > header.tensor_infos.end);
+
+        // bytes hidden in the padding before the tensor data are not signed
+        data[header.tensor_infos.end] = 1;
+        std::fs::write(&file_path, data).unwrap();
+        assert!(handler.embedded_signature(&file_path).is_err());
+    }
+
+    #[test]
+    fn test_embedded_signature_duplicate_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+
+        // a second signature entry, or a signature that is not a string
+        let with_entries = |entries: &[(u32, &[u8])]| {
+            let original = build_gguf();
+            let header = Header::read(&original).unwrap();
+            let mut data = original[..header.tensor_infos.end].to_vec();
+            let count = u64::from_le_bytes(data[16..24].try_into().unwrap());
+            data[16..24].copy_from_slice(&(count + entries.len() as u64).to_le_bytes());
+            let mut metadata = vec![];
+            for (value_type, value) in entries {
+                push_string(&mut metadata, EMBEDDED_SIGNATURE_KEY);
+                metadata.extend_from_slice(&value_type.to_le_bytes());
+                metadata.extend_from_slice(value);
+            }
+            data.splice(24..24, metadata);
+            data.resize(data.len().next_multiple_of(DEFAULT_ALIGNMENT), 0);
+            data.extend_from_slice(&original[header.data_offset..]);
+            data
+        };
+        let mut signature = vec![];
+        push_string(&mut signature, "{}");
+
+        let handler = GGUFHandler::new();
+        std::fs::write(&file_path, with_entries(&[(STRING_VALUE_TYPE, &signature)])).unwrap();
+        assert!(handler.embedded_signature(&file_path).is_ok());
+        std::fs::write(
+            &file_path,
+            with_entries(&[
+                (STRING_VALUE_TYPE, &signature),
+                (STRING_VALUE_TYPE, &signature),
+            ]),
+        )
+        .unwrap();
+        assert!(handler.embedded_signature(&file_path).is_err());
+        std::fs::write(&file_path, with_entries(&[(4, &1u32.to_le_bytes())])).unwrap();
+        assert!(handler.embedded_signature(&file_path).is_err());
     }
 }
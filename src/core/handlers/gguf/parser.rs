@@ -0,0 +1,594 @@
+use std::{fmt, ops::Range};
+
+use super::{
+    fixed_value_size, ALIGNMENT_KEY, ARRAY_VALUE_TYPE, DEFAULT_ALIGNMENT, STRING_VALUE_TYPE,
+};
+
+// arrays of arrays are allowed by the format but not used in practice, deeper ones are only
+// found in files crafted to exhaust the stack of parsers
+pub(super) const MAX_ARRAY_DEPTH: usize = 8;
+
+// why the header can't be walked any further
+pub(super) enum Anomaly {
+    Truncated,
+    UnknownType(u32),
+    TooDeep,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::Truncated => write!(f, "exceeds the file"),
+            Anomaly::UnknownType(value_type) => write!(f, "has unknown type {}", value_type),
+            Anomaly::TooDeep => write!(f, "has arrays nested too deeply"),
+        }
+    }
+}
+
+/// Type of the tensor data, numbered as in ggml.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct GGMLType(pub u32);
+
+impl GGMLType {
+    pub const F32: Self = Self(0);
+    pub const F16: Self = Self(1);
+    pub const Q4_0: Self = Self(2);
+    pub const Q8_0: Self = Self(8);
+    pub const I8: Self = Self(24);
+    pub const I16: Self = Self(25);
+    pub const I32: Self = Self(26);
+    pub const I64: Self = Self(27);
+    pub const F64: Self = Self(28);
+    pub const BF16: Self = Self(30);
+
+    // name, number of elements per block and size of a block
+    fn layout(&self) -> Option<(&'static str, u64, u64)> {
+        match self.0 {
+            0 => Some(("F32", 1, 4)),
+            1 => Some(("F16", 1, 2)),
+            2 => Some(("Q4_0", 32, 18)),
+            3 => Some(("Q4_1", 32, 20)),
+            6 => Some(("Q5_0", 32, 22)),
+            7 => Some(("Q5_1", 32, 24)),
+            8 => Some(("Q8_0", 32, 34)),
+            9 => Some(("Q8_1", 32, 36)),
+            10 => Some(("Q2_K", 256, 84)),
+            11 => Some(("Q3_K", 256, 110)),
+            12 => Some(("Q4_K", 256, 144)),
+            13 => Some(("Q5_K", 256, 176)),
+            14 => Some(("Q6_K", 256, 210)),
+            15 => Some(("Q8_K", 256, 292)),
+            16 => Some(("IQ2_XXS", 256, 66)),
+            17 => Some(("IQ2_XS", 256, 74)),
+            18 => Some(("IQ3_XXS", 256, 98)),
+            19 => Some(("IQ1_S", 256, 50)),
+            20 => Some(("IQ4_NL", 32, 18)),
+            21 => Some(("IQ3_S", 256, 110)),
+            22 => Some(("IQ2_S", 256, 82)),
+            23 => Some(("IQ4_XS", 256, 136)),
+            24 => Some(("I8", 1, 1)),
+            25 => Some(("I16", 1, 2)),
+            26 => Some(("I32", 1, 4)),
+            27 => Some(("I64", 1, 8)),
+            28 => Some(("F64", 1, 8)),
+            29 => Some(("IQ1_M", 256, 56)),
+            30 => Some(("BF16", 1, 2)),
+            34 => Some(("TQ1_0", 256, 54)),
+            35 => Some(("TQ2_0", 256, 66)),
+            39 => Some(("MXFP4", 32, 17)),
+            _ => None,
+        }
+    }
+
+    /// Number of elements per block and size of a block, blocks of a single element for the
+    /// plain types.
+    pub fn block_size(&self) -> Option<(u64, u64)> {
+        self.layout().map(|(_, elements, bytes)| (elements, bytes))
+    }
+}
+
+impl fmt::Display for GGMLType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.layout() {
+            Some((name, _, _)) => write!(f, "{}", name),
+            None => write!(f, "UNKNOWN_{}", self.0),
+        }
+    }
+}
+
+/// A typed metadata value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum MetadataValue {
+    Uint8(u8),
+    Int8(i8),
+    Uint16(u16),
+    Int16(i16),
+    Uint32(u32),
+    Int32(i32),
+    Float32(f32),
+    Bool(bool),
+    String(String),
+    Array(Vec<MetadataValue>),
+    Uint64(u64),
+    Int64(i64),
+    Float64(f64),
+}
+
+impl MetadataValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetadataValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// arrays are shortened to their first values, such as the tokens of the vocabulary
+impl fmt::Display for MetadataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataValue::Uint8(v) => write!(f, "{}", v),
+            MetadataValue::Int8(v) => write!(f, "{}", v),
+            MetadataValue::Uint16(v) => write!(f, "{}", v),
+            MetadataValue::Int16(v) => write!(f, "{}", v),
+            MetadataValue::Uint32(v) => write!(f, "{}", v),
+            MetadataValue::Int32(v) => write!(f, "{}", v),
+            MetadataValue::Float32(v) => write!(f, "{}", v),
+            MetadataValue::Bool(v) => write!(f, "{}", v),
+            MetadataValue::String(v) => write!(f, "{}", v),
+            MetadataValue::Uint64(v) => write!(f, "{}", v),
+            MetadataValue::Int64(v) => write!(f, "{}", v),
+            MetadataValue::Float64(v) => write!(f, "{}", v),
+            MetadataValue::Array(values) => {
+                for (index, value) in values.iter().take(3).enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                if values.len() > 3 {
+                    write!(f, ", ...")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A metadata entry and the range of its bytes in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MetadataEntry {
+    pub key: String,
+    pub value: MetadataValue,
+    pub range: Range<usize>,
+}
+
+/// The info of a tensor and the range of its bytes in the file, the offset of the data being the
+/// last 8 bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TensorInfo {
+    pub name: String,
+    pub dimensions: Vec<u64>,
+    pub tensor_type: GGMLType,
+    /// Offset of the data, relative to the tensor data section.
+    pub offset: u64,
+    pub info: Range<usize>,
+}
+
+impl TensorInfo {
+    pub fn elements(&self) -> Option<u64> {
+        self.dimensions
+            .iter()
+            .try_fold(1u64, |elements, dimension| elements.checked_mul(*dimension))
+    }
+
+    /// Returns the size of the data, if the type is known and the size doesn't overflow.
+    pub fn data_size(&self) -> Option<u64> {
+        let (elements_per_block, bytes_per_block) = self.tensor_type.block_size()?;
+        self.elements()?
+            .div_ceil(elements_per_block)
+            .checked_mul(bytes_per_block)
+    }
+}
+
+// values that can be walked without being decoded
+pub(super) enum Value<'a> {
+    Uint32(u32),
+    String(&'a [u8]),
+    Other,
+}
+
+pub(super) struct Reader<'a> {
+    pub data: &'a [u8],
+    pub offset: usize,
+    // sizes and counts are 32 bits in the first version
+    version: u32,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            version: 3,
+        }
+    }
+
+    pub fn bytes(&mut self, size: u64) -> Result<&'a [u8], Anomaly> {
+        let end = usize::try_from(size)
+            .ok()
+            .and_then(|size| self.offset.checked_add(size))
+            .filter(|end| *end <= self.data.len())
+            .ok_or(Anomaly::Truncated)?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    // the bytes of a value of fixed size
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Anomaly> {
+        Ok(self.bytes(N as u64)?.try_into().unwrap())
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Anomaly> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Anomaly> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    // a length or a count
+    pub fn size(&mut self) -> Result<u64, Anomaly> {
+        if self.version == 1 {
+            self.u32().map(u64::from)
+        } else {
+            self.u64()
+        }
+    }
+
+    pub fn string(&mut self) -> Result<&'a [u8], Anomaly> {
+        let size = self.size()?;
+        self.bytes(size)
+    }
+
+    /// Reads the magic and the version, returns the number of tensors and of metadata entries.
+    pub fn preamble(&mut self) -> anyhow::Result<(u64, u64)> {
+        let (Ok(magic), Ok(version)) = (self.bytes(4), self.u32()) else {
+            anyhow::bail!("not a GGUF file");
+        };
+        if magic != b"GGUF" {
+            anyhow::bail!("not a GGUF file");
+        }
+        check_version(version)?;
+        self.version = version;
+
+        match (self.size(), self.size()) {
+            (Ok(tensor_count), Ok(metadata_count)) => Ok((tensor_count, metadata_count)),
+            _ => anyhow::bail!("not a GGUF file"),
+        }
+    }
+
+    // walks through a value, only decoding the ones needed to check the header
+    pub fn value(&mut self, value_type: u32) -> Result<Value<'a>, Anomaly> {
+        self.walk_value(value_type, 0)
+    }
+
+    fn walk_value(&mut self, value_type: u32, depth: usize) -> Result<Value<'a>, Anomaly> {
+        if let Some(size) = fixed_value_size(value_type) {
+            let bytes = self.bytes(size)?;
+            return Ok(match value_type {
+                4 => Value::Uint32(u32::from_le_bytes(bytes.try_into().unwrap())),
+                _ => Value::Other,
+            });
+        }
+
+        match value_type {
+            STRING_VALUE_TYPE => Ok(Value::String(self.string()?)),
+            ARRAY_VALUE_TYPE if depth >= MAX_ARRAY_DEPTH => Err(Anomaly::TooDeep),
+            ARRAY_VALUE_TYPE => {
+                let element_type = self.u32()?;
+                let count = self.size()?;
+                match fixed_value_size(element_type) {
+                    Some(size) => {
+                        self.bytes(size.checked_mul(count).ok_or(Anomaly::Truncated)?)?;
+                    }
+                    None if element_type == STRING_VALUE_TYPE
+                        || element_type == ARRAY_VALUE_TYPE =>
+                    {
+                        for _ in 0..count {
+                            self.walk_value(element_type, depth + 1)?;
+                        }
+                    }
+                    None => return Err(Anomaly::UnknownType(element_type)),
+                }
+                Ok(Value::Other)
+            }
+            _ => Err(Anomaly::UnknownType(value_type)),
+        }
+    }
+
+    /// Decodes a value.
+    pub fn typed_value(&mut self, value_type: u32) -> Result<MetadataValue, Anomaly> {
+        self.read_value(value_type, 0)
+    }
+
+    fn read_value(&mut self, value_type: u32, depth: usize) -> Result<MetadataValue, Anomaly> {
+        Ok(match value_type {
+            0 => MetadataValue::Uint8(u8::from_le_bytes(self.array()?)),
+            1 => MetadataValue::Int8(i8::from_le_bytes(self.array()?)),
+            2 => MetadataValue::Uint16(u16::from_le_bytes(self.array()?)),
+            3 => MetadataValue::Int16(i16::from_le_bytes(self.array()?)),
+            4 => MetadataValue::Uint32(u32::from_le_bytes(self.array()?)),
+            5 => MetadataValue::Int32(i32::from_le_bytes(self.array()?)),
+            6 => MetadataValue::Float32(f32::from_le_bytes(self.array()?)),
+            7 => MetadataValue::Bool(self.array::<1>()?[0] != 0),
+            STRING_VALUE_TYPE => {
+                MetadataValue::String(String::from_utf8_lossy(self.string()?).to_string())
+            }
+            ARRAY_VALUE_TYPE if depth >= MAX_ARRAY_DEPTH => return Err(Anomaly::TooDeep),
+            ARRAY_VALUE_TYPE => {
+                let element_type = self.u32()?;
+                let count = self.size()?;
+                // not preallocated, the count is checked against the file as the values are read
+                let mut values = vec![];
+                for _ in 0..count {
+                    values.push(self.read_value(element_type, depth + 1)?);
+                }
+                MetadataValue::Array(values)
+            }
+            10 => MetadataValue::Uint64(u64::from_le_bytes(self.array()?)),
+            11 => MetadataValue::Int64(i64::from_le_bytes(self.array()?)),
+            12 => MetadataValue::Float64(f64::from_le_bytes(self.array()?)),
+            _ => return Err(Anomaly::UnknownType(value_type)),
+        })
+    }
+
+    pub fn tensor_info(&mut self) -> Result<TensorInfo, Anomaly> {
+        let start = self.offset;
+        let name = String::from_utf8_lossy(self.string()?).to_string();
+        let dimension_count = self.u32()?;
+        let mut dimensions = vec![];
+        for _ in 0..dimension_count {
+            dimensions.push(self.size()?);
+        }
+        let tensor_type = GGMLType(self.u32()?);
+        let offset = self.u64()?;
+        Ok(TensorInfo {
+            name,
+            dimensions,
+            tensor_type,
+            offset,
+            info: start..self.offset,
+        })
+    }
+}
+
+/// Fails for the versions that can't be parsed, including the ones of big endian files.
+pub(super) fn check_version(version: u32) -> anyhow::Result<()> {
+    if (1..=3).contains(&version) {
+        Ok(())
+    } else if (1..=3).contains(&version.swap_bytes()) {
+        anyhow::bail!("big endian GGUF files are not supported")
+    } else {
+        anyhow::bail!("GGUF version {} is not supported", version)
+    }
+}
+
+/// Everything preceding the tensor data of a GGUF file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Header {
+    pub version: u32,
+    pub metadata: Vec<MetadataEntry>,
+    pub tensors: Vec<TensorInfo>,
+    /// Range of the tensor infos in the file.
+    pub tensor_infos: Range<usize>,
+    pub alignment: usize,
+    /// Offset of the tensor data section, tensor offsets are relative to it.
+    pub data_offset: usize,
+}
+
+impl Header {
+    /// Parses the header from the bytes of the file, usually memory mapped. The values are checked
+    /// against the size of the file as they are read, so that malformed files fail instead of
+    /// allocating or reading out of bounds.
+    pub fn read(data: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader::new(data);
+        let (tensor_count, metadata_count) = reader.preamble()?;
+
+        let mut metadata = vec![];
+        for index in 0..metadata_count {
+            let start = reader.offset;
+            let entry = (|| {
+                let key = String::from_utf8_lossy(reader.string()?).to_string();
+                let value_type = reader.u32()?;
+                Ok::<_, Anomaly>((key, reader.typed_value(value_type)?))
+            })();
+            let (key, value) = entry.map_err(|anomaly| {
+                anyhow::anyhow!(
+                    "metadata entry {} of {} at offset {} {}",
+                    index,
+                    metadata_count,
+                    start,
+                    anomaly
+                )
+            })?;
+            metadata.push(MetadataEntry {
+                key,
+                value,
+                range: start..reader.offset,
+            });
+        }
+
+        let infos_start = reader.offset;
+        let mut tensors = vec![];
+        for index in 0..tensor_count {
+            let start = reader.offset;
+            tensors.push(reader.tensor_info().map_err(|anomaly| {
+                anyhow::anyhow!(
+                    "tensor info {} of {} at offset {} {}",
+                    index,
+                    tensor_count,
+                    start,
+                    anomaly
+                )
+            })?);
+        }
+
+        let alignment = metadata
+            .iter()
+            .find(|entry| entry.key == ALIGNMENT_KEY)
+            .and_then(|entry| match entry.value {
+                MetadataValue::Uint32(alignment) => Some(alignment as usize),
+                _ => None,
+            })
+            .filter(|alignment| *alignment > 0)
+            .unwrap_or(DEFAULT_ALIGNMENT);
+
+        Ok(Self {
+            version: reader.version,
+            metadata,
+            tensors,
+            tensor_infos: infos_start..reader.offset,
+            alignment,
+            data_offset: reader.offset.next_multiple_of(alignment),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.metadata
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+    }
+
+    /// Returns the range of the data of the tensor in the file, if its size is known and it
+    /// doesn't overflow.
+    pub fn data_range(&self, tensor: &TensorInfo) -> Option<Range<usize>> {
+        let start = usize::try_from(tensor.offset)
+            .ok()?
+            .checked_add(self.data_offset)?;
+        let size = usize::try_from(tensor.data_size()?).ok()?;
+        Some(start..start.checked_add(size)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::handlers::gguf::{push_string, tests::build_gguf};
+
+    #[test]
+    fn test_read() {
+        let data = build_gguf();
+        let header = Header::read(&data).unwrap();
+
+        assert_eq!(header.version, 3);
+        assert_eq!(
+            header.get("general.name").and_then(|v| v.as_str()),
+            Some("test")
+        );
+        assert_eq!(header.data_offset % DEFAULT_ALIGNMENT, 0);
+        assert_eq!(data.len() - header.data_offset, 32 + 34);
+        let types: Vec<String> = header
+            .tensors
+            .iter()
+            .map(|tensor| tensor.tensor_type.to_string())
+            .collect();
+        assert_eq!(types, vec!["F32", "Q8_0"]);
+        assert_eq!(
+            header.data_range(&header.tensors[1]),
+            Some(header.data_offset + 32..data.len())
+        );
+
+        // sizes and counts are 32 bits in the first version
+        let mut data = b"GGUF".to_vec();
+        data.extend(1u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(4u32.to_le_bytes());
+        data.extend(b"keys");
+        data.extend(ARRAY_VALUE_TYPE.to_le_bytes());
+        data.extend(4u32.to_le_bytes());
+        data.extend(2u32.to_le_bytes());
+        data.extend([7u8, 0, 0, 0, 9, 0, 0, 0]);
+        data.extend(1u32.to_le_bytes());
+        data.push(b'a');
+        data.extend(2u32.to_le_bytes());
+        data.extend([4u32, 8].iter().flat_map(|d| d.to_le_bytes()));
+        data.extend(30u32.to_le_bytes());
+        data.extend(0u64.to_le_bytes());
+        let header = Header::read(&data).unwrap();
+        assert_eq!(header.get("keys").unwrap().to_string(), "7, 9");
+        assert_eq!(header.tensors[0].dimensions, vec![4, 8]);
+        assert_eq!(header.tensors[0].data_size(), Some(64));
+        assert_eq!(header.tensor_infos.end, data.len());
+
+        let mut data = build_gguf();
+        data[4..8].copy_from_slice(&3u32.to_be_bytes());
+        assert!(Header::read(&data)
+            .unwrap_err()
+            .to_string()
+            .contains("big endian"));
+    }
+
+    // a pseudo random generator, the same inputs being tested on every run
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn test_read_malformed() {
+        let data = build_gguf();
+        let header_size = Header::read(&data).unwrap().tensor_infos.end;
+
+        // truncated at every byte of the header
+        for size in 0..header_size {
+            assert!(Header::read(&data[..size]).is_err(), "{} bytes", size);
+        }
+
+        // huge counts and lengths fail without allocating
+        let mut huge = data.clone();
+        huge[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Header::read(&huge).is_err());
+        let mut huge = data.clone();
+        huge[24..32].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        assert!(Header::read(&huge).is_err());
+
+        // arrays of arrays, until the stack of a recursive parser overflows
+        let mut nested = b"GGUF".to_vec();
+        nested.extend(3u32.to_le_bytes());
+        nested.extend(0u64.to_le_bytes());
+        nested.extend(1u64.to_le_bytes());
+        push_string(&mut nested, "nested");
+        nested.extend(ARRAY_VALUE_TYPE.to_le_bytes());
+        for _ in 0..100_000 {
+            nested.extend(ARRAY_VALUE_TYPE.to_le_bytes());
+            nested.extend(1u64.to_le_bytes());
+        }
+        assert!(Header::read(&nested)
+            .unwrap_err()
+            .to_string()
+            .contains("nested too deeply"));
+
+        // random bytes of the header replaced, the parser either fails or returns ranges
+        // within the file
+        let mut state = 0x2545f4914f6cdd1d;
+        for _ in 0..20_000 {
+            let mut mutated = data.clone();
+            for _ in 0..1 + xorshift(&mut state) % 4 {
+                let index = (xorshift(&mut state) % header_size as u64) as usize;
+                mutated[index] = xorshift(&mut state) as u8;
+            }
+            if let Ok(header) = Header::read(&mutated) {
+                assert!(header.tensor_infos.end <= mutated.len());
+                for entry in &header.metadata {
+                    assert!(entry.range.end <= header.tensor_infos.start);
+                }
+            }
+        }
+    }
+}
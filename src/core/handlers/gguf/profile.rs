@@ -0,0 +1,133 @@
+use super::{MetadataEntry, MetadataValue};
+
+use crate::core::profile::ModelProfile;
+
+fn as_u64(value: &MetadataValue) -> Option<u64> {
+    match value {
+        MetadataValue::Uint8(v) => Some(*v as u64),
+        MetadataValue::Uint16(v) => Some(*v as u64),
+        MetadataValue::Uint32(v) => Some(*v as u64),
+        MetadataValue::Uint64(v) => Some(*v),
+        MetadataValue::Int8(v) => u64::try_from(*v).ok(),
+        MetadataValue::Int16(v) => u64::try_from(*v).ok(),
+        MetadataValue::Int32(v) => u64::try_from(*v).ok(),
+        MetadataValue::Int64(v) => u64::try_from(*v).ok(),
+        // per layer values, such as the number of KV heads of models mixing attention types
+        MetadataValue::Array(values) => values.iter().filter_map(as_u64).max(),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &MetadataValue) -> Option<f64> {
+    match value {
+        MetadataValue::Float32(v) => Some(*v as f64),
+        MetadataValue::Float64(v) => Some(*v),
+        _ => as_u64(value).map(|v| v as f64),
+    }
+}
+
+/// Builds the profile of the model from the typed values of the metadata of its architecture.
+pub(crate) fn profile(metadata: &[MetadataEntry]) -> Option<ModelProfile> {
+    let get = |key: &str| {
+        metadata
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+    };
+    let Some(MetadataValue::String(arch)) = get("general.architecture") else {
+        return None;
+    };
+    let integer = |key: &str| get(&format!("{}.{}", arch, key)).and_then(as_u64);
+    let float = |key: &str| get(&format!("{}.{}", arch, key)).and_then(as_f64);
+
+    let vocab_size = integer("vocab_size").or_else(|| match get("tokenizer.ggml.tokens") {
+        Some(MetadataValue::Array(tokens)) => Some(tokens.len() as u64),
+        _ => None,
+    });
+    let rope_scaling_type = match get(&format!("{}.rope.scaling.type", arch)) {
+        Some(MetadataValue::String(scaling)) if scaling != "none" => Some(scaling.clone()),
+        _ => None,
+    };
+
+    Some(ModelProfile {
+        architecture: arch.clone(),
+        context_length: integer("context_length"),
+        embedding_length: integer("embedding_length"),
+        block_count: integer("block_count"),
+        feed_forward_length: integer("feed_forward_length"),
+        head_count: integer("attention.head_count"),
+        head_count_kv: integer("attention.head_count_kv"),
+        key_length: integer("attention.key_length"),
+        value_length: integer("attention.value_length"),
+        rope_dimension_count: integer("rope.dimension_count"),
+        rope_freq_base: float("rope.freq_base"),
+        rope_scaling_factor: rope_scaling_type.as_ref().and(float("rope.scaling.factor")),
+        rope_original_context_length: rope_scaling_type
+            .as_ref()
+            .and(integer("rope.scaling.original_context_length")),
+        rope_scaling_type,
+        vocab_size,
+        expert_count: integer("expert_count"),
+        expert_used_count: integer("expert_used_count"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(key: &str, value: MetadataValue) -> MetadataEntry {
+        MetadataEntry {
+            key: key.to_string(),
+            value,
+            range: 0..0,
+        }
+    }
+
+    #[test]
+    fn test_profile() {
+        let tokens = ["<s>", "</s>", "a", "b"]
+            .iter()
+            .map(|token| MetadataValue::String(token.to_string()))
+            .collect();
+        let metadata = vec![
+            meta(
+                "general.architecture",
+                MetadataValue::String("qwen3moe".to_string()),
+            ),
+            meta("qwen3moe.context_length", MetadataValue::Uint32(32768)),
+            meta(
+                "qwen3moe.attention.head_count_kv",
+                MetadataValue::Array(vec![MetadataValue::Uint32(4), MetadataValue::Uint32(8)]),
+            ),
+            meta("qwen3moe.rope.freq_base", MetadataValue::Float32(1000000.0)),
+            meta(
+                "qwen3moe.rope.scaling.type",
+                MetadataValue::String("yarn".to_string()),
+            ),
+            meta("qwen3moe.rope.scaling.factor", MetadataValue::Float32(4.0)),
+            meta("qwen3moe.expert_count", MetadataValue::Uint32(128)),
+            meta("tokenizer.ggml.tokens", MetadataValue::Array(tokens)),
+        ];
+
+        let found = profile(&metadata).unwrap();
+        assert_eq!(
+            found,
+            ModelProfile {
+                architecture: "qwen3moe".to_string(),
+                context_length: Some(32768),
+                head_count_kv: Some(8),
+                rope_freq_base: Some(1000000.0),
+                rope_scaling_type: Some("yarn".to_string()),
+                rope_scaling_factor: Some(4.0),
+                vocab_size: Some(4),
+                expert_count: Some(128),
+                ..Default::default()
+            }
+        );
+        assert!(found
+            .rows()
+            .contains(&("rope scaling", "yarn x4".to_string())));
+        assert_eq!(profile(&metadata[1..]), None);
+    }
+}
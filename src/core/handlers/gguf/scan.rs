@@ -0,0 +1,362 @@
+use std::path::Path;
+
+use super::{
+    parser::{Anomaly, Reader, TensorInfo, Value},
+    ALIGNMENT_KEY, DEFAULT_ALIGNMENT,
+};
+use crate::core::scan::{Issue, Safety};
+
+const CHAT_TEMPLATE_KEY: &str = "tokenizer.chat_template";
+// chat templates are a few KB at most
+const MAX_CHAT_TEMPLATE_SIZE: usize = 64 * 1024;
+// Jinja constructs reaching Python objects, as used by template injection payloads
+const TEMPLATE_CODE_PATTERNS: &[&str] = &[
+    "__class__",
+    "__globals__",
+    "__subclasses__",
+    "__builtins__",
+    "__import__",
+    "__mro__",
+    "__base__",
+    "__init__",
+    "popen",
+    "subprocess",
+    "os.system",
+    "eval(",
+    "exec(",
+];
+
+fn issue(
+    rule_id: &'static str,
+    safety: Safety,
+    message: String,
+    location: &str,
+    offset: usize,
+) -> Issue {
+    Issue {
+        rule_id,
+        safety,
+        message,
+        location: location.to_string(),
+        offset: Some(offset as u64),
+    }
+}
+
+fn check_chat_template(key: &str, template: &[u8], offset: usize) -> Option<Issue> {
+    let template = String::from_utf8_lossy(template);
+    if let Some(pattern) = TEMPLATE_CODE_PATTERNS
+        .iter()
+        .find(|pattern| template.contains(**pattern))
+    {
+        Some(issue(
+            "chat-template-code",
+            Safety::Dangerous,
+            format!("chat template referencing {}", pattern),
+            key,
+            offset,
+        ))
+    } else if template.len() > MAX_CHAT_TEMPLATE_SIZE {
+        Some(issue(
+            "large-chat-template",
+            Safety::Suspicious,
+            format!("chat template of {} bytes", template.len()),
+            key,
+            offset,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Returns the offset of the data following the data of the tensors, if it's not zero padding to
+/// the alignment.
+pub(super) fn unreferenced_data_offset(data: &[u8], data_end: u64, alignment: u64) -> Option<u64> {
+    let file_size = data.len() as u64;
+    if file_size <= data_end {
+        return None;
+    }
+    let is_padding = file_size <= data_end.next_multiple_of(alignment)
+        && data[data_end as usize..].iter().all(|byte| *byte == 0);
+    (!is_padding).then_some(data_end)
+}
+
+// checks that the tensor data is within the file, doesn't overlap and that no data follows it
+fn check_tensors(
+    tensors: &[TensorInfo],
+    data_offset: u64,
+    alignment: u64,
+    data: &[u8],
+) -> Vec<Issue> {
+    let file_size = data.len() as u64;
+    let mut issues = vec![];
+    let mut ranges = vec![];
+
+    for tensor in tensors {
+        let Some(size) = tensor.data_size() else {
+            issues.push(issue(
+                "unknown-tensor-type",
+                Safety::Suspicious,
+                format!("unknown type {} or invalid size", tensor.tensor_type.0),
+                &tensor.name,
+                tensor.info.start,
+            ));
+            continue;
+        };
+        match data_offset
+            .checked_add(tensor.offset)
+            .and_then(|start| Some((start, start.checked_add(size)?)))
+            .filter(|(_, end)| *end <= file_size)
+        {
+            Some((start, end)) => ranges.push((start, end, tensor)),
+            None => issues.push(issue(
+                "invalid-tensor-offset",
+                Safety::Dangerous,
+                format!(
+                    "data at offset {} of {} bytes exceeding the file",
+                    tensor.offset, size
+                ),
+                &tensor.name,
+                tensor.info.start,
+            )),
+        }
+    }
+
+    ranges.sort_by_key(|(start, end, _)| (*start, *end));
+    let mut last: Option<(u64, &TensorInfo)> = None;
+    for (start, end, tensor) in &ranges {
+        if let Some((last_end, last_tensor)) = last {
+            // empty tensors can share their offset
+            if *start < last_end && start != end {
+                issues.push(issue(
+                    "invalid-tensor-offset",
+                    Safety::Dangerous,
+                    format!("data overlapping the data of {}", last_tensor.name),
+                    &tensor.name,
+                    tensor.info.start,
+                ));
+            }
+            if *end <= last_end {
+                continue;
+            }
+        }
+        last = Some((*end, tensor));
+    }
+
+    let data_end = last.map(|(end, _)| end).unwrap_or(data_offset);
+    if let Some(offset) = unreferenced_data_offset(data, data_end, alignment) {
+        issues.push(issue(
+            "unreferenced-data",
+            Safety::Suspicious,
+            format!(
+                "{} bytes following the data of the {} declared tensors",
+                file_size - offset,
+                tensors.len()
+            ),
+            "",
+            offset as usize,
+        ));
+    }
+
+    issues
+}
+
+/// Statically scans the header of the model for malformed or unusual metadata and tensor infos
+/// that could exploit parsers, and for code in the chat templates.
+pub(crate) fn scan(file_path: &Path) -> anyhow::Result<Vec<Issue>> {
+    let file = std::fs::File::open(file_path)?;
+    let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
+    let mut reader = Reader::new(&data);
+
+    let (tensor_count, metadata_count) = reader.preamble()?;
+
+    let mut issues = vec![];
+    let mut alignment = DEFAULT_ALIGNMENT as u64;
+
+    for index in 0..metadata_count {
+        let offset = reader.offset;
+        let key = match reader.string() {
+            Ok(key) => String::from_utf8_lossy(key).to_string(),
+            Err(_) => {
+                issues.push(issue(
+                    "count-mismatch",
+                    Safety::Dangerous,
+                    format!(
+                        "header declaring {} metadata entries but holding {}",
+                        metadata_count, index
+                    ),
+                    "",
+                    offset,
+                ));
+                return Ok(issues);
+            }
+        };
+
+        match reader.u32().and_then(|value_type| reader.value(value_type)) {
+            Ok(Value::Uint32(value)) if key == ALIGNMENT_KEY && value > 0 => {
+                alignment = value as u64;
+            }
+            Ok(Value::String(template))
+                if key == CHAT_TEMPLATE_KEY
+                    || key.starts_with(&format!("{}.", CHAT_TEMPLATE_KEY)) =>
+            {
+                issues.extend(check_chat_template(&key, template, offset));
+            }
+            Ok(_) => {}
+            Err(anomaly @ (Anomaly::UnknownType(_) | Anomaly::TooDeep)) => {
+                issues.push(issue(
+                    "invalid-metadata-type",
+                    Safety::Dangerous,
+                    format!("value that {}", anomaly),
+                    &key,
+                    offset,
+                ));
+                return Ok(issues);
+            }
+            Err(Anomaly::Truncated) => {
+                issues.push(issue(
+                    "count-mismatch",
+                    Safety::Dangerous,
+                    "value exceeding the file".to_string(),
+                    &key,
+                    offset,
+                ));
+                return Ok(issues);
+            }
+        }
+    }
+
+    let mut tensors = vec![];
+    for index in 0..tensor_count {
+        let offset = reader.offset;
+        match reader.tensor_info() {
+            Ok(tensor) => tensors.push(tensor),
+            Err(_) => {
+                issues.push(issue(
+                    "count-mismatch",
+                    Safety::Dangerous,
+                    format!(
+                        "header declaring {} tensors but holding {}",
+                        tensor_count, index
+                    ),
+                    "",
+                    offset,
+                ));
+                return Ok(issues);
+            }
+        }
+    }
+
+    let data_offset = (reader.offset as u64).next_multiple_of(alignment);
+    issues.extend(check_tensors(&tensors, data_offset, alignment, &data));
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::handlers::gguf::STRING_VALUE_TYPE;
+
+    // a GGUF file with the given metadata entries and F32 tensors
+    fn gguf(
+        metadata: &[(&str, u32, Vec<u8>)],
+        tensors: &[(&str, u64, u64)],
+        data: usize,
+    ) -> Vec<u8> {
+        let mut buffer = b"GGUF".to_vec();
+        buffer.extend(3u32.to_le_bytes());
+        buffer.extend((tensors.len() as u64).to_le_bytes());
+        buffer.extend((metadata.len() as u64).to_le_bytes());
+        for (key, value_type, value) in metadata {
+            super::super::push_string(&mut buffer, key);
+            buffer.extend(value_type.to_le_bytes());
+            buffer.extend(value);
+        }
+        for (name, elements, offset) in tensors {
+            super::super::push_string(&mut buffer, name);
+            buffer.extend(1u32.to_le_bytes());
+            buffer.extend(elements.to_le_bytes());
+            buffer.extend(0u32.to_le_bytes());
+            buffer.extend(offset.to_le_bytes());
+        }
+        buffer.resize(buffer.len().next_multiple_of(DEFAULT_ALIGNMENT) + data, 0);
+        buffer
+    }
+
+    fn string_value(value: &str) -> Vec<u8> {
+        let mut buffer = vec![];
+        super::super::push_string(&mut buffer, value);
+        buffer
+    }
+
+    fn rules_of(data: &[u8]) -> Vec<(&'static str, String)> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, data).unwrap();
+        scan(&file_path)
+            .unwrap()
+            .into_iter()
+            .map(|issue| (issue.rule_id, issue.location))
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_valid() {
+        let data = gguf(
+            &[
+                ("general.name", STRING_VALUE_TYPE, string_value("llama")),
+                (
+                    CHAT_TEMPLATE_KEY,
+                    STRING_VALUE_TYPE,
+                    string_value("{% for message in messages %}{{ message.content }}{% endfor %}"),
+                ),
+            ],
+            &[("a", 4, 0), ("b", 8, 32)],
+            64,
+        );
+        assert!(rules_of(&data).is_empty());
+    }
+
+    #[test]
+    fn test_unreferenced_data_offset() {
+        let mut data = vec![0u8; 40];
+        // zero padding to the alignment
+        assert_eq!(unreferenced_data_offset(&data, 36, 32), None);
+        assert_eq!(unreferenced_data_offset(&data, 40, 32), None);
+        // bytes appended in the padding, or after it
+        data[38] = 1;
+        assert_eq!(unreferenced_data_offset(&data, 36, 32), Some(36));
+        assert_eq!(unreferenced_data_offset(&[0u8; 70], 36, 32), Some(36));
+    }
+
+    #[test]
+    fn test_scan_anomalies() {
+        let template = "{{ self.__init__.__globals__.__builtins__.__import__('os').popen('id') }}";
+        let data = gguf(
+            &[(CHAT_TEMPLATE_KEY, STRING_VALUE_TYPE, string_value(template))],
+            // b overlaps a, c exceeds the file
+            &[("a", 8, 0), ("b", 4, 16), ("c", 4, 1024)],
+            64,
+        );
+        assert_eq!(
+            rules_of(&data),
+            vec![
+                ("chat-template-code", CHAT_TEMPLATE_KEY.to_string()),
+                ("invalid-tensor-offset", "c".to_string()),
+                ("invalid-tensor-offset", "b".to_string()),
+                ("unreferenced-data", "".to_string()),
+            ]
+        );
+
+        let data = gguf(&[("general.name", 13, vec![0u8; 8])], &[("a", 4, 0)], 16);
+        assert_eq!(
+            rules_of(&data),
+            vec![("invalid-metadata-type", "general.name".to_string())]
+        );
+
+        // more tensors declared than the header holds
+        let mut data = gguf(&[], &[("a", 4, 0)], 0);
+        data[8..16].copy_from_slice(&1000u64.to_le_bytes());
+        assert_eq!(rules_of(&data), vec![("count-mismatch", "".to_string())]);
+    }
+}
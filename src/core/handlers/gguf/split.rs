@@ -0,0 +1,344 @@
+use std::{
+    io::{BufWriter, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use super::{push_string, split_paths, Header, ALIGNMENT_KEY, EMBEDDED_SIGNATURE_KEY};
+
+// keys llama.cpp adds to the parts of a split model
+const SPLIT_NO_KEY: &str = "split.no";
+const SPLIT_COUNT_KEY: &str = "split.count";
+const SPLIT_TENSORS_COUNT_KEY: &str = "split.tensors.count";
+
+// GGUF value types of the split keys
+const UINT16_VALUE_TYPE: u32 = 2;
+const INT32_VALUE_TYPE: u32 = 5;
+
+/// Maximum number of tensors or bytes of tensor data of each part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SplitLimit {
+    Tensors(usize),
+    Size(usize),
+}
+
+/// Parts of a split model, or the parts a model has been merged from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Split {
+    pub parts: Vec<PathBuf>,
+    pub tensors: usize,
+    /// Whether the embedded signature of the input has been removed.
+    pub signed: bool,
+}
+
+// a tensor of a GGUF file, its info without the offset and its data
+struct RawTensor {
+    info: Range<usize>,
+    data: Range<usize>,
+}
+
+// a GGUF file as the raw bytes of its metadata entries and tensors
+struct RawGGUF {
+    buffer: memmap2::Mmap,
+    version: u32,
+    metadata: Vec<(String, Range<usize>)>,
+    tensors: Vec<RawTensor>,
+    alignment: usize,
+}
+
+impl RawGGUF {
+    fn read(file_path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(file_path)?;
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
+        let header = Header::read(&buffer)?;
+        if header.version == 1 {
+            // sizes and counts would need to be written as 32 bits
+            anyhow::bail!("GGUF version 1 is not supported, convert the model again");
+        }
+
+        let metadata = header
+            .metadata
+            .iter()
+            .map(|entry| (entry.key.clone(), entry.range.clone()))
+            .collect();
+        let mut tensors = vec![];
+        for t_info in &header.tensors {
+            let data = header
+                .data_range(t_info)
+                .filter(|range| range.end <= buffer.len())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("data of tensor {} is out of bounds", t_info.name)
+                })?;
+            // the offset, last field of the info, is written again
+            tensors.push(RawTensor {
+                info: t_info.info.start..t_info.info.end - 8,
+                data,
+            });
+        }
+
+        Ok(Self {
+            version: header.version,
+            alignment: header.alignment,
+            buffer,
+            metadata,
+            tensors,
+        })
+    }
+
+    fn entry(&self, key: &str) -> Option<&[u8]> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, range)| &self.buffer[range.clone()])
+    }
+}
+
+fn entry(key: &str, value_type: u32, value: &[u8]) -> Vec<u8> {
+    let mut entry = vec![];
+    push_string(&mut entry, key);
+    entry.extend_from_slice(&value_type.to_le_bytes());
+    entry.extend_from_slice(value);
+    entry
+}
+
+// a writer counting the bytes written, to align the tensor data
+struct Aligned<W: Write> {
+    inner: W,
+    written: usize,
+}
+
+impl<W: Write> Aligned<W> {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.inner.write_all(data)?;
+        self.written += data.len();
+        Ok(())
+    }
+
+    fn pad_to(&mut self, offset: usize) -> anyhow::Result<()> {
+        self.write(&vec![0u8; offset - self.written])
+    }
+}
+
+// writes a GGUF file with the metadata entries and the tensors, each aligned
+fn write(
+    file_path: &Path,
+    version: u32,
+    metadata: &[Vec<u8>],
+    tensors: &[(&RawGGUF, &RawTensor)],
+    alignment: usize,
+) -> anyhow::Result<()> {
+    let mut writer = Aligned {
+        inner: BufWriter::new(std::fs::File::create(file_path)?),
+        written: 0,
+    };
+
+    writer.write(b"GGUF")?;
+    writer.write(&version.to_le_bytes())?;
+    writer.write(&(tensors.len() as u64).to_le_bytes())?;
+    writer.write(&(metadata.len() as u64).to_le_bytes())?;
+    for entry in metadata {
+        writer.write(entry)?;
+    }
+    let mut offsets = vec![];
+    let mut offset = 0;
+    for (file, tensor) in tensors {
+        writer.write(&file.buffer[tensor.info.clone()])?;
+        writer.write(&(offset as u64).to_le_bytes())?;
+        offsets.push(offset);
+        offset = (offset + tensor.data.len()).next_multiple_of(alignment);
+    }
+
+    let data_offset = writer.written.next_multiple_of(alignment);
+    for ((file, tensor), offset) in tensors.iter().zip(offsets) {
+        writer.pad_to(data_offset + offset)?;
+        writer.write(&file.buffer[tensor.data.clone()])?;
+    }
+    writer.pad_to(writer.written.next_multiple_of(alignment))?;
+
+    writer.inner.flush()?;
+    Ok(())
+}
+
+/// Splits a GGUF model in parts named like llama.cpp does, prefix-00001-of-00003.gguf: the first
+/// part has the metadata of the model and every part the split keys of llama.cpp.
+pub(crate) fn split(input: &Path, prefix: &Path, limit: SplitLimit) -> anyhow::Result<Split> {
+    if split_paths(input).is_some() {
+        anyhow::bail!("{} is already split, merge it first", input.display());
+    }
+    let file = RawGGUF::read(input)?;
+    if file.entry(SPLIT_COUNT_KEY).is_some() {
+        anyhow::bail!("{} is already a part of a split model", input.display());
+    }
+
+    let mut parts: Vec<Vec<&RawTensor>> = vec![vec![]];
+    let mut size = 0;
+    for tensor in &file.tensors {
+        let current = parts.last().unwrap();
+        let full = match limit {
+            SplitLimit::Tensors(max) => current.len() >= max,
+            SplitLimit::Size(max) => size + tensor.data.len() > max,
+        };
+        if full && !current.is_empty() {
+            parts.push(vec![]);
+            size = 0;
+        }
+        parts.last_mut().unwrap().push(tensor);
+        size += tensor.data.len();
+    }
+    if parts.len() < 2 {
+        anyhow::bail!("the model fits in a single part");
+    }
+
+    let count = u16::try_from(parts.len())
+        .map_err(|_| anyhow::anyhow!("{} parts are too many", parts.len()))?;
+    let alignment = file.alignment;
+    let paths: Vec<PathBuf> = (1..=parts.len())
+        .map(|number| {
+            PathBuf::from(format!(
+                "{}-{:05}-of-{:05}.gguf",
+                prefix.display(),
+                number,
+                parts.len()
+            ))
+        })
+        .collect();
+
+    for (number, (tensors, path)) in parts.iter().zip(&paths).enumerate() {
+        let mut metadata: Vec<Vec<u8>> = if number == 0 {
+            file.metadata
+                .iter()
+                .filter(|(key, _)| key != EMBEDDED_SIGNATURE_KEY)
+                .map(|(_, range)| file.buffer[range.clone()].to_vec())
+                .collect()
+        } else {
+            // the parts are read with the alignment of the model
+            file.entry(ALIGNMENT_KEY)
+                .map(|entry| vec![entry.to_vec()])
+                .unwrap_or_default()
+        };
+        metadata.push(entry(
+            SPLIT_NO_KEY,
+            UINT16_VALUE_TYPE,
+            &(number as u16).to_le_bytes(),
+        ));
+        metadata.push(entry(
+            SPLIT_COUNT_KEY,
+            UINT16_VALUE_TYPE,
+            &count.to_le_bytes(),
+        ));
+        metadata.push(entry(
+            SPLIT_TENSORS_COUNT_KEY,
+            INT32_VALUE_TYPE,
+            &(file.tensors.len() as i32).to_le_bytes(),
+        ));
+
+        let tensors: Vec<(&RawGGUF, &RawTensor)> =
+            tensors.iter().map(|tensor| (&file, *tensor)).collect();
+        write(path, file.version, &metadata, &tensors, alignment)?;
+    }
+
+    Ok(Split {
+        parts: paths,
+        tensors: file.tensors.len(),
+        signed: file.entry(EMBEDDED_SIGNATURE_KEY).is_some(),
+    })
+}
+
+/// Merges the parts of a model split by llama.cpp, any of which can be given, into a single GGUF
+/// file without the split keys.
+pub(crate) fn merge(input: &Path, output: &Path) -> anyhow::Result<Split> {
+    let paths = split_paths(input)
+        .ok_or_else(|| anyhow::anyhow!("{} is not a part of a split model", input.display()))?;
+    let files = paths
+        .iter()
+        .map(|path| {
+            RawGGUF::read(path).map_err(|e| anyhow::anyhow!("can't read {}: {}", path.display(), e))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let first = &files[0];
+    let metadata: Vec<Vec<u8>> = first
+        .metadata
+        .iter()
+        .filter(|(key, _)| {
+            ![
+                SPLIT_NO_KEY,
+                SPLIT_COUNT_KEY,
+                SPLIT_TENSORS_COUNT_KEY,
+                EMBEDDED_SIGNATURE_KEY,
+            ]
+            .contains(&key.as_str())
+        })
+        .map(|(_, range)| first.buffer[range.clone()].to_vec())
+        .collect();
+    let tensors: Vec<(&RawGGUF, &RawTensor)> = files
+        .iter()
+        .flat_map(|file| file.tensors.iter().map(move |tensor| (file, tensor)))
+        .collect();
+    write(output, first.version, &metadata, &tensors, first.alignment)?;
+
+    Ok(Split {
+        tensors: tensors.len(),
+        signed: files
+            .iter()
+            .any(|file| file.entry(EMBEDDED_SIGNATURE_KEY).is_some()),
+        parts: paths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        handlers::{gguf::GGUFHandler, Handler},
+        DetailLevel,
+    };
+
+    #[test]
+    fn test_split_and_merge() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input = temp_dir.path().join("model.gguf");
+        std::fs::write(&input, super::super::tests::build_gguf()).unwrap();
+        let handler = GGUFHandler::new();
+        // compared bitwise, the test model has NaNs
+        let values = |path: &Path| -> Vec<(String, Vec<u64>)> {
+            handler
+                .tensor_data(path, None)
+                .unwrap()
+                .iter()
+                .map(|tensor| {
+                    let values = tensor.values().map(f64::to_bits).collect();
+                    (tensor.id.clone(), values)
+                })
+                .collect()
+        };
+
+        let split = split(&input, &temp_dir.path().join("out"), SplitLimit::Tensors(1)).unwrap();
+        assert_eq!(split.tensors, 2);
+        assert_eq!(
+            split.parts,
+            vec![
+                temp_dir.path().join("out-00001-of-00002.gguf"),
+                temp_dir.path().join("out-00002-of-00002.gguf"),
+            ]
+        );
+        let second = handler
+            .inspect(&split.parts[1], DetailLevel::Full, None)
+            .unwrap();
+        assert_eq!(second.shards.unwrap()[1].num_tensors, 1);
+        assert_eq!(second.metadata.get("general.name").unwrap(), "test");
+        assert_eq!(values(&split.parts[1]), values(&input));
+
+        let merged = temp_dir.path().join("merged.gguf");
+        assert_eq!(merge(&split.parts[0], &merged).unwrap().tensors, 2);
+        // the same file, with the data of the last tensor padded like llama.cpp does
+        let merged = std::fs::read(&merged).unwrap();
+        let original = std::fs::read(&input).unwrap();
+        assert_eq!(merged[..original.len()], original);
+        assert_eq!(merged.len() % super::super::DEFAULT_ALIGNMENT, 0);
+
+        assert!(merge(&input, &temp_dir.path().join("none.gguf")).is_err());
+        // too small to be split by size
+        assert!(super::split(&input, &input, SplitLimit::Size(1 << 20)).is_err());
+    }
+}
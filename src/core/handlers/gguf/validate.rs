@@ -0,0 +1,217 @@
+use std::{collections::HashSet, path::Path};
+
+use super::{
+    parser::{Reader, TensorInfo, Value},
+    scan::unreferenced_data_offset,
+    DEFAULT_ALIGNMENT,
+};
+use crate::core::validate::{violation, Violation};
+
+// recomputes the offset of each tensor from the size and alignment of the previous one, as
+// llama.cpp does when loading the model
+fn check_layout(
+    tensors: &[TensorInfo],
+    data_offset: u64,
+    alignment: u64,
+    data: &[u8],
+) -> Vec<Violation> {
+    let file_size = data.len() as u64;
+    let mut violations = vec![];
+    let mut names = HashSet::new();
+    // end of the data of the previous tensor relative to the data section, unknown after a
+    // tensor of unknown size
+    let mut data_end = Some(0);
+
+    for tensor in tensors {
+        let offset = data_offset.checked_add(tensor.offset);
+        if !names.insert(tensor.name.as_str()) {
+            violations.push(violation(
+                "duplicate-tensor",
+                format!("{} is declared more than once", tensor.name),
+                Some(tensor.info.start as u64),
+            ));
+        }
+        if tensor.offset % alignment != 0 {
+            violations.push(violation(
+                "misaligned-tensor",
+                format!(
+                    "tensor {} at offset {} is not aligned to {} bytes",
+                    tensor.name, tensor.offset, alignment
+                ),
+                offset,
+            ));
+        }
+        if let Some(expected) = data_end
+            .map(|end: u64| end.next_multiple_of(alignment))
+            .filter(|expected| *expected != tensor.offset)
+        {
+            violations.push(violation(
+                "unexpected-offset",
+                format!(
+                    "tensor {} at offset {}, expected at {}",
+                    tensor.name, tensor.offset, expected
+                ),
+                offset,
+            ));
+        }
+
+        let Some(size) = tensor.data_size() else {
+            violations.push(violation(
+                "invalid-tensor",
+                format!(
+                    "tensor {} has unknown type {} or an invalid size",
+                    tensor.name, tensor.tensor_type.0
+                ),
+                Some(tensor.info.start as u64),
+            ));
+            data_end = None;
+            continue;
+        };
+        let end = tensor.offset.checked_add(size);
+        if end
+            .and_then(|end| data_offset.checked_add(end))
+            .is_none_or(|end| end > file_size)
+        {
+            violations.push(violation(
+                "offset-out-of-bounds",
+                format!(
+                    "tensor {} of {} bytes at offset {} exceeds the file of {} bytes",
+                    tensor.name, size, tensor.offset, file_size
+                ),
+                offset,
+            ));
+        }
+        data_end = end;
+    }
+
+    if let Some(offset) = data_end
+        .and_then(|end| data_offset.checked_add(end))
+        .and_then(|end| unreferenced_data_offset(data, end, alignment))
+    {
+        violations.push(violation(
+            "trailing-data",
+            format!(
+                "{} bytes after the last tensor are not part of any tensor",
+                file_size - offset
+            ),
+            Some(offset),
+        ));
+    }
+
+    violations
+}
+
+/// Strictly validates the header of the model and the layout of the tensor data, catching
+/// truncated files and tampered offsets.
+pub(crate) fn validate(file_path: &Path) -> anyhow::Result<Vec<Violation>> {
+    let file = std::fs::File::open(file_path)?;
+    let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
+    let mut reader = Reader::new(&data);
+
+    let (tensor_count, metadata_count) = reader.preamble()?;
+    let mut violations = vec![];
+    let mut alignment = DEFAULT_ALIGNMENT as u64;
+
+    for index in 0..metadata_count {
+        let offset = Some(reader.offset as u64);
+        let entry = reader.string().and_then(|key| {
+            let value_type = reader.u32()?;
+            Ok((key, reader.value(value_type)?))
+        });
+        match entry {
+            Ok((b"general.alignment", Value::Uint32(value))) if value.is_power_of_two() => {
+                alignment = value as u64;
+            }
+            Ok((b"general.alignment", _)) => violations.push(violation(
+                "invalid-metadata",
+                "general.alignment is not a power of two".to_string(),
+                offset,
+            )),
+            Ok(_) => {}
+            Err(anomaly) => {
+                violations.push(violation(
+                    "invalid-header",
+                    format!("metadata entry {} of {} {}", index, metadata_count, anomaly),
+                    offset,
+                ));
+                return Ok(violations);
+            }
+        }
+    }
+
+    let mut tensors = vec![];
+    for index in 0..tensor_count {
+        let offset = reader.offset as u64;
+        match reader.tensor_info() {
+            Ok(tensor) => tensors.push(tensor),
+            Err(_) => {
+                violations.push(violation(
+                    "invalid-header",
+                    format!("tensor info {} of {} exceeds the file", index, tensor_count),
+                    Some(offset),
+                ));
+                return Ok(violations);
+            }
+        }
+    }
+
+    let data_offset = (reader.offset as u64).next_multiple_of(alignment);
+    violations.extend(check_layout(&tensors, data_offset, alignment, &data));
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a GGUF file with F32 tensors of the given number of elements and offset
+    fn validate_gguf(tensors: &[(&str, u64, u64)], data: usize) -> Vec<&'static str> {
+        let mut buffer = b"GGUF".to_vec();
+        buffer.extend(3u32.to_le_bytes());
+        buffer.extend((tensors.len() as u64).to_le_bytes());
+        buffer.extend(0u64.to_le_bytes());
+        for (name, elements, offset) in tensors {
+            super::super::push_string(&mut buffer, name);
+            buffer.extend(1u32.to_le_bytes());
+            buffer.extend(elements.to_le_bytes());
+            buffer.extend(0u32.to_le_bytes());
+            buffer.extend(offset.to_le_bytes());
+        }
+        buffer.resize(buffer.len().next_multiple_of(DEFAULT_ALIGNMENT) + data, 0);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.gguf");
+        std::fs::write(&file_path, buffer).unwrap();
+
+        validate(&file_path)
+            .unwrap()
+            .iter()
+            .map(|violation| violation.rule_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_valid() {
+        // 12 bytes padded to 32, then 32 bytes
+        assert!(validate_gguf(&[("a", 3, 0), ("b", 8, 32)], 64).is_empty());
+    }
+
+    #[test]
+    fn test_validate_violations() {
+        // truncated download
+        assert_eq!(
+            validate_gguf(&[("a", 3, 0), ("b", 8, 32)], 48),
+            vec!["offset-out-of-bounds"]
+        );
+        // tampered offset, pointing inside of the data of a
+        assert_eq!(
+            validate_gguf(&[("a", 16, 0), ("b", 8, 36)], 128),
+            vec!["misaligned-tensor", "unexpected-offset", "trailing-data"]
+        );
+        assert_eq!(
+            validate_gguf(&[("a", 8, 0), ("a", 8, 32)], 64),
+            vec!["duplicate-tensor"]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+// first line of every model saved with Booster.save_model
+const MODEL_HEADER: &str = "tree";
+// header keys reported as metadata
+const HEADER_KEYS: [&str; 6] = [
+    "num_class",
+    "num_tree_per_iteration",
+    "label_index",
+    "max_feature_idx",
+    "objective",
+    "average_output",
+];
+
+#[derive(Debug, Default)]
+struct Model {
+    version: String,
+    metadata: Metadata,
+    tensors: Vec<TensorDescriptor>,
+}
+
+fn build_tree_descriptor(index: &str, fields: &Metadata) -> TensorDescriptor {
+    let num_leaves: usize = fields
+        .get("num_leaves")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+
+    let mut metadata = Metadata::new();
+    for key in ["num_leaves", "num_cat", "shrinkage", "is_linear"] {
+        if let Some(value) = fields.get(key) {
+            metadata.insert(key.to_string(), value.clone());
+        }
+    }
+
+    // leaf values are stored as 64 bits floats
+    TensorDescriptor {
+        id: Some(format!("tree.{}", index)),
+        shape: vec![num_leaves],
+        dtype: "F64".to_string(),
+        size: num_leaves * 8,
+        metadata,
+        stats: None,
+        preview: None,
+    }
+}
+
+fn parse_model<R: BufRead>(reader: R) -> anyhow::Result<Model> {
+    let mut lines = reader.lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    if header.trim() != MODEL_HEADER {
+        anyhow::bail!("not a LightGBM model: missing '{}' header", MODEL_HEADER);
+    }
+
+    let mut model = Model::default();
+    let mut current_tree: Option<(String, Metadata)> = None;
+    let mut in_parameters = false;
+
+    for line in lines {
+        let line = line?;
+        let line = line.trim();
+
+        if in_parameters {
+            if line == "end of parameters" {
+                in_parameters = false;
+            } else if let Some((key, value)) = line
+                .strip_prefix('[')
+                .and_then(|l| l.strip_suffix(']'))
+                .and_then(|l| l.split_once(':'))
+            {
+                let value = value.trim();
+                if !value.is_empty() {
+                    model
+                        .metadata
+                        .insert(format!("parameters.{}", key.trim()), value.to_string());
+                }
+            }
+            continue;
+        }
+
+        if line == "parameters:" {
+            in_parameters = true;
+            continue;
+        }
+
+        if line.is_empty() || line == "end of trees" {
+            if let Some((index, fields)) = current_tree.take() {
+                model.tensors.push(build_tree_descriptor(&index, &fields));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        if key == "Tree" {
+            current_tree = Some((value.to_string(), Metadata::new()));
+        } else if let Some((_, fields)) = current_tree.as_mut() {
+            // only keep the scalar fields, arrays can be huge
+            if !value.contains(' ') {
+                fields.insert(key.to_string(), value.to_string());
+            }
+        } else if model.tensors.is_empty() {
+            match key {
+                "version" => model.version = value.to_string(),
+                "feature_names" => {
+                    let names: Vec<&str> = value.split_whitespace().collect();
+                    model
+                        .metadata
+                        .insert("num_features".to_string(), names.len().to_string());
+                    model
+                        .metadata
+                        .insert("feature_names".to_string(), names.join(", "));
+                }
+                _ if HEADER_KEYS.contains(&key) => {
+                    model.metadata.insert(key.to_string(), value.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some((index, fields)) = current_tree.take() {
+        model.tensors.push(build_tree_descriptor(&index, &fields));
+    }
+
+    model
+        .metadata
+        .insert("num_trees".to_string(), model.tensors.len().to_string());
+
+    Ok(model)
+}
+
+pub(crate) struct LightGBMHandler;
+
+impl LightGBMHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for LightGBMHandler {
+    fn file_type(&self) -> FileType {
+        FileType::LightGBM
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        let is_txt = file_path
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or("")
+            .eq_ignore_ascii_case("txt");
+        if !is_txt {
+            return false;
+        }
+
+        // plain text files are only claimed if they start with the model header
+        let Ok(file) = std::fs::File::open(file_path) else {
+            return false;
+        };
+        let mut first_line = String::new();
+        BufReader::new(file)
+            .read_line(&mut first_line)
+            .is_ok_and(|_| first_line.trim() == MODEL_HEADER)
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        // models are self contained
+        Ok(vec![file_path.to_path_buf()])
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let mut inspection = Inspection::default();
+
+        let file = std::fs::File::open(file_path)?;
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.file_size = file.metadata()?.len();
+
+        let model = parse_model(BufReader::new(file))?;
+
+        inspection.file_type = FileType::LightGBM;
+        inspection.version = model.version;
+        inspection.metadata = model.metadata;
+
+        let mut tensors = model.tensors;
+
+        inspection.num_tensors = tensors.len();
+        inspection.data_size = tensors.iter().map(|t| t.size).sum::<usize>();
+
+        inspection.unique_shapes = tensors
+            .iter()
+            .map(|t| t.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = tensors
+            .iter()
+            .map(|t| t.dtype.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            tensors.retain(|t| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| t.id.as_ref().is_some_and(|id| id.contains(f)))
+            });
+            inspection.tensors = Some(tensors);
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "tree
+version=v4
+num_class=1
+num_tree_per_iteration=1
+label_index=0
+max_feature_idx=1
+objective=binary sigmoid:1
+feature_names=age income
+feature_infos=[18:90] [0:100000]
+tree_sizes=300 200
+
+Tree=0
+num_leaves=3
+num_cat=0
+split_feature=0 1
+leaf_value=0.1 -0.2 0.3
+shrinkage=1
+
+Tree=1
+num_leaves=2
+num_cat=0
+split_feature=1
+leaf_value=0.05 -0.05
+shrinkage=0.1
+
+
+end of trees
+
+feature_importances:
+age=2
+income=1
+
+parameters:
+[boosting: gbdt]
+[objective: binary]
+[num_leaves: 31]
+[data: ]
+end of parameters
+
+pandas_categorical:null
+";
+
+    #[test]
+    fn test_parse_model() {
+        let model = parse_model(MODEL.as_bytes()).unwrap();
+
+        assert_eq!(model.version, "v4");
+        assert_eq!(model.metadata.get("objective").unwrap(), "binary sigmoid:1");
+        assert_eq!(model.metadata.get("num_features").unwrap(), "2");
+        assert_eq!(model.metadata.get("feature_names").unwrap(), "age, income");
+        assert_eq!(model.metadata.get("num_trees").unwrap(), "2");
+        assert_eq!(model.metadata.get("parameters.boosting").unwrap(), "gbdt");
+        assert_eq!(model.metadata.get("parameters.num_leaves").unwrap(), "31");
+        assert!(!model.metadata.contains_key("parameters.data"));
+
+        assert_eq!(model.tensors.len(), 2);
+        assert_eq!(model.tensors[0].id.as_deref(), Some("tree.0"));
+        assert_eq!(model.tensors[0].shape, vec![3]);
+        assert_eq!(model.tensors[1].metadata.get("shrinkage").unwrap(), "0.1");
+    }
+
+    #[test]
+    fn test_parse_invalid_model() {
+        assert!(parse_model("".as_bytes()).is_err());
+        assert!(parse_model("hello\nworld".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let handler = LightGBMHandler::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let model_path = temp_dir.path().join("model.txt");
+        std::fs::write(&model_path, MODEL).unwrap();
+        assert!(handler.is_handler_for(&model_path, &Scope::Inspection));
+        assert!(handler.is_handler_for(&model_path, &Scope::Signing));
+
+        let notes_path = temp_dir.path().join("notes.txt");
+        std::fs::write(&notes_path, "some notes").unwrap();
+        assert!(!handler.is_handler_for(&notes_path, &Scope::Inspection));
+
+        assert!(!handler.is_handler_for(Path::new("model.txt"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use glob::glob;
+use serde::Deserialize;
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata};
+
+use super::{safetensors, Handler, Scope};
+
+const MLMODEL_FILE_NAME: &str = "MLmodel";
+// generic flavor of every model, the other flavors know where the weights are
+const PYFUNC_FLAVOR: &str = "python_function";
+// flavor settings pointing to the weights, relative to the model directory
+const WEIGHT_KEYS: [&str; 6] = [
+    "model_data",
+    "model_binary",
+    "saved_model_dir",
+    "pickled_model",
+    "data",
+    "model_path",
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct MLModel {
+    #[serde(default)]
+    artifact_path: Option<String>,
+    #[serde(default)]
+    flavors: BTreeMap<String, serde_yaml_ng::Value>,
+    #[serde(default)]
+    mlflow_version: Option<String>,
+    #[serde(default)]
+    model_uuid: Option<String>,
+    #[serde(default)]
+    run_id: Option<String>,
+    #[serde(default)]
+    utc_time_created: Option<String>,
+}
+
+impl MLModel {
+    fn from_path(path: &Path) -> anyhow::Result<Self> {
+        serde_yaml_ng::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow::anyhow!("invalid {}: {}", path.display(), e))
+    }
+
+    // flavors in the order their weights are looked for, python_function last
+    fn flavors(&self) -> impl Iterator<Item = (&String, &serde_yaml_ng::Value)> {
+        self.flavors
+            .iter()
+            .filter(|(name, _)| *name != PYFUNC_FLAVOR)
+            .chain(
+                self.flavors
+                    .iter()
+                    .filter(|(name, _)| *name == PYFUNC_FLAVOR),
+            )
+    }
+
+    // paths of the weights declared by the flavors, relative to the model directory
+    fn weight_paths(&self) -> Vec<(&str, &str)> {
+        let mut paths: Vec<(&str, &str)> = vec![];
+        for (flavor, settings) in self.flavors() {
+            for key in WEIGHT_KEYS {
+                if let Some(path) = settings.get(key).and_then(|value| value.as_str()) {
+                    if !paths.iter().any(|(_, other)| *other == path) {
+                        paths.push((flavor, path));
+                    }
+                }
+            }
+        }
+        paths
+    }
+
+    fn metadata(&self) -> Metadata {
+        let mut metadata = Metadata::new();
+        let fields = [
+            ("artifact_path", &self.artifact_path),
+            ("model_uuid", &self.model_uuid),
+            ("run_id", &self.run_id),
+            ("utc_time_created", &self.utc_time_created),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                metadata.insert(format!("mlflow.{}", key), value.clone());
+            }
+        }
+        metadata.insert(
+            "mlflow.flavors".to_string(),
+            self.flavors.keys().cloned().collect::<Vec<_>>().join(", "),
+        );
+        // framework versions, loader modules and the like
+        for (flavor, settings) in &self.flavors {
+            let Some(settings) = settings.as_mapping() else {
+                continue;
+            };
+            for (key, value) in settings {
+                let value = match value {
+                    serde_yaml_ng::Value::String(value) => value.clone(),
+                    serde_yaml_ng::Value::Number(value) => value.to_string(),
+                    serde_yaml_ng::Value::Bool(value) => value.to_string(),
+                    _ => continue,
+                };
+                if let Some(key) = key.as_str() {
+                    metadata.insert(format!("mlflow.{}.{}", flavor, key), value);
+                }
+            }
+        }
+        metadata
+    }
+}
+
+/// Returns the MLflow model directory if the path is either a MLmodel file or a directory containing one.
+fn model_dir(file_path: &Path) -> Option<PathBuf> {
+    if file_path.is_dir() {
+        if file_path.join(MLMODEL_FILE_NAME).is_file() {
+            return Some(file_path.to_path_buf());
+        }
+    } else if file_path.file_name().unwrap_or_default() == MLMODEL_FILE_NAME {
+        return Some(
+            file_path
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default(),
+        );
+    }
+
+    None
+}
+
+fn files_of(base_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in glob(base_path.join("**/*").to_str().unwrap())? {
+        let path = entry?;
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+// format of the weights saved by the flavor, MLflow using its own extensions for some of them
+fn format_of_flavor(flavor: &str) -> Option<FileType> {
+    match flavor {
+        "onnx" => Some(FileType::ONNX),
+        "pytorch" => Some(FileType::PyTorch),
+        "tensorflow" => Some(FileType::TensorFlow),
+        "xgboost" => Some(FileType::XGBoost),
+        "lightgbm" => Some(FileType::LightGBM),
+        _ => None,
+    }
+}
+
+// the weights of the model and the handler to inspect them with, the first supported path of the
+// flavors or, for folders, the first supported file they contain
+fn weights_of(base_path: &Path, model: &MLModel) -> Option<(PathBuf, Box<dyn Handler>)> {
+    for (flavor, relative_path) in model.weight_paths() {
+        let path = base_path.join(relative_path);
+        if !path.exists() {
+            continue;
+        }
+        if let Ok(handler) = super::handler_for(None, &path, Scope::Inspection) {
+            return Some((path, handler));
+        }
+        if let Some(format) = format_of_flavor(flavor).filter(|_| path.is_file()) {
+            return super::handler_for(Some(format), &path, Scope::Inspection)
+                .ok()
+                .map(|handler| (path, handler));
+        }
+        if path.is_dir() {
+            // sharded safetensors are inspected through their index
+            let mut files = files_of(&path).ok()?;
+            files.sort_by_key(|file| !safetensors::is_safetensors_index(file));
+            for file in files {
+                if let Ok(handler) = super::handler_for(None, &file, Scope::Inspection) {
+                    return Some((file, handler));
+                }
+            }
+        }
+    }
+    None
+}
+
+pub(crate) struct MLflowHandler;
+
+impl MLflowHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for MLflowHandler {
+    fn file_type(&self) -> FileType {
+        FileType::MLflow
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        model_dir(file_path).is_some()
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let base_path = model_dir(file_path)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a MLflow model", file_path.display()))?;
+
+        // the whole model directory, including the conda, virtualenv and pip requirements
+        files_of(&base_path)
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let base_path = model_dir(file_path).unwrap_or_else(|| {
+            // format was forced, assume the file is the MLmodel
+            file_path
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default()
+        });
+        let model = MLModel::from_path(&base_path.join(MLMODEL_FILE_NAME))?;
+
+        let mut inspection = match weights_of(&base_path, &model) {
+            Some((weights, handler)) => {
+                let mut inspection = handler.inspect(&weights, detail, filter)?;
+                inspection.metadata.insert(
+                    "mlflow.weights".to_string(),
+                    weights
+                        .strip_prefix(&base_path)?
+                        .to_string_lossy()
+                        .to_string(),
+                );
+                inspection.metadata.insert(
+                    "mlflow.weights_format".to_string(),
+                    handler.file_type().to_string(),
+                );
+                inspection
+            }
+            None => Inspection::default(),
+        };
+
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.file_type = FileType::MLflow;
+        inspection.file_size = files_of(&base_path)?
+            .iter()
+            .map(|path| path.metadata().map(|m| m.len()).unwrap_or(0))
+            .sum();
+        inspection.version = model.mlflow_version.clone().unwrap_or_default();
+        inspection.metadata.extend(model.metadata());
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MLMODEL: &str = "artifact_path: model
+flavors:
+  python_function:
+    env:
+      conda: conda.yaml
+      virtualenv: python_env.yaml
+    loader_module: mlflow.lightgbm
+    data: model.lgb
+    python_version: 3.10.12
+  lightgbm:
+    code: null
+    data: model.lgb
+    lgb_version: 4.1.0
+    model_class: lightgbm.basic.Booster
+mlflow_version: 2.9.2
+model_uuid: 3f1c0c2e2a7e4b0a9b1c3d4e5f6a7b8c
+run_id: 0a1b2c3d4e5f
+utc_time_created: '2024-01-10 12:00:00.000000'
+";
+
+    const LIGHTGBM_MODEL: &str = "tree
+version=v4
+num_class=1
+
+Tree=0
+num_leaves=2
+leaf_value=0.1 -0.1
+
+end of trees
+";
+
+    fn build_model(dir: &Path) {
+        std::fs::write(dir.join(MLMODEL_FILE_NAME), MLMODEL).unwrap();
+        std::fs::write(dir.join("conda.yaml"), "dependencies: []").unwrap();
+        std::fs::write(dir.join("requirements.txt"), "lightgbm==4.1.0").unwrap();
+        std::fs::write(dir.join("model.lgb"), LIGHTGBM_MODEL).unwrap();
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = MLflowHandler::new();
+
+        assert!(!handler.is_handler_for(dir.path(), &Scope::Inspection));
+        assert!(handler.is_handler_for(Path::new("path/to/MLmodel"), &Scope::Signing));
+        assert!(!handler.is_handler_for(Path::new("model.yaml"), &Scope::Inspection));
+
+        build_model(dir.path());
+        assert!(handler.is_handler_for(dir.path(), &Scope::Inspection));
+
+        // the environment is signed along with the weights
+        let mut names: Vec<String> = handler
+            .paths_to_sign(&dir.path().join(MLMODEL_FILE_NAME))
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["MLmodel", "conda.yaml", "model.lgb", "requirements.txt"]
+        );
+    }
+
+    #[test]
+    fn test_inspect() {
+        let dir = tempfile::tempdir().unwrap();
+        build_model(dir.path());
+
+        let model = MLModel::from_path(&dir.path().join(MLMODEL_FILE_NAME)).unwrap();
+        // the flavor of the framework comes first
+        assert_eq!(model.weight_paths(), vec![("lightgbm", "model.lgb")]);
+
+        // the LightGBM model is not a .txt file, its handler is found through the flavor
+        let inspection = MLflowHandler::new()
+            .inspect(dir.path(), DetailLevel::Brief, None)
+            .unwrap();
+        assert!(matches!(inspection.file_type, FileType::MLflow));
+        assert_eq!(inspection.version, "2.9.2");
+        assert_eq!(inspection.num_tensors, 1);
+        assert_eq!(
+            inspection.metadata.get("mlflow.weights_format").unwrap(),
+            "LightGBM"
+        );
+        assert_eq!(
+            inspection.metadata.get("mlflow.run_id").unwrap(),
+            "0a1b2c3d4e5f"
+        );
+        assert_eq!(
+            inspection.metadata.get("mlflow.flavors").unwrap(),
+            "lightgbm, python_function"
+        );
+        assert_eq!(
+            inspection
+                .metadata
+                .get("mlflow.lightgbm.lgb_version")
+                .unwrap(),
+            "4.1.0"
+        );
+    }
+}
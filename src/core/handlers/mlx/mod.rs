@@ -0,0 +1,366 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+pub(crate) mod npy;
+
+use npy::NpyHeader;
+use serde_json::Value;
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+pub(crate) const CONFIG_FILE_NAME: &str = "config.json";
+
+// architecture related keys of HF style config.json files
+const ARCHITECTURE_KEYS: [&str; 12] = [
+    "model_type",
+    "architectures",
+    "hidden_size",
+    "intermediate_size",
+    "num_hidden_layers",
+    "num_attention_heads",
+    "num_key_value_heads",
+    "head_dim",
+    "vocab_size",
+    "max_position_embeddings",
+    "rope_theta",
+    "torch_dtype",
+];
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(value_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// Returns the architecture metadata from a config.json file, with keys prefixed by `config.`.
+pub(crate) fn config_metadata(config_path: &Path) -> anyhow::Result<Metadata> {
+    let config: Value = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+    let mut metadata = Metadata::new();
+
+    for key in ARCHITECTURE_KEYS {
+        if let Some(value) = config.get(key).filter(|v| !v.is_null()) {
+            metadata.insert(format!("config.{}", key), value_to_string(value));
+        }
+    }
+
+    // MLX quantized models store the quantization parameters in the config
+    if let Some(Value::Object(quantization)) = config.get("quantization") {
+        for (key, value) in quantization {
+            if !value.is_object() {
+                metadata.insert(
+                    format!("config.quantization.{}", key),
+                    value_to_string(value),
+                );
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Returns true if the safetensors header metadata has been written by MLX.
+pub(crate) fn is_mlx_metadata(metadata: &Metadata) -> bool {
+    metadata.get("format").is_some_and(|f| f == "mlx")
+}
+
+fn is_npz(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or("")
+        .eq_ignore_ascii_case("npz")
+}
+
+/// Returns the npz weight files of a MLX model directory.
+fn weight_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_npz(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn read_npz(file_path: &Path) -> anyhow::Result<(String, Vec<TensorDescriptor>)> {
+    let file = std::fs::File::open(file_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut tensors = vec![];
+    let mut version = String::new();
+
+    for idx in 0..archive.len() {
+        let mut member = archive.by_index(idx)?;
+        let name = member.name().to_string();
+        let size = member.size() as usize;
+        // only read the npy header, not the array data
+        let header = NpyHeader::read(&mut member)
+            .map_err(|e| anyhow::anyhow!("failed to read array {}: {}", name, e))?;
+
+        if version.is_empty() {
+            version = format!("npy v{}.{}", header.version.0, header.version.1);
+        }
+
+        let mut metadata = Metadata::new();
+        metadata.insert("file".to_string(), file_path.display().to_string());
+        if header.fortran_order {
+            metadata.insert("fortran_order".to_string(), "true".to_string());
+        }
+
+        tensors.push(TensorDescriptor {
+            id: Some(name.trim_end_matches(".npy").to_string()),
+            shape: header.shape.clone(),
+            dtype: header.dtype(),
+            size: size.saturating_sub(header.header_size),
+            metadata,
+            stats: None,
+            preview: None,
+        });
+    }
+
+    Ok((version, tensors))
+}
+
+pub(crate) struct MLXHandler;
+
+impl MLXHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for MLXHandler {
+    fn file_type(&self) -> FileType {
+        FileType::MLX
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        if file_path.is_dir() {
+            // a MLX repository has a config and npz weights
+            file_path.join(CONFIG_FILE_NAME).is_file()
+                && weight_files(file_path).is_ok_and(|files| !files.is_empty())
+        } else {
+            is_npz(file_path)
+        }
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let (base_path, mut paths) = if file_path.is_dir() {
+            (file_path.to_path_buf(), weight_files(file_path)?)
+        } else {
+            (
+                file_path
+                    .parent()
+                    .ok_or_else(|| anyhow::anyhow!("no parent path"))?
+                    .to_path_buf(),
+                vec![file_path.to_path_buf()],
+            )
+        };
+
+        // the config defines how the weights are interpreted
+        let config_path = base_path.join(CONFIG_FILE_NAME);
+        if config_path.is_file() {
+            paths.push(config_path);
+        }
+
+        Ok(paths)
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let mut inspection = Inspection::default();
+
+        let (base_path, weight_paths) = if file_path.is_dir() {
+            (file_path.to_path_buf(), weight_files(file_path)?)
+        } else {
+            (
+                file_path
+                    .parent()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_default(),
+                vec![file_path.to_path_buf()],
+            )
+        };
+
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.file_type = FileType::MLX;
+
+        let mut tensors = vec![];
+        for weight_path in &weight_paths {
+            inspection.file_size += weight_path.metadata()?.len();
+
+            let (version, archive_tensors) = read_npz(weight_path)?;
+            if inspection.version.is_empty() {
+                inspection.version = version;
+            }
+            tensors.extend(archive_tensors);
+        }
+
+        let config_path = base_path.join(CONFIG_FILE_NAME);
+        if config_path.is_file() {
+            inspection.metadata.extend(config_metadata(&config_path)?);
+        }
+
+        inspection.num_tensors = tensors.len();
+        inspection.data_size = tensors.iter().map(|t| t.size).sum::<usize>();
+        inspection.header_size =
+            (inspection.file_size as usize).saturating_sub(inspection.data_size);
+
+        inspection.unique_shapes = tensors
+            .iter()
+            .map(|t| t.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = tensors
+            .iter()
+            .map(|t| t.dtype.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            tensors.retain(|t| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| t.id.as_ref().is_some_and(|id| id.contains(f)))
+            });
+            inspection.tensors = Some(tensors);
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn write_npz(path: &Path, arrays: &[(&str, &str, &[u8])]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        for (name, dict, data) in arrays {
+            writer
+                .start_file(
+                    format!("{}.npy", name),
+                    zip::write::SimpleFileOptions::default()
+                        .compression_method(zip::CompressionMethod::Stored),
+                )
+                .unwrap();
+            writer.write_all(npy::NPY_MAGIC).unwrap();
+            writer.write_all(&[1, 0]).unwrap();
+            writer
+                .write_all(&(dict.len() as u16).to_le_bytes())
+                .unwrap();
+            writer.write_all(dict.as_bytes()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_inspect_mlx_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base_path = temp_dir.path();
+
+        write_npz(
+            &base_path.join("weights.npz"),
+            &[
+                (
+                    "model.embed_tokens.weight",
+                    "{'descr': '<f2', 'fortran_order': False, 'shape': (4, 2), }",
+                    &[0u8; 16],
+                ),
+                (
+                    "model.norm.weight",
+                    "{'descr': '<f4', 'fortran_order': False, 'shape': (2,), }",
+                    &[0u8; 8],
+                ),
+            ],
+        );
+        std::fs::write(
+            base_path.join(CONFIG_FILE_NAME),
+            r#"{"model_type": "llama", "architectures": ["LlamaForCausalLM"], "hidden_size": 2,
+                "quantization": {"group_size": 64, "bits": 4}}"#,
+        )
+        .unwrap();
+
+        let handler = MLXHandler::new();
+        assert!(handler.is_handler_for(base_path, &Scope::Inspection));
+
+        let inspection = handler.inspect(base_path, DetailLevel::Full, None).unwrap();
+
+        assert_eq!(inspection.num_tensors, 2);
+        assert_eq!(inspection.data_size, 24);
+        assert_eq!(inspection.version, "npy v1.0");
+        assert_eq!(
+            inspection.metadata.get("config.model_type").unwrap(),
+            "llama"
+        );
+        assert_eq!(
+            inspection.metadata.get("config.architectures").unwrap(),
+            "LlamaForCausalLM"
+        );
+        assert_eq!(
+            inspection.metadata.get("config.quantization.bits").unwrap(),
+            "4"
+        );
+
+        let tensors = inspection.tensors.unwrap();
+        assert_eq!(tensors[0].id.as_deref(), Some("model.embed_tokens.weight"));
+        assert_eq!(tensors[0].shape, vec![4, 2]);
+        assert_eq!(tensors[0].dtype, "F16");
+
+        let mut names: Vec<String> = handler
+            .paths_to_sign(base_path)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["config.json", "weights.npz"]);
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let handler = MLXHandler::new();
+
+        assert!(handler.is_handler_for(Path::new("weights.npz"), &Scope::Inspection));
+        assert!(handler.is_handler_for(Path::new("path/to/WEIGHTS.NPZ"), &Scope::Signing));
+
+        assert!(!handler.is_handler_for(Path::new("weights.npy"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
+
+        // a directory without npz weights is not a MLX repository
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join(CONFIG_FILE_NAME), "{}").unwrap();
+        assert!(!handler.is_handler_for(temp_dir.path(), &Scope::Inspection));
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::Read;
+
+// https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+pub(crate) const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct NpyHeader {
+    /// Array protocol type string, e.g. `<f4`.
+    pub descr: String,
+    pub fortran_order: bool,
+    pub shape: Vec<usize>,
+    /// Format (major, minor) version.
+    pub version: (u8, u8),
+    /// Size of the preamble and header dictionary, the data starts right after it.
+    pub header_size: usize,
+}
+
+impl NpyHeader {
+    pub fn read<R: Read>(reader: &mut R) -> anyhow::Result<Self> {
+        let mut preamble = [0u8; 8];
+        reader.read_exact(&mut preamble)?;
+        if &preamble[..6] != NPY_MAGIC {
+            anyhow::bail!("not a npy file: invalid magic");
+        }
+
+        let major = preamble[6];
+        let (header_len, preamble_size) = match major {
+            1 => {
+                let mut len = [0u8; 2];
+                reader.read_exact(&mut len)?;
+                (u16::from_le_bytes(len) as usize, 10)
+            }
+            2 | 3 => {
+                let mut len = [0u8; 4];
+                reader.read_exact(&mut len)?;
+                (u32::from_le_bytes(len) as usize, 12)
+            }
+            _ => anyhow::bail!("unsupported npy version {}", major),
+        };
+
+        let mut header = vec![0u8; header_len];
+        reader.read_exact(&mut header)?;
+        let header = String::from_utf8_lossy(&header);
+
+        Ok(Self {
+            descr: dict_value(&header, "descr")
+                .map(|v| v.trim_matches(['\'', '"']).to_string())
+                .ok_or_else(|| anyhow::anyhow!("npy header is missing 'descr'"))?,
+            fortran_order: dict_value(&header, "fortran_order") == Some("True"),
+            shape: parse_shape(
+                dict_value(&header, "shape")
+                    .ok_or_else(|| anyhow::anyhow!("npy header is missing 'shape'"))?,
+            )?,
+            version: (major, preamble[7]),
+            header_size: preamble_size + header_len,
+        })
+    }
+
+    /// Human readable dtype name for the array protocol type string.
+    pub fn dtype(&self) -> String {
+        let kind_size = self.descr.trim_start_matches(['<', '>', '|', '=']);
+        match kind_size {
+            "f2" => "F16",
+            "f4" => "F32",
+            "f8" => "F64",
+            "i1" => "I8",
+            "i2" => "I16",
+            "i4" => "I32",
+            "i8" => "I64",
+            "u1" => "U8",
+            "u2" => "U16",
+            "u4" => "U32",
+            "u8" => "U64",
+            "b1" => "BOOL",
+            "c8" => "C64",
+            "c16" => "C128",
+            // bfloat16 has no numpy equivalent and is stored as raw 2 bytes values
+            "V2" => "BF16",
+            other => other,
+        }
+        .to_string()
+    }
+}
+
+/// Returns the raw value for a key of the python dictionary literal used as npy header.
+fn dict_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let key_pos = header
+        .find(&format!("'{}'", key))
+        .or_else(|| header.find(&format!("\"{}\"", key)))?;
+    let rest = header[key_pos + key.len() + 2..].trim_start();
+    let rest = rest.strip_prefix(':')?.trim_start();
+
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else if rest.starts_with('\'') || rest.starts_with('"') {
+        rest[1..].find(['\'', '"'])? + 2
+    } else {
+        rest.find([',', '}']).unwrap_or(rest.len())
+    };
+
+    Some(rest[..end].trim())
+}
+
+fn parse_shape(shape: &str) -> anyhow::Result<Vec<usize>> {
+    shape
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        .map(|d| {
+            d.trim_end_matches('L')
+                .parse::<usize>()
+                .map_err(|e| anyhow::anyhow!("invalid npy shape dimension '{}': {}", d, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_npy(dict: &str) -> Vec<u8> {
+        let mut data = NPY_MAGIC.to_vec();
+        data.extend_from_slice(&[1, 0]);
+        data.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+        data.extend_from_slice(dict.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_read_header() {
+        let data = build_npy("{'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), }\n");
+        let header = NpyHeader::read(&mut data.as_slice()).unwrap();
+
+        assert_eq!(header.descr, "<f4");
+        assert_eq!(header.dtype(), "F32");
+        assert!(!header.fortran_order);
+        assert_eq!(header.shape, vec![3, 4]);
+        assert_eq!(header.header_size, data.len());
+    }
+
+    #[test]
+    fn test_read_scalar_and_vector_headers() {
+        let data = build_npy("{'descr': '|u1', 'fortran_order': True, 'shape': (), }");
+        let header = NpyHeader::read(&mut data.as_slice()).unwrap();
+        assert_eq!(header.dtype(), "U8");
+        assert!(header.fortran_order);
+        assert!(header.shape.is_empty());
+
+        let data = build_npy("{'descr': '<V2', 'fortran_order': False, 'shape': (10,), }");
+        let header = NpyHeader::read(&mut data.as_slice()).unwrap();
+        assert_eq!(header.dtype(), "BF16");
+        assert_eq!(header.shape, vec![10]);
+    }
+
+    #[test]
+    fn test_read_invalid_header() {
+        assert!(NpyHeader::read(&mut b"not a npy file".as_slice()).is_err());
+        let data = build_npy("{'fortran_order': False}");
+        assert!(NpyHeader::read(&mut data.as_slice()).is_err());
+    }
+}
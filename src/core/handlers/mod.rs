@@ -1,20 +1,75 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
 
-use crate::cli::DetailLevel;
+use crate::core::DetailLevel;
 
-use super::{FileType, Inspection};
+use super::{data::TensorData, graph::Graph, FileType, Inspection, ValueInfo};
 
+pub(crate) mod executorch;
 pub(crate) mod gguf;
+pub(crate) mod lightgbm;
+pub(crate) mod mlflow;
+pub(crate) mod mlx;
 pub(crate) mod onnx;
+pub(crate) mod openvino;
+pub(crate) mod plugin;
 pub(crate) mod pytorch;
 pub(crate) mod safetensors;
+pub(crate) mod tensorflow;
+pub(crate) mod tensorrt;
+pub(crate) mod xgboost;
 
-pub(crate) enum Scope {
+/// Key the signature manifest is stored under when embedded in the model file.
+pub(crate) const EMBEDDED_SIGNATURE_KEY: &str = "tensorman.signature";
+
+/// A model file that can carry its own signature.
+pub struct EmbeddedSignature {
+    /// Content of the file without the embedded signature, this is what gets hashed.
+    pub content: Box<dyn Read>,
+    /// The embedded signature manifest, if any.
+    pub manifest: Option<String>,
+}
+
+/// Atomically replaces the file with the given content, preserving its permissions.
+pub(crate) fn replace_file(file_path: &Path, content: &mut dyn Read) -> anyhow::Result<()> {
+    // write to a temporary file in the same folder so that it can be renamed
+    let folder = file_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut output = tempfile::NamedTempFile::new_in(folder)?;
+    std::io::copy(content, &mut output)?;
+    output
+        .as_file()
+        .set_permissions(std::fs::metadata(file_path)?.permissions())?;
+    output.persist(file_path)?;
+
+    Ok(())
+}
+
+/// Whether a path referenced by a model, such as the location of ONNX external data or a shard
+/// of a SafeTensors index, is absolute or escapes the folder of the model.
+pub(crate) fn is_outside_of_model_folder(location: &str) -> bool {
+    Path::new(location).is_absolute()
+        || location.starts_with(['/', '\\'])
+        // windows drive letters and URLs
+        || location.contains(':')
+        || location.split(['/', '\\']).any(|part| part == "..")
+}
+
+/// A readable and seekable file, see [`Handler::scan_header`].
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+pub enum Scope {
     Inspection,
     Signing,
 }
 
-pub(crate) trait Handler {
+pub trait Handler {
     fn file_type(&self) -> FileType;
 
     fn is_handler_for(&self, file_path: &Path, scope: &Scope) -> bool;
@@ -26,22 +81,89 @@ pub(crate) trait Handler {
         filter: Option<String>,
     ) -> anyhow::Result<Inspection>;
 
+    /// Reads every part of the file needed to inspect it, skipping the tensor data, so that
+    /// only the header of remote files gets downloaded.
+    fn scan_header(&self, _reader: &mut dyn ReadSeek) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "remote inspection not supported for this format"
+        ))
+    }
+
     fn create_graph(&self, _file_path: &Path, _output_path: &Path) -> anyhow::Result<()> {
         Err(anyhow::anyhow!(
             "graph generation not supported for this format"
         ))
     }
+
+    /// Returns the computation graph of the model, to render it as an interactive page.
+    fn graph(&self, _file_path: &Path) -> anyhow::Result<Graph> {
+        Err(anyhow::anyhow!(
+            "graph generation not supported for this format"
+        ))
+    }
+
+    /// Returns the tensors computed by the graph of the model, with their declared or inferred
+    /// element types and dimensions.
+    fn intermediate_shapes(&self, _file_path: &Path) -> anyhow::Result<Vec<ValueInfo>> {
+        Err(anyhow::anyhow!(
+            "shape inference not supported for this format"
+        ))
+    }
+
+    /// Returns the raw data of the tensors matching the filter, tensors with element types
+    /// that can't be decoded are omitted.
+    fn tensor_data(
+        &self,
+        _file_path: &Path,
+        _filter: Option<String>,
+    ) -> anyhow::Result<Vec<TensorData>> {
+        Err(anyhow::anyhow!(
+            "reading tensor data not supported for this format"
+        ))
+    }
+
+    /// Returns the content of the file in a canonical byte form, the same for models with the same
+    /// tensors and metadata however they have been exported, the embedded signature excluded.
+    fn canonical_content(&self, _file_path: &Path) -> anyhow::Result<Box<dyn Read>> {
+        Err(anyhow::anyhow!(
+            "canonicalization not supported for this format"
+        ))
+    }
+
+    /// Returns the signature embedded in the file, along with the file content it has been computed on.
+    fn embedded_signature(&self, _file_path: &Path) -> anyhow::Result<EmbeddedSignature> {
+        Err(anyhow::anyhow!(
+            "embedded signatures not supported for this format"
+        ))
+    }
+
+    /// Rewrites the file with the signature manifest embedded in its metadata.
+    fn embed_signature(&self, _file_path: &Path, _manifest: &str) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "embedded signatures not supported for this format"
+        ))
+    }
 }
 
-pub(crate) fn handler_for(
+pub fn handler_for(
     format: Option<FileType>,
     file_path: &Path,
     scope: Scope,
 ) -> anyhow::Result<Box<dyn Handler>> {
+    tracing::debug!("looking for the handler of {}", file_path.display());
+
     let safetensors_handler = safetensors::SafeTensorsHandler::new();
     let onnx_handler = onnx::OnnxHandler::new();
     let gguf_handler = gguf::GGUFHandler::new();
     let pytorch_handler = pytorch::PyTorchHandler::new();
+    let tensorflow_handler = tensorflow::TensorFlowHandler::new();
+    let openvino_handler = openvino::OpenVINOHandler::new();
+    let tensorrt_handler = tensorrt::TensorRTHandler::new();
+    let executorch_handler = executorch::ExecuTorchHandler::new();
+    let mlx_handler = mlx::MLXHandler::new();
+    let xgboost_handler = xgboost::XGBoostHandler::new();
+    let lightgbm_handler = lightgbm::LightGBMHandler::new();
+    let mlflow_handler = mlflow::MLflowHandler::new();
 
     match &format {
         None => {
@@ -53,6 +175,28 @@ pub(crate) fn handler_for(
                 Ok(Box::new(gguf_handler))
             } else if pytorch_handler.is_handler_for(file_path, &scope) {
                 Ok(Box::new(pytorch_handler))
+            } else if tensorflow_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(tensorflow_handler))
+            } else if openvino_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(openvino_handler))
+            } else if tensorrt_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(tensorrt_handler))
+            } else if executorch_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(executorch_handler))
+            } else if mlx_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(mlx_handler))
+            } else if xgboost_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(xgboost_handler))
+            } else if lightgbm_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(lightgbm_handler))
+            } else if mlflow_handler.is_handler_for(file_path, &scope) {
+                Ok(Box::new(mlflow_handler))
+            } else if let Some(plugin) = plugin::plugins()
+                .iter()
+                .find(|plugin| plugin.is_handler_for(file_path, &scope))
+            {
+                // formats of external plugins come after the built-in ones
+                Ok(Box::new(plugin.clone()))
             } else {
                 anyhow::bail!("unsupported file format")
             }
@@ -66,6 +210,22 @@ pub(crate) fn handler_for(
                 Ok(Box::new(gguf_handler))
             } else if forced_format.is_pytorch() {
                 Ok(Box::new(pytorch_handler))
+            } else if forced_format.is_tensorflow() {
+                Ok(Box::new(tensorflow_handler))
+            } else if forced_format.is_openvino() {
+                Ok(Box::new(openvino_handler))
+            } else if forced_format.is_tensorrt() {
+                Ok(Box::new(tensorrt_handler))
+            } else if forced_format.is_executorch() {
+                Ok(Box::new(executorch_handler))
+            } else if forced_format.is_mlx() {
+                Ok(Box::new(mlx_handler))
+            } else if forced_format.is_xgboost() {
+                Ok(Box::new(xgboost_handler))
+            } else if forced_format.is_lightgbm() {
+                Ok(Box::new(lightgbm_handler))
+            } else if forced_format.is_mlflow() {
+                Ok(Box::new(mlflow_handler))
             } else {
                 anyhow::bail!("unsupported file format")
             }
@@ -73,6 +233,37 @@ pub(crate) fn handler_for(
     }
 }
 
+/// Returns the file as a header scan downloads it, what hasn't been read is left zeroed.
+#[cfg(test)]
+pub(crate) fn scanned(handler: &dyn Handler, data: &[u8]) -> Vec<u8> {
+    struct Recorder<'a> {
+        cursor: std::io::Cursor<&'a [u8]>,
+        copy: Vec<u8>,
+    }
+
+    impl Read for Recorder<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let position = self.cursor.position() as usize;
+            let read = self.cursor.read(buf)?;
+            self.copy[position..position + read].copy_from_slice(&buf[..read]);
+            Ok(read)
+        }
+    }
+
+    impl Seek for Recorder<'_> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.cursor.seek(pos)
+        }
+    }
+
+    let mut recorder = Recorder {
+        cursor: std::io::Cursor::new(data),
+        copy: vec![0; data.len()],
+    };
+    handler.scan_header(&mut recorder).unwrap();
+    recorder.copy
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,6 +284,30 @@ mod tests {
 
         let handler = handler_for(Some(FileType::PyTorch), path, Scope::Inspection).unwrap();
         assert!(matches!(handler.file_type(), FileType::PyTorch));
+
+        let handler = handler_for(Some(FileType::TensorFlow), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::TensorFlow));
+
+        let handler = handler_for(Some(FileType::OpenVINO), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::OpenVINO));
+
+        let handler = handler_for(Some(FileType::TensorRT), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::TensorRT));
+
+        let handler = handler_for(Some(FileType::ExecuTorch), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::ExecuTorch));
+
+        let handler = handler_for(Some(FileType::MLX), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::MLX));
+
+        let handler = handler_for(Some(FileType::XGBoost), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::XGBoost));
+
+        let handler = handler_for(Some(FileType::LightGBM), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::LightGBM));
+
+        let handler = handler_for(Some(FileType::MLflow), path, Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::MLflow));
     }
 
     #[test]
@@ -109,6 +324,21 @@ mod tests {
 
         let handler = handler_for(None, Path::new("model.pt"), Scope::Inspection).unwrap();
         assert!(matches!(handler.file_type(), FileType::PyTorch));
+
+        let handler = handler_for(None, Path::new("saved_model.pb"), Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::TensorFlow));
+
+        let handler = handler_for(None, Path::new("model.engine"), Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::TensorRT));
+
+        let handler = handler_for(None, Path::new("model.pte"), Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::ExecuTorch));
+
+        let handler = handler_for(None, Path::new("weights.npz"), Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::MLX));
+
+        let handler = handler_for(None, Path::new("model.ubj"), Scope::Inspection).unwrap();
+        assert!(matches!(handler.file_type(), FileType::XGBoost));
     }
 
     #[test]
@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    io::{BufWriter, Cursor, Write},
+    path::{Path, PathBuf},
+};
+
+use protobuf::Message;
+
+use super::{
+    element_type, external_data_path, is_outside_of_model_folder,
+    protos::{tensor_proto::DataLocation, ModelProto, StringStringEntryProto, TensorProto},
+    replace_file, take_signature, typed_data,
+};
+
+// offsets of the external data are aligned to the pages, so that the tensors can be memory mapped
+const ALIGNMENT: usize = 4096;
+
+// protobuf can't parse messages of 2GB or more
+const MAX_MODEL_SIZE: u64 = i32::MAX as u64;
+
+/// Tensors whose data has been moved in or out of the model.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Relocation {
+    pub tensors: Vec<String>,
+    /// External data files of the input model.
+    pub data_files: Vec<PathBuf>,
+    /// Whether the model had an embedded signature, which no longer matches and is removed.
+    pub signed: bool,
+}
+
+fn read_model(file_path: &Path) -> anyhow::Result<ModelProto> {
+    let mut file = std::fs::File::open(file_path)?;
+    Ok(Message::parse_from_reader(&mut file)?)
+}
+
+fn write_model(model: &ModelProto, file_path: &Path) -> anyhow::Result<()> {
+    let size = model.compute_size();
+    if size > MAX_MODEL_SIZE {
+        anyhow::bail!(
+            "the model would be {}, over the 2GB limit of protobuf",
+            humansize::format_size(size, humansize::DECIMAL)
+        );
+    }
+
+    let mut content = Cursor::new(model.write_to_bytes()?);
+    if file_path.exists() {
+        replace_file(file_path, &mut content)
+    } else {
+        std::io::copy(&mut content, &mut std::fs::File::create(file_path)?)?;
+        Ok(())
+    }
+}
+
+// the folder the external data locations are relative to
+fn base_path_of(file_path: &Path) -> &Path {
+    file_path
+        .parent()
+        .filter(|path| !path.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+fn clear_data(tensor: &mut TensorProto) {
+    tensor.raw_data.clear();
+    tensor.float_data.clear();
+    tensor.int32_data.clear();
+    tensor.int64_data.clear();
+    tensor.double_data.clear();
+    tensor.uint64_data.clear();
+}
+
+/// Moves the external data of the initializers into the model, returning the tensors and the
+/// data files read.
+fn inline_external_data(
+    model: &mut ModelProto,
+    base_path: &Path,
+) -> anyhow::Result<(Vec<String>, Vec<PathBuf>)> {
+    let mut data_files: HashMap<PathBuf, memmap2::Mmap> = HashMap::new();
+    let mut tensors = vec![];
+
+    for tensor in model.graph.mut_or_insert_default().initializer.iter_mut() {
+        if tensor.data_location.value() != DataLocation::EXTERNAL as i32 {
+            continue;
+        }
+        let data_path = external_data_path(base_path, tensor)?
+            .ok_or_else(|| anyhow::anyhow!("tensor {} has no data location", tensor.name))?;
+        let entries: HashMap<&str, &str> = tensor
+            .external_data
+            .iter()
+            .map(|e| (e.key.as_str(), e.value.as_str()))
+            .collect();
+        let offset = entries
+            .get("offset")
+            .map(|o| o.parse::<usize>())
+            .transpose()?
+            .unwrap_or(0);
+        let length = match entries.get("length") {
+            Some(length) => length.parse::<usize>()?,
+            None => {
+                element_type(tensor.data_type)
+                    .ok_or_else(|| anyhow::anyhow!("tensor {} has no data length", tensor.name))?
+                    .size()
+                    * tensor.dims.iter().map(|d| *d as usize).product::<usize>()
+            }
+        };
+
+        if !data_files.contains_key(&data_path) {
+            let file = std::fs::File::open(&data_path)?;
+            data_files.insert(data_path.clone(), unsafe {
+                memmap2::MmapOptions::new().map(&file)?
+            });
+        }
+        let data = data_files[&data_path]
+            .get(offset..offset + length)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "data of tensor {} is out of the bounds of {}",
+                    tensor.name,
+                    data_path.display()
+                )
+            })?
+            .to_vec();
+
+        clear_data(tensor);
+        tensor.raw_data = data;
+        tensor.external_data.clear();
+        tensor.data_location = DataLocation::DEFAULT.into();
+        tensors.push(tensor.name.clone());
+    }
+
+    let mut data_files: Vec<PathBuf> = data_files.into_keys().collect();
+    data_files.sort();
+    Ok((tensors, data_files))
+}
+
+/// Writes a self-contained copy of the ONNX model, the external data of its initializers being
+/// stored in the model itself. The output can be the input itself.
+pub(crate) fn pack(input: &Path, output: &Path) -> anyhow::Result<Relocation> {
+    let mut model = read_model(input)?;
+    let signed = take_signature(&mut model)?.is_some();
+    let (tensors, data_files) = inline_external_data(&mut model, base_path_of(input))?;
+    write_model(&model, output)?;
+
+    Ok(Relocation {
+        tensors,
+        data_files,
+        signed,
+    })
+}
+
+/// Writes a copy of the ONNX model with the data of the initializers of at least threshold bytes
+/// moved to a single external data file, at the location relative to the folder of the output.
+/// External data of the input is moved to the new file too.
+pub(crate) fn unpack(
+    input: &Path,
+    output: &Path,
+    threshold: usize,
+    location: &str,
+) -> anyhow::Result<Relocation> {
+    if location.is_empty() || is_outside_of_model_folder(location) {
+        anyhow::bail!(
+            "the external data location {} must be relative to the folder of the model",
+            location
+        );
+    }
+
+    let mut model = read_model(input)?;
+    let signed = take_signature(&mut model)?.is_some();
+    let (_, data_files) = inline_external_data(&mut model, base_path_of(input))?;
+
+    let data_path = base_path_of(output).join(location);
+    let mut writer = None;
+    let mut offset: usize = 0;
+    let mut tensors = vec![];
+    for tensor in model.graph.mut_or_insert_default().initializer.iter_mut() {
+        // strings can't be stored externally
+        let Some(element_type) = element_type(tensor.data_type) else {
+            continue;
+        };
+        let is_raw = !tensor.raw_data.is_empty();
+        let data = if is_raw {
+            std::mem::take(&mut tensor.raw_data)
+        } else {
+            typed_data(tensor, element_type)
+        };
+        if data.is_empty() || data.len() < threshold {
+            if is_raw {
+                tensor.raw_data = data;
+            }
+            continue;
+        }
+
+        if writer.is_none() {
+            writer = Some(BufWriter::new(std::fs::File::create(&data_path)?));
+        }
+        let writer = writer.as_mut().unwrap();
+        let start = offset.next_multiple_of(ALIGNMENT);
+        writer.write_all(&vec![0u8; start - offset])?;
+        writer.write_all(&data)?;
+        offset = start + data.len();
+
+        clear_data(tensor);
+        tensor.external_data.clear();
+        for (key, value) in [
+            ("location", location.to_string()),
+            ("offset", start.to_string()),
+            ("length", data.len().to_string()),
+        ] {
+            let mut entry = StringStringEntryProto::new();
+            entry.key = key.to_string();
+            entry.value = value;
+            tensor.external_data.push(entry);
+        }
+        tensor.data_location = DataLocation::EXTERNAL.into();
+        tensors.push(tensor.name.clone());
+    }
+    if let Some(mut writer) = writer {
+        writer.flush()?;
+    }
+    write_model(&model, output).inspect_err(|_| {
+        if !tensors.is_empty() {
+            let _ = std::fs::remove_file(&data_path);
+        }
+    })?;
+
+    Ok(Relocation {
+        tensors,
+        data_files,
+        signed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::handlers::{onnx::OnnxHandler, Handler};
+
+    fn tensor_values(file_path: &Path) -> Vec<(String, Vec<f64>)> {
+        OnnxHandler::new()
+            .tensor_data(file_path, None)
+            .unwrap()
+            .iter()
+            .map(|tensor| (tensor.id.clone(), tensor.values().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unpack_and_pack() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("model.onnx");
+
+        let mut model = ModelProto::new();
+        let graph = model.graph.mut_or_insert_default();
+        let mut large = TensorProto::new();
+        large.name = "large".to_string();
+        large.data_type = 1;
+        large.dims = vec![64];
+        large.float_data = (0..64).map(|value| value as f32).collect();
+        let mut small = TensorProto::new();
+        small.name = "small".to_string();
+        small.data_type = 7;
+        small.dims = vec![1];
+        small.raw_data = 42i64.to_le_bytes().to_vec();
+        graph.initializer = vec![large, small];
+        std::fs::write(&input, model.write_to_bytes().unwrap()).unwrap();
+        let values = tensor_values(&input);
+
+        let unpacked = dir.path().join("unpacked.onnx");
+        let relocation = unpack(&input, &unpacked, 256, "unpacked.onnx.data").unwrap();
+        assert_eq!(relocation.tensors, vec!["large"]);
+        assert!(relocation.data_files.is_empty());
+        let data_path = dir.path().join("unpacked.onnx.data");
+        assert_eq!(std::fs::metadata(&data_path).unwrap().len(), 256);
+        assert_eq!(
+            OnnxHandler::new().paths_to_sign(&unpacked).unwrap(),
+            vec![unpacked.clone(), data_path.clone()]
+        );
+        assert_eq!(tensor_values(&unpacked), values);
+
+        // packed in place
+        let relocation = pack(&unpacked, &unpacked).unwrap();
+        assert_eq!(relocation.tensors, vec!["large"]);
+        assert_eq!(relocation.data_files, vec![data_path]);
+        assert_eq!(
+            OnnxHandler::new().paths_to_sign(&unpacked).unwrap(),
+            vec![unpacked.clone()]
+        );
+        assert_eq!(tensor_values(&unpacked), values);
+
+        assert!(unpack(&input, &unpacked, 0, "../weights.bin").is_err());
+    }
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use super::{
+    data_type_string,
+    protos::{
+        attribute_proto::AttributeType, type_proto, AttributeProto, GraphProto, ModelProto,
+        TensorProto, ValueInfoProto,
+    },
+    read_model_constants, shapes,
+};
+use crate::core::{
+    graph::{Graph, GraphNode, GraphTensor},
+    operators::{OperatorCount, Operators, DEFAULT_DOMAIN},
+    Dim, ValueInfo,
+};
+
+// longer attribute values, such as embedded strings and long lists, are truncated
+const MAX_VALUES: usize = 16;
+const MAX_STRING_SIZE: usize = 80;
+
+fn shape_string(elem_type: i32, dims: impl Iterator<Item = String>) -> String {
+    format!(
+        "{}[{}]",
+        data_type_string(elem_type),
+        dims.collect::<Vec<_>>().join(",")
+    )
+}
+
+fn tensor_shape(tensor: &TensorProto) -> String {
+    shape_string(tensor.data_type, tensor.dims.iter().map(i64::to_string))
+}
+
+fn truncated_string(bytes: &[u8]) -> String {
+    let value = String::from_utf8_lossy(bytes);
+    if value.chars().count() > MAX_STRING_SIZE {
+        format!(
+            "{}…",
+            value.chars().take(MAX_STRING_SIZE - 1).collect::<String>()
+        )
+    } else {
+        value.to_string()
+    }
+}
+
+fn list_string<T: ToString>(values: &[T]) -> String {
+    let mut items: Vec<String> = values
+        .iter()
+        .take(MAX_VALUES)
+        .map(ToString::to_string)
+        .collect();
+    if values.len() > MAX_VALUES {
+        items.push(format!("… {} more", values.len() - MAX_VALUES));
+    }
+    format!("[{}]", items.join(", "))
+}
+
+/// The value of the attribute, formatted and truncated if long.
+pub(super) fn attribute_value(attribute: &AttributeProto) -> String {
+    if !attribute.ref_attr_name.is_empty() {
+        return format!("ref {}", attribute.ref_attr_name);
+    }
+    match attribute.type_.enum_value() {
+        Ok(AttributeType::FLOAT) => attribute.f.to_string(),
+        Ok(AttributeType::INT) => attribute.i.to_string(),
+        Ok(AttributeType::STRING) => truncated_string(&attribute.s),
+        Ok(AttributeType::TENSOR) => tensor_shape(&attribute.t),
+        Ok(AttributeType::GRAPH) => format!("graph of {} nodes", attribute.g.node.len()),
+        Ok(AttributeType::FLOATS) => list_string(&attribute.floats),
+        Ok(AttributeType::INTS) => list_string(&attribute.ints),
+        Ok(AttributeType::STRINGS) => list_string(
+            &attribute
+                .strings
+                .iter()
+                .map(|value| truncated_string(value))
+                .collect::<Vec<_>>(),
+        ),
+        Ok(AttributeType::TENSORS) => format!("{} tensors", attribute.tensors.len()),
+        Ok(AttributeType::GRAPHS) => format!("{} graphs", attribute.graphs.len()),
+        Ok(other) => format!("{:?}", other).to_lowercase(),
+        Err(value) => format!("unknown type {}", value),
+    }
+}
+
+/// The dimensions of the tensor, if its rank is known.
+pub(super) fn dims_of(tensor: &type_proto::Tensor) -> Option<Vec<Dim>> {
+    let shape = tensor.shape.as_ref()?;
+    Some(
+        shape
+            .dim
+            .iter()
+            .map(|dim| {
+                if dim.has_dim_value() {
+                    Dim::Fixed(dim.dim_value())
+                } else if dim.has_dim_param() {
+                    Dim::Named(dim.dim_param().to_string())
+                } else {
+                    Dim::Named("?".to_string())
+                }
+            })
+            .collect(),
+    )
+}
+
+fn value_info(value: &ValueInfoProto) -> ValueInfo {
+    let (dtype, dims) = match &value.type_.value {
+        Some(type_proto::Value::TensorType(tensor)) => (
+            data_type_string(tensor.elem_type).to_string(),
+            dims_of(tensor),
+        ),
+        Some(type_proto::Value::SparseTensorType(tensor)) => (
+            format!("sparse {}", data_type_string(tensor.elem_type)),
+            None,
+        ),
+        Some(type_proto::Value::SequenceType(_)) => ("sequence".to_string(), None),
+        Some(type_proto::Value::MapType(_)) => ("map".to_string(), None),
+        Some(type_proto::Value::OptionalType(_)) => ("optional".to_string(), None),
+        _ => ("unknown".to_string(), None),
+    };
+    ValueInfo {
+        name: value.name.clone(),
+        dtype,
+        dims,
+    }
+}
+
+/// The inputs of the graph, but its initializers older models list among them, and its outputs.
+pub(super) fn inputs_and_outputs(graph: &GraphProto) -> (Vec<ValueInfo>, Vec<ValueInfo>) {
+    let inputs = graph
+        .input
+        .iter()
+        .filter(|input| {
+            !graph
+                .initializer
+                .iter()
+                .any(|initializer| initializer.name == input.name)
+        })
+        .map(value_info)
+        .collect();
+    (inputs, graph.output.iter().map(value_info).collect())
+}
+
+/// The element types and dimensions of the tensors of the graph, declared or inferred from its
+/// inputs and initializers.
+pub(super) fn tensor_shapes(graph: &GraphProto) -> HashMap<&str, String> {
+    shapes::infer_shapes(graph)
+        .into_iter()
+        .map(|(name, value)| (name, value.shape()))
+        .collect()
+}
+
+/// The nodes of the main graph of the model, with its inputs and outputs.
+pub(crate) fn graph_of(model: &ModelProto, name: &str) -> Graph {
+    let graph = &model.graph;
+    let shapes = tensor_shapes(graph);
+
+    let tensor = |name: &String| GraphTensor {
+        name: name.clone(),
+        shape: shapes.get(name.as_str()).cloned(),
+    };
+    let value_node = |name: &String, op_type: &str, is_input: bool| GraphNode {
+        name: name.clone(),
+        op_type: op_type.to_string(),
+        attributes: vec![],
+        inputs: if is_input { vec![] } else { vec![tensor(name)] },
+        outputs: if is_input { vec![tensor(name)] } else { vec![] },
+    };
+
+    // older models list their initializers among the inputs
+    let inputs = graph.input.iter().filter(|input| {
+        !graph
+            .initializer
+            .iter()
+            .any(|initializer| initializer.name == input.name)
+    });
+
+    let mut nodes: Vec<GraphNode> = inputs
+        .map(|input| value_node(&input.name, "Input", true))
+        .collect();
+    for (index, node) in graph.node.iter().enumerate() {
+        nodes.push(GraphNode {
+            name: if node.name.is_empty() {
+                format!("op#{}", index)
+            } else {
+                node.name.clone()
+            },
+            op_type: if node.domain.is_empty() || node.domain == "ai.onnx" {
+                node.op_type.clone()
+            } else {
+                format!("{}.{}", node.domain, node.op_type)
+            },
+            attributes: node
+                .attribute
+                .iter()
+                .map(|attribute| (attribute.name.clone(), attribute_value(attribute)))
+                .collect(),
+            // optional inputs and outputs left out are empty names
+            inputs: node
+                .input
+                .iter()
+                .filter(|name| !name.is_empty())
+                .map(tensor)
+                .collect(),
+            outputs: node
+                .output
+                .iter()
+                .filter(|name| !name.is_empty())
+                .map(tensor)
+                .collect(),
+        });
+    }
+    nodes.extend(
+        graph
+            .output
+            .iter()
+            .map(|output| value_node(&output.name, "Output", false)),
+    );
+
+    Graph {
+        name: name.to_string(),
+        nodes,
+    }
+}
+
+// counts the nodes of the graph and of the subgraphs of their attributes, such as the branches
+// of If and the bodies of Loop and Scan
+fn count_operators<'a>(graph: &'a GraphProto, counts: &mut HashMap<(&'a str, &'a str), usize>) {
+    for node in &graph.node {
+        let domain = if node.domain == DEFAULT_DOMAIN {
+            ""
+        } else {
+            node.domain.as_str()
+        };
+        *counts.entry((domain, node.op_type.as_str())).or_default() += 1;
+        for attribute in &node.attribute {
+            for subgraph in attribute.g.iter().chain(&attribute.graphs) {
+                count_operators(subgraph, counts);
+            }
+        }
+    }
+}
+
+/// The operators of the model by number of nodes, with the opset it imports for each domain.
+pub(super) fn operators(model: &ModelProto) -> Operators {
+    let mut counts = HashMap::new();
+    count_operators(&model.graph, &mut counts);
+
+    let mut counts: Vec<_> = counts
+        .into_iter()
+        .map(|((domain, op_type), count)| OperatorCount {
+            domain: domain.to_string(),
+            op_type: op_type.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.domain.cmp(&b.domain))
+            .then_with(|| a.op_type.cmp(&b.op_type))
+    });
+
+    let opsets: BTreeMap<String, i64> = model
+        .opset_import
+        .iter()
+        .map(|opset| {
+            let domain = if opset.domain.is_empty() {
+                DEFAULT_DOMAIN
+            } else {
+                opset.domain.as_str()
+            };
+            (domain.to_string(), opset.version)
+        })
+        .collect();
+
+    Operators {
+        opsets,
+        counts,
+        unavailable: vec![],
+    }
+}
+
+/// Reads the graph of the model, skipping the tensor data.
+pub(crate) fn graph(file_path: &Path) -> anyhow::Result<Graph> {
+    let model = read_model_constants(file_path)?;
+    let name = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(graph_of(&model, &name))
+}
+
+#[cfg(test)]
+mod tests {
+    use protobuf::Message;
+
+    use super::super::protos::{
+        tensor_shape_proto::Dimension, type_proto::Tensor, NodeProto, OperatorSetIdProto, TypeProto,
+    };
+    use super::*;
+
+    fn value(name: &str, dims: &[Dimension]) -> ValueInfoProto {
+        let mut tensor = Tensor::new();
+        tensor.elem_type = 1;
+        tensor.shape.mut_or_insert_default().dim = dims.to_vec();
+        let mut type_ = TypeProto::new();
+        type_.value = Some(type_proto::Value::TensorType(tensor));
+
+        let mut value = ValueInfoProto::new();
+        value.name = name.to_string();
+        value.type_ = Some(type_).into();
+        value
+    }
+
+    #[test]
+    fn test_graph() {
+        let mut batch = Dimension::new();
+        batch.set_dim_param("batch".to_string());
+        let mut channels = Dimension::new();
+        channels.set_dim_value(3);
+
+        let mut weight = TensorProto::new();
+        weight.name = "weight".to_string();
+        weight.data_type = 1;
+        weight.dims = vec![8, 3, 3, 3];
+        weight.raw_data = vec![0; 8 * 3 * 3 * 3 * 4];
+
+        let mut kernel_shape = AttributeProto::new();
+        kernel_shape.name = "kernel_shape".to_string();
+        kernel_shape.type_ = AttributeType::INTS.into();
+        kernel_shape.ints = vec![3, 3];
+
+        let mut conv = NodeProto::new();
+        conv.op_type = "Conv".to_string();
+        conv.input = vec!["x".to_string(), "weight".to_string(), String::new()];
+        conv.output = vec!["y".to_string()];
+        conv.attribute.push(kernel_shape);
+
+        let mut model = ModelProto::new();
+        let graph = model.graph.mut_or_insert_default();
+        graph.input = vec![value("x", &[batch, channels]), value("weight", &[])];
+        graph.output = vec![value("y", &[])];
+        graph.initializer.push(weight);
+        graph.node.push(conv);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.onnx");
+        std::fs::write(&file_path, model.write_to_bytes().unwrap()).unwrap();
+
+        let (inputs, outputs) = inputs_and_outputs(&model.graph);
+        assert_eq!(
+            inputs,
+            vec![ValueInfo {
+                name: "x".to_string(),
+                dtype: "FLOAT".to_string(),
+                dims: Some(vec![Dim::Named("batch".to_string()), Dim::Fixed(3)]),
+            }]
+        );
+        assert_eq!(inputs[0].shape(), "FLOAT[batch,3]");
+        assert_eq!(outputs[0].shape(), "FLOAT[]");
+
+        let graph = super::graph(&file_path).unwrap();
+        assert_eq!(graph.name, "model");
+        let nodes: Vec<_> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.op_type.as_str()))
+            .collect();
+        // the initializer isn't an input of the graph
+        assert_eq!(
+            nodes,
+            vec![("x", "Input"), ("op#0", "Conv"), ("y", "Output")]
+        );
+
+        let conv = &graph.nodes[1];
+        assert_eq!(
+            conv.attributes,
+            vec![("kernel_shape".to_string(), "[3, 3]".to_string())]
+        );
+        let shapes: Vec<_> = conv
+            .inputs
+            .iter()
+            .map(|input| input.shape.as_deref())
+            .collect();
+        assert_eq!(shapes, vec![Some("FLOAT[batch,3]"), Some("FLOAT[8,3,3,3]")]);
+    }
+
+    #[test]
+    fn test_operators() {
+        let node = |domain: &str, op_type: &str| {
+            let mut node = NodeProto::new();
+            node.domain = domain.to_string();
+            node.op_type = op_type.to_string();
+            node
+        };
+        let opset = |domain: &str, version: i64| {
+            let mut opset = OperatorSetIdProto::new();
+            opset.domain = domain.to_string();
+            opset.version = version;
+            opset
+        };
+
+        let mut branch = AttributeProto::new();
+        branch.name = "then_branch".to_string();
+        branch.type_ = AttributeType::GRAPH.into();
+        branch.g.mut_or_insert_default().node = vec![node("", "Relu"), node("", "Gelu")];
+        let mut if_node = node("", "If");
+        if_node.attribute.push(branch);
+
+        let mut model = ModelProto::new();
+        model.opset_import = vec![opset("", 20), opset("com.microsoft", 1)];
+        model.graph.mut_or_insert_default().node = vec![
+            node("", "Relu"),
+            node("ai.onnx", "Relu"),
+            node("com.microsoft", "FusedGemm"),
+            if_node,
+        ];
+
+        let operators = operators(&model);
+        assert_eq!(
+            operators.opsets,
+            BTreeMap::from([
+                ("ai.onnx".to_string(), 20),
+                ("com.microsoft".to_string(), 1)
+            ])
+        );
+        let counts: Vec<_> = operators
+            .counts
+            .iter()
+            .map(|count| (count.name(), count.count))
+            .collect();
+        // the nodes of the branch are counted
+        assert_eq!(
+            counts,
+            vec![
+                ("Relu".to_string(), 3),
+                ("Gelu".to_string(), 1),
+                ("If".to_string(), 1),
+                ("com.microsoft.FusedGemm".to_string(), 1),
+            ]
+        );
+        assert_eq!(operators.unavailable_in(17), vec!["Gelu requires opset 20"]);
+    }
+}
@@ -1,22 +1,57 @@
 use std::{
     collections::{HashMap, HashSet},
+    io::{Cursor, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
+mod external;
+mod graph;
 mod protos;
+mod scan;
+mod shapes;
+mod validate;
 
 use dot_graph::Graph;
 use protobuf::Message;
 
-use protos::{tensor_proto::DataLocation, ModelProto, NodeProto, TensorProto};
+use protos::{
+    tensor_proto::DataLocation, ModelProto, NodeProto, StringStringEntryProto, TensorProto,
+};
 use rayon::prelude::*;
 
-use crate::{
-    cli::DetailLevel,
-    core::{handlers::Handler, FileType, Inspection, Metadata, TensorDescriptor},
+use crate::core::{
+    data::{ElementType, Encoding, TensorData},
+    graph::dot_label,
+    handlers::Handler,
+    DetailLevel, FileType, Inspection, Metadata, TensorDescriptor, ValueInfo,
+};
+
+use super::{
+    is_outside_of_model_folder, replace_file, EmbeddedSignature, ReadSeek, Scope,
+    EMBEDDED_SIGNATURE_KEY,
 };
 
-use super::Scope;
+pub(crate) use external::{pack, unpack, Relocation};
+pub(crate) use scan::scan;
+pub(crate) use validate::validate;
+
+// attributes shown on the operators of the DOT graph, those telling apart the export settings
+const DOT_ATTRIBUTES: &[&str] = &[
+    "kernel_shape",
+    "strides",
+    "pads",
+    "dilations",
+    "group",
+    "epsilon",
+    "axis",
+    "axes",
+    "perm",
+    "transA",
+    "transB",
+    "mode",
+    "to",
+];
 
 #[inline]
 fn data_type_bits(dtype: i32) -> usize {
@@ -78,6 +113,63 @@ pub(crate) fn data_type_string(dtype: i32) -> &'static str {
     }
 }
 
+fn element_type(dtype: i32) -> Option<ElementType> {
+    match dtype {
+        1 => Some(ElementType::F32),
+        2 => Some(ElementType::U8),
+        3 => Some(ElementType::I8),
+        4 => Some(ElementType::U16),
+        5 => Some(ElementType::I16),
+        6 => Some(ElementType::I32),
+        7 => Some(ElementType::I64),
+        9 => Some(ElementType::Bool),
+        10 => Some(ElementType::F16),
+        11 => Some(ElementType::F64),
+        12 => Some(ElementType::U32),
+        13 => Some(ElementType::U64),
+        16 => Some(ElementType::BF16),
+        _ => None,
+    }
+}
+
+/// Serializes the values of the typed data fields as little endian bytes of the element type.
+fn typed_data(tensor: &TensorProto, element_type: ElementType) -> Vec<u8> {
+    let size = element_type.size();
+    if !tensor.float_data.is_empty() {
+        tensor
+            .float_data
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    } else if !tensor.double_data.is_empty() {
+        tensor
+            .double_data
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    } else if !tensor.int64_data.is_empty() {
+        tensor
+            .int64_data
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    } else if !tensor.uint64_data.is_empty() {
+        // also used for uint32
+        tensor
+            .uint64_data
+            .iter()
+            .flat_map(|v| v.to_le_bytes()[..size].to_vec())
+            .collect()
+    } else {
+        // narrower types, including the bits of float16 and bfloat16, are stored as int32
+        tensor
+            .int32_data
+            .iter()
+            .flat_map(|v| v.to_le_bytes()[..size.min(4)].to_vec())
+            .collect()
+    }
+}
+
 fn build_tensor_descriptor(tensor: &TensorProto) -> TensorDescriptor {
     let mut metadata = Metadata::new();
     if !tensor.doc_string.is_empty() {
@@ -107,7 +199,103 @@ fn build_tensor_descriptor(tensor: &TensorProto) -> TensorDescriptor {
                 / 8
         },
         metadata,
+        stats: None,
+        preview: None,
+    }
+}
+
+/// Returns the path of the external data of the tensor, refusing locations outside of the folder
+/// of the model so that a malicious model can't get arbitrary files read, hashed or signed.
+fn external_data_path(base_path: &Path, tensor: &TensorProto) -> anyhow::Result<Option<PathBuf>> {
+    let Some(location) = tensor
+        .external_data
+        .iter()
+        .find(|entry| entry.key == "location")
+        .map(|entry| entry.value.as_str())
+    else {
+        return Ok(None);
+    };
+
+    if is_outside_of_model_folder(location) {
+        return Err(anyhow::anyhow!(
+            "tensor {} reads its external data from {}, outside of the model folder",
+            tensor.name,
+            location
+        ));
     }
+
+    Ok(Some(base_path.join(location)))
+}
+
+// removes the embedded signature from the model metadata, returning it
+fn take_signature(model: &mut ModelProto) -> anyhow::Result<Option<String>> {
+    let mut signatures = model
+        .metadata_props
+        .extract_if(.., |prop| prop.key == EMBEDDED_SIGNATURE_KEY)
+        .map(|prop| prop.value);
+    let signature = signatures.next();
+    if signatures.next().is_some() {
+        anyhow::bail!("multiple {} metadata properties", EMBEDDED_SIGNATURE_KEY);
+    }
+    Ok(signature)
+}
+
+const METADATA_PROPS_FIELD: u64 = 14;
+
+/// Returns the byte range of the metadata property holding the embedded signature in the
+/// serialized model, and its value, without parsing the rest of the model.
+fn signature_range(data: &[u8]) -> anyhow::Result<Option<(std::ops::Range<usize>, String)>> {
+    let mut reader = Cursor::new(data);
+    let mut signature = None;
+    while (reader.position() as usize) < data.len() {
+        let start = reader.position() as usize;
+        let key = read_varint(&mut reader)?;
+        match key & 7 {
+            0 => {
+                read_varint(&mut reader)?;
+            }
+            1 => reader.set_position(reader.position() + 8),
+            5 => reader.set_position(reader.position() + 4),
+            2 => {
+                let size = read_varint(&mut reader)?;
+                let value_start = reader.position() as usize;
+                let end = reader
+                    .position()
+                    .checked_add(size)
+                    .filter(|end| *end <= data.len() as u64)
+                    .ok_or_else(|| anyhow::anyhow!("invalid protobuf field size"))?
+                    as usize;
+                reader.set_position(end as u64);
+
+                if key >> 3 == METADATA_PROPS_FIELD {
+                    let prop = StringStringEntryProto::parse_from_bytes(&data[value_start..end])?;
+                    if prop.key == EMBEDDED_SIGNATURE_KEY {
+                        if signature.is_some() {
+                            anyhow::bail!(
+                                "multiple {} metadata properties",
+                                EMBEDDED_SIGNATURE_KEY
+                            );
+                        }
+                        signature = Some((start..end, prop.value));
+                    }
+                }
+            }
+            wire_type => anyhow::bail!("unsupported protobuf wire type {}", wire_type),
+        }
+    }
+
+    if reader.position() as usize != data.len() {
+        anyhow::bail!("protobuf message overflows its size");
+    }
+    Ok(signature)
+}
+
+// reads the file without the given range of bytes
+fn read_without(file_path: &Path, range: std::ops::Range<usize>) -> anyhow::Result<impl Read> {
+    let before = std::fs::File::open(file_path)?.take(range.start as u64);
+    let mut after = std::fs::File::open(file_path)?;
+    after.seek(SeekFrom::Start(range.end as u64))?;
+    Ok(before.chain(after))
 }
 
 #[inline]
@@ -126,23 +314,17 @@ fn in_range(low: char, c: char, high: char) -> bool {
 }
 
 fn str_to_node_name(s: &str) -> String {
-    let mut result = String::new();
+    let result: String = s
+        .chars()
+        .map(|c| if is_constituent(c) { c } else { '_' })
+        .collect();
+    let result = result.trim_matches('_');
 
-    // make sure the name starts with a letter or underscore or dot
-    if let Some(first) = s.chars().next() {
-        if !is_letter_or_underscore_or_dot(first) {
-            result.push('_');
-        }
+    // make sure the name starts with a letter or underscore or dot, as numeric tensor names don't
+    match result.chars().next() {
+        Some(first) if is_letter_or_underscore_or_dot(first) => result.to_string(),
+        _ => format!("_{}", result),
     }
-
-    for c in s.chars() {
-        if is_constituent(c) {
-            result.push(c);
-        } else {
-            result.push('_');
-        }
-    }
-    result.trim_matches('_').to_string()
 }
 
 fn op_to_dot_node(op: &NodeProto, op_id: usize) -> dot_graph::Node {
@@ -153,7 +335,303 @@ fn op_to_dot_node(op: &NodeProto, op_id: usize) -> dot_graph::Node {
     };
     let node_name = str_to_node_name(&node_label);
 
-    dot_graph::Node::new(&node_name).label(&node_label)
+    let mut lines = vec![node_label];
+    lines.extend(
+        op.attribute
+            .iter()
+            .filter(|attribute| DOT_ATTRIBUTES.contains(&attribute.name.as_str()))
+            .map(|attribute| format!("{}={}", attribute.name, graph::attribute_value(attribute))),
+    );
+
+    dot_graph::Node::new(&node_name).label(&dot_label(&lines))
+}
+
+/// Messages of the model that can contain tensor data, everything else is read through.
+#[derive(Debug, Clone, Copy)]
+enum Scanned {
+    Model,
+    Function,
+    Graph,
+    Node,
+    Attribute,
+    Tensor,
+    SparseTensor,
+}
+
+impl Scanned {
+    // type of the nested message in the field, if it's one of the above
+    fn nested(self, field: u64) -> Option<Self> {
+        match (self, field) {
+            (Scanned::Model, 7) | (Scanned::Attribute, 6) | (Scanned::Attribute, 11) => {
+                Some(Scanned::Graph)
+            }
+            (Scanned::Model, 25) => Some(Scanned::Function),
+            (Scanned::Graph, 1) | (Scanned::Function, 7) => Some(Scanned::Node),
+            (Scanned::Node, 5) => Some(Scanned::Attribute),
+            (Scanned::Graph, 5)
+            | (Scanned::Attribute, 5)
+            | (Scanned::Attribute, 10)
+            | (Scanned::SparseTensor, 1)
+            | (Scanned::SparseTensor, 2) => Some(Scanned::Tensor),
+            (Scanned::Graph, 15) | (Scanned::Attribute, 22) | (Scanned::Attribute, 23) => {
+                Some(Scanned::SparseTensor)
+            }
+            _ => None,
+        }
+    }
+
+    // packed float, int32, int64, double and uint64 values and raw data, zeros once skipped
+    // are still valid values
+    fn is_tensor_data(self, field: u64) -> bool {
+        matches!(self, Scanned::Tensor) && matches!(field, 4 | 5 | 7 | 9 | 10 | 11)
+    }
+}
+
+fn read_varint(reader: &mut dyn Read) -> anyhow::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("invalid protobuf varint")
+}
+
+fn scan_bytes(reader: &mut dyn Read, size: u64) -> anyhow::Result<()> {
+    if std::io::copy(&mut reader.take(size), &mut std::io::sink())? != size {
+        anyhow::bail!("unexpected end of protobuf message");
+    }
+    Ok(())
+}
+
+// walks the protobuf wire format of the message ending at the given offset
+fn scan_message(reader: &mut dyn ReadSeek, message: Scanned, end: u64) -> anyhow::Result<()> {
+    while reader.stream_position()? < end {
+        let key = read_varint(reader)?;
+        let field = key >> 3;
+        match key & 7 {
+            0 => {
+                read_varint(reader)?;
+            }
+            1 => scan_bytes(reader, 8)?,
+            5 => scan_bytes(reader, 4)?,
+            2 => {
+                let size = read_varint(reader)?;
+                let nested_end = reader
+                    .stream_position()?
+                    .checked_add(size)
+                    .filter(|nested_end| *nested_end <= end)
+                    .ok_or_else(|| anyhow::anyhow!("invalid protobuf field size"))?;
+
+                if let Some(nested) = message.nested(field) {
+                    scan_message(reader, nested, nested_end)?;
+                } else if message.is_tensor_data(field) {
+                    reader.seek(SeekFrom::Start(nested_end))?;
+                } else {
+                    scan_bytes(reader, size)?;
+                }
+            }
+            wire_type => anyhow::bail!("unsupported protobuf wire type {}", wire_type),
+        }
+    }
+
+    if reader.stream_position()? != end {
+        anyhow::bail!("protobuf message overflows its size");
+    }
+    Ok(())
+}
+
+// the default recursion limit of protobuf parsers
+const MAX_MESSAGE_DEPTH: usize = 100;
+
+// tensor data kept to infer shapes, enough for the shapes, axes and indexes of the graph
+const MAX_CONSTANT_SIZE: u64 = 512;
+
+fn write_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+// copies the message ending at the given offset without the data of its tensors larger than
+// the given size, the sizes of the nested messages being updated
+fn strip_message(
+    reader: &mut Cursor<&[u8]>,
+    message: Scanned,
+    end: u64,
+    stripped: &mut Vec<u8>,
+    max_kept_size: u64,
+    depth: usize,
+) -> anyhow::Result<()> {
+    if depth > MAX_MESSAGE_DEPTH {
+        anyhow::bail!("protobuf messages nested too deeply");
+    }
+
+    while reader.position() < end {
+        let start = reader.position();
+        let key = read_varint(reader)?;
+        let field = key >> 3;
+        match key & 7 {
+            0 => {
+                read_varint(reader)?;
+            }
+            1 => reader.set_position(reader.position() + 8),
+            5 => reader.set_position(reader.position() + 4),
+            2 => {
+                let size = read_varint(reader)?;
+                let nested_end = reader
+                    .position()
+                    .checked_add(size)
+                    .filter(|nested_end| *nested_end <= end)
+                    .ok_or_else(|| anyhow::anyhow!("invalid protobuf field size"))?;
+
+                if let Some(nested) = message.nested(field) {
+                    let mut nested_stripped = vec![];
+                    strip_message(
+                        reader,
+                        nested,
+                        nested_end,
+                        &mut nested_stripped,
+                        max_kept_size,
+                        depth + 1,
+                    )?;
+                    write_varint(stripped, key);
+                    write_varint(stripped, nested_stripped.len() as u64);
+                    stripped.extend(nested_stripped);
+                    continue;
+                }
+                reader.set_position(nested_end);
+                if message.is_tensor_data(field) && size > max_kept_size {
+                    continue;
+                }
+            }
+            wire_type => anyhow::bail!("unsupported protobuf wire type {}", wire_type),
+        }
+        if reader.position() > end {
+            anyhow::bail!("protobuf message overflows its size");
+        }
+        stripped.extend_from_slice(&reader.get_ref()[start as usize..reader.position() as usize]);
+    }
+
+    if reader.position() != end {
+        anyhow::bail!("protobuf message overflows its size");
+    }
+    Ok(())
+}
+
+/// Parses the model without the data of its tensors, for the structure of models of several GB
+/// to be read quickly and without loading their weights in memory.
+fn read_model_structure(file_path: &Path) -> anyhow::Result<ModelProto> {
+    let file = std::fs::File::open(file_path)?;
+    let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
+    parse_model_structure(&data)
+}
+
+// same as read_model_structure, for a model already in memory
+fn parse_model_structure(data: &[u8]) -> anyhow::Result<ModelProto> {
+    parse_model_stripped(data, 0)
+}
+
+/// Same as read_model_structure, keeping the values of the small tensors, such as the shapes
+/// given to Reshape, for the shapes of the tensors of the graph to be inferred.
+fn read_model_constants(file_path: &Path) -> anyhow::Result<ModelProto> {
+    let file = std::fs::File::open(file_path)?;
+    let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
+    parse_model_stripped(&data, MAX_CONSTANT_SIZE)
+}
+
+// parses the model without the tensor data larger than the given size
+fn parse_model_stripped(data: &[u8], max_kept_size: u64) -> anyhow::Result<ModelProto> {
+    let mut stripped = vec![];
+    strip_message(
+        &mut Cursor::new(data),
+        Scanned::Model,
+        data.len() as u64,
+        &mut stripped,
+        max_kept_size,
+        0,
+    )?;
+    Ok(Message::parse_from_bytes(&stripped)?)
+}
+
+// fields of ModelProto with their wire type and whether they are singular
+const MODEL_FIELDS: &[(u64, u64, bool)] = &[
+    (1, 0, true),
+    (2, 2, true),
+    (3, 2, true),
+    (4, 2, true),
+    (5, 0, true),
+    (6, 2, true),
+    (7, 2, true),
+    (8, 2, false),
+    (14, 2, false),
+    (20, 2, false),
+    (25, 2, false),
+];
+
+// skips the next top level field of the model, if it's a field of ModelProto
+fn skip_model_field(reader: &mut Cursor<&[u8]>, seen: &mut HashSet<u64>) -> Option<()> {
+    let key = read_varint(reader).ok()?;
+    let (field, wire_type) = (key >> 3, key & 7);
+    let (_, _, singular) = MODEL_FIELDS
+        .iter()
+        .find(|(number, expected, _)| *number == field && *expected == wire_type)?;
+    // writers never repeat a singular field, a second one is appended
+    if !seen.insert(field) && *singular {
+        return None;
+    }
+
+    let size = read_varint(reader).ok()?;
+    if wire_type == 2 {
+        let end = reader
+            .position()
+            .checked_add(size)
+            .filter(|end| *end <= reader.get_ref().len() as u64)?;
+        reader.set_position(end);
+    }
+    Some(())
+}
+
+/// Returns the size of the model at the beginning of the data, the top level fields end at the
+/// first unknown, repeated or truncated field.
+fn model_size(data: &[u8]) -> u64 {
+    let mut reader = Cursor::new(data);
+    let mut seen = HashSet::new();
+    let mut size = 0;
+    while size < data.len() as u64 && skip_model_field(&mut reader, &mut seen).is_some() {
+        size = reader.position();
+    }
+    size
+}
+
+// adds the operators of the nodes and of their subgraphs
+fn collect_operators(nodes: &[NodeProto], operators: &mut HashSet<String>) {
+    for node in nodes {
+        operators.insert(node.op_type.clone());
+        for attribute in &node.attribute {
+            for graph in attribute.g.iter().chain(&attribute.graphs) {
+                collect_operators(&graph.node, operators);
+            }
+        }
+    }
+}
+
+/// Returns the types of the operators used by the graph of the model, its subgraphs and functions.
+pub(crate) fn operator_types(file_path: &Path) -> anyhow::Result<HashSet<String>> {
+    let data = std::fs::read(file_path)?;
+    let model: ModelProto = Message::parse_from_bytes(&data[..model_size(&data) as usize])?;
+
+    let mut operators = HashSet::new();
+    collect_operators(&model.graph.node, &mut operators);
+    for function in &model.functions {
+        collect_operators(&function.node, &mut operators);
+    }
+    Ok(operators)
 }
 
 pub(crate) struct OnnxHandler;
@@ -164,6 +642,125 @@ impl OnnxHandler {
     }
 }
 
+/// Inspects the ONNX model read in memory, without parsing the data of its tensors.
+pub(crate) fn inspect_buffer(
+    data: &[u8],
+    detail: DetailLevel,
+    filter: Option<String>,
+) -> anyhow::Result<Inspection> {
+    let mut inspection = Inspection::default();
+
+    // the data is only read for the stats
+    let onnx_model = parse_model_structure(data)?;
+
+    inspection.file_size = data.len() as u64;
+
+    inspection.file_type = FileType::ONNX;
+
+    if onnx_model.model_version != 0 {
+        inspection.version = format!(
+            "{} (IR v{})",
+            onnx_model.model_version, onnx_model.ir_version
+        );
+    } else {
+        inspection.version = format!("IR v{}", onnx_model.ir_version);
+    }
+
+    // TODO: check the presence of sparse tensors from graph.sparse_initializer
+
+    inspection.num_tensors = onnx_model.graph.initializer.len();
+    inspection.data_size = onnx_model
+        .graph
+        .initializer
+        .par_iter()
+        .map(|t| {
+            if t.dims.is_empty() {
+                0
+            } else {
+                data_type_bits(t.data_type) * t.dims.iter().map(|d| *d as usize).product::<usize>()
+            }
+        })
+        .sum::<usize>()
+        / 8;
+
+    inspection.unique_shapes = onnx_model
+        .graph
+        .initializer
+        .par_iter()
+        .map(|t| t.dims.iter().map(|d| *d as usize).collect::<Vec<_>>())
+        .filter(|shape| !shape.is_empty())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    // sort shapes by volume
+    inspection.unique_shapes.sort_by(|a, b| {
+        let size_a: usize = a.iter().product();
+        let size_b: usize = b.iter().product();
+        size_a.cmp(&size_b)
+    });
+
+    inspection.unique_dtypes = onnx_model
+        .graph
+        .initializer
+        .par_iter()
+        .map(|t| data_type_string(t.data_type).to_string())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    if !onnx_model.producer_name.is_empty() {
+        inspection.metadata.insert(
+            "producer_name".to_string(),
+            onnx_model.producer_name.clone(),
+        );
+    }
+
+    if !onnx_model.producer_version.is_empty() {
+        inspection.metadata.insert(
+            "producer_version".to_string(),
+            onnx_model.producer_version.clone(),
+        );
+    }
+
+    if !onnx_model.domain.is_empty() {
+        inspection
+            .metadata
+            .insert("domain".to_string(), onnx_model.domain.clone());
+    }
+
+    if !onnx_model.doc_string.is_empty() {
+        inspection
+            .metadata
+            .insert("doc_string".to_string(), onnx_model.doc_string.clone());
+    }
+
+    onnx_model.metadata_props.iter().for_each(|prop| {
+        inspection
+            .metadata
+            .insert(prop.key.clone(), prop.value.clone());
+    });
+
+    let (inputs, outputs) = graph::inputs_and_outputs(&onnx_model.graph);
+    inspection.inputs = Some(inputs);
+    inspection.outputs = Some(outputs);
+    inspection.operators = Some(graph::operators(&onnx_model));
+
+    if matches!(detail, DetailLevel::Full) {
+        inspection.tensors = Some(
+            onnx_model
+                .graph
+                .initializer
+                .par_iter()
+                .filter(|t_info| filter.as_ref().is_none_or(|f| t_info.name.contains(f)))
+                .map(build_tensor_descriptor)
+                .collect(),
+        );
+    }
+
+    Ok(inspection)
+}
+
 impl Handler for OnnxHandler {
     fn file_type(&self) -> FileType {
         FileType::ONNX
@@ -175,16 +772,14 @@ impl Handler for OnnxHandler {
             .unwrap_or_default()
             .to_str()
             .unwrap_or("")
-            .to_ascii_lowercase()
-            == "onnx"
+            .eq_ignore_ascii_case("onnx")
     }
 
     fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
         let base_path = file_path
             .parent()
             .ok_or_else(|| anyhow::anyhow!("no parent path"))?;
-        let mut file = std::fs::File::open(file_path)?;
-        let onnx_model: ModelProto = Message::parse_from_reader(&mut file)?;
+        let onnx_model = read_model_structure(file_path)?;
 
         // ONNX files can contain external data
         let external_paths: HashSet<PathBuf> = onnx_model
@@ -192,19 +787,8 @@ impl Handler for OnnxHandler {
             .initializer
             .par_iter()
             .filter(|t| t.data_location.value() == DataLocation::EXTERNAL as i32)
-            .filter_map(|t| {
-                t.external_data
-                    .first()
-                    .map(|data| PathBuf::from(&data.value))
-                    .map(|p| {
-                        if p.is_relative() {
-                            base_path.join(p)
-                        } else {
-                            p
-                        }
-                    })
-            })
-            .collect();
+            .filter_map(|t| external_data_path(base_path, t).transpose())
+            .collect::<anyhow::Result<_>>()?;
 
         let mut paths = vec![file_path.to_path_buf()];
         paths.extend(external_paths);
@@ -218,121 +802,100 @@ impl Handler for OnnxHandler {
         detail: DetailLevel,
         filter: Option<String>,
     ) -> anyhow::Result<Inspection> {
-        let mut inspection = Inspection::default();
-
-        let mut file = std::fs::File::open(file_path)?;
+        let file = std::fs::File::open(file_path)?;
+        let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
 
+        let mut inspection = inspect_buffer(&data, detail, filter)?;
         inspection.file_path = file_path.canonicalize()?;
-        inspection.file_size = file.metadata()?.len();
 
-        let onnx_model: ModelProto = Message::parse_from_reader(&mut file)?;
-
-        inspection.file_type = FileType::ONNX;
-
-        if onnx_model.model_version != 0 {
-            inspection.version = format!(
-                "{} (IR v{})",
-                onnx_model.model_version, onnx_model.ir_version
-            );
-        } else {
-            inspection.version = format!("IR v{}", onnx_model.ir_version);
-        }
-
-        // TODO: check the presence of sparse tensors from graph.sparse_initializer
-
-        inspection.num_tensors = onnx_model.graph.initializer.len();
-        inspection.data_size = onnx_model
-            .graph
-            .initializer
-            .par_iter()
-            .map(|t| {
-                if t.dims.is_empty() {
-                    0
-                } else {
-                    data_type_bits(t.data_type)
-                        * t.dims.iter().map(|d| *d as usize).product::<usize>()
-                }
-            })
-            .sum::<usize>()
-            / 8;
-
-        inspection.unique_shapes = onnx_model
-            .graph
-            .initializer
-            .par_iter()
-            .map(|t| t.dims.iter().map(|d| *d as usize).collect::<Vec<_>>())
-            .filter(|shape| !shape.is_empty())
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
+        Ok(inspection)
+    }
 
-        // sort shapes by volume
-        inspection.unique_shapes.sort_by(|a, b| {
-            let size_a: usize = a.iter().product();
-            let size_b: usize = b.iter().product();
-            size_a.cmp(&size_b)
-        });
+    fn scan_header(&self, reader: &mut dyn ReadSeek) -> anyhow::Result<()> {
+        let size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        scan_message(reader, Scanned::Model, size)
+    }
 
-        inspection.unique_dtypes = onnx_model
-            .graph
-            .initializer
-            .par_iter()
-            .map(|t| data_type_string(t.data_type).to_string())
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
+    fn tensor_data(
+        &self,
+        file_path: &Path,
+        filter: Option<String>,
+    ) -> anyhow::Result<Vec<TensorData>> {
+        let base_path = file_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("no parent path"))?;
+        let mut file = std::fs::File::open(file_path)?;
+        let mut onnx_model: ModelProto = Message::parse_from_reader(&mut file)?;
 
-        if !onnx_model.producer_name.is_empty() {
-            inspection.metadata.insert(
-                "producer_name".to_string(),
-                onnx_model.producer_name.clone(),
-            );
-        }
+        let initializers =
+            std::mem::take(&mut onnx_model.graph.mut_or_insert_default().initializer);
+        // external data files are usually shared by many tensors
+        let mut external_files: HashMap<PathBuf, Arc<memmap2::Mmap>> = HashMap::new();
+        let mut tensors = vec![];
 
-        if !onnx_model.producer_version.is_empty() {
-            inspection.metadata.insert(
-                "producer_version".to_string(),
-                onnx_model.producer_version.clone(),
-            );
-        }
+        for mut tensor in initializers {
+            if filter.as_ref().is_some_and(|f| !tensor.name.contains(f)) {
+                continue;
+            }
+            let Some(element_type) = element_type(tensor.data_type) else {
+                continue;
+            };
+            let encoding = Encoding::Plain(element_type);
 
-        if !onnx_model.domain.is_empty() {
-            inspection
-                .metadata
-                .insert("domain".to_string(), onnx_model.domain.clone());
-        }
+            if tensor.data_location.value() == DataLocation::EXTERNAL as i32 {
+                let entries: HashMap<&str, &str> = tensor
+                    .external_data
+                    .iter()
+                    .map(|e| (e.key.as_str(), e.value.as_str()))
+                    .collect();
+                let data_path = external_data_path(base_path, &tensor)?.ok_or_else(|| {
+                    anyhow::anyhow!("tensor {} has no data location", tensor.name)
+                })?;
+                let offset = entries
+                    .get("offset")
+                    .map(|o| o.parse::<usize>())
+                    .transpose()?
+                    .unwrap_or(0);
+                let length = match entries.get("length") {
+                    Some(length) => length.parse::<usize>()?,
+                    None => encoding
+                        .data_size(tensor.dims.iter().map(|d| *d as usize).product::<usize>()),
+                };
 
-        if !onnx_model.doc_string.is_empty() {
-            inspection
-                .metadata
-                .insert("doc_string".to_string(), onnx_model.doc_string.clone());
-        }
+                let buffer = match external_files.get(&data_path) {
+                    Some(buffer) => buffer.clone(),
+                    None => {
+                        let data_file = std::fs::File::open(&data_path)?;
+                        let buffer =
+                            Arc::new(unsafe { memmap2::MmapOptions::new().map(&data_file)? });
+                        external_files.insert(data_path, buffer.clone());
+                        buffer
+                    }
+                };
 
-        onnx_model.metadata_props.iter().for_each(|prop| {
-            inspection
-                .metadata
-                .insert(prop.key.clone(), prop.value.clone());
-        });
-
-        if matches!(detail, DetailLevel::Full) {
-            inspection.tensors = Some(
-                onnx_model
-                    .graph
-                    .initializer
-                    .par_iter()
-                    .filter(|t_info| filter.as_ref().map_or(true, |f| t_info.name.contains(f)))
-                    .map(build_tensor_descriptor)
-                    .collect(),
-            );
+                tensors.push(TensorData::new(
+                    tensor.name,
+                    encoding,
+                    buffer,
+                    offset..offset + length,
+                    Some(offset as u64),
+                )?);
+            } else if !tensor.raw_data.is_empty() {
+                let raw_data = std::mem::take(&mut tensor.raw_data);
+                tensors.push(TensorData::owned(tensor.name, encoding, raw_data));
+            } else {
+                let data = typed_data(&tensor, element_type);
+                tensors.push(TensorData::owned(tensor.name, encoding, data));
+            }
         }
 
-        Ok(inspection)
+        Ok(tensors)
     }
 
     // adapted from https://github.com/onnx/onnx/blob/main/onnx/tools/net_drawer.py
     fn create_graph(&self, file_path: &Path, output_path: &Path) -> anyhow::Result<()> {
-        let mut file = std::fs::File::open(file_path)?;
-        let onnx_model: ModelProto = Message::parse_from_reader(&mut file)?;
+        let onnx_model = read_model_constants(file_path)?;
         let mut dot_graph = Graph::new(
             // make sure the name is quoted
             &format!(
@@ -343,32 +906,49 @@ impl Handler for OnnxHandler {
         );
         let mut dot_nodes = HashMap::new();
         let mut dot_node_counts = HashMap::new();
+        // edges are labeled with the element type and dimensions of their tensor, if known
+        let shapes = graph::tensor_shapes(&onnx_model.graph);
+        let edge_label = |name: &String| {
+            shapes
+                .get(name.as_str())
+                .map(|shape| dot_label(&[shape]))
+                .unwrap_or_default()
+        };
 
         for (op_id, op) in onnx_model.graph.node.iter().enumerate() {
             let op_node = op_to_dot_node(op, op_id);
             dot_graph.add_node(op_node.clone());
-            for input_name in &op.input {
+            // optional inputs and outputs left out are empty names
+            for input_name in op.input.iter().filter(|name| !name.is_empty()) {
                 let input_node = dot_nodes.entry(input_name.clone()).or_insert_with(|| {
                     let count = dot_node_counts.entry(input_name.clone()).or_insert(0);
                     let node = dot_graph::Node::new(&str_to_node_name(&format!(
                         "{}{}",
                         input_name, count
-                    )));
-                    node.label(input_name);
+                    )))
+                    .label(&dot_label(&[input_name]));
                     *count += 1;
                     node
                 });
                 dot_graph.add_node(input_node.clone());
-                dot_graph.add_edge(dot_graph::Edge::new(&input_node.name, &op_node.name, ""));
+                dot_graph.add_edge(dot_graph::Edge::new(
+                    &input_node.name,
+                    &op_node.name,
+                    &edge_label(input_name),
+                ));
             }
-            for output_name in &op.output {
+            for output_name in op.output.iter().filter(|name| !name.is_empty()) {
                 let count = dot_node_counts.entry(output_name.clone()).or_insert(0);
                 let output_node =
-                    dot_graph::Node::new(&str_to_node_name(&format!("{}{}", output_name, count)));
-                output_node.label(output_name);
+                    dot_graph::Node::new(&str_to_node_name(&format!("{}{}", output_name, count)))
+                        .label(&dot_label(&[output_name]));
                 dot_nodes.insert(output_name.clone(), output_node.clone());
                 dot_graph.add_node(output_node.clone());
-                dot_graph.add_edge(dot_graph::Edge::new(&op_node.name, &output_node.name, ""));
+                dot_graph.add_edge(dot_graph::Edge::new(
+                    &op_node.name,
+                    &output_node.name,
+                    &edge_label(output_name),
+                ));
             }
         }
 
@@ -377,4 +957,434 @@ impl Handler for OnnxHandler {
         std::fs::write(output_path, dot_string)
             .map_err(|e| anyhow::anyhow!("failed to write dot string to output path: {:?}", e))
     }
+
+    fn graph(&self, file_path: &Path) -> anyhow::Result<crate::core::graph::Graph> {
+        graph::graph(file_path)
+    }
+
+    fn intermediate_shapes(&self, file_path: &Path) -> anyhow::Result<Vec<ValueInfo>> {
+        let onnx_model = read_model_constants(file_path)?;
+        Ok(shapes::intermediate_shapes(&onnx_model.graph))
+    }
+
+    fn canonical_content(&self, file_path: &Path) -> anyhow::Result<Box<dyn Read>> {
+        let mut file = std::fs::File::open(file_path)?;
+        let mut onnx_model: ModelProto = Message::parse_from_reader(&mut file)?;
+        take_signature(&mut onnx_model)?;
+
+        // the order of these lists doesn't matter, unlike the one of the nodes
+        onnx_model.metadata_props.sort_by(|a, b| a.key.cmp(&b.key));
+        onnx_model
+            .opset_import
+            .sort_by(|a, b| (&a.domain, a.version).cmp(&(&b.domain, b.version)));
+        let graph = onnx_model.graph.mut_or_insert_default();
+        graph.initializer.sort_by(|a, b| a.name.cmp(&b.name));
+        graph.value_info.sort_by(|a, b| a.name.cmp(&b.name));
+        for node in graph.node.iter_mut() {
+            node.attribute.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        // fields are serialized in the order of their numbers
+        Ok(Box::new(Cursor::new(onnx_model.write_to_bytes()?)))
+    }
+
+    fn embedded_signature(&self, file_path: &Path) -> anyhow::Result<EmbeddedSignature> {
+        let file = std::fs::File::open(file_path)?;
+        let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
+
+        // the bytes of the model as they are, but for the signature property
+        let (range, manifest) = match signature_range(&data)? {
+            Some((range, manifest)) => (range, Some(manifest)),
+            None => (data.len()..data.len(), None),
+        };
+        Ok(EmbeddedSignature {
+            content: Box::new(read_without(file_path, range)?),
+            manifest,
+        })
+    }
+
+    fn embed_signature(&self, file_path: &Path, manifest: &str) -> anyhow::Result<()> {
+        let file = std::fs::File::open(file_path)?;
+        let data = unsafe { memmap2::MmapOptions::new().map(&file)? };
+        // replace any previous signature
+        let range = signature_range(&data)?
+            .map(|(range, _)| range)
+            .unwrap_or(data.len()..data.len());
+
+        // repeated fields can be appended, the property is added after the rest of the model
+        let mut prop = StringStringEntryProto::new();
+        prop.key = EMBEDDED_SIGNATURE_KEY.to_string();
+        prop.value = manifest.to_string();
+        let prop = prop.write_to_bytes()?;
+        let mut field = vec![];
+        write_varint(&mut field, METADATA_PROPS_FIELD << 3 | 2);
+        write_varint(&mut field, prop.len() as u64);
+        field.extend(prop);
+
+        replace_file(
+            file_path,
+            &mut read_without(file_path, range)?.chain(Cursor::new(field)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn initializer(name: &str, data_type: i32) -> TensorProto {
+        let mut tensor = TensorProto::new();
+        tensor.name = name.to_string();
+        tensor.data_type = data_type;
+        tensor.dims = vec![2];
+        tensor
+    }
+
+    fn external(name: &str, location: &str) -> TensorProto {
+        let mut tensor = initializer(name, 1);
+        tensor.data_location = DataLocation::EXTERNAL.into();
+        let mut entry = StringStringEntryProto::new();
+        entry.key = "location".to_string();
+        entry.value = location.to_string();
+        tensor.external_data.push(entry);
+        tensor
+    }
+
+    #[test]
+    fn test_paths_to_sign_external_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("model.onnx");
+
+        let mut model = ModelProto::new();
+        let graph = model.graph.mut_or_insert_default();
+        graph.initializer = vec![
+            external("a", "weights.bin"),
+            external("b", "weights.bin"),
+            external("c", "data/more.bin"),
+        ];
+        std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+        let mut paths = OnnxHandler::new().paths_to_sign(&model_path).unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                temp_dir.path().join("data/more.bin"),
+                model_path.clone(),
+                temp_dir.path().join("weights.bin"),
+            ]
+        );
+
+        // a malicious model can't get files outside of its folder signed or read
+        for location in ["/etc/passwd", "../../etc/passwd", "data/../../secret.bin"] {
+            let graph = model.graph.mut_or_insert_default();
+            graph.initializer = vec![external("a", "weights.bin"), external("b", location)];
+            std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+            let error = OnnxHandler::new()
+                .paths_to_sign(&model_path)
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("outside of the model folder"), "{}", error);
+            let error = OnnxHandler::new()
+                .tensor_data(&model_path, Some("b".to_string()))
+                .err()
+                .unwrap()
+                .to_string();
+            assert!(error.contains("outside of the model folder"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_is_outside_of_model_folder() {
+        assert!(!is_outside_of_model_folder("weights.bin"));
+        assert!(!is_outside_of_model_folder("data/weights..bin"));
+        assert!(is_outside_of_model_folder("/etc/passwd"));
+        assert!(is_outside_of_model_folder("..\\weights.bin"));
+        assert!(is_outside_of_model_folder("C:\\weights.bin"));
+    }
+
+    #[test]
+    fn test_tensor_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base_path = temp_dir.path();
+
+        let mut raw = initializer("raw", 1);
+        raw.raw_data = [1.0f32, f32::NAN]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+
+        let mut typed = initializer("typed", 10);
+        // float16 bits of 1.0 and -2.0
+        typed.int32_data = vec![0x3c00, 0xc000];
+
+        let mut external = initializer("external", 7);
+        external.data_location = DataLocation::EXTERNAL.into();
+        for (key, value) in [
+            ("location", "weights.bin"),
+            ("offset", "8"),
+            ("length", "16"),
+        ] {
+            let mut entry = StringStringEntryProto::new();
+            entry.key = key.to_string();
+            entry.value = value.to_string();
+            external.external_data.push(entry);
+        }
+        let mut external_data = vec![0u8; 8];
+        external_data.extend_from_slice(&3i64.to_le_bytes());
+        external_data.extend_from_slice(&(-4i64).to_le_bytes());
+        std::fs::write(base_path.join("weights.bin"), external_data).unwrap();
+
+        let mut model = ModelProto::new();
+        let graph = model.graph.mut_or_insert_default();
+        graph.initializer = vec![raw, typed, external, initializer("strings", 8)];
+        let model_path = base_path.join("model.onnx");
+        std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+        let tensors = OnnxHandler::new().tensor_data(&model_path, None).unwrap();
+        // string tensors can't be decoded
+        assert_eq!(tensors.len(), 3);
+
+        assert_eq!(tensors[0].id, "raw");
+        let values: Vec<f64> = tensors[0].values().collect();
+        assert_eq!(values[0], 1.0);
+        assert!(values[1].is_nan());
+
+        assert_eq!(tensors[1].values().collect::<Vec<_>>(), vec![1.0, -2.0]);
+
+        assert_eq!(tensors[2].file_offset, Some(8));
+        assert_eq!(tensors[2].values().collect::<Vec<_>>(), vec![3.0, -4.0]);
+
+        let filtered = OnnxHandler::new()
+            .tensor_data(&model_path, Some("typ".to_string()))
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[test]
+    fn test_scan_header() {
+        let mut weights = initializer("weights", 1);
+        weights.raw_data = vec![1; 8];
+        let mut constant = initializer("constant", 1);
+        constant.float_data = vec![1.0, 2.0];
+
+        let mut model = ModelProto::new();
+        model.ir_version = 8;
+        let graph = model.graph.mut_or_insert_default();
+        graph.name = "main".to_string();
+        graph.initializer = vec![weights];
+        let mut node = NodeProto::new();
+        node.op_type = "Constant".to_string();
+        let mut attribute = protos::AttributeProto::new();
+        attribute.name = "value".to_string();
+        attribute.t = Some(constant).into();
+        node.attribute.push(attribute);
+        graph.node.push(node);
+        let data = model.write_to_bytes().unwrap();
+
+        let scanned = super::super::scanned(&OnnxHandler::new(), &data);
+        assert_ne!(scanned, data);
+
+        // the structure is intact, only the values are zeroed
+        let scanned = ModelProto::parse_from_bytes(&scanned).unwrap();
+        let graph = scanned.graph.unwrap();
+        assert_eq!(scanned.ir_version, 8);
+        assert_eq!(graph.name, "main");
+        assert_eq!(graph.initializer[0].name, "weights");
+        assert_eq!(graph.initializer[0].raw_data, vec![0; 8]);
+        let constant = graph.node[0].attribute[0].t.as_ref().unwrap();
+        assert_eq!(constant.name, "constant");
+        assert_eq!(constant.dims, vec![2]);
+        assert_eq!(constant.float_data, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_read_model_structure() {
+        let mut weights = initializer("weights", 1);
+        weights.raw_data = vec![1; 8];
+        let mut constant = initializer("constant", 1);
+        constant.float_data = vec![1.0, 2.0];
+
+        let mut model = ModelProto::new();
+        model.ir_version = 8;
+        model.producer_name = "test".to_string();
+        let graph = model.graph.mut_or_insert_default();
+        graph.initializer = vec![weights, external("external", "weights.bin")];
+        let mut node = NodeProto::new();
+        node.op_type = "Constant".to_string();
+        let mut attribute = protos::AttributeProto::new();
+        attribute.name = "value".to_string();
+        attribute.t = Some(constant).into();
+        node.attribute.push(attribute);
+        graph.node.push(node);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("model.onnx");
+        std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+        // the same model, without the values
+        let graph = model.graph.mut_or_insert_default();
+        graph.initializer[0].raw_data.clear();
+        graph.node[0].attribute[0]
+            .t
+            .mut_or_insert_default()
+            .float_data
+            .clear();
+        assert_eq!(read_model_structure(&model_path).unwrap(), model);
+
+        let mut data = model.write_to_bytes().unwrap();
+        data.pop();
+        std::fs::write(&model_path, data).unwrap();
+        assert!(read_model_structure(&model_path).is_err());
+    }
+
+    #[test]
+    fn test_create_graph() {
+        let mut strides = protos::AttributeProto::new();
+        strides.name = "strides".to_string();
+        strides.type_ = protos::attribute_proto::AttributeType::INTS.into();
+        strides.ints = vec![2, 2];
+        // not a key attribute
+        let mut auto_pad = protos::AttributeProto::new();
+        auto_pad.name = "auto_pad".to_string();
+        auto_pad.type_ = protos::attribute_proto::AttributeType::STRING.into();
+        auto_pad.s = b"NOTSET".to_vec();
+
+        let mut conv = NodeProto::new();
+        conv.name = "conv \"1\"".to_string();
+        conv.op_type = "Conv".to_string();
+        // numeric tensor names, as in older PyTorch exports
+        conv.input = vec!["0".to_string(), "weight".to_string(), String::new()];
+        conv.output = vec!["1".to_string()];
+        conv.attribute = vec![strides, auto_pad];
+
+        let mut weight = initializer("weight", 1);
+        weight.dims = vec![8, 3, 3, 3];
+        let mut model = ModelProto::new();
+        let graph = model.graph.mut_or_insert_default();
+        graph.initializer.push(weight);
+        graph.node.push(conv);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.onnx");
+        let output_path = temp_dir.path().join("model.dot");
+        std::fs::write(&file_path, model.write_to_bytes().unwrap()).unwrap();
+
+        OnnxHandler::new()
+            .create_graph(&file_path, &output_path)
+            .unwrap();
+        let dot = std::fs::read_to_string(&output_path).unwrap();
+        assert!(dot.contains(r#"[label="conv \"1\"/Conv (op#0)\nstrides=[2, 2]"]"#));
+        assert!(!dot.contains("auto_pad"));
+        assert!(dot.contains(r#""_00"[label="0"]"#));
+        assert!(dot.contains(r#""weight0" -> "conv__1__Conv__op_0"[label="FLOAT[8,3,3,3]"]"#));
+        assert!(dot.contains(r#""conv__1__Conv__op_0" -> "_10"[label=""]"#));
+    }
+
+    #[test]
+    fn test_embed_signature() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("model.onnx");
+
+        let mut model = ModelProto::new();
+        model.ir_version = 8;
+        let mut prop = StringStringEntryProto::new();
+        prop.key = "author".to_string();
+        prop.value = "test".to_string();
+        model.metadata_props.push(prop);
+        let mut raw = initializer("raw", 1);
+        raw.raw_data = vec![0; 8];
+        model.graph.mut_or_insert_default().initializer = vec![raw];
+        std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+        let handler = OnnxHandler::new();
+        let read_content = |embedded: EmbeddedSignature| {
+            let mut content = embedded.content;
+            let mut data = vec![];
+            content.read_to_end(&mut data).unwrap();
+            (embedded.manifest, data)
+        };
+
+        let (manifest, unsigned) = read_content(handler.embedded_signature(&model_path).unwrap());
+        assert!(manifest.is_none());
+        // the bytes of the model are signed as they are
+        assert_eq!(unsigned, std::fs::read(&model_path).unwrap());
+
+        handler.embed_signature(&model_path, "{}").unwrap();
+        let inspection = handler
+            .inspect(&model_path, DetailLevel::Brief, None)
+            .unwrap();
+        assert_eq!(
+            inspection.metadata.get("author").map(String::as_str),
+            Some("test")
+        );
+
+        // the signed content doesn't change when the signature is embedded or replaced
+        let (manifest, signed) = read_content(handler.embedded_signature(&model_path).unwrap());
+        assert_eq!(manifest.as_deref(), Some("{}"));
+        assert_eq!(signed, unsigned);
+
+        handler.embed_signature(&model_path, "[]").unwrap();
+        let (manifest, signed) = read_content(handler.embedded_signature(&model_path).unwrap());
+        assert_eq!(manifest.as_deref(), Some("[]"));
+        assert_eq!(signed, unsigned);
+
+        // a second signature property is rejected rather than left unsigned
+        let mut model = ModelProto::parse_from_bytes(&std::fs::read(&model_path).unwrap()).unwrap();
+        model
+            .metadata_props
+            .push(model.metadata_props.last().unwrap().clone());
+        std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+        assert!(handler.embedded_signature(&model_path).is_err());
+        assert!(handler.embed_signature(&model_path, "{}").is_err());
+    }
+
+    #[test]
+    fn test_canonical_content() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let canonical = |initializers: &[&str], props: &[(&str, &str)]| {
+            let mut model = ModelProto::new();
+            model.graph.mut_or_insert_default().initializer = initializers
+                .iter()
+                .map(|name| {
+                    let mut tensor = TensorProto::new();
+                    tensor.name = name.to_string();
+                    tensor
+                })
+                .collect();
+            model.metadata_props = props
+                .iter()
+                .map(|(key, value)| {
+                    let mut prop = StringStringEntryProto::new();
+                    prop.key = key.to_string();
+                    prop.value = value.to_string();
+                    prop
+                })
+                .collect();
+            let model_path = temp_dir.path().join("model.onnx");
+            std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+            let mut content = vec![];
+            OnnxHandler::new()
+                .canonical_content(&model_path)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            content
+        };
+
+        assert_eq!(
+            canonical(&["a", "b"], &[("k", "v"), ("author", "me")]),
+            canonical(
+                &["b", "a"],
+                &[("author", "me"), (EMBEDDED_SIGNATURE_KEY, "{}"), ("k", "v")]
+            )
+        );
+        assert_ne!(
+            canonical(&["a", "b"], &[("k", "v")]),
+            canonical(&["a", "c"], &[("k", "v")])
+        );
+    }
 }
@@ -0,0 +1,249 @@
+use std::{collections::HashSet, path::Path};
+
+use protobuf::Message;
+
+use super::{
+    is_outside_of_model_folder, model_size,
+    protos::{GraphProto, ModelProto, NodeProto, TensorProto},
+};
+use crate::core::scan::{Issue, Safety};
+
+// domains of the standard operators
+const STANDARD_DOMAINS: &[&str] = &[
+    "",
+    "ai.onnx",
+    "ai.onnx.ml",
+    "ai.onnx.training",
+    "ai.onnx.preview.training",
+];
+// operators implemented by ONNX Runtime itself
+const CONTRIB_DOMAIN: &str = "com.microsoft";
+// operators running Python code, exported from PyTorch autograd functions or onnxruntime-extensions
+const PYTHON_OPERATORS: &[&str] = &["PythonOp", "PythonOpGrad", "PyOp"];
+// string attributes are usually names and modes, larger ones are unusual
+const MAX_ATTRIBUTE_SIZE: usize = 64 * 1024;
+
+pub(super) fn node_location(node: &NodeProto) -> String {
+    if node.name.is_empty() {
+        node.op_type.clone()
+    } else {
+        format!("{} ({})", node.name, node.op_type)
+    }
+}
+
+struct Scanner<'a> {
+    // domain and name of the functions defined by the model
+    functions: HashSet<(&'a str, &'a str)>,
+    // non standard operators already reported
+    reported: HashSet<(String, String)>,
+    issues: Vec<Issue>,
+}
+
+impl Scanner<'_> {
+    fn issue(&mut self, rule_id: &'static str, safety: Safety, message: String, location: String) {
+        self.issues.push(Issue {
+            rule_id,
+            safety,
+            message,
+            location,
+            offset: None,
+        });
+    }
+
+    fn scan_tensor(&mut self, tensor: &TensorProto) {
+        for entry in &tensor.external_data {
+            if entry.key == "location" && is_outside_of_model_folder(&entry.value) {
+                self.issue(
+                    "external-data-traversal",
+                    Safety::Dangerous,
+                    format!("external data read from {}", entry.value),
+                    tensor.name.clone(),
+                );
+            }
+        }
+    }
+
+    fn scan_graph(&mut self, graph: &GraphProto) {
+        for tensor in &graph.initializer {
+            self.scan_tensor(tensor);
+        }
+        for sparse in &graph.sparse_initializer {
+            self.scan_tensor(&sparse.values);
+            self.scan_tensor(&sparse.indices);
+        }
+        for node in &graph.node {
+            self.scan_node(node);
+        }
+    }
+
+    fn scan_node(&mut self, node: &NodeProto) {
+        let location = node_location(node);
+        let operator = (node.domain.clone(), node.op_type.clone());
+
+        if PYTHON_OPERATORS.contains(&node.op_type.as_str()) {
+            self.issue(
+                "python-operator",
+                Safety::Dangerous,
+                format!("{} operator running Python code", node.op_type),
+                location.clone(),
+            );
+        } else if !STANDARD_DOMAINS.contains(&node.domain.as_str())
+            && !self
+                .functions
+                .contains(&(node.domain.as_str(), node.op_type.as_str()))
+            && self.reported.insert(operator)
+        {
+            let rule_id = if node.domain == CONTRIB_DOMAIN {
+                "contrib-operator"
+            } else {
+                "custom-operator"
+            };
+            self.issue(
+                rule_id,
+                Safety::Suspicious,
+                format!("{} operator of the {} domain", node.op_type, node.domain),
+                location.clone(),
+            );
+        }
+
+        for attribute in &node.attribute {
+            let size = attribute.s.len() + attribute.strings.iter().map(Vec::len).sum::<usize>();
+            if size > MAX_ATTRIBUTE_SIZE {
+                self.issue(
+                    "large-attribute",
+                    Safety::Suspicious,
+                    format!("attribute {} of {} bytes", attribute.name, size),
+                    location.clone(),
+                );
+            }
+
+            for tensor in attribute.t.iter().chain(&attribute.tensors) {
+                self.scan_tensor(tensor);
+            }
+            // subgraphs of If, Loop and Scan
+            for graph in attribute.g.iter().chain(&attribute.graphs) {
+                self.scan_graph(graph);
+            }
+        }
+    }
+}
+
+/// Statically scans the model for operators that can run code, external data outside of its
+/// folder and data appended to it.
+pub(crate) fn scan(file_path: &Path) -> anyhow::Result<Vec<Issue>> {
+    let data = std::fs::read(file_path)?;
+    // bytes appended to the model are ignored by the parser if they look like unknown fields
+    let size = model_size(&data);
+    let model: ModelProto = Message::parse_from_bytes(&data[..size as usize])?;
+
+    let mut scanner = Scanner {
+        functions: model
+            .functions
+            .iter()
+            .map(|function| (function.domain.as_str(), function.name.as_str()))
+            .collect(),
+        reported: HashSet::new(),
+        issues: vec![],
+    };
+
+    scanner.scan_graph(&model.graph);
+    for function in &model.functions {
+        for node in &function.node {
+            scanner.scan_node(node);
+        }
+    }
+
+    if size < data.len() as u64 {
+        scanner.issues.push(Issue {
+            rule_id: "unreferenced-data",
+            safety: Safety::Suspicious,
+            message: format!(
+                "{} bytes following the end of the model",
+                data.len() as u64 - size
+            ),
+            location: String::new(),
+            offset: Some(size),
+        });
+    }
+
+    Ok(scanner.issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::protos::{AttributeProto, FunctionProto, StringStringEntryProto};
+    use super::*;
+
+    fn node(name: &str, domain: &str, op_type: &str) -> NodeProto {
+        let mut node = NodeProto::new();
+        node.name = name.to_string();
+        node.domain = domain.to_string();
+        node.op_type = op_type.to_string();
+        node
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut external = TensorProto::new();
+        external.name = "weights".to_string();
+        let mut entry = StringStringEntryProto::new();
+        entry.key = "location".to_string();
+        entry.value = "../../etc/passwd".to_string();
+        external.external_data.push(entry);
+
+        let mut large = AttributeProto::new();
+        large.name = "payload".to_string();
+        large.s = vec![0u8; MAX_ATTRIBUTE_SIZE + 1];
+        let mut custom = node("custom", "com.example", "Decrypt");
+        custom.attribute.push(large);
+
+        // the then branch of an If node
+        let mut branch = GraphProto::new();
+        branch
+            .node
+            .push(node("python", "com.microsoft", "PythonOp"));
+        let mut then_branch = AttributeProto::new();
+        then_branch.g = Some(branch).into();
+        let mut condition = node("if", "", "If");
+        condition.attribute.push(then_branch);
+
+        let mut model = ModelProto::new();
+        let graph = model.graph.mut_or_insert_default();
+        graph.initializer.push(external);
+        graph.node = vec![
+            node("matmul", "", "MatMul"),
+            node("attention_0", "com.microsoft", "Attention"),
+            node("attention_1", "com.microsoft", "Attention"),
+            node("gelu", "local", "Gelu"),
+            custom,
+            condition,
+        ];
+        let mut function = FunctionProto::new();
+        function.domain = "local".to_string();
+        function.name = "Gelu".to_string();
+        model.functions.push(function);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.onnx");
+        let mut data = model.write_to_bytes().unwrap();
+        data.extend(b"\x80\x04payload");
+        std::fs::write(&file_path, data).unwrap();
+
+        let issues = scan(&file_path).unwrap();
+        let rules: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.rule_id, issue.location.as_str()))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("external-data-traversal", "weights"),
+                ("contrib-operator", "attention_0 (Attention)"),
+                ("custom-operator", "custom (Decrypt)"),
+                ("large-attribute", "custom (Decrypt)"),
+                ("python-operator", "python (PythonOp)"),
+                ("unreferenced-data", ""),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1303 @@
+use std::collections::HashMap;
+
+use super::{
+    data_type_string,
+    protos::{
+        tensor_proto::DataLocation, type_proto, AttributeProto, GraphProto, NodeProto, TensorProto,
+    },
+};
+use crate::core::{operators::DEFAULT_DOMAIN, Dim, ValueInfo};
+
+// element types of the tensors produced by the operators below
+const FLOAT: i32 = 1;
+const UINT8: i32 = 2;
+const INT32: i32 = 6;
+const INT64: i32 = 7;
+const BOOL: i32 = 9;
+
+// larger integer tensors are not shapes, axes or indexes, their values are not tracked
+const MAX_VALUES: usize = 64;
+
+// operators producing a tensor of the type and dimensions of their first input
+const ELEMENTWISE: &[&str] = &[
+    "Abs",
+    "Acos",
+    "Acosh",
+    "Asin",
+    "Asinh",
+    "Atan",
+    "Atanh",
+    "BatchNormalization",
+    "Ceil",
+    "Celu",
+    "Clip",
+    "Cos",
+    "Cosh",
+    "CumSum",
+    "Elu",
+    "Erf",
+    "Exp",
+    "Floor",
+    "Gelu",
+    "GroupNormalization",
+    "HardSigmoid",
+    "HardSwish",
+    "Hardmax",
+    "Identity",
+    "InstanceNormalization",
+    "LRN",
+    "LayerNormalization",
+    "LeakyRelu",
+    "Log",
+    "LogSoftmax",
+    "LpNormalization",
+    "MeanVarianceNormalization",
+    "Mish",
+    "Neg",
+    "PRelu",
+    "RMSNormalization",
+    "Reciprocal",
+    "Relu",
+    "Round",
+    "Selu",
+    "Shrink",
+    "Sigmoid",
+    "Sign",
+    "Sin",
+    "Sinh",
+    "Softmax",
+    "Softplus",
+    "Softsign",
+    "Sqrt",
+    "Tan",
+    "Tanh",
+    "ThresholdedRelu",
+    "Trilu",
+];
+
+// arithmetic operators, broadcasting their inputs
+const BROADCASTING: &[&str] = &[
+    "Add",
+    "BitShift",
+    "BitwiseAnd",
+    "BitwiseOr",
+    "BitwiseXor",
+    "Div",
+    "Max",
+    "Mean",
+    "Min",
+    "Mod",
+    "Mul",
+    "Pow",
+    "Sub",
+    "Sum",
+];
+
+// comparisons and logical operators, broadcasting their inputs to a tensor of booleans
+const COMPARISONS: &[&str] = &[
+    "And",
+    "Equal",
+    "Greater",
+    "GreaterOrEqual",
+    "Less",
+    "LessOrEqual",
+    "Or",
+    "Xor",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct TensorType {
+    elem_type: i32,
+    // none if the rank isn't known
+    dims: Option<Vec<Dim>>,
+}
+
+impl TensorType {
+    fn new(elem_type: i32, dims: Vec<Dim>) -> Self {
+        Self {
+            elem_type,
+            dims: Some(dims),
+        }
+    }
+}
+
+fn unknown() -> Dim {
+    Dim::Named("?".to_string())
+}
+
+fn fixed(dim: &Dim) -> Option<i64> {
+    match dim {
+        Dim::Fixed(size) => Some(*size),
+        Dim::Named(_) => None,
+    }
+}
+
+fn all_fixed(dims: &[Dim]) -> Option<Vec<i64>> {
+    dims.iter().map(fixed).collect()
+}
+
+fn product(dims: &[Dim]) -> Dim {
+    all_fixed(dims)
+        .map(|sizes| Dim::Fixed(sizes.iter().product()))
+        .unwrap_or_else(unknown)
+}
+
+// the axis counted from the start, if in range
+fn axis(axis: i64, rank: usize) -> Option<usize> {
+    let axis = if axis < 0 { axis + rank as i64 } else { axis };
+    (0..rank as i64).contains(&axis).then_some(axis as usize)
+}
+
+// models are valid, a dimension broadcast to a fixed one other than 1 is that one
+fn broadcast_dim(a: &Dim, b: &Dim) -> Dim {
+    match (a, b) {
+        (Dim::Fixed(1), other) | (other, Dim::Fixed(1)) => other.clone(),
+        (Dim::Fixed(_), _) => a.clone(),
+        (_, Dim::Fixed(_)) => b.clone(),
+        _ if a == b => a.clone(),
+        _ => unknown(),
+    }
+}
+
+fn broadcast(shapes: &[&[Dim]]) -> Vec<Dim> {
+    let rank = shapes
+        .iter()
+        .map(|dims| dims.len())
+        .max()
+        .unwrap_or_default();
+    (0..rank)
+        .map(|index| {
+            shapes
+                .iter()
+                .filter_map(|dims| index.checked_sub(rank - dims.len()).map(|i| &dims[i]))
+                .fold(Dim::Fixed(1), |result, dim| broadcast_dim(&result, dim))
+        })
+        .collect()
+}
+
+// numpy semantics, vectors being promoted to matrices and the added dimension removed
+fn matmul(a: &[Dim], b: &[Dim]) -> Option<Vec<Dim>> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let rows = (a.len() > 1).then(|| a[a.len() - 2].clone());
+    let columns = (b.len() > 1).then(|| b[b.len() - 1].clone());
+    let batch = broadcast(&[
+        &a[..a.len().saturating_sub(2)],
+        &b[..b.len().saturating_sub(2)],
+    ]);
+    Some(batch.into_iter().chain(rows).chain(columns).collect())
+}
+
+// number of elements from start to end by step, the bounds being clamped like Slice does
+fn slice_len(size: i64, start: i64, end: i64, step: i64) -> i64 {
+    let resolve = |value: i64| if value < 0 { value + size } else { value };
+    let (start, end) = (resolve(start), resolve(end));
+    if step > 0 {
+        let (start, end) = (start.clamp(0, size), end.clamp(0, size));
+        ((end - start + step - 1) / step).max(0)
+    } else {
+        let (start, end) = (start.clamp(0, size - 1), end.clamp(-1, size - 1));
+        ((start - end - step - 1) / -step).max(0)
+    }
+}
+
+fn attribute<'a>(node: &'a NodeProto, name: &str) -> Option<&'a AttributeProto> {
+    node.attribute
+        .iter()
+        .find(|attribute| attribute.name == name)
+}
+
+fn int_attribute(node: &NodeProto, name: &str) -> Option<i64> {
+    attribute(node, name).map(|attribute| attribute.i)
+}
+
+fn ints_attribute<'a>(node: &'a NodeProto, name: &str) -> Option<&'a [i64]> {
+    attribute(node, name).map(|attribute| attribute.ints.as_slice())
+}
+
+fn tensor_dims(tensor: &TensorProto) -> Vec<Dim> {
+    tensor.dims.iter().copied().map(Dim::Fixed).collect()
+}
+
+// the values of a small integer tensor stored in the model, flattened
+fn tensor_values(tensor: &TensorProto) -> Option<Vec<Dim>> {
+    if tensor.data_location.value() == DataLocation::EXTERNAL as i32 {
+        return None;
+    }
+    let count = tensor.dims.iter().product::<i64>() as usize;
+    if count > MAX_VALUES {
+        return None;
+    }
+    let values: Vec<i64> = match tensor.data_type {
+        INT64 if !tensor.raw_data.is_empty() => tensor
+            .raw_data
+            .chunks_exact(8)
+            .map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect(),
+        INT64 => tensor.int64_data.clone(),
+        INT32 if !tensor.raw_data.is_empty() => tensor
+            .raw_data
+            .chunks_exact(4)
+            .map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap()) as i64)
+            .collect(),
+        INT32 => tensor
+            .int32_data
+            .iter()
+            .map(|value| *value as i64)
+            .collect(),
+        _ => return None,
+    };
+    (values.len() == count).then(|| values.into_iter().map(Dim::Fixed).collect())
+}
+
+#[derive(Default)]
+struct Inference<'a> {
+    types: HashMap<&'a str, TensorType>,
+    // values of the small integer tensors, such as shapes and axes, symbolic when taken from
+    // the dimensions of a tensor
+    values: HashMap<&'a str, Vec<Dim>>,
+}
+
+impl<'a> Inference<'a> {
+    fn input_type(&self, node: &NodeProto, index: usize) -> Option<&TensorType> {
+        node.input
+            .get(index)
+            .filter(|name| !name.is_empty())
+            .and_then(|name| self.types.get(name.as_str()))
+    }
+
+    fn dims(&self, node: &NodeProto, index: usize) -> Option<&[Dim]> {
+        self.input_type(node, index)?.dims.as_deref()
+    }
+
+    fn elem_type(&self, node: &NodeProto, index: usize) -> Option<i32> {
+        self.input_type(node, index)
+            .map(|tensor_type| tensor_type.elem_type)
+    }
+
+    fn value(&self, node: &NodeProto, index: usize) -> Option<&[Dim]> {
+        node.input
+            .get(index)
+            .and_then(|name| self.values.get(name.as_str()))
+            .map(Vec::as_slice)
+    }
+
+    fn fixed_value(&self, node: &NodeProto, index: usize) -> Option<Vec<i64>> {
+        all_fixed(self.value(node, index)?)
+    }
+
+    // axes given by an attribute, or by an input since they have been moved to one
+    fn axes(&self, node: &NodeProto, index: usize) -> Option<Vec<i64>> {
+        ints_attribute(node, "axes")
+            .map(<[i64]>::to_vec)
+            .or_else(|| self.fixed_value(node, index))
+    }
+
+    // the dimensions of the output of the first input with the same element type
+    fn like_input(&self, node: &NodeProto, dims: Option<Vec<Dim>>) -> Option<TensorType> {
+        Some(TensorType::new(self.elem_type(node, 0)?, dims?))
+    }
+
+    fn set(&mut self, name: &'a str, tensor_type: TensorType) {
+        // declared dimensions are kept, unless the rank is all they leave out
+        if self
+            .types
+            .get(name)
+            .is_none_or(|declared| declared.dims.is_none())
+        {
+            self.types.insert(name, tensor_type);
+        }
+    }
+
+    fn infer(&mut self, node: &'a NodeProto) {
+        if !node.domain.is_empty() && node.domain != DEFAULT_DOMAIN {
+            return;
+        }
+        for (name, tensor_type) in node.output.iter().zip(self.outputs(node)) {
+            if let (false, Some(tensor_type)) = (name.is_empty(), tensor_type) {
+                self.set(name, tensor_type);
+            }
+        }
+        if let (Some(name), Some(value)) = (node.output.first(), self.output_value(node)) {
+            self.values.insert(name, value);
+        }
+    }
+
+    // the type and dimensions of each output of the node, as far as they can be inferred
+    fn outputs(&self, node: &NodeProto) -> Vec<Option<TensorType>> {
+        let op_type = node.op_type.as_str();
+        let input = self.input_type(node, 0).cloned();
+        let dims = self.dims(node, 0);
+
+        if ELEMENTWISE.contains(&op_type) {
+            return vec![input];
+        }
+        if BROADCASTING.contains(&op_type) || COMPARISONS.contains(&op_type) {
+            let shapes: Option<Vec<&[Dim]>> =
+                (0..node.input.len()).map(|i| self.dims(node, i)).collect();
+            let elem_type = if COMPARISONS.contains(&op_type) {
+                BOOL
+            } else {
+                self.elem_type(node, 0).unwrap_or(FLOAT)
+            };
+            return vec![shapes.map(|shapes| TensorType::new(elem_type, broadcast(&shapes)))];
+        }
+
+        match op_type {
+            "Not" | "IsNaN" | "IsInf" => vec![input.map(|input| TensorType {
+                elem_type: BOOL,
+                ..input
+            })],
+            "Dropout" => vec![
+                input.clone(),
+                input.map(|input| TensorType {
+                    elem_type: BOOL,
+                    ..input
+                }),
+            ],
+            "Cast" => vec![input
+                .zip(int_attribute(node, "to"))
+                .map(|(input, to)| TensorType {
+                    elem_type: to as i32,
+                    ..input
+                })],
+            "CastLike" => vec![input
+                .zip(self.elem_type(node, 1))
+                .map(|(input, elem_type)| TensorType { elem_type, ..input })],
+            "DequantizeLinear" => vec![input
+                .zip(self.elem_type(node, 1))
+                .map(|(input, elem_type)| TensorType { elem_type, ..input })],
+            "QuantizeLinear" => vec![input.map(|input| TensorType {
+                elem_type: self.elem_type(node, 2).unwrap_or(UINT8),
+                ..input
+            })],
+            "Where" => {
+                let shapes: Option<Vec<&[Dim]>> = (0..3).map(|i| self.dims(node, i)).collect();
+                vec![shapes
+                    .zip(self.elem_type(node, 1))
+                    .map(|(shapes, elem_type)| TensorType::new(elem_type, broadcast(&shapes)))]
+            }
+            "MatMul" => vec![self.like_input(
+                node,
+                matmul(
+                    dims.unwrap_or_default(),
+                    self.dims(node, 1).unwrap_or_default(),
+                ),
+            )],
+            "MatMulInteger" => vec![dims
+                .zip(self.dims(node, 1))
+                .and_then(|(a, b)| matmul(a, b))
+                .map(|dims| TensorType::new(INT32, dims))],
+            "Gemm" => vec![self.like_input(node, self.gemm(node))],
+            "Conv" | "ConvInteger" => {
+                let output = self.conv(node).map(|dims| {
+                    let elem_type = if op_type == "Conv" {
+                        self.elem_type(node, 0).unwrap_or(FLOAT)
+                    } else {
+                        INT32
+                    };
+                    TensorType::new(elem_type, dims)
+                });
+                vec![output]
+            }
+            "ConvTranspose" => vec![self.like_input(node, self.conv_transpose(node))],
+            "MaxPool" | "AveragePool" | "LpPool" => {
+                let output = self.like_input(node, self.pool(node));
+                let indices = output.as_ref().map(|output| TensorType {
+                    elem_type: INT64,
+                    ..output.clone()
+                });
+                vec![output, indices]
+            }
+            "GlobalAveragePool" | "GlobalMaxPool" | "GlobalLpPool" => {
+                let output = dims.filter(|dims| dims.len() > 2).map(|dims| {
+                    dims[..2]
+                        .iter()
+                        .cloned()
+                        .chain(dims[2..].iter().map(|_| Dim::Fixed(1)))
+                        .collect()
+                });
+                vec![self.like_input(node, output)]
+            }
+            "Flatten" => {
+                let output = dims.and_then(|dims| {
+                    let axis = axis(int_attribute(node, "axis").unwrap_or(1), dims.len() + 1)?;
+                    Some(vec![product(&dims[..axis]), product(&dims[axis..])])
+                });
+                vec![self.like_input(node, output)]
+            }
+            "Transpose" => {
+                let output = dims.and_then(|dims| match ints_attribute(node, "perm") {
+                    Some(perm) => perm
+                        .iter()
+                        .map(|index| axis(*index, dims.len()).map(|index| dims[index].clone()))
+                        .collect(),
+                    None => Some(dims.iter().rev().cloned().collect()),
+                });
+                vec![self.like_input(node, output)]
+            }
+            "Concat" => vec![self.like_input(node, self.concat(node))],
+            "Unsqueeze" => {
+                let output = dims.zip(self.axes(node, 1)).and_then(|(dims, axes)| {
+                    let rank = dims.len() + axes.len();
+                    let axes: Option<Vec<usize>> =
+                        axes.iter().map(|index| axis(*index, rank)).collect();
+                    let axes = axes?;
+                    let mut remaining = dims.iter();
+                    (0..rank)
+                        .map(|index| {
+                            if axes.contains(&index) {
+                                Some(Dim::Fixed(1))
+                            } else {
+                                remaining.next().cloned()
+                            }
+                        })
+                        .collect()
+                });
+                vec![self.like_input(node, output)]
+            }
+            "Squeeze" => {
+                let output = dims.and_then(|dims| match self.axes(node, 1) {
+                    Some(axes) => {
+                        let axes: Option<Vec<usize>> =
+                            axes.iter().map(|index| axis(*index, dims.len())).collect();
+                        let axes = axes?;
+                        Some(
+                            dims.iter()
+                                .enumerate()
+                                .filter(|(index, _)| !axes.contains(index))
+                                .map(|(_, dim)| dim.clone())
+                                .collect(),
+                        )
+                    }
+                    None if node.input.len() < 2 => Some(
+                        dims.iter()
+                            .filter(|dim| **dim != Dim::Fixed(1))
+                            .cloned()
+                            .collect(),
+                    ),
+                    None => None,
+                });
+                vec![self.like_input(node, output)]
+            }
+            "Reshape" => vec![self.like_input(node, self.reshape(node))],
+            "Shape" => vec![self
+                .shape_value(node)
+                .map(|value| TensorType::new(INT64, vec![Dim::Fixed(value.len() as i64)]))],
+            "Size" => vec![Some(TensorType::new(INT64, vec![]))],
+            "Gather" => {
+                let output = dims.zip(self.dims(node, 1)).and_then(|(data, indices)| {
+                    let axis = axis(int_attribute(node, "axis").unwrap_or(0), data.len())?;
+                    Some(
+                        data[..axis]
+                            .iter()
+                            .chain(indices)
+                            .chain(&data[axis + 1..])
+                            .cloned()
+                            .collect(),
+                    )
+                });
+                vec![self.like_input(node, output)]
+            }
+            "GatherElements" => {
+                vec![self.like_input(node, self.dims(node, 1).map(<[Dim]>::to_vec))]
+            }
+            _ if op_type.starts_with("Reduce") => vec![self.like_input(node, self.reduce(node))],
+            "ArgMax" | "ArgMin" => {
+                let output = dims.and_then(|dims| {
+                    let axis = axis(int_attribute(node, "axis").unwrap_or(0), dims.len())?;
+                    let keep = int_attribute(node, "keepdims").unwrap_or(1) != 0;
+                    Some(Self::reduced(dims, &[axis], keep))
+                });
+                vec![output.map(|dims| TensorType::new(INT64, dims))]
+            }
+            "Constant" => vec![Self::constant(node)],
+            "ConstantOfShape" => {
+                let elem_type = attribute(node, "value")
+                    .and_then(|value| value.t.as_ref())
+                    .map(|tensor| tensor.data_type)
+                    .unwrap_or(FLOAT);
+                vec![self
+                    .value(node, 0)
+                    .map(|dims| TensorType::new(elem_type, dims.to_vec()))]
+            }
+            "Expand" => {
+                let output = dims
+                    .zip(self.value(node, 1))
+                    .map(|(dims, shape)| broadcast(&[dims, shape]));
+                vec![self.like_input(node, output)]
+            }
+            "Tile" => {
+                let output = dims.zip(self.fixed_value(node, 1)).map(|(dims, repeats)| {
+                    dims.iter()
+                        .zip(repeats)
+                        .map(|(dim, repeat)| match (dim, repeat) {
+                            (_, 1) => dim.clone(),
+                            (Dim::Fixed(size), repeat) => Dim::Fixed(size * repeat),
+                            _ => unknown(),
+                        })
+                        .collect()
+                });
+                vec![self.like_input(node, output)]
+            }
+            "Split" => self.split(node),
+            "Slice" => vec![self.like_input(node, self.slice(node))],
+            "Pad" => vec![self.like_input(node, self.pad(node))],
+            "Resize" => {
+                // only the sizes are integers, the dimensions scaled are left unknown
+                let output = self
+                    .value(node, 3)
+                    .map(<[Dim]>::to_vec)
+                    .or_else(|| dims.map(|dims| dims.iter().map(|_| unknown()).collect()));
+                vec![self.like_input(node, output)]
+            }
+            "Range" => vec![self.like_input(node, Some(vec![unknown()]))],
+            "NonZero" => vec![dims.map(|dims| {
+                TensorType::new(INT64, vec![Dim::Fixed(dims.len() as i64), unknown()])
+            })],
+            "TopK" => {
+                let output = dims.and_then(|dims| {
+                    let axis = axis(int_attribute(node, "axis").unwrap_or(-1), dims.len())?;
+                    let k = self
+                        .value(node, 1)
+                        .and_then(|k| k.first().cloned())
+                        .unwrap_or_else(unknown);
+                    let mut dims = dims.to_vec();
+                    dims[axis] = k;
+                    Some(dims)
+                });
+                vec![
+                    self.like_input(node, output.clone()),
+                    output.map(|dims| TensorType::new(INT64, dims)),
+                ]
+            }
+            "DepthToSpace" | "SpaceToDepth" => {
+                let output = dims.filter(|dims| dims.len() == 4).and_then(|dims| {
+                    let block = int_attribute(node, "blocksize")?;
+                    // the blocks of channels are moved to the spatial dimensions, or back
+                    let (multiplier, divisor) = if op_type == "DepthToSpace" {
+                        (1, block)
+                    } else {
+                        (block, 1)
+                    };
+                    let scale = |dim: &Dim, multiplier: i64, divisor: i64| {
+                        fixed(dim).map(|size| Dim::Fixed(size * multiplier / divisor))
+                    };
+                    Some(vec![
+                        dims[0].clone(),
+                        scale(&dims[1], multiplier * multiplier, divisor * divisor)
+                            .unwrap_or_else(unknown),
+                        scale(&dims[2], divisor, multiplier).unwrap_or_else(unknown),
+                        scale(&dims[3], divisor, multiplier).unwrap_or_else(unknown),
+                    ])
+                });
+                vec![self.like_input(node, output)]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn gemm(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let (a, b) = (self.dims(node, 0)?, self.dims(node, 1)?);
+        if a.len() != 2 || b.len() != 2 {
+            return None;
+        }
+        let rows = if int_attribute(node, "transA").unwrap_or(0) != 0 {
+            &a[1]
+        } else {
+            &a[0]
+        };
+        let columns = if int_attribute(node, "transB").unwrap_or(0) != 0 {
+            &b[0]
+        } else {
+            &b[1]
+        };
+        Some(vec![rows.clone(), columns.clone()])
+    }
+
+    // spatial dimensions of the output of a convolution or pooling of the input with the kernel
+    fn windowed(node: &NodeProto, input: &[Dim], kernel: &[Dim]) -> Vec<Dim> {
+        let count = input.len();
+        let strides = ints_attribute(node, "strides").unwrap_or_default();
+        let dilations = ints_attribute(node, "dilations").unwrap_or_default();
+        let pads = ints_attribute(node, "pads").unwrap_or_default();
+        let auto_pad = attribute(node, "auto_pad")
+            .map(|attribute| String::from_utf8_lossy(&attribute.s).to_string())
+            .unwrap_or_default();
+        let ceil_mode = int_attribute(node, "ceil_mode").unwrap_or(0) != 0;
+
+        (0..count)
+            .map(|index| {
+                let stride = strides.get(index).copied().unwrap_or(1);
+                let dilation = dilations.get(index).copied().unwrap_or(1);
+                let (begin, end) = match auto_pad.as_str() {
+                    "VALID" => (0, 0),
+                    _ => (
+                        pads.get(index).copied().unwrap_or(0),
+                        pads.get(index + count).copied().unwrap_or(0),
+                    ),
+                };
+                let window = kernel
+                    .get(index)
+                    .and_then(fixed)
+                    .map(|size| (size - 1) * dilation + 1);
+                match (&input[index], window) {
+                    // pointwise windows keep the dimension, symbolic or not
+                    (dim, Some(1)) if stride == 1 && begin == 0 && end == 0 => dim.clone(),
+                    (Dim::Fixed(size), _) if auto_pad.starts_with("SAME") => {
+                        Dim::Fixed((size + stride - 1) / stride)
+                    }
+                    (Dim::Fixed(size), Some(window)) => {
+                        let span = size + begin + end - window;
+                        if ceil_mode {
+                            Dim::Fixed((span + stride - 1) / stride + 1)
+                        } else {
+                            Dim::Fixed(span / stride + 1)
+                        }
+                    }
+                    _ => unknown(),
+                }
+            })
+            .collect()
+    }
+
+    fn conv(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let (input, weight) = (self.dims(node, 0)?, self.dims(node, 1)?);
+        if input.len() < 3 || weight.len() != input.len() {
+            return None;
+        }
+        let kernel = ints_attribute(node, "kernel_shape")
+            .map(|kernel| kernel.iter().copied().map(Dim::Fixed).collect())
+            .unwrap_or_else(|| weight[2..].to_vec());
+        let mut dims = vec![input[0].clone(), weight[0].clone()];
+        dims.extend(Self::windowed(node, &input[2..], &kernel));
+        Some(dims)
+    }
+
+    fn pool(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let input = self.dims(node, 0)?;
+        if input.len() < 3 {
+            return None;
+        }
+        let kernel: Vec<Dim> = ints_attribute(node, "kernel_shape")?
+            .iter()
+            .copied()
+            .map(Dim::Fixed)
+            .collect();
+        let mut dims = input[..2].to_vec();
+        dims.extend(Self::windowed(node, &input[2..], &kernel));
+        Some(dims)
+    }
+
+    fn conv_transpose(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let (input, weight) = (self.dims(node, 0)?, self.dims(node, 1)?);
+        if input.len() < 3 || weight.len() != input.len() {
+            return None;
+        }
+        let group = int_attribute(node, "group").unwrap_or(1);
+        let channels = fixed(&weight[1])
+            .map(|channels| Dim::Fixed(channels * group))
+            .unwrap_or_else(unknown);
+        let mut dims = vec![input[0].clone(), channels];
+        if let Some(output_shape) = ints_attribute(node, "output_shape") {
+            dims.extend(output_shape.iter().copied().map(Dim::Fixed));
+            return Some(dims);
+        }
+
+        let count = input.len() - 2;
+        let strides = ints_attribute(node, "strides").unwrap_or_default();
+        let dilations = ints_attribute(node, "dilations").unwrap_or_default();
+        let pads = ints_attribute(node, "pads").unwrap_or_default();
+        let output_padding = ints_attribute(node, "output_padding").unwrap_or_default();
+        let kernel = ints_attribute(node, "kernel_shape")
+            .map(|kernel| kernel.iter().copied().map(Dim::Fixed).collect())
+            .unwrap_or_else(|| weight[2..].to_vec());
+        for index in 0..count {
+            let stride = strides.get(index).copied().unwrap_or(1);
+            let dilation = dilations.get(index).copied().unwrap_or(1);
+            let padding = pads.get(index).copied().unwrap_or(0)
+                + pads.get(index + count).copied().unwrap_or(0);
+            let extra = output_padding.get(index).copied().unwrap_or(0);
+            dims.push(
+                match (fixed(&input[index + 2]), kernel.get(index).and_then(fixed)) {
+                    (Some(size), Some(kernel)) => Dim::Fixed(
+                        stride * (size - 1) + extra + (kernel - 1) * dilation + 1 - padding,
+                    ),
+                    _ => unknown(),
+                },
+            );
+        }
+        Some(dims)
+    }
+
+    fn concat(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let shapes: Vec<&[Dim]> = (0..node.input.len())
+            .map(|index| self.dims(node, index))
+            .collect::<Option<_>>()?;
+        let first = shapes.first()?;
+        let axis = axis(int_attribute(node, "axis")?, first.len())?;
+        let mut dims = first.to_vec();
+        dims[axis] = shapes
+            .iter()
+            .map(|dims| dims.get(axis).and_then(fixed))
+            .sum::<Option<i64>>()
+            .map(Dim::Fixed)
+            .unwrap_or_else(unknown);
+        Some(dims)
+    }
+
+    fn reshape(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let Some(target) = self.value(node, 1) else {
+            // the rank is the length of the shape
+            let length = self.dims(node, 1)?.first().and_then(fixed)?;
+            return Some((0..length).map(|_| unknown()).collect());
+        };
+        let input = self.dims(node, 0);
+        let allow_zero = int_attribute(node, "allowzero").unwrap_or(0) != 0;
+
+        let mut dims: Vec<Dim> = target
+            .iter()
+            .enumerate()
+            .map(|(index, dim)| match dim {
+                Dim::Fixed(0) if !allow_zero => input
+                    .and_then(|input| input.get(index))
+                    .cloned()
+                    .unwrap_or_else(unknown),
+                dim => dim.clone(),
+            })
+            .collect();
+        if let Some(inferred) = dims.iter().position(|dim| *dim == Dim::Fixed(-1)) {
+            let total = input
+                .and_then(all_fixed)
+                .map(|sizes| sizes.iter().product::<i64>());
+            let others: Option<i64> = dims
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != inferred)
+                .map(|(_, dim)| fixed(dim))
+                .product();
+            dims[inferred] = match (total, others) {
+                (Some(total), Some(others)) if others != 0 => Dim::Fixed(total / others),
+                _ => unknown(),
+            };
+        }
+        Some(dims)
+    }
+
+    // the dimensions of the input Shape returns, between its start and end attributes
+    fn shape_value(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let dims = self.dims(node, 0)?;
+        let rank = dims.len() as i64;
+        let resolve = |value: i64| {
+            if value < 0 {
+                (value + rank).max(0)
+            } else {
+                value.min(rank)
+            }
+        };
+        let start = resolve(int_attribute(node, "start").unwrap_or(0)) as usize;
+        let end = resolve(int_attribute(node, "end").unwrap_or(rank)) as usize;
+        Some(dims[start..end.max(start)].to_vec())
+    }
+
+    fn reduced(dims: &[Dim], axes: &[usize], keep: bool) -> Vec<Dim> {
+        dims.iter()
+            .enumerate()
+            .filter_map(|(index, dim)| match (axes.contains(&index), keep) {
+                (false, _) => Some(dim.clone()),
+                (true, true) => Some(Dim::Fixed(1)),
+                (true, false) => None,
+            })
+            .collect()
+    }
+
+    fn reduce(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let dims = self.dims(node, 0)?;
+        let keep = int_attribute(node, "keepdims").unwrap_or(1) != 0;
+        let axes: Vec<usize> = match self.axes(node, 1) {
+            Some(axes) if !axes.is_empty() => axes
+                .iter()
+                .map(|index| axis(*index, dims.len()))
+                .collect::<Option<_>>()?,
+            // the axes input is given, its values aren't known
+            _ if node.input.get(1).is_some_and(|name| !name.is_empty()) => return None,
+            _ if int_attribute(node, "noop_with_empty_axes").unwrap_or(0) != 0 => {
+                return Some(dims.to_vec())
+            }
+            _ => (0..dims.len()).collect(),
+        };
+        Some(Self::reduced(dims, &axes, keep))
+    }
+
+    fn constant(node: &NodeProto) -> Option<TensorType> {
+        let attribute = node.attribute.first()?;
+        match attribute.name.as_str() {
+            "value" => attribute
+                .t
+                .as_ref()
+                .map(|tensor| TensorType::new(tensor.data_type, tensor_dims(tensor))),
+            "value_int" => Some(TensorType::new(INT64, vec![])),
+            "value_ints" => Some(TensorType::new(
+                INT64,
+                vec![Dim::Fixed(attribute.ints.len() as i64)],
+            )),
+            "value_float" => Some(TensorType::new(FLOAT, vec![])),
+            "value_floats" => Some(TensorType::new(
+                FLOAT,
+                vec![Dim::Fixed(attribute.floats.len() as i64)],
+            )),
+            _ => None,
+        }
+    }
+
+    fn split(&self, node: &NodeProto) -> Vec<Option<TensorType>> {
+        let Some((dims, elem_type)) = self.dims(node, 0).zip(self.elem_type(node, 0)) else {
+            return vec![];
+        };
+        let Some(axis) = axis(int_attribute(node, "axis").unwrap_or(0), dims.len()) else {
+            return vec![];
+        };
+        let count = node.output.len() as i64;
+        let sizes: Vec<Dim> = match ints_attribute(node, "split")
+            .map(<[i64]>::to_vec)
+            .or_else(|| self.fixed_value(node, 1))
+        {
+            Some(sizes) => sizes.into_iter().map(Dim::Fixed).collect(),
+            None => {
+                // equal parts, the last one being smaller if the dimension isn't divisible
+                let part = fixed(&dims[axis]).map(|size| (size + count - 1) / count);
+                (0..count)
+                    .map(|index| match (fixed(&dims[axis]), part) {
+                        (Some(size), Some(part)) => {
+                            Dim::Fixed(part.min(size - part * index).max(0))
+                        }
+                        _ => unknown(),
+                    })
+                    .collect()
+            }
+        };
+        sizes
+            .into_iter()
+            .map(|size| {
+                let mut dims = dims.to_vec();
+                dims[axis] = size;
+                Some(TensorType::new(elem_type, dims))
+            })
+            .collect()
+    }
+
+    // starts, ends, axes and steps, given by inputs since opset 10
+    fn slice_arguments(&self, node: &NodeProto) -> Option<[Vec<i64>; 4]> {
+        let (starts, ends, axes, steps) = match ints_attribute(node, "starts") {
+            Some(starts) => (
+                starts.to_vec(),
+                ints_attribute(node, "ends")?.to_vec(),
+                ints_attribute(node, "axes").map(<[i64]>::to_vec),
+                None,
+            ),
+            None => (
+                self.fixed_value(node, 1)?,
+                self.fixed_value(node, 2)?,
+                match node.input.get(3).filter(|name| !name.is_empty()) {
+                    Some(_) => Some(self.fixed_value(node, 3)?),
+                    None => None,
+                },
+                match node.input.get(4).filter(|name| !name.is_empty()) {
+                    Some(_) => Some(self.fixed_value(node, 4)?),
+                    None => None,
+                },
+            ),
+        };
+        let axes = axes.unwrap_or_else(|| (0..starts.len() as i64).collect());
+        let steps = steps.unwrap_or_else(|| vec![1; starts.len()]);
+        Some([starts, ends, axes, steps])
+    }
+
+    fn slice_dims(dims: &[Dim], arguments: &[Vec<i64>; 4]) -> Option<Vec<Dim>> {
+        let [starts, ends, axes, steps] = arguments;
+        let mut dims = dims.to_vec();
+        for (((start, end), index), step) in starts.iter().zip(ends).zip(axes).zip(steps) {
+            let index = axis(*index, dims.len())?;
+            dims[index] = match &dims[index] {
+                Dim::Fixed(size) if *step != 0 => Dim::Fixed(slice_len(*size, *start, *end, *step)),
+                // the whole dimension
+                dim if *start == 0 && *end >= i32::MAX as i64 && *step == 1 => dim.clone(),
+                _ => unknown(),
+            };
+        }
+        Some(dims)
+    }
+
+    fn slice(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let dims = self.dims(node, 0)?;
+        match self.slice_arguments(node) {
+            Some(arguments) => Self::slice_dims(dims, &arguments),
+            None => Some(dims.iter().map(|_| unknown()).collect()),
+        }
+    }
+
+    fn pad(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        let dims = self.dims(node, 0)?;
+        let pads = ints_attribute(node, "pads")
+            .map(<[i64]>::to_vec)
+            .or_else(|| self.fixed_value(node, 1));
+        // the axes padded since opset 18, all of them by default
+        let axes: Vec<usize> = match node.input.get(3).filter(|name| !name.is_empty()) {
+            Some(_) => self
+                .fixed_value(node, 3)?
+                .iter()
+                .map(|index| axis(*index, dims.len()))
+                .collect::<Option<_>>()?,
+            None => (0..dims.len()).collect(),
+        };
+        let Some(pads) = pads.filter(|pads| pads.len() == axes.len() * 2) else {
+            return Some(dims.iter().map(|_| unknown()).collect());
+        };
+
+        let mut dims = dims.to_vec();
+        for (position, index) in axes.iter().enumerate() {
+            let padding = pads[position] + pads[position + axes.len()];
+            dims[*index] = match &dims[*index] {
+                _ if padding == 0 => dims[*index].clone(),
+                Dim::Fixed(size) => Dim::Fixed(size + padding),
+                _ => unknown(),
+            };
+        }
+        Some(dims)
+    }
+
+    // the values of the output of the operators computing shapes, axes and indexes
+    fn output_value(&self, node: &NodeProto) -> Option<Vec<Dim>> {
+        match node.op_type.as_str() {
+            "Constant" => {
+                let attribute = node.attribute.first()?;
+                match attribute.name.as_str() {
+                    "value" => tensor_values(attribute.t.as_ref()?),
+                    "value_int" => Some(vec![Dim::Fixed(attribute.i)]),
+                    "value_ints" => Some(attribute.ints.iter().copied().map(Dim::Fixed).collect()),
+                    _ => None,
+                }
+            }
+            "Shape" => self.shape_value(node),
+            "Size" => Some(vec![product(self.dims(node, 0)?)]),
+            "Identity" | "Cast" | "Squeeze" | "Unsqueeze" | "Reshape" => {
+                self.value(node, 0).map(<[Dim]>::to_vec)
+            }
+            "Concat" => (0..node.input.len())
+                .map(|index| self.value(node, index).map(<[Dim]>::to_vec))
+                .collect::<Option<Vec<_>>>()
+                .map(|values| values.concat()),
+            "Gather" if int_attribute(node, "axis").unwrap_or(0) == 0 => {
+                let data = self.value(node, 0)?;
+                self.fixed_value(node, 1)?
+                    .iter()
+                    .map(|index| axis(*index, data.len()).map(|index| data[index].clone()))
+                    .collect()
+            }
+            "Slice" => {
+                let data = self.value(node, 0)?;
+                let [starts, ends, axes, steps] = self.slice_arguments(node)?;
+                if axes.iter().any(|index| axis(*index, 1) != Some(0)) || starts.len() != 1 {
+                    return None;
+                }
+                let (start, end, step) = (starts[0], ends[0], steps[0]);
+                let size = data.len() as i64;
+                let resolve = |value: i64| if value < 0 { value + size } else { value };
+                if step <= 0 {
+                    return None;
+                }
+                let (start, end) = (resolve(start).clamp(0, size), resolve(end).clamp(0, size));
+                Some(
+                    (start..end)
+                        .step_by(step as usize)
+                        .map(|index| data[index as usize].clone())
+                        .collect(),
+                )
+            }
+            op_type @ ("Add" | "Sub" | "Mul" | "Div") => {
+                let (a, b) = (self.fixed_value(node, 0)?, self.fixed_value(node, 1)?);
+                let length = a.len().max(b.len());
+                if (a.len() != length && a.len() != 1) || (b.len() != length && b.len() != 1) {
+                    return None;
+                }
+                (0..length)
+                    .map(|index| {
+                        let (a, b) = (a[index % a.len()], b[index % b.len()]);
+                        match op_type {
+                            "Add" => Some(a + b),
+                            "Sub" => Some(a - b),
+                            "Mul" => Some(a * b),
+                            _ => a.checked_div(b),
+                        }
+                        .map(Dim::Fixed)
+                    })
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+}
+
+fn declared_type(type_: &type_proto::Value) -> Option<TensorType> {
+    match type_ {
+        type_proto::Value::TensorType(tensor) => Some(TensorType {
+            elem_type: tensor.elem_type,
+            dims: super::graph::dims_of(tensor),
+        }),
+        _ => None,
+    }
+}
+
+/// Infers the element type and dimensions of the tensors of the graph from its inputs and
+/// initializers through the operators of the default domain, like onnx.shape_inference does,
+/// the ones the graph declares being kept.
+pub(super) fn infer_shapes(graph: &GraphProto) -> HashMap<&str, ValueInfo> {
+    let mut inference = Inference::default();
+    for value in graph
+        .input
+        .iter()
+        .chain(&graph.value_info)
+        .chain(&graph.output)
+    {
+        if let Some(tensor_type) = value.type_.value.as_ref().and_then(declared_type) {
+            inference.types.insert(&value.name, tensor_type);
+        }
+    }
+    // initializers older models list among the inputs have the dimensions of their data
+    for initializer in &graph.initializer {
+        inference.types.insert(
+            &initializer.name,
+            TensorType::new(initializer.data_type, tensor_dims(initializer)),
+        );
+        if let Some(values) = tensor_values(initializer) {
+            inference.values.insert(&initializer.name, values);
+        }
+    }
+
+    for node in &graph.node {
+        inference.infer(node);
+    }
+
+    inference
+        .types
+        .into_iter()
+        .map(|(name, tensor_type)| {
+            (
+                name,
+                ValueInfo {
+                    name: name.to_string(),
+                    dtype: data_type_string(tensor_type.elem_type).to_string(),
+                    dims: tensor_type.dims,
+                },
+            )
+        })
+        .collect()
+}
+
+/// The tensors produced by the nodes of the graph, in execution order, but its outputs, with
+/// their declared or inferred shapes.
+pub(super) fn intermediate_shapes(graph: &GraphProto) -> Vec<ValueInfo> {
+    let mut shapes = infer_shapes(graph);
+    graph
+        .node
+        .iter()
+        .flat_map(|node| &node.output)
+        .filter(|name| !name.is_empty() && !graph.output.iter().any(|output| output.name == **name))
+        .map(|name| {
+            shapes.remove(name.as_str()).unwrap_or_else(|| ValueInfo {
+                name: name.clone(),
+                dtype: "unknown".to_string(),
+                dims: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::protos::{
+        attribute_proto::AttributeType, tensor_shape_proto::Dimension, type_proto::Tensor,
+        TypeProto, ValueInfoProto,
+    };
+    use super::*;
+
+    fn value(name: &str, dims: &[&str]) -> ValueInfoProto {
+        let mut tensor = Tensor::new();
+        tensor.elem_type = FLOAT;
+        tensor.shape.mut_or_insert_default().dim = dims
+            .iter()
+            .map(|dim| {
+                let mut dimension = Dimension::new();
+                match dim.parse() {
+                    Ok(size) => dimension.set_dim_value(size),
+                    Err(_) => dimension.set_dim_param(dim.to_string()),
+                }
+                dimension
+            })
+            .collect();
+        let mut type_ = TypeProto::new();
+        type_.value = Some(type_proto::Value::TensorType(tensor));
+
+        let mut value = ValueInfoProto::new();
+        value.name = name.to_string();
+        value.type_ = Some(type_).into();
+        value
+    }
+
+    fn tensor(name: &str, data_type: i32, dims: &[i64], values: &[i64]) -> TensorProto {
+        let mut tensor = TensorProto::new();
+        tensor.name = name.to_string();
+        tensor.data_type = data_type;
+        tensor.dims = dims.to_vec();
+        tensor.int64_data = values.to_vec();
+        tensor
+    }
+
+    fn ints(name: &str, values: &[i64]) -> AttributeProto {
+        let mut attribute = AttributeProto::new();
+        attribute.name = name.to_string();
+        attribute.type_ = AttributeType::INTS.into();
+        attribute.ints = values.to_vec();
+        attribute
+    }
+
+    fn node(
+        op_type: &str,
+        inputs: &[&str],
+        output: &str,
+        attributes: Vec<AttributeProto>,
+    ) -> NodeProto {
+        let mut node = NodeProto::new();
+        node.op_type = op_type.to_string();
+        node.input = inputs.iter().map(|name| name.to_string()).collect();
+        node.output = vec![output.to_string()];
+        node.attribute = attributes;
+        node
+    }
+
+    fn shapes(graph: &GraphProto) -> Vec<(String, String)> {
+        intermediate_shapes(graph)
+            .into_iter()
+            .map(|value| (value.name.clone(), value.shape()))
+            .collect()
+    }
+
+    #[test]
+    fn test_attention_reshape() {
+        let mut index = AttributeProto::new();
+        index.name = "value".to_string();
+        index.type_ = AttributeType::TENSOR.into();
+        index.t = Some(tensor("", INT64, &[], &[1])).into();
+
+        let mut graph = GraphProto::new();
+        graph.input = vec![value("x", &["batch", "sequence", "768"])];
+        graph.output = vec![value("y", &[])];
+        graph.initializer = vec![
+            tensor("weight", FLOAT, &[768, 768], &[]),
+            tensor("zero", INT64, &[1], &[0]),
+            tensor("heads", INT64, &[2], &[12, -1]),
+        ];
+        graph.node = vec![
+            node("MatMul", &["x", "weight"], "projected", vec![]),
+            node("Shape", &["projected"], "shape", vec![]),
+            node("Constant", &[], "one", vec![index]),
+            node("Gather", &["shape", "one"], "sequence", vec![]),
+            node("Unsqueeze", &["sequence", "zero"], "sequences", vec![]),
+            node("Concat", &["zero", "sequences", "heads"], "target", {
+                let mut axis = AttributeProto::new();
+                axis.name = "axis".to_string();
+                axis.i = 0;
+                vec![axis]
+            }),
+            node("Reshape", &["projected", "target"], "split", vec![]),
+            node(
+                "Transpose",
+                &["split"],
+                "transposed",
+                vec![ints("perm", &[0, 2, 1, 3])],
+            ),
+            node("Softmax", &["transposed"], "y", vec![]),
+        ];
+
+        assert_eq!(
+            shapes(&graph),
+            vec![
+                (
+                    "projected".to_string(),
+                    "FLOAT[batch,sequence,768]".to_string()
+                ),
+                ("shape".to_string(), "INT64[3]".to_string()),
+                ("one".to_string(), "INT64[]".to_string()),
+                ("sequence".to_string(), "INT64[]".to_string()),
+                ("sequences".to_string(), "INT64[1]".to_string()),
+                ("target".to_string(), "INT64[4]".to_string()),
+                // the batch is copied by the 0, the heads dimension is left unknown
+                (
+                    "split".to_string(),
+                    "FLOAT[batch,sequence,12,?]".to_string()
+                ),
+                (
+                    "transposed".to_string(),
+                    "FLOAT[batch,12,sequence,?]".to_string()
+                ),
+            ]
+        );
+        // the declared output is kept
+        assert_eq!(infer_shapes(&graph)["y"].shape(), "FLOAT[]");
+    }
+
+    #[test]
+    fn test_convolution() {
+        let mut graph = GraphProto::new();
+        graph.input = vec![value("image", &["1", "3", "224", "224"])];
+        graph.initializer = vec![
+            tensor("kernel", FLOAT, &[64, 3, 7, 7], &[]),
+            tensor("classifier", FLOAT, &[1000, 64], &[]),
+        ];
+        graph.node = vec![
+            node(
+                "Conv",
+                &["image", "kernel"],
+                "features",
+                vec![ints("strides", &[2, 2]), ints("pads", &[3, 3, 3, 3])],
+            ),
+            node(
+                "MaxPool",
+                &["features", ""],
+                "pooled",
+                vec![
+                    ints("kernel_shape", &[3, 3]),
+                    ints("strides", &[2, 2]),
+                    ints("pads", &[1, 1, 1, 1]),
+                ],
+            ),
+            node("GlobalAveragePool", &["pooled"], "averaged", vec![]),
+            node("Flatten", &["averaged"], "flat", vec![]),
+            node(
+                "Gemm",
+                &["flat", "classifier"],
+                "logits",
+                vec![{
+                    let mut transpose = AttributeProto::new();
+                    transpose.name = "transB".to_string();
+                    transpose.i = 1;
+                    transpose
+                }],
+            ),
+            node("Custom", &["logits"], "custom", vec![]),
+        ];
+
+        assert_eq!(
+            shapes(&graph),
+            vec![
+                ("features".to_string(), "FLOAT[1,64,112,112]".to_string()),
+                ("pooled".to_string(), "FLOAT[1,64,56,56]".to_string()),
+                ("averaged".to_string(), "FLOAT[1,64,1,1]".to_string()),
+                ("flat".to_string(), "FLOAT[1,64]".to_string()),
+                ("logits".to_string(), "FLOAT[1,1000]".to_string()),
+                ("custom".to_string(), "unknown".to_string()),
+            ]
+        );
+    }
+}
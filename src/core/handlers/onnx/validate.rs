@@ -0,0 +1,424 @@
+use std::{collections::HashSet, path::Path};
+
+use protobuf::Message;
+
+use super::{
+    data_type_bits, data_type_string, model_size,
+    protos::{
+        tensor_proto::DataLocation, type_proto, GraphProto, ModelProto, NodeProto, TensorProto,
+        ValueInfoProto,
+    },
+    scan::node_location,
+};
+use crate::core::validate::{violation, Violation};
+
+// both names of the default domain
+const DEFAULT_DOMAINS: &[&str] = &["", "ai.onnx"];
+const STRING_DATA_TYPE: i32 = 8;
+
+// the first IR version supporting the opset of the default domain
+fn min_ir_version(opset: i64) -> i64 {
+    match opset {
+        ..=8 => 3,
+        9 => 4,
+        10 => 5,
+        11 => 6,
+        12..=14 => 7,
+        15..=18 => 8,
+        19..=20 => 9,
+        21..=22 => 10,
+        _ => 11,
+    }
+}
+
+fn domain_of(domain: &str) -> &str {
+    if DEFAULT_DOMAINS.contains(&domain) {
+        ""
+    } else {
+        domain
+    }
+}
+
+// element type and dimensions of a tensor value, unknown dimensions are None
+fn tensor_type_of(value: &ValueInfoProto) -> Option<(i32, Vec<Option<i64>>)> {
+    match &value.type_.value {
+        Some(type_proto::Value::TensorType(tensor)) => Some((
+            tensor.elem_type,
+            tensor
+                .shape
+                .dim
+                .iter()
+                .map(|dim| dim.has_dim_value().then(|| dim.dim_value()))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+struct Validator {
+    // domains imported by the model
+    domains: HashSet<String>,
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    fn push(&mut self, rule_id: &'static str, message: String) {
+        self.violations.push(violation(rule_id, message, None));
+    }
+
+    fn check_tensor(&mut self, tensor: &TensorProto) {
+        if data_type_string(tensor.data_type) == "UNKNOWN" {
+            self.push(
+                "invalid-tensor",
+                format!(
+                    "tensor {} has unknown data type {}",
+                    tensor.name, tensor.data_type
+                ),
+            );
+            return;
+        }
+        let Some(elements) = tensor.dims.iter().try_fold(1u64, |elements, dim| {
+            elements.checked_mul(u64::try_from(*dim).ok()?)
+        }) else {
+            self.push(
+                "invalid-tensor",
+                format!("tensor {} has invalid dims {:?}", tensor.name, tensor.dims),
+            );
+            return;
+        };
+
+        // strings are not stored as raw data and external data is checked when read
+        let is_external = tensor.data_location.enum_value() == Ok(DataLocation::EXTERNAL);
+        if !tensor.raw_data.is_empty() && !is_external && tensor.data_type != STRING_DATA_TYPE {
+            let expected = elements
+                .checked_mul(data_type_bits(tensor.data_type) as u64)
+                .map(|bits| bits.div_ceil(8));
+            if expected != Some(tensor.raw_data.len() as u64) {
+                self.push(
+                    "invalid-tensor",
+                    format!(
+                        "tensor {} of dims {:?} and type {} has {} bytes of data",
+                        tensor.name,
+                        tensor.dims,
+                        data_type_string(tensor.data_type),
+                        tensor.raw_data.len()
+                    ),
+                );
+            }
+        }
+    }
+
+    // checks the initializers against the graph inputs they provide a default value for
+    fn check_initializers(&mut self, graph: &GraphProto) {
+        let mut names = HashSet::new();
+        for tensor in &graph.initializer {
+            if !names.insert(tensor.name.as_str()) {
+                self.push(
+                    "duplicate-tensor",
+                    format!("initializer {} is declared more than once", tensor.name),
+                );
+            }
+            self.check_tensor(tensor);
+
+            let Some((elem_type, dims)) = graph
+                .input
+                .iter()
+                .find(|input| input.name == tensor.name)
+                .and_then(tensor_type_of)
+            else {
+                continue;
+            };
+            let is_compatible = elem_type == tensor.data_type
+                && dims.len() == tensor.dims.len()
+                && dims
+                    .iter()
+                    .zip(&tensor.dims)
+                    .all(|(dim, tensor_dim)| dim.is_none_or(|dim| dim == *tensor_dim));
+            if !is_compatible {
+                self.push(
+                    "type-mismatch",
+                    format!(
+                        "initializer {} of type {} and dims {:?} doesn't match the graph input",
+                        tensor.name,
+                        data_type_string(tensor.data_type),
+                        tensor.dims
+                    ),
+                );
+            }
+        }
+    }
+
+    // checks that every input is available when the node runs and every output is assigned once
+    fn check_nodes(&mut self, nodes: &[NodeProto], available: &mut HashSet<String>) {
+        for node in nodes {
+            let location = node_location(node);
+            if !self.domains.contains(domain_of(&node.domain)) {
+                self.push(
+                    "invalid-opset",
+                    format!(
+                        "no opset imported for the {} domain of {}",
+                        node.domain, location
+                    ),
+                );
+            }
+
+            for input in node.input.iter().filter(|input| !input.is_empty()) {
+                if !available.contains(input) {
+                    self.push(
+                        "dangling-input",
+                        format!(
+                            "input {} of {} is not produced by a previous node",
+                            input, location
+                        ),
+                    );
+                }
+            }
+
+            // subgraphs of If, Loop and Scan can reference the values of the outer scope
+            for attribute in &node.attribute {
+                for graph in attribute.g.iter().chain(&attribute.graphs) {
+                    self.check_graph(graph, available);
+                }
+            }
+
+            for output in node.output.iter().filter(|output| !output.is_empty()) {
+                if !available.insert(output.clone()) {
+                    self.push(
+                        "duplicate-name",
+                        format!("{} is assigned more than once, by {}", output, location),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_graph(&mut self, graph: &GraphProto, outer: &HashSet<String>) {
+        self.check_initializers(graph);
+
+        let mut available = outer.clone();
+        for name in graph
+            .input
+            .iter()
+            .map(|input| &input.name)
+            .chain(graph.initializer.iter().map(|tensor| &tensor.name))
+        {
+            available.insert(name.clone());
+        }
+
+        self.check_nodes(&graph.node, &mut available);
+
+        for output in &graph.output {
+            if !available.contains(&output.name) {
+                self.push(
+                    "dangling-input",
+                    format!("graph output {} is not produced by any node", output.name),
+                );
+            }
+        }
+    }
+}
+
+/// Checks the model the way onnx.checker does: IR version and opsets, references between the
+/// nodes, duplicate names and initializers not matching their declaration.
+pub(crate) fn validate(file_path: &Path) -> anyhow::Result<Vec<Violation>> {
+    let data = std::fs::read(file_path)?;
+    let mut violations = vec![];
+
+    let size = model_size(&data);
+    if size < data.len() as u64 {
+        violations.push(violation(
+            "trailing-data",
+            format!(
+                "{} bytes after the end of the model are not part of it",
+                data.len() as u64 - size
+            ),
+            Some(size),
+        ));
+    }
+
+    let model: ModelProto = match Message::parse_from_bytes(&data) {
+        Ok(model) => model,
+        Err(e) => {
+            violations.push(violation(
+                "invalid-header",
+                format!("model can't be parsed: {}", e),
+                None,
+            ));
+            return Ok(violations);
+        }
+    };
+
+    let mut validator = Validator {
+        domains: HashSet::new(),
+        violations,
+    };
+
+    if model.ir_version < 3 {
+        validator.push(
+            "invalid-ir-version",
+            format!("IR version {} is not supported", model.ir_version),
+        );
+    }
+    if model.opset_import.is_empty() {
+        validator.push("invalid-opset", "no opset imported".to_string());
+    }
+    for opset in &model.opset_import {
+        let domain = domain_of(&opset.domain);
+        if !validator.domains.insert(domain.to_string()) {
+            validator.push(
+                "invalid-opset",
+                format!(
+                    "opset of the {} domain imported more than once",
+                    opset.domain
+                ),
+            );
+        }
+        if domain.is_empty() && model.ir_version < min_ir_version(opset.version) {
+            validator.push(
+                "invalid-ir-version",
+                format!(
+                    "IR version {} is too old for opset {}, at least {} is required",
+                    model.ir_version,
+                    opset.version,
+                    min_ir_version(opset.version)
+                ),
+            );
+        }
+    }
+
+    validator.check_graph(&model.graph, &HashSet::new());
+    for function in &model.functions {
+        let mut available = function.input.iter().cloned().collect();
+        validator.check_nodes(&function.node, &mut available);
+    }
+
+    Ok(validator.violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::protos::{
+        tensor_shape_proto::Dimension, type_proto::Tensor, AttributeProto, OperatorSetIdProto,
+        TypeProto,
+    };
+    use super::*;
+
+    fn node(name: &str, op_type: &str, inputs: &[&str], outputs: &[&str]) -> NodeProto {
+        let mut node = NodeProto::new();
+        node.name = name.to_string();
+        node.op_type = op_type.to_string();
+        node.input = inputs.iter().map(|input| input.to_string()).collect();
+        node.output = outputs.iter().map(|output| output.to_string()).collect();
+        node
+    }
+
+    fn value(name: &str, elem_type: i32, dims: &[i64]) -> ValueInfoProto {
+        let mut tensor = Tensor::new();
+        tensor.elem_type = elem_type;
+        for dim_value in dims {
+            let mut dim = Dimension::new();
+            dim.set_dim_value(*dim_value);
+            tensor.shape.mut_or_insert_default().dim.push(dim);
+        }
+        let mut type_ = TypeProto::new();
+        type_.value = Some(type_proto::Value::TensorType(tensor));
+
+        let mut value = ValueInfoProto::new();
+        value.name = name.to_string();
+        value.type_ = Some(type_).into();
+        value
+    }
+
+    fn initializer(name: &str, dims: &[i64], size: usize) -> TensorProto {
+        let mut tensor = TensorProto::new();
+        tensor.name = name.to_string();
+        tensor.data_type = 1;
+        tensor.dims = dims.to_vec();
+        tensor.raw_data = vec![0; size];
+        tensor
+    }
+
+    fn model(ir_version: i64, opset: i64, graph: GraphProto) -> ModelProto {
+        let mut model = ModelProto::new();
+        model.ir_version = ir_version;
+        let mut opset_import = OperatorSetIdProto::new();
+        opset_import.version = opset;
+        model.opset_import.push(opset_import);
+        model.graph = Some(graph).into();
+        model
+    }
+
+    fn rules_of(model: &ModelProto) -> Vec<&'static str> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.onnx");
+        std::fs::write(&file_path, model.write_to_bytes().unwrap()).unwrap();
+        validate(&file_path)
+            .unwrap()
+            .iter()
+            .map(|violation| violation.rule_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_valid() {
+        // y = x * w, then a subgraph reading y from the outer scope
+        let mut branch = GraphProto::new();
+        branch.node.push(node("neg", "Neg", &["y"], &["z"]));
+        branch.output.push(value("z", 1, &[]));
+        let mut then_branch = AttributeProto::new();
+        then_branch.g = Some(branch).into();
+        let mut condition = node("if", "If", &["c"], &["out"]);
+        condition.attribute.push(then_branch);
+
+        let mut graph = GraphProto::new();
+        graph.input = vec![value("x", 1, &[2]), value("c", 9, &[]), value("w", 1, &[2])];
+        graph.initializer.push(initializer("w", &[2], 8));
+        graph.node = vec![node("mul", "Mul", &["x", "w"], &["y"]), condition];
+        graph.output.push(value("out", 1, &[2]));
+
+        let model = model(8, 17, graph);
+        assert!(rules_of(&model).is_empty());
+
+        // a payload appended to the model
+        let mut data = model.write_to_bytes().unwrap();
+        let size = data.len() as u64;
+        data.extend(b"PK\x03\x04payload");
+        assert_eq!(model_size(&data), size);
+        // a second IR version, that would override the first one
+        data.truncate(size as usize);
+        data.extend([0x08, 0x07]);
+        assert_eq!(model_size(&data), size);
+    }
+
+    #[test]
+    fn test_validate_violations() {
+        let mut graph = GraphProto::new();
+        graph.input = vec![value("x", 1, &[2]), value("w", 1, &[2])];
+        graph.initializer = vec![initializer("w", &[2], 8), initializer("w", &[3], 8)];
+        graph.node = vec![
+            // b is only produced by the next node
+            node("add", "Add", &["x", "b"], &["y"]),
+            node("relu", "Relu", &["x"], &["b"]),
+            node("neg", "Neg", &["x"], &["y"]),
+        ];
+        graph.output.push(value("out", 1, &[2]));
+        let mut model = model(7, 17, graph);
+        let mut custom = node("custom", "Decrypt", &["x"], &["d"]);
+        custom.domain = "com.example".to_string();
+        model.graph.mut_or_insert_default().node.push(custom);
+
+        assert_eq!(
+            rules_of(&model),
+            vec![
+                "invalid-ir-version",
+                "duplicate-tensor",
+                "invalid-tensor",
+                "type-mismatch",
+                "dangling-input",
+                "duplicate-name",
+                "invalid-opset",
+                "dangling-input",
+            ]
+        );
+    }
+}
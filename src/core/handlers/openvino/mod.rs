@@ -0,0 +1,431 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader, XmlVersion,
+};
+use rayon::prelude::*;
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+#[derive(Debug, Default)]
+struct Layer {
+    id: String,
+    name: String,
+    layer_type: String,
+    element_type: Option<String>,
+    shape: Vec<usize>,
+    offset: Option<usize>,
+    size: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct IrModel {
+    name: String,
+    version: String,
+    layers: Vec<Layer>,
+    precisions: BTreeSet<String>,
+    rt_info: Metadata,
+}
+
+fn attributes(element: &BytesStart) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut attrs = BTreeMap::new();
+    for attr in element.attributes() {
+        let attr = attr?;
+        attrs.insert(
+            attr.key.as_ref().to_string(),
+            attr.normalized_value(XmlVersion::Implicit1_0)?.to_string(),
+        );
+    }
+    Ok(attrs)
+}
+
+fn parse_shape(shape: &str) -> Vec<usize> {
+    shape
+        .split(',')
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        // dynamic dimensions (-1, ?, ranges) are reported as 0
+        .map(|d| d.parse::<usize>().unwrap_or(0))
+        .collect()
+}
+
+fn parse_ir(xml: &str) -> anyhow::Result<IrModel> {
+    let mut reader = Reader::from_str(xml);
+    let mut model = IrModel::default();
+    let mut found_net = false;
+    let mut layer: Option<Layer> = None;
+    // path of the current element inside the top level rt_info block
+    let mut rt_info_path: Option<Vec<String>> = None;
+
+    loop {
+        let (element, is_empty) = match reader.read_event()? {
+            Event::Eof => break,
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                let name = e.local_name();
+                let name = name.as_ref();
+                if name == "layer" {
+                    if let Some(layer) = layer.take() {
+                        model.layers.push(layer);
+                    }
+                } else if let Some(path) = rt_info_path.as_mut() {
+                    if path.pop().is_none() {
+                        rt_info_path = None;
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        let name = element.local_name();
+        let name = name.as_ref().to_string();
+        let attrs = attributes(&element)?;
+
+        if let Some(path) = rt_info_path.as_mut() {
+            if let Some(value) = attrs.get("value") {
+                let mut key = path.clone();
+                key.push(name.clone());
+                model.rt_info.insert(key.join("."), value.to_string());
+            }
+            if !is_empty {
+                path.push(name);
+            }
+            continue;
+        }
+
+        match name.as_str() {
+            "net" => {
+                found_net = true;
+                model.name = attrs.get("name").cloned().unwrap_or_default();
+                model.version = attrs.get("version").cloned().unwrap_or_default();
+            }
+            "layer" => {
+                let new_layer = Layer {
+                    id: attrs.get("id").cloned().unwrap_or_default(),
+                    name: attrs.get("name").cloned().unwrap_or_default(),
+                    layer_type: attrs.get("type").cloned().unwrap_or_default(),
+                    ..Default::default()
+                };
+                if is_empty {
+                    model.layers.push(new_layer);
+                } else {
+                    layer = Some(new_layer);
+                }
+            }
+            "data" => {
+                if let Some(layer) = layer.as_mut() {
+                    layer.element_type = attrs.get("element_type").cloned();
+                    layer.shape = attrs
+                        .get("shape")
+                        .map(|s| parse_shape(s))
+                        .unwrap_or_default();
+                    layer.offset = attrs.get("offset").and_then(|o| o.parse().ok());
+                    layer.size = attrs.get("size").and_then(|s| s.parse().ok());
+                }
+            }
+            "port" => {
+                if let Some(precision) = attrs.get("precision") {
+                    model.precisions.insert(precision.to_string());
+                }
+            }
+            // layers can have their own rt_info, we only care about the model one
+            "rt_info" if layer.is_none() && !is_empty => {
+                rt_info_path = Some(vec![]);
+            }
+            _ => {}
+        }
+    }
+
+    if !found_net {
+        anyhow::bail!("not an OpenVINO IR file: missing <net> element");
+    }
+
+    Ok(model)
+}
+
+fn build_tensor_descriptor(layer: &Layer) -> TensorDescriptor {
+    let mut metadata = Metadata::new();
+    metadata.insert("layer_id".to_string(), layer.id.clone());
+    if let Some(offset) = layer.offset {
+        metadata.insert("offset".to_string(), offset.to_string());
+    }
+
+    TensorDescriptor {
+        id: Some(layer.name.clone()),
+        shape: layer.shape.clone(),
+        dtype: layer
+            .element_type
+            .as_ref()
+            .map(|t| t.to_ascii_uppercase())
+            .unwrap_or_else(|| "UNKNOWN".to_string()),
+        size: layer.size.unwrap_or(0),
+        metadata,
+        stats: None,
+        preview: None,
+    }
+}
+
+fn has_extension(file_path: &Path, ext: &str) -> bool {
+    file_path
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or("")
+        .eq_ignore_ascii_case(ext)
+}
+
+/// Returns the (topology, weights) pair of paths for an IR model given either of them.
+fn ir_paths(file_path: &Path) -> (PathBuf, PathBuf) {
+    if has_extension(file_path, "bin") {
+        (file_path.with_extension("xml"), file_path.to_path_buf())
+    } else {
+        (file_path.to_path_buf(), file_path.with_extension("bin"))
+    }
+}
+
+pub(crate) struct OpenVINOHandler;
+
+impl OpenVINOHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for OpenVINOHandler {
+    fn file_type(&self) -> FileType {
+        FileType::OpenVINO
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        // an IR model is always a pair of .xml topology and .bin weights files
+        if has_extension(file_path, "xml") {
+            file_path.with_extension("bin").is_file()
+        } else if has_extension(file_path, "bin") {
+            file_path.with_extension("xml").is_file()
+        } else {
+            false
+        }
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let (xml_path, bin_path) = ir_paths(file_path);
+        let mut paths = vec![xml_path];
+        // models without constants might not have a weights file
+        if bin_path.exists() {
+            paths.push(bin_path);
+        }
+        Ok(paths)
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let (xml_path, bin_path) = ir_paths(file_path);
+
+        let mut inspection = Inspection::default();
+
+        let xml = std::fs::read_to_string(&xml_path)?;
+
+        inspection.file_path = xml_path.canonicalize()?;
+        inspection.header_size = xml.len();
+        inspection.file_size = xml.len() as u64 + bin_path.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let model = parse_ir(&xml)?;
+
+        inspection.file_type = FileType::OpenVINO;
+        inspection.version = format!("IR v{}", model.version);
+
+        if !model.name.is_empty() {
+            inspection
+                .metadata
+                .insert("name".to_string(), model.name.clone());
+        }
+
+        inspection
+            .metadata
+            .insert("layers".to_string(), model.layers.len().to_string());
+
+        let mut layer_types: BTreeMap<&str, usize> = BTreeMap::new();
+        for layer in &model.layers {
+            *layer_types.entry(layer.layer_type.as_str()).or_insert(0) += 1;
+        }
+        inspection.metadata.insert(
+            "layer_types".to_string(),
+            layer_types
+                .iter()
+                .map(|(layer_type, count)| format!("{}({})", layer_type, count))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+
+        if !model.precisions.is_empty() {
+            inspection.metadata.insert(
+                "precisions".to_string(),
+                model
+                    .precisions
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+
+        inspection.metadata.extend(model.rt_info.clone());
+
+        // weights are stored as Const layers referencing a region of the .bin file
+        let constants: Vec<&Layer> = model
+            .layers
+            .iter()
+            .filter(|l| l.layer_type == "Const")
+            .collect();
+
+        inspection.num_tensors = constants.len();
+        inspection.data_size = constants
+            .par_iter()
+            .map(|l| l.size.unwrap_or(0))
+            .sum::<usize>();
+
+        inspection.unique_shapes = constants
+            .par_iter()
+            .map(|l| l.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = constants
+            .par_iter()
+            .filter_map(|l| l.element_type.as_ref().map(|t| t.to_ascii_uppercase()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            inspection.tensors = Some(
+                constants
+                    .par_iter()
+                    .filter(|l| filter.as_ref().is_none_or(|f| l.name.contains(f)))
+                    .map(|l| build_tensor_descriptor(l))
+                    .collect(),
+            );
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_IR: &str = r#"<?xml version="1.0"?>
+<net name="test_model" version="11">
+    <layers>
+        <layer id="0" name="input" type="Parameter" version="opset1">
+            <data shape="1,3,224,224" element_type="f32"/>
+            <output>
+                <port id="0" precision="FP32" names="input">
+                    <dim>1</dim>
+                    <dim>3</dim>
+                    <dim>224</dim>
+                    <dim>224</dim>
+                </port>
+            </output>
+        </layer>
+        <layer id="1" name="conv.weight" type="Const" version="opset1">
+            <data element_type="f16" shape="64,3,7,7" offset="0" size="18816"/>
+            <output>
+                <port id="0" precision="FP16"/>
+            </output>
+            <rt_info>
+                <attribute name="fused_names" version="0" value="conv.weight"/>
+            </rt_info>
+        </layer>
+        <layer id="2" name="conv" type="Convolution" version="opset1">
+            <data strides="2,2" dilations="1,1" pads_begin="3,3" pads_end="3,3" auto_pad="explicit"/>
+        </layer>
+    </layers>
+    <edges>
+        <edge from-layer="0" from-port="0" to-layer="2" to-port="0"/>
+    </edges>
+    <rt_info>
+        <MO_version value="2023.0.0"/>
+        <conversion_parameters>
+            <framework value="onnx"/>
+        </conversion_parameters>
+    </rt_info>
+</net>
+"#;
+
+    #[test]
+    fn test_parse_ir() {
+        let model = parse_ir(TEST_IR).unwrap();
+
+        assert_eq!(model.name, "test_model");
+        assert_eq!(model.version, "11");
+        assert_eq!(model.layers.len(), 3);
+        assert_eq!(
+            model.precisions.iter().cloned().collect::<Vec<_>>(),
+            vec!["FP16", "FP32"]
+        );
+
+        let weight = &model.layers[1];
+        assert_eq!(weight.layer_type, "Const");
+        assert_eq!(weight.shape, vec![64, 3, 7, 7]);
+        assert_eq!(weight.offset, Some(0));
+        assert_eq!(weight.size, Some(18816));
+
+        assert_eq!(model.rt_info.get("MO_version").unwrap(), "2023.0.0");
+        assert_eq!(
+            model
+                .rt_info
+                .get("conversion_parameters.framework")
+                .unwrap(),
+            "onnx"
+        );
+        // layer level rt_info is not part of the model metadata
+        assert!(!model.rt_info.contains_key("attribute"));
+    }
+
+    #[test]
+    fn test_parse_invalid_ir() {
+        assert!(parse_ir("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let xml_path = temp_dir.path().join("model.xml");
+        let bin_path = temp_dir.path().join("model.bin");
+        let handler = OpenVINOHandler::new();
+
+        std::fs::write(&xml_path, TEST_IR).unwrap();
+        // no weights file, just a random xml
+        assert!(!handler.is_handler_for(&xml_path, &Scope::Inspection));
+
+        std::fs::write(&bin_path, b"").unwrap();
+        assert!(handler.is_handler_for(&xml_path, &Scope::Inspection));
+        assert!(handler.is_handler_for(&bin_path, &Scope::Signing));
+
+        let paths = handler.paths_to_sign(&bin_path).unwrap();
+        assert_eq!(paths, vec![xml_path, bin_path]);
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, Shape, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+// environment variable overriding the plugins folder
+const PLUGINS_DIR_VAR: &str = "TMAN_PLUGINS_DIR";
+
+// request written as JSON to the standard input of the plugin
+#[derive(Debug, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request<'a> {
+    // name of the plugin and extensions of the files it handles
+    Describe,
+    Inspect {
+        path: &'a Path,
+        detail: &'a str,
+        filter: Option<&'a str>,
+    },
+    // files of the model, relative to its folder unless absolute
+    PathsToSign {
+        path: &'a Path,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct Description {
+    name: String,
+    extensions: Vec<String>,
+}
+
+// what the plugin knows of the file, tman fills in the rest of the inspection
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PluginInspection {
+    version: String,
+    header_size: usize,
+    num_tensors: usize,
+    data_size: usize,
+    unique_shapes: Vec<Shape>,
+    unique_dtypes: Vec<String>,
+    metadata: Metadata,
+    tensors: Option<Vec<TensorDescriptor>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PathsToSign {
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+// runs the plugin with the request on its standard input, parsing the response from its output
+fn call<T: DeserializeOwned>(command: &Path, request: &Request) -> anyhow::Result<T> {
+    tracing::debug!("running plugin {} with {:?}", command.display(), request);
+    let mut child = Command::new(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("can't run plugin {}: {}", command.display(), e))?;
+
+    let request = serde_json::to_vec(request)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // plugins that don't need the request may exit without reading it
+        if let Err(e) = stdin.write_all(&request) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    let output = child.wait_with_output()?;
+
+    if let Ok(response) = serde_json::from_slice::<ErrorResponse>(&output.stdout) {
+        anyhow::bail!("plugin {}: {}", command.display(), response.error);
+    }
+    if !output.status.success() {
+        anyhow::bail!(
+            "plugin {} failed with {}: {}",
+            command.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| anyhow::anyhow!("invalid response from plugin {}: {}", command.display(), e))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// A handler of a format tman doesn't support, implemented by an external program speaking JSON
+/// over its standard input and output.
+#[derive(Debug, Clone)]
+pub(crate) struct PluginHandler {
+    name: String,
+    command: PathBuf,
+    extensions: Vec<String>,
+}
+
+impl PluginHandler {
+    /// Runs the plugin to get its name and the extensions it handles.
+    pub(crate) fn describe(command: &Path) -> anyhow::Result<Self> {
+        let description: Description = call(command, &Request::Describe)?;
+        Ok(Self {
+            name: description.name,
+            command: command.to_path_buf(),
+            extensions: description
+                .extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+                .collect(),
+        })
+    }
+}
+
+/// Returns the folder plugins are looked for in, TMAN_PLUGINS_DIR if set or else the plugins
+/// folder inside the user configuration folder.
+pub(crate) fn plugins_dir() -> Option<PathBuf> {
+    match std::env::var_os(PLUGINS_DIR_VAR) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dirs::config_dir().map(|path| path.join("tensor-man").join("plugins")),
+    }
+}
+
+/// Returns the plugins of the executables of the folder, sorted by file name. Those that fail to
+/// describe themselves are skipped with a warning.
+pub(crate) fn discover(folder: &Path) -> Vec<PluginHandler> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return vec![];
+    };
+    let mut commands: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_executable(path))
+        .collect();
+    commands.sort();
+
+    commands
+        .iter()
+        .filter_map(|command| match PluginHandler::describe(command) {
+            Ok(plugin) => Some(plugin),
+            Err(e) => {
+                tracing::warn!("ignoring {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the plugins of the plugins folder, which are only run to describe themselves once.
+pub(crate) fn plugins() -> &'static [PluginHandler] {
+    static PLUGINS: OnceLock<Vec<PluginHandler>> = OnceLock::new();
+    PLUGINS.get_or_init(|| {
+        plugins_dir()
+            .map(|folder| discover(&folder))
+            .unwrap_or_default()
+    })
+}
+
+impl Handler for PluginHandler {
+    fn file_type(&self) -> FileType {
+        FileType::Plugin
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        let name = file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        // extensions such as tar.acme are matched too
+        self.extensions
+            .iter()
+            .any(|ext| name.ends_with(&format!(".{}", ext)))
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let response: PathsToSign = call(&self.command, &Request::PathsToSign { path: file_path })?;
+        let base_path = file_path.parent().unwrap_or(Path::new(""));
+        Ok(response
+            .paths
+            .into_iter()
+            .map(|path| base_path.join(path))
+            .collect())
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let full = matches!(detail, DetailLevel::Full);
+        let response: PluginInspection = call(
+            &self.command,
+            &Request::Inspect {
+                path: file_path,
+                detail: if full { "full" } else { "brief" },
+                filter: filter.as_deref(),
+            },
+        )?;
+
+        let mut inspection = Inspection {
+            file_path: file_path.canonicalize()?,
+            file_type: FileType::Plugin,
+            file_size: std::fs::metadata(file_path)?.len(),
+            header_size: response.header_size,
+            version: response.version,
+            num_tensors: response.num_tensors,
+            data_size: response.data_size,
+            unique_shapes: response.unique_shapes,
+            unique_dtypes: response.unique_dtypes,
+            metadata: response.metadata,
+            ..Default::default()
+        };
+        inspection
+            .metadata
+            .insert("plugin".to_string(), self.name.clone());
+
+        if full {
+            // in case the plugin ignored the filter
+            inspection.tensors = Some(
+                response
+                    .tensors
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|tensor| {
+                        filter.as_ref().is_none_or(|f| {
+                            tensor.id.as_ref().is_some_and(|id| id.contains(f.as_str()))
+                        })
+                    })
+                    .collect(),
+            );
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    const PLUGIN: &str = r#"#!/bin/sh
+request=$(cat)
+case "$request" in
+  *'"describe"'*) echo '{"name": "acme", "extensions": [".ACME"]}' ;;
+  *'"inspect"'*) echo '{"version": "2", "num_tensors": 2, "data_size": 8, "unique_dtypes": ["F16"], "metadata": {"vendor": "acme"}, "tensors": [{"id": "w", "shape": [2], "dtype": "F16", "size": 4, "metadata": {}}, {"id": "b", "shape": [2], "dtype": "F16", "size": 4, "metadata": {}}]}' ;;
+  *'"paths_to_sign"'*) echo '{"paths": ["model.acme", "model.weights"]}' ;;
+  *) echo '{"error": "unsupported command"}' ;;
+esac
+"#;
+
+    fn install(folder: &Path, name: &str, script: &str) {
+        let path = folder.join(name);
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        std::fs::create_dir(&plugins_dir).unwrap();
+        install(&plugins_dir, "acme", PLUGIN);
+        install(&plugins_dir, "broken", "#!/bin/sh\nexit 3\n");
+        // not executable
+        std::fs::write(plugins_dir.join("README"), "plugins").unwrap();
+
+        let plugins = discover(&plugins_dir);
+        assert_eq!(plugins.len(), 1);
+        let plugin = &plugins[0];
+        assert_eq!(plugin.name, "acme");
+        assert!(plugin.is_handler_for(Path::new("model.acme"), &Scope::Inspection));
+        assert!(plugin.is_handler_for(Path::new("MODEL.TAR.ACME"), &Scope::Signing));
+        assert!(!plugin.is_handler_for(Path::new("model.safetensors"), &Scope::Inspection));
+
+        let model = dir.path().join("model.acme");
+        std::fs::write(&model, b"acme model").unwrap();
+        let inspection = plugin
+            .inspect(&model, DetailLevel::Full, Some("w".to_string()))
+            .unwrap();
+        assert!(inspection.file_type.is_plugin());
+        assert_eq!(inspection.file_size, 10);
+        assert_eq!(inspection.version, "2");
+        assert_eq!(inspection.num_tensors, 2);
+        assert_eq!(inspection.metadata.get("plugin").unwrap(), "acme");
+        assert_eq!(inspection.metadata.get("vendor").unwrap(), "acme");
+        // the filter is applied even if the plugin ignores it
+        assert_eq!(inspection.tensors.unwrap().len(), 1);
+
+        assert_eq!(
+            plugin.paths_to_sign(&model).unwrap(),
+            vec![
+                dir.path().join("model.acme"),
+                dir.path().join("model.weights")
+            ]
+        );
+
+        let error = call::<Description>(&plugins_dir.join("broken"), &Request::Describe)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("failed with exit status: 3"), "{}", error);
+    }
+}
@@ -0,0 +1,736 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    path::Path,
+    rc::Rc,
+};
+
+use anyhow::anyhow;
+use safetensors::{tensor::TensorView, Dtype};
+use serde::Serialize;
+
+use crate::core::pickle::Reader;
+
+// name inspect.py gives to a file made of a single tensor
+const SINGLE_TENSOR_NAME: &str = "<unamed-single-tensor>";
+
+// keys of the checkpoints nesting the state dict of the model next to the optimizer state
+const STATE_DICT_KEYS: &[&str] = &["state_dict", "model"];
+
+type Shared<T> = Rc<RefCell<T>>;
+
+// a storage of the archive, data/<key>
+#[derive(Debug, Clone)]
+struct Storage {
+    key: String,
+    // class of the storage, such as FloatStorage
+    storage_type: String,
+}
+
+// a view of a storage
+#[derive(Debug, Clone)]
+struct Tensor {
+    storage: Storage,
+    offset: usize,
+    shape: Vec<usize>,
+    stride: Vec<usize>,
+}
+
+// the Python objects the state dicts are made of, instances of other classes are never rebuilt
+#[derive(Debug, Clone)]
+enum Object {
+    Mark,
+    None,
+    Int(i64),
+    String(String),
+    // the values of the other literals are never used
+    Bool,
+    Float,
+    Bytes,
+    Tuple(Vec<Object>),
+    List(Shared<Vec<Object>>),
+    Dict(Shared<Vec<(Object, Object)>>),
+    // a module attribute, module.name
+    Global(String),
+    Storage(Storage),
+    Tensor(Tensor),
+    // an instance of a class, by class name
+    Opaque(String),
+}
+
+impl Object {
+    fn dict() -> Self {
+        Object::Dict(Rc::new(RefCell::new(vec![])))
+    }
+
+    fn list(items: Vec<Object>) -> Self {
+        Object::List(Rc::new(RefCell::new(items)))
+    }
+
+    // the Python type of the object
+    fn kind(&self) -> String {
+        match self {
+            Object::Mark => "mark",
+            Object::None => "None",
+            Object::Bool => "bool",
+            Object::Int(_) => "int",
+            Object::Float => "float",
+            Object::String(_) => "str",
+            Object::Bytes => "bytes",
+            Object::Tuple(_) => "tuple",
+            Object::List(_) => "list",
+            Object::Dict(_) => "dict",
+            Object::Global(name) => name,
+            Object::Storage(storage) => &storage.storage_type,
+            Object::Tensor(_) => "tensor",
+            Object::Opaque(name) => name,
+        }
+        .to_string()
+    }
+
+    fn usize(&self) -> anyhow::Result<usize> {
+        match self {
+            Object::Int(value) => Ok(usize::try_from(*value)?),
+            other => Err(anyhow!("expected an int, got a {}", other.kind())),
+        }
+    }
+
+    fn usizes(&self) -> anyhow::Result<Vec<usize>> {
+        match self {
+            Object::Tuple(items) => items.iter().map(Object::usize).collect(),
+            Object::List(items) => items.borrow().iter().map(Object::usize).collect(),
+            other => Err(anyhow!("expected a tuple, got a {}", other.kind())),
+        }
+    }
+}
+
+// two's complement little endian integer of LONG1 and LONG4
+fn long(bytes: &[u8]) -> anyhow::Result<i64> {
+    if bytes.len() > 8 {
+        return Err(anyhow!("integer of {} bytes is too large", bytes.len()));
+    }
+    let fill = if bytes.last().is_some_and(|byte| byte & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut value = [fill; 8];
+    value[..bytes.len()].copy_from_slice(bytes);
+    Ok(i64::from_le_bytes(value))
+}
+
+// resolves the persistent id ('storage', storage_type, key, location, numel)
+fn persistent_load(pid: Object) -> anyhow::Result<Object> {
+    match pid {
+        Object::Tuple(items) => match items.as_slice() {
+            [Object::String(tag), Object::Global(storage_type), Object::String(key), ..]
+                if tag == "storage" =>
+            {
+                Ok(Object::Storage(Storage {
+                    key: key.clone(),
+                    storage_type: storage_type
+                        .rsplit('.')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                }))
+            }
+            _ => Err(anyhow!("unsupported persistent id")),
+        },
+        other => Err(anyhow!(
+            "unsupported persistent id of type {}",
+            other.kind()
+        )),
+    }
+}
+
+// calls the function with the arguments, only for the functions rebuilding tensors and dicts
+fn reduce(callable: Object, args: Object) -> anyhow::Result<Object> {
+    let Object::Global(name) = callable else {
+        return Ok(Object::Opaque(callable.kind()));
+    };
+    let args = match args {
+        Object::Tuple(args) => args,
+        other => return Err(anyhow!("arguments of {} are a {}", name, other.kind())),
+    };
+
+    match name.as_str() {
+        "torch._utils._rebuild_tensor" | "torch._utils._rebuild_tensor_v2" => match args.as_slice()
+        {
+            [Object::Storage(storage), offset, shape, stride, ..] => Ok(Object::Tensor(Tensor {
+                storage: storage.clone(),
+                offset: offset.usize()?,
+                shape: shape.usizes()?,
+                stride: stride.usizes()?,
+            })),
+            _ => Err(anyhow!("invalid arguments of {}", name)),
+        },
+        // the parameters are the tensors themselves
+        "torch._utils._rebuild_parameter"
+        | "torch._utils._rebuild_parameter_with_state"
+        | "torch.nn.parameter.Parameter" => args
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("invalid arguments of {}", name)),
+        // subclasses of tensors, (function, type, arguments, state)
+        "torch._tensor._rebuild_from_type_v2" => match <[Object; 4]>::try_from(args) {
+            Ok([function, _, args, _]) => reduce(function, args),
+            Err(_) => Err(anyhow!("invalid arguments of {}", name)),
+        },
+        "collections.OrderedDict" | "builtins.dict" => Ok(Object::dict()),
+        _ => Ok(Object::Opaque(name)),
+    }
+}
+
+// pops the objects pushed after the last mark
+fn pop_mark(stack: &mut Vec<Object>) -> anyhow::Result<Vec<Object>> {
+    let mark = stack
+        .iter()
+        .rposition(|object| matches!(object, Object::Mark))
+        .ok_or_else(|| anyhow!("mark not found"))?;
+    let items = stack.split_off(mark + 1);
+    stack.pop();
+    Ok(items)
+}
+
+fn set_items(dict: &Object, items: Vec<Object>) -> anyhow::Result<()> {
+    let Object::Dict(dict) = dict else {
+        return Err(anyhow!("can't set the items of a {}", dict.kind()));
+    };
+    let mut items = items.into_iter();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        dict.borrow_mut().push((key, value));
+    }
+    Ok(())
+}
+
+fn append(list: &Object, items: Vec<Object>) -> anyhow::Result<()> {
+    match list {
+        Object::List(list) => {
+            list.borrow_mut().extend(items);
+            Ok(())
+        }
+        // the items of other classes are dropped with them
+        Object::Opaque(_) => Ok(()),
+        other => Err(anyhow!("can't append to a {}", other.kind())),
+    }
+}
+
+/// Rebuilds the object of the pickle without running any code: the dicts, tensors and Python
+/// literals are rebuilt, and any other object is replaced by its class name.
+fn load(data: &[u8]) -> anyhow::Result<Object> {
+    let mut reader = Reader::new(data, 0);
+    let mut stack: Vec<Object> = vec![];
+    let mut memo: HashMap<u64, Object> = HashMap::new();
+    let pop = |stack: &mut Vec<Object>| stack.pop().ok_or_else(|| anyhow!("empty stack"));
+
+    loop {
+        let opcode_offset = reader.offset;
+        let opcode = reader.bytes(1)?[0];
+        match opcode {
+            // STOP
+            b'.' => return pop(&mut stack),
+            // PROTO
+            0x80 => {
+                reader.bytes(1)?;
+            }
+            // FRAME
+            0x95 => {
+                reader.bytes(8)?;
+            }
+            // MARK
+            b'(' => stack.push(Object::Mark),
+            // NONE, NEWTRUE, NEWFALSE
+            b'N' => stack.push(Object::None),
+            0x88 | 0x89 => stack.push(Object::Bool),
+            // BININT, BININT1, BININT2
+            b'J' => stack.push(Object::Int(reader.uint(4)? as u32 as i32 as i64)),
+            b'K' => stack.push(Object::Int(reader.uint(1)? as i64)),
+            b'M' => stack.push(Object::Int(reader.uint(2)? as i64)),
+            // LONG1, LONG4
+            0x8a => stack.push(Object::Int(long(reader.sized(1)?)?)),
+            0x8b => stack.push(Object::Int(long(reader.sized(4)?)?)),
+            // INT, with 00 and 01 for the booleans of protocol 0
+            b'I' => stack.push(match reader.line()?.as_str() {
+                "00" | "01" => Object::Bool,
+                value => Object::Int(value.parse()?),
+            }),
+            // LONG
+            b'L' => stack.push(Object::Int(reader.line()?.trim_end_matches('L').parse()?)),
+            // BINFLOAT, FLOAT
+            b'G' => {
+                reader.bytes(8)?;
+                stack.push(Object::Float);
+            }
+            b'F' => {
+                reader.line()?;
+                stack.push(Object::Float);
+            }
+            // SHORT_BINUNICODE, SHORT_BINSTRING
+            0x8c | b'U' => stack.push(Object::String(
+                String::from_utf8_lossy(reader.sized(1)?).to_string(),
+            )),
+            // BINUNICODE, BINSTRING
+            b'X' | b'T' => stack.push(Object::String(
+                String::from_utf8_lossy(reader.sized(4)?).to_string(),
+            )),
+            // BINUNICODE8
+            0x8d => stack.push(Object::String(
+                String::from_utf8_lossy(reader.sized(8)?).to_string(),
+            )),
+            // UNICODE, STRING
+            b'V' | b'S' => {
+                let line = reader.line()?;
+                stack.push(Object::String(line.trim_matches(['\'', '"']).to_string()));
+            }
+            // SHORT_BINBYTES, BINBYTES, BINBYTES8
+            b'C' | b'B' | 0x8e => {
+                reader.sized(match opcode {
+                    b'C' => 1,
+                    b'B' => 4,
+                    _ => 8,
+                })?;
+                stack.push(Object::Bytes);
+            }
+            // EMPTY_TUPLE, TUPLE, TUPLE1, TUPLE2, TUPLE3
+            b')' => stack.push(Object::Tuple(vec![])),
+            b't' => {
+                let items = pop_mark(&mut stack)?;
+                stack.push(Object::Tuple(items));
+            }
+            0x85..=0x87 => {
+                let size = (opcode - 0x84) as usize;
+                if stack.len() < size {
+                    return Err(anyhow!("empty stack"));
+                }
+                let items = stack.split_off(stack.len() - size);
+                stack.push(Object::Tuple(items));
+            }
+            // EMPTY_LIST, LIST, EMPTY_SET
+            b']' | 0x8f => stack.push(Object::list(vec![])),
+            b'l' => {
+                let items = pop_mark(&mut stack)?;
+                stack.push(Object::list(items));
+            }
+            // EMPTY_DICT, DICT
+            b'}' => stack.push(Object::dict()),
+            b'd' => {
+                let items = pop_mark(&mut stack)?;
+                let dict = Object::dict();
+                set_items(&dict, items)?;
+                stack.push(dict);
+            }
+            // APPEND
+            b'a' => {
+                let item = pop(&mut stack)?;
+                append(
+                    stack.last().ok_or_else(|| anyhow!("empty stack"))?,
+                    vec![item],
+                )?;
+            }
+            // APPENDS, ADDITEMS
+            b'e' | 0x90 => {
+                let items = pop_mark(&mut stack)?;
+                append(stack.last().ok_or_else(|| anyhow!("empty stack"))?, items)?;
+            }
+            // SETITEM
+            b's' => {
+                let value = pop(&mut stack)?;
+                let key = pop(&mut stack)?;
+                set_items(
+                    stack.last().ok_or_else(|| anyhow!("empty stack"))?,
+                    vec![key, value],
+                )?;
+            }
+            // SETITEMS
+            b'u' => {
+                let items = pop_mark(&mut stack)?;
+                set_items(stack.last().ok_or_else(|| anyhow!("empty stack"))?, items)?;
+            }
+            // POP, POP_MARK, DUP
+            b'0' => {
+                pop(&mut stack)?;
+            }
+            b'1' => {
+                pop_mark(&mut stack)?;
+            }
+            b'2' => {
+                let top = stack.last().ok_or_else(|| anyhow!("empty stack"))?.clone();
+                stack.push(top);
+            }
+            // PUT, BINPUT, LONG_BINPUT, MEMOIZE
+            b'p' | b'q' | b'r' | 0x94 => {
+                let index = match opcode {
+                    b'p' => reader.line()?.parse()?,
+                    b'q' => reader.uint(1)?,
+                    b'r' => reader.uint(4)?,
+                    _ => memo.len() as u64,
+                };
+                let top = stack.last().ok_or_else(|| anyhow!("empty stack"))?.clone();
+                memo.insert(index, top);
+            }
+            // GET, BINGET, LONG_BINGET
+            b'g' | b'h' | b'j' => {
+                let index: u64 = match opcode {
+                    b'g' => reader.line()?.parse()?,
+                    b'h' => reader.uint(1)?,
+                    _ => reader.uint(4)?,
+                };
+                let object = memo
+                    .get(&index)
+                    .ok_or_else(|| anyhow!("memo {} not found", index))?;
+                stack.push(object.clone());
+            }
+            // GLOBAL
+            b'c' => {
+                let module = reader.line()?;
+                let name = reader.line()?;
+                stack.push(Object::Global(format!("{}.{}", module, name)));
+            }
+            // STACK_GLOBAL
+            0x93 => {
+                let name = pop(&mut stack)?;
+                let module = pop(&mut stack)?;
+                match (module, name) {
+                    (Object::String(module), Object::String(name)) => {
+                        stack.push(Object::Global(format!("{}.{}", module, name)))
+                    }
+                    _ => return Err(anyhow!("computed global at offset {}", opcode_offset)),
+                }
+            }
+            // BINPERSID, PERSID
+            b'Q' => {
+                let pid = pop(&mut stack)?;
+                stack.push(persistent_load(pid)?);
+            }
+            b'P' => return Err(anyhow!("unsupported persistent id {}", reader.line()?)),
+            // REDUCE
+            b'R' => {
+                let args = pop(&mut stack)?;
+                let callable = pop(&mut stack)?;
+                stack.push(reduce(callable, args)?);
+            }
+            // NEWOBJ
+            0x81 => {
+                pop(&mut stack)?;
+                let class = pop(&mut stack)?;
+                stack.push(Object::Opaque(class.kind()));
+            }
+            // BUILD, the state of the object is dropped, such as the _metadata of the state dicts
+            b'b' => {
+                pop(&mut stack)?;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "unsupported opcode 0x{:02x} at offset {}",
+                    opcode,
+                    opcode_offset
+                ))
+            }
+        }
+    }
+}
+
+/// An object of the PyTorch file that is not a tensor and has been dropped.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Dropped {
+    pub name: String,
+    pub kind: String,
+}
+
+// the key of a dict as a tensor name
+fn key_name(key: &Object) -> String {
+    match key {
+        Object::String(key) => key.clone(),
+        Object::Int(key) => key.to_string(),
+        other => format!("<{}>", other.kind()),
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+// collects the tensors of the nested dicts with their dotted names, and the other objects
+fn flatten(
+    prefix: &str,
+    object: &Object,
+    tensors: &mut Vec<(String, Tensor)>,
+    dropped: &mut Vec<Dropped>,
+) {
+    match object {
+        Object::Tensor(tensor) => tensors.push((prefix.to_string(), tensor.clone())),
+        Object::Dict(items) => {
+            for (key, value) in items.borrow().iter() {
+                flatten(&join(prefix, &key_name(key)), value, tensors, dropped);
+            }
+        }
+        other => dropped.push(Dropped {
+            name: prefix.to_string(),
+            kind: other.kind(),
+        }),
+    }
+}
+
+fn dtype_of(storage_type: &str) -> Option<Dtype> {
+    Some(match storage_type {
+        "DoubleStorage" => Dtype::F64,
+        "FloatStorage" => Dtype::F32,
+        "HalfStorage" => Dtype::F16,
+        "BFloat16Storage" => Dtype::BF16,
+        "LongStorage" => Dtype::I64,
+        "IntStorage" => Dtype::I32,
+        "ShortStorage" => Dtype::I16,
+        "CharStorage" => Dtype::I8,
+        "ByteStorage" => Dtype::U8,
+        "BoolStorage" => Dtype::BOOL,
+        _ => return None,
+    })
+}
+
+// copies the elements of the view in row major order
+fn contiguous(storage: &[u8], tensor: &Tensor, element_size: usize) -> anyhow::Result<Vec<u8>> {
+    let count: usize = tensor.shape.iter().product();
+    if count == 0 {
+        return Ok(vec![]);
+    }
+    if tensor.shape.len() != tensor.stride.len() {
+        return Err(anyhow!("shape and stride have different lengths"));
+    }
+    let last = tensor.offset
+        + tensor
+            .shape
+            .iter()
+            .zip(&tensor.stride)
+            .map(|(dim, stride)| (dim - 1) * stride)
+            .sum::<usize>();
+    if (last + 1) * element_size > storage.len() {
+        return Err(anyhow!(
+            "view out of the bounds of storage {}",
+            tensor.storage.key
+        ));
+    }
+
+    let mut data = Vec::with_capacity(count * element_size);
+    let mut index = vec![0; tensor.shape.len()];
+    for _ in 0..count {
+        let offset = tensor.offset
+            + index
+                .iter()
+                .zip(&tensor.stride)
+                .map(|(i, stride)| i * stride)
+                .sum::<usize>();
+        data.extend_from_slice(&storage[offset * element_size..(offset + 1) * element_size]);
+        // next index, the last dimension first
+        for dim in (0..index.len()).rev() {
+            index[dim] += 1;
+            if index[dim] < tensor.shape[dim] {
+                break;
+            }
+            index[dim] = 0;
+        }
+    }
+    Ok(data)
+}
+
+/// Converts the state dict of a PyTorch archive to a safetensors file without unpickling it with
+/// Python: only the tensors are kept, the other objects are returned. The nested dicts are
+/// flattened with dotted names, and the model of checkpoints with a state_dict or model entry is
+/// converted alone.
+pub(crate) fn convert(input: &Path, output: &Path) -> anyhow::Result<(usize, Vec<Dropped>)> {
+    let mut magic = [0u8; 4];
+    std::fs::File::open(input)?.read_exact(&mut magic)?;
+    if magic != *b"PK\x03\x04" {
+        return Err(anyhow!(
+            "only the zip based format of PyTorch 1.6 and later can be converted"
+        ));
+    }
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(input)?)?;
+    let pickle_name = archive
+        .file_names()
+        .find(|name| *name == "data.pkl" || name.ends_with("/data.pkl"))
+        .ok_or_else(|| anyhow!("data.pkl not found in the archive"))?
+        .to_string();
+    let prefix = pickle_name.trim_end_matches("data.pkl").to_string();
+    let mut data = vec![];
+    archive.by_name(&pickle_name)?.read_to_end(&mut data)?;
+    let root = load(&data).map_err(|e| anyhow!("invalid pickle {}: {}", pickle_name, e))?;
+
+    let mut tensors = vec![];
+    let mut dropped = vec![];
+    match &root {
+        Object::Tensor(tensor) => tensors.push((SINGLE_TENSOR_NAME.to_string(), tensor.clone())),
+        Object::Dict(items) => {
+            let state_dict = items.borrow().iter().find_map(|(key, value)| {
+                (matches!(value, Object::Dict(_))
+                    && STATE_DICT_KEYS.contains(&key_name(key).as_str()))
+                .then(|| (key_name(key), value.clone()))
+            });
+            match state_dict {
+                Some((name, state_dict)) => {
+                    flatten("", &state_dict, &mut tensors, &mut dropped);
+                    for (key, value) in items.borrow().iter() {
+                        if key_name(key) != name {
+                            dropped.push(Dropped {
+                                name: key_name(key),
+                                kind: value.kind(),
+                            });
+                        }
+                    }
+                }
+                None => flatten("", &root, &mut tensors, &mut dropped),
+            }
+        }
+        other => {
+            return Err(anyhow!(
+                "the file contains a {}, not a state dict",
+                other.kind()
+            ))
+        }
+    }
+
+    let mut storages: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut converted: BTreeMap<String, (Dtype, Vec<usize>, Vec<u8>)> = BTreeMap::new();
+    for (name, tensor) in tensors {
+        let Some(dtype) = dtype_of(&tensor.storage.storage_type) else {
+            dropped.push(Dropped {
+                name,
+                kind: format!("tensor of {}", tensor.storage.storage_type),
+            });
+            continue;
+        };
+        if !storages.contains_key(&tensor.storage.key) {
+            let mut data = vec![];
+            archive
+                .by_name(&format!("{}data/{}", prefix, tensor.storage.key))?
+                .read_to_end(&mut data)?;
+            storages.insert(tensor.storage.key.clone(), data);
+        }
+        let data = contiguous(&storages[&tensor.storage.key], &tensor, dtype.size())
+            .map_err(|e| anyhow!("invalid tensor {}: {}", name, e))?;
+        converted.insert(name, (dtype, tensor.shape, data));
+    }
+
+    let views = converted
+        .iter()
+        .map(|(name, (dtype, shape, data))| {
+            TensorView::new(*dtype, shape.clone(), data)
+                .map(|view| (name.as_str(), view))
+                .map_err(|e| anyhow!("invalid tensor {}: {:?}", name, e))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let metadata = Some(HashMap::from([("format".to_string(), "pt".to_string())]));
+    safetensors::serialize_to_file(views, &metadata, output)
+        .map_err(|e| anyhow!("can't write {}: {:?}", output.display(), e))?;
+
+    Ok((converted.len(), dropped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        handlers::{safetensors::SafeTensorsHandler, Handler},
+        DetailLevel,
+    };
+
+    fn string(data: &mut Vec<u8>, value: &str) {
+        data.push(0x8c);
+        data.push(value.len() as u8);
+        data.extend(value.as_bytes());
+    }
+
+    // _rebuild_tensor_v2 of the storage, with the offset, shape and stride
+    fn tensor(data: &mut Vec<u8>, key: &str, offset: u8, shape: [u8; 2], stride: [u8; 2]) {
+        data.extend(b"ctorch._utils\n_rebuild_tensor_v2\n(");
+        data.push(b'(');
+        string(data, "storage");
+        data.extend(b"ctorch\nFloatStorage\n");
+        string(data, key);
+        string(data, "cpu");
+        data.extend([b'K', 6, b't', b'Q', b'K', offset]);
+        data.extend([b'K', shape[0], b'K', shape[1], 0x86]);
+        data.extend([b'K', stride[0], b'K', stride[1], 0x86]);
+        data.extend(b"\x89ccollections\nOrderedDict\n)Rtq\x00R");
+    }
+
+    #[test]
+    fn test_convert() {
+        // {"state_dict": OrderedDict(w=..., wt=w.T, step=1), "optimizer": Adam(), "epoch": 3}
+        let mut data = vec![0x80, 2, b'}', b'('];
+        string(&mut data, "state_dict");
+        data.extend(b"ccollections\nOrderedDict\n)R(");
+        string(&mut data, "w");
+        tensor(&mut data, "0", 0, [2, 3], [3, 1]);
+        string(&mut data, "wt");
+        tensor(&mut data, "0", 0, [3, 2], [1, 3]);
+        string(&mut data, "step");
+        data.extend([b'K', 1, b'u', b'}', b'b']);
+        string(&mut data, "optimizer");
+        data.extend(b"ctorch.optim.adam\nAdam\n)\x81");
+        string(&mut data, "epoch");
+        data.extend([b'K', 3, b'u', b'.']);
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("model.pt");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&input).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("model/data.pkl", options).unwrap();
+        std::io::Write::write_all(&mut writer, &data).unwrap();
+        writer.start_file("model/data/0", options).unwrap();
+        for value in 0..6 {
+            std::io::Write::write_all(&mut writer, &(value as f32).to_le_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let output = dir.path().join("model.safetensors");
+        let (converted, dropped) = convert(&input, &output).unwrap();
+        assert_eq!(converted, 2);
+        assert_eq!(
+            dropped,
+            vec![
+                Dropped {
+                    name: "step".to_string(),
+                    kind: "int".to_string()
+                },
+                Dropped {
+                    name: "optimizer".to_string(),
+                    kind: "torch.optim.adam.Adam".to_string()
+                },
+                Dropped {
+                    name: "epoch".to_string(),
+                    kind: "int".to_string()
+                },
+            ]
+        );
+
+        let handler = SafeTensorsHandler::new();
+        let inspection = handler.inspect(&output, DetailLevel::Full, None).unwrap();
+        assert_eq!(inspection.metadata.get("format").unwrap(), "pt");
+        let values: Vec<(String, Vec<f64>)> = handler
+            .tensor_data(&output, None)
+            .unwrap()
+            .iter()
+            .map(|tensor| (tensor.id.clone(), tensor.values().collect()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("w".to_string(), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]),
+                ("wt".to_string(), vec![0.0, 3.0, 1.0, 4.0, 2.0, 5.0]),
+            ]
+        );
+
+        // legacy files aren't zip archives
+        let legacy = dir.path().join("legacy.pt");
+        std::fs::write(&legacy, b"\x80\x02\x8a\x0al\xfc\x9cF\xf9 j\xa8P\x19.").unwrap();
+        assert!(convert(&legacy, &output).is_err());
+    }
+}
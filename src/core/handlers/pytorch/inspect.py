@@ -4,6 +4,90 @@ import os
 import argparse
 import numpy as np
 
+# longer argument values are truncated
+MAX_VALUE_SIZE = 80
+
+
+def short_repr(value):
+    value = repr(value)
+    return value if len(value) <= MAX_VALUE_SIZE else value[: MAX_VALUE_SIZE - 3] + "..."
+
+
+def target_name(target):
+    if isinstance(target, str):
+        return target
+    return getattr(target, "__name__", short_repr(target))
+
+
+def tensor_shape(tensor):
+    dtype = str(tensor.dtype).replace("torch.", "")
+    return f"{dtype}{list(tensor.shape)}"
+
+
+def trace(file_path):
+    """Traces the module of the file with torch.fx into a node-link graph."""
+    import torch.fx
+
+    # the whole pickle is loaded to get the module, running its code, hence the container
+    model = torch.load(file_path, weights_only=False, map_location=torch.device("cpu"))
+    if isinstance(model, dict) and isinstance(model.get("model"), torch.nn.Module):
+        model = model["model"]
+    if not isinstance(model, torch.nn.Module):
+        return {"nodes": None, "links": [], "reason": "no nn.Module in the file"}
+
+    try:
+        traced = torch.fx.symbolic_trace(model)
+    except Exception as e:
+        return {"nodes": None, "links": [], "reason": f"can't trace the module: {e}"}
+
+    modules = dict(traced.named_modules())
+    nodes = []
+    links = []
+    for node in traced.graph.nodes:
+        attributes = []
+        module_type = None
+        shape = None
+        if node.op == "call_module":
+            module = modules[node.target]
+            module_type = type(module).__name__
+            if module.extra_repr():
+                attributes.append(["module", short_repr(module.extra_repr())[1:-1]])
+            for name, tensor in module.named_parameters(recurse=False):
+                attributes.append([name, tensor_shape(tensor)])
+            for name, tensor in module.named_buffers(recurse=False):
+                attributes.append([name, tensor_shape(tensor)])
+        elif node.op == "get_attr":
+            value = traced
+            for part in node.target.split("."):
+                value = getattr(value, part, None)
+            if isinstance(value, torch.Tensor):
+                shape = tensor_shape(value)
+
+        # the inputs are links, the other arguments are constants of the node
+        for index, arg in enumerate(node.args):
+            if not isinstance(arg, torch.fx.Node) and node.op != "output":
+                attributes.append([f"arg{index}", short_repr(arg)])
+        for name, arg in node.kwargs.items():
+            if not isinstance(arg, torch.fx.Node):
+                attributes.append([name, short_repr(arg)])
+
+        nodes.append(
+            {
+                "id": node.name,
+                "op": node.op,
+                "target": target_name(node.target),
+                "module_type": module_type,
+                "attributes": attributes,
+                "shape": shape,
+            }
+        )
+        links.extend(
+            {"source": input_node.name, "target": node.name}
+            for input_node in node.all_input_nodes
+        )
+
+    return {"nodes": nodes, "links": links, "reason": None}
+
 
 def main():
     parser = argparse.ArgumentParser(description="Inspect PyTorch model files")
@@ -12,12 +96,19 @@ def main():
         "--detailed", action="store_true", help="Show detailed tensor information"
     )
     parser.add_argument("--filter", help="Filter tensors by name pattern")
+    parser.add_argument(
+        "--graph", action="store_true", help="Trace the module with torch.fx"
+    )
 
     args = parser.parse_args()
 
     file_path = os.path.abspath(args.file)
     file_size = os.path.getsize(file_path)
 
+    if args.graph:
+        print(json.dumps(trace(file_path)))
+        return
+
     try:
         model = torch.load(
             file_path, weights_only=True, mmap=True, map_location=torch.device("cpu")
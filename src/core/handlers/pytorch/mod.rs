@@ -1,12 +1,140 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
 
-use crate::{
-    cli::DetailLevel,
-    core::{docker, FileType, Inspection},
+use crate::core::{
+    docker,
+    graph::{module_tree_dot, Graph, GraphNode, GraphTensor},
+    DetailLevel, FileType, Inspection,
 };
 
 use super::{Handler, Scope};
 
+mod convert;
+
+pub(crate) use convert::convert;
+
+// a node of the torch.fx graph of the module, as printed by the script
+#[derive(Debug, Deserialize)]
+struct FxNode {
+    id: String,
+    // placeholder, get_attr, call_function, call_module, call_method or output
+    op: String,
+    target: String,
+    module_type: Option<String>,
+    attributes: Vec<(String, String)>,
+    shape: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FxLink {
+    source: String,
+    target: String,
+}
+
+// the node-link graph of the traced module, or why there's none
+#[derive(Debug, Deserialize)]
+struct FxGraph {
+    nodes: Option<Vec<FxNode>>,
+    links: Vec<FxLink>,
+    reason: Option<String>,
+}
+
+impl FxGraph {
+    fn into_graph(self, name: &str) -> Result<Graph, String> {
+        let Some(fx_nodes) = self.nodes else {
+            return Err(self
+                .reason
+                .unwrap_or("the module can't be traced".to_string()));
+        };
+
+        let shapes: HashMap<&str, Option<String>> = fx_nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.shape.clone()))
+            .collect();
+        let tensor = |id: &str| GraphTensor {
+            name: id.to_string(),
+            shape: shapes.get(id).cloned().flatten(),
+        };
+        let mut inputs: HashMap<&str, Vec<GraphTensor>> = HashMap::new();
+        for link in &self.links {
+            inputs
+                .entry(link.target.as_str())
+                .or_default()
+                .push(tensor(&link.source));
+        }
+
+        let nodes = fx_nodes
+            .iter()
+            .map(|node| {
+                let op_type = match node.op.as_str() {
+                    "placeholder" => "Input".to_string(),
+                    "output" => "Output".to_string(),
+                    "get_attr" => "Attribute".to_string(),
+                    _ => node.module_type.clone().unwrap_or(node.target.clone()),
+                };
+                // the path of the module or attribute in the model
+                let mut attributes = vec![];
+                if matches!(node.op.as_str(), "call_module" | "get_attr") {
+                    attributes.push(("target".to_string(), node.target.clone()));
+                }
+                attributes.extend(node.attributes.iter().cloned());
+
+                GraphNode {
+                    name: node.id.clone(),
+                    op_type,
+                    attributes,
+                    inputs: inputs.remove(node.id.as_str()).unwrap_or_default(),
+                    outputs: if node.op == "output" {
+                        vec![]
+                    } else {
+                        vec![tensor(&node.id)]
+                    },
+                }
+            })
+            .collect();
+
+        Ok(Graph {
+            name: name.to_string(),
+            nodes,
+        })
+    }
+}
+
+fn inspector() -> anyhow::Result<docker::Inspector> {
+    if !docker::docker_exists() {
+        return Err(anyhow::anyhow!(
+            "docker is required to inspect pytorch models, make sure the docker binary is in $PATH and that /var/run/docker.sock is shared from the host if you are running tensor-man itself inside a container."
+        ));
+    }
+
+    Ok(docker::Inspector::new(
+        include_str!("inspect.Dockerfile"),
+        include_str!("inspect.py"),
+        include_str!("inspect.requirements"),
+    ))
+}
+
+fn model_name(file_path: &Path) -> String {
+    file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// traces the module of the file with torch.fx, in the container as its code gets to run
+fn trace(file_path: &Path) -> anyhow::Result<FxGraph> {
+    inspector()?.run_script(
+        file_path,
+        vec![],
+        vec!["--graph".to_string()],
+        "tracing in container",
+    )
+}
+
 pub(crate) struct PyTorchHandler;
 
 impl PyTorchHandler {
@@ -53,18 +181,29 @@ impl Handler for PyTorchHandler {
         detail: DetailLevel,
         filter: Option<String>,
     ) -> anyhow::Result<Inspection> {
-        if !docker::docker_exists() {
-            return Err(anyhow::anyhow!(
-                "docker is required to inspect pytorch models, make sure the docker binary is in $PATH and that /var/run/docker.sock is shared from the host if you are running tensor-man itself inside a container."
-            ));
-        }
+        inspector()?.run(file_path, vec![], detail, filter)
+    }
 
-        docker::Inspector::new(
-            include_str!("inspect.Dockerfile"),
-            include_str!("inspect.py"),
-            include_str!("inspect.requirements"),
-        )
-        .run(file_path, vec![], detail, filter)
+    fn create_graph(&self, file_path: &Path, output_path: &Path) -> anyhow::Result<()> {
+        let name = model_name(file_path);
+        let dot_string = match trace(file_path)?.into_graph(&name) {
+            Ok(graph) => graph.to_dot()?,
+            // checkpoints with only the weights still have the hierarchy of their modules
+            Err(reason) => {
+                tracing::info!("{}, graphing the module hierarchy of the weights", reason);
+                let inspection = self.inspect(file_path, DetailLevel::Full, None)?;
+                module_tree_dot(&name, inspection.tensors.as_deref().unwrap_or_default())?
+            }
+        };
+
+        std::fs::write(output_path, dot_string)
+            .map_err(|e| anyhow::anyhow!("failed to write dot string to output path: {:?}", e))
+    }
+
+    fn graph(&self, file_path: &Path) -> anyhow::Result<Graph> {
+        trace(file_path)?
+            .into_graph(&model_name(file_path))
+            .map_err(|reason| anyhow::anyhow!("can't graph {}: {}", file_path.display(), reason))
     }
 }
 
@@ -111,4 +250,59 @@ mod tests {
         assert!(!handler.is_handler_for(Path::new("pytorch.txt"), &Scope::Inspection));
         assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
     }
+
+    #[test]
+    fn test_fx_graph() {
+        // as printed by the script for a convolution followed by a ReLU
+        let fx: FxGraph = serde_json::from_str(
+            r#"{
+                "nodes": [
+                    {"id": "x", "op": "placeholder", "target": "x", "module_type": null, "attributes": [], "shape": null},
+                    {"id": "conv", "op": "call_module", "target": "conv", "module_type": "Conv2d",
+                     "attributes": [["module", "3, 8, kernel_size=(3, 3), stride=(2, 2)"], ["weight", "float32[8, 3, 3, 3]"]], "shape": null},
+                    {"id": "scale", "op": "get_attr", "target": "scale", "module_type": null, "attributes": [], "shape": "float32[8]"},
+                    {"id": "mul", "op": "call_function", "target": "mul", "module_type": null, "attributes": [], "shape": null},
+                    {"id": "relu", "op": "call_function", "target": "relu", "module_type": null, "attributes": [["inplace", "False"]], "shape": null},
+                    {"id": "output", "op": "output", "target": "output", "module_type": null, "attributes": [], "shape": null}
+                ],
+                "links": [
+                    {"source": "x", "target": "conv"},
+                    {"source": "conv", "target": "mul"},
+                    {"source": "scale", "target": "mul"},
+                    {"source": "mul", "target": "relu"},
+                    {"source": "relu", "target": "output"}
+                ],
+                "reason": null
+            }"#,
+        )
+        .unwrap();
+
+        let graph = fx.into_graph("model").unwrap();
+        let op_types: Vec<_> = graph
+            .nodes
+            .iter()
+            .map(|node| node.op_type.as_str())
+            .collect();
+        assert_eq!(
+            op_types,
+            vec!["Input", "Conv2d", "Attribute", "mul", "relu", "Output"]
+        );
+        assert_eq!(graph.nodes[1].attributes[0].1, "conv");
+
+        let dot = graph.to_dot().unwrap();
+        assert!(dot.contains(
+            r#""n1"[label="Conv2d\nconv\ntarget=conv\nmodule=3, 8, kernel_size=(3, 3), stride=(2, 2)\nweight=float32[8, 3, 3, 3]"]"#
+        ));
+        assert!(dot.contains(r#""n2" -> "n3"[label="float32[8]"]"#));
+        assert!(dot.contains(r#""n4" -> "n5"[label=""]"#));
+
+        let weights_only: FxGraph = serde_json::from_str(
+            r#"{"nodes": null, "links": [], "reason": "no nn.Module in the file"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            weights_only.into_graph("model").unwrap_err(),
+            "no nn.Module in the file"
+        );
+    }
 }
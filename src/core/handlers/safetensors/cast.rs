@@ -0,0 +1,228 @@
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use regex::Regex;
+use safetensors::{tensor::TensorInfo, Dtype};
+use serde::Serialize;
+
+use super::{element_type, read_header, serialize_header, take_signature, METADATA_KEY};
+use crate::core::data::{f32_to_bf16, f32_to_f16};
+
+/// Floating point data type the tensors are cast to.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum CastType {
+    F16,
+    BF16,
+    F32,
+}
+
+impl CastType {
+    fn dtype(&self) -> Dtype {
+        match self {
+            CastType::F16 => Dtype::F16,
+            CastType::BF16 => Dtype::BF16,
+            CastType::F32 => Dtype::F32,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            CastType::F16 | CastType::BF16 => 2,
+            CastType::F32 => 4,
+        }
+    }
+
+    fn encode(&self, value: f32) -> Vec<u8> {
+        match self {
+            CastType::F16 => f32_to_f16(value).to_le_bytes().to_vec(),
+            CastType::BF16 => f32_to_bf16(value).to_le_bytes().to_vec(),
+            CastType::F32 => value.to_le_bytes().to_vec(),
+        }
+    }
+}
+
+/// Tensors the cast applies to, by name.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CastFilter {
+    pub include: Option<Regex>,
+    pub exclude: Option<Regex>,
+}
+
+impl CastFilter {
+    fn matches(&self, name: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(name))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(name))
+    }
+}
+
+/// A tensor of the output file, cast or copied as is.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CastTensor {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl CastTensor {
+    pub(crate) fn is_cast(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+fn is_float(dtype: Dtype) -> bool {
+    matches!(dtype, Dtype::F16 | Dtype::BF16 | Dtype::F32 | Dtype::F64)
+}
+
+/// Writes a copy of the safetensors file with the floating point tensors selected by the filter
+/// cast to the data type, the other tensors and the metadata being kept. The embedded signature
+/// no longer matches and is dropped.
+pub(crate) fn cast(
+    input: &Path,
+    output: &Path,
+    to: CastType,
+    filter: &CastFilter,
+) -> anyhow::Result<Vec<CastTensor>> {
+    let file = std::fs::File::open(input)?;
+    let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
+    let mut header = read_header(&mut buffer.as_ref())?;
+    take_signature(&mut header)?;
+    // data starts after the 8 bytes header length and the header itself
+    let data_offset = 8 + u64::from_le_bytes(buffer[..8].try_into()?) as usize;
+
+    let mut tensors = vec![];
+    for (name, value) in &header {
+        if name == METADATA_KEY {
+            continue;
+        }
+        let info: TensorInfo = serde_json::from_value(value.clone())
+            .map_err(|e| anyhow::anyhow!("invalid tensor {}: {}", name, e))?;
+        let (start, end) = info.data_offsets;
+        if start > end || data_offset + end > buffer.len() {
+            anyhow::bail!("data of tensor {} is out of bounds", name);
+        }
+        tensors.push((name.clone(), info));
+    }
+    // the data keeps the order of the input file
+    tensors.sort_by_key(|(_, info)| info.data_offsets.0);
+
+    let mut report = vec![];
+    let mut offset = 0;
+    for (name, info) in &tensors {
+        let dtype = if is_float(info.dtype) && filter.matches(name) {
+            to.dtype()
+        } else {
+            info.dtype
+        };
+        let size = if dtype == info.dtype {
+            info.data_offsets.1 - info.data_offsets.0
+        } else {
+            info.shape.iter().product::<usize>() * to.size()
+        };
+        header.insert(
+            name.clone(),
+            serde_json::json!({
+                "dtype": dtype,
+                "shape": info.shape,
+                "data_offsets": [offset, offset + size],
+            }),
+        );
+        offset += size;
+        report.push(CastTensor {
+            name: name.clone(),
+            from: format!("{:?}", info.dtype),
+            to: format!("{:?}", dtype),
+        });
+    }
+
+    // written next to the output and renamed, the output may be the input itself
+    let folder = output
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let temp = tempfile::NamedTempFile::new_in(folder)?;
+    let mut writer = BufWriter::new(temp.as_file());
+    writer.write_all(&serialize_header(&header)?)?;
+    for ((_, info), tensor) in tensors.iter().zip(&report) {
+        let data = &buffer[data_offset + info.data_offsets.0..data_offset + info.data_offsets.1];
+        if !tensor.is_cast() {
+            writer.write_all(data)?;
+            continue;
+        }
+        let element_type = element_type(info.dtype)
+            .ok_or_else(|| anyhow::anyhow!("can't decode {} values", tensor.from))?;
+        for chunk in data.chunks_exact(element_type.size()) {
+            writer.write_all(&to.encode(element_type.read(chunk) as f32))?;
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+    temp.as_file()
+        .set_permissions(std::fs::metadata(input)?.permissions())?;
+    temp.persist(output)?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        handlers::{safetensors::SafeTensorsHandler, Handler},
+        DetailLevel,
+    };
+
+    #[test]
+    fn test_cast() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("model.safetensors");
+        let header = r#"{"a.weight":{"dtype":"F32","shape":[2],"data_offsets":[0,8]},"ids":{"dtype":"I32","shape":[1],"data_offsets":[8,12]},"b.bias":{"dtype":"F32","shape":[1],"data_offsets":[12,16]},"__metadata__":{"format":"pt"}}"#;
+        let mut data = (header.len() as u64).to_le_bytes().to_vec();
+        data.extend(header.as_bytes());
+        for value in [1.5f32, -2.0] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(7i32.to_le_bytes());
+        data.extend(0.25f32.to_le_bytes());
+        std::fs::write(&input, data).unwrap();
+
+        let output = dir.path().join("model-f16.safetensors");
+        let filter = CastFilter {
+            exclude: Some(Regex::new(r"\.bias$").unwrap()),
+            ..Default::default()
+        };
+        let report = cast(&input, &output, CastType::F16, &filter).unwrap();
+        let cast_names: Vec<&str> = report
+            .iter()
+            .filter(|tensor| tensor.is_cast())
+            .map(|tensor| tensor.name.as_str())
+            .collect();
+        assert_eq!(cast_names, vec!["a.weight"]);
+
+        let handler = SafeTensorsHandler::new();
+        let inspection = handler.inspect(&output, DetailLevel::Full, None).unwrap();
+        assert_eq!(inspection.metadata.get("format").unwrap(), "pt");
+        assert_eq!(inspection.data_size, 4 + 4 + 4);
+        let values: Vec<(String, Vec<f64>)> = handler
+            .tensor_data(&output, None)
+            .unwrap()
+            .iter()
+            .map(|tensor| (tensor.id.clone(), tensor.values().collect()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("a.weight".to_string(), vec![1.5, -2.0]),
+                ("ids".to_string(), vec![7.0]),
+                ("b.bias".to_string(), vec![0.25]),
+            ]
+        );
+    }
+}
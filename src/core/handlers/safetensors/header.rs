@@ -0,0 +1,202 @@
+use std::{borrow::Cow, fmt};
+
+use safetensors::tensor::TensorInfo;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, Visitor};
+
+use crate::core::{progress::Spinner, Metadata};
+
+use super::METADATA_KEY;
+
+// headers from this size on show the progress of the parsing
+const LARGE_HEADER_SIZE: usize = 16 * 1024 * 1024;
+// tensors parsed between two updates of the progress
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Header of a safetensors file parsed from its mapped content, the tensor names borrowed from
+/// it unless they contain escapes.
+pub(super) struct ParsedHeader<'a> {
+    /// Size of the JSON header, without the 8 bytes of its size prefix.
+    pub size: usize,
+    pub tensors: Vec<(Cow<'a, str>, TensorInfo)>,
+    pub metadata: Option<Metadata>,
+}
+
+impl ParsedHeader<'_> {
+    /// Offset of the tensor data in the file.
+    pub fn data_offset(&self) -> usize {
+        8 + self.size
+    }
+}
+
+// a JSON string borrowed from the input when possible
+struct Name<'a>(Cow<'a, str>);
+
+impl<'de> serde::Deserialize<'de> for Name<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl<'de> Visitor<'de> for NameVisitor {
+            type Value = Name<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E> {
+                Ok(Name(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(Name(Cow::Owned(value.to_string())))
+            }
+        }
+
+        deserializer.deserialize_str(NameVisitor)
+    }
+}
+
+// visits the entries of the header one at a time, without building a map of them
+struct HeaderVisitor<'s> {
+    spinner: Option<&'s Spinner>,
+}
+
+impl<'de> Visitor<'de> for HeaderVisitor<'_> {
+    type Value = (Vec<(Cow<'de, str>, TensorInfo)>, Option<Metadata>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of tensors")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tensors = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        let mut metadata = None;
+        while let Some(Name(name)) = map.next_key()? {
+            if name == METADATA_KEY {
+                // values are strings, anything else is kept as JSON
+                let block: Option<serde_json::Map<String, serde_json::Value>> = map.next_value()?;
+                metadata = block.map(|block| {
+                    block
+                        .into_iter()
+                        .map(|(key, value)| match value {
+                            serde_json::Value::String(value) => (key, value),
+                            value => (key, value.to_string()),
+                        })
+                        .collect()
+                });
+                continue;
+            }
+
+            tensors.push((name, map.next_value()?));
+            if let Some(spinner) = self.spinner {
+                if tensors.len() % PROGRESS_INTERVAL == 0 {
+                    spinner.set_message(&format!("parsing header: {} tensors", tensors.len()));
+                }
+            }
+        }
+        Ok((tensors, metadata))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for HeaderVisitor<'_> {
+    type Value = <Self as Visitor<'de>>::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+/// Parses the header of the mapped safetensors file, checking that the data of every tensor is
+/// within the file and matches its type and shape. Unlike the safetensors crate, headers of any
+/// size are accepted as long as they fit in the file.
+pub(super) fn parse_header(buffer: &[u8]) -> anyhow::Result<ParsedHeader<'_>> {
+    let size = buffer
+        .get(..8)
+        .ok_or_else(|| anyhow::anyhow!("file is too small to be a safetensors file"))?;
+    let size = u64::from_le_bytes(size.try_into()?);
+    let json = usize::try_from(size)
+        .ok()
+        .and_then(|size| buffer.get(8..8usize.checked_add(size)?))
+        .ok_or_else(|| anyhow::anyhow!("header size {} is larger than the file", size))?;
+
+    let spinner = (json.len() >= LARGE_HEADER_SIZE).then(|| Spinner::new("parsing header"));
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    let (tensors, metadata) = HeaderVisitor {
+        spinner: spinner.as_ref(),
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|e| anyhow::anyhow!("invalid header: {}", e))?;
+    // only the padding can follow the header
+    deserializer.end()?;
+
+    let data_size = buffer.len() - 8 - json.len();
+    for (name, info) in &tensors {
+        let (start, end) = info.data_offsets;
+        if start > end || end > data_size {
+            anyhow::bail!("data of tensor {} is out of bounds", name);
+        }
+        let expected = info
+            .shape
+            .iter()
+            .try_fold(info.dtype.size(), |size, dim| size.checked_mul(*dim));
+        if expected != Some(end - start) {
+            anyhow::bail!(
+                "data of tensor {} is {} bytes, which doesn't match its type and shape",
+                name,
+                end - start
+            );
+        }
+    }
+
+    Ok(ParsedHeader {
+        size: json.len(),
+        tensors,
+        metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(header: &str, data_size: usize) -> Vec<u8> {
+        let mut buffer = (header.len() as u64).to_le_bytes().to_vec();
+        buffer.extend_from_slice(header.as_bytes());
+        buffer.resize(buffer.len() + data_size, 0);
+        buffer
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let buffer = build(
+            r#"{"a\"b":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"c":{"dtype":"F32","shape":[1,2],"data_offsets":[4,12]},"__metadata__":{"format":"pt","n":1}}  "#,
+            12,
+        );
+        let header = parse_header(&buffer).unwrap();
+        assert_eq!(header.data_offset(), buffer.len() - 12);
+        assert_eq!(header.tensors.len(), 2);
+        // escaped names are unescaped, the others borrowed from the buffer
+        assert_eq!(header.tensors[0].0, "a\"b");
+        assert!(matches!(header.tensors[1].0, Cow::Borrowed("c")));
+        assert_eq!(header.tensors[1].1.shape, vec![1, 2]);
+        let metadata = header.metadata.unwrap();
+        assert_eq!(metadata.get("format").unwrap(), "pt");
+        assert_eq!(metadata.get("n").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_parse_invalid_header() {
+        let tensor = r#"{"a":{"dtype":"F32","shape":[2],"data_offsets":[0,8]}}"#;
+        assert!(parse_header(&build(tensor, 8)).is_ok());
+        // data out of the file
+        assert!(parse_header(&build(tensor, 4)).is_err());
+        // size not matching the shape
+        assert!(parse_header(&build(&tensor.replace("[2]", "[3]"), 12)).is_err());
+        // trailing garbage, truncated JSON and a size prefix past the end of the file
+        assert!(parse_header(&build(&format!("{} x", tensor), 8)).is_err());
+        assert!(parse_header(&build(&tensor[..20], 8)).is_err());
+        let mut buffer = build(tensor, 8);
+        buffer[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(parse_header(&buffer).is_err());
+        assert!(parse_header(&[1, 2]).is_err());
+    }
+}
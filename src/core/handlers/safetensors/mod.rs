@@ -1,25 +1,108 @@
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
+    io::Read,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use rayon::prelude::*;
 
-use safetensors::{tensor::TensorInfo, SafeTensors};
+use safetensors::{tensor::TensorInfo, Dtype};
 use serde::Deserialize;
 
-use crate::{
-    cli::DetailLevel,
-    core::{FileType, Inspection, Metadata, TensorDescriptor},
+use crate::core::{
+    data::{ElementType, Encoding, TensorData},
+    graph::module_tree_dot,
+    DetailLevel, FileType, Inspection, Metadata, Shard, TensorDescriptor,
 };
 
-use super::{Handler, Scope};
+mod cast;
+mod header;
+mod scan;
+mod validate;
+
+pub(crate) use cast::{cast, CastFilter, CastType};
+pub(crate) use scan::scan;
+pub(crate) use validate::validate;
+
+use header::parse_header;
+
+use super::{
+    is_outside_of_model_folder, mlx, replace_file, EmbeddedSignature, Handler, ReadSeek, Scope,
+    EMBEDDED_SIGNATURE_KEY,
+};
 
 #[derive(Debug, Deserialize)]
 struct TensorIndex {
     weight_map: HashMap<String, String>,
 }
 
+/// Inspects the SafeTensors file read in memory, only its header is parsed.
+pub(crate) fn inspect_buffer(
+    buffer: &[u8],
+    detail: DetailLevel,
+    filter: Option<String>,
+) -> anyhow::Result<Inspection> {
+    let mut inspection = Inspection {
+        file_size: buffer.len() as u64,
+        ..Default::default()
+    };
+
+    // read header, the tensor names are borrowed from the buffer
+    let header = parse_header(buffer)?;
+
+    inspection.file_type = FileType::SafeTensors;
+    inspection.header_size = header.size;
+    inspection.version = "0.x".to_string();
+
+    let mut tensors = header.tensors;
+
+    inspection.num_tensors = tensors.len();
+    inspection.data_size = tensors
+        .par_iter()
+        .map(|t| t.1.data_offsets.1 - t.1.data_offsets.0)
+        .sum::<usize>();
+
+    inspection.unique_shapes = tensors
+        .par_iter()
+        .map(|t| t.1.shape.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    // sort shapes by volume
+    inspection.unique_shapes.sort_by(|a, b| {
+        let size_a: usize = a.iter().product();
+        let size_b: usize = b.iter().product();
+        size_a.cmp(&size_b)
+    });
+
+    inspection.unique_dtypes = tensors
+        .par_iter()
+        .map(|t| format!("{:?}", t.1.dtype))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    if let Some(block_metadata) = header.metadata {
+        inspection.metadata = block_metadata;
+    }
+
+    if matches!(detail, DetailLevel::Full) {
+        // sort by offset
+        tensors.sort_by_key(|(_, info)| info.data_offsets.0);
+
+        inspection.tensors = Some(
+            tensors
+                .par_iter()
+                .filter(|(tensor_id, _)| filter.as_ref().is_none_or(|f| tensor_id.contains(f)))
+                .map(|(tensor_id, tensor_info)| build_tensor_descriptor(tensor_id, tensor_info))
+                .collect(),
+        );
+    }
+
+    Ok(inspection)
+}
+
 pub(crate) struct SafeTensorsHandler;
 
 impl SafeTensorsHandler {
@@ -28,7 +111,7 @@ impl SafeTensorsHandler {
     }
 }
 
-fn is_safetensors_index(file_path: &Path) -> bool {
+pub(crate) fn is_safetensors_index(file_path: &Path) -> bool {
     file_path
         .file_name()
         .unwrap_or_default()
@@ -36,6 +119,149 @@ fn is_safetensors_index(file_path: &Path) -> bool {
         .ends_with(".safetensors.index.json")
 }
 
+/// Returns the unique files referenced by the weight map of an index, sorted by name.
+pub(crate) fn shard_paths(index_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let base_path = index_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("no parent path"))?;
+
+    let index = std::fs::read_to_string(index_path)?;
+    let index: TensorIndex = serde_json::from_str(&index)?;
+
+    // a malicious index could otherwise get arbitrary files read, hashed or signed
+    if let Some(location) = index
+        .weight_map
+        .values()
+        .find(|location| is_outside_of_model_folder(location))
+    {
+        anyhow::bail!(
+            "{} references {}, outside of the model folder",
+            index_path.display(),
+            location
+        );
+    }
+
+    let mut unique: Vec<PathBuf> = index
+        .weight_map
+        .values()
+        .map(|location| base_path.join(location))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    unique.sort();
+    Ok(unique)
+}
+
+// same limit enforced by the safetensors crate
+const MAX_HEADER_SIZE: u64 = 100_000_000;
+const METADATA_KEY: &str = "__metadata__";
+
+type Header = BTreeMap<String, serde_json::Value>;
+
+// reads the raw header bytes leaving the file positioned at the beginning of the data
+fn read_header_bytes(file: &mut impl Read) -> anyhow::Result<Vec<u8>> {
+    let mut header_size = [0u8; 8];
+    file.read_exact(&mut header_size)?;
+    let header_size = u64::from_le_bytes(header_size);
+    if header_size > MAX_HEADER_SIZE {
+        anyhow::bail!("header size {} is too large", header_size);
+    }
+
+    let mut header = vec![0u8; header_size as usize];
+    file.read_exact(&mut header)?;
+
+    Ok(header)
+}
+
+// reads the header leaving the file positioned at the beginning of the data
+fn read_header(file: &mut impl Read) -> anyhow::Result<Header> {
+    Ok(serde_json::from_slice(&read_header_bytes(file)?)?)
+}
+
+// removes the embedded signature from the header, empty metadata is dropped so that
+// the header is the same whether the file has been signed or not
+fn take_signature(header: &mut Header) -> anyhow::Result<Option<String>> {
+    let Some(metadata) = header
+        .get_mut(METADATA_KEY)
+        .and_then(|metadata| metadata.as_object_mut())
+    else {
+        return Ok(None);
+    };
+    let signature = match metadata.remove(EMBEDDED_SIGNATURE_KEY) {
+        None => None,
+        Some(serde_json::Value::String(signature)) => Some(signature),
+        Some(_) => anyhow::bail!("{} is not a string", EMBEDDED_SIGNATURE_KEY),
+    };
+    if metadata.is_empty() {
+        header.remove(METADATA_KEY);
+    }
+    Ok(signature)
+}
+
+// serializes the header with its size prefix, padded with spaces to keep the data aligned
+fn serialize_header(header: &Header) -> anyhow::Result<Vec<u8>> {
+    let mut json = serde_json::to_vec(header)?;
+    json.resize(json.len().next_multiple_of(8), b' ');
+
+    let mut serialized = (json.len() as u64).to_le_bytes().to_vec();
+    serialized.extend(json);
+    Ok(serialized)
+}
+
+/// Edits the __metadata__ block of the header and rewrites the file, streaming the tensor data
+/// as is. The embedded signature, if any, no longer matches and is removed: returns the new
+/// metadata and whether the file was signed.
+pub(crate) fn edit_metadata(
+    file_path: &Path,
+    edit: impl FnOnce(&mut Metadata),
+) -> anyhow::Result<(Metadata, bool)> {
+    let mut file = std::fs::File::open(file_path)?;
+    let mut header = read_header(&mut file)?;
+    let signed = take_signature(&mut header)?.is_some();
+
+    let mut metadata: Metadata = header
+        .remove(METADATA_KEY)
+        .and_then(|value| value.as_object().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(value) => (key, value),
+            value => (key, value.to_string()),
+        })
+        .collect();
+    edit(&mut metadata);
+
+    if !metadata.is_empty() {
+        header.insert(METADATA_KEY.to_string(), serde_json::to_value(&metadata)?);
+    }
+    let serialized = serialize_header(&header)?;
+    replace_file(file_path, &mut std::io::Cursor::new(serialized).chain(file))?;
+
+    Ok((metadata, signed))
+}
+
+// reads the regions of a mapped file one after the other
+struct MappedRanges {
+    buffer: memmap2::Mmap,
+    ranges: std::collections::VecDeque<std::ops::Range<usize>>,
+}
+
+impl Read for MappedRanges {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while let Some(range) = self.ranges.front_mut() {
+            if range.start >= range.end {
+                self.ranges.pop_front();
+                continue;
+            }
+            let size = buf.len().min(range.len());
+            buf[..size].copy_from_slice(&self.buffer[range.start..range.start + size]);
+            range.start += size;
+            return Ok(size);
+        }
+        Ok(0)
+    }
+}
+
 fn build_tensor_descriptor(tensor_id: &str, tensor_info: &TensorInfo) -> TensorDescriptor {
     TensorDescriptor {
         id: Some(tensor_id.to_string()),
@@ -43,6 +269,114 @@ fn build_tensor_descriptor(tensor_id: &str, tensor_info: &TensorInfo) -> TensorD
         dtype: format!("{:?}", &tensor_info.dtype),
         size: tensor_info.data_offsets.1 - tensor_info.data_offsets.0,
         metadata: Metadata::new(),
+        stats: None,
+        preview: None,
+    }
+}
+
+fn element_type(dtype: Dtype) -> Option<ElementType> {
+    match dtype {
+        Dtype::BOOL => Some(ElementType::Bool),
+        Dtype::U8 => Some(ElementType::U8),
+        Dtype::I8 => Some(ElementType::I8),
+        Dtype::I16 => Some(ElementType::I16),
+        Dtype::U16 => Some(ElementType::U16),
+        Dtype::F16 => Some(ElementType::F16),
+        Dtype::BF16 => Some(ElementType::BF16),
+        Dtype::I32 => Some(ElementType::I32),
+        Dtype::U32 => Some(ElementType::U32),
+        Dtype::F32 => Some(ElementType::F32),
+        Dtype::F64 => Some(ElementType::F64),
+        Dtype::I64 => Some(ElementType::I64),
+        Dtype::U64 => Some(ElementType::U64),
+        _ => None,
+    }
+}
+
+impl SafeTensorsHandler {
+    // aggregates the inspections of the shards referenced by the index
+    fn inspect_index(
+        &self,
+        index_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let shards = shard_paths(index_path)?;
+        if shards.is_empty() {
+            anyhow::bail!("{} references no files", index_path.display());
+        }
+        let shards = shards
+            .par_iter()
+            .map(|shard| {
+                self.inspect(shard, detail.clone(), filter.clone())
+                    .map_err(|e| anyhow::anyhow!("can't inspect {}: {}", shard.display(), e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut inspection = Inspection {
+            file_path: index_path.canonicalize()?,
+            file_type: FileType::SafeTensors,
+            file_size: std::fs::metadata(index_path)?.len(),
+            version: "0.x".to_string(),
+            ..Default::default()
+        };
+
+        // metadata of the index, such as the total size, then the one of the shards
+        let index: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(index_path)?)?;
+        if let Some(metadata) = index.get("metadata").and_then(|m| m.as_object()) {
+            for (key, value) in metadata {
+                let value = match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                inspection.metadata.insert(key.to_string(), value);
+            }
+        }
+
+        let mut shapes = HashSet::new();
+        let mut dtypes = HashSet::new();
+        let mut summaries = vec![];
+        for shard in shards {
+            inspection.file_size += shard.file_size;
+            inspection.header_size += shard.header_size;
+            inspection.num_tensors += shard.num_tensors;
+            inspection.data_size += shard.data_size;
+            shapes.extend(shard.unique_shapes.iter().cloned());
+            dtypes.extend(shard.unique_dtypes.iter().cloned());
+            for (key, value) in &shard.metadata {
+                inspection
+                    .metadata
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+            if let Some(tensors) = shard.tensors {
+                inspection
+                    .tensors
+                    .get_or_insert_with(Vec::new)
+                    .extend(tensors);
+            }
+
+            let mut unique_dtypes = shard.unique_dtypes;
+            unique_dtypes.sort();
+            summaries.push(Shard {
+                file_path: shard.file_path,
+                file_size: shard.file_size,
+                num_tensors: shard.num_tensors,
+                data_size: shard.data_size,
+                unique_dtypes,
+            });
+        }
+
+        inspection.unique_shapes = shapes.into_iter().collect();
+        // sort shapes by volume
+        inspection
+            .unique_shapes
+            .sort_by_key(|shape| (shape.iter().product::<usize>(), shape.clone()));
+        inspection.unique_dtypes = dtypes.into_iter().collect();
+        inspection.unique_dtypes.sort();
+        inspection.shards = Some(summaries);
+
+        Ok(inspection)
     }
 }
 
@@ -51,48 +385,22 @@ impl Handler for SafeTensorsHandler {
         FileType::SafeTensors
     }
 
-    fn is_handler_for(&self, file_path: &Path, scope: &Scope) -> bool {
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
         let is_safetensors = file_path
             .extension()
             .unwrap_or_default()
             .to_str()
             .unwrap_or("")
-            .to_ascii_lowercase()
-            == "safetensors";
-
-        match scope {
-            // can only inspect safetensors files
-            Scope::Inspection => is_safetensors,
-            // can sign safetensors files directly or an index referencing multiple files
-            Scope::Signing => is_safetensors || is_safetensors_index(file_path),
-        }
+            .eq_ignore_ascii_case("safetensors");
+
+        // can inspect and sign safetensors files directly or an index referencing multiple files
+        is_safetensors || is_safetensors_index(file_path)
     }
 
     fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
         if is_safetensors_index(file_path) {
-            // load unique paths from index
-            let base_path = file_path
-                .parent()
-                .ok_or_else(|| anyhow::anyhow!("no parent path"))?;
-
-            let index = std::fs::read_to_string(file_path)?;
-            let index: TensorIndex = serde_json::from_str(&index)?;
-
-            let unique: HashSet<PathBuf> = index
-                .weight_map
-                .values()
-                .map(PathBuf::from)
-                .map(|p| {
-                    if p.is_relative() {
-                        base_path.join(p)
-                    } else {
-                        p
-                    }
-                })
-                .collect();
-
             let mut paths = vec![file_path.to_path_buf()];
-            paths.extend(unique);
+            paths.extend(shard_paths(file_path)?);
             Ok(paths)
         } else {
             // safetensors are self contained
@@ -106,87 +414,359 @@ impl Handler for SafeTensorsHandler {
         detail: DetailLevel,
         filter: Option<String>,
     ) -> anyhow::Result<Inspection> {
-        let mut inspection = Inspection::default();
+        if is_safetensors_index(file_path) {
+            return self.inspect_index(file_path, detail, filter);
+        }
 
         let file = std::fs::File::open(file_path)?;
-        let buffer = unsafe {
-            memmap2::MmapOptions::new()
-                .map(&file)
-                .unwrap_or_else(|_| panic!("failed to map file {}", file_path.display()))
-        };
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
 
+        let mut inspection = inspect_buffer(&buffer, detail, filter)?;
         inspection.file_path = file_path.canonicalize()?;
-        inspection.file_size = file.metadata()?.len();
 
-        // read header
-        let (header_size, header) = SafeTensors::read_metadata(&buffer)?;
+        // MLX models keep the architecture parameters in a separate config file
+        if mlx::is_mlx_metadata(&inspection.metadata) {
+            let config_path = file_path.with_file_name(mlx::CONFIG_FILE_NAME);
+            if let Ok(config) = mlx::config_metadata(&config_path) {
+                inspection.metadata.extend(config);
+            }
+        }
 
-        inspection.file_type = FileType::SafeTensors;
-        inspection.header_size = header_size;
-        inspection.version = "0.x".to_string();
+        Ok(inspection)
+    }
 
-        let tensors = header.tensors();
+    fn create_graph(&self, file_path: &Path, output_path: &Path) -> anyhow::Result<()> {
+        // the weights have no execution graph, their names give the module hierarchy
+        let inspection = self.inspect(file_path, DetailLevel::Full, None)?;
+        let name = file_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = name.trim_end_matches(".safetensors.index");
+        let dot_string = module_tree_dot(name, inspection.tensors.as_deref().unwrap_or_default())?;
+
+        std::fs::write(output_path, dot_string)
+            .map_err(|e| anyhow::anyhow!("failed to write dot string to output path: {:?}", e))
+    }
 
-        // transform tensors to a vector
-        let mut tensors: Vec<_> = tensors.into_iter().collect();
+    fn scan_header(&self, mut reader: &mut dyn ReadSeek) -> anyhow::Result<()> {
+        // the size prefix and the JSON header, the tensor data follows
+        read_header(&mut reader).map(|_| ())
+    }
 
-        inspection.num_tensors = tensors.len();
-        inspection.data_size = tensors
-            .par_iter()
-            .map(|t| t.1.data_offsets.1 - t.1.data_offsets.0)
-            .sum::<usize>();
+    fn tensor_data(
+        &self,
+        file_path: &Path,
+        filter: Option<String>,
+    ) -> anyhow::Result<Vec<TensorData>> {
+        if is_safetensors_index(file_path) {
+            let mut tensors = vec![];
+            for shard in shard_paths(file_path)? {
+                tensors.extend(self.tensor_data(&shard, filter.clone())?);
+            }
+            return Ok(tensors);
+        }
 
-        inspection.unique_shapes = tensors
-            .par_iter()
-            .map(|t| t.1.shape.clone())
-            .collect::<HashSet<_>>()
+        let file = std::fs::File::open(file_path)?;
+        let buffer = Arc::new(unsafe { memmap2::MmapOptions::new().map(&file)? });
+
+        let header = parse_header(&buffer)?;
+        let data_offset = header.data_offset();
+
+        let mut tensors: Vec<_> = header
+            .tensors
             .into_iter()
+            .filter(|(tensor_id, _)| filter.as_ref().is_none_or(|f| tensor_id.contains(f)))
             .collect();
-        // sort shapes by volume
-        inspection.unique_shapes.sort_by(|a, b| {
-            let size_a: usize = a.iter().product();
-            let size_b: usize = b.iter().product();
-            size_a.cmp(&size_b)
-        });
+        // sort by offset
+        tensors.sort_by_key(|(_, info)| info.data_offsets.0);
 
-        inspection.unique_dtypes = tensors
-            .par_iter()
-            .map(|t| format!("{:?}", t.1.dtype))
-            .collect::<HashSet<_>>()
+        tensors
             .into_iter()
-            .collect();
+            .filter_map(|(tensor_id, tensor_info)| {
+                element_type(tensor_info.dtype)
+                    .map(|element_type| (tensor_id, tensor_info, element_type))
+            })
+            .map(|(tensor_id, tensor_info, element_type)| {
+                let (start, end) = tensor_info.data_offsets;
+                TensorData::new(
+                    tensor_id.into_owned(),
+                    Encoding::Plain(element_type),
+                    buffer.clone(),
+                    data_offset + start..data_offset + end,
+                    Some((data_offset + start) as u64),
+                )
+            })
+            .collect()
+    }
 
-        if let Some(block_metadata) = header.metadata() {
-            inspection.metadata = BTreeMap::from_iter(
-                block_metadata
-                    .iter()
-                    .map(|(k, v)| (k.to_string(), v.to_string())),
-            );
+    fn canonical_content(&self, file_path: &Path) -> anyhow::Result<Box<dyn Read>> {
+        let file = std::fs::File::open(file_path)?;
+        let buffer = unsafe { memmap2::MmapOptions::new().map(&file)? };
+        let mut header = read_header(&mut buffer.as_ref())?;
+        take_signature(&mut header)?;
+        // data starts after the 8 bytes header length and the header itself
+        let data_offset = 8 + u64::from_le_bytes(buffer[..8].try_into()?) as usize;
+
+        // tensors sorted by name, their data laid out in the same order without gaps
+        let mut ranges = std::collections::VecDeque::new();
+        let mut offset = 0;
+        for (name, value) in header.iter_mut() {
+            if name == METADATA_KEY {
+                continue;
+            }
+            let info: TensorInfo = serde_json::from_value(value.clone())
+                .map_err(|e| anyhow::anyhow!("invalid tensor {}: {}", name, e))?;
+            let (start, end) = info.data_offsets;
+            if start > end || data_offset + end > buffer.len() {
+                anyhow::bail!("data of tensor {} is out of bounds", name);
+            }
+            *value = serde_json::json!({
+                "dtype": info.dtype,
+                "shape": info.shape,
+                "data_offsets": [offset, offset + end - start],
+            });
+            ranges.push_back(data_offset + start..data_offset + end);
+            offset += end - start;
         }
 
-        if matches!(detail, DetailLevel::Full) {
-            // sort by offset
-            tensors.sort_by_key(|(_, info)| info.data_offsets.0);
-
-            inspection.tensors = Some(
-                tensors
-                    .par_iter()
-                    .filter(|(tensor_id, _)| {
-                        filter.as_ref().map_or(true, |f| tensor_id.contains(f))
-                    })
-                    .map(|(tensor_id, tensor_info)| build_tensor_descriptor(tensor_id, tensor_info))
-                    .collect(),
-            );
+        let header = serialize_header(&header)?;
+        Ok(Box::new(
+            std::io::Cursor::new(header).chain(MappedRanges { buffer, ranges }),
+        ))
+    }
+
+    fn embedded_signature(&self, file_path: &Path) -> anyhow::Result<EmbeddedSignature> {
+        let mut file = std::fs::File::open(file_path)?;
+        let raw_header = read_header_bytes(&mut file)?;
+        let mut header: Header = serde_json::from_slice(&raw_header)?;
+
+        // embed_signature always writes the header in canonical form, a signed header that
+        // isn't could carry duplicate keys, reordered entries or padding the signature
+        // doesn't cover
+        let canonical = serialize_header(&header)?[8..] == raw_header[..];
+        let manifest = take_signature(&mut header)?;
+        if manifest.is_some() && !canonical {
+            anyhow::bail!("the header of a signed file is not in canonical form");
         }
 
-        Ok(inspection)
+        // the header without the signature followed by the untouched data
+        let header = serialize_header(&header)?;
+        Ok(EmbeddedSignature {
+            content: Box::new(std::io::Cursor::new(header).chain(file)),
+            manifest,
+        })
+    }
+
+    fn embed_signature(&self, file_path: &Path, manifest: &str) -> anyhow::Result<()> {
+        let mut file = std::fs::File::open(file_path)?;
+        let mut header = read_header(&mut file)?;
+        // replace any previous signature
+        take_signature(&mut header)?;
+
+        header
+            .entry(METADATA_KEY.to_string())
+            .or_insert_with(|| serde_json::Value::Object(Default::default()))
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("invalid {} block", METADATA_KEY))?
+            .insert(
+                EMBEDDED_SIGNATURE_KEY.to_string(),
+                serde_json::Value::String(manifest.to_string()),
+            );
+
+        let header = serialize_header(&header)?;
+        replace_file(file_path, &mut std::io::Cursor::new(header).chain(file))?;
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
 
+    fn create_test_file(header: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(".safetensors")
+            .tempfile()
+            .unwrap();
+        file.write_all(&(header.len() as u64).to_le_bytes())
+            .unwrap();
+        file.write_all(header.as_bytes()).unwrap();
+        file.write_all(&[1, 2, 3, 4]).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    fn read_content(mut embedded: EmbeddedSignature) -> Vec<u8> {
+        let mut content = vec![];
+        embedded.content.read_to_end(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_embed_signature() {
+        let handler = SafeTensorsHandler::new();
+        let file = create_test_file(
+            r#"{"t":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"__metadata__":{"a":"b"}}"#,
+        );
+
+        let unsigned = handler.embedded_signature(file.path()).unwrap();
+        assert!(unsigned.manifest.is_none());
+        let unsigned = read_content(unsigned);
+
+        handler.embed_signature(file.path(), "{}").unwrap();
+        // the file is still valid and keeps its metadata and data
+        let inspection = handler
+            .inspect(file.path(), DetailLevel::Brief, None)
+            .unwrap();
+        assert_eq!(inspection.metadata.get("a").map(String::as_str), Some("b"));
+        let tensors = handler.tensor_data(file.path(), None).unwrap();
+        assert_eq!(tensors[0].bytes(), &[1, 2, 3, 4]);
+
+        // the signed content doesn't change when the signature is embedded
+        let signed = handler.embedded_signature(file.path()).unwrap();
+        assert_eq!(signed.manifest.as_deref(), Some("{}"));
+        assert_eq!(read_content(signed), unsigned);
+
+        // signing again replaces the previous signature
+        handler.embed_signature(file.path(), "[]").unwrap();
+        let signed = handler.embedded_signature(file.path()).unwrap();
+        assert_eq!(signed.manifest.as_deref(), Some("[]"));
+        assert_eq!(read_content(signed), unsigned);
+    }
+
+    #[test]
+    fn test_embed_signature_without_metadata() {
+        let handler = SafeTensorsHandler::new();
+        let file = create_test_file(r#"{"t":{"dtype":"U8","shape":[4],"data_offsets":[0,4]}}"#);
+
+        let unsigned = read_content(handler.embedded_signature(file.path()).unwrap());
+        handler.embed_signature(file.path(), "{}").unwrap();
+        let signed = handler.embedded_signature(file.path()).unwrap();
+
+        assert_eq!(signed.manifest.as_deref(), Some("{}"));
+        assert_eq!(read_content(signed), unsigned);
+    }
+
+    #[test]
+    fn test_embedded_signature_not_canonical() {
+        let handler = SafeTensorsHandler::new();
+        let header =
+            r#"{"__metadata__":{"a":"b"},"t":{"data_offsets":[0,4],"dtype":"U8","shape":[4]}}"#;
+        let file = create_test_file(header);
+        handler.embed_signature(file.path(), "{}").unwrap();
+        let signed = std::fs::read(file.path()).unwrap();
+        let header_size = u64::from_le_bytes(signed[..8].try_into().unwrap()) as usize;
+        let signed_header = std::str::from_utf8(&signed[8..8 + header_size]).unwrap();
+
+        let tamper = |header: String| {
+            let file = create_test_file(&header);
+            handler.embedded_signature(file.path()).map(|_| ())
+        };
+        // the untouched header verifies
+        assert!(tamper(signed_header.to_string()).is_ok());
+        // duplicate keys, whitespace and non-space padding are rejected
+        assert!(tamper(signed_header.replacen(r#"{"a":"b","#, r#"{"a":"c","a":"b","#, 1)).is_err());
+        assert!(tamper(signed_header.replacen(r#"{"a""#, r#"{ "a""#, 1)).is_err());
+        assert!(tamper(format!("{}\n", signed_header.trim_end())).is_err());
+    }
+
+    #[test]
+    fn test_non_string_signature() {
+        let handler = SafeTensorsHandler::new();
+        let file = create_test_file(
+            r#"{"__metadata__":{"tensorman.signature":{}},"t":{"data_offsets":[0,4],"dtype":"U8","shape":[4]}}"#,
+        );
+        assert!(handler.embedded_signature(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_edit_metadata() {
+        let handler = SafeTensorsHandler::new();
+        let file = create_test_file(
+            r#"{"t":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"__metadata__":{"a":"b","user":"me"}}"#,
+        );
+        handler.embed_signature(file.path(), "{}").unwrap();
+
+        let (metadata, signed) = edit_metadata(file.path(), |metadata| {
+            metadata.remove("user");
+            metadata.insert("source".to_string(), "hub".to_string());
+        })
+        .unwrap();
+        assert!(signed);
+        assert_eq!(metadata.keys().collect::<Vec<_>>(), vec!["a", "source"]);
+
+        let inspection = handler
+            .inspect(file.path(), DetailLevel::Brief, None)
+            .unwrap();
+        assert_eq!(inspection.metadata, metadata);
+        assert_eq!(
+            handler.tensor_data(file.path(), None).unwrap()[0].bytes(),
+            &[1, 2, 3, 4]
+        );
+
+        let (metadata, signed) = edit_metadata(file.path(), |metadata| metadata.clear()).unwrap();
+        assert!(!signed && metadata.is_empty());
+        let inspection = handler
+            .inspect(file.path(), DetailLevel::Brief, None)
+            .unwrap();
+        assert!(inspection.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_canonical_content() {
+        let handler = SafeTensorsHandler::new();
+        let a = create_test_file(
+            r#"{"a":{"dtype":"U8","shape":[2],"data_offsets":[0,2]}, "b":{"dtype":"U8","shape":[2],"data_offsets":[2,4]},"__metadata__":{"x":"y"}}"#,
+        );
+        // same tensors and metadata, data in the opposite order and a signature
+        let b = create_test_file(
+            r#"{"__metadata__":{"x":"y"},"b":{"shape":[2],"dtype":"U8","data_offsets":[0,2]},"a":{"dtype":"U8","shape":[2],"data_offsets":[2,4]}}"#,
+        );
+        let mut data = std::fs::read(b.path()).unwrap();
+        let size = data.len();
+        data[size - 4..].copy_from_slice(&[3, 4, 1, 2]);
+        std::fs::write(b.path(), data).unwrap();
+        handler.embed_signature(b.path(), "{}").unwrap();
+
+        let canonical = |path: &Path| {
+            let mut content = vec![];
+            handler
+                .canonical_content(path)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            content
+        };
+        let content = canonical(a.path());
+        assert_eq!(content, canonical(b.path()));
+        assert_eq!(content[content.len() - 4..], [1, 2, 3, 4]);
+
+        // and canonicalizing is idempotent
+        std::fs::write(b.path(), &content).unwrap();
+        assert_eq!(canonical(b.path()), content);
+        let data = handler.tensor_data(b.path(), None).unwrap();
+        assert_eq!(data[1].id, "b");
+        assert_eq!(data[1].bytes(), &[3, 4]);
+    }
+
+    #[test]
+    fn test_scan_header() {
+        let handler = SafeTensorsHandler::new();
+        let file = create_test_file(
+            r#"{"t":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"__metadata__":{"a":"b"}}"#,
+        );
+        let data = std::fs::read(file.path()).unwrap();
+
+        // everything but the data is read
+        let scanned = super::super::scanned(&handler, &data);
+        assert_eq!(scanned[..data.len() - 4], data[..data.len() - 4]);
+        assert_eq!(scanned[data.len() - 4..], [0, 0, 0, 0]);
+    }
+
     #[test]
     fn test_is_handler_for() {
         let handler = SafeTensorsHandler::new();
@@ -215,21 +795,87 @@ mod tests {
     fn test_is_handler_for_index() {
         let handler = SafeTensorsHandler::new();
 
-        // Index files should only be handled in signing scope
-        assert!(handler.is_handler_for(Path::new("model.safetensors.index.json"), &Scope::Signing));
-        assert!(handler.is_handler_for(
-            Path::new("path/to/model.safetensors.index.json"),
-            &Scope::Signing
-        ));
-
-        // Index files should not be handled in inspection scope
-        assert!(!handler.is_handler_for(
-            Path::new("model.safetensors.index.json"),
-            &Scope::Inspection
-        ));
-        assert!(!handler.is_handler_for(
-            Path::new("path/to/model.safetensors.index.json"),
-            &Scope::Inspection
-        ));
+        // Index files are handled in both scopes
+        for scope in [Scope::Inspection, Scope::Signing] {
+            assert!(handler.is_handler_for(Path::new("model.safetensors.index.json"), &scope));
+            assert!(
+                handler.is_handler_for(Path::new("path/to/model.safetensors.index.json"), &scope)
+            );
+            assert!(!handler.is_handler_for(Path::new("config.json"), &scope));
+        }
+    }
+
+    #[test]
+    fn test_inspect_index() {
+        let handler = SafeTensorsHandler::new();
+        let dir = tempfile::tempdir().unwrap();
+        let shards = [
+            (
+                "model-00001-of-00002.safetensors",
+                r#"{"a":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"__metadata__":{"format":"pt"}}"#,
+            ),
+            (
+                "model-00002-of-00002.safetensors",
+                r#"{"b":{"dtype":"F16","shape":[2],"data_offsets":[0,4]}}"#,
+            ),
+        ];
+        for (name, header) in shards {
+            let file = create_test_file(header);
+            std::fs::copy(file.path(), dir.path().join(name)).unwrap();
+        }
+        let index_path = dir.path().join("model.safetensors.index.json");
+        std::fs::write(
+            &index_path,
+            r#"{"metadata":{"total_size":8},"weight_map":{"a":"model-00001-of-00002.safetensors","b":"model-00002-of-00002.safetensors"}}"#,
+        )
+        .unwrap();
+
+        let inspection = handler
+            .inspect(&index_path, DetailLevel::Full, None)
+            .unwrap();
+        assert_eq!(inspection.num_tensors, 2);
+        assert_eq!(inspection.data_size, 8);
+        assert_eq!(inspection.unique_dtypes, vec!["F16", "U8"]);
+        assert_eq!(inspection.metadata.get("total_size").unwrap(), "8");
+        assert_eq!(inspection.metadata.get("format").unwrap(), "pt");
+        let tensors: Vec<String> = inspection
+            .tensors
+            .unwrap()
+            .into_iter()
+            .filter_map(|tensor| tensor.id)
+            .collect();
+        assert_eq!(tensors, vec!["a", "b"]);
+
+        let shards = inspection.shards.unwrap();
+        assert_eq!(shards.len(), 2);
+        assert_eq!(shards[1].num_tensors, 1);
+        assert_eq!(shards[1].unique_dtypes, vec!["F16"]);
+
+        let data = handler.tensor_data(&index_path, None).unwrap();
+        assert_eq!(data.len(), 2);
+    }
+
+    #[test]
+    fn test_shard_paths_outside_of_model_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_path = dir.path().join("model.safetensors.index.json");
+
+        for location in ["/etc/passwd", "../x.safetensors"] {
+            std::fs::write(
+                &index_path,
+                format!(
+                    r#"{{"weight_map":{{"a":"model.safetensors","b":"{}"}}}}"#,
+                    location
+                ),
+            )
+            .unwrap();
+            assert!(shard_paths(&index_path).is_err());
+        }
+
+        std::fs::write(&index_path, r#"{"weight_map":{"a":"model.safetensors"}}"#).unwrap();
+        assert_eq!(
+            shard_paths(&index_path).unwrap(),
+            vec![dir.path().join("model.safetensors")]
+        );
     }
 }
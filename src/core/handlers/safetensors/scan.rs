@@ -0,0 +1,64 @@
+use std::{io::Seek, path::Path};
+
+use super::{read_header, METADATA_KEY};
+use crate::core::scan::{Issue, Safety};
+
+/// Statically scans the file for data following the data of the tensors declared by its header.
+pub(crate) fn scan(file_path: &Path) -> anyhow::Result<Vec<Issue>> {
+    let mut file = std::fs::File::open(file_path)?;
+    let file_size = file.metadata()?.len();
+    let header = read_header(&mut file)?;
+    let data_offset = file.stream_position()?;
+
+    // the end of the data of the last tensor, relative to the data section
+    let data_size = header
+        .iter()
+        .filter(|(name, _)| *name != METADATA_KEY)
+        .filter_map(|(_, tensor)| tensor.get("data_offsets")?.get(1)?.as_u64())
+        .max()
+        .unwrap_or_default();
+
+    let data_end = data_offset.saturating_add(data_size);
+    if file_size > data_end {
+        Ok(vec![Issue {
+            rule_id: "unreferenced-data",
+            safety: Safety::Suspicious,
+            message: format!(
+                "{} bytes following the data of the {} declared tensors",
+                file_size - data_end,
+                header.keys().filter(|name| *name != METADATA_KEY).count()
+            ),
+            location: String::new(),
+            offset: Some(data_end),
+        }])
+    } else {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let header =
+            r#"{"t":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"__metadata__":{"a":"b"}}"#;
+        let mut data = (header.len() as u64).to_le_bytes().to_vec();
+        data.extend(header.as_bytes());
+        data.extend([1, 2, 3, 4]);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.safetensors");
+        std::fs::write(&file_path, &data).unwrap();
+        assert!(scan(&file_path).unwrap().is_empty());
+
+        // a pickle appended to the file
+        data.extend(b"\x80\x04payload");
+        std::fs::write(&file_path, &data).unwrap();
+        let issues = scan(&file_path).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].rule_id, "unreferenced-data");
+        assert_eq!(issues[0].offset, Some(8 + header.len() as u64 + 4));
+    }
+}
@@ -0,0 +1,341 @@
+use std::{collections::HashSet, fmt, io::Read, path::Path};
+
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use super::{MAX_HEADER_SIZE, METADATA_KEY};
+use crate::core::validate::{violation, Violation};
+
+// the entries of the header in the order they are declared, duplicates included
+struct Entries(Vec<(String, serde_json::Value)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TensorEntry {
+    dtype: String,
+    shape: Vec<u64>,
+    data_offsets: (u64, u64),
+}
+
+// size in bytes of an element of the dtype
+fn dtype_size(dtype: &str) -> Option<u64> {
+    match dtype {
+        "BOOL" | "U8" | "I8" | "F8_E5M2" | "F8_E4M3" => Some(1),
+        "I16" | "U16" | "F16" | "BF16" => Some(2),
+        "I32" | "U32" | "F32" => Some(4),
+        "I64" | "U64" | "F64" => Some(8),
+        _ => None,
+    }
+}
+
+// a tensor whose data starts at begin and ends at end, relative to the data section
+struct Extent<'a> {
+    name: &'a str,
+    begin: u64,
+    end: u64,
+}
+
+fn check_entries(
+    entries: &[(String, serde_json::Value)],
+    data_start: u64,
+    data_size: u64,
+) -> Vec<Violation> {
+    let mut violations = vec![];
+    let mut seen = HashSet::new();
+    let mut extents: Vec<Extent> = vec![];
+
+    for (name, value) in entries {
+        if !seen.insert(name.as_str()) {
+            violations.push(violation(
+                "duplicate-tensor",
+                format!("{} is declared more than once", name),
+                None,
+            ));
+        }
+
+        if name == METADATA_KEY {
+            let is_valid = value
+                .as_object()
+                .is_some_and(|metadata| metadata.values().all(serde_json::Value::is_string));
+            if !is_valid {
+                violations.push(violation(
+                    "invalid-metadata",
+                    format!("{} is not a map of strings", METADATA_KEY),
+                    None,
+                ));
+            }
+            continue;
+        }
+
+        let tensor: TensorEntry = match serde_json::from_value(value.clone()) {
+            Ok(tensor) => tensor,
+            Err(e) => {
+                violations.push(violation(
+                    "invalid-tensor",
+                    format!("tensor {} can't be parsed: {}", name, e),
+                    None,
+                ));
+                continue;
+            }
+        };
+
+        let (begin, end) = tensor.data_offsets;
+        let offset = data_start.checked_add(begin);
+        if begin > end {
+            violations.push(violation(
+                "invalid-tensor",
+                format!(
+                    "tensor {} ends at {} before starting at {}",
+                    name, end, begin
+                ),
+                offset,
+            ));
+            continue;
+        }
+
+        match dtype_size(&tensor.dtype) {
+            Some(size) => {
+                let expected = tensor
+                    .shape
+                    .iter()
+                    .try_fold(size, |total, dimension| total.checked_mul(*dimension));
+                if expected != Some(end - begin) {
+                    violations.push(violation(
+                        "invalid-tensor",
+                        format!(
+                            "tensor {} of shape {:?} and dtype {} has {} bytes of data",
+                            name,
+                            tensor.shape,
+                            tensor.dtype,
+                            end - begin
+                        ),
+                        offset,
+                    ));
+                }
+            }
+            None => violations.push(violation(
+                "invalid-tensor",
+                format!("tensor {} has unknown dtype {}", name, tensor.dtype),
+                offset,
+            )),
+        }
+
+        if end > data_size {
+            violations.push(violation(
+                "offset-out-of-bounds",
+                format!(
+                    "tensor {} ends at {}, after the {} bytes of data",
+                    name, end, data_size
+                ),
+                offset,
+            ));
+        }
+
+        if let Some(previous) = extents.last() {
+            if begin < previous.begin {
+                violations.push(violation(
+                    "non-monotonic-offsets",
+                    format!(
+                        "tensor {} starts at {}, before the previous tensor {} at {}",
+                        name, begin, previous.name, previous.begin
+                    ),
+                    offset,
+                ));
+            }
+        }
+        extents.push(Extent { name, begin, end });
+    }
+
+    // walk the data in order, the tensors must cover it exactly once
+    extents.sort_by_key(|extent| (extent.begin, extent.end));
+    let mut cursor = 0;
+    let mut last = None;
+    for extent in &extents {
+        if extent.begin > cursor {
+            violations.push(violation(
+                "data-gap",
+                format!(
+                    "{} bytes before tensor {} are not part of any tensor",
+                    extent.begin - cursor,
+                    extent.name
+                ),
+                data_start.checked_add(cursor),
+            ));
+        } else if extent.begin < cursor {
+            violations.push(violation(
+                "overlapping-data",
+                format!(
+                    "tensor {} overlaps tensor {} by {} bytes",
+                    extent.name,
+                    last.unwrap_or_default(),
+                    cursor.min(extent.end) - extent.begin
+                ),
+                data_start.checked_add(extent.begin),
+            ));
+        }
+        if extent.end > cursor {
+            cursor = extent.end;
+            last = Some(extent.name);
+        }
+    }
+    if cursor < data_size {
+        violations.push(violation(
+            "trailing-data",
+            format!(
+                "{} bytes after the last tensor are not part of any tensor",
+                data_size - cursor
+            ),
+            data_start.checked_add(cursor),
+        ));
+    }
+
+    violations
+}
+
+/// Strictly validates the structure of a safetensors file, reading its header only.
+pub(crate) fn validate(file_path: &Path) -> anyhow::Result<Vec<Violation>> {
+    let mut file = std::fs::File::open(file_path)?;
+    let file_size = file.metadata()?.len();
+
+    let mut header_size = [0u8; 8];
+    if file.read_exact(&mut header_size).is_err() {
+        return Ok(vec![violation(
+            "invalid-header",
+            format!("file of {} bytes is too small", file_size),
+            None,
+        )]);
+    }
+    let header_size = u64::from_le_bytes(header_size);
+    if header_size > MAX_HEADER_SIZE || header_size > file_size - 8 {
+        return Ok(vec![violation(
+            "invalid-header",
+            format!(
+                "header of {} bytes exceeds the file of {} bytes",
+                header_size, file_size
+            ),
+            Some(0),
+        )]);
+    }
+
+    let mut header = vec![0u8; header_size as usize];
+    file.read_exact(&mut header)?;
+    let entries: Entries = match serde_json::from_slice(&header) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Ok(vec![violation(
+                "invalid-header",
+                format!("header is not a JSON object: {}", e),
+                Some(8),
+            )])
+        }
+    };
+
+    let data_start = 8 + header_size;
+    Ok(check_entries(
+        &entries.0,
+        data_start,
+        file_size - data_start,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_header(header: &str, data_size: usize) -> Vec<&'static str> {
+        let mut content = (header.len() as u64).to_le_bytes().to_vec();
+        content.extend(header.as_bytes());
+        content.extend(vec![0u8; data_size]);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.safetensors");
+        std::fs::write(&file_path, content).unwrap();
+
+        validate(&file_path)
+            .unwrap()
+            .iter()
+            .map(|violation| violation.rule_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_valid() {
+        assert!(validate_header(
+            r#"{"__metadata__":{"a":"b"},"a":{"dtype":"F32","shape":[2],"data_offsets":[0,8]},"b":{"dtype":"U8","shape":[],"data_offsets":[8,9]}}"#,
+            9
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_validate_violations() {
+        // overlap, then a gap before c and trailing bytes
+        assert_eq!(
+            validate_header(
+                r#"{"a":{"dtype":"U8","shape":[4],"data_offsets":[0,4]},"b":{"dtype":"U8","shape":[4],"data_offsets":[2,6]},"c":{"dtype":"U8","shape":[2],"data_offsets":[8,10]}}"#,
+                12
+            ),
+            vec!["overlapping-data", "data-gap", "trailing-data"]
+        );
+        // declared in reverse order, the second a is the one loaded
+        assert_eq!(
+            validate_header(
+                r#"{"a":{"dtype":"U8","shape":[4],"data_offsets":[4,8]},"a":{"dtype":"U8","shape":[4],"data_offsets":[0,4]}}"#,
+                8
+            ),
+            vec!["duplicate-tensor", "non-monotonic-offsets"]
+        );
+        // beyond the end of the file, size not matching the shape, unknown dtype
+        assert_eq!(
+            validate_header(
+                r#"{"a":{"dtype":"U8","shape":[4],"data_offsets":[0,8]},"b":{"dtype":"X","shape":[],"data_offsets":[8,8]},"__metadata__":{"a":1}}"#,
+                4
+            ),
+            vec![
+                "invalid-tensor",
+                "offset-out-of-bounds",
+                "invalid-tensor",
+                "offset-out-of-bounds",
+                "invalid-metadata"
+            ]
+        );
+        assert_eq!(validate_header("[]", 0), vec!["invalid-header"]);
+    }
+
+    #[test]
+    fn test_validate_truncated() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.safetensors");
+        std::fs::write(&file_path, 1000u64.to_le_bytes()).unwrap();
+
+        let violations = validate(&file_path).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule_id, "invalid-header");
+    }
+}
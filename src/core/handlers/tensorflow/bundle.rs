@@ -0,0 +1,234 @@
+use std::path::Path;
+
+use protobuf::Message;
+
+use super::protos::{BundleEntryProto, BundleHeaderProto};
+
+// https://github.com/google/leveldb/blob/main/doc/table_format.md
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const FOOTER_SIZE: usize = 48;
+const BLOCK_TRAILER_SIZE: usize = 5;
+
+/// A tensor bundle index (variables/variables.index), this is a LevelDB-style
+/// sorted string table where the empty key maps to the bundle header and every
+/// other key is a tensor name mapping to its entry.
+pub(crate) struct BundleIndex {
+    pub header: BundleHeaderProto,
+    pub entries: Vec<(String, BundleEntryProto)>,
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> anyhow::Result<u64> {
+    let mut result: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *data
+            .get(*pos)
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of data while reading varint"))?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    anyhow::bail!("varint too long")
+}
+
+fn read_block_handle(data: &[u8], pos: &mut usize) -> anyhow::Result<(usize, usize)> {
+    let offset = read_varint(data, pos)? as usize;
+    let size = read_varint(data, pos)? as usize;
+    Ok((offset, size))
+}
+
+fn read_block(data: &[u8], handle: (usize, usize)) -> anyhow::Result<&[u8]> {
+    let (offset, size) = handle;
+    let end = offset
+        .checked_add(size)
+        .and_then(|end| end.checked_add(BLOCK_TRAILER_SIZE))
+        .ok_or_else(|| anyhow::anyhow!("block handle overflow"))?;
+    if end > data.len() {
+        anyhow::bail!(
+            "block at offset {} with size {} is out of bounds",
+            offset,
+            size
+        );
+    }
+
+    let compression = data[offset + size];
+    if compression != 0 {
+        anyhow::bail!(
+            "compressed table blocks are not supported (type {})",
+            compression
+        );
+    }
+
+    Ok(&data[offset..offset + size])
+}
+
+fn block_entries(block: &[u8]) -> anyhow::Result<Vec<(Vec<u8>, &[u8])>> {
+    if block.len() < 4 {
+        anyhow::bail!("block too small");
+    }
+
+    let num_restarts = u32::from_le_bytes(block[block.len() - 4..].try_into()?) as usize;
+    let restarts_size = num_restarts
+        .checked_add(1)
+        .and_then(|n| n.checked_mul(4))
+        .ok_or_else(|| anyhow::anyhow!("invalid number of restarts"))?;
+    if restarts_size > block.len() {
+        anyhow::bail!("invalid number of restarts: {}", num_restarts);
+    }
+
+    let entries_end = block.len() - restarts_size;
+    let mut entries = vec![];
+    let mut pos = 0;
+    let mut key: Vec<u8> = vec![];
+
+    while pos < entries_end {
+        let shared = read_varint(block, &mut pos)? as usize;
+        let non_shared = read_varint(block, &mut pos)? as usize;
+        let value_size = read_varint(block, &mut pos)? as usize;
+
+        if shared > key.len() || pos + non_shared + value_size > entries_end {
+            anyhow::bail!("corrupted block entry at offset {}", pos);
+        }
+
+        key.truncate(shared);
+        key.extend_from_slice(&block[pos..pos + non_shared]);
+        pos += non_shared;
+
+        entries.push((key.clone(), &block[pos..pos + value_size]));
+        pos += value_size;
+    }
+
+    Ok(entries)
+}
+
+impl BundleIndex {
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < FOOTER_SIZE {
+            anyhow::bail!("file too small to be a tensor bundle index");
+        }
+
+        let footer = &data[data.len() - FOOTER_SIZE..];
+        let magic = u64::from_le_bytes(footer[FOOTER_SIZE - 8..].try_into()?);
+        if magic != TABLE_MAGIC {
+            anyhow::bail!("invalid tensor bundle index magic: {:x}", magic);
+        }
+
+        let mut pos = 0;
+        // metaindex block, unused
+        let _ = read_block_handle(footer, &mut pos)?;
+        let index_handle = read_block_handle(footer, &mut pos)?;
+
+        let mut header = BundleHeaderProto::new();
+        let mut entries = vec![];
+
+        for (_, handle_bytes) in block_entries(read_block(data, index_handle)?)? {
+            let mut pos = 0;
+            let handle = read_block_handle(handle_bytes, &mut pos)?;
+            for (key, value) in block_entries(read_block(data, handle)?)? {
+                if key.is_empty() {
+                    header = BundleHeaderProto::parse_from_bytes(value)?;
+                } else {
+                    entries.push((
+                        String::from_utf8_lossy(&key).to_string(),
+                        BundleEntryProto::parse_from_bytes(value)?,
+                    ));
+                }
+            }
+        }
+
+        Ok(Self { header, entries })
+    }
+
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn write_block(out: &mut Vec<u8>, entries: &[(&[u8], Vec<u8>)]) -> (usize, usize) {
+        let offset = out.len();
+        for (key, value) in entries {
+            write_varint(out, 0);
+            write_varint(out, key.len() as u64);
+            write_varint(out, value.len() as u64);
+            out.extend_from_slice(key);
+            out.extend_from_slice(value);
+        }
+        // single restart point at the beginning of the block
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        let size = out.len() - offset;
+        // no compression + dummy crc
+        out.extend_from_slice(&[0, 0, 0, 0, 0]);
+        (offset, size)
+    }
+
+    pub(crate) fn build_table(entries: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![];
+        let data_handle = write_block(&mut data, entries);
+
+        let mut handle = vec![];
+        write_varint(&mut handle, data_handle.0 as u64);
+        write_varint(&mut handle, data_handle.1 as u64);
+        let index_handle = write_block(&mut data, &[(b"~", handle)]);
+
+        let mut footer = vec![];
+        // empty metaindex
+        write_varint(&mut footer, 0);
+        write_varint(&mut footer, 0);
+        write_varint(&mut footer, index_handle.0 as u64);
+        write_varint(&mut footer, index_handle.1 as u64);
+        footer.resize(FOOTER_SIZE - 8, 0);
+        footer.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+
+        data.extend(footer);
+        data
+    }
+
+    #[test]
+    fn test_parse_bundle_index() {
+        let mut header = BundleHeaderProto::new();
+        header.num_shards = 1;
+
+        let mut entry = BundleEntryProto::new();
+        entry.dtype = 1;
+        entry.size = 16;
+        let mut shape = super::super::protos::TensorShapeProto::new();
+        for size in [2, 2] {
+            let mut dim = super::super::protos::tensor_shape_proto::Dim::new();
+            dim.size = size;
+            shape.dim.push(dim);
+        }
+        entry.shape = Some(shape).into();
+
+        let table = build_table(&[
+            (b"", header.write_to_bytes().unwrap()),
+            (b"dense/kernel", entry.write_to_bytes().unwrap()),
+        ]);
+
+        let index = BundleIndex::from_bytes(&table).unwrap();
+        assert_eq!(index.header.num_shards, 1);
+        assert_eq!(index.entries.len(), 1);
+        assert_eq!(index.entries[0].0, "dense/kernel");
+        assert_eq!(index.entries[0].1.size, 16);
+        assert_eq!(index.entries[0].1.shape.dim.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_bundle_index() {
+        assert!(BundleIndex::from_bytes(b"too small").is_err());
+        assert!(BundleIndex::from_bytes(&[0u8; 128]).is_err());
+    }
+}
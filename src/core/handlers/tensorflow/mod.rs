@@ -0,0 +1,541 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+mod bundle;
+mod protos;
+
+use glob::glob;
+use protobuf::Message;
+use rayon::prelude::*;
+
+use bundle::BundleIndex;
+use protos::{SavedModel, TensorInfo, TensorShapeProto};
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+const SAVED_MODEL_FILE_NAME: &str = "saved_model.pb";
+// used by object based checkpoints to store the serialized object graph, not an actual weight
+const OBJECT_GRAPH_KEY: &str = "_CHECKPOINTABLE_OBJECT_GRAPH";
+
+#[inline]
+fn data_type_bits(dtype: i32) -> usize {
+    // reference types are encoded as dtype + 100
+    match dtype % 100 {
+        1 => 32,   // DT_FLOAT
+        2 => 64,   // DT_DOUBLE
+        3 => 32,   // DT_INT32
+        4 => 8,    // DT_UINT8
+        5 => 16,   // DT_INT16
+        6 => 8,    // DT_INT8
+        7 => 8,    // DT_STRING (assuming 8 bits per character)
+        8 => 64,   // DT_COMPLEX64
+        9 => 64,   // DT_INT64
+        10 => 8,   // DT_BOOL
+        11 => 8,   // DT_QINT8
+        12 => 8,   // DT_QUINT8
+        13 => 32,  // DT_QINT32
+        14 => 16,  // DT_BFLOAT16
+        15 => 16,  // DT_QINT16
+        16 => 16,  // DT_QUINT16
+        17 => 16,  // DT_UINT16
+        18 => 128, // DT_COMPLEX128
+        19 => 16,  // DT_HALF
+        22 => 32,  // DT_UINT32
+        23 => 64,  // DT_UINT64
+        24 => 8,   // DT_FLOAT8_E5M2
+        25 => 8,   // DT_FLOAT8_E4M3FN
+        29 => 4,   // DT_INT4
+        30 => 4,   // DT_UINT4
+        _ => 0,    // DT_RESOURCE, DT_VARIANT and unknown types have no fixed size
+    }
+}
+
+#[inline]
+fn data_type_string(dtype: i32) -> &'static str {
+    match dtype % 100 {
+        1 => "FLOAT",
+        2 => "DOUBLE",
+        3 => "INT32",
+        4 => "UINT8",
+        5 => "INT16",
+        6 => "INT8",
+        7 => "STRING",
+        8 => "COMPLEX64",
+        9 => "INT64",
+        10 => "BOOL",
+        11 => "QINT8",
+        12 => "QUINT8",
+        13 => "QINT32",
+        14 => "BFLOAT16",
+        15 => "QINT16",
+        16 => "QUINT16",
+        17 => "UINT16",
+        18 => "COMPLEX128",
+        19 => "HALF",
+        20 => "RESOURCE",
+        21 => "VARIANT",
+        22 => "UINT32",
+        23 => "UINT64",
+        24 => "FLOAT8_E5M2",
+        25 => "FLOAT8_E4M3FN",
+        29 => "INT4",
+        30 => "UINT4",
+        _ => "UNKNOWN",
+    }
+}
+
+fn shape_of(shape: &TensorShapeProto) -> Vec<usize> {
+    // unknown dimensions (-1) are reported as 0
+    shape.dim.iter().map(|d| d.size.max(0) as usize).collect()
+}
+
+fn tensor_info_to_string(info: &TensorInfo) -> String {
+    let dims = info
+        .tensor_shape
+        .dim
+        .iter()
+        .map(|d| {
+            if d.size < 0 {
+                "?".to_string()
+            } else {
+                d.size.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "{} {} [{}]",
+        info.name(),
+        data_type_string(info.dtype),
+        dims.join(", ")
+    )
+}
+
+/// Returns the SavedModel directory if the path is either a saved_model.pb file or a directory containing one.
+fn saved_model_dir(file_path: &Path) -> Option<PathBuf> {
+    if file_path.is_dir() {
+        if file_path.join(SAVED_MODEL_FILE_NAME).is_file() {
+            return Some(file_path.to_path_buf());
+        }
+    } else if file_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .eq_ignore_ascii_case(SAVED_MODEL_FILE_NAME)
+    {
+        return Some(
+            file_path
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default(),
+        );
+    }
+
+    None
+}
+
+pub(crate) struct TensorFlowHandler;
+
+impl TensorFlowHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for TensorFlowHandler {
+    fn file_type(&self) -> FileType {
+        FileType::TensorFlow
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        saved_model_dir(file_path).is_some()
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let base_path = saved_model_dir(file_path)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a SavedModel", file_path.display()))?;
+
+        // the whole SavedModel directory, including variables and assets
+        let mut paths = vec![];
+        for entry in glob(base_path.join("**/*").to_str().unwrap())? {
+            let path = entry?;
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let base_path = saved_model_dir(file_path).unwrap_or_else(|| {
+            // format was forced, assume the file is the saved model protobuf
+            file_path
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default()
+        });
+        let saved_model_path = if file_path.is_dir() {
+            base_path.join(SAVED_MODEL_FILE_NAME)
+        } else {
+            file_path.to_path_buf()
+        };
+
+        let mut inspection = Inspection::default();
+
+        let mut file = std::fs::File::open(&saved_model_path)?;
+
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.header_size = file.metadata()?.len() as usize;
+        inspection.file_size = self
+            .paths_to_sign(&saved_model_path)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|p| p.metadata().ok())
+            .map(|m| m.len())
+            .sum();
+
+        let saved_model: SavedModel = Message::parse_from_reader(&mut file)?;
+
+        inspection.file_type = FileType::TensorFlow;
+
+        let meta_graph = saved_model
+            .meta_graphs
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("SavedModel has no meta graphs"))?;
+
+        if !meta_graph.meta_info_def.tensorflow_version.is_empty() {
+            inspection.version = format!(
+                "{} (schema v{})",
+                meta_graph.meta_info_def.tensorflow_version, saved_model.saved_model_schema_version
+            );
+        } else {
+            inspection.version = format!("schema v{}", saved_model.saved_model_schema_version);
+        }
+
+        if !meta_graph.meta_info_def.tags.is_empty() {
+            inspection
+                .metadata
+                .insert("tags".to_string(), meta_graph.meta_info_def.tags.join(", "));
+        }
+
+        if saved_model.meta_graphs.len() > 1 {
+            inspection.metadata.insert(
+                "meta_graphs".to_string(),
+                saved_model.meta_graphs.len().to_string(),
+            );
+        }
+
+        for (sig_name, sig_def) in &meta_graph.signature_def {
+            if !sig_def.method_name.is_empty() {
+                inspection.metadata.insert(
+                    format!("signature_def.{}.method_name", sig_name),
+                    sig_def.method_name.clone(),
+                );
+            }
+            for (io_name, infos) in [("inputs", &sig_def.inputs), ("outputs", &sig_def.outputs)] {
+                for (key, info) in infos {
+                    inspection.metadata.insert(
+                        format!("signature_def.{}.{}.{}", sig_name, io_name, key),
+                        tensor_info_to_string(info),
+                    );
+                }
+            }
+        }
+
+        // weights are stored in the variables bundle, if present
+        let index_path = base_path.join("variables").join("variables.index");
+        let mut tensors: Vec<TensorDescriptor> = if index_path.is_file() {
+            let index = BundleIndex::from_path(&index_path)?;
+
+            inspection.metadata.insert(
+                "num_shards".to_string(),
+                index.header.num_shards.to_string(),
+            );
+
+            index
+                .entries
+                .par_iter()
+                .filter(|(name, _)| name != OBJECT_GRAPH_KEY)
+                .map(|(name, entry)| {
+                    let mut metadata = Metadata::new();
+                    metadata.insert("shard_id".to_string(), entry.shard_id.to_string());
+                    metadata.insert("offset".to_string(), entry.offset.to_string());
+
+                    TensorDescriptor {
+                        id: Some(name.to_string()),
+                        shape: shape_of(&entry.shape),
+                        dtype: data_type_string(entry.dtype).to_string(),
+                        size: entry.size as usize,
+                        metadata,
+                        stats: None,
+                        preview: None,
+                    }
+                })
+                .collect()
+        } else {
+            // frozen graphs store weights as constants
+            meta_graph
+                .graph_def
+                .node
+                .par_iter()
+                .filter(|node| node.op == "Const")
+                .filter_map(|node| {
+                    node.attr
+                        .get("value")
+                        .filter(|attr| attr.has_tensor())
+                        .map(|attr| (node, attr.tensor()))
+                })
+                .map(|(node, tensor)| {
+                    let shape = shape_of(&tensor.tensor_shape);
+                    let size = if !tensor.tensor_content.is_empty() {
+                        tensor.tensor_content.len()
+                    } else {
+                        (data_type_bits(tensor.dtype) * shape.iter().product::<usize>()) / 8
+                    };
+
+                    TensorDescriptor {
+                        id: Some(node.name.to_string()),
+                        shape,
+                        dtype: data_type_string(tensor.dtype).to_string(),
+                        size,
+                        metadata: Metadata::new(),
+                        stats: None,
+                        preview: None,
+                    }
+                })
+                .collect()
+        };
+
+        inspection.num_tensors = tensors.len();
+        inspection.data_size = tensors.par_iter().map(|t| t.size).sum::<usize>();
+
+        inspection.unique_shapes = tensors
+            .par_iter()
+            .map(|t| t.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = tensors
+            .par_iter()
+            .map(|t| t.dtype.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            tensors.retain(|t| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| t.id.as_ref().is_some_and(|id| id.contains(f)))
+            });
+            tensors.sort_by(|a, b| a.id.cmp(&b.id));
+            inspection.tensors = Some(tensors);
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_handler_for() {
+        let handler = TensorFlowHandler::new();
+
+        assert!(handler.is_handler_for(Path::new("saved_model.pb"), &Scope::Inspection));
+        assert!(handler.is_handler_for(Path::new("path/to/saved_model.pb"), &Scope::Signing));
+        assert!(handler.is_handler_for(Path::new("SAVED_MODEL.PB"), &Scope::Inspection));
+
+        assert!(!handler.is_handler_for(Path::new("model.pb"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new("model.onnx"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
+    }
+
+    #[test]
+    fn test_is_handler_for_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let handler = TensorFlowHandler::new();
+
+        assert!(!handler.is_handler_for(temp_dir.path(), &Scope::Inspection));
+
+        std::fs::write(temp_dir.path().join(SAVED_MODEL_FILE_NAME), b"").unwrap();
+
+        assert!(handler.is_handler_for(temp_dir.path(), &Scope::Inspection));
+    }
+
+    #[test]
+    fn test_paths_to_sign_includes_variables_and_assets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base_path = temp_dir.path();
+
+        std::fs::create_dir_all(base_path.join("variables")).unwrap();
+        std::fs::create_dir_all(base_path.join("assets")).unwrap();
+        std::fs::write(base_path.join(SAVED_MODEL_FILE_NAME), b"").unwrap();
+        std::fs::write(base_path.join("variables/variables.index"), b"").unwrap();
+        std::fs::write(
+            base_path.join("variables/variables.data-00000-of-00001"),
+            b"",
+        )
+        .unwrap();
+        std::fs::write(base_path.join("assets/vocab.txt"), b"").unwrap();
+
+        let handler = TensorFlowHandler::new();
+        let paths = handler
+            .paths_to_sign(&base_path.join(SAVED_MODEL_FILE_NAME))
+            .unwrap();
+
+        let mut names: Vec<String> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+
+        assert_eq!(
+            names,
+            vec![
+                "saved_model.pb",
+                "variables.data-00000-of-00001",
+                "variables.index",
+                "vocab.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inspect() {
+        use protos::{
+            meta_graph_def::MetaInfoDef, tensor_info, tensor_shape_proto::Dim, BundleEntryProto,
+            BundleHeaderProto, MetaGraphDef, SignatureDef,
+        };
+
+        let shape = |sizes: &[i64]| {
+            let mut shape = TensorShapeProto::new();
+            shape.dim = sizes
+                .iter()
+                .map(|size| {
+                    let mut dim = Dim::new();
+                    dim.size = *size;
+                    dim
+                })
+                .collect();
+            shape
+        };
+
+        let mut input = TensorInfo::new();
+        input.encoding = Some(tensor_info::Encoding::Name(
+            "serving_default_x:0".to_string(),
+        ));
+        input.dtype = 1;
+        input.tensor_shape = Some(shape(&[-1, 4])).into();
+        let mut signature = SignatureDef::new();
+        signature.method_name = "tensorflow/serving/predict".to_string();
+        signature.inputs.insert("x".to_string(), input);
+
+        let mut meta_info = MetaInfoDef::new();
+        meta_info.tags = vec!["serve".to_string()];
+        meta_info.tensorflow_version = "2.15.0".to_string();
+        let mut meta_graph = MetaGraphDef::new();
+        meta_graph.meta_info_def = Some(meta_info).into();
+        meta_graph
+            .signature_def
+            .insert("serving_default".to_string(), signature);
+        let mut saved_model = SavedModel::new();
+        saved_model.saved_model_schema_version = 1;
+        saved_model.meta_graphs.push(meta_graph);
+
+        let mut header = BundleHeaderProto::new();
+        header.num_shards = 1;
+        let mut kernel = BundleEntryProto::new();
+        kernel.dtype = 1;
+        kernel.size = 4 * 2 * 4;
+        kernel.shape = Some(shape(&[4, 2])).into();
+        let mut bias = BundleEntryProto::new();
+        bias.dtype = 1;
+        bias.size = 2 * 4;
+        bias.offset = 32;
+        bias.shape = Some(shape(&[2])).into();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base_path = temp_dir.path();
+        std::fs::create_dir_all(base_path.join("variables")).unwrap();
+        std::fs::write(
+            base_path.join(SAVED_MODEL_FILE_NAME),
+            saved_model.write_to_bytes().unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            base_path.join("variables/variables.index"),
+            bundle::tests::build_table(&[
+                (b"", header.write_to_bytes().unwrap()),
+                (b"dense/bias", bias.write_to_bytes().unwrap()),
+                (b"dense/kernel", kernel.write_to_bytes().unwrap()),
+                (
+                    OBJECT_GRAPH_KEY.as_bytes(),
+                    BundleEntryProto::new().write_to_bytes().unwrap(),
+                ),
+            ]),
+        )
+        .unwrap();
+        std::fs::write(
+            base_path.join("variables/variables.data-00000-of-00001"),
+            [0u8; 40],
+        )
+        .unwrap();
+
+        let inspection = TensorFlowHandler::new()
+            .inspect(base_path, DetailLevel::Full, None)
+            .unwrap();
+
+        assert!(inspection.file_type.is_tensorflow());
+        assert_eq!(inspection.version, "2.15.0 (schema v1)");
+        assert_eq!(inspection.metadata["tags"], "serve");
+        assert_eq!(
+            inspection.metadata["signature_def.serving_default.method_name"],
+            "tensorflow/serving/predict"
+        );
+        assert_eq!(
+            inspection.metadata["signature_def.serving_default.inputs.x"],
+            "serving_default_x:0 FLOAT [?, 4]"
+        );
+        assert_eq!(inspection.metadata["num_shards"], "1");
+        // the object graph isn't a weight
+        assert_eq!(inspection.num_tensors, 2);
+        assert_eq!(inspection.data_size, 40);
+        assert_eq!(inspection.unique_dtypes, vec!["FLOAT"]);
+        assert_eq!(inspection.unique_shapes, vec![vec![2], vec![4, 2]]);
+
+        let tensors = inspection.tensors.unwrap();
+        let ids: Vec<_> = tensors.iter().map(|t| t.id.as_deref().unwrap()).collect();
+        assert_eq!(ids, vec!["dense/bias", "dense/kernel"]);
+        assert_eq!(tensors[0].metadata["offset"], "32");
+
+        // the file size is the one of the whole directory
+        let file_size: u64 = ["saved_model.pb", "variables/variables.index"]
+            .iter()
+            .map(|name| base_path.join(name).metadata().unwrap().len())
+            .sum::<u64>()
+            + 40;
+        assert_eq!(inspection.file_size, file_size);
+    }
+}
@@ -0,0 +1,5 @@
+mod inner {
+    include!(concat!(env!("OUT_DIR"), "/tensorflow-protos/mod.rs"));
+}
+
+pub use inner::tensorflow::*;
@@ -0,0 +1,375 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+// serialized engines start with either of these magic values depending on the TensorRT version
+const ENGINE_MAGICS: [&[u8; 4]; 2] = [b"ptrt", b"ftrt"];
+const ENGINE_HEADER_SIZE: usize = 32;
+const PRECISIONS: [&str; 7] = ["FP32", "FP16", "BF16", "FP8", "INT8", "INT4", "INT32"];
+
+/// Fixed size header of a serialized TensorRT engine (plan). The format is not
+/// publicly documented so everything past the magic is parsed on a best-effort basis:
+///
+/// * 0..4: magic, `ptrt` or `ftrt`
+/// * 4..8: serialization version, little endian
+/// * 8..16: size of the serialized payload, little endian
+/// * 24..28: major, minor, patch and build of the TensorRT builder
+///
+/// These offsets don't come from a specification and the tests only cover synthetic headers,
+/// not engines built by TensorRT itself: the builder version is only reported when its major is
+/// plausible and the payload size when it fits in the file.
+#[derive(Debug)]
+struct EngineHeader {
+    magic: [u8; 4],
+    serialization_version: u32,
+    payload_size: u64,
+    builder_version: Option<String>,
+}
+
+impl EngineHeader {
+    fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < ENGINE_HEADER_SIZE {
+            anyhow::bail!("file too small to be a TensorRT engine");
+        }
+
+        let magic: [u8; 4] = data[0..4].try_into()?;
+        if !ENGINE_MAGICS.iter().any(|m| **m == magic) {
+            anyhow::bail!("invalid TensorRT engine magic: {}", hex::encode(magic));
+        }
+
+        let serialization_version = u32::from_le_bytes(data[4..8].try_into()?);
+        let payload_size = u64::from_le_bytes(data[8..16].try_into()?);
+        // major, minor, patch, build of the TensorRT library used to build the engine
+        let (major, minor, patch, build) = (data[24], data[25], data[26], data[27]);
+        let builder_version = if (5..=20).contains(&major) {
+            Some(format!("{}.{}.{}.{}", major, minor, patch, build))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            magic,
+            serialization_version,
+            payload_size,
+            builder_version,
+        })
+    }
+}
+
+/// Binding as reported by `trtexec --exportLayerInfo=<engine>.json --profilingVerbosity=detailed`.
+#[derive(Debug, Deserialize)]
+struct LayerTensor {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Dimensions", default)]
+    dimensions: Vec<i64>,
+    #[serde(rename = "Format/Datatype", default)]
+    format: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LayerInfo {
+    #[serde(rename = "Inputs", default)]
+    inputs: Vec<LayerTensor>,
+    #[serde(rename = "Outputs", default)]
+    outputs: Vec<LayerTensor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngineInfo {
+    #[serde(rename = "Layers", default)]
+    layers: Vec<serde_json::Value>,
+    #[serde(rename = "Bindings", default)]
+    bindings: Vec<String>,
+}
+
+fn precision_of(format: &str) -> Option<&'static str> {
+    PRECISIONS
+        .iter()
+        .find(|p| format.to_ascii_uppercase().contains(*p))
+        .copied()
+}
+
+fn precision_bits(precision: &str) -> usize {
+    match precision {
+        "FP32" | "INT32" => 32,
+        "FP16" | "BF16" => 16,
+        "FP8" | "INT8" => 8,
+        "INT4" => 4,
+        _ => 0,
+    }
+}
+
+fn layer_info_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+fn build_tensor_descriptor(tensor: &LayerTensor, direction: &str) -> TensorDescriptor {
+    let precision = precision_of(&tensor.format).unwrap_or("UNKNOWN");
+    // dynamic dimensions (-1) are reported as 0
+    let shape: Vec<usize> = tensor
+        .dimensions
+        .iter()
+        .map(|d| (*d).max(0) as usize)
+        .collect();
+
+    let mut metadata = Metadata::new();
+    metadata.insert("binding".to_string(), direction.to_string());
+    if !tensor.format.is_empty() {
+        metadata.insert("format".to_string(), tensor.format.clone());
+    }
+
+    TensorDescriptor {
+        id: Some(tensor.name.clone()),
+        size: (precision_bits(precision) * shape.iter().product::<usize>()) / 8,
+        shape,
+        dtype: precision.to_string(),
+        metadata,
+        stats: None,
+        preview: None,
+    }
+}
+
+pub(crate) struct TensorRTHandler;
+
+impl TensorRTHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for TensorRTHandler {
+    fn file_type(&self) -> FileType {
+        FileType::TensorRT
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        let file_ext = file_path
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        file_ext == "engine" || file_ext == "plan" || file_ext == "trt"
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        // the layer information the bindings are read from is signed along with the engine,
+        // so that it can't be swapped without breaking the signature
+        let info_path = layer_info_path(file_path);
+        if info_path.is_file() {
+            Ok(vec![file_path.to_path_buf(), info_path])
+        } else {
+            Ok(vec![file_path.to_path_buf()])
+        }
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let mut inspection = Inspection::default();
+
+        let file = std::fs::File::open(file_path)?;
+        let buffer = unsafe {
+            memmap2::MmapOptions::new()
+                .len(ENGINE_HEADER_SIZE.min(file.metadata()?.len() as usize))
+                .map(&file)?
+        };
+
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.file_size = file.metadata()?.len();
+
+        let header = EngineHeader::parse(&buffer)?;
+
+        inspection.file_type = FileType::TensorRT;
+        inspection.version = header
+            .builder_version
+            .clone()
+            .unwrap_or_else(|| format!("plan v{}", header.serialization_version));
+        inspection.header_size = ENGINE_HEADER_SIZE;
+        inspection.data_size =
+            if header.payload_size > 0 && header.payload_size <= inspection.file_size {
+                header.payload_size as usize
+            } else {
+                inspection.file_size as usize - ENGINE_HEADER_SIZE
+            };
+
+        inspection.metadata.insert(
+            "magic".to_string(),
+            String::from_utf8_lossy(&header.magic).to_string(),
+        );
+        inspection.metadata.insert(
+            "serialization_version".to_string(),
+            header.serialization_version.to_string(),
+        );
+
+        // bindings and precisions are not recoverable from the plan itself without
+        // deserializing it on a GPU, use the trtexec layer information if available.
+        let info_path = layer_info_path(file_path);
+        let mut tensors = vec![];
+        if info_path.is_file() {
+            let info: EngineInfo = serde_json::from_str(&std::fs::read_to_string(&info_path)?)?;
+            let layers: Vec<LayerInfo> = info
+                .layers
+                .into_iter()
+                .filter_map(|layer| serde_json::from_value(layer).ok())
+                .collect();
+
+            inspection
+                .metadata
+                .insert("layers".to_string(), layers.len().to_string());
+            inspection
+                .metadata
+                .insert("layer_info".to_string(), info_path.display().to_string());
+
+            let mut precisions = BTreeSet::new();
+            let mut seen = HashSet::new();
+            for layer in &layers {
+                for (direction, layer_tensors) in
+                    [("input", &layer.inputs), ("output", &layer.outputs)]
+                {
+                    for tensor in layer_tensors {
+                        if let Some(precision) = precision_of(&tensor.format) {
+                            precisions.insert(precision);
+                        }
+                        if info.bindings.contains(&tensor.name) && seen.insert(&tensor.name) {
+                            tensors.push(build_tensor_descriptor(tensor, direction));
+                        }
+                    }
+                }
+            }
+
+            if !precisions.is_empty() {
+                inspection.metadata.insert(
+                    "precisions".to_string(),
+                    precisions.into_iter().collect::<Vec<_>>().join(", "),
+                );
+            }
+        }
+
+        inspection.num_tensors = tensors.len();
+        inspection.unique_shapes = tensors
+            .iter()
+            .map(|t| t.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = tensors
+            .iter()
+            .map(|t| t.dtype.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            tensors.retain(|t| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| t.id.as_ref().is_some_and(|id| id.contains(f)))
+            });
+            inspection.tensors = Some(tensors);
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_header(magic: &[u8; 4], major: u8) -> Vec<u8> {
+        let mut data = vec![0u8; ENGINE_HEADER_SIZE];
+        data[0..4].copy_from_slice(magic);
+        data[4..8].copy_from_slice(&239u32.to_le_bytes());
+        data[8..16].copy_from_slice(&1024u64.to_le_bytes());
+        data[24..28].copy_from_slice(&[major, 6, 1, 6]);
+        data
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = EngineHeader::parse(&build_header(b"ftrt", 8)).unwrap();
+        assert_eq!(&header.magic, b"ftrt");
+        assert_eq!(header.serialization_version, 239);
+        assert_eq!(header.payload_size, 1024);
+        assert_eq!(header.builder_version, Some("8.6.1.6".to_string()));
+
+        // implausible version bytes are not reported
+        let header = EngineHeader::parse(&build_header(b"ptrt", 0xff)).unwrap();
+        assert!(header.builder_version.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_header() {
+        assert!(EngineHeader::parse(b"ftrt").is_err());
+        assert!(EngineHeader::parse(&build_header(b"nope", 8)).is_err());
+    }
+
+    #[test]
+    fn test_paths_to_sign() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine_path = dir.path().join("model.engine");
+        std::fs::write(&engine_path, build_header(b"ftrt", 8)).unwrap();
+
+        let handler = TensorRTHandler::new();
+        assert_eq!(
+            handler.paths_to_sign(&engine_path).unwrap(),
+            vec![engine_path.clone()]
+        );
+
+        // the trtexec layer information is signed as well
+        let info_path = dir.path().join("model.engine.json");
+        std::fs::write(&info_path, r#"{"Layers":[],"Bindings":[]}"#).unwrap();
+        assert_eq!(
+            handler.paths_to_sign(&engine_path).unwrap(),
+            vec![engine_path, info_path]
+        );
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let handler = TensorRTHandler::new();
+
+        assert!(handler.is_handler_for(Path::new("model.engine"), &Scope::Inspection));
+        assert!(handler.is_handler_for(Path::new("path/to/model.plan"), &Scope::Signing));
+        assert!(handler.is_handler_for(Path::new("MODEL.TRT"), &Scope::Inspection));
+
+        assert!(!handler.is_handler_for(Path::new("model.onnx"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new("engine.txt"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
+    }
+
+    #[test]
+    fn test_precision_of() {
+        assert_eq!(precision_of("Row major linear FP32"), Some("FP32"));
+        assert_eq!(
+            precision_of("Channel major FP16 format where channel % 2 == 0"),
+            Some("FP16")
+        );
+        assert_eq!(precision_of("Int8 NC/32HW32"), Some("INT8"));
+        assert_eq!(precision_of("unknown"), None);
+    }
+}
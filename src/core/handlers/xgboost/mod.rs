@@ -0,0 +1,393 @@
+use std::{
+    collections::HashSet,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+mod ubjson;
+
+use serde_json::Value;
+
+use crate::core::{DetailLevel, FileType, Inspection, Metadata, TensorDescriptor};
+
+use super::{Handler, Scope};
+
+// how many bytes of a .json file to look at when searching for the learner object
+const SNIFF_SIZE: usize = 4096;
+
+fn extension_of(file_path: &Path) -> String {
+    file_path
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// Returns true if the json file looks like a model saved with `Booster.save_model`.
+fn is_xgboost_json(file_path: &Path) -> bool {
+    let Ok(file) = std::fs::File::open(file_path) else {
+        return false;
+    };
+    let mut head = vec![];
+    if file.take(SNIFF_SIZE as u64).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    String::from_utf8_lossy(&head).contains("\"learner\"")
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(value_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// Flattens the (possibly nested) parameters object into prefixed metadata entries.
+fn flatten_params(prefix: &str, value: &Value, metadata: &mut Metadata) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                flatten_params(&format!("{}.{}", prefix, key), value, metadata);
+            }
+        }
+        other => {
+            metadata.insert(prefix.to_string(), value_to_string(other));
+        }
+    }
+}
+
+fn build_tree_descriptor(index: usize, tree: &Value, group: Option<&Value>) -> TensorDescriptor {
+    // split conditions hold one 32 bits float per node, leaf values for leaves
+    let num_nodes = tree["split_conditions"].as_array().map_or_else(
+        || {
+            tree["tree_param"]["num_nodes"]
+                .as_str()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0)
+        },
+        |conditions| conditions.len(),
+    );
+
+    let mut metadata = Metadata::new();
+    if let Some(children) = tree["left_children"].as_array() {
+        let leaves = children.iter().filter(|c| c.as_i64() == Some(-1)).count();
+        metadata.insert("leaves".to_string(), leaves.to_string());
+    }
+    if let Some(group) = group {
+        metadata.insert("group".to_string(), value_to_string(group));
+    }
+
+    TensorDescriptor {
+        id: Some(format!("trees.{}", index)),
+        shape: vec![num_nodes],
+        dtype: "F32".to_string(),
+        size: num_nodes * 4,
+        metadata,
+        stats: None,
+        preview: None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct Model {
+    version: String,
+    booster: String,
+    metadata: Metadata,
+    tensors: Vec<TensorDescriptor>,
+}
+
+fn parse_model(model: &Value) -> anyhow::Result<Model> {
+    let learner = model
+        .get("learner")
+        .ok_or_else(|| anyhow::anyhow!("not a XGBoost model: missing learner object"))?;
+
+    let mut parsed = Model {
+        version: model["version"]
+            .as_array()
+            .map(|v| {
+                v.iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let booster = &learner["gradient_booster"];
+    parsed.booster = booster["name"].as_str().unwrap_or("unknown").to_string();
+    parsed
+        .metadata
+        .insert("booster".to_string(), parsed.booster.clone());
+
+    if let Some(objective) = learner["objective"]["name"].as_str() {
+        parsed
+            .metadata
+            .insert("objective".to_string(), objective.to_string());
+    }
+    if let Value::Object(objective) = &learner["objective"] {
+        for (key, value) in objective.iter().filter(|(k, _)| *k != "name") {
+            flatten_params(&format!("objective.{}", key), value, &mut parsed.metadata);
+        }
+    }
+
+    flatten_params(
+        "learner_model_param",
+        &learner["learner_model_param"],
+        &mut parsed.metadata,
+    );
+    if let Some(num_features) = learner["learner_model_param"]["num_feature"].as_str() {
+        parsed
+            .metadata
+            .insert("num_features".to_string(), num_features.to_string());
+    }
+
+    if let Some(names) = learner["feature_names"].as_array() {
+        if !names.is_empty() {
+            parsed.metadata.insert(
+                "feature_names".to_string(),
+                value_to_string(&Value::Array(names.clone())),
+            );
+        }
+    }
+
+    if let Value::Object(attributes) = &learner["attributes"] {
+        for (key, value) in attributes {
+            parsed
+                .metadata
+                .insert(format!("attributes.{}", key), value_to_string(value));
+        }
+    }
+
+    // dart boosters wrap a regular tree booster
+    let tree_booster = if booster.get("gbtree").is_some() {
+        &booster["gbtree"]
+    } else {
+        booster
+    };
+
+    match parsed.booster.as_str() {
+        "gblinear" => {
+            if let Some(weights) = booster["model"]["weights"].as_array() {
+                parsed.tensors.push(TensorDescriptor {
+                    id: Some("weights".to_string()),
+                    shape: vec![weights.len()],
+                    dtype: "F32".to_string(),
+                    size: weights.len() * 4,
+                    metadata: Metadata::new(),
+                    stats: None,
+                    preview: None,
+                });
+            }
+        }
+        _ => {
+            let model = &tree_booster["model"];
+            flatten_params(
+                "gbtree_model_param",
+                &model["gbtree_model_param"],
+                &mut parsed.metadata,
+            );
+
+            let tree_info = model["tree_info"].as_array();
+            if let Some(trees) = model["trees"].as_array() {
+                parsed
+                    .metadata
+                    .insert("num_trees".to_string(), trees.len().to_string());
+                for (index, tree) in trees.iter().enumerate() {
+                    let group = tree_info.and_then(|info| info.get(index));
+                    parsed
+                        .tensors
+                        .push(build_tree_descriptor(index, tree, group));
+                }
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+pub(crate) struct XGBoostHandler;
+
+impl XGBoostHandler {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Handler for XGBoostHandler {
+    fn file_type(&self) -> FileType {
+        FileType::XGBoost
+    }
+
+    fn is_handler_for(&self, file_path: &Path, _scope: &Scope) -> bool {
+        match extension_of(file_path).as_str() {
+            "ubj" => true,
+            "json" => is_xgboost_json(file_path),
+            _ => false,
+        }
+    }
+
+    fn paths_to_sign(&self, file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        // models are self contained
+        Ok(vec![file_path.to_path_buf()])
+    }
+
+    fn inspect(
+        &self,
+        file_path: &Path,
+        detail: DetailLevel,
+        filter: Option<String>,
+    ) -> anyhow::Result<Inspection> {
+        let mut inspection = Inspection::default();
+
+        let data = std::fs::read(file_path)?;
+        let (encoding, value) = if extension_of(file_path) == "ubj" {
+            ("ubjson", ubjson::from_slice(&data)?)
+        } else {
+            ("json", serde_json::from_slice(&data)?)
+        };
+        let model = parse_model(&value)?;
+
+        inspection.file_path = file_path.canonicalize()?;
+        inspection.file_type = FileType::XGBoost;
+        inspection.file_size = data.len() as u64;
+        inspection.version = model.version;
+        inspection.metadata = model.metadata;
+        inspection
+            .metadata
+            .insert("encoding".to_string(), encoding.to_string());
+
+        let mut tensors = model.tensors;
+
+        inspection.num_tensors = tensors.len();
+        inspection.data_size = tensors.iter().map(|t| t.size).sum::<usize>();
+
+        inspection.unique_shapes = tensors
+            .iter()
+            .map(|t| t.shape.clone())
+            .filter(|shape| !shape.is_empty())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // sort shapes by volume
+        inspection.unique_shapes.sort_by(|a, b| {
+            let size_a: usize = a.iter().product();
+            let size_b: usize = b.iter().product();
+            size_a.cmp(&size_b)
+        });
+
+        inspection.unique_dtypes = tensors
+            .iter()
+            .map(|t| t.dtype.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if matches!(detail, DetailLevel::Full) {
+            tensors.retain(|t| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| t.id.as_ref().is_some_and(|id| id.contains(f)))
+            });
+            inspection.tensors = Some(tensors);
+        }
+
+        Ok(inspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_json() -> Value {
+        serde_json::json!({
+            "learner": {
+                "attributes": {"best_iteration": "1"},
+                "feature_names": ["age", "income"],
+                "gradient_booster": {
+                    "name": "gbtree",
+                    "model": {
+                        "gbtree_model_param": {"num_parallel_tree": "1", "num_trees": "2"},
+                        "tree_info": [0, 0],
+                        "trees": [
+                            {
+                                "left_children": [1, -1, -1],
+                                "split_conditions": [0.5, 0.1, -0.2],
+                                "tree_param": {"num_nodes": "3"}
+                            },
+                            {
+                                "left_children": [-1],
+                                "split_conditions": [0.3],
+                                "tree_param": {"num_nodes": "1"}
+                            }
+                        ]
+                    }
+                },
+                "learner_model_param": {"base_score": "5E-1", "num_class": "0", "num_feature": "2"},
+                "objective": {"name": "binary:logistic", "reg_loss_param": {"scale_pos_weight": "1"}}
+            },
+            "version": [2, 0, 3]
+        })
+    }
+
+    #[test]
+    fn test_parse_model() {
+        let model = parse_model(&model_json()).unwrap();
+
+        assert_eq!(model.version, "2.0.3");
+        assert_eq!(model.booster, "gbtree");
+        assert_eq!(model.metadata.get("objective").unwrap(), "binary:logistic");
+        assert_eq!(
+            model
+                .metadata
+                .get("objective.reg_loss_param.scale_pos_weight")
+                .unwrap(),
+            "1"
+        );
+        assert_eq!(model.metadata.get("num_trees").unwrap(), "2");
+        assert_eq!(model.metadata.get("num_features").unwrap(), "2");
+        assert_eq!(model.metadata.get("feature_names").unwrap(), "age, income");
+        assert_eq!(
+            model.metadata.get("attributes.best_iteration").unwrap(),
+            "1"
+        );
+
+        assert_eq!(model.tensors.len(), 2);
+        assert_eq!(model.tensors[0].shape, vec![3]);
+        assert_eq!(model.tensors[0].metadata.get("leaves").unwrap(), "2");
+        assert_eq!(model.tensors[1].size, 4);
+    }
+
+    #[test]
+    fn test_parse_invalid_model() {
+        assert!(parse_model(&serde_json::json!({"weights": []})).is_err());
+    }
+
+    #[test]
+    fn test_is_handler_for() {
+        let handler = XGBoostHandler::new();
+
+        assert!(handler.is_handler_for(Path::new("model.ubj"), &Scope::Inspection));
+        assert!(handler.is_handler_for(Path::new("path/to/MODEL.UBJ"), &Scope::Signing));
+        assert!(!handler.is_handler_for(Path::new("model.txt"), &Scope::Inspection));
+        assert!(!handler.is_handler_for(Path::new(""), &Scope::Inspection));
+
+        // json files are only claimed if they contain a learner
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("model.json");
+        std::fs::write(&model_path, model_json().to_string()).unwrap();
+        assert!(handler.is_handler_for(&model_path, &Scope::Inspection));
+
+        let config_path = temp_dir.path().join("config.json");
+        std::fs::write(&config_path, r#"{"model_type": "llama"}"#).unwrap();
+        assert!(!handler.is_handler_for(&config_path, &Scope::Inspection));
+    }
+}
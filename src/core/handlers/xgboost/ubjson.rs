@@ -0,0 +1,206 @@
+// Universal Binary JSON decoder, as used by XGBoost for .ubj model files.
+//
+// https://ubjson.org/type-reference/
+
+use serde_json::{Map, Number, Value};
+
+// nesting limit to avoid stack exhaustion on malicious files
+const MAX_DEPTH: usize = 128;
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(size)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of data at offset {}", self.pos))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn marker(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.buf.get(self.pos).copied()
+    }
+
+    fn integer(&mut self, marker: u8) -> anyhow::Result<i64> {
+        Ok(match marker {
+            b'i' => self.take(1)?[0] as i8 as i64,
+            b'U' => self.take(1)?[0] as i64,
+            b'I' => i16::from_be_bytes(self.take(2)?.try_into()?) as i64,
+            b'l' => i32::from_be_bytes(self.take(4)?.try_into()?) as i64,
+            b'L' => i64::from_be_bytes(self.take(8)?.try_into()?),
+            _ => anyhow::bail!("expected an integer marker, got '{}'", marker as char),
+        })
+    }
+
+    fn length(&mut self) -> anyhow::Result<usize> {
+        let marker = self.marker()?;
+        let len = self.integer(marker)?;
+        if len < 0 {
+            anyhow::bail!("negative length {}", len);
+        }
+        Ok(len as usize)
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let len = self.length()?;
+        Ok(String::from_utf8_lossy(self.take(len)?).to_string())
+    }
+
+    /// Parses the optional type and count of an optimized container.
+    fn container_header(&mut self) -> anyhow::Result<(Option<u8>, Option<usize>)> {
+        let mut value_type = None;
+        if self.peek() == Some(b'$') {
+            self.pos += 1;
+            value_type = Some(self.marker()?);
+        }
+        let mut count = None;
+        if self.peek() == Some(b'#') {
+            self.pos += 1;
+            count = Some(self.length()?);
+        } else if value_type.is_some() {
+            anyhow::bail!("typed container without count at offset {}", self.pos);
+        }
+        // refuse counts that can't possibly be backed by the remaining data
+        if let Some(count) = count {
+            if count > self.buf.len() - self.pos {
+                anyhow::bail!("container count {} exceeds the data size", count);
+            }
+        }
+        Ok((value_type, count))
+    }
+
+    fn value(&mut self, depth: usize) -> anyhow::Result<Value> {
+        let marker = self.marker()?;
+        self.value_of(marker, depth)
+    }
+
+    fn value_of(&mut self, marker: u8, depth: usize) -> anyhow::Result<Value> {
+        if depth > MAX_DEPTH {
+            anyhow::bail!("maximum nesting depth exceeded");
+        }
+
+        Ok(match marker {
+            b'Z' => Value::Null,
+            b'T' => Value::Bool(true),
+            b'F' => Value::Bool(false),
+            b'i' | b'U' | b'I' | b'l' | b'L' => Value::from(self.integer(marker)?),
+            b'd' => number(f32::from_be_bytes(self.take(4)?.try_into()?) as f64),
+            b'D' => number(f64::from_be_bytes(self.take(8)?.try_into()?)),
+            b'C' => Value::String((self.take(1)?[0] as char).to_string()),
+            b'S' | b'H' => Value::String(self.string()?),
+            b'[' => {
+                let (value_type, count) = self.container_header()?;
+                let mut items = vec![];
+                match count {
+                    Some(count) => {
+                        for _ in 0..count {
+                            items.push(match value_type {
+                                Some(value_type) => self.value_of(value_type, depth + 1)?,
+                                None => self.value(depth + 1)?,
+                            });
+                        }
+                    }
+                    None => loop {
+                        match self.marker()? {
+                            b']' => break,
+                            b'N' => continue,
+                            marker => items.push(self.value_of(marker, depth + 1)?),
+                        }
+                    },
+                }
+                Value::Array(items)
+            }
+            b'{' => {
+                let (value_type, count) = self.container_header()?;
+                let mut object = Map::new();
+                match count {
+                    Some(count) => {
+                        for _ in 0..count {
+                            let key = self.string()?;
+                            let value = match value_type {
+                                Some(value_type) => self.value_of(value_type, depth + 1)?,
+                                None => self.value(depth + 1)?,
+                            };
+                            object.insert(key, value);
+                        }
+                    }
+                    None => loop {
+                        if self.peek() == Some(b'}') {
+                            self.pos += 1;
+                            break;
+                        }
+                        let key = self.string()?;
+                        let value = self.value(depth + 1)?;
+                        object.insert(key, value);
+                    },
+                }
+                Value::Object(object)
+            }
+            _ => anyhow::bail!(
+                "invalid marker '{}' at offset {}",
+                marker as char,
+                self.pos - 1
+            ),
+        })
+    }
+}
+
+fn number(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+/// Decodes a UBJSON document into a JSON value.
+pub(crate) fn from_slice(buf: &[u8]) -> anyhow::Result<Value> {
+    let mut reader = Reader { buf, pos: 0 };
+    reader.value(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_object() {
+        // {"name": "gbtree", "num": 300, "ok": true}
+        let mut data = vec![b'{'];
+        data.extend_from_slice(b"U\x04nameSU\x06gbtree");
+        data.extend_from_slice(b"U\x03numI\x01\x2c");
+        data.extend_from_slice(b"U\x02okT");
+        data.push(b'}');
+
+        let value = from_slice(&data).unwrap();
+        assert_eq!(value["name"], "gbtree");
+        assert_eq!(value["num"], 300);
+        assert_eq!(value["ok"], true);
+    }
+
+    #[test]
+    fn test_decode_typed_array() {
+        let mut data = b"[$d#U\x02".to_vec();
+        data.extend_from_slice(&0.5f32.to_be_bytes());
+        data.extend_from_slice(&(-1.0f32).to_be_bytes());
+
+        let value = from_slice(&data).unwrap();
+        assert_eq!(value, serde_json::json!([0.5, -1.0]));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(from_slice(b"").is_err());
+        assert!(from_slice(b"[").is_err());
+        assert!(from_slice(b"SU\x10short").is_err());
+        assert!(from_slice(b"[#L\x7f\xff\xff\xff\xff\xff\xff\xff").is_err());
+        assert!(from_slice(&[b'['; MAX_DEPTH + 2]).is_err());
+    }
+}
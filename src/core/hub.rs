@@ -0,0 +1,369 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use ureq::http::{header, StatusCode};
+
+const SCHEME: &str = "hf://";
+const DEFAULT_ENDPOINT: &str = "https://huggingface.co";
+const DEFAULT_REVISION: &str = "main";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A Hugging Face Hub model, or file of a model, `hf://org/repo[@revision][/path]`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HubUri {
+    pub repo: String,
+    pub revision: String,
+    // path of a file in the repository, the whole repository if not set
+    pub path: Option<String>,
+}
+
+impl HubUri {
+    pub(crate) fn is_hub_uri(path: &Path) -> bool {
+        path.to_string_lossy().starts_with(SCHEME)
+    }
+
+    pub(crate) fn parse(uri: &str) -> anyhow::Result<Self> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "invalid Hugging Face Hub URI '{}', expected {}org/repo[@revision][/path]",
+                uri,
+                SCHEME
+            )
+        };
+        let rest = uri.strip_prefix(SCHEME).ok_or_else(invalid)?;
+
+        let mut parts = rest.splitn(3, '/');
+        let org = parts.next().unwrap_or_default();
+        let name = parts.next().ok_or_else(invalid)?;
+        let path = parts
+            .next()
+            .map(|path| path.trim_end_matches('/').to_string())
+            .filter(|path| !path.is_empty());
+        let (name, revision) = match name.split_once('@') {
+            Some((name, revision)) => (name, revision.to_string()),
+            None => (name, DEFAULT_REVISION.to_string()),
+        };
+
+        let valid = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+        };
+        if !valid(org) || !valid(name) || !valid(&revision) {
+            return Err(invalid());
+        }
+        if path.as_deref().is_some_and(|path| !is_safe_path(path)) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            repo: format!("{}/{}", org, name),
+            revision,
+            path,
+        })
+    }
+
+    /// Identifies the model regardless of the revision.
+    pub(crate) fn model_id(&self) -> String {
+        match &self.path {
+            Some(path) => format!("{}{}/{}", SCHEME, self.repo, path),
+            None => format!("{}{}", SCHEME, self.repo),
+        }
+    }
+}
+
+// relative path without parent or empty components
+pub(crate) fn is_safe_path(path: &str) -> bool {
+    !path.starts_with('/')
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|component| !component.is_empty() && component != "..")
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RepoFile {
+    pub rfilename: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoInfo {
+    // commit the revision resolves to
+    sha: String,
+    #[serde(default)]
+    siblings: Vec<RepoFile>,
+}
+
+/// A model repository at a given commit.
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    pub commit: String,
+    pub files: Vec<RepoFile>,
+}
+
+/// Client of the Hugging Face Hub API.
+pub(crate) struct Hub {
+    agent: ureq::Agent,
+    // HF_ENDPOINT, for mirrors and private hubs
+    endpoint: String,
+    // HF_TOKEN, required for private and gated models, and to upload
+    token: Option<String>,
+}
+
+impl Hub {
+    pub(crate) fn new() -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_connect(Some(HTTP_TIMEOUT))
+            .build()
+            .into();
+
+        Self {
+            agent,
+            endpoint: std::env::var("HF_ENDPOINT")
+                .unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            token: std::env::var("HF_TOKEN")
+                .or_else(|_| std::env::var("HUGGING_FACE_HUB_TOKEN"))
+                .ok()
+                .filter(|token| !token.is_empty()),
+        }
+    }
+
+    fn get(&self, url: &str) -> anyhow::Result<ureq::http::Response<ureq::Body>> {
+        let mut request = self.agent.get(url);
+        if let Some(token) = &self.token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+
+        let response = request
+            .call()
+            .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+        match response.status() {
+            StatusCode::OK => Ok(response),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(anyhow::anyhow!(
+                "GET {}: access denied, set HF_TOKEN for private or gated models",
+                url
+            )),
+            status => Err(anyhow::anyhow!("GET {} failed: {}", url, status)),
+        }
+    }
+
+    /// Resolves the revision to a commit and lists the files of the repository.
+    pub(crate) fn snapshot(&self, uri: &HubUri) -> anyhow::Result<Snapshot> {
+        let url = format!(
+            "{}/api/models/{}/revision/{}?blobs=true",
+            self.endpoint, uri.repo, uri.revision
+        );
+        let info: RepoInfo = serde_json::from_slice(
+            &self
+                .get(&url)?
+                .body_mut()
+                .with_config()
+                .limit(64 * 1024 * 1024)
+                .read_to_vec()?,
+        )?;
+
+        // both end up in local paths
+        if info.sha.is_empty() || !info.sha.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("invalid commit '{}' for {}", info.sha, uri.repo);
+        }
+        if let Some(file) = info
+            .siblings
+            .iter()
+            .find(|file| !is_safe_path(&file.rfilename))
+        {
+            anyhow::bail!("invalid file name '{}' in {}", file.rfilename, uri.repo);
+        }
+
+        Ok(Snapshot {
+            commit: info.sha,
+            files: info.siblings,
+        })
+    }
+
+    /// Downloads a file of the repository at the given commit.
+    pub(crate) fn download(
+        &self,
+        uri: &HubUri,
+        commit: &str,
+        file: &str,
+        output: &Path,
+    ) -> anyhow::Result<()> {
+        let url = format!("{}/{}/resolve/{}/{}", self.endpoint, uri.repo, commit, file);
+        let mut reader = self.get(&url)?.into_body().into_reader();
+
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // only complete files end up in the cache
+        let partial = PathBuf::from(format!("{}.part", output.display()));
+        let mut writer = std::fs::File::create(&partial)?;
+        std::io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+        std::fs::rename(&partial, output)?;
+
+        Ok(())
+    }
+
+    /// Commits a small file to the repository.
+    pub(crate) fn upload(&self, uri: &HubUri, path: &str, content: &[u8]) -> anyhow::Result<()> {
+        let Some(token) = &self.token else {
+            anyhow::bail!("HF_TOKEN is required to upload to the Hugging Face Hub");
+        };
+
+        let url = format!(
+            "{}/api/models/{}/commit/{}",
+            self.endpoint, uri.repo, uri.revision
+        );
+        // newline delimited JSON, the commit header followed by the files
+        let body = [
+            serde_json::json!({
+                "key": "header",
+                "value": {"summary": format!("Upload {} with tensor-man", path), "description": ""}
+            }),
+            serde_json::json!({
+                "key": "file",
+                "value": {"content": STANDARD.encode(content), "path": path, "encoding": "base64"}
+            }),
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let response = self
+            .agent
+            .post(&url)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .send(body)
+            .map_err(|e| anyhow::anyhow!("POST {} failed: {}", url, e))?;
+        if !response.status().is_success() {
+            anyhow::bail!("upload of {} failed: {}", path, response.status());
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the folder model snapshots are downloaded to.
+pub(crate) fn cache_path(uri: &HubUri, commit: &str) -> anyhow::Result<PathBuf> {
+    dirs::cache_dir()
+        .map(|path| {
+            path.join("tensor-man")
+                .join("hf")
+                .join(&uri.repo)
+                .join(commit)
+        })
+        .ok_or_else(|| anyhow::anyhow!("can't determine the user cache folder"))
+}
+
+/// Selects the files to download: the whole repository, or the file and its signatures.
+pub(crate) fn files_to_download<'a>(
+    uri: &HubUri,
+    snapshot: &'a Snapshot,
+    ignore: Option<&str>,
+) -> Vec<&'a RepoFile> {
+    let related = uri.path.as_ref().map(|path| {
+        let path = Path::new(path);
+        [
+            path.to_path_buf(),
+            path.with_extension("signature"),
+            path.with_extension("intoto.json"),
+        ]
+    });
+
+    snapshot
+        .files
+        .iter()
+        .filter(|file| match &related {
+            Some(related) => related
+                .iter()
+                .any(|path| path == Path::new(&file.rfilename)),
+            None => !ignore.is_some_and(|ignore| file.rfilename.contains(ignore)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uri() {
+        let uri = HubUri::parse("hf://meta-llama/Llama-3.2-1B").unwrap();
+        assert_eq!(uri.repo, "meta-llama/Llama-3.2-1B");
+        assert_eq!(uri.revision, "main");
+        assert_eq!(uri.path, None);
+        assert_eq!(uri.model_id(), "hf://meta-llama/Llama-3.2-1B");
+
+        let uri = HubUri::parse("hf://org/model@v1.0/onnx/model.onnx").unwrap();
+        assert_eq!(uri.repo, "org/model");
+        assert_eq!(uri.revision, "v1.0");
+        assert_eq!(uri.path.as_deref(), Some("onnx/model.onnx"));
+        assert_eq!(uri.model_id(), "hf://org/model/onnx/model.onnx");
+
+        assert!(HubUri::is_hub_uri(Path::new("hf://org/model")));
+        assert!(is_safe_path("onnx/model.onnx"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(!is_safe_path("onnx//model.onnx"));
+        assert!(!HubUri::is_hub_uri(Path::new("./org/model")));
+
+        for invalid in [
+            "org/model",
+            "hf://model",
+            "hf://org/",
+            "hf://org/model@",
+            "hf://org/model/../../etc/passwd",
+            "hf://org/mo del",
+        ] {
+            assert!(HubUri::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_files_to_download() {
+        let snapshot = Snapshot {
+            commit: "abc".to_string(),
+            files: [
+                ".gitattributes",
+                "config.json",
+                "model.safetensors",
+                "model.signature",
+                "onnx/model.onnx",
+            ]
+            .iter()
+            .map(|name| RepoFile {
+                rfilename: name.to_string(),
+                size: None,
+            })
+            .collect(),
+        };
+        let names = |uri: &str, ignore: Option<&str>| -> Vec<String> {
+            files_to_download(&HubUri::parse(uri).unwrap(), &snapshot, ignore)
+                .iter()
+                .map(|file| file.rfilename.clone())
+                .collect()
+        };
+
+        assert_eq!(names("hf://org/model", None).len(), 5);
+        assert_eq!(names("hf://org/model", Some("onnx")).len(), 4);
+        assert_eq!(
+            names("hf://org/model/model.safetensors", None),
+            vec!["model.safetensors", "model.signature"]
+        );
+        assert_eq!(
+            names("hf://org/model/onnx/model.onnx", None),
+            vec!["onnx/model.onnx"]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::TensorDescriptor;
+
+/// Tensors sharing a common prefix, such as the ones of a transformer block.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct LayerGroup {
+    /// Common prefix, such as model.layers.17 or blk.17.
+    pub name: String,
+    pub tensors: usize,
+    pub parameters: usize,
+    /// Size of the tensor data, in bytes.
+    pub size: usize,
+}
+
+/// The group of a tensor: its name up to the first numeric component, the layer index, or
+/// without its last component, such as weight or bias, if there's none.
+pub(crate) fn layer_of(name: &str) -> &str {
+    let mut end = 0;
+    for (index, component) in name.split('.').enumerate() {
+        end += component.len() + usize::from(index > 0);
+        if !component.is_empty() && component.chars().all(|c| c.is_ascii_digit()) {
+            return &name[..end];
+        }
+    }
+    name.rsplit_once('.')
+        .map(|(prefix, _)| prefix)
+        .unwrap_or(name)
+}
+
+/// Orders model.layers.2 before model.layers.10.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_components = a.split('.');
+    let mut b_components = b.split('.');
+    loop {
+        match (a_components.next(), b_components.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => {
+                let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => a.cmp(b),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+/// Groups the tensors by layer, with the number of tensors, parameters and bytes of each,
+/// sorted by name with the layer indexes in numeric order.
+pub(crate) fn group_by_layer(tensors: &[TensorDescriptor]) -> Vec<LayerGroup> {
+    let mut groups: BTreeMap<&str, LayerGroup> = BTreeMap::new();
+    for tensor in tensors {
+        let name = layer_of(tensor.id.as_deref().unwrap_or_default());
+        let group = groups.entry(name).or_insert_with(|| LayerGroup {
+            name: name.to_string(),
+            tensors: 0,
+            parameters: 0,
+            size: 0,
+        });
+        group.tensors += 1;
+        group.parameters += tensor.shape.iter().product::<usize>();
+        group.size += tensor.size;
+    }
+
+    let mut groups: Vec<LayerGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_by_layer() {
+        assert_eq!(
+            layer_of("model.layers.17.mlp.up_proj.weight"),
+            "model.layers.17"
+        );
+        assert_eq!(layer_of("blk.0.attn_q.weight"), "blk.0");
+        assert_eq!(layer_of("model.embed_tokens.weight"), "model.embed_tokens");
+        assert_eq!(layer_of("output"), "output");
+
+        let tensor = |id: &str, shape: Vec<usize>| TensorDescriptor {
+            id: Some(id.to_string()),
+            size: shape.iter().product::<usize>() * 2,
+            shape,
+            dtype: "BF16".to_string(),
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        let groups = group_by_layer(&[
+            tensor("model.layers.10.self_attn.q_proj.weight", vec![8, 8]),
+            tensor("model.embed_tokens.weight", vec![100, 8]),
+            tensor("model.layers.2.self_attn.q_proj.weight", vec![8, 8]),
+            tensor("model.layers.2.input_layernorm.weight", vec![8]),
+            tensor("model.norm.weight", vec![8]),
+        ]);
+        let summary: Vec<(&str, usize, usize, usize)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    group.tensors,
+                    group.parameters,
+                    group.size,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("model.embed_tokens", 1, 800, 1600),
+                ("model.layers.2", 2, 72, 144),
+                ("model.layers.10", 1, 64, 128),
+                ("model.norm", 1, 8, 16),
+            ]
+        );
+    }
+}
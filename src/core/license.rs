@@ -0,0 +1,146 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Metadata;
+
+// metadata keys holding the license, by priority: GGUF, then Hugging Face and ONNX metadata_props
+const LICENSE_KEYS: &[&str] = &[
+    "general.license",
+    "general.license.name",
+    "license",
+    "license_name",
+];
+// words that don't identify a license
+const FILLER_WORDS: &[&str] = &["license", "licence", "the", "version"];
+// common spellings of licenses, and their SPDX like Hugging Face id
+const ALIASES: &[(&str, &str)] = &[
+    ("apache", "apache-2.0"),
+    ("apache-2", "apache-2.0"),
+    ("apache2", "apache-2.0"),
+    ("apache-2.0", "apache-2.0"),
+    ("apache2.0", "apache-2.0"),
+    ("mit", "mit"),
+    ("bsd-3", "bsd-3-clause"),
+    ("gpl-3", "gpl-3.0"),
+    ("gplv3", "gpl-3.0"),
+    ("gpl-v3", "gpl-3.0"),
+    ("agpl-3", "agpl-3.0"),
+    ("lgpl-3", "lgpl-3.0"),
+    ("cc-by-4", "cc-by-4.0"),
+    ("cc-by-sa-4", "cc-by-sa-4.0"),
+    ("cc-by-nc-4", "cc-by-nc-4.0"),
+];
+
+/// License declared in the metadata of a model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct License {
+    /// Normalized name, lowercase and dash separated like the Hugging Face license ids.
+    pub name: String,
+    /// Metadata key the license has been read from.
+    pub source: String,
+}
+
+/// Normalizes the spelling of a license, "Apache License 2.0" and "apache_2.0" are both apache-2.0.
+pub(crate) fn normalize(value: &str) -> String {
+    let lowercase = value.trim().to_ascii_lowercase();
+    let name = lowercase
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty() && !FILLER_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join("-");
+
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, id)| id.to_string())
+        .unwrap_or(name)
+}
+
+/// Detects the license of a model from its metadata or from the Hugging Face config.json next to it.
+pub(crate) fn detect(metadata: &Metadata, config: Option<&Value>) -> Option<License> {
+    let license = |key: &str, value: &str| {
+        let name = normalize(value);
+        (!name.is_empty()).then(|| License {
+            name,
+            source: key.to_string(),
+        })
+    };
+
+    for key in LICENSE_KEYS {
+        if let Some(found) = metadata
+            .iter()
+            .find(|(other, _)| other.eq_ignore_ascii_case(key))
+            .and_then(|(key, value)| license(key, value))
+        {
+            return Some(found);
+        }
+    }
+    if let Some(found) = metadata
+        .iter()
+        .filter(|(key, _)| key.to_ascii_lowercase().ends_with("license"))
+        .find_map(|(key, value)| license(key, value))
+    {
+        return Some(found);
+    }
+
+    match config.and_then(|config| config.get("license")) {
+        Some(Value::String(value)) => license("config.json license", value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Apache License 2.0"), "apache-2.0");
+        assert_eq!(normalize("apache_2.0"), "apache-2.0");
+        assert_eq!(normalize(" MIT "), "mit");
+        assert_eq!(normalize("GPLv3"), "gpl-3.0");
+        assert_eq!(normalize("llama3"), "llama3");
+        assert_eq!(normalize("CC BY-NC 4.0"), "cc-by-nc-4.0");
+    }
+
+    #[test]
+    fn test_detect() {
+        let metadata = |entries: &[(&str, &str)]| -> Metadata {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        let license = |name: &str, source: &str| {
+            Some(License {
+                name: name.to_string(),
+                source: source.to_string(),
+            })
+        };
+
+        // GGUF
+        assert_eq!(
+            detect(
+                &metadata(&[
+                    ("general.license.link", "https://example.com"),
+                    ("general.license", "Apache-2.0")
+                ]),
+                None
+            ),
+            license("apache-2.0", "general.license")
+        );
+        // ONNX metadata_props
+        assert_eq!(
+            detect(&metadata(&[("model_license", "MIT")]), None),
+            license("mit", "model_license")
+        );
+        // Hugging Face config.json
+        let config = serde_json::json!({"model_type": "llama", "license": "llama3"});
+        assert_eq!(
+            detect(&metadata(&[("format", "pt")]), Some(&config)),
+            license("llama3", "config.json license")
+        );
+        assert_eq!(detect(&metadata(&[("license", " ")]), None), None);
+    }
+}
@@ -0,0 +1,228 @@
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::data::{ElementType, Encoding, TensorData};
+
+// number of values of each window
+const WINDOW_SIZE: usize = 64 * 1024;
+// the last window is only tested if it has enough values
+const MIN_WINDOW_SIZE: usize = 16 * 1024;
+// chi-square of the 256 byte values against the uniform distribution: 255 on average for
+// random bits, with a standard deviation of about 22.6
+const MAX_CHI_SQUARE: f64 = 255.0 + 10.0 * 22.6;
+
+/// A region of a tensor whose least significant mantissa bits are not random.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct LsbPattern {
+    pub id: String,
+    /// Index of the first value of the region.
+    pub start: usize,
+    pub count: usize,
+    /// Lowest mantissa bit varying across the tensor, the one tested.
+    pub bit: u32,
+    /// Highest chi-square of the windows of the region.
+    pub chi_square: f64,
+    /// File offset of the region, if the tensor is stored contiguously.
+    pub file_offset: Option<u64>,
+}
+
+fn mantissa_bits(element_type: ElementType) -> Option<u32> {
+    match element_type {
+        ElementType::F64 => Some(52),
+        ElementType::F32 => Some(23),
+        ElementType::F16 => Some(10),
+        ElementType::BF16 => Some(7),
+        _ => None,
+    }
+}
+
+/// Chi-square of the distribution of the bytes against the uniform one.
+pub(crate) fn chi_square(bytes: impl Iterator<Item = u8>) -> f64 {
+    let mut counts = [0usize; 256];
+    let mut total = 0;
+    for byte in bytes {
+        counts[byte as usize] += 1;
+        total += 1;
+    }
+
+    let expected = total as f64 / 256.0;
+    counts
+        .iter()
+        .map(|count| (*count as f64 - expected).powi(2) / expected)
+        .sum()
+}
+
+// the bits of a little endian value
+fn raw_bits(value: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes[..value.len()].copy_from_slice(value);
+    u64::from_le_bytes(bytes)
+}
+
+// tests the bit of the values packed 8 by 8, and the byte starting at that bit if it's part of
+// the mantissa
+fn test_window(window: &[u8], size: usize, bit: u32, mantissa_bits: u32) -> f64 {
+    let packed = window.chunks(8 * size).map(|chunk| {
+        chunk.chunks_exact(size).fold(0u8, |byte, value| {
+            (byte << 1) | ((raw_bits(value) >> bit) & 1) as u8
+        })
+    });
+    let mut result = chi_square(packed);
+
+    if bit + 8 <= mantissa_bits {
+        result = result.max(chi_square(
+            window
+                .chunks_exact(size)
+                .map(|value| (raw_bits(value) >> bit) as u8),
+        ));
+    }
+    result
+}
+
+fn scan_tensor(tensor: &TensorData) -> Vec<LsbPattern> {
+    let Encoding::Plain(element_type) = tensor.encoding else {
+        return vec![];
+    };
+    let Some(mantissa_bits) = mantissa_bits(element_type) else {
+        return vec![];
+    };
+
+    let size = element_type.size();
+    let bytes = tensor.bytes();
+    // bits always equal, as the low bits of weights upcast from a narrower type, are skipped
+    let Some(first) = bytes.chunks_exact(size).next().map(raw_bits) else {
+        return vec![];
+    };
+    let mantissa_mask = (1u64 << mantissa_bits) - 1;
+    let varying = bytes.chunks_exact(size).fold(0, |varying, value| {
+        varying | ((raw_bits(value) ^ first) & mantissa_mask)
+    });
+    if varying == 0 {
+        return vec![];
+    }
+    let bit = varying.trailing_zeros();
+
+    let mut regions: Vec<LsbPattern> = vec![];
+    for (index, window) in bytes.chunks(WINDOW_SIZE * size).enumerate() {
+        let count = window.len() / size;
+        if count < MIN_WINDOW_SIZE {
+            continue;
+        }
+        let chi_square = test_window(window, size, bit, mantissa_bits);
+        if chi_square <= MAX_CHI_SQUARE {
+            continue;
+        }
+
+        let start = index * WINDOW_SIZE;
+        match regions.last_mut() {
+            Some(region) if region.start + region.count == start => {
+                region.count += count;
+                region.chi_square = region.chi_square.max(chi_square);
+            }
+            _ => regions.push(LsbPattern {
+                id: tensor.id.clone(),
+                start,
+                count,
+                bit,
+                chi_square,
+                file_offset: tensor.element_file_offset(start),
+            }),
+        }
+    }
+
+    regions
+}
+
+/// Tests in parallel the least significant mantissa bits of the floating point tensors, random in
+/// trained weights, and returns the regions where they follow a pattern, as data hidden in them
+/// does unless encrypted.
+pub(crate) fn find_lsb_patterns(tensors: &[TensorData]) -> Vec<LsbPattern> {
+    let mut found: Vec<LsbPattern> = tensors.par_iter().flat_map(scan_tensor).collect();
+
+    found.sort_by(|a, b| a.id.cmp(&b.id).then(a.start.cmp(&b.start)));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // normally distributed weights, from pseudo random numbers
+    fn weights(count: usize) -> Vec<f32> {
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut uniform = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+        };
+        (0..count)
+            .map(|_| {
+                let radius = (-2.0 * uniform().ln()).sqrt();
+                (radius * (2.0 * std::f64::consts::PI * uniform()).cos() * 0.02) as f32
+            })
+            .collect()
+    }
+
+    // hides the bits of the text in the given bit of the values, starting at the given index
+    fn embed(values: &mut [f32], start: usize, bit: u32, text: &[u8]) {
+        let bits = text
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1));
+        for (value, hidden) in values[start..].iter_mut().zip(bits) {
+            let raw = value.to_bits() & !(1 << bit) | ((hidden as u32) << bit);
+            *value = f32::from_bits(raw);
+        }
+    }
+
+    fn tensor(id: &str, values: &[f32]) -> TensorData {
+        TensorData::owned(
+            id.to_string(),
+            Encoding::Plain(ElementType::F32),
+            values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_chi_square() {
+        assert_eq!(chi_square((0..=255u8).cycle().take(256 * 4)), 0.0);
+        assert_eq!(chi_square(std::iter::repeat_n(0u8, 256)), 255.0 * 256.0);
+    }
+
+    #[test]
+    fn test_find_lsb_patterns() {
+        let text = "the quick brown fox jumps over the lazy dog. ".repeat(1000);
+
+        let clean = weights(256 * 1024);
+        let mut hidden = clean.clone();
+        embed(&mut hidden, WINDOW_SIZE, 0, text.as_bytes());
+        // weights upcast from bfloat16, the lowest 16 bits are always zero
+        let mut upcast: Vec<f32> = clean
+            .iter()
+            .map(|value| f32::from_bits(value.to_bits() & 0xffff0000))
+            .collect();
+        let upcast_tensor = tensor("upcast", &upcast);
+        embed(&mut upcast, 0, 16, text.as_bytes());
+
+        let found = find_lsb_patterns(&[
+            tensor("clean", &clean),
+            tensor("hidden", &hidden),
+            upcast_tensor,
+            tensor("upcast_hidden", &upcast),
+        ]);
+        let regions: Vec<_> = found
+            .iter()
+            .map(|region| (region.id.as_str(), region.start, region.count, region.bit))
+            .collect();
+        assert_eq!(
+            regions,
+            vec![
+                ("hidden", WINDOW_SIZE, WINDOW_SIZE * 3, 0),
+                ("upcast_hidden", 0, WINDOW_SIZE * 4, 16),
+            ]
+        );
+    }
+}
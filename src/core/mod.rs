@@ -1,32 +1,99 @@
 use std::{collections::BTreeMap, fmt, path::PathBuf};
 
 use clap::ValueEnum;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub(crate) mod adapter;
+pub(crate) mod archive;
+pub(crate) mod badge;
+pub(crate) mod card;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod checksums;
+pub(crate) mod compare;
+pub(crate) mod compression;
+pub(crate) mod config_check;
+pub(crate) mod data;
+pub(crate) mod diff;
 pub(crate) mod docker;
+pub(crate) mod entropy;
+pub(crate) mod estimate;
+pub(crate) mod extract;
+pub(crate) mod filter;
+pub(crate) mod graph;
 pub(crate) mod handlers;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod hub;
+pub(crate) mod layers;
+pub(crate) mod license;
+pub(crate) mod lsb;
+pub(crate) mod operators;
+pub(crate) mod pickle;
+pub(crate) mod pipe;
+pub(crate) mod profile;
+pub(crate) mod progress;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod registry;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod remote;
+pub(crate) mod report;
+pub(crate) mod repository;
+pub(crate) mod sarif;
+pub(crate) mod scan;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod schema;
+#[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod signing;
+pub(crate) mod stats;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod storage;
+pub(crate) mod validate;
 
-pub(crate) type Metadata = BTreeMap<String, String>;
+pub type Metadata = BTreeMap<String, String>;
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
-pub(crate) struct TensorDescriptor {
+#[derive(Debug, Clone, ValueEnum)]
+pub enum DetailLevel {
+    /// Print metadata and high level information only.
+    Brief,
+    /// Print the metadata and detailed tensor information.
+    Full,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+pub struct TensorDescriptor {
     pub id: Option<String>,
     pub shape: Vec<usize>,
     pub dtype: String,
     pub size: usize,
     pub metadata: Metadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<stats::TensorStats>,
+    // first values of the tensor, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Vec<f64>>,
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, Clone, Default, Deserialize, Serialize, ValueEnum)]
-pub(crate) enum FileType {
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, ValueEnum, JsonSchema)]
+pub enum FileType {
     #[default]
     Unknown,
     SafeTensors,
     ONNX,
     GGUF,
     PyTorch,
+    TensorFlow,
+    OpenVINO,
+    TensorRT,
+    ExecuTorch,
+    MLX,
+    XGBoost,
+    LightGBM,
+    #[value(name = "mlflow")]
+    MLflow,
+    /// Handled by an external plugin, can't be forced.
+    #[value(skip)]
+    Plugin,
 }
 
 #[allow(dead_code)]
@@ -50,6 +117,42 @@ impl FileType {
     pub fn is_pytorch(&self) -> bool {
         matches!(self, FileType::PyTorch)
     }
+
+    pub fn is_tensorflow(&self) -> bool {
+        matches!(self, FileType::TensorFlow)
+    }
+
+    pub fn is_openvino(&self) -> bool {
+        matches!(self, FileType::OpenVINO)
+    }
+
+    pub fn is_tensorrt(&self) -> bool {
+        matches!(self, FileType::TensorRT)
+    }
+
+    pub fn is_executorch(&self) -> bool {
+        matches!(self, FileType::ExecuTorch)
+    }
+
+    pub fn is_mlx(&self) -> bool {
+        matches!(self, FileType::MLX)
+    }
+
+    pub fn is_xgboost(&self) -> bool {
+        matches!(self, FileType::XGBoost)
+    }
+
+    pub fn is_lightgbm(&self) -> bool {
+        matches!(self, FileType::LightGBM)
+    }
+
+    pub fn is_mlflow(&self) -> bool {
+        matches!(self, FileType::MLflow)
+    }
+
+    pub fn is_plugin(&self) -> bool {
+        matches!(self, FileType::Plugin)
+    }
 }
 
 impl fmt::Display for FileType {
@@ -60,14 +163,95 @@ impl fmt::Display for FileType {
             FileType::ONNX => write!(f, "ONNX"),
             FileType::GGUF => write!(f, "GGUF"),
             FileType::PyTorch => write!(f, "PyTorch"),
+            FileType::TensorFlow => write!(f, "TensorFlow"),
+            FileType::OpenVINO => write!(f, "OpenVINO"),
+            FileType::TensorRT => write!(f, "TensorRT"),
+            FileType::ExecuTorch => write!(f, "ExecuTorch"),
+            FileType::MLX => write!(f, "MLX"),
+            FileType::XGBoost => write!(f, "XGBoost"),
+            FileType::LightGBM => write!(f, "LightGBM"),
+            FileType::MLflow => write!(f, "MLflow"),
+            FileType::Plugin => write!(f, "plugin"),
         }
     }
 }
 
 pub(crate) type Shape = Vec<usize>;
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
-pub(crate) struct Inspection {
+/// Version of the format of serialized inspections, bumped on incompatible changes.
+pub(crate) const INSPECTION_SCHEMA_VERSION: &str = "1.0";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(transparent)]
+pub struct SchemaVersion(pub String);
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        Self(INSPECTION_SCHEMA_VERSION.to_string())
+    }
+}
+
+/// A dimension of a graph input or output, fixed or named by the model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Dim {
+    Fixed(i64),
+    // symbolic dimension such as batch, ? if unnamed
+    Named(String),
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dim::Fixed(size) => write!(f, "{}", size),
+            Dim::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// An input or output of the graph of a model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct ValueInfo {
+    pub name: String,
+    // element type of tensors, or the kind of value for the others, such as sequence
+    pub dtype: String,
+    // none if the rank isn't known
+    pub dims: Option<Vec<Dim>>,
+}
+
+impl ValueInfo {
+    /// The data type and dimensions, such as INT64[batch,sequence], or the data type alone if
+    /// the rank isn't known.
+    pub fn shape(&self) -> String {
+        match &self.dims {
+            Some(dims) => format!(
+                "{}[{}]",
+                self.dtype,
+                dims.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            None => self.dtype.clone(),
+        }
+    }
+}
+
+/// A file of a model sharded across multiple files.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct Shard {
+    pub file_path: PathBuf,
+    pub file_size: u64,
+    pub num_tensors: usize,
+    pub data_size: usize,
+    pub unique_dtypes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+pub struct Inspection {
+    // inspections saved before the field was added follow the first version
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     pub file_path: PathBuf,
     pub file_type: FileType,
     pub file_size: u64,
@@ -78,7 +262,38 @@ pub(crate) struct Inspection {
     pub unique_shapes: Vec<Shape>,
     pub unique_dtypes: Vec<String>,
     pub metadata: Metadata,
+    // metadata values with their native types, arrays included, for formats with typed metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed_metadata: Option<BTreeMap<String, serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<license::License>,
+    // hyperparameters of the model, if declared in its metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<profile::ModelProfile>,
+    // LoRA adapter, if the file is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<adapter::Adapter>,
+    // inputs and outputs of the graph of ONNX models, the initializers excluded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<ValueInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<ValueInfo>>,
+    // tensors computed by the graph of ONNX models, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intermediates: Option<Vec<ValueInfo>>,
+    // operators of ONNX models and the opsets they import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operators: Option<operators::Operators>,
+    // files of sharded models, the inspection being the aggregate of their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shards: Option<Vec<Shard>>,
+    // files of archives, along with the models among them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<archive::Member>>,
     pub tensors: Option<Vec<TensorDescriptor>>,
+    // tensors grouped by layer, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layers: Option<Vec<layers::LayerGroup>>,
 }
 
 impl Inspection {
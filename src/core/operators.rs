@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// operators of the default ai.onnx domain with the opset introducing them and, for the
+// deprecated ones, the opset removing them
+const DEFAULT_OPERATORS: &[(&str, i64, Option<i64>)] = &[
+    ("Abs", 1, None),
+    ("Acos", 7, None),
+    ("Acosh", 9, None),
+    ("Add", 1, None),
+    ("AffineGrid", 20, None),
+    ("And", 1, None),
+    ("ArgMax", 1, None),
+    ("ArgMin", 1, None),
+    ("Asin", 7, None),
+    ("Asinh", 9, None),
+    ("Atan", 7, None),
+    ("Atanh", 9, None),
+    ("Attention", 23, None),
+    ("AveragePool", 1, None),
+    ("BatchNormalization", 1, None),
+    ("Bernoulli", 15, None),
+    ("BitShift", 11, None),
+    ("BitwiseAnd", 18, None),
+    ("BitwiseNot", 18, None),
+    ("BitwiseOr", 18, None),
+    ("BitwiseXor", 18, None),
+    ("BlackmanWindow", 17, None),
+    ("Cast", 1, None),
+    ("CastLike", 15, None),
+    ("Ceil", 1, None),
+    ("Celu", 12, None),
+    ("CenterCropPad", 18, None),
+    ("Clip", 1, None),
+    ("Col2Im", 18, None),
+    ("Compress", 9, None),
+    ("Concat", 1, None),
+    ("ConcatFromSequence", 11, None),
+    ("Constant", 1, None),
+    ("ConstantOfShape", 9, None),
+    ("Conv", 1, None),
+    ("ConvInteger", 10, None),
+    ("ConvTranspose", 1, None),
+    ("Cos", 7, None),
+    ("Cosh", 9, None),
+    ("CumSum", 11, None),
+    ("DFT", 17, None),
+    ("DeformConv", 19, None),
+    ("DepthToSpace", 1, None),
+    ("DequantizeLinear", 10, None),
+    ("Det", 11, None),
+    ("Div", 1, None),
+    ("Dropout", 1, None),
+    ("DynamicQuantizeLinear", 11, None),
+    ("Einsum", 12, None),
+    ("Elu", 1, None),
+    ("Equal", 1, None),
+    ("Erf", 9, None),
+    ("Exp", 1, None),
+    ("Expand", 8, None),
+    ("EyeLike", 9, None),
+    ("Flatten", 1, None),
+    ("Floor", 1, None),
+    ("GRU", 1, None),
+    ("Gather", 1, None),
+    ("GatherElements", 11, None),
+    ("GatherND", 11, None),
+    ("Gelu", 20, None),
+    ("Gemm", 1, None),
+    ("GlobalAveragePool", 1, None),
+    ("GlobalLpPool", 1, None),
+    ("GlobalMaxPool", 1, None),
+    ("Greater", 1, None),
+    ("GreaterOrEqual", 12, None),
+    ("GridSample", 16, None),
+    ("GroupNormalization", 18, None),
+    ("HammingWindow", 17, None),
+    ("HannWindow", 17, None),
+    ("HardSigmoid", 1, None),
+    ("HardSwish", 14, None),
+    ("Hardmax", 1, None),
+    ("Identity", 1, None),
+    ("If", 1, None),
+    ("ImageDecoder", 20, None),
+    ("InstanceNormalization", 1, None),
+    ("IsInf", 10, None),
+    ("IsNaN", 9, None),
+    ("LRN", 1, None),
+    ("LSTM", 1, None),
+    ("LayerNormalization", 17, None),
+    ("LeakyRelu", 1, None),
+    ("Less", 1, None),
+    ("LessOrEqual", 12, None),
+    ("Log", 1, None),
+    ("LogSoftmax", 1, None),
+    ("Loop", 1, None),
+    ("LpNormalization", 1, None),
+    ("LpPool", 1, None),
+    ("MatMul", 1, None),
+    ("MatMulInteger", 10, None),
+    ("Max", 1, None),
+    ("MaxPool", 1, None),
+    ("MaxRoiPool", 1, None),
+    ("MaxUnpool", 9, None),
+    ("Mean", 1, None),
+    ("MeanVarianceNormalization", 9, None),
+    ("MelWeightMatrix", 17, None),
+    ("Min", 1, None),
+    ("Mish", 18, None),
+    ("Mod", 10, None),
+    ("Mul", 1, None),
+    ("Multinomial", 7, None),
+    ("Neg", 1, None),
+    ("NegativeLogLikelihoodLoss", 12, None),
+    ("NonMaxSuppression", 10, None),
+    ("NonZero", 9, None),
+    ("Not", 1, None),
+    ("OneHot", 9, None),
+    ("Optional", 15, None),
+    ("OptionalGetElement", 15, None),
+    ("OptionalHasElement", 15, None),
+    ("Or", 1, None),
+    ("PRelu", 1, None),
+    ("Pad", 1, None),
+    ("Pow", 1, None),
+    ("QLinearConv", 10, None),
+    ("QLinearMatMul", 10, None),
+    ("QuantizeLinear", 10, None),
+    ("RMSNormalization", 23, None),
+    ("RNN", 1, None),
+    ("RandomNormal", 1, None),
+    ("RandomNormalLike", 1, None),
+    ("RandomUniform", 1, None),
+    ("RandomUniformLike", 1, None),
+    ("Range", 11, None),
+    ("Reciprocal", 1, None),
+    ("ReduceL1", 1, None),
+    ("ReduceL2", 1, None),
+    ("ReduceLogSum", 1, None),
+    ("ReduceLogSumExp", 1, None),
+    ("ReduceMax", 1, None),
+    ("ReduceMean", 1, None),
+    ("ReduceMin", 1, None),
+    ("ReduceProd", 1, None),
+    ("ReduceSum", 1, None),
+    ("ReduceSumSquare", 1, None),
+    ("RegexFullMatch", 20, None),
+    ("Relu", 1, None),
+    ("Reshape", 1, None),
+    ("Resize", 10, None),
+    ("ReverseSequence", 10, None),
+    ("RoiAlign", 10, None),
+    ("RotaryEmbedding", 23, None),
+    ("Round", 11, None),
+    ("STFT", 17, None),
+    ("Scan", 8, None),
+    ("Scatter", 9, Some(11)),
+    ("ScatterElements", 11, None),
+    ("ScatterND", 11, None),
+    ("Selu", 1, None),
+    ("SequenceAt", 11, None),
+    ("SequenceConstruct", 11, None),
+    ("SequenceEmpty", 11, None),
+    ("SequenceErase", 11, None),
+    ("SequenceInsert", 11, None),
+    ("SequenceLength", 11, None),
+    ("SequenceMap", 17, None),
+    ("Shape", 1, None),
+    ("Shrink", 9, None),
+    ("Sigmoid", 1, None),
+    ("Sign", 9, None),
+    ("Sin", 7, None),
+    ("Sinh", 9, None),
+    ("Size", 1, None),
+    ("Slice", 1, None),
+    ("Softmax", 1, None),
+    ("SoftmaxCrossEntropyLoss", 12, None),
+    ("Softplus", 1, None),
+    ("Softsign", 1, None),
+    ("SpaceToDepth", 1, None),
+    ("Split", 1, None),
+    ("SplitToSequence", 11, None),
+    ("Sqrt", 1, None),
+    ("Squeeze", 1, None),
+    ("StringConcat", 20, None),
+    ("StringNormalizer", 10, None),
+    ("StringSplit", 20, None),
+    ("Sub", 1, None),
+    ("Sum", 1, None),
+    ("Tan", 7, None),
+    ("Tanh", 1, None),
+    ("TfIdfVectorizer", 9, None),
+    ("ThresholdedRelu", 10, None),
+    ("Tile", 1, None),
+    ("TopK", 1, None),
+    ("Transpose", 1, None),
+    ("Trilu", 14, None),
+    ("Unique", 11, None),
+    ("Unsqueeze", 1, None),
+    ("Upsample", 7, Some(10)),
+    ("Where", 9, None),
+    ("Xor", 1, None),
+];
+
+/// Name of the default domain, for the operators and opset imports with an empty one.
+pub(crate) const DEFAULT_DOMAIN: &str = "ai.onnx";
+
+/// Number of nodes of an operator of the model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct OperatorCount {
+    /// Domain of the operator, empty for the default one.
+    pub domain: String,
+    pub op_type: String,
+    pub count: usize,
+}
+
+impl OperatorCount {
+    /// The operator qualified by its domain, unless it's the default one.
+    pub fn name(&self) -> String {
+        if self.domain.is_empty() {
+            self.op_type.clone()
+        } else {
+            format!("{}.{}", self.domain, self.op_type)
+        }
+    }
+}
+
+/// The operators of an ONNX model, with the operator sets it imports.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct Operators {
+    /// Version of the operator set imported for each domain, ai.onnx being the default one.
+    pub opsets: BTreeMap<String, i64>,
+    /// Operators by number of nodes, those of the subgraphs included.
+    pub counts: Vec<OperatorCount>,
+    /// Operators of the default domain that aren't available in the opset checked against.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unavailable: Vec<String>,
+}
+
+impl Operators {
+    /// Why each operator of the default domain isn't available in the opset, the ones of the
+    /// other domains being left out.
+    pub(crate) fn unavailable_in(&self, opset: i64) -> Vec<String> {
+        self.counts
+            .iter()
+            .filter(|operator| operator.domain.is_empty())
+            .filter_map(|operator| {
+                match DEFAULT_OPERATORS
+                    .iter()
+                    .find(|(op_type, _, _)| *op_type == operator.op_type)
+                {
+                    None => Some(format!(
+                        "{} is not an operator of the {} domain",
+                        operator.op_type, DEFAULT_DOMAIN
+                    )),
+                    Some((op_type, since, _)) if *since > opset => {
+                        Some(format!("{} requires opset {}", op_type, since))
+                    }
+                    Some((op_type, _, Some(removed))) if *removed <= opset => {
+                        Some(format!("{} was removed in opset {}", op_type, removed))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unavailable_in() {
+        let count = |domain: &str, op_type: &str| OperatorCount {
+            domain: domain.to_string(),
+            op_type: op_type.to_string(),
+            count: 1,
+        };
+        let operators = Operators {
+            opsets: BTreeMap::from([(DEFAULT_DOMAIN.to_string(), 20)]),
+            counts: vec![
+                count("", "MatMul"),
+                count("", "Gelu"),
+                count("", "LayerNormalization"),
+                count("", "Upsample"),
+                count("", "FusedGemm"),
+                // other domains aren't checked
+                count("com.microsoft", "FusedGemm"),
+            ],
+            unavailable: vec![],
+        };
+
+        assert_eq!(
+            operators.unavailable_in(17),
+            vec![
+                "Gelu requires opset 20",
+                "Upsample was removed in opset 10",
+                "FusedGemm is not an operator of the ai.onnx domain",
+            ]
+        );
+        assert_eq!(
+            operators.unavailable_in(9),
+            vec![
+                "Gelu requires opset 20",
+                "LayerNormalization requires opset 17",
+                "FusedGemm is not an operator of the ai.onnx domain",
+            ]
+        );
+        assert_eq!(operators.counts[5].name(), "com.microsoft.FusedGemm");
+    }
+}
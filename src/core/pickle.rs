@@ -0,0 +1,584 @@
+use std::{collections::HashMap, io::Read, path::Path};
+
+use anyhow::anyhow;
+
+use super::scan::{Issue, Safety};
+
+// extensions of the files that may contain pickles
+const PICKLE_EXTENSIONS: &[&str] = &["pt", "pth", "bin", "ckpt", "pkl", "pickle"];
+
+// modules that can run code, access the system or the network, every global is dangerous
+const DANGEROUS_MODULES: &[&str] = &[
+    "os",
+    "nt",
+    "posix",
+    "subprocess",
+    "sys",
+    "socket",
+    "shutil",
+    "runpy",
+    "pty",
+    "webbrowser",
+    "importlib",
+    "pickle",
+    "_pickle",
+    "marshal",
+    "ctypes",
+    "code",
+    "commands",
+    "asyncio",
+    "multiprocessing",
+    "requests",
+    "urllib",
+    "httplib",
+    "http",
+    "aiohttp",
+    "ftplib",
+    "smtplib",
+    "telnetlib",
+    "bdb",
+    "pdb",
+    "timeit",
+    "signal",
+    "tempfile",
+    "torch.hub",
+    "torch.serialization",
+];
+
+// dangerous globals of otherwise harmless modules
+const DANGEROUS_GLOBALS: &[(&str, &str)] = &[
+    ("builtins", "eval"),
+    ("builtins", "exec"),
+    ("builtins", "execfile"),
+    ("builtins", "compile"),
+    ("builtins", "open"),
+    ("builtins", "getattr"),
+    ("builtins", "setattr"),
+    ("builtins", "delattr"),
+    ("builtins", "globals"),
+    ("builtins", "locals"),
+    ("builtins", "breakpoint"),
+    ("builtins", "input"),
+    ("builtins", "apply"),
+    ("builtins", "__import__"),
+    ("operator", "attrgetter"),
+    ("operator", "methodcaller"),
+    ("functools", "partial"),
+    ("pydoc", "pipepager"),
+    ("numpy.testing._private.utils", "runstring"),
+];
+
+// globals used by PyTorch, NumPy and the standard library to rebuild data
+const SAFE_GLOBALS: &[(&str, &str)] = &[
+    ("collections", "OrderedDict"),
+    ("collections", "defaultdict"),
+    ("torch", "Size"),
+    ("torch", "device"),
+    ("torch", "dtype"),
+    ("torch._tensor", "_rebuild_from_type_v2"),
+    ("torch.nn.parameter", "Parameter"),
+    ("numpy", "dtype"),
+    ("numpy", "ndarray"),
+    ("numpy.core.multiarray", "_reconstruct"),
+    ("numpy.core.multiarray", "scalar"),
+    ("numpy._core.multiarray", "_reconstruct"),
+    ("numpy._core.multiarray", "scalar"),
+    ("_codecs", "encode"),
+    ("builtins", "set"),
+    ("builtins", "frozenset"),
+    ("builtins", "slice"),
+    ("builtins", "range"),
+    ("builtins", "complex"),
+    ("builtins", "bytearray"),
+    ("builtins", "dict"),
+    ("builtins", "list"),
+    ("builtins", "tuple"),
+    ("builtins", "int"),
+    ("builtins", "float"),
+    ("builtins", "bool"),
+    ("builtins", "str"),
+    ("builtins", "bytes"),
+];
+
+// torch globals for the data types (torch.float16, ...)
+const TORCH_DTYPES: &[&str] = &[
+    "float16",
+    "float32",
+    "float64",
+    "bfloat16",
+    "half",
+    "float",
+    "double",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "uint8",
+    "bool",
+    "long",
+    "int",
+    "short",
+    "complex64",
+    "complex128",
+];
+
+/// A module attribute imported by a pickle, with GLOBAL, STACK_GLOBAL or INST.
+#[derive(Debug, Clone, PartialEq)]
+struct Import {
+    module: String,
+    name: String,
+    safety: Safety,
+    // name of the pickle in the archive, empty for plain pickle files
+    pickle: String,
+    // offset of the opcode in the pickle
+    offset: usize,
+}
+
+impl Import {
+    fn new(module: &str, name: &str, pickle: &str, offset: usize) -> Self {
+        Self {
+            module: module.to_string(),
+            name: name.to_string(),
+            safety: safety_of(module, name),
+            pickle: pickle.to_string(),
+            offset,
+        }
+    }
+
+    // the issue of an import that isn't safe
+    fn issue(&self) -> Option<Issue> {
+        let rule_id = match self.safety {
+            Safety::Safe => return None,
+            Safety::Suspicious => "suspicious-import",
+            Safety::Dangerous => "dangerous-import",
+        };
+        Some(Issue {
+            rule_id,
+            safety: self.safety,
+            message: format!("import of {}.{}", self.module, self.name),
+            location: self.pickle.clone(),
+            // offsets in the archive entries are meaningless in the file
+            offset: self.pickle.is_empty().then_some(self.offset as u64),
+        })
+    }
+}
+
+fn safety_of(module: &str, name: &str) -> Safety {
+    // python 2 pickles
+    let module = match module {
+        "__builtin__" | "__builtins__" => "builtins",
+        "copy_reg" => "copyreg",
+        _ => module,
+    };
+
+    let is_submodule_of =
+        |parent: &str| module == parent || module.starts_with(&format!("{}.", parent));
+    if DANGEROUS_MODULES
+        .iter()
+        .any(|parent| is_submodule_of(parent))
+        || DANGEROUS_GLOBALS.contains(&(module, name))
+    {
+        Safety::Dangerous
+    } else if SAFE_GLOBALS.contains(&(module, name))
+        || (module == "torch._utils" && name.starts_with("_rebuild_"))
+        || (module == "torch" && (name.ends_with("Storage") || TORCH_DTYPES.contains(&name)))
+    {
+        Safety::Safe
+    } else {
+        Safety::Suspicious
+    }
+}
+
+/// Reads the opcodes and arguments of a pickle.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pub offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], offset: usize) -> Self {
+        Self { data, offset }
+    }
+
+    pub(crate) fn bytes(&mut self, size: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(size)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("unexpected end of pickle at offset {}", self.offset))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn uint(&mut self, size: usize) -> anyhow::Result<u64> {
+        Ok(self
+            .bytes(size)?
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | *byte as u64))
+    }
+
+    pub(crate) fn sized(&mut self, size_of_size: usize) -> anyhow::Result<&'a [u8]> {
+        let size = self.uint(size_of_size)?;
+        self.bytes(usize::try_from(size)?)
+    }
+
+    pub(crate) fn line(&mut self) -> anyhow::Result<String> {
+        let rest = &self.data[self.offset..];
+        let end = rest
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| anyhow!("unterminated line at offset {}", self.offset))?;
+        self.offset += end + 1;
+        Ok(String::from_utf8_lossy(&rest[..end]).to_string())
+    }
+}
+
+/// Walks the opcodes of the pickle starting at the offset without executing them, and returns
+/// the imports and the offset following the STOP opcode.
+fn walk(data: &[u8], offset: usize, pickle: &str) -> anyhow::Result<(Vec<Import>, usize)> {
+    let mut reader = Reader::new(data, offset);
+    let mut imports = vec![];
+    // strings pushed on the stack, None for any other value, to resolve STACK_GLOBAL
+    let mut pushed: Vec<Option<String>> = vec![];
+    let mut memo: HashMap<u64, Option<String>> = HashMap::new();
+
+    loop {
+        let opcode_offset = reader.offset;
+        let opcode = reader.bytes(1)?[0];
+        match opcode {
+            // STOP
+            b'.' => return Ok((imports, reader.offset)),
+            // GLOBAL and INST
+            b'c' | b'i' => {
+                let module = reader.line()?;
+                let name = reader.line()?;
+                imports.push(Import::new(&module, &name, pickle, opcode_offset));
+                pushed.push(None);
+            }
+            // STACK_GLOBAL
+            0x93 => {
+                let name = pushed.pop().flatten();
+                let module = pushed.pop().flatten();
+                match (module, name) {
+                    (Some(module), Some(name)) => {
+                        imports.push(Import::new(&module, &name, pickle, opcode_offset))
+                    }
+                    // the module or name are computed, which only makes sense to evade scanners
+                    _ => imports.push(Import {
+                        module: "?".to_string(),
+                        name: "?".to_string(),
+                        safety: Safety::Dangerous,
+                        pickle: pickle.to_string(),
+                        offset: opcode_offset,
+                    }),
+                }
+                pushed.push(None);
+            }
+            // UNICODE, STRING
+            b'V' | b'S' => {
+                let line = reader.line()?;
+                pushed.push(Some(line.trim_matches(['\'', '"']).to_string()));
+            }
+            // SHORT_BINUNICODE, SHORT_BINSTRING
+            0x8c | b'U' => {
+                let value = reader.sized(1)?;
+                pushed.push(Some(String::from_utf8_lossy(value).to_string()));
+            }
+            // BINUNICODE, BINSTRING
+            b'X' | b'T' => {
+                let value = reader.sized(4)?;
+                pushed.push(Some(String::from_utf8_lossy(value).to_string()));
+            }
+            // BINUNICODE8
+            0x8d => {
+                let value = reader.sized(8)?;
+                pushed.push(Some(String::from_utf8_lossy(value).to_string()));
+            }
+            // MEMOIZE
+            0x94 => {
+                let index = memo.len() as u64;
+                memo.insert(index, pushed.last().cloned().flatten());
+            }
+            // PUT, BINPUT, LONG_BINPUT
+            b'p' | b'q' | b'r' => {
+                let index = match opcode {
+                    b'p' => reader.line()?.parse()?,
+                    b'q' => reader.uint(1)?,
+                    _ => reader.uint(4)?,
+                };
+                memo.insert(index, pushed.last().cloned().flatten());
+            }
+            // GET, BINGET, LONG_BINGET
+            b'g' | b'h' | b'j' => {
+                let index: u64 = match opcode {
+                    b'g' => reader.line()?.parse()?,
+                    b'h' => reader.uint(1)?,
+                    _ => reader.uint(4)?,
+                };
+                pushed.push(memo.get(&index).cloned().flatten());
+            }
+            // INT, LONG, FLOAT, PERSID
+            b'I' | b'L' | b'F' | b'P' => {
+                reader.line()?;
+                pushed.push(None);
+            }
+            // BININT1, EXT1
+            b'K' | 0x82 => {
+                reader.bytes(1)?;
+                pushed.push(None);
+            }
+            // BININT2, EXT2
+            b'M' | 0x83 => {
+                reader.bytes(2)?;
+                pushed.push(None);
+            }
+            // BININT, EXT4
+            b'J' | 0x84 => {
+                reader.bytes(4)?;
+                pushed.push(None);
+            }
+            // BINFLOAT
+            b'G' => {
+                reader.bytes(8)?;
+                pushed.push(None);
+            }
+            // PROTO
+            0x80 => {
+                reader.bytes(1)?;
+            }
+            // FRAME
+            0x95 => {
+                reader.bytes(8)?;
+            }
+            // LONG1, SHORT_BINBYTES
+            0x8a | b'C' => {
+                reader.sized(1)?;
+                pushed.push(None);
+            }
+            // LONG4, BINBYTES
+            0x8b | b'B' => {
+                reader.sized(4)?;
+                pushed.push(None);
+            }
+            // BINBYTES8, BYTEARRAY8
+            0x8e | 0x96 => {
+                reader.sized(8)?;
+                pushed.push(None);
+            }
+            // opcodes without arguments
+            b'(' | b'0' | b'1' | b'2' | b'N' | b'Q' | b'R' | b'a' | b'b' | b'd' | b'}' | b'e'
+            | b'l' | b']' | b'o' | b's' | b't' | b')' | b'u' | 0x81 | 0x85 | 0x86 | 0x87 | 0x88
+            | 0x89 | 0x8f | 0x90 | 0x91 | 0x92 | 0x97 | 0x98 => {
+                pushed.push(None);
+            }
+            _ => {
+                return Err(anyhow!(
+                    "unknown opcode 0x{:02x} at offset {}",
+                    opcode,
+                    opcode_offset
+                ))
+            }
+        }
+    }
+}
+
+/// Returns the imports of the pickles of the data: a single pickle, or a sequence of pickles
+/// followed by raw data as in the legacy PyTorch format.
+fn scan_pickles(data: &[u8], pickle: &str) -> anyhow::Result<Vec<Import>> {
+    let (mut imports, mut offset) = walk(data, 0, pickle)?;
+    // following pickles start with PROTO
+    while data.get(offset) == Some(&0x80) {
+        match walk(data, offset, pickle) {
+            Ok((more, next)) => {
+                imports.extend(more);
+                offset = next;
+            }
+            // raw data starting like a pickle
+            Err(_) => break,
+        }
+    }
+    Ok(imports)
+}
+
+// whether the data starts like a PyTorch archive or a pickle of protocol 2 or higher
+fn has_pickle_magic(file_path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    match std::fs::File::open(file_path).and_then(|mut file| file.read(&mut magic)) {
+        Ok(4) => magic == *b"PK\x03\x04" || magic[0] == 0x80,
+        Ok(size) => size > 0 && magic[0] == 0x80,
+        Err(_) => false,
+    }
+}
+
+/// Whether the file can be scanned, from its extension and, for .bin files that are also used for
+/// raw weights, its first bytes.
+pub(crate) fn is_pickle_based(file_path: &Path) -> bool {
+    let ext = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    PICKLE_EXTENSIONS.contains(&ext.as_str()) && (ext != "bin" || has_pickle_magic(file_path))
+}
+
+/// Statically scans a pickle, a PyTorch archive or a legacy PyTorch file for harmful imports.
+pub(crate) fn scan(file_path: &Path) -> anyhow::Result<Vec<Issue>> {
+    let mut imports = vec![];
+
+    let mut file = std::fs::File::open(file_path)?;
+    let mut magic = [0u8; 4];
+    let is_zip = file.read(&mut magic)? == 4 && magic == *b"PK\x03\x04";
+    if is_zip {
+        // PyTorch archives store the model in data.pkl, TorchScript ones in constants.pkl too
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(file_path)?)?;
+        let names: Vec<String> = archive
+            .file_names()
+            .filter(|name| name.ends_with(".pkl"))
+            .map(String::from)
+            .collect();
+        if names.is_empty() {
+            return Err(anyhow!("no pickle found in the archive"));
+        }
+        for name in names {
+            let mut data = vec![];
+            archive.by_name(&name)?.read_to_end(&mut data)?;
+            imports.extend(
+                scan_pickles(&data, &name)
+                    .map_err(|e| anyhow!("invalid pickle {}: {}", name, e))?,
+            );
+        }
+    } else {
+        let data = std::fs::read(file_path)?;
+        imports = scan_pickles(&data, "").map_err(|e| anyhow!("invalid pickle: {}", e))?;
+    }
+
+    // report each import once
+    let mut seen = std::collections::HashSet::new();
+    imports.retain(|import| seen.insert((import.module.clone(), import.name.clone())));
+
+    Ok(imports.iter().filter_map(Import::issue).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safety_of() {
+        assert_eq!(safety_of("os", "system"), Safety::Dangerous);
+        assert_eq!(safety_of("os.path", "join"), Safety::Dangerous);
+        assert_eq!(safety_of("__builtin__", "eval"), Safety::Dangerous);
+        assert_eq!(safety_of("builtins", "set"), Safety::Safe);
+        assert_eq!(
+            safety_of("torch._utils", "_rebuild_tensor_v2"),
+            Safety::Safe
+        );
+        assert_eq!(safety_of("torch", "BFloat16Storage"), Safety::Safe);
+        assert_eq!(safety_of("torch", "load"), Safety::Suspicious);
+        assert_eq!(safety_of("__main__", "Net"), Safety::Suspicious);
+        // not a submodule of os
+        assert_eq!(safety_of("osmium", "Reader"), Safety::Suspicious);
+    }
+
+    #[test]
+    fn test_walk_protocol_0() {
+        // pickle.dumps(os.system("id"), protocol=0) with a reduce
+        let data = b"cposix\nsystem\np0\n(Vid\np1\ntp2\nRp3\n.";
+        let (imports, end) = walk(data, 0, "").unwrap();
+        assert_eq!(end, data.len());
+        assert_eq!(imports, vec![Import::new("posix", "system", "", 0)]);
+        assert_eq!(imports[0].safety, Safety::Dangerous);
+    }
+
+    #[test]
+    fn test_walk_stack_global() {
+        // protocol 4: STACK_GLOBAL with the module and name pushed as short unicode strings,
+        // then again through the memo
+        let mut data = vec![0x80, 4, 0x95];
+        data.extend(0u64.to_le_bytes());
+        for value in ["torch._utils", "_rebuild_tensor_v2"] {
+            data.push(0x8c);
+            data.push(value.len() as u8);
+            data.extend(value.as_bytes());
+            data.push(0x94);
+        }
+        data.push(0x93);
+        // BINGET 0 and 1
+        data.extend([b'h', 0, b'h', 1, 0x93, b'.']);
+
+        let (imports, _) = walk(&data, 0, "data.pkl").unwrap();
+        assert_eq!(imports.len(), 2);
+        assert!(imports.iter().all(|import| import.module == "torch._utils"
+            && import.name == "_rebuild_tensor_v2"
+            && import.safety == Safety::Safe
+            && import.pickle == "data.pkl"));
+
+        // computed names can't be resolved
+        let data = [0x80, 4, b'N', b'N', 0x93, b'.'];
+        let (imports, _) = walk(&data, 0, "").unwrap();
+        assert_eq!(imports[0].module, "?");
+        assert_eq!(imports[0].safety, Safety::Dangerous);
+    }
+
+    #[test]
+    fn test_walk_errors() {
+        assert!(walk(b"\x80\x02\xff.", 0, "").is_err());
+        // truncated
+        assert!(walk(b"\x80\x04\x8c\x10os", 0, "").is_err());
+        assert!(walk(b"cos\nsystem", 0, "").is_err());
+    }
+
+    #[test]
+    fn test_scan_archive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("model.pt");
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&file_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("archive/data.pkl", options).unwrap();
+        std::io::Write::write_all(
+            &mut writer,
+            b"\x80\x02ccollections\nOrderedDict\nq\x00)Rq\x01cbuiltins\nexec\nq\x02.",
+        )
+        .unwrap();
+        writer.start_file("archive/data/0", options).unwrap();
+        std::io::Write::write_all(&mut writer, &[0u8; 16]).unwrap();
+        writer.finish().unwrap();
+
+        // OrderedDict is safe
+        let issues = scan(&file_path).unwrap();
+        assert_eq!(
+            issues,
+            vec![Issue {
+                rule_id: "dangerous-import",
+                safety: Safety::Dangerous,
+                message: "import of builtins.exec".to_string(),
+                location: "archive/data.pkl".to_string(),
+                offset: None,
+            }]
+        );
+
+        // legacy format, a sequence of pickles followed by raw data
+        let file_path = temp_dir.path().join("legacy.pth");
+        std::fs::write(
+            &file_path,
+            b"\x80\x02K\x01.\x80\x02ctorch\nFloatStorage\nq\x00.\x80\x02c__main__\nNet\n.\x00\x80\x01",
+        )
+        .unwrap();
+        let issues = scan(&file_path).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].message, "import of __main__.Net");
+
+        assert!(is_pickle_based(&file_path));
+        let weights_path = temp_dir.path().join("weights.bin");
+        std::fs::write(&weights_path, [0u8; 16]).unwrap();
+        assert!(!is_pickle_based(&weights_path));
+        let weights_path = temp_dir.path().join("pytorch_model.bin");
+        std::fs::write(&weights_path, b"\x80\x02N.").unwrap();
+        assert!(is_pickle_based(&weights_path));
+    }
+}
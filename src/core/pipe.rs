@@ -0,0 +1,182 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+// bytes read at once from the stream
+const CHUNK_SIZE: usize = 64 * 1024;
+// bytes needed to tell the formats apart
+const SNIFF_SIZE: usize = 9;
+
+/// Returns true if the path is `-`, the standard input.
+pub(crate) fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Returns the extension of the format the content starts with, if it is a SafeTensors, GGUF or
+/// ONNX file.
+pub(crate) fn sniff_extension(content: &[u8]) -> Option<&'static str> {
+    let head = &content[..content.len().min(SNIFF_SIZE)];
+    if head.starts_with(b"GGUF") {
+        Some("gguf")
+    } else if head.len() == SNIFF_SIZE && head[8] == b'{' {
+        // the size of the JSON header followed by its opening brace
+        Some("safetensors")
+    } else if head.first() == Some(&0x08) {
+        // the ir_version field of the ONNX model, written first
+        Some("onnx")
+    } else {
+        None
+    }
+}
+
+/// A file read once from a stream such as the standard input: the bytes read so far are buffered
+/// so that the header can be parsed with seeks, the rest of the stream is only counted.
+pub(crate) struct PipedFile<R: Read> {
+    inner: R,
+    buffer: Vec<u8>,
+    position: u64,
+    eof: bool,
+}
+
+impl<R: Read> PipedFile<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: vec![],
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the number of bytes buffered so far.
+    pub(crate) fn buffered(&self) -> u64 {
+        self.buffer.len() as u64
+    }
+
+    // reads from the stream until the buffer has the bytes up to end or the stream is over
+    fn fill(&mut self, end: u64) -> std::io::Result<()> {
+        while !self.eof && (self.buffer.len() as u64) < end {
+            let start = self.buffer.len();
+            self.buffer.resize(start + CHUNK_SIZE, 0);
+            let read = self.inner.read(&mut self.buffer[start..]);
+            self.buffer
+                .truncate(start + read.as_ref().map_or(0, |read| *read));
+            match read {
+                Ok(read) => self.eof = read == 0,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the extension of the format the stream starts with, if it is a SafeTensors, GGUF
+    /// or ONNX file.
+    pub(crate) fn sniff_extension(&mut self) -> std::io::Result<Option<&'static str>> {
+        self.fill(SNIFF_SIZE as u64)?;
+        Ok(sniff_extension(&self.buffer))
+    }
+
+    /// Consumes the rest of the stream and writes the buffered bytes to a file of the size of the
+    /// stream, the rest is left zeroed. Returns the size of the stream.
+    pub(crate) fn save(mut self, path: &Path) -> anyhow::Result<u64> {
+        let rest = std::io::copy(&mut self.inner, &mut std::io::sink())?;
+        let size = self.buffered() + rest;
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(&self.buffer)?;
+        // sparse on most file systems
+        file.set_len(size)?;
+        file.flush()?;
+
+        Ok(size)
+    }
+}
+
+impl<R: Read> Read for PipedFile<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.fill(self.position.saturating_add(buf.len() as u64))?;
+        let Some(available) = usize::try_from(self.position)
+            .ok()
+            .and_then(|position| self.buffer.get(position..))
+        else {
+            return Ok(0);
+        };
+
+        let size = available.len().min(buf.len());
+        buf[..size].copy_from_slice(&available[..size]);
+        self.position += size as u64;
+        Ok(size)
+    }
+}
+
+impl<R: Read> Seek for PipedFile<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => {
+                // the size is only known once the whole stream is buffered
+                self.fill(u64::MAX)?;
+                self.buffered().checked_add_signed(delta)
+            }
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piped_file() {
+        let content: Vec<u8> = (0..200_000u32).map(|value| value as u8).collect();
+        let mut file = PipedFile::new(content.as_slice());
+
+        let mut head = [0u8; 4];
+        file.seek(SeekFrom::Start(70_000)).unwrap();
+        file.read_exact(&mut head).unwrap();
+        assert_eq!(head, content[70_000..70_004]);
+        // already buffered bytes are read again
+        file.seek(SeekFrom::Current(-8)).unwrap();
+        file.read_exact(&mut head).unwrap();
+        assert_eq!(head, content[69_996..70_000]);
+        assert!(file.buffered() < content.len() as u64);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model");
+        assert_eq!(file.save(&path).unwrap(), content.len() as u64);
+        let saved = std::fs::read(&path).unwrap();
+        assert_eq!(saved.len(), content.len());
+        assert_eq!(saved[..70_004], content[..70_004]);
+        assert!(saved[140_000..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_sniff_extension() {
+        let sniff = |content: &[u8]| PipedFile::new(content).sniff_extension().unwrap();
+        assert_eq!(sniff(b"GGUF\x03\x00\x00\x00"), Some("gguf"));
+        assert_eq!(
+            sniff(b"\x10\x00\x00\x00\x00\x00\x00\x00{\"a\":{}}"),
+            Some("safetensors")
+        );
+        assert_eq!(sniff(b"\x08\x07\x12\x04test"), Some("onnx"));
+        assert_eq!(sniff(b"PK\x03\x04"), None);
+        assert_eq!(sniff(b""), None);
+
+        // the sniffed bytes are read again
+        let mut file = PipedFile::new(b"GGUF".as_slice());
+        file.sniff_extension().unwrap();
+        let mut content = vec![];
+        file.read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"GGUF");
+    }
+}
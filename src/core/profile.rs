@@ -0,0 +1,88 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Hyperparameters of a transformer, as declared by the model metadata.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct ModelProfile {
+    pub architecture: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_forward_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_count: Option<u64>,
+    /// Largest number of key and value heads of the layers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_count_kv: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rope_dimension_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rope_freq_base: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rope_scaling_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rope_scaling_factor: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rope_original_context_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vocab_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expert_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expert_used_count: Option<u64>,
+}
+
+impl ModelProfile {
+    /// Labels and values of the hyperparameters that are set, for display.
+    pub(crate) fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![("architecture", self.architecture.clone())];
+        let integers = [
+            ("context length", self.context_length),
+            ("embedding size", self.embedding_length),
+            ("layers", self.block_count),
+            ("feed forward", self.feed_forward_length),
+            ("heads", self.head_count),
+            ("kv heads", self.head_count_kv),
+            ("key length", self.key_length),
+            ("value length", self.value_length),
+            ("rope dims", self.rope_dimension_count),
+        ];
+        rows.extend(
+            integers
+                .into_iter()
+                .filter_map(|(label, value)| value.map(|value| (label, value.to_string()))),
+        );
+        if let Some(base) = self.rope_freq_base {
+            rows.push(("rope base", base.to_string()));
+        }
+        if let Some(scaling) = &self.rope_scaling_type {
+            let mut value = scaling.clone();
+            if let Some(factor) = self.rope_scaling_factor {
+                value.push_str(&format!(" x{}", factor));
+            }
+            if let Some(original) = self.rope_original_context_length {
+                value.push_str(&format!(" from {} tokens", original));
+            }
+            rows.push(("rope scaling", value));
+        }
+        let integers = [
+            ("vocab size", self.vocab_size),
+            ("experts", self.expert_count),
+            ("experts used", self.expert_used_count),
+        ];
+        rows.extend(
+            integers
+                .into_iter()
+                .filter_map(|(label, value)| value.map(|value| (label, value.to_string()))),
+        );
+        rows
+    }
+}
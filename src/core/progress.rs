@@ -0,0 +1,155 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+const BYTES_TEMPLATE: &str =
+    "{prefix:>5} [{bar:30}] {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta}) {wide_msg}";
+
+// bars are hidden along with the info messages, for instance with --quiet
+fn draw_target() -> ProgressDrawTarget {
+    if tracing::enabled!(tracing::Level::INFO) {
+        ProgressDrawTarget::stderr()
+    } else {
+        ProgressDrawTarget::hidden()
+    }
+}
+
+fn bytes_style() -> ProgressStyle {
+    ProgressStyle::with_template(BYTES_TEMPLATE)
+        .unwrap()
+        .progress_chars("=> ")
+}
+
+/// Progress of the bytes hashed, for each file and in total, drawn on stderr only if it is a
+/// terminal and info messages are logged.
+pub(crate) struct Progress {
+    bars: MultiProgress,
+    total: ProgressBar,
+}
+
+impl Progress {
+    pub(crate) fn for_files(paths: &[PathBuf]) -> Self {
+        let total_size = paths
+            .iter()
+            .filter_map(|path| path.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        let bars = MultiProgress::with_draw_target(draw_target());
+        let total = bars.add(
+            ProgressBar::new(total_size)
+                .with_style(bytes_style())
+                .with_prefix("total"),
+        );
+
+        Self { bars, total }
+    }
+
+    /// Logs the message above the bars, hidden while it is written.
+    pub(crate) fn log(&self, message: &str) {
+        self.bars.suspend(|| tracing::info!("{}", message));
+    }
+
+    /// Starts the bar of a file, cleared once dropped.
+    pub(crate) fn file(&self, path: &Path) -> FileProgress {
+        let size = path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        let file = self.bars.insert_before(
+            &self.total,
+            ProgressBar::new(size)
+                .with_style(bytes_style())
+                .with_prefix("file")
+                .with_message(
+                    path.file_name()
+                        .unwrap_or(path.as_os_str())
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+        );
+
+        FileProgress {
+            file,
+            total: self.total.clone(),
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.total.finish_and_clear();
+    }
+}
+
+pub(crate) struct FileProgress {
+    file: ProgressBar,
+    total: ProgressBar,
+}
+
+impl FileProgress {
+    /// A progress that is never drawn.
+    #[cfg(test)]
+    pub(crate) fn hidden() -> Self {
+        Self {
+            file: ProgressBar::hidden(),
+            total: ProgressBar::hidden(),
+        }
+    }
+
+    pub(crate) fn inc(&self, bytes: u64) {
+        self.file.inc(bytes);
+        self.total.inc(bytes);
+    }
+
+    /// Advances the progress with the bytes read.
+    pub(crate) fn wrap_read<R: Read>(&self, reader: R) -> ProgressRead<'_, R> {
+        ProgressRead {
+            reader,
+            progress: self,
+        }
+    }
+}
+
+impl Drop for FileProgress {
+    fn drop(&mut self) {
+        self.file.finish_and_clear();
+    }
+}
+
+pub(crate) struct ProgressRead<'a, R> {
+    reader: R,
+    progress: &'a FileProgress,
+}
+
+impl<R: Read> Read for ProgressRead<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.progress.inc(read as u64);
+        Ok(read)
+    }
+}
+
+/// Spinner with the elapsed time for long operations of unknown duration, cleared once dropped.
+pub(crate) struct Spinner(ProgressBar);
+
+impl Spinner {
+    pub(crate) fn new(message: &str) -> Self {
+        let spinner = ProgressBar::with_draw_target(None, draw_target())
+            .with_style(ProgressStyle::with_template("{spinner} {msg} ({elapsed})").unwrap())
+            .with_message(message.to_string());
+        spinner.enable_steady_tick(Duration::from_millis(100));
+        Self(spinner)
+    }
+
+    pub(crate) fn set_message(&self, message: &str) {
+        self.0.set_message(message.to_string());
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.0.finish_and_clear();
+    }
+}
@@ -0,0 +1,658 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use ureq::http::{header, Response, StatusCode};
+
+const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(120);
+// signatures and manifests are small, models are never downloaded
+const MAX_BODY_SIZE: u64 = 64 * 1024 * 1024;
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const MANIFEST_MEDIA_TYPES: &str = "application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.v2+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json";
+const EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+const EMPTY_CONFIG: &[u8] = b"{}";
+
+pub(crate) const SIGNATURE_ARTIFACT_TYPE: &str =
+    "application/vnd.dreadnode.tensor-man.signature.v1";
+pub(crate) const MANIFEST_MEDIA_TYPE: &str =
+    "application/vnd.dreadnode.tensor-man.manifest.v1+json";
+pub(crate) const DSSE_MEDIA_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+
+const TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+const CREATED_ANNOTATION: &str = "org.opencontainers.image.created";
+
+/// An image reference, `[registry/]repository[:tag][@digest]`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Reference {
+    pub registry: String,
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+impl Reference {
+    pub(crate) fn parse(reference: &str) -> anyhow::Result<Self> {
+        let invalid = || anyhow::anyhow!("invalid image reference '{}'", reference);
+
+        let (name, digest) = match reference.split_once('@') {
+            Some((name, digest)) => (name, Some(digest.to_string())),
+            None => (reference, None),
+        };
+        // the tag is after the last path component, the registry can have a port
+        let (name, tag) = match name.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => (repository, Some(tag.to_string())),
+            _ => (name, None),
+        };
+
+        // the first component is a registry only if it looks like a host name, as docker does
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, repository))
+                if host.contains('.') || host.contains(':') || host == "localhost" =>
+            {
+                (host.to_string(), repository.to_string())
+            }
+            Some(_) => (DOCKER_HUB_REGISTRY.to_string(), name.to_string()),
+            None => (DOCKER_HUB_REGISTRY.to_string(), format!("library/{}", name)),
+        };
+
+        if repository.is_empty()
+            || repository
+                .chars()
+                .any(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || "._-/".contains(c)))
+        {
+            return Err(invalid());
+        }
+        if let Some(digest) = &digest {
+            if !digest.starts_with("sha256:") || digest.len() != 71 {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Self {
+            registry: if registry == "docker.io" {
+                DOCKER_HUB_REGISTRY.to_string()
+            } else {
+                registry
+            },
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    // digest if set, otherwise tag
+    fn reference(&self) -> &str {
+        self.digest
+            .as_deref()
+            .or(self.tag.as_deref())
+            .unwrap_or("latest")
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.registry, self.repository)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// OCI content descriptor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_type: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl Descriptor {
+    fn of(media_type: &str, data: &[u8]) -> Self {
+        Self {
+            media_type: media_type.to_string(),
+            digest: digest_of(data),
+            size: data.len() as u64,
+            artifact_type: None,
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    /// Creation date of the artifact, if annotated.
+    pub(crate) fn created(&self) -> Option<&str> {
+        self.annotations.get(CREATED_ANNOTATION).map(|s| s.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageManifest {
+    schema_version: u32,
+    media_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    artifact_type: Option<String>,
+    config: Descriptor,
+    layers: Vec<Descriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subject: Option<Descriptor>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageIndex {
+    schema_version: u32,
+    media_type: String,
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+}
+
+/// A signature downloaded from the registry.
+pub(crate) struct Signature {
+    // file name the signature has been pushed with
+    pub title: Option<String>,
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+fn digest_of(data: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(digest(&SHA256, data)))
+}
+
+// tag of the image index listing the referrers of a digest, on registries without referrers API
+fn fallback_tag(digest: &str) -> String {
+    digest.replace(':', "-")
+}
+
+// parses a WWW-Authenticate challenge into its scheme and parameters
+fn parse_challenge(challenge: &str) -> Option<(String, BTreeMap<String, String>)> {
+    let (scheme, rest) = challenge.trim().split_once(' ')?;
+    let mut params = BTreeMap::new();
+    let mut rest = rest.trim();
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => value.split_at(value.find(',').unwrap_or(value.len())),
+        };
+        params.insert(key.trim().to_lowercase(), value.to_string());
+        rest = next.trim_start_matches([',', ' ']);
+    }
+    Some((scheme.to_lowercase(), params))
+}
+
+/// Minimal client of the OCI distribution API, to store signatures as artifacts referring to models.
+pub(crate) struct Client {
+    agent: ureq::Agent,
+    base_url: String,
+    repository: String,
+    // from TMAN_REGISTRY_USERNAME and TMAN_REGISTRY_PASSWORD
+    credentials: Option<(String, String)>,
+    // value of the Authorization header, once authenticated
+    authorization: Option<String>,
+}
+
+impl Client {
+    pub(crate) fn new(reference: &Reference, plain_http: bool) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(HTTP_TIMEOUT))
+            .build()
+            .into();
+        let credentials = match (
+            std::env::var("TMAN_REGISTRY_USERNAME"),
+            std::env::var("TMAN_REGISTRY_PASSWORD"),
+        ) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            _ => None,
+        };
+
+        Self {
+            agent,
+            base_url: format!(
+                "{}://{}",
+                if plain_http { "http" } else { "https" },
+                reference.registry
+            ),
+            repository: reference.repository.clone(),
+            credentials,
+            authorization: None,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/v2/{}/{}", self.base_url, self.repository, path)
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&[u8]>,
+    ) -> anyhow::Result<Response<ureq::Body>> {
+        let mut request = ureq::http::Request::builder().method(method).uri(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if let Some(authorization) = &self.authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+
+        let response = match body {
+            Some(body) => self.agent.run(request.body(body)?),
+            None => self.agent.run(request.body(())?),
+        };
+        response.map_err(|e| anyhow::anyhow!("{} {} failed: {}", method, url, e))
+    }
+
+    // sends the request, authenticating and retrying once if required by the registry
+    fn request(
+        &mut self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&[u8]>,
+    ) -> anyhow::Result<Response<ureq::Body>> {
+        let response = self.send(method, url, headers, body)?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let challenge = response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| anyhow::anyhow!("{} {}: unauthorized", method, url))?
+            .to_string();
+        self.authenticate(&challenge)?;
+
+        let response = self.send(method, url, headers, body)?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            anyhow::bail!(
+                "{} {}: unauthorized, set TMAN_REGISTRY_USERNAME and TMAN_REGISTRY_PASSWORD",
+                method,
+                url
+            );
+        }
+        Ok(response)
+    }
+
+    fn authenticate(&mut self, challenge: &str) -> anyhow::Result<()> {
+        let (scheme, params) = parse_challenge(challenge)
+            .ok_or_else(|| anyhow::anyhow!("invalid authentication challenge: {}", challenge))?;
+        let basic = self.credentials.as_ref().map(|(username, password)| {
+            format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", username, password))
+            )
+        });
+
+        match scheme.as_str() {
+            "basic" => {
+                self.authorization = Some(basic.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "the registry requires TMAN_REGISTRY_USERNAME and TMAN_REGISTRY_PASSWORD"
+                    )
+                })?);
+            }
+            "bearer" => {
+                let realm = params
+                    .get("realm")
+                    .ok_or_else(|| anyhow::anyhow!("missing realm in challenge: {}", challenge))?;
+                let mut request = self.agent.get(realm);
+                for key in ["service", "scope"] {
+                    if let Some(value) = params.get(key) {
+                        request = request.query(key, value);
+                    }
+                }
+                if let Some(basic) = &basic {
+                    request = request.header(header::AUTHORIZATION, basic);
+                }
+
+                let mut response = request
+                    .call()
+                    .map_err(|e| anyhow::anyhow!("token request to {} failed: {}", realm, e))?;
+                if !response.status().is_success() {
+                    anyhow::bail!("token request to {} failed: {}", realm, response.status());
+                }
+                let token: serde_json::Value = serde_json::from_slice(&Self::read(&mut response)?)?;
+                let token = token
+                    .get("token")
+                    .or_else(|| token.get("access_token"))
+                    .and_then(|token| token.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("no token in the response of {}", realm))?;
+                self.authorization = Some(format!("Bearer {}", token));
+            }
+            _ => anyhow::bail!("unsupported authentication scheme: {}", scheme),
+        }
+
+        Ok(())
+    }
+
+    fn read(response: &mut Response<ureq::Body>) -> anyhow::Result<Vec<u8>> {
+        Ok(response
+            .body_mut()
+            .with_config()
+            .limit(MAX_BODY_SIZE)
+            .read_to_vec()?)
+    }
+
+    fn check(
+        response: &Response<ureq::Body>,
+        expected: StatusCode,
+        what: &str,
+    ) -> anyhow::Result<()> {
+        if response.status() != expected {
+            anyhow::bail!("{} failed: {}", what, response.status());
+        }
+        Ok(())
+    }
+
+    fn header<'a>(response: &'a Response<ureq::Body>, name: &str) -> Option<&'a str> {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    // returns the manifest and its descriptor, None if it doesn't exist
+    fn get_manifest(&mut self, reference: &str) -> anyhow::Result<Option<(Descriptor, Vec<u8>)>> {
+        let url = self.url(&format!("manifests/{}", reference));
+        let mut response = self.request("GET", &url, &[("Accept", MANIFEST_MEDIA_TYPES)], None)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Self::check(
+            &response,
+            StatusCode::OK,
+            &format!("fetching manifest {}", reference),
+        )?;
+
+        let media_type = Self::header(&response, "content-type")
+            .and_then(|value| value.split(';').next())
+            .unwrap_or(OCI_MANIFEST)
+            .trim()
+            .to_string();
+        let data = Self::read(&mut response)?;
+        let descriptor = Descriptor::of(&media_type, &data);
+        if reference.starts_with("sha256:") && descriptor.digest != reference {
+            anyhow::bail!("manifest {} doesn't match its digest", reference);
+        }
+
+        Ok(Some((descriptor, data)))
+    }
+
+    // uploads the manifest, returns true if the registry processed its subject
+    fn put_manifest(
+        &mut self,
+        reference: &str,
+        media_type: &str,
+        data: &[u8],
+    ) -> anyhow::Result<bool> {
+        let url = self.url(&format!("manifests/{}", reference));
+        let response = self.request("PUT", &url, &[("Content-Type", media_type)], Some(data))?;
+        Self::check(
+            &response,
+            StatusCode::CREATED,
+            &format!("pushing manifest {}", reference),
+        )?;
+        Ok(Self::header(&response, "oci-subject").is_some())
+    }
+
+    fn push_blob(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let digest = digest_of(data);
+        let url = self.url(&format!("blobs/{}", digest));
+        if self.request("HEAD", &url, &[], None)?.status() == StatusCode::OK {
+            return Ok(());
+        }
+
+        let url = self.url("blobs/uploads/");
+        let response = self.request("POST", &url, &[], Some(&[]))?;
+        Self::check(&response, StatusCode::ACCEPTED, "starting blob upload")?;
+        let location = Self::header(&response, "location")
+            .ok_or_else(|| anyhow::anyhow!("no upload location returned by the registry"))?;
+        let location = if location.starts_with('/') {
+            format!("{}{}", self.base_url, location)
+        } else {
+            location.to_string()
+        };
+        let separator = if location.contains('?') { '&' } else { '?' };
+        let url = format!(
+            "{}{}digest={}",
+            location,
+            separator,
+            digest.replace(':', "%3A")
+        );
+
+        let response = self.request(
+            "PUT",
+            &url,
+            &[("Content-Type", "application/octet-stream")],
+            Some(data),
+        )?;
+        Self::check(&response, StatusCode::CREATED, "uploading blob")
+    }
+
+    fn get_blob(&mut self, digest: &str) -> anyhow::Result<Vec<u8>> {
+        let url = self.url(&format!("blobs/{}", digest));
+        let mut response = self.request("GET", &url, &[], None)?;
+        Self::check(
+            &response,
+            StatusCode::OK,
+            &format!("fetching blob {}", digest),
+        )?;
+
+        let data = Self::read(&mut response)?;
+        if digest_of(&data) != digest {
+            anyhow::bail!("blob {} doesn't match its digest", digest);
+        }
+        Ok(data)
+    }
+
+    /// Resolves a reference to the descriptor of its manifest.
+    pub(crate) fn resolve(&mut self, reference: &Reference) -> anyhow::Result<Descriptor> {
+        self.get_manifest(reference.reference())?
+            .map(|(descriptor, _)| descriptor)
+            .ok_or_else(|| anyhow::anyhow!("{} not found", reference))
+    }
+
+    /// Pushes a signature as an artifact referring to the subject manifest, returns its descriptor.
+    pub(crate) fn push_signature(
+        &mut self,
+        subject: &Descriptor,
+        media_type: &str,
+        title: &str,
+        data: &[u8],
+    ) -> anyhow::Result<Descriptor> {
+        let created = chrono::Utc::now().to_rfc3339();
+
+        self.push_blob(EMPTY_CONFIG)?;
+        self.push_blob(data)?;
+
+        let mut layer = Descriptor::of(media_type, data);
+        layer
+            .annotations
+            .insert(TITLE_ANNOTATION.to_string(), title.to_string());
+        let manifest = ImageManifest {
+            schema_version: 2,
+            media_type: OCI_MANIFEST.to_string(),
+            artifact_type: Some(SIGNATURE_ARTIFACT_TYPE.to_string()),
+            config: Descriptor::of(EMPTY_MEDIA_TYPE, EMPTY_CONFIG),
+            layers: vec![layer],
+            subject: Some(Descriptor {
+                annotations: BTreeMap::new(),
+                artifact_type: None,
+                ..subject.clone()
+            }),
+            annotations: BTreeMap::from([(CREATED_ANNOTATION.to_string(), created.clone())]),
+        };
+        let data = serde_json::to_vec(&manifest)?;
+        let mut descriptor = Descriptor::of(OCI_MANIFEST, &data);
+        descriptor.artifact_type = manifest.artifact_type.clone();
+        descriptor.annotations = manifest.annotations.clone();
+
+        let referrers_supported = self.put_manifest(&descriptor.digest, OCI_MANIFEST, &data)?;
+        if !referrers_supported {
+            // list the artifact in the index tagged after the subject digest
+            let tag = fallback_tag(&subject.digest);
+            let mut index = match self.get_manifest(&tag)? {
+                Some((_, data)) => serde_json::from_slice(&data)?,
+                None => ImageIndex {
+                    schema_version: 2,
+                    media_type: OCI_INDEX.to_string(),
+                    manifests: vec![],
+                },
+            };
+            index.manifests.push(descriptor.clone());
+            self.put_manifest(&tag, OCI_INDEX, &serde_json::to_vec(&index)?)?;
+        }
+
+        Ok(descriptor)
+    }
+
+    /// Lists the signature artifacts referring to the subject manifest.
+    pub(crate) fn signatures(&mut self, subject: &Descriptor) -> anyhow::Result<Vec<Descriptor>> {
+        let url = self.url(&format!(
+            "referrers/{}?artifactType={}",
+            subject.digest, SIGNATURE_ARTIFACT_TYPE
+        ));
+        let mut response = self.request("GET", &url, &[("Accept", OCI_INDEX)], None)?;
+        let index: ImageIndex = if response.status() == StatusCode::OK {
+            serde_json::from_slice(&Self::read(&mut response)?)?
+        } else {
+            match self.get_manifest(&fallback_tag(&subject.digest))? {
+                Some((_, data)) => serde_json::from_slice(&data)?,
+                None => return Ok(vec![]),
+            }
+        };
+
+        // the artifact type filter is optional for registries
+        Ok(index
+            .manifests
+            .into_iter()
+            .filter(|m| m.artifact_type.as_deref() == Some(SIGNATURE_ARTIFACT_TYPE))
+            .collect())
+    }
+
+    /// Downloads the signature stored in the artifact.
+    pub(crate) fn pull_signature(&mut self, artifact: &Descriptor) -> anyhow::Result<Signature> {
+        let (_, data) = self
+            .get_manifest(&artifact.digest)?
+            .ok_or_else(|| anyhow::anyhow!("artifact {} not found", artifact.digest))?;
+        let manifest: ImageManifest = serde_json::from_slice(&data)?;
+        let [layer] = manifest.layers.as_slice() else {
+            anyhow::bail!("artifact {} is not a signature", artifact.digest);
+        };
+
+        Ok(Signature {
+            title: layer.annotations.get(TITLE_ANNOTATION).cloned(),
+            media_type: layer.media_type.clone(),
+            data: self.get_blob(&layer.digest)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        let reference = Reference::parse("ghcr.io/org/models/llama:v1").unwrap();
+        assert_eq!(reference.registry, "ghcr.io");
+        assert_eq!(reference.repository, "org/models/llama");
+        assert_eq!(reference.reference(), "v1");
+
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        let reference = Reference::parse(&format!("localhost:5000/model@{}", digest)).unwrap();
+        assert_eq!(reference.registry, "localhost:5000");
+        assert_eq!(reference.repository, "model");
+        assert_eq!(reference.tag, None);
+        assert_eq!(reference.reference(), digest);
+        assert_eq!(
+            reference.to_string(),
+            format!("localhost:5000/model@{}", digest)
+        );
+
+        let reference = Reference::parse("model").unwrap();
+        assert_eq!(reference.registry, DOCKER_HUB_REGISTRY);
+        assert_eq!(reference.repository, "library/model");
+        assert_eq!(reference.reference(), "latest");
+        assert_eq!(
+            Reference::parse("docker.io/org/model:1.0")
+                .unwrap()
+                .repository,
+            "org/model"
+        );
+
+        assert!(Reference::parse("ghcr.io/Org/Model").is_err());
+        assert!(Reference::parse("model@sha256:abc").is_err());
+        assert!(Reference::parse("ghcr.io/").is_err());
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        let (scheme, params) = parse_challenge(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/model:pull,push""#,
+        )
+        .unwrap();
+        assert_eq!(scheme, "bearer");
+        assert_eq!(params["realm"], "https://auth.docker.io/token");
+        assert_eq!(params["service"], "registry.docker.io");
+        assert_eq!(params["scope"], "repository:library/model:pull,push");
+
+        let (scheme, params) = parse_challenge("Basic realm=Registry").unwrap();
+        assert_eq!(scheme, "basic");
+        assert_eq!(params["realm"], "Registry");
+        assert!(parse_challenge("Bearer").is_none());
+    }
+
+    #[test]
+    fn test_artifact_manifest() {
+        let subject = Descriptor::of(OCI_MANIFEST, b"model");
+        assert_eq!(
+            fallback_tag(&subject.digest),
+            format!("sha256-{}", &subject.digest[7..])
+        );
+
+        let manifest = ImageManifest {
+            schema_version: 2,
+            media_type: OCI_MANIFEST.to_string(),
+            artifact_type: Some(SIGNATURE_ARTIFACT_TYPE.to_string()),
+            config: Descriptor::of(EMPTY_MEDIA_TYPE, EMPTY_CONFIG),
+            layers: vec![Descriptor::of(MANIFEST_MEDIA_TYPE, b"signature")],
+            subject: Some(subject),
+            annotations: BTreeMap::new(),
+        };
+        let json: serde_json::Value = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["schemaVersion"], 2);
+        assert_eq!(json["artifactType"], SIGNATURE_ARTIFACT_TYPE);
+        // digest of the empty descriptor of the OCI specification
+        assert_eq!(
+            json["config"]["digest"],
+            "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+        assert!(json["subject"]["mediaType"].is_string());
+        assert!(json.get("annotations").is_none());
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::Duration,
+};
+
+use ureq::{
+    http::{header, Response, StatusCode},
+    Body,
+};
+
+// granularity of the downloads
+const BLOCK_SIZE: u64 = 64 * 1024;
+// limit of the read-ahead when reading sequentially, in blocks
+const MAX_READ_AHEAD: u64 = 256;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns true if the path is an HTTP(S) URL.
+pub(crate) fn is_url(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Returns the last component of the URL path, if any.
+pub(crate) fn file_name_of(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    path.split_once('/')
+        .and_then(|(_, path)| path.rsplit('/').next())
+        .filter(|name| !name.is_empty() && *name != "." && *name != ".." && !name.contains('\\'))
+}
+
+// parses `bytes START-END/SIZE` into the start of the range and the size of the file
+fn parse_content_range(value: &str) -> Option<(u64, u64)> {
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, size.trim().parse().ok()?))
+}
+
+/// Source of the bytes of a remote file.
+pub(crate) trait RangeReader {
+    /// Returns the bytes from start to end included, along with the size of the file.
+    fn read_range(&self, start: u64, end: u64) -> anyhow::Result<(Vec<u8>, u64)>;
+}
+
+/// Checks the response to a range request, returns the bytes from start to end included
+/// along with the size of the file.
+pub(crate) fn range_of(
+    mut response: Response<Body>,
+    url: &str,
+    start: u64,
+    end: u64,
+) -> anyhow::Result<(Vec<u8>, u64)> {
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => {}
+        StatusCode::OK => anyhow::bail!(
+            "{} doesn't support range requests, download the file to inspect it",
+            url
+        ),
+        StatusCode::RANGE_NOT_SATISFIABLE if start == 0 => anyhow::bail!("{} is empty", url),
+        status => anyhow::bail!("GET {} failed: {}", url, status),
+    }
+
+    let (range_start, size) = response
+        .headers()
+        .get(header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_range)
+        .ok_or_else(|| anyhow::anyhow!("invalid Content-Range from {}", url))?;
+    if range_start != start || start >= size {
+        anyhow::bail!("unexpected range returned by {}", url);
+    }
+
+    let expected = end.min(size - 1) - start + 1;
+    // a body as large as the limit is rejected
+    let data = response
+        .body_mut()
+        .with_config()
+        .limit(expected + 1)
+        .read_to_vec()?;
+    if data.len() as u64 != expected {
+        anyhow::bail!(
+            "expected {} bytes from {}, got {}",
+            expected,
+            url,
+            data.len()
+        );
+    }
+
+    Ok((data, size))
+}
+
+/// A file served over HTTP(S).
+struct HttpRange {
+    agent: ureq::Agent,
+    url: String,
+}
+
+impl RangeReader for HttpRange {
+    fn read_range(&self, start: u64, end: u64) -> anyhow::Result<(Vec<u8>, u64)> {
+        let response = self
+            .agent
+            .get(&self.url)
+            .header(header::RANGE, format!("bytes={}-{}", start, end))
+            .call()
+            .map_err(|e| anyhow::anyhow!("GET {} failed: {}", self.url, e))?;
+        range_of(response, &self.url, start, end)
+    }
+}
+
+/// A remote file, only the parts being read are downloaded using range requests.
+pub(crate) struct RemoteFile {
+    reader: Box<dyn RangeReader>,
+    size: u64,
+    position: u64,
+    // downloaded blocks by index
+    blocks: BTreeMap<u64, Vec<u8>>,
+    // block following the last download and how many blocks to download when reading from it
+    next_block: u64,
+    read_ahead: u64,
+}
+
+impl RemoteFile {
+    /// Opens a file served over HTTP(S).
+    pub(crate) fn open(url: &str) -> anyhow::Result<Self> {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_connect(Some(HTTP_TIMEOUT))
+            .build()
+            .into();
+
+        Self::new(Box::new(HttpRange {
+            agent,
+            url: url.to_string(),
+        }))
+    }
+
+    pub(crate) fn new(reader: Box<dyn RangeReader>) -> anyhow::Result<Self> {
+        // every format starts with a header, the first block also tells the size of the file
+        let (data, size) = reader.read_range(0, BLOCK_SIZE - 1)?;
+
+        Ok(Self {
+            reader,
+            size,
+            position: 0,
+            blocks: BTreeMap::from([(0, data)]),
+            next_block: 1,
+            read_ahead: 1,
+        })
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the number of bytes downloaded so far.
+    pub(crate) fn downloaded(&self) -> u64 {
+        self.blocks.values().map(|block| block.len() as u64).sum()
+    }
+
+    /// Writes the downloaded parts to a file of the same size, the rest is left zeroed.
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut file = std::fs::File::create(path)?;
+        // sparse on most file systems
+        file.set_len(self.size)?;
+        for (index, block) in &self.blocks {
+            file.seek(SeekFrom::Start(index * BLOCK_SIZE))?;
+            file.write_all(block)?;
+        }
+        file.flush()?;
+
+        Ok(())
+    }
+
+    // downloads the blocks of the range that are missing, the end is excluded
+    fn fetch(&mut self, start: u64, end: u64) -> anyhow::Result<()> {
+        let last_block = (end - 1) / BLOCK_SIZE;
+        let num_blocks = self.size.div_ceil(BLOCK_SIZE);
+
+        let mut index = start / BLOCK_SIZE;
+        while index <= last_block {
+            if self.blocks.contains_key(&index) {
+                index += 1;
+                continue;
+            }
+
+            // consecutive missing blocks are downloaded at once, reading ahead more and
+            // more as long as the file is read sequentially
+            let first = index;
+            if first == self.next_block {
+                self.read_ahead = (self.read_ahead * 2).min(MAX_READ_AHEAD);
+            } else {
+                self.read_ahead = 1;
+            }
+            while index < num_blocks
+                && !self.blocks.contains_key(&index)
+                && (index <= last_block || index < first + self.read_ahead)
+            {
+                index += 1;
+            }
+
+            let (data, _) = self
+                .reader
+                .read_range(first * BLOCK_SIZE, (index * BLOCK_SIZE).min(self.size) - 1)?;
+            for (offset, block) in data.chunks(BLOCK_SIZE as usize).enumerate() {
+                self.blocks.insert(first + offset as u64, block.to_vec());
+            }
+            self.next_block = index;
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for RemoteFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let end = self.size.min(self.position + buf.len() as u64);
+        self.fetch(self.position, end)
+            .map_err(std::io::Error::other)?;
+
+        let mut read = 0;
+        while self.position < end {
+            let block = &self.blocks[&(self.position / BLOCK_SIZE)];
+            let offset = (self.position % BLOCK_SIZE) as usize;
+            let size = (block.len() - offset).min((end - self.position) as usize);
+            buf[read..read + size].copy_from_slice(&block[offset..offset + size]);
+            read += size;
+            self.position += size as u64;
+        }
+
+        Ok(read)
+    }
+}
+
+impl Seek for RemoteFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.size.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        assert!(is_url(Path::new("https://example.com/model.safetensors")));
+        assert!(is_url(Path::new("http://localhost:8000/model.gguf")));
+        assert!(!is_url(Path::new("hf://org/model")));
+        assert!(!is_url(Path::new("./https/model.gguf")));
+
+        assert_eq!(
+            file_name_of("https://example.com/models/model.onnx?download=true#x"),
+            Some("model.onnx")
+        );
+        assert_eq!(file_name_of("https://example.com/"), None);
+        assert_eq!(file_name_of("https://example.com"), None);
+        assert_eq!(file_name_of("https://example.com/a/.."), None);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 0-65535/1234567"),
+            Some((0, 1234567))
+        );
+        assert_eq!(parse_content_range("bytes 10-19/20"), Some((10, 20)));
+        assert_eq!(parse_content_range("bytes */20"), None);
+        assert_eq!(parse_content_range("bytes 0-9/*"), None);
+        assert_eq!(parse_content_range("items 0-9/20"), None);
+    }
+}
@@ -0,0 +1,468 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use clap::ValueEnum;
+
+use super::{
+    card::{format_count, CardSignature},
+    handlers::EMBEDDED_SIGNATURE_KEY,
+    Inspection,
+};
+
+// number of shapes charted, the most common ones
+const MAX_SHAPES: usize = 10;
+// width of the Markdown bar charts, in characters
+const BAR_WIDTH: usize = 40;
+// longer metadata values, such as vocabularies and chat templates, are truncated
+const MAX_VALUE_SIZE: usize = 120;
+
+const STYLE: &str = "body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;margin:2em auto;max-width:1100px;padding:0 1em;color:#1f2328}
+table{border-collapse:collapse;margin:1em 0}th,td{border:1px solid #d0d7de;padding:4px 10px;text-align:left;vertical-align:top}
+th{background:#f6f8fa}code{font-size:90%}.chart td{border:none;padding:2px 8px}
+.bar{background:#0969da;height:14px;min-width:1px}.ok{color:#1a7f37}.warn{color:#9a6700}";
+
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub(crate) enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// The format of a report file, by its extension.
+    pub(crate) fn of_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Data a report is rendered from.
+#[derive(Debug, Default)]
+pub(crate) struct Report {
+    pub name: String,
+    // full detail inspections of the model files
+    pub inspections: Vec<Inspection>,
+    pub signature: Option<CardSignature>,
+}
+
+// the elements a report is made of, in either format
+trait Writer {
+    fn heading(&mut self, level: usize, text: &str);
+    fn paragraph(&mut self, text: &str, class: Option<&str>);
+    fn table(&mut self, header: &[&str], rows: &[Vec<String>]);
+    fn chart(&mut self, rows: &[(String, usize)]);
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn truncate(value: &str) -> String {
+    if value.chars().count() <= MAX_VALUE_SIZE {
+        return value.to_string();
+    }
+    let truncated: String = value.chars().take(MAX_VALUE_SIZE).collect();
+    format!("{}… ({} characters)", truncated, value.chars().count())
+}
+
+struct Markdown(String);
+
+// writing to a String can't fail
+impl Writer for Markdown {
+    fn heading(&mut self, level: usize, text: &str) {
+        _ = writeln!(self.0, "{} {}\n", "#".repeat(level), text);
+    }
+
+    fn paragraph(&mut self, text: &str, _class: Option<&str>) {
+        _ = writeln!(self.0, "{}\n", text);
+    }
+
+    fn table(&mut self, header: &[&str], rows: &[Vec<String>]) {
+        _ = writeln!(self.0, "| {} |", header.join(" | "));
+        _ = writeln!(self.0, "|{}", "---|".repeat(header.len()));
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|cell| markdown_cell(cell)).collect();
+            _ = writeln!(self.0, "| {} |", cells.join(" | "));
+        }
+        self.0.push('\n');
+    }
+
+    fn chart(&mut self, rows: &[(String, usize)]) {
+        let max = rows
+            .iter()
+            .map(|(_, value)| *value)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        self.0.push_str("```\n");
+        for (label, value) in rows {
+            let bar = (value * BAR_WIDTH).div_ceil(max);
+            _ = writeln!(
+                self.0,
+                "{:<width$} {} {}",
+                label,
+                "█".repeat(bar),
+                format_count(*value),
+                width = width
+            );
+        }
+        self.0.push_str("```\n\n");
+    }
+}
+
+struct Html(String);
+
+impl Writer for Html {
+    fn heading(&mut self, level: usize, text: &str) {
+        _ = writeln!(self.0, "<h{0}>{1}</h{0}>", level, escape_html(text));
+    }
+
+    fn paragraph(&mut self, text: &str, class: Option<&str>) {
+        match class {
+            Some(class) => _ = writeln!(self.0, "<p class=\"{}\">{}</p>", class, escape_html(text)),
+            None => _ = writeln!(self.0, "<p>{}</p>", escape_html(text)),
+        }
+    }
+
+    fn table(&mut self, header: &[&str], rows: &[Vec<String>]) {
+        self.0.push_str("<table>\n<tr>");
+        for cell in header {
+            _ = write!(self.0, "<th>{}</th>", escape_html(cell));
+        }
+        self.0.push_str("</tr>\n");
+        for row in rows {
+            self.0.push_str("<tr>");
+            for cell in row {
+                _ = write!(self.0, "<td>{}</td>", escape_html(cell));
+            }
+            self.0.push_str("</tr>\n");
+        }
+        self.0.push_str("</table>\n");
+    }
+
+    fn chart(&mut self, rows: &[(String, usize)]) {
+        let max = rows
+            .iter()
+            .map(|(_, value)| *value)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        self.0.push_str("<table class=\"chart\">\n");
+        for (label, value) in rows {
+            _ = writeln!(
+                self.0,
+                "<tr><td><code>{}</code></td><td style=\"width:400px\"><div class=\"bar\" style=\"width:{:.1}%\"></div></td><td>{}</td></tr>",
+                escape_html(label),
+                *value as f64 * 100.0 / max as f64,
+                format_count(*value)
+            );
+        }
+        self.0.push_str("</table>\n");
+    }
+}
+
+fn file_name(inspection: &Inspection) -> String {
+    inspection
+        .file_path
+        .file_name()
+        .unwrap_or(inspection.file_path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+impl Report {
+    fn tensors(&self) -> impl Iterator<Item = &super::TensorDescriptor> {
+        self.inspections
+            .iter()
+            .flat_map(|inspection| inspection.tensors.iter().flatten())
+    }
+
+    // number of parameters of each data type, by decreasing count
+    fn dtype_parameters(&self) -> Vec<(String, usize)> {
+        let mut parameters: BTreeMap<&str, usize> = BTreeMap::new();
+        for tensor in self.tensors() {
+            *parameters.entry(tensor.dtype.as_str()).or_default() +=
+                tensor.shape.iter().product::<usize>();
+        }
+        let mut parameters: Vec<(String, usize)> = parameters
+            .into_iter()
+            .map(|(dtype, count)| (dtype.to_string(), count))
+            .collect();
+        parameters.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        parameters
+    }
+
+    // number of tensors of the most common shapes, by decreasing count
+    fn shape_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for tensor in self.tensors() {
+            *counts.entry(format!("{:?}", tensor.shape)).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(MAX_SHAPES);
+        counts
+    }
+
+    fn write(&self, writer: &mut impl Writer) {
+        let parameters: usize = self
+            .tensors()
+            .map(|tensor| tensor.shape.iter().product::<usize>())
+            .sum();
+        let file_size: u64 = self.inspections.iter().map(|i| i.file_size).sum();
+
+        writer.heading(1, &format!("{} report", self.name));
+        writer.paragraph(
+            &format!(
+                "Generated by {} v{} on {}.",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION"),
+                chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
+            ),
+            None,
+        );
+
+        writer.heading(2, "Summary");
+        writer.table(
+            &["", ""],
+            &[
+                vec!["Files".to_string(), self.inspections.len().to_string()],
+                vec![
+                    "Size".to_string(),
+                    humansize::format_size(file_size, humansize::DECIMAL),
+                ],
+                vec![
+                    "Parameters".to_string(),
+                    format!("{} ({})", format_count(parameters), parameters),
+                ],
+                vec![
+                    "Tensors".to_string(),
+                    self.inspections
+                        .iter()
+                        .map(|inspection| inspection.num_tensors)
+                        .sum::<usize>()
+                        .to_string(),
+                ],
+                vec![
+                    "Signature".to_string(),
+                    match &self.signature {
+                        Some(signature) => format!(
+                            "{} signer(s), {}",
+                            signature.signers.len(),
+                            signature.source
+                        ),
+                        None => "not signed".to_string(),
+                    },
+                ],
+            ],
+        );
+
+        let dtypes = self.dtype_parameters();
+        if !dtypes.is_empty() {
+            writer.heading(2, "Parameters by data type");
+            writer.chart(&dtypes);
+            writer.heading(2, "Most common shapes");
+            writer.chart(&self.shape_counts());
+        }
+
+        writer.heading(2, "Files");
+        writer.table(
+            &["File", "Format", "Version", "Size", "Tensors", "Data types"],
+            &self
+                .inspections
+                .iter()
+                .map(|inspection| {
+                    vec![
+                        file_name(inspection),
+                        inspection.file_type.to_string(),
+                        inspection.version.clone(),
+                        humansize::format_size(inspection.file_size, humansize::DECIMAL),
+                        inspection.num_tensors.to_string(),
+                        inspection.unique_dtypes.join(", "),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        for inspection in &self.inspections {
+            writer.heading(2, &file_name(inspection));
+            let metadata: Vec<Vec<String>> = inspection
+                .metadata
+                .iter()
+                .filter(|(key, _)| key.as_str() != EMBEDDED_SIGNATURE_KEY)
+                .map(|(key, value)| vec![key.clone(), truncate(value)])
+                .collect();
+            if !metadata.is_empty() {
+                writer.heading(3, "Metadata");
+                writer.table(&["Key", "Value"], &metadata);
+            }
+
+            if let Some(tensors) = &inspection.tensors {
+                writer.heading(3, "Tensors");
+                writer.table(
+                    &["Name", "Data type", "Shape", "Parameters", "Size"],
+                    &tensors
+                        .iter()
+                        .map(|tensor| {
+                            vec![
+                                tensor.id.clone().unwrap_or_default(),
+                                tensor.dtype.clone(),
+                                format!("{:?}", tensor.shape),
+                                tensor.shape.iter().product::<usize>().to_string(),
+                                humansize::format_size(tensor.size, humansize::DECIMAL),
+                            ]
+                        })
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+
+        writer.heading(2, "Signature");
+        match &self.signature {
+            Some(signature) => {
+                writer.paragraph(
+                    &format!(
+                        "Signed with tensor-man, the signature is {}. It has not been verified, run tman verify with the public keys of the publisher.",
+                        signature.source
+                    ),
+                    Some("ok"),
+                );
+                writer.table(
+                    &["Signer", "Algorithm", "Signed at"],
+                    &signature
+                        .signers
+                        .iter()
+                        .map(|signer| {
+                            vec![
+                                signer.fingerprint.clone(),
+                                signer.algorithm.clone(),
+                                signer.signed_at.clone(),
+                            ]
+                        })
+                        .collect::<Vec<_>>(),
+                );
+            }
+            None => writer.paragraph("The model is not signed.", Some("warn")),
+        }
+    }
+
+    /// Renders the report as Markdown, the charts as text.
+    pub(crate) fn render_markdown(&self) -> String {
+        let mut writer = Markdown(String::new());
+        self.write(&mut writer);
+        writer.0
+    }
+
+    /// Renders the report as a self-contained HTML page, with no external resources.
+    pub(crate) fn render_html(&self) -> String {
+        let mut writer = Html(String::new());
+        self.write(&mut writer);
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(&self.name),
+            STYLE,
+            writer.0
+        )
+    }
+
+    pub(crate) fn render(&self, format: &ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.render_markdown(),
+            ReportFormat::Html => self.render_html(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{card::CardSigner, FileType, TensorDescriptor};
+
+    fn report() -> Report {
+        let tensor = |id: &str, shape: Vec<usize>, dtype: &str| TensorDescriptor {
+            id: Some(id.to_string()),
+            shape,
+            dtype: dtype.to_string(),
+            size: 0,
+            metadata: Default::default(),
+            stats: None,
+            preview: None,
+        };
+        Report {
+            name: "tiny<llama>".to_string(),
+            inspections: vec![Inspection {
+                file_path: "/models/tiny.safetensors".into(),
+                file_type: FileType::SafeTensors,
+                num_tensors: 3,
+                unique_dtypes: vec!["BF16".to_string(), "F32".to_string()],
+                metadata: [
+                    ("format".to_string(), "pt".to_string()),
+                    (EMBEDDED_SIGNATURE_KEY.to_string(), "{}".to_string()),
+                ]
+                .into(),
+                tensors: Some(vec![
+                    tensor("embed", vec![100, 10], "BF16"),
+                    tensor("q", vec![10, 10], "BF16"),
+                    tensor("norm", vec![10], "F32"),
+                ]),
+                ..Default::default()
+            }],
+            signature: Some(CardSignature {
+                source: "in `tensor-man.signature`".to_string(),
+                signers: vec![CardSigner {
+                    fingerprint: "37decd85fd37c6d1".to_string(),
+                    algorithm: "Ed25519".to_string(),
+                    signed_at: "2024-10-16T13:54:17+00:00".to_string(),
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let report = report();
+        assert_eq!(
+            report.dtype_parameters(),
+            vec![("BF16".to_string(), 1100), ("F32".to_string(), 10)]
+        );
+
+        let markdown = report.render_markdown();
+        for line in [
+            "# tiny<llama> report",
+            "| Parameters | 1.11K (1110) |",
+            "| Signature | 1 signer(s), in `tensor-man.signature` |",
+            &format!("BF16 {} 1.1K", "█".repeat(BAR_WIDTH)),
+            "F32  █ 10",
+            "| tiny.safetensors | SafeTensors |",
+            "| format | pt |",
+            "| embed | BF16 | [100, 10] | 1000 |",
+            "| 37decd85fd37c6d1 | Ed25519 | 2024-10-16T13:54:17+00:00 |",
+        ] {
+            assert!(markdown.contains(line), "{} not in\n{}", line, markdown);
+        }
+        assert!(!markdown.contains(EMBEDDED_SIGNATURE_KEY));
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = report().render_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>tiny&lt;llama&gt;</title>"));
+        assert!(html.contains("<td>embed</td><td>BF16</td><td>[100, 10]</td>"));
+        assert!(html.contains("<div class=\"bar\" style=\"width:100.0%\"></div>"));
+        // self-contained
+        assert!(!html.contains("src=") && !html.contains("href="));
+        assert_eq!(ReportFormat::of_extension("HTM"), Some(ReportFormat::Html));
+        assert_eq!(ReportFormat::of_extension("txt"), None);
+    }
+}
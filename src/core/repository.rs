@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+
+use super::Metadata;
+
+// configuration of a Triton model, shared by all of its versions
+const TRITON_CONFIG: &str = "config.pbtxt";
+// manifest of a TorchServe model archive
+const MAR_MANIFEST: &str = "MAR-INF/MANIFEST.json";
+
+/// Layout of a model serving repository.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RepositoryType {
+    /// Triton Inference Server, a `<model>/<version>/` folder for each version of a model.
+    Triton,
+    /// TorchServe model store, a `.mar` archive for each model.
+    TorchServe,
+}
+
+impl RepositoryType {
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        if !path.is_dir() {
+            return None;
+        }
+        let entries: Vec<PathBuf> = std::fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        if entries.iter().any(|entry| is_triton_model(entry)) {
+            Some(Self::Triton)
+        } else if entries.iter().any(|entry| is_mar(entry)) {
+            Some(Self::TorchServe)
+        } else {
+            None
+        }
+    }
+}
+
+fn is_version(path: &Path) -> bool {
+    path.is_dir()
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn is_triton_model(path: &Path) -> bool {
+    path.is_dir()
+        && (path.join(TRITON_CONFIG).is_file()
+            || std::fs::read_dir(path)
+                .map(|entries| entries.flatten().any(|entry| is_version(&entry.path())))
+                .unwrap_or(false))
+}
+
+fn is_mar(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("mar"))
+}
+
+/// A version of a model of a serving repository.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModelVersion {
+    pub model: String,
+    pub version: String,
+    /// Folder of the version for Triton, archive of the model for TorchServe.
+    pub path: PathBuf,
+    /// Folder the files of the version are relative to in its signature.
+    pub base_path: PathBuf,
+}
+
+impl ModelVersion {
+    /// Returns the files of the version, along with the files of the model shared by all of its
+    /// versions such as the Triton configuration.
+    pub(crate) fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        if self.path.is_file() {
+            return Ok(vec![self.path.clone()]);
+        }
+
+        let mut files = vec![];
+        for entry in glob(&self.base_path.join("**/*").to_string_lossy())? {
+            let entry = entry?;
+            if !entry.is_file() {
+                continue;
+            }
+            // files of the other versions are left to their own signature
+            let other_version = entry
+                .strip_prefix(&self.base_path)?
+                .components()
+                .next()
+                .map(|first| self.base_path.join(first))
+                .is_some_and(|first| first != self.path && is_version(&first));
+            if !other_version {
+                files.push(entry);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Returns the top level settings of the Triton configuration of the model, such as its
+    /// platform or backend.
+    pub(crate) fn config(&self) -> Metadata {
+        let mut config = Metadata::new();
+        let Ok(data) = std::fs::read_to_string(self.base_path.join(TRITON_CONFIG)) else {
+            return config;
+        };
+        let mut depth = 0usize;
+        for line in data.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if depth == 0 {
+                if let Some((key, value)) = line.split_once(':') {
+                    let value = value.trim();
+                    if !value.is_empty() && !value.starts_with(['{', '[']) {
+                        config.insert(key.trim().to_string(), value.trim_matches('"').to_string());
+                    }
+                }
+            }
+            for c in line.chars() {
+                match c {
+                    '{' | '[' => depth += 1,
+                    '}' | ']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        }
+        config
+    }
+}
+
+// name and version of the model from the manifest of the archive, if any
+fn mar_model_of(path: &Path) -> Option<(String, String)> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path).ok()?).ok()?;
+    let manifest: serde_json::Value =
+        serde_json::from_reader(archive.by_name(MAR_MANIFEST).ok()?).ok()?;
+    let model = manifest.get("model")?;
+    Some((
+        model.get("modelName")?.as_str()?.to_string(),
+        model
+            .get("modelVersion")
+            .and_then(|version| version.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    ))
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Returns the type of the repository and the versions of its models, sorted by model name and
+/// version number.
+pub(crate) fn model_versions(path: &Path) -> anyhow::Result<(RepositoryType, Vec<ModelVersion>)> {
+    let repository_type = RepositoryType::of(path)
+        .ok_or_else(|| anyhow::anyhow!("{} is not a model repository", path.display()))?;
+    let path = path.canonicalize()?;
+
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(&path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    let mut versions = vec![];
+    for entry in entries {
+        match repository_type {
+            RepositoryType::Triton if is_triton_model(&entry) => {
+                let mut model_versions: Vec<(u64, PathBuf)> = std::fs::read_dir(&entry)?
+                    .flatten()
+                    .map(|version| version.path())
+                    .filter(|version| is_version(version))
+                    .filter_map(|version| Some((file_name_of(&version).parse().ok()?, version)))
+                    .collect();
+                model_versions.sort();
+                versions.extend(model_versions.into_iter().map(|(_, version)| ModelVersion {
+                    model: file_name_of(&entry),
+                    version: file_name_of(&version),
+                    path: version,
+                    base_path: entry.clone(),
+                }));
+            }
+            RepositoryType::TorchServe if is_mar(&entry) => {
+                let (model, version) = mar_model_of(&entry).unwrap_or_else(|| {
+                    (
+                        entry
+                            .file_stem()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string(),
+                        String::new(),
+                    )
+                });
+                versions.push(ModelVersion {
+                    model,
+                    version,
+                    path: entry.clone(),
+                    base_path: path.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if versions.is_empty() {
+        anyhow::bail!("no model versions found in {}", path.display());
+    }
+
+    Ok((repository_type, versions))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_triton_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("densenet_onnx");
+        for version in ["1", "10", "2"] {
+            std::fs::create_dir_all(model.join(version)).unwrap();
+            std::fs::write(model.join(version).join("model.onnx"), version).unwrap();
+        }
+        std::fs::write(
+            model.join(TRITON_CONFIG),
+            "name: \"densenet_onnx\"\nplatform: \"onnxruntime_onnx\" # runtime\nmax_batch_size: 0\ninput [\n  {\n    name: \"data_0\"\n  }\n]\n",
+        )
+        .unwrap();
+        std::fs::write(model.join("labels.txt"), "cat").unwrap();
+
+        assert_eq!(RepositoryType::of(dir.path()), Some(RepositoryType::Triton));
+        assert_eq!(RepositoryType::of(&model.join("1")), None);
+
+        let (_, versions) = model_versions(dir.path()).unwrap();
+        let numbers: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(numbers, vec!["1", "2", "10"]);
+        assert!(versions.iter().all(|v| v.model == "densenet_onnx"));
+
+        // the files of the model are signed with each version, not the other versions
+        let base = model.canonicalize().unwrap();
+        let mut files = versions[1].files().unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                base.join("2/model.onnx"),
+                base.join(TRITON_CONFIG),
+                base.join("labels.txt")
+            ]
+        );
+
+        let config = versions[0].config();
+        assert_eq!(config.get("platform").unwrap(), "onnxruntime_onnx");
+        assert_eq!(config.get("max_batch_size").unwrap(), "0");
+        // nested settings are skipped
+        assert!(!config.contains_key("input"));
+        assert_eq!(config.get("name").unwrap(), "densenet_onnx");
+        assert_eq!(config.len(), 3);
+    }
+
+    #[test]
+    fn test_torchserve_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = zip::write::SimpleFileOptions::default();
+        let mut zip =
+            zip::ZipWriter::new(std::fs::File::create(dir.path().join("resnet.mar")).unwrap());
+        zip.start_file(MAR_MANIFEST, options).unwrap();
+        zip.write_all(br#"{"model": {"modelName": "resnet-18", "modelVersion": "1.0"}}"#)
+            .unwrap();
+        zip.finish().unwrap();
+        std::fs::write(dir.path().join("other.mar"), b"not a zip").unwrap();
+        std::fs::write(dir.path().join("config.properties"), b"").unwrap();
+
+        assert_eq!(
+            RepositoryType::of(dir.path()),
+            Some(RepositoryType::TorchServe)
+        );
+        let (_, versions) = model_versions(dir.path()).unwrap();
+        assert_eq!(versions.len(), 2);
+        // archives without a manifest are named after the file
+        assert_eq!(versions[0].model, "other");
+        assert_eq!(versions[0].version, "");
+        assert_eq!(versions[1].model, "resnet-18");
+        assert_eq!(versions[1].version, "1.0");
+        assert_eq!(versions[1].files().unwrap(), vec![versions[1].path.clone()]);
+    }
+}
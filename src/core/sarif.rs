@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/dreadnode/tensor-man";
+
+/// A kind of finding, listed in the rules of the tool.
+pub(crate) struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    // error, warning or note
+    pub level: &'static str,
+}
+
+/// A single result, located in a file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Finding {
+    pub rule_id: &'static str,
+    pub message: String,
+    // URI of the file, relative to the working directory when possible
+    pub uri: String,
+    // offset of the finding in a binary file
+    pub byte_offset: Option<u64>,
+}
+
+/// URI of the path as expected by code scanning tools, relative to the working directory and
+/// with forward slashes.
+pub(crate) fn artifact_uri(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+
+    let uri = relative.to_string_lossy().replace('\\', "/");
+    uri.strip_prefix("./").unwrap_or(&uri).to_string()
+}
+
+/// Returns the SARIF 2.1.0 log of a run, the error is reported as a failed execution of the tool.
+pub(crate) fn log(rules: &[Rule], findings: &[Finding], error: Option<String>) -> Value {
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let index = rules.iter().position(|rule| rule.id == finding.rule_id);
+            let level = index.map(|index| rules[index].level).unwrap_or("error");
+            // code scanning requires a line, even for binary files
+            let mut region = json!({ "startLine": 1 });
+            if let Some(offset) = finding.byte_offset {
+                region["byteOffset"] = json!(offset);
+            }
+
+            json!({
+                "ruleId": finding.rule_id,
+                "ruleIndex": index,
+                "level": level,
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.uri },
+                        "region": region,
+                    }
+                }],
+            })
+        })
+        .collect();
+
+    let mut invocation = json!({ "executionSuccessful": error.is_none() });
+    if let Some(error) = error {
+        invocation["toolExecutionNotifications"] = json!([{
+            "level": "error",
+            "message": { "text": error },
+        }]);
+    }
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules.iter().map(|rule| json!({
+                        "id": rule.id,
+                        "shortDescription": { "text": rule.description },
+                        "defaultConfiguration": { "level": rule.level },
+                    })).collect::<Vec<_>>(),
+                }
+            },
+            "invocations": [invocation],
+            "results": results,
+        }]
+    })
+}
+
+/// Saves the SARIF log to the file.
+pub(crate) fn save(
+    path: &Path,
+    rules: &[Rule],
+    findings: &[Finding],
+    error: Option<String>,
+) -> anyhow::Result<()> {
+    std::fs::write(
+        path,
+        serde_json::to_string_pretty(&log(rules, findings, error))?,
+    )?;
+    tracing::info!("SARIF log written to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &[Rule] = &[Rule {
+        id: "modified-file",
+        description: "A signed file has been modified.",
+        level: "error",
+    }];
+
+    #[test]
+    fn test_log() {
+        let findings = vec![Finding {
+            rule_id: "modified-file",
+            message: "model.safetensors has been modified".to_string(),
+            uri: "models/model.safetensors".to_string(),
+            byte_offset: Some(1024),
+        }];
+
+        let log = log(RULES, &findings, None);
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "modified-file");
+        assert_eq!(run["invocations"][0]["executionSuccessful"], true);
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleIndex"], 0);
+        assert_eq!(result["level"], "error");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            "models/model.safetensors"
+        );
+        assert_eq!(location["region"]["byteOffset"], 1024);
+
+        let log = super::log(RULES, &[], Some("unsupported format".to_string()));
+        let invocation = &log["runs"][0]["invocations"][0];
+        assert_eq!(invocation["executionSuccessful"], false);
+        assert_eq!(
+            invocation["toolExecutionNotifications"][0]["message"]["text"],
+            "unsupported format"
+        );
+    }
+
+    #[test]
+    fn test_artifact_uri() {
+        assert_eq!(
+            artifact_uri(Path::new("./models/model.gguf")),
+            "models/model.gguf"
+        );
+        assert_eq!(artifact_uri(Path::new("model.onnx")), "model.onnx");
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::{
+    entropy,
+    handlers::{self, gguf, onnx, safetensors, Scope},
+    lsb, pickle,
+    sarif::Rule,
+};
+
+/// Kinds of issues, as rules of the SARIF log.
+pub(crate) const RULES: &[Rule] = &[
+    Rule {
+        id: "dangerous-import",
+        description: "A pickle imports a function that can run code or access the system.",
+        level: "error",
+    },
+    Rule {
+        id: "suspicious-import",
+        description:
+            "A pickle imports an unknown function or class, that may run code when loaded.",
+        level: "warning",
+    },
+    Rule {
+        id: "invalid-file",
+        description: "The file can't be parsed.",
+        level: "error",
+    },
+    Rule {
+        id: "python-operator",
+        description: "An ONNX node runs Python code.",
+        level: "error",
+    },
+    Rule {
+        id: "custom-operator",
+        description:
+            "An ONNX node uses an operator of a custom domain, implemented by a native library.",
+        level: "warning",
+    },
+    Rule {
+        id: "contrib-operator",
+        description: "An ONNX node uses a com.microsoft contrib operator.",
+        level: "note",
+    },
+    Rule {
+        id: "external-data-traversal",
+        description:
+            "An ONNX tensor reads its data from an absolute path or outside of the model folder.",
+        level: "error",
+    },
+    Rule {
+        id: "large-attribute",
+        description:
+            "An ONNX node has an unusually large string attribute, that could hide a payload.",
+        level: "warning",
+    },
+    Rule {
+        id: "chat-template-code",
+        description:
+            "A GGUF chat template reaches Python internals, as template injection payloads do.",
+        level: "error",
+    },
+    Rule {
+        id: "large-chat-template",
+        description: "A GGUF chat template is unusually large.",
+        level: "warning",
+    },
+    Rule {
+        id: "invalid-metadata-type",
+        description: "A GGUF metadata value has an unknown type.",
+        level: "error",
+    },
+    Rule {
+        id: "count-mismatch",
+        description: "A GGUF header declares more metadata entries or tensors than it holds.",
+        level: "error",
+    },
+    Rule {
+        id: "invalid-tensor-offset",
+        description: "The data of a GGUF tensor overlaps another tensor or exceeds the file.",
+        level: "error",
+    },
+    Rule {
+        id: "unknown-tensor-type",
+        description: "A GGUF tensor has an unknown type or an invalid size.",
+        level: "warning",
+    },
+    Rule {
+        id: "unreferenced-data",
+        description:
+            "Data follows the end of the model declared by its header, as appended payloads do.",
+        level: "warning",
+    },
+    Rule {
+        id: "high-entropy-data",
+        description: "A region of tensor data has an entropy inconsistent with weights, as encrypted or compressed archives have.",
+        level: "warning",
+    },
+    Rule {
+        id: "lsb-pattern",
+        description: "The least significant mantissa bits of a floating point tensor are not random, as when data is hidden in them.",
+        level: "warning",
+    },
+];
+
+/// How harmful an issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Safety {
+    // rebuilds tensors, arrays or builtin types
+    Safe,
+    // unknown code that may run when loading the model, or unusual constructs
+    Suspicious,
+    // gives access to code execution, the system or the network
+    Dangerous,
+}
+
+impl fmt::Display for Safety {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Safety::Safe => write!(f, "safe"),
+            Safety::Suspicious => write!(f, "suspicious"),
+            Safety::Dangerous => write!(f, "dangerous"),
+        }
+    }
+}
+
+/// A potentially harmful construct found in a file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Issue {
+    pub rule_id: &'static str,
+    pub safety: Safety,
+    pub message: String,
+    // pickle of the archive, node or tensor the issue is about
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub location: String,
+    // offset in the file, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+/// Result of the scan of a file.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Scan {
+    pub file_path: PathBuf,
+    // the most harmful issue found
+    pub verdict: Safety,
+    pub issues: Vec<Issue>,
+}
+
+fn has_extension(file_path: &Path, extension: &str) -> bool {
+    file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Whether the file is a model that can be scanned.
+pub(crate) fn is_scannable(file_path: &Path) -> bool {
+    has_extension(file_path, "onnx")
+        || has_extension(file_path, "gguf")
+        || has_extension(file_path, "safetensors")
+        || pickle::is_pickle_based(file_path)
+}
+
+// scans the tensor data for embedded payloads
+fn scan_tensor_data(file_path: &Path, entropy: bool, lsb: bool) -> anyhow::Result<Vec<Issue>> {
+    let handler = handlers::handler_for(None, file_path, Scope::Inspection)?;
+    let tensors = handler.tensor_data(file_path, None)?;
+    let mut issues = vec![];
+
+    if entropy {
+        issues.extend(
+            entropy::find_high_entropy(&tensors)
+                .into_iter()
+                .map(|region| Issue {
+                    rule_id: "high-entropy-data",
+                    safety: Safety::Suspicious,
+                    message: format!(
+                        "{} bytes with an entropy of {:.2} bits per byte, unlike weights",
+                        region.size, region.entropy
+                    ),
+                    location: region.id,
+                    offset: region.file_offset,
+                }),
+        );
+    }
+    if lsb {
+        issues.extend(
+            lsb::find_lsb_patterns(&tensors)
+                .into_iter()
+                .map(|region| Issue {
+                    rule_id: "lsb-pattern",
+                    safety: Safety::Suspicious,
+                    message: format!(
+                        "mantissa bit {} of {} values following a pattern (chi-square {:.0})",
+                        region.bit, region.count, region.chi_square
+                    ),
+                    location: region.id,
+                    offset: region.file_offset,
+                }),
+        );
+    }
+
+    Ok(issues)
+}
+
+/// Statically scans an ONNX, GGUF or safetensors model or a pickle based file, without loading it.
+/// With `entropy` and `lsb` the tensor data of the models is also scanned for embedded payloads.
+pub(crate) fn scan(file_path: &Path, entropy: bool, lsb: bool) -> anyhow::Result<Scan> {
+    let mut issues = if has_extension(file_path, "onnx") {
+        onnx::scan(file_path)?
+    } else if has_extension(file_path, "gguf") {
+        gguf::scan(file_path)?
+    } else if has_extension(file_path, "safetensors") {
+        safetensors::scan(file_path)?
+    } else {
+        pickle::scan(file_path)?
+    };
+
+    // the tensor data of pickles can't be read without loading them
+    if (entropy || lsb) && !pickle::is_pickle_based(file_path) {
+        issues.extend(scan_tensor_data(file_path, entropy, lsb)?);
+    }
+
+    Ok(Scan {
+        file_path: file_path.to_path_buf(),
+        verdict: issues
+            .iter()
+            .map(|issue| issue.safety)
+            .max()
+            .unwrap_or(Safety::Safe),
+        issues,
+    })
+}
@@ -0,0 +1,55 @@
+use clap::ValueEnum;
+use schemars::generate::SchemaSettings;
+
+use super::{signing::Manifest, Inspection};
+
+/// Files tman produces that integrators can validate against a JSON Schema.
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum SchemaKind {
+    /// Inspections saved by tman inspect, one per file.
+    Inspection,
+    /// Signature manifests created by tman sign.
+    Manifest,
+}
+
+/// Returns the JSON Schema (draft 2020-12) of the files as they are written.
+pub(crate) fn json_schema(kind: &SchemaKind) -> serde_json::Value {
+    let generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    let schema = match kind {
+        SchemaKind::Inspection => generator.into_root_schema_for::<Inspection>(),
+        SchemaKind::Manifest => generator.into_root_schema_for::<Manifest>(),
+    };
+    schema.to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_schema() {
+        let schema = json_schema(&SchemaKind::Inspection);
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert!(schema["properties"]["schema_version"].is_object());
+        assert!(schema["properties"]["tensors"].is_object());
+        assert!(schema["$defs"]["FileType"].is_object());
+
+        let schema = json_schema(&SchemaKind::Manifest);
+        let properties = schema["properties"].as_object().unwrap();
+        for property in ["version", "algorithms", "checksums", "signatures"] {
+            assert!(properties.contains_key(property), "{} missing", property);
+        }
+        // legacy and in memory fields are not written
+        for property in ["signed_at", "base_path", "signing_key"] {
+            assert!(!properties.contains_key(property), "{} found", property);
+        }
+
+        let inspection = serde_json::to_value(Inspection::default()).unwrap();
+        assert_eq!(inspection["schema_version"], "1.0");
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{fingerprint, Failure, SigningKey, VerifyingKey};
+use crate::core::checksums;
+
+const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+// statement type emitted by cosign attest-blob, v1 is accepted as well
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v0.1";
+const STATEMENT_TYPE_V1: &str = "https://in-toto.io/Statement/v1";
+const PREDICATE_TYPE: &str = "https://github.com/dreadnode/tensor-man/signature/v1";
+
+/// A file covered by the statement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Subject {
+    // path relative to the model folder
+    pub name: String,
+    // hex-encoded digests by algorithm, sha256 is the one checked by cosign
+    pub digest: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Predicate {
+    // software name and version
+    pub signed_with: String,
+    // ISO 8601 timestamp of when the statement was created
+    pub signed_at: String,
+}
+
+/// In-toto statement about the model files, the payload of the envelope.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    pub subject: Vec<Subject>,
+    #[serde(rename = "predicateType")]
+    pub predicate_type: String,
+    pub predicate: Predicate,
+}
+
+impl Statement {
+    /// Creates a statement with the SHA-256 of the files, named relative to the base path.
+    pub(crate) fn new(base_path: &Path, paths: &[PathBuf]) -> anyhow::Result<Self> {
+        Ok(Self {
+            statement_type: STATEMENT_TYPE.to_string(),
+            subject: subjects_of(base_path, paths)?,
+            predicate_type: PREDICATE_TYPE.to_string(),
+            predicate: Predicate {
+                signed_with: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+                signed_at: chrono::Utc::now().to_rfc3339(),
+            },
+        })
+    }
+
+    /// Checks that the statement covers exactly these files, with the same content.
+    pub(crate) fn check_subjects(&self, base_path: &Path, paths: &[PathBuf]) -> anyhow::Result<()> {
+        let signed: BTreeMap<&str, Option<&String>> = self
+            .subject
+            .iter()
+            .map(|subject| (subject.name.as_str(), subject.digest.get("sha256")))
+            .collect();
+        let computed = subjects_of(base_path, paths)?;
+
+        for subject in &computed {
+            match signed.get(subject.name.as_str()) {
+                None => {
+                    return Err(
+                        Failure::MissingFile.error(format!("file {} is not signed", subject.name))
+                    )
+                }
+                Some(digest) if *digest != subject.digest.get("sha256") => {
+                    return Err(Failure::ModifiedFile
+                        .error(format!("checksum mismatch for {}", subject.name)))
+                }
+                _ => {}
+            }
+        }
+        if let Some(missing) = signed
+            .keys()
+            .find(|name| !computed.iter().any(|subject| subject.name == **name))
+        {
+            return Err(Failure::MissingFile.error(format!("signed file {} is missing", missing)));
+        }
+
+        Ok(())
+    }
+}
+
+// sha256 subjects of the files, sorted by name
+fn subjects_of(base_path: &Path, paths: &[PathBuf]) -> anyhow::Result<Vec<Subject>> {
+    let base_path = base_path.canonicalize()?;
+    let mut subjects = paths
+        .par_iter()
+        .map(|path| {
+            let name = path
+                .canonicalize()?
+                .strip_prefix(&base_path)
+                .map_err(|_| {
+                    anyhow::anyhow!("{} is not in {}", path.display(), base_path.display())
+                })?
+                .to_string_lossy()
+                .to_string();
+            Ok(Subject {
+                name,
+                digest: BTreeMap::from([("sha256".to_string(), checksums::sha256_of(path)?)]),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    subjects.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(subjects)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EnvelopeSignature {
+    // fingerprint of the public key, ignored by cosign
+    #[serde(default)]
+    pub keyid: String,
+    // base64-encoded signature of the pre-authentication encoding of the payload
+    pub sig: String,
+}
+
+/// DSSE envelope, the signature format of cosign attestations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Envelope {
+    #[serde(rename = "payloadType")]
+    pub payload_type: String,
+    // base64-encoded statement
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+// DSSE pre-authentication encoding, the data actually signed
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut data = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    data.extend_from_slice(payload);
+    data
+}
+
+impl Envelope {
+    /// Returns true if the data looks like a DSSE envelope rather than a signature manifest.
+    pub(crate) fn is_envelope(data: &str) -> bool {
+        serde_json::from_str::<serde_json::Value>(data)
+            .map(|value| value.get("payloadType").is_some())
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("can't read envelope {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Creates an envelope for the statement signed with the key.
+    pub(crate) fn sign(statement: &Statement, signing_key: &SigningKey) -> anyhow::Result<Self> {
+        let mut this = Self {
+            payload_type: PAYLOAD_TYPE.to_string(),
+            payload: STANDARD.encode(serde_json::to_vec(statement)?),
+            signatures: vec![],
+        };
+        this.countersign(signing_key)?;
+        Ok(this)
+    }
+
+    /// Adds a signature of the same payload.
+    pub(crate) fn countersign(&mut self, signing_key: &SigningKey) -> anyhow::Result<()> {
+        let keyid = fingerprint(signing_key.public_key());
+        if self.signatures.iter().any(|s| s.keyid == keyid) {
+            anyhow::bail!("the envelope has already been signed by {}", &keyid[..16]);
+        }
+
+        let signature = signing_key.sign(&pae(&self.payload_type, &self.payload()?))?;
+        self.signatures.push(EnvelopeSignature {
+            keyid,
+            sig: STANDARD.encode(signature),
+        });
+        Ok(())
+    }
+
+    fn payload(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(STANDARD.decode(&self.payload)?)
+    }
+
+    /// Decodes the statement, the signatures are not verified.
+    pub(crate) fn statement(&self) -> anyhow::Result<Statement> {
+        if self.payload_type != PAYLOAD_TYPE {
+            anyhow::bail!("unsupported payload type {}", self.payload_type);
+        }
+        let statement: Statement = serde_json::from_slice(&self.payload()?)?;
+        if statement.statement_type != STATEMENT_TYPE
+            && statement.statement_type != STATEMENT_TYPE_V1
+        {
+            anyhow::bail!("unsupported statement type {}", statement.statement_type);
+        }
+        if statement.predicate_type != PREDICATE_TYPE {
+            anyhow::bail!("unsupported predicate type {}", statement.predicate_type);
+        }
+        Ok(statement)
+    }
+
+    /// Returns the keys with a valid signature of the payload, each key only once.
+    pub(crate) fn signed_by<'a>(&self, keys: &'a [VerifyingKey]) -> Vec<&'a VerifyingKey> {
+        let Ok(data) = self
+            .payload()
+            .map(|payload| pae(&self.payload_type, &payload))
+        else {
+            return vec![];
+        };
+
+        keys.iter()
+            .filter(|key| {
+                // signatures made by cosign have no key id, try them all
+                self.signatures.iter().any(|signature| {
+                    (signature.keyid.is_empty() || signature.keyid == fingerprint(key.as_bytes()))
+                        && STANDARD
+                            .decode(&signature.sig)
+                            .map(|sig| key.verify(&data, &sig).is_ok())
+                            .unwrap_or(false)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::signing::SigningAlgorithm;
+
+    #[test]
+    fn test_pae() {
+        // example of the DSSE specification
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+    }
+
+    #[test]
+    fn test_envelope() {
+        let folder = tempfile::tempdir().unwrap();
+        let model = folder.path().join("model.safetensors");
+        std::fs::write(&model, "abc").unwrap();
+        let paths = vec![model.clone()];
+
+        let author = SigningKey::generate(SigningAlgorithm::Ed25519).unwrap().0;
+        let reviewer = SigningKey::generate(SigningAlgorithm::EcdsaP256).unwrap().0;
+        let keys = [
+            VerifyingKey::new(author.public_key().to_vec()).unwrap(),
+            VerifyingKey::new(reviewer.public_key().to_vec()).unwrap(),
+        ];
+
+        let statement = Statement::new(folder.path(), &paths).unwrap();
+        assert_eq!(statement.subject[0].name, "model.safetensors");
+        assert_eq!(
+            statement.subject[0].digest["sha256"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let mut envelope = Envelope::sign(&statement, &author).unwrap();
+        assert!(envelope.countersign(&author).is_err());
+        envelope.countersign(&reviewer).unwrap();
+
+        let data = serde_json::to_string(&envelope).unwrap();
+        assert!(Envelope::is_envelope(&data));
+        assert!(!Envelope::is_envelope(r#"{"version": "3.0"}"#));
+
+        let envelope: Envelope = serde_json::from_str(&data).unwrap();
+        assert_eq!(envelope.signed_by(&keys).len(), 2);
+        envelope
+            .statement()
+            .unwrap()
+            .check_subjects(folder.path(), &paths)
+            .unwrap();
+
+        // cosign doesn't set the key id
+        let mut anonymous = envelope.clone();
+        anonymous.signatures[0].keyid.clear();
+        assert_eq!(anonymous.signed_by(&keys[..1]).len(), 1);
+
+        // tampered payload
+        let mut tampered = envelope.clone();
+        let mut statement = tampered.statement().unwrap();
+        statement.subject[0]
+            .digest
+            .insert("sha256".to_string(), "00".repeat(32));
+        tampered.payload = STANDARD.encode(serde_json::to_vec(&statement).unwrap());
+        assert!(tampered.signed_by(&keys).is_empty());
+
+        // tampered file
+        std::fs::write(&model, "abd").unwrap();
+        assert!(envelope
+            .statement()
+            .unwrap()
+            .check_subjects(folder.path(), &paths)
+            .is_err());
+
+        // extra file
+        std::fs::write(&model, "abc").unwrap();
+        let extra = folder.path().join("config.json");
+        std::fs::write(&extra, "{}").unwrap();
+        assert!(envelope
+            .statement()
+            .unwrap()
+            .check_subjects(folder.path(), &[model, extra])
+            .is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{fingerprint, SigningAlgorithm, VerifyingKey};
+
+const INDEX_FILE_NAME: &str = "keyring.json";
+const PINS_FILE_NAME: &str = "pins.json";
+
+/// A public key imported in the keyring.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct KeyInfo {
+    // fingerprint of the public key, as stored in signature manifests
+    pub fingerprint: String,
+    // optional human readable name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub algorithm: SigningAlgorithm,
+    // only trusted keys are used to verify signatures
+    pub trusted: bool,
+    pub added_at: String,
+}
+
+/// Local folder of public keys, each stored in its own file, plus an index with their metadata.
+#[derive(Debug)]
+pub(crate) struct Keyring {
+    path: PathBuf,
+    keys: Vec<KeyInfo>,
+    // hex-encoded public keys trusted on first use, by model path
+    pins: BTreeMap<String, Vec<String>>,
+}
+
+impl Keyring {
+    /// Returns the default keyring folder, inside the user configuration folder.
+    pub(crate) fn default_path() -> anyhow::Result<PathBuf> {
+        dirs::config_dir()
+            .map(|path| path.join("tensor-man").join("keys"))
+            .ok_or_else(|| anyhow::anyhow!("can't determine the user configuration folder"))
+    }
+
+    /// Opens the keyring in the given folder, an empty one if it doesn't exist yet.
+    pub(crate) fn open(path: &Path) -> anyhow::Result<Self> {
+        let index_path = path.join(INDEX_FILE_NAME);
+        let keys = if index_path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&index_path)?)?
+        } else {
+            vec![]
+        };
+        let pins_path = path.join(PINS_FILE_NAME);
+        let pins = if pins_path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&pins_path)?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            keys,
+            pins,
+        })
+    }
+
+    /// Opens the keyring in the given folder, or in the default one if not set.
+    pub(crate) fn open_or_default(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => Self::open(path),
+            None => Self::open(&Self::default_path()?),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn keys(&self) -> &[KeyInfo] {
+        &self.keys
+    }
+
+    fn key_path(&self, fingerprint: &str) -> PathBuf {
+        self.path.join(format!("{}.pub", fingerprint))
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.path)?;
+        std::fs::write(
+            self.path.join(INDEX_FILE_NAME),
+            serde_json::to_string_pretty(&self.keys)?,
+        )?;
+        if !self.pins.is_empty() {
+            std::fs::write(
+                self.path.join(PINS_FILE_NAME),
+                serde_json::to_string_pretty(&self.pins)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Imports a public key, returns its metadata.
+    pub(crate) fn add(
+        &mut self,
+        public_key: Vec<u8>,
+        name: Option<String>,
+        trusted: bool,
+    ) -> anyhow::Result<&KeyInfo> {
+        let algorithm = VerifyingKey::new(public_key.clone())?.algorithm();
+        let fingerprint = fingerprint(&public_key);
+        if self.keys.iter().any(|key| key.fingerprint == fingerprint) {
+            anyhow::bail!("key {} is already in the keyring", &fingerprint[..16]);
+        }
+        if let Some(name) = &name {
+            if self.keys.iter().any(|key| key.name.as_ref() == Some(name)) {
+                anyhow::bail!("a key named '{}' is already in the keyring", name);
+            }
+        }
+
+        std::fs::create_dir_all(&self.path)?;
+        std::fs::write(self.key_path(&fingerprint), &public_key)?;
+
+        self.keys.push(KeyInfo {
+            fingerprint,
+            name,
+            algorithm,
+            trusted,
+            added_at: chrono::Utc::now().to_rfc3339(),
+        });
+        self.save()?;
+
+        Ok(self.keys.last().unwrap())
+    }
+
+    // finds a key by name or by fingerprint prefix
+    fn find(&self, id: &str) -> anyhow::Result<usize> {
+        let found: Vec<usize> = self
+            .keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| key.name.as_deref() == Some(id) || key.fingerprint.starts_with(id))
+            .map(|(index, _)| index)
+            .collect();
+
+        match found.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(anyhow::anyhow!("no key matching '{}' in the keyring", id)),
+            _ => Err(anyhow::anyhow!(
+                "'{}' matches {} keys in the keyring",
+                id,
+                found.len()
+            )),
+        }
+    }
+
+    /// Removes a key, by name or fingerprint prefix.
+    pub(crate) fn remove(&mut self, id: &str) -> anyhow::Result<KeyInfo> {
+        let index = self.find(id)?;
+        let key = self.keys.remove(index);
+        let key_path = self.key_path(&key.fingerprint);
+        if key_path.exists() {
+            std::fs::remove_file(key_path)?;
+        }
+        self.save()?;
+        Ok(key)
+    }
+
+    /// Marks a key, by name or fingerprint prefix, as trusted or not.
+    pub(crate) fn set_trusted(&mut self, id: &str, trusted: bool) -> anyhow::Result<&KeyInfo> {
+        let index = self.find(id)?;
+        self.keys[index].trusted = trusted;
+        self.save()?;
+        Ok(&self.keys[index])
+    }
+
+    /// Returns the public keys pinned on first use for the model, if any.
+    pub(crate) fn pinned_keys(&self, model: &str) -> anyhow::Result<Option<Vec<Vec<u8>>>> {
+        self.pins
+            .get(model)
+            .map(|keys| keys.iter().map(hex::decode).collect())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("invalid pinned key for {}: {}", model, e))
+    }
+
+    /// Pins the public keys a model has been signed with the first time it has been verified.
+    pub(crate) fn pin(&mut self, model: &str, public_keys: &[Vec<u8>]) -> anyhow::Result<()> {
+        if self.pins.contains_key(model) {
+            anyhow::bail!("keys for {} have already been pinned", model);
+        }
+        self.pins.insert(
+            model.to_string(),
+            public_keys.iter().map(hex::encode).collect(),
+        );
+        self.save()
+    }
+
+    /// Loads a public key, by name or fingerprint prefix.
+    pub(crate) fn public_key(&self, id: &str) -> anyhow::Result<Vec<u8>> {
+        let key = &self.keys[self.find(id)?];
+        let public_key = std::fs::read(self.key_path(&key.fingerprint))?;
+        if fingerprint(&public_key) != key.fingerprint {
+            anyhow::bail!("key file of {} has been modified", &key.fingerprint[..16]);
+        }
+        Ok(public_key)
+    }
+
+    /// Loads the public keys marked as trusted.
+    pub(crate) fn trusted_keys(&self) -> anyhow::Result<Vec<Vec<u8>>> {
+        self.keys
+            .iter()
+            .filter(|key| key.trusted)
+            .map(|key| self.public_key(&key.fingerprint))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::signing::SigningKey;
+
+    #[test]
+    fn test_keyring() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("keys");
+        let author = SigningKey::generate(SigningAlgorithm::Ed25519)
+            .unwrap()
+            .0
+            .public_key()
+            .to_vec();
+        let reviewer = SigningKey::generate(SigningAlgorithm::EcdsaP256)
+            .unwrap()
+            .0
+            .public_key()
+            .to_vec();
+
+        let mut keyring = Keyring::open(&path).unwrap();
+        assert!(keyring.keys().is_empty());
+        let fingerprint = keyring
+            .add(author.clone(), Some("author".to_string()), false)
+            .unwrap()
+            .fingerprint
+            .clone();
+        keyring.add(reviewer.clone(), None, true).unwrap();
+        assert!(keyring.add(author.clone(), None, false).is_err());
+        assert!(keyring.add(vec![0; 3], None, false).is_err());
+
+        // persisted
+        let mut keyring = Keyring::open(&path).unwrap();
+        assert_eq!(keyring.keys().len(), 2);
+        assert_eq!(keyring.keys()[1].algorithm, SigningAlgorithm::EcdsaP256);
+        assert_eq!(keyring.trusted_keys().unwrap(), vec![reviewer.clone()]);
+
+        // by name or fingerprint prefix
+        keyring.set_trusted("author", true).unwrap();
+        assert_eq!(keyring.trusted_keys().unwrap().len(), 2);
+        assert!(keyring.set_trusted("nope", true).is_err());
+        let removed = keyring.remove(&fingerprint[..8]).unwrap();
+        assert_eq!(removed.name.as_deref(), Some("author"));
+
+        let mut keyring = Keyring::open(&path).unwrap();
+        assert_eq!(keyring.trusted_keys().unwrap(), vec![reviewer.clone()]);
+        let reviewer_id = keyring.keys()[0].fingerprint[..8].to_string();
+        assert_eq!(keyring.public_key(&reviewer_id).unwrap(), reviewer);
+        assert!(keyring.public_key(&fingerprint[..8]).is_err());
+
+        // trust on first use
+        assert!(keyring.pinned_keys("/models/a").unwrap().is_none());
+        keyring
+            .pin("/models/a", std::slice::from_ref(&reviewer))
+            .unwrap();
+        assert!(keyring.pin("/models/a", &[author]).is_err());
+        let keyring = Keyring::open(&path).unwrap();
+        assert_eq!(
+            keyring.pinned_keys("/models/a").unwrap(),
+            Some(vec![reviewer])
+        );
+        // pinned keys are not trusted for other models
+        assert_eq!(keyring.trusted_keys().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,363 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use ring::{
+    rand,
+    signature::{
+        EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair, UnparsedPublicKey,
+        ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_ASN1_SIGNING, ED25519, RSA_PSS_2048_8192_SHA256,
+        RSA_PSS_SHA256,
+    },
+};
+use rsa::{
+    pkcs1::DecodeRsaPublicKey, pkcs8::EncodePrivateKey, traits::PublicKeyParts, RsaPrivateKey,
+    RsaPublicKey,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::interop;
+
+// sizes of the raw public keys
+const ED25519_PUBLIC_KEY_SIZE: usize = 32;
+// uncompressed point
+const ECDSA_P256_PUBLIC_KEY_SIZE: usize = 65;
+const ML_DSA_65_PUBLIC_KEY_SIZE: usize = 1952;
+
+#[cfg(not(feature = "ml-dsa"))]
+fn ml_dsa_not_enabled() -> anyhow::Error {
+    anyhow::anyhow!("ML-DSA support is not enabled, rebuild with --features ml-dsa")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum, JsonSchema)]
+pub(crate) enum SigningAlgorithm {
+    Ed25519,
+    EcdsaP256,
+    #[value(name = "rsa-pss-2048")]
+    RsaPss2048,
+    #[value(name = "rsa-pss-4096")]
+    RsaPss4096,
+    #[value(name = "ml-dsa-65")]
+    MlDsa65,
+}
+
+impl SigningAlgorithm {
+    fn rsa_pss(modulus_bits: usize) -> anyhow::Result<Self> {
+        match modulus_bits {
+            2048 => Ok(Self::RsaPss2048),
+            4096 => Ok(Self::RsaPss4096),
+            bits => Err(anyhow::anyhow!("unsupported RSA key size: {} bits", bits)),
+        }
+    }
+
+    /// Determines the algorithm of a public key, either raw or PKCS#1 DER encoded for RSA.
+    pub fn of_public_key(public_key: &[u8]) -> anyhow::Result<Self> {
+        match public_key.len() {
+            ED25519_PUBLIC_KEY_SIZE => Ok(Self::Ed25519),
+            ECDSA_P256_PUBLIC_KEY_SIZE if public_key[0] == 0x04 => Ok(Self::EcdsaP256),
+            ML_DSA_65_PUBLIC_KEY_SIZE => Ok(Self::MlDsa65),
+            size => match RsaPublicKey::from_pkcs1_der(public_key) {
+                Ok(rsa) => Self::rsa_pss(rsa.size() * 8),
+                Err(_) => Err(anyhow::anyhow!("unsupported public key of {} bytes", size)),
+            },
+        }
+    }
+}
+
+/// A public key used to verify manifests.
+#[derive(Debug, Clone)]
+pub(crate) struct VerifyingKey {
+    algorithm: SigningAlgorithm,
+    public_key: Vec<u8>,
+}
+
+impl VerifyingKey {
+    pub fn new(public_key: Vec<u8>) -> anyhow::Result<Self> {
+        Ok(Self {
+            algorithm: SigningAlgorithm::of_public_key(&public_key)?,
+            public_key,
+        })
+    }
+
+    pub fn algorithm(&self) -> SigningAlgorithm {
+        self.algorithm
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> anyhow::Result<()> {
+        let algorithm: &dyn ring::signature::VerificationAlgorithm = match self.algorithm {
+            SigningAlgorithm::Ed25519 => &ED25519,
+            SigningAlgorithm::EcdsaP256 => &ECDSA_P256_SHA256_ASN1,
+            SigningAlgorithm::RsaPss2048 | SigningAlgorithm::RsaPss4096 => {
+                &RSA_PSS_2048_8192_SHA256
+            }
+            SigningAlgorithm::MlDsa65 => {
+                #[cfg(feature = "ml-dsa")]
+                return aws_lc_rs::signature::UnparsedPublicKey::new(
+                    &aws_lc_rs::signature::ML_DSA_65,
+                    &self.public_key,
+                )
+                .verify(data, signature)
+                .map_err(|e| anyhow::anyhow!("{}", e));
+                #[cfg(not(feature = "ml-dsa"))]
+                return Err(ml_dsa_not_enabled());
+            }
+        };
+
+        UnparsedPublicKey::new(algorithm, &self.public_key)
+            .verify(data, signature)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
+/// A private key used to sign manifests.
+#[derive(Debug)]
+pub(crate) enum SigningKey {
+    Ed25519(Ed25519KeyPair),
+    EcdsaP256(EcdsaKeyPair),
+    RsaPss(RsaKeyPair),
+    #[cfg(feature = "ml-dsa")]
+    MlDsa65(aws_lc_rs::signature::PqdsaKeyPair),
+}
+
+impl SigningKey {
+    /// Generates a new key, returns it with its PKCS#8 encoding.
+    pub fn generate(algorithm: SigningAlgorithm) -> anyhow::Result<(Self, Vec<u8>)> {
+        let rng = rand::SystemRandom::new();
+        let generation_error = |e: &dyn std::fmt::Display| {
+            anyhow::anyhow!("Failed to generate {:?} key pair: {}", algorithm, e)
+        };
+
+        let pkcs8 = match algorithm {
+            SigningAlgorithm::Ed25519 => Ed25519KeyPair::generate_pkcs8(&rng)
+                .map_err(|e| generation_error(&e))?
+                .as_ref()
+                .to_vec(),
+            SigningAlgorithm::EcdsaP256 => {
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng)
+                    .map_err(|e| generation_error(&e))?
+                    .as_ref()
+                    .to_vec()
+            }
+            // ring can't generate RSA keys
+            SigningAlgorithm::RsaPss2048 | SigningAlgorithm::RsaPss4096 => {
+                let bits = if algorithm == SigningAlgorithm::RsaPss2048 {
+                    2048
+                } else {
+                    4096
+                };
+                RsaPrivateKey::new(&mut rsa::rand_core::OsRng, bits)
+                    .map_err(|e| generation_error(&e))?
+                    .to_pkcs8_der()
+                    .map_err(|e| generation_error(&e))?
+                    .as_bytes()
+                    .to_vec()
+            }
+            #[cfg(feature = "ml-dsa")]
+            SigningAlgorithm::MlDsa65 => aws_lc_rs::signature::PqdsaKeyPair::generate(
+                &aws_lc_rs::signature::ML_DSA_65_SIGNING,
+            )
+            .and_then(|pair| pair.to_pkcs8v1())
+            .map_err(|e| generation_error(&e))?
+            .as_ref()
+            .to_vec(),
+            #[cfg(not(feature = "ml-dsa"))]
+            SigningAlgorithm::MlDsa65 => return Err(ml_dsa_not_enabled()),
+        };
+
+        Ok((Self::from_pkcs8(&pkcs8)?, pkcs8))
+    }
+
+    /// Parses a PKCS#8 encoded key of any of the supported algorithms.
+    pub fn from_pkcs8(pkcs8: &[u8]) -> anyhow::Result<Self> {
+        if let Ok(pair) = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8) {
+            return Ok(Self::Ed25519(pair));
+        }
+
+        let rng = rand::SystemRandom::new();
+        if let Ok(pair) = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8, &rng) {
+            return Ok(Self::EcdsaP256(pair));
+        }
+        if let Ok(pair) = RsaKeyPair::from_pkcs8(pkcs8) {
+            // make sure the key size is supported
+            SigningAlgorithm::rsa_pss(pair.public().modulus_len() * 8)?;
+            return Ok(Self::RsaPss(pair));
+        }
+        #[cfg(feature = "ml-dsa")]
+        if let Ok(pair) = aws_lc_rs::signature::PqdsaKeyPair::from_pkcs8(
+            &aws_lc_rs::signature::ML_DSA_65_SIGNING,
+            pkcs8,
+        ) {
+            return Ok(Self::MlDsa65(pair));
+        }
+
+        Err(anyhow::anyhow!(
+            "Failed to parse key pair: unsupported or invalid PKCS#8 key"
+        ))
+    }
+
+    pub fn algorithm(&self) -> SigningAlgorithm {
+        match self {
+            Self::Ed25519(_) => SigningAlgorithm::Ed25519,
+            Self::EcdsaP256(_) => SigningAlgorithm::EcdsaP256,
+            Self::RsaPss(pair) => SigningAlgorithm::rsa_pss(pair.public().modulus_len() * 8)
+                .expect("RSA key size is validated when parsing"),
+            #[cfg(feature = "ml-dsa")]
+            Self::MlDsa65(_) => SigningAlgorithm::MlDsa65,
+        }
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        match self {
+            Self::Ed25519(pair) => pair.public_key().as_ref(),
+            Self::EcdsaP256(pair) => pair.public_key().as_ref(),
+            Self::RsaPss(pair) => pair.public_key().as_ref(),
+            #[cfg(feature = "ml-dsa")]
+            Self::MlDsa65(pair) => {
+                use aws_lc_rs::signature::KeyPair as _;
+                pair.public_key().as_ref()
+            }
+        }
+    }
+
+    pub fn sign(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Ed25519(pair) => Ok(pair.sign(data).as_ref().to_vec()),
+            Self::EcdsaP256(pair) => pair
+                .sign(&rand::SystemRandom::new(), data)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|e| anyhow::anyhow!("Failed to sign: {}", e)),
+            Self::RsaPss(pair) => {
+                let mut signature = vec![0u8; pair.public().modulus_len()];
+                pair.sign(
+                    &RSA_PSS_SHA256,
+                    &rand::SystemRandom::new(),
+                    data,
+                    &mut signature,
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to sign: {}", e))?;
+                Ok(signature)
+            }
+            #[cfg(feature = "ml-dsa")]
+            Self::MlDsa65(pair) => {
+                let mut signature = vec![0u8; pair.algorithm().signature_len()];
+                let size = pair
+                    .sign(data, &mut signature)
+                    .map_err(|e| anyhow::anyhow!("Failed to sign: {}", e))?;
+                signature.truncate(size);
+                Ok(signature)
+            }
+        }
+    }
+}
+
+pub(crate) fn create_key(
+    private_key: &Path,
+    public_key: &Path,
+    algorithm: SigningAlgorithm,
+) -> anyhow::Result<()> {
+    tracing::info!("Generating {:?} private key ...", algorithm);
+
+    let (pair, pkcs8) = SigningKey::generate(algorithm)?;
+
+    tracing::info!("Writing private key to {} ...", private_key.display());
+    std::fs::write(private_key, &pkcs8)?;
+
+    tracing::info!("Writing public key to {} ...", public_key.display());
+    std::fs::write(public_key, pair.public_key())?;
+
+    Ok(())
+}
+
+/// Loads a PKCS#8, OpenSSH or minisign private key.
+pub(crate) fn load_key(path: &Path) -> anyhow::Result<SigningKey> {
+    tracing::info!("Loading signing key from {}...", path.display());
+
+    let data =
+        std::fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read key file: {}", e))?;
+    if interop::is_openssh_private_key(&data) {
+        interop::openssh_private_key(&data, interop::read_password)
+    } else if interop::is_minisign_key(&data) {
+        interop::minisign_secret_key(&data, interop::read_password)
+    } else {
+        SigningKey::from_pkcs8(&data)
+    }
+}
+
+/// Loads a raw, OpenSSH, minisign or PEM public key.
+pub(crate) fn load_public_key(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let data = std::fs::read(path)?;
+    if interop::is_openssh_public_key(&data) {
+        interop::openssh_public_key(&data)
+    } else if interop::is_minisign_key(&data) {
+        interop::minisign_public_key(&data)
+    } else if interop::is_pem_public_key(&data) {
+        interop::pem_public_key(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_sign() {
+        for algorithm in [
+            SigningAlgorithm::Ed25519,
+            SigningAlgorithm::EcdsaP256,
+            SigningAlgorithm::RsaPss2048,
+        ] {
+            let (key, pkcs8) = SigningKey::generate(algorithm).unwrap();
+            assert_eq!(key.algorithm(), algorithm);
+
+            // round trip through the PKCS#8 encoding
+            let key = SigningKey::from_pkcs8(&pkcs8).unwrap();
+            assert_eq!(key.algorithm(), algorithm);
+            assert_eq!(
+                SigningAlgorithm::of_public_key(key.public_key()).unwrap(),
+                algorithm
+            );
+
+            let signature = key.sign(b"data").unwrap();
+            let verifying_key = VerifyingKey::new(key.public_key().to_vec()).unwrap();
+            assert_eq!(verifying_key.algorithm(), algorithm);
+            assert!(verifying_key.verify(b"data", &signature).is_ok());
+            assert!(verifying_key.verify(b"tampered", &signature).is_err());
+        }
+    }
+
+    #[cfg(feature = "ml-dsa")]
+    #[test]
+    fn test_ml_dsa() {
+        let (_, pkcs8) = SigningKey::generate(SigningAlgorithm::MlDsa65).unwrap();
+        let key = SigningKey::from_pkcs8(&pkcs8).unwrap();
+        assert_eq!(key.algorithm(), SigningAlgorithm::MlDsa65);
+
+        let signature = key.sign(b"data").unwrap();
+        let verifying_key = VerifyingKey::new(key.public_key().to_vec()).unwrap();
+        assert_eq!(verifying_key.algorithm(), SigningAlgorithm::MlDsa65);
+        assert!(verifying_key.verify(b"data", &signature).is_ok());
+        assert!(verifying_key.verify(b"tampered", &signature).is_err());
+    }
+
+    #[cfg(not(feature = "ml-dsa"))]
+    #[test]
+    fn test_ml_dsa_not_enabled() {
+        assert!(SigningKey::generate(SigningAlgorithm::MlDsa65).is_err());
+
+        let verifying_key = VerifyingKey::new(vec![0u8; ML_DSA_65_PUBLIC_KEY_SIZE]).unwrap();
+        assert_eq!(verifying_key.algorithm(), SigningAlgorithm::MlDsa65);
+        assert!(verifying_key.verify(b"data", b"signature").is_err());
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(SigningKey::from_pkcs8(b"not a key").is_err());
+        assert!(SigningAlgorithm::of_public_key(&[0u8; 16]).is_err());
+    }
+}
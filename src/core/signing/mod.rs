@@ -0,0 +1,2062 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Seek},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake2::{Blake2b512, Digest};
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    archive,
+    data::TensorData,
+    progress::{FileProgress, Progress},
+};
+
+mod dsse;
+mod interop;
+mod keyring;
+mod keys;
+mod policy;
+mod revocation;
+mod timestamp;
+mod verdict;
+
+pub(crate) use dsse::{Envelope, Statement};
+pub(crate) use interop::public_key_to_pem;
+pub(crate) use keyring::*;
+pub(crate) use keys::*;
+pub(crate) use policy::Policy;
+pub(crate) use revocation::*;
+pub use verdict::Failure;
+pub(crate) use verdict::*;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub(crate) enum HashAlgorithm {
+    BLAKE2b512,
+}
+
+impl HashAlgorithm {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::BLAKE2b512 => "BLAKE2b512",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Algorithms {
+    hash: HashAlgorithm,
+    // signing algorithm of 1.0 manifests, each signature has its own since 2.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<SigningAlgorithm>,
+    // if set, files are hashed in chunks of this size and their checksum is the merkle root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chunk_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub(crate) enum Version {
+    #[serde(rename = "1.0")]
+    V1,
+    // multiple signatures
+    #[serde(rename = "2.0")]
+    V2,
+    // the whole manifest is signed, binding the checksums to the file paths and sizes
+    #[serde(rename = "3.0")]
+    V3,
+}
+
+// domain separation between leaves and inner nodes of the merkle tree
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+fn merkle_leaf(chunk: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2b512::new();
+    hasher.update([MERKLE_LEAF_PREFIX]);
+    hasher.update(chunk);
+    hasher.finalize().to_vec()
+}
+
+/// Computes the root of the merkle tree of the given leaves, odd nodes are promoted to the next level.
+fn merkle_root(leaves: &[Vec<u8>]) -> Vec<u8> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Blake2b512::new();
+                    hasher.update([MERKLE_NODE_PREFIX]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().to_vec()
+                }
+                [single] => single.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level.pop().unwrap_or_else(|| merkle_leaf(&[]))
+}
+
+// hex-encoded blake2b512 hash of the public key
+pub(crate) fn fingerprint(public_key: &[u8]) -> String {
+    let mut hasher = Blake2b512::new();
+    hasher.update(public_key);
+    hex::encode(hasher.finalize())
+}
+
+// parses a duration made of a number and a unit, s, m, h, d or w
+pub(crate) fn parse_duration(duration: &str) -> anyhow::Result<chrono::Duration> {
+    let duration = duration.trim();
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow::anyhow!("missing unit in duration '{}'", duration))?;
+    let (value, unit) = duration.split_at(split);
+    let value: i64 = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid duration '{}'", duration))?;
+
+    match unit {
+        "s" => Ok(chrono::Duration::seconds(value)),
+        "m" => Ok(chrono::Duration::minutes(value)),
+        "h" => Ok(chrono::Duration::hours(value)),
+        "d" => Ok(chrono::Duration::days(value)),
+        "w" => Ok(chrono::Duration::weeks(value)),
+        _ => Err(anyhow::anyhow!(
+            "invalid unit in duration '{}', expected s, m, h, d or w",
+            duration
+        )),
+    }
+}
+
+/// A signature of the manifest, the same checksums can be signed by multiple parties.
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub(crate) struct Signer {
+    // hex-encoded blake2b512 hash of the public key of the signing key
+    pub(crate) public_key: String,
+    // algorithm used for signing
+    pub(crate) algorithm: SigningAlgorithm,
+    // ISO 8601 timestamp of when the signature was created
+    pub(crate) signed_at: String,
+    // software name and version
+    pub(crate) signed_with: String,
+    // hex-encoded signature of the checksums
+    pub(crate) signature: String,
+    // base64-encoded RFC 3161 timestamp token of the signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) timestamp: Option<String>,
+    // ISO 8601 timestamp after which the signature is no longer valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expires_at: Option<String>,
+    // hex-encoded public key, if included its fingerprint must match public_key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) raw_public_key: Option<String>,
+}
+
+impl Signer {
+    // signatures of 1.0 and 2.0 manifests with an expiration also sign the signing and expiration dates
+    fn legacy_signed_data(&self, data: &str) -> String {
+        match &self.expires_at {
+            Some(expires_at) => format!(
+                "{}.signed_at={}.expires_at={}",
+                data, self.signed_at, expires_at
+            ),
+            None => data.to_string(),
+        }
+    }
+
+    /// Returns the public key included in the signature, if any.
+    pub(crate) fn included_public_key(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(raw_public_key) = &self.raw_public_key else {
+            return Ok(None);
+        };
+        let public_key = hex::decode(raw_public_key)?;
+        if fingerprint(&public_key) != self.public_key {
+            anyhow::bail!(
+                "the public key included in the signature by {} doesn't match its fingerprint",
+                &self.public_key[..16]
+            );
+        }
+        Ok(Some(public_key))
+    }
+
+    pub(crate) fn expiration(&self) -> anyhow::Result<Option<chrono::DateTime<chrono::Utc>>> {
+        self.expires_at
+            .as_ref()
+            .map(|date| Ok(chrono::DateTime::parse_from_rfc3339(date)?.to_utc()))
+            .transpose()
+    }
+
+    /// Returns the time the signature has been timestamped at, if a timestamp is present. The
+    /// TSA certificate, PEM or DER encoded, is the one the timestamp must have been signed with.
+    pub(crate) fn timestamp(
+        &self,
+        tsa_certificate: Option<&[u8]>,
+    ) -> anyhow::Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let Some(token) = &self.timestamp else {
+            return Ok(None);
+        };
+        let tsa_certificate = tsa_certificate
+            .map(timestamp::load_certificate)
+            .transpose()?;
+
+        timestamp::verify_timestamp(
+            &STANDARD.decode(token)?,
+            &hex::decode(&self.signature)?,
+            tsa_certificate.as_deref(),
+        )
+        .map(Some)
+    }
+}
+
+/// Result of the verification of a single file against a signature manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FileStatus {
+    Ok,
+    // checksum or size differs from the signed one
+    Modified,
+    // signed but not found
+    Missing,
+    // found but not signed
+    Extra,
+}
+
+impl std::fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileStatus::Ok => write!(f, "OK"),
+            FileStatus::Modified => write!(f, "modified"),
+            FileStatus::Missing => write!(f, "missing"),
+            FileStatus::Extra => write!(f, "extra"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+pub(crate) struct Manifest {
+    // version of the manifest format
+    pub(crate) version: Version,
+    // signer fields of 1.0 manifests, moved to the signatures when loaded
+    #[serde(default, skip_serializing)]
+    signed_at: Option<String>,
+    #[serde(default, skip_serializing)]
+    signed_with: Option<String>,
+    #[serde(default, skip_serializing)]
+    public_key: Option<String>,
+    #[serde(default, skip_serializing)]
+    signature: Option<String>,
+    #[serde(default, skip_serializing)]
+    timestamp: Option<String>,
+    // algorithms used for hashing and signing
+    pub(crate) algorithms: Algorithms,
+    // checksums of the files
+    pub(crate) checksums: BTreeMap<String, String>,
+    // checksums of the data of each tensor, by file and tensor name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tensor_checksums: BTreeMap<String, BTreeMap<String, String>>,
+    // checksums of the files of archives, by archive and member name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) member_checksums: BTreeMap<String, BTreeMap<String, String>>,
+    // checksums of each chunk of the files, if hashed in chunks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) chunk_checksums: BTreeMap<String, Vec<String>>,
+    // sizes of the files, since 3.0
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) sizes: BTreeMap<String, u64>,
+    // signatures of the checksums
+    #[serde(default)]
+    pub(crate) signatures: Vec<Signer>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    base_path: PathBuf,
+    #[serde(skip_serializing, skip_deserializing)]
+    signing_key: Option<SigningKey>,
+    #[serde(skip_serializing, skip_deserializing)]
+    verifying_keys: Vec<VerifyingKey>,
+    // minimum number of valid signatures made with the verifying keys
+    #[serde(skip_serializing, skip_deserializing)]
+    required_signers: usize,
+    // signatures made with revoked keys are refused
+    #[serde(skip_serializing, skip_deserializing)]
+    revocations: Option<RevocationList>,
+    // expiration of the signatures we create
+    #[serde(skip_serializing, skip_deserializing)]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    // include the public key in the signatures we create
+    #[serde(skip_serializing, skip_deserializing)]
+    include_public_key: bool,
+}
+
+impl Manifest {
+    pub(crate) fn from_signature_path(base_path: &Path, path: &Path) -> anyhow::Result<Self> {
+        Self::from_signature_str(base_path, &std::fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn from_signature_str(base_path: &Path, signature: &str) -> anyhow::Result<Self> {
+        let mut this: Manifest = serde_json::from_str(signature)?;
+        this.base_path = base_path.canonicalize()?;
+
+        if matches!(this.version, Version::V1) {
+            this.version = Version::V2;
+            this.signatures = vec![Signer {
+                public_key: this.public_key.take().unwrap_or_default(),
+                algorithm: this
+                    .algorithms
+                    .signature
+                    .take()
+                    .ok_or_else(|| anyhow::anyhow!("missing signing algorithm"))?,
+                signed_at: this.signed_at.take().unwrap_or_default(),
+                signed_with: this.signed_with.take().unwrap_or_default(),
+                signature: this.signature.take().unwrap_or_default(),
+                timestamp: this.timestamp.take(),
+                expires_at: None,
+                raw_public_key: None,
+            }];
+        }
+
+        // included public keys must match the pinned fingerprints
+        for signer in &this.signatures {
+            signer.included_public_key()?;
+        }
+
+        Ok(this)
+    }
+
+    fn new(base_path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            version: Version::V3,
+            signed_at: None,
+            signed_with: None,
+            public_key: None,
+            signature: None,
+            timestamp: None,
+            algorithms: Algorithms {
+                hash: HashAlgorithm::BLAKE2b512,
+                signature: None,
+                chunk_size: None,
+            },
+            checksums: BTreeMap::new(),
+            tensor_checksums: BTreeMap::new(),
+            member_checksums: BTreeMap::new(),
+            chunk_checksums: BTreeMap::new(),
+            sizes: BTreeMap::new(),
+            signatures: vec![],
+            signing_key: None,
+            verifying_keys: vec![],
+            required_signers: 1,
+            revocations: None,
+            expires_at: None,
+            include_public_key: false,
+            base_path: base_path.canonicalize()?,
+        })
+    }
+
+    pub(crate) fn from_signing_key(
+        base_path: &Path,
+        signing_key: SigningKey,
+    ) -> anyhow::Result<Self> {
+        let mut this = Self::new(base_path)?;
+        this.signing_key = Some(signing_key);
+        Ok(this)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_public_key(
+        base_path: &Path,
+        public_key_bytes: Vec<u8>,
+    ) -> anyhow::Result<Self> {
+        Self::from_public_keys(base_path, vec![public_key_bytes])
+    }
+
+    pub(crate) fn from_public_keys(
+        base_path: &Path,
+        public_keys: Vec<Vec<u8>>,
+    ) -> anyhow::Result<Self> {
+        let mut this = Self::new(base_path)?;
+        for public_key in public_keys {
+            // each key must only count once towards the required signers
+            if this
+                .verifying_keys
+                .iter()
+                .any(|key| key.as_bytes() == public_key.as_slice())
+            {
+                anyhow::bail!(
+                    "public key {} given more than once",
+                    &fingerprint(&public_key)[..16]
+                );
+            }
+            this.verifying_keys.push(VerifyingKey::new(public_key)?);
+        }
+        Ok(this)
+    }
+
+    pub(crate) fn from_public_key_paths(
+        base_path: &Path,
+        public_keys: &[PathBuf],
+    ) -> anyhow::Result<Self> {
+        let public_keys = public_keys
+            .iter()
+            .map(|path| load_public_key(path))
+            .collect::<anyhow::Result<_>>()?;
+        Self::from_public_keys(base_path, public_keys)
+    }
+
+    /// Creates a manifest to verify signatures against a threshold policy.
+    pub(crate) fn from_policy(base_path: &Path, policy: &Policy) -> anyhow::Result<Self> {
+        let mut this = Self::from_public_key_paths(base_path, &policy.trusted_keys)?;
+        this.set_required_signers(policy.threshold)?;
+        Ok(this)
+    }
+
+    /// Sets the key to add a signature to an already signed manifest with.
+    pub(crate) fn set_signing_key(&mut self, signing_key: SigningKey) {
+        self.signing_key = Some(signing_key);
+    }
+
+    /// Requires at least this many valid signatures made with different verifying keys.
+    pub(crate) fn set_required_signers(&mut self, required_signers: usize) -> anyhow::Result<()> {
+        if required_signers == 0 || required_signers > self.verifying_keys.len() {
+            anyhow::bail!(
+                "can't require {} signers with {} public keys",
+                required_signers,
+                self.verifying_keys.len()
+            );
+        }
+        self.required_signers = required_signers;
+        Ok(())
+    }
+
+    /// Makes the signatures we create expire at the given time.
+    pub(crate) fn set_expiration(&mut self, expires_at: chrono::DateTime<chrono::Utc>) {
+        self.expires_at = Some(expires_at);
+    }
+
+    /// Includes the full public key in the signatures we create, so they can be verified without
+    /// shipping the key separately.
+    pub(crate) fn set_include_public_key(&mut self, include_public_key: bool) {
+        self.include_public_key = include_public_key;
+    }
+
+    /// Refuses signatures made with the keys revoked by the list, which must be signed by the issuer
+    /// public key if given, or by one of the verifying keys otherwise.
+    pub(crate) fn set_revocations(
+        &mut self,
+        revocations: RevocationList,
+        issuer: Option<Vec<u8>>,
+    ) -> anyhow::Result<()> {
+        match issuer {
+            Some(issuer) => revocations.verify(&[VerifyingKey::new(issuer)?])?,
+            None => revocations.verify(&self.verifying_keys)?,
+        }
+        self.revocations = Some(revocations);
+        Ok(())
+    }
+
+    fn relative_path(&self, path: &Path) -> anyhow::Result<String> {
+        let path = path.canonicalize()?;
+        path.strip_prefix(&self.base_path)
+            .map(|p| p.to_string_lossy().to_string())
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "base_path={} path={} error={}",
+                    self.base_path.display(),
+                    path.display(),
+                    e
+                )
+            })
+    }
+
+    /// Hash the files in chunks of the given size, chunks are hashed in parallel and
+    /// can be verified individually.
+    pub(crate) fn set_chunk_size(&mut self, chunk_size: u64) -> anyhow::Result<()> {
+        if chunk_size == 0 {
+            anyhow::bail!("chunk size must be greater than zero");
+        }
+        self.algorithms.chunk_size = Some(chunk_size);
+        Ok(())
+    }
+
+    fn compute_chunked_checksum(
+        &mut self,
+        path: &Path,
+        chunk_size: u64,
+        progress: &FileProgress,
+    ) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+
+        let file = std::fs::File::open(path)?;
+        let leaves: Vec<Vec<u8>> = if file.metadata()?.len() == 0 {
+            vec![merkle_leaf(&[])]
+        } else {
+            let data = unsafe { memmap2::Mmap::map(&file)? };
+            data.par_chunks(chunk_size as usize)
+                .map(|chunk| {
+                    let leaf = merkle_leaf(chunk);
+                    progress.inc(chunk.len() as u64);
+                    leaf
+                })
+                .collect()
+        };
+
+        self.checksums
+            .insert(relative_path.clone(), hex::encode(merkle_root(&leaves)));
+        self.sizes
+            .insert(relative_path.clone(), file.metadata()?.len());
+        self.chunk_checksums
+            .insert(relative_path, leaves.iter().map(hex::encode).collect());
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn compute_checksum(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.compute_checksum_with(path, &FileProgress::hidden())
+    }
+
+    fn compute_checksum_with(
+        &mut self,
+        path: &Path,
+        progress: &FileProgress,
+    ) -> anyhow::Result<()> {
+        if let Some(chunk_size) = self.algorithms.chunk_size {
+            return self.compute_chunked_checksum(path, chunk_size, progress);
+        }
+
+        let file = std::fs::File::open(path)?;
+        self.compute_checksum_of(path, &mut progress.wrap_read(file))
+    }
+
+    // computes the checksums of the files in order, reporting the progress
+    fn compute_checksums(&mut self, paths: &[PathBuf], action: &str) -> anyhow::Result<()> {
+        let progress = Progress::for_files(paths);
+        for path in paths {
+            progress.log(&format!("{} {} ...", action, path.display()));
+
+            self.compute_checksum_with(path, &progress.file(path))?;
+        }
+        Ok(())
+    }
+
+    // hashes the given content as the one of the file at path
+    fn compute_checksum_of(&mut self, path: &Path, content: &mut dyn Read) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+
+        let mut hasher = Blake2b512::new();
+        let size = std::io::copy(content, &mut hasher)?;
+        let hash_bytes = hasher.finalize();
+        let hash = hex::encode(hash_bytes);
+        tracing::trace!("{} ({} bytes): {}", relative_path, size, hash);
+
+        self.checksums.insert(relative_path.clone(), hash);
+        self.sizes.insert(relative_path, size);
+        Ok(())
+    }
+
+    fn tensor_checksums_of(tensors: &[TensorData]) -> BTreeMap<String, String> {
+        tensors
+            .par_iter()
+            .map(|tensor| {
+                let mut hasher = Blake2b512::new();
+                hasher.update(tensor.bytes());
+                (tensor.id.clone(), hex::encode(hasher.finalize()))
+            })
+            .collect()
+    }
+
+    /// Records the checksums of the data of each tensor stored in the file.
+    pub(crate) fn add_tensor_checksums(
+        &mut self,
+        path: &Path,
+        tensors: &[TensorData],
+    ) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+        self.tensor_checksums
+            .insert(relative_path, Self::tensor_checksums_of(tensors));
+        Ok(())
+    }
+
+    /// Returns the names of the tensors of the file whose data doesn't match the recorded checksums.
+    pub(crate) fn tampered_tensors(
+        &self,
+        path: &Path,
+        tensors: &[TensorData],
+    ) -> anyhow::Result<Vec<String>> {
+        let Some(expected) = self.tensor_checksums.get(&self.relative_path(path)?) else {
+            return Ok(vec![]);
+        };
+        let actual = Self::tensor_checksums_of(tensors);
+
+        let mut tampered: Vec<String> = expected
+            .iter()
+            .filter(|(id, checksum)| actual.get(*id) != Some(*checksum))
+            .map(|(id, _)| id.clone())
+            .collect();
+        // tensors that were not there at signing time
+        tampered.extend(
+            actual
+                .keys()
+                .filter(|id| !expected.contains_key(*id))
+                .cloned(),
+        );
+        tampered.sort();
+
+        Ok(tampered)
+    }
+
+    fn member_checksums_of(path: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+        let mut checksums = BTreeMap::new();
+        archive::for_each_member(path, |name, content| {
+            let mut hasher = Blake2b512::new();
+            std::io::copy(content, &mut hasher)?;
+            checksums.insert(name.to_string(), hex::encode(hasher.finalize()));
+            Ok(())
+        })?;
+        Ok(checksums)
+    }
+
+    /// Records the checksums of each file of the archive.
+    pub(crate) fn add_member_checksums(&mut self, path: &Path) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+        self.member_checksums
+            .insert(relative_path, Self::member_checksums_of(path)?);
+        Ok(())
+    }
+
+    /// Returns the names of the files of the archive that don't match the recorded checksums,
+    /// added or removed ones included.
+    pub(crate) fn tampered_members(&self, path: &Path) -> anyhow::Result<Vec<String>> {
+        let Some(expected) = self.member_checksums.get(&self.relative_path(path)?) else {
+            return Ok(vec![]);
+        };
+        let actual = Self::member_checksums_of(path)?;
+
+        let mut tampered: Vec<String> = expected
+            .iter()
+            .filter(|(name, checksum)| actual.get(*name) != Some(*checksum))
+            .map(|(name, _)| name.clone())
+            .chain(
+                actual
+                    .keys()
+                    .filter(|name| !expected.contains_key(*name))
+                    .cloned(),
+            )
+            .collect();
+        tampered.sort();
+
+        Ok(tampered)
+    }
+
+    /// Compares the computed checksums with the signed ones, file by file, sorted by path.
+    pub(crate) fn file_report(&self, signature: &Self) -> Vec<(String, FileStatus)> {
+        let is_modified = |path: &str, signed_path: &str| {
+            self.checksums.get(path) != signature.checksums.get(signed_path)
+                || (!signature.sizes.is_empty()
+                    && self.sizes.get(path) != signature.sizes.get(signed_path))
+        };
+
+        // a single file can be verified against the signature of a file with another name
+        if self.checksums.len() == 1 && signature.checksums.len() == 1 {
+            let path = self.checksums.keys().next().unwrap();
+            let signed_path = signature.checksums.keys().next().unwrap();
+            let status = if is_modified(path, signed_path) {
+                FileStatus::Modified
+            } else {
+                FileStatus::Ok
+            };
+            return vec![(path.clone(), status)];
+        }
+
+        let mut report: Vec<(String, FileStatus)> = self
+            .checksums
+            .keys()
+            .map(|path| {
+                let status = if !signature.checksums.contains_key(path) {
+                    FileStatus::Extra
+                } else if is_modified(path, path) {
+                    FileStatus::Modified
+                } else {
+                    FileStatus::Ok
+                };
+                (path.clone(), status)
+            })
+            .collect();
+        report.extend(
+            signature
+                .checksums
+                .keys()
+                .filter(|path| !self.checksums.contains_key(*path))
+                .map(|path| (path.clone(), FileStatus::Missing)),
+        );
+        report.sort_by(|a, b| a.0.cmp(&b.0));
+
+        report
+    }
+
+    fn data_to_sign(&self) -> String {
+        // sort hashes by lexicographical order and join them with dots
+        let mut checksums = self
+            .checksums
+            .values()
+            .map(|s| s.to_owned())
+            .collect::<Vec<String>>();
+        checksums.sort();
+
+        // tensor checksums are signed along with their names
+        let mut tensor_checksums = self
+            .tensor_checksums
+            .values()
+            .flat_map(|tensors| tensors.iter().map(|(id, c)| format!("{}={}", id, c)))
+            .collect::<Vec<String>>();
+        tensor_checksums.sort();
+        checksums.extend(tensor_checksums);
+
+        checksums.join(".")
+    }
+
+    // returns the data signed by the signer, for 3.0 manifests the canonical JSON serialization
+    // of the manifest (fields in a fixed order, maps sorted by key, no whitespace) along with
+    // the signer metadata, only the checksums for older versions
+    fn signed_data(&self, signer: &Signer) -> anyhow::Result<String> {
+        if !matches!(self.version, Version::V3) {
+            return Ok(signer.legacy_signed_data(&self.data_to_sign()));
+        }
+
+        #[derive(Serialize)]
+        struct SignedContent<'a> {
+            version: &'a Version,
+            algorithms: &'a Algorithms,
+            checksums: &'a BTreeMap<String, String>,
+            sizes: &'a BTreeMap<String, u64>,
+            tensor_checksums: &'a BTreeMap<String, BTreeMap<String, String>>,
+            // only in the signed data of archives, for the signatures of other files to verify
+            #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+            member_checksums: &'a BTreeMap<String, BTreeMap<String, String>>,
+            chunk_checksums: &'a BTreeMap<String, Vec<String>>,
+            public_key: &'a str,
+            algorithm: SigningAlgorithm,
+            signed_at: &'a str,
+            signed_with: &'a str,
+            expires_at: Option<&'a str>,
+        }
+
+        Ok(serde_json::to_string(&SignedContent {
+            version: &self.version,
+            algorithms: &self.algorithms,
+            checksums: &self.checksums,
+            sizes: &self.sizes,
+            tensor_checksums: &self.tensor_checksums,
+            member_checksums: &self.member_checksums,
+            chunk_checksums: &self.chunk_checksums,
+            public_key: &signer.public_key,
+            algorithm: signer.algorithm,
+            signed_at: &signer.signed_at,
+            signed_with: &signer.signed_with,
+            expires_at: signer.expires_at.as_deref(),
+        })?)
+    }
+
+    fn create_signature(&mut self) -> anyhow::Result<&str> {
+        let signing_key = self
+            .signing_key
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no signing key"))?;
+        let public_key = fingerprint(signing_key.public_key());
+        if self.signatures.iter().any(|s| s.public_key == public_key) {
+            anyhow::bail!("the manifest has already been signed with this key");
+        }
+
+        let mut signer = Signer {
+            public_key,
+            algorithm: signing_key.algorithm(),
+            signed_at: chrono::Utc::now().to_rfc3339(),
+            signed_with: format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            signature: String::new(),
+            timestamp: None,
+            expires_at: self.expires_at.map(|date| date.to_rfc3339()),
+            raw_public_key: self
+                .include_public_key
+                .then(|| hex::encode(signing_key.public_key())),
+        };
+
+        // sign data
+        signer.signature = hex::encode(signing_key.sign(self.signed_data(&signer)?.as_bytes())?);
+
+        self.signatures.push(signer);
+
+        Ok(&self.signatures.last().unwrap().signature)
+    }
+
+    // after hashing the files again, makes sure they are the ones that have been signed
+    fn check_unchanged(
+        &mut self,
+        signed: BTreeMap<String, String>,
+        signed_sizes: BTreeMap<String, u64>,
+    ) -> anyhow::Result<()> {
+        self.verify_checksums(&signed)
+            .map_err(|e| anyhow::anyhow!("the files changed since they have been signed: {}", e))?;
+        self.checksums = signed;
+        self.sizes = signed_sizes;
+        Ok(())
+    }
+
+    // 3.0 manifests bind the checksums to the file paths and sizes, the only file of a single
+    // file manifest can still be renamed as it can't be swapped with another one
+    fn verify_files(&self, signature: &Self) -> anyhow::Result<()> {
+        if self.checksums.len() == 1 && signature.checksums.len() == 1 {
+            let (path, checksum) = self.checksums.first_key_value().unwrap();
+            let (signed_path, signed_checksum) = signature.checksums.first_key_value().unwrap();
+            if checksum != signed_checksum
+                || self.sizes.get(path) != signature.sizes.get(signed_path)
+            {
+                return Err(Failure::ModifiedFile
+                    .error(format!("missing or invalid checksum for {}", path)));
+            }
+            return Ok(());
+        }
+
+        for (path, checksum) in &self.checksums {
+            match signature.checksums.get(path) {
+                None => {
+                    return Err(Failure::MissingFile.error(format!("file {} is not signed", path)))
+                }
+                Some(signed) if signed != checksum => {
+                    return Err(Failure::ModifiedFile
+                        .error(format!("missing or invalid checksum for {}", path)))
+                }
+                _ => {}
+            }
+            if signature.sizes.get(path) != self.sizes.get(path) {
+                return Err(Failure::ModifiedFile.error(format!("invalid size for {}", path)));
+            }
+        }
+        for path in signature.checksums.keys() {
+            if !self.checksums.contains_key(path) {
+                return Err(Failure::MissingFile.error(format!("missing file {}", path)));
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_checksums(&self, checksums: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        // check if all the required checksums are present, use the checksum value instead
+        // of the path as the file name might be different
+        let provided_checksums = checksums.values().collect::<Vec<&String>>();
+        for (path, required_checksum) in self.checksums.iter() {
+            if !provided_checksums.contains(&required_checksum) {
+                return Err(Failure::ModifiedFile
+                    .error(format!("missing or invalid checksum for {}", path)));
+            }
+        }
+        // check if all the provided checksums are valid
+        let required_checksums = self.checksums.values().collect::<Vec<&String>>();
+        for (path, expected_checksum) in checksums {
+            if !required_checksums.contains(&expected_checksum) {
+                return Err(Failure::ModifiedFile.error(format!("invalid checksum for {}", path)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the signatures made with revoked keys, along with their revocation.
+    pub(crate) fn revoked_signers<'a>(
+        &'a self,
+        signature: &'a Self,
+    ) -> Vec<(&'a Signer, &'a Revocation)> {
+        match &self.revocations {
+            Some(revocations) => signature
+                .signatures
+                .iter()
+                .filter_map(|signer| {
+                    revocations
+                        .find(&signer.public_key)
+                        .map(|revocation| (signer, revocation))
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // returns the signatures made with our keys, along with the key
+    fn matching_signers<'a>(&self, signature: &'a Self) -> Vec<(&'a Signer, &VerifyingKey)> {
+        let mut matching: Vec<(&Signer, &VerifyingKey)> = vec![];
+        for signer in &signature.signatures {
+            if let Some(revocations) = &self.revocations {
+                if revocations.find(&signer.public_key).is_some() {
+                    continue;
+                }
+            }
+            // repeated signatures of the same key only count once
+            if matching
+                .iter()
+                .any(|(other, _)| other.public_key == signer.public_key)
+            {
+                continue;
+            }
+            if let Some(key) = self
+                .verifying_keys
+                .iter()
+                .find(|key| fingerprint(key.as_bytes()) == signer.public_key)
+            {
+                matching.push((signer, key));
+            }
+        }
+        matching
+    }
+
+    fn verify_signer(key: &VerifyingKey, signer: &Signer, data: &str) -> anyhow::Result<()> {
+        if signer.algorithm != key.algorithm() {
+            return Err(Failure::KeyMismatch.error(format!(
+                "signing algorithm mismatch: signed with {:?}, verifying with {:?}",
+                signer.algorithm,
+                key.algorithm()
+            )));
+        }
+
+        let signature = hex::decode(&signer.signature).map_err(|e| {
+            Failure::InvalidSignature.error(format!("signature verification failed: {}", e))
+        })?;
+        key.verify(data.as_bytes(), &signature).map_err(|e| {
+            Failure::InvalidSignature.error(format!("signature verification failed: {}", e))
+        })?;
+
+        if let Some(expires_at) = signer.expiration()? {
+            if expires_at < chrono::Utc::now() {
+                return Err(Failure::Expired.error(format!(
+                    "signature by {} expired at {}",
+                    &signer.public_key[..16],
+                    expires_at.to_rfc3339()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the signatures of the manifest, as is, that have been made with our public keys.
+    pub(crate) fn trusted_signers<'a>(&self, signature: &'a Self) -> Vec<&'a Signer> {
+        self.matching_signers(signature)
+            .into_iter()
+            .filter(|(signer, key)| {
+                signature
+                    .signed_data(signer)
+                    .and_then(|data| Self::verify_signer(key, signer, &data))
+                    .is_ok()
+            })
+            .map(|(signer, _)| signer)
+            .collect()
+    }
+
+    /// Returns the time the signature of this manifest has been made at if it can be trusted,
+    /// either because it has been timestamped by a TSA or because it has been signed along with
+    /// the checksums: always in 3.0 manifests, only with an expiration in older ones.
+    pub(crate) fn authenticated_date(
+        &self,
+        signer: &Signer,
+        tsa_certificate: Option<&[u8]>,
+    ) -> anyhow::Result<Option<chrono::DateTime<chrono::Utc>>> {
+        if let Some(time) = signer.timestamp(tsa_certificate)? {
+            Ok(Some(time))
+        } else if matches!(self.version, Version::V3) || signer.expires_at.is_some() {
+            Ok(Some(
+                chrono::DateTime::parse_from_rfc3339(&signer.signed_at)?.to_utc(),
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Name of the algorithm the files have been hashed with.
+    pub(crate) fn hash_algorithm(&self) -> &'static str {
+        self.algorithms.hash.name()
+    }
+
+    /// Returns true if the signature manifest, as is, has been signed with our public keys.
+    pub(crate) fn is_authentic(&self, signature: &Self) -> bool {
+        self.trusted_signers(signature).len() >= self.required_signers
+    }
+
+    pub(crate) fn sign(&mut self, paths: &mut [PathBuf]) -> anyhow::Result<&str> {
+        paths.sort();
+
+        // compute checksums for all files
+        self.compute_checksums(paths, "Signing")?;
+
+        // sign
+        self.create_signature()
+    }
+
+    /// Signs the content of a file that will carry its own signature.
+    pub(crate) fn sign_embedded(
+        &mut self,
+        path: &Path,
+        mut content: impl Read,
+    ) -> anyhow::Result<&str> {
+        if self.algorithms.chunk_size.is_some() {
+            anyhow::bail!("embedded signatures can't be computed in chunks");
+        }
+
+        tracing::info!("Signing {} ...", path.display());
+
+        self.compute_checksum_of(path, &mut content)?;
+        self.create_signature()
+    }
+
+    /// Adds a signature to an already signed manifest, the files must not have changed since.
+    pub(crate) fn countersign(&mut self, paths: &mut [PathBuf]) -> anyhow::Result<&str> {
+        paths.sort();
+
+        let signed = std::mem::take(&mut self.checksums);
+        let signed_sizes = std::mem::take(&mut self.sizes);
+        self.compute_checksums(paths, "Hashing")?;
+        self.check_unchanged(signed, signed_sizes)?;
+
+        self.create_signature()
+    }
+
+    /// Adds a signature to the manifest embedded in a file.
+    pub(crate) fn countersign_embedded(
+        &mut self,
+        path: &Path,
+        mut content: impl Read,
+    ) -> anyhow::Result<&str> {
+        tracing::info!("Hashing {} ...", path.display());
+
+        let signed = std::mem::take(&mut self.checksums);
+        let signed_sizes = std::mem::take(&mut self.sizes);
+        self.compute_checksum_of(path, &mut content)?;
+        self.check_unchanged(signed, signed_sizes)?;
+
+        self.create_signature()
+    }
+
+    pub(crate) fn verify(&mut self, paths: &mut [PathBuf], signature: &Self) -> anyhow::Result<()> {
+        paths.sort();
+
+        // hash with the same chunk size used for signing
+        self.algorithms.chunk_size = signature.algorithms.chunk_size;
+
+        // compute checksums for all files
+        self.compute_checksums(paths, "Hashing")?;
+
+        self.verify_against(signature)
+    }
+
+    /// Verifies the content of a file against the signature embedded in it.
+    pub(crate) fn verify_embedded(
+        &mut self,
+        path: &Path,
+        mut content: impl Read,
+        signature: &Self,
+    ) -> anyhow::Result<()> {
+        if signature.algorithms.chunk_size.is_some() {
+            anyhow::bail!("embedded signatures can't be computed in chunks");
+        }
+
+        tracing::info!("Hashing {} ...", path.display());
+
+        self.compute_checksum_of(path, &mut content)?;
+        self.verify_against(signature)
+    }
+
+    // compares the computed checksums with the signed ones and verifies the signatures
+    fn verify_against(&mut self, signature: &Self) -> anyhow::Result<()> {
+        // check public key fingerprints
+        if self.matching_signers(signature).is_empty() {
+            if let Some((signer, revocation)) = self.revoked_signers(signature).first() {
+                return Err(Failure::KeyMismatch.error(format!(
+                    "key {} has been revoked at {}: {}",
+                    &signer.public_key[..16],
+                    revocation.revoked_at,
+                    revocation.reason
+                )));
+            }
+            return Err(Failure::KeyMismatch.error("public key fingerprint mismatch"));
+        }
+        // verify individual checksums
+        if matches!(signature.version, Version::V3) {
+            self.verify_files(signature)?;
+        }
+        self.verify_checksums(&signature.checksums)?;
+        // files are identical, and so are their chunks
+        self.chunk_checksums = signature.chunk_checksums.clone();
+        // files are identical, and so are their tensors, use the signed tensor checksums
+        self.tensor_checksums = signature.tensor_checksums.clone();
+        self.member_checksums = signature.member_checksums.clone();
+
+        // verify signatures, 3.0 ones cover the signed manifest, which matches the files, older
+        // ones the checksums we computed
+        let mut valid = 0;
+        let mut last_error = None;
+        for (signer, key) in self.matching_signers(signature) {
+            let data = match signature.version {
+                Version::V3 => signature.signed_data(signer)?,
+                _ => signer.legacy_signed_data(&self.data_to_sign()),
+            };
+            match Self::verify_signer(key, signer, &data) {
+                Ok(()) => valid += 1,
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        if valid < self.required_signers {
+            return Err(match last_error {
+                Some(e) if self.required_signers == 1 => e,
+                _ => Failure::InvalidSignature.error(format!(
+                    "{} valid signatures, at least {} required",
+                    valid, self.required_signers
+                )),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Signs the SHA-256 of the files in a DSSE envelope, verifiable by cosign.
+    pub(crate) fn sign_envelope(&self, paths: &[PathBuf]) -> anyhow::Result<Envelope> {
+        let signing_key = self
+            .signing_key
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no signing key"))?;
+        for path in paths {
+            tracing::info!("Signing {} ...", path.display());
+        }
+
+        Envelope::sign(&Statement::new(&self.base_path, paths)?, signing_key)
+    }
+
+    /// Adds a signature to a DSSE envelope, the files must not have changed since.
+    pub(crate) fn countersign_envelope(
+        &self,
+        paths: &[PathBuf],
+        envelope: &mut Envelope,
+    ) -> anyhow::Result<()> {
+        let signing_key = self
+            .signing_key
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no signing key"))?;
+        for path in paths {
+            tracing::info!("Hashing {} ...", path.display());
+        }
+
+        envelope
+            .statement()?
+            .check_subjects(&self.base_path, paths)
+            .map_err(|e| anyhow::anyhow!("files changed since they have been signed: {}", e))?;
+        envelope.countersign(signing_key)
+    }
+
+    /// Verifies the files against a DSSE envelope, returns the keys with a valid signature.
+    pub(crate) fn verify_envelope(
+        &self,
+        paths: &[PathBuf],
+        envelope: &Envelope,
+    ) -> anyhow::Result<Vec<&VerifyingKey>> {
+        let statement = envelope.statement()?;
+        let signed_by: Vec<&VerifyingKey> = envelope
+            .signed_by(&self.verifying_keys)
+            .into_iter()
+            .filter(|key| {
+                let fingerprint = fingerprint(key.as_bytes());
+                match self.revocations.as_ref().and_then(|r| r.find(&fingerprint)) {
+                    Some(revocation) => {
+                        tracing::warn!(
+                            "Refusing signature by {}, revoked at {}: {}",
+                            &fingerprint[..16],
+                            revocation.revoked_at,
+                            revocation.reason
+                        );
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect();
+
+        if signed_by.len() < self.required_signers {
+            return Err(Failure::InvalidSignature.error(format!(
+                "{} valid signatures, at least {} required",
+                signed_by.len(),
+                self.required_signers
+            )));
+        }
+
+        for path in paths {
+            tracing::info!("Hashing {} ...", path.display());
+        }
+        statement.check_subjects(&self.base_path, paths)?;
+
+        Ok(signed_by)
+    }
+
+    /// Obtains from the TSA a trusted timestamp of the last signature.
+    pub(crate) fn add_timestamp(&mut self, tsa_url: &str) -> anyhow::Result<()> {
+        let signer = self
+            .signatures
+            .last_mut()
+            .ok_or_else(|| anyhow::anyhow!("the manifest has not been signed"))?;
+        let token = timestamp::request_timestamp(tsa_url, &hex::decode(&signer.signature)?)?;
+        signer.timestamp = Some(STANDARD.encode(token));
+        Ok(())
+    }
+
+    /// Verifies a byte range of a file signed in chunks, only the chunks overlapping the range are read.
+    pub(crate) fn verify_range(
+        &self,
+        path: &Path,
+        range: Range<u64>,
+        signature: &Self,
+    ) -> anyhow::Result<()> {
+        let Some(chunk_size) = signature.algorithms.chunk_size else {
+            anyhow::bail!("the file has not been signed in chunks");
+        };
+        if self.matching_signers(signature).is_empty() {
+            return Err(Failure::KeyMismatch.error("public key fingerprint mismatch"));
+        }
+        if !self.is_authentic(signature) {
+            return Err(Failure::InvalidSignature.error("signature verification failed"));
+        }
+
+        let relative_path = self.relative_path(path)?;
+        let (Some(checksum), Some(chunks)) = (
+            signature.checksums.get(&relative_path),
+            signature.chunk_checksums.get(&relative_path),
+        ) else {
+            return Err(
+                Failure::MissingFile.error(format!("missing checksum for {}", relative_path))
+            );
+        };
+
+        // make sure the chunk checksums are the ones the signed root was computed from
+        let leaves = chunks
+            .iter()
+            .map(hex::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+        if &hex::encode(merkle_root(&leaves)) != checksum {
+            return Err(Failure::InvalidSignature
+                .error(format!("invalid chunk checksums for {}", relative_path)));
+        }
+
+        let mut file = std::fs::File::open(path)?;
+        let file_size = file.metadata()?.len();
+        if signature
+            .sizes
+            .get(&relative_path)
+            .is_some_and(|size| *size != file_size)
+        {
+            return Err(Failure::ModifiedFile.error(format!("invalid size for {}", relative_path)));
+        }
+        if range.start >= range.end || range.end > file_size {
+            anyhow::bail!(
+                "invalid range {}..{} for a file of {} bytes",
+                range.start,
+                range.end,
+                file_size
+            );
+        }
+
+        let first = range.start / chunk_size;
+        let last = (range.end - 1) / chunk_size;
+        for index in first..=last {
+            let offset = index * chunk_size;
+            let mut chunk = vec![0u8; chunk_size.min(file_size - offset) as usize];
+            file.seek(std::io::SeekFrom::Start(offset))?;
+            file.read_exact(&mut chunk)?;
+
+            if chunks.get(index as usize) != Some(&hex::encode(merkle_leaf(&chunk))) {
+                return Err(Failure::ModifiedFile.error(format!(
+                    "invalid checksum for chunk {} (bytes {}..{}) of {}",
+                    index,
+                    offset,
+                    offset + chunk.len() as u64,
+                    relative_path
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn create_test_keypair() -> SigningKey {
+        SigningKey::generate(SigningAlgorithm::Ed25519).unwrap().0
+    }
+
+    fn create_temp_file_with_content(content: &str) -> anyhow::Result<NamedTempFile> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(content.as_bytes())?;
+        temp_file.flush()?;
+        Ok(temp_file)
+    }
+
+    #[test]
+    fn test_will_create_a_signature() {
+        let keypair = create_test_keypair();
+
+        let temp_file = create_temp_file_with_content("test").unwrap();
+
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        manifest.compute_checksum(temp_file.path()).unwrap();
+        let signature = manifest.create_signature().unwrap();
+
+        assert!(!signature.is_empty());
+
+        assert!(matches!(manifest.version, Version::V3));
+        assert_eq!(manifest.signatures.len(), 1);
+        let signer = &manifest.signatures[0];
+        assert!(!signer.signed_at.is_empty());
+        assert!(!signer.signed_with.is_empty());
+        assert!(!signer.public_key.is_empty());
+        assert!(matches!(
+            manifest.algorithms.hash,
+            HashAlgorithm::BLAKE2b512
+        ));
+        assert!(matches!(signer.algorithm, SigningAlgorithm::Ed25519));
+
+        assert_eq!(manifest.checksums.len(), 1);
+        assert_eq!(manifest.checksums.values().next().unwrap(), "a71079d42853dea26e453004338670a53814b78137ffbed07603a41d76a483aa9bc33b582f77d30a65e6f29a896c0411f38312e1d66e0bf16386c86a89bea572");
+    }
+
+    #[test]
+    fn test_will_verify_correct_signature() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+    }
+
+    #[test]
+    fn test_wont_verify_with_wrong_key() {
+        let keypair = create_test_keypair();
+        let other_keypair = create_test_keypair();
+        let pub_key = other_keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        ref_manifest.compute_checksum(temp_file.path()).unwrap();
+        ref_manifest.create_signature().unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+
+        manifest.compute_checksum(temp_file.path()).unwrap();
+
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_wont_verify_a_tampered_file() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        ref_manifest.compute_checksum(temp_file.path()).unwrap();
+        ref_manifest.create_signature().unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+
+        let temp_file = create_temp_file_with_content("tost").unwrap();
+
+        manifest.compute_checksum(temp_file.path()).unwrap();
+
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_wont_verify_empty_file() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        ref_manifest.compute_checksum(temp_file.path()).unwrap();
+        ref_manifest.create_signature().unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+
+        let empty_file = create_temp_file_with_content("").unwrap();
+        manifest.compute_checksum(empty_file.path()).unwrap();
+
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_wont_verify_extra_file() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        ref_manifest.compute_checksum(temp_file.path()).unwrap();
+        ref_manifest.create_signature().unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+
+        // Compute checksum for original file
+        manifest.compute_checksum(temp_file.path()).unwrap();
+
+        // Add checksum for an extra file
+        let extra_file = create_temp_file_with_content("extra").unwrap();
+        manifest.compute_checksum(extra_file.path()).unwrap();
+
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_wont_verify_without_signature() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        ref_manifest.compute_checksum(temp_file.path()).unwrap();
+        // Deliberately skip creating signature
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        manifest.compute_checksum(temp_file.path()).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_inner_folder_name_preserved() {
+        let keypair = create_test_keypair();
+
+        // Create a temporary directory with a nested file structure
+        let temp_dir = tempfile::tempdir().unwrap();
+        let inner_dir = temp_dir.path().join("inner");
+        std::fs::create_dir(&inner_dir).unwrap();
+
+        let test_file = inner_dir.join("test.txt");
+        std::fs::write(&test_file, "test content").unwrap();
+
+        let base_path = temp_dir.path();
+
+        let mut manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+
+        manifest.compute_checksum(&test_file).unwrap();
+
+        // Verify the checksum key preserves the inner folder name
+        assert!(manifest.checksums.contains_key("inner/test.txt"));
+    }
+
+    fn create_test_tensors(second: &[u8]) -> Vec<TensorData> {
+        use crate::core::data::{ElementType, Encoding};
+
+        vec![
+            TensorData::owned(
+                "a".to_string(),
+                Encoding::Plain(ElementType::U8),
+                vec![1, 2, 3],
+            ),
+            TensorData::owned(
+                "b".to_string(),
+                Encoding::Plain(ElementType::U8),
+                second.to_vec(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_tensor_checksums_are_signed() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        ref_manifest
+            .add_tensor_checksums(temp_file.path(), &create_test_tensors(&[4, 5]))
+            .unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key.clone()).unwrap();
+        assert!(manifest.is_authentic(&ref_manifest));
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+
+        // tampering with the tensor checksums invalidates the signature
+        let (_, tensors) = ref_manifest.tensor_checksums.iter_mut().next().unwrap();
+        tensors.insert("b".to_string(), "00".to_string());
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        assert!(!manifest.is_authentic(&ref_manifest));
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_tampered_tensors() {
+        let keypair = create_test_keypair();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        manifest
+            .add_tensor_checksums(temp_file.path(), &create_test_tensors(&[4, 5]))
+            .unwrap();
+
+        assert!(manifest
+            .tampered_tensors(temp_file.path(), &create_test_tensors(&[4, 5]))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            manifest
+                .tampered_tensors(temp_file.path(), &create_test_tensors(&[4, 6]))
+                .unwrap(),
+            vec!["b"]
+        );
+    }
+
+    #[test]
+    fn test_member_checksums() {
+        use std::io::Write;
+
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        let write_zip = |members: &[(&str, &[u8])]| {
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            for (name, content) in members {
+                zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(content).unwrap();
+            }
+            zip.finish().unwrap();
+        };
+        write_zip(&[("a", b"a"), ("b", b"b")]);
+
+        let mut ref_manifest = Manifest::from_signing_key(dir.path(), keypair).unwrap();
+        ref_manifest.add_member_checksums(&path).unwrap();
+        let mut paths = vec![path.clone()];
+        _ = ref_manifest.sign(&mut paths).unwrap();
+        assert_eq!(ref_manifest.member_checksums["bundle.zip"].len(), 2);
+
+        let mut manifest = Manifest::from_public_key(dir.path(), pub_key).unwrap();
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+        assert!(ref_manifest.tampered_members(&path).unwrap().is_empty());
+
+        write_zip(&[("a", b"a"), ("b", b"modified"), ("c", b"c")]);
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+        assert_eq!(
+            ref_manifest.tampered_members(&path).unwrap(),
+            vec!["b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_file_report() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in [("a", "a"), ("b", "b"), ("c", "c")] {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+
+        let mut ref_manifest = Manifest::from_signing_key(dir.path(), keypair).unwrap();
+        ref_manifest
+            .sign(&mut ["a", "b", "c"].map(|name| dir.path().join(name)))
+            .unwrap();
+
+        std::fs::write(dir.path().join("b"), "modified").unwrap();
+        std::fs::remove_file(dir.path().join("c")).unwrap();
+        std::fs::write(dir.path().join("d"), "d").unwrap();
+
+        let mut manifest = Manifest::from_public_key(dir.path(), pub_key).unwrap();
+        assert!(manifest
+            .verify(
+                &mut ["a", "b", "d"].map(|name| dir.path().join(name)),
+                &ref_manifest
+            )
+            .is_err());
+        assert_eq!(
+            manifest.file_report(&ref_manifest),
+            vec![
+                ("a".to_string(), FileStatus::Ok),
+                ("b".to_string(), FileStatus::Modified),
+                ("c".to_string(), FileStatus::Missing),
+                ("d".to_string(), FileStatus::Extra),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_embedded_signature() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test with signature").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        // only the content without the signature is hashed
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        ref_manifest
+            .sign_embedded(temp_file.path(), "test".as_bytes())
+            .unwrap();
+        assert_eq!(ref_manifest.checksums.values().next().unwrap(), "a71079d42853dea26e453004338670a53814b78137ffbed07603a41d76a483aa9bc33b582f77d30a65e6f29a896c0411f38312e1d66e0bf16386c86a89bea572");
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key.clone()).unwrap();
+        manifest
+            .verify_embedded(temp_file.path(), "test".as_bytes(), &ref_manifest)
+            .unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        assert!(manifest
+            .verify_embedded(temp_file.path(), "tost".as_bytes(), &ref_manifest)
+            .is_err());
+    }
+
+    #[test]
+    fn test_will_verify_v1_manifest() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        // 1.0 manifests only sign the checksums
+        ref_manifest.version = Version::V2;
+        let mut paths = vec![temp_file.path().to_path_buf()];
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        // same manifest in the 1.0 format, with the signer fields at the top level
+        let signer = &ref_manifest.signatures[0];
+        let v1 = serde_json::json!({
+            "version": "1.0",
+            "signed_at": signer.signed_at,
+            "signed_with": signer.signed_with,
+            "public_key": signer.public_key,
+            "algorithms": { "hash": "BLAKE2b512", "signature": "Ed25519" },
+            "checksums": ref_manifest.checksums,
+            "signature": signer.signature,
+        });
+        let v1 = Manifest::from_signature_str(base_path, &v1.to_string()).unwrap();
+        assert_eq!(v1.signatures.len(), 1);
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        manifest.verify(&mut paths, &v1).unwrap();
+    }
+
+    #[test]
+    fn test_multiple_signatures() {
+        let keys: Vec<SigningKey> = (0..3).map(|_| create_test_keypair()).collect();
+        let public_keys: Vec<Vec<u8>> = keys.iter().map(|k| k.public_key().to_vec()).collect();
+        let mut keys = keys.into_iter();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keys.next().unwrap()).unwrap();
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        // countersign the serialized manifest
+        let mut ref_manifest =
+            Manifest::from_signature_str(base_path, &serde_json::to_string(&ref_manifest).unwrap())
+                .unwrap();
+        ref_manifest.set_signing_key(keys.next().unwrap());
+        _ = ref_manifest.countersign(&mut paths).unwrap();
+        assert_eq!(ref_manifest.signatures.len(), 2);
+        // the same key can't sign twice
+        assert!(ref_manifest.create_signature().is_err());
+
+        // both signatures are required
+        let mut manifest =
+            Manifest::from_public_keys(base_path, public_keys[..2].to_vec()).unwrap();
+        manifest.set_required_signers(2).unwrap();
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+        assert_eq!(manifest.trusted_signers(&ref_manifest).len(), 2);
+
+        // the third key didn't sign
+        let mut manifest = Manifest::from_public_keys(
+            base_path,
+            vec![public_keys[0].clone(), public_keys[2].clone()],
+        )
+        .unwrap();
+        manifest.set_required_signers(2).unwrap();
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+        assert!(manifest.set_required_signers(3).is_err());
+
+        // can't countersign modified files
+        let mut ref_manifest =
+            Manifest::from_signature_str(base_path, &serde_json::to_string(&ref_manifest).unwrap())
+                .unwrap();
+        ref_manifest.set_signing_key(keys.next().unwrap());
+        std::fs::write(temp_file.path(), "tost").unwrap();
+        assert!(ref_manifest.countersign(&mut paths).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90d").unwrap(), chrono::Duration::days(90));
+        assert_eq!(parse_duration(" 12h").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_duration("4w").unwrap(), chrono::Duration::weeks(4));
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("1y").is_err());
+    }
+
+    #[test]
+    fn test_threshold_policy() {
+        let keys: Vec<SigningKey> = (0..3).map(|_| create_test_keypair()).collect();
+        let folder = tempfile::tempdir().unwrap();
+        for (i, key) in keys.iter().enumerate() {
+            std::fs::write(folder.path().join(format!("{}.pub", i)), key.public_key()).unwrap();
+        }
+        let policy_path = folder.path().join("policy.json");
+        std::fs::write(
+            &policy_path,
+            r#"{"threshold": 2, "trusted_keys": ["0.pub", "1.pub", "2.pub"]}"#,
+        )
+        .unwrap();
+        let policy = Policy::from_path(&policy_path).unwrap();
+
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+        let mut paths = vec![temp_file.path().to_path_buf()];
+        let mut keys = keys.into_iter();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keys.next().unwrap()).unwrap();
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        // a single approver is not enough, even if its signature is repeated
+        let mut manifest = Manifest::from_policy(base_path, &policy).unwrap();
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+        let repeated = ref_manifest.signatures[0].clone();
+        let mut forged =
+            Manifest::from_signature_str(base_path, &serde_json::to_string(&ref_manifest).unwrap())
+                .unwrap();
+        forged.signatures.push(repeated);
+        assert!(manifest.verify(&mut paths, &forged).is_err());
+
+        // two out of three
+        let mut ref_manifest =
+            Manifest::from_signature_str(base_path, &serde_json::to_string(&ref_manifest).unwrap())
+                .unwrap();
+        ref_manifest.set_signing_key(keys.nth(1).unwrap());
+        _ = ref_manifest.countersign(&mut paths).unwrap();
+        let mut manifest = Manifest::from_policy(base_path, &policy).unwrap();
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+
+        // the same key can't be trusted twice
+        let key = std::fs::read(folder.path().join("0.pub")).unwrap();
+        assert!(Manifest::from_public_keys(base_path, vec![key.clone(), key]).is_err());
+    }
+
+    #[test]
+    fn test_revoked_signer() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        let mut revocations = RevocationList::default();
+        revocations
+            .revoke(
+                fingerprint(&pub_key),
+                "2026-01-01T00:00:00Z".to_string(),
+                "key compromised".to_string(),
+            )
+            .unwrap();
+        revocations.sign(&keypair).unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        let issuer = create_test_keypair().public_key().to_vec();
+        assert!(manifest
+            .set_revocations(RevocationList::default(), Some(issuer))
+            .is_err());
+        manifest.set_revocations(revocations, None).unwrap();
+        assert_eq!(manifest.revoked_signers(&ref_manifest).len(), 1);
+
+        let err = manifest.verify(&mut paths, &ref_manifest).unwrap_err();
+        assert!(err.to_string().contains("key compromised"));
+    }
+
+    #[test]
+    fn test_signature_expiration() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        ref_manifest.set_expiration(chrono::Utc::now() - chrono::Duration::days(1));
+        _ = ref_manifest.sign(&mut paths).unwrap();
+        let signer = ref_manifest.signatures[0].clone();
+        assert!(signer.expiration().unwrap().is_some());
+        assert!(ref_manifest
+            .authenticated_date(&signer, None)
+            .unwrap()
+            .is_some());
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        let err = manifest.verify(&mut paths, &ref_manifest).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        // the expiration is signed
+        ref_manifest.signatures[0].expires_at =
+            Some((chrono::Utc::now() + chrono::Duration::days(1)).to_rfc3339());
+        let err = manifest.verify(&mut paths, &ref_manifest).unwrap_err();
+        assert!(err.to_string().contains("verification failed"));
+    }
+
+    #[test]
+    fn test_authenticated_date() {
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+        let mut paths = vec![temp_file.path().to_path_buf()];
+
+        // the signing date is part of the signed content of 3.0 manifests
+        let mut ref_manifest =
+            Manifest::from_signing_key(base_path, create_test_keypair()).unwrap();
+        _ = ref_manifest.sign(&mut paths).unwrap();
+        let signer = ref_manifest.signatures[0].clone();
+        assert!(signer.expires_at.is_none());
+        assert_eq!(
+            ref_manifest
+                .authenticated_date(&signer, None)
+                .unwrap()
+                .map(|date| date.to_rfc3339()),
+            Some(signer.signed_at.clone())
+        );
+
+        // but not of older ones without an expiration
+        let mut legacy = Manifest::from_signing_key(base_path, create_test_keypair()).unwrap();
+        legacy.version = Version::V2;
+        _ = legacy.sign(&mut paths).unwrap();
+        let signer = legacy.signatures[0].clone();
+        assert!(legacy.authenticated_date(&signer, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_canonical_manifest_signature() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let folder = tempfile::tempdir().unwrap();
+        let base_path = folder.path();
+        std::fs::write(base_path.join("a.bin"), "aaaa").unwrap();
+        std::fs::write(base_path.join("b.bin"), "bbbb").unwrap();
+        let mut paths = vec![base_path.join("a.bin"), base_path.join("b.bin")];
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        _ = ref_manifest.sign(&mut paths).unwrap();
+        assert!(matches!(ref_manifest.version, Version::V3));
+        assert_eq!(ref_manifest.sizes.get("a.bin"), Some(&4));
+
+        let json = serde_json::to_string(&ref_manifest).unwrap();
+        let verify = |json: &str| {
+            let signature = Manifest::from_signature_str(base_path, json).unwrap();
+            let mut manifest = Manifest::from_public_key(base_path, pub_key.clone()).unwrap();
+            manifest.verify(&mut paths.clone(), &signature)
+        };
+        verify(&json).unwrap();
+
+        // the signer metadata is signed
+        assert!(verify(&json.replace("\"signed_at\":\"2", "\"signed_at\":\"1")).is_err());
+        // and so are the paths, files can't be swapped
+        std::fs::rename(base_path.join("a.bin"), base_path.join("c.bin")).unwrap();
+        std::fs::rename(base_path.join("b.bin"), base_path.join("a.bin")).unwrap();
+        std::fs::rename(base_path.join("c.bin"), base_path.join("b.bin")).unwrap();
+        assert!(verify(&json).is_err());
+        // while the legacy format only signs the checksum values
+        let mut legacy = Manifest::from_signing_key(base_path, create_test_keypair()).unwrap();
+        legacy.version = Version::V2;
+        _ = legacy.sign(&mut paths.clone()).unwrap();
+        std::fs::rename(base_path.join("a.bin"), base_path.join("c.bin")).unwrap();
+        std::fs::rename(base_path.join("b.bin"), base_path.join("a.bin")).unwrap();
+        std::fs::rename(base_path.join("c.bin"), base_path.join("b.bin")).unwrap();
+        let mut manifest = Manifest::from_public_key(
+            base_path,
+            legacy.signing_key.as_ref().unwrap().public_key().to_vec(),
+        )
+        .unwrap();
+        manifest.verify(&mut paths.clone(), &legacy).unwrap();
+
+        // a single file can be renamed
+        let single = base_path.join("model.bin");
+        std::fs::write(&single, "test").unwrap();
+        let mut ref_manifest =
+            Manifest::from_signing_key(base_path, create_test_keypair()).unwrap();
+        let pub_key = ref_manifest
+            .signing_key
+            .as_ref()
+            .unwrap()
+            .public_key()
+            .to_vec();
+        _ = ref_manifest.sign(&mut [single.clone()]).unwrap();
+        let renamed = base_path.join("renamed.bin");
+        std::fs::rename(&single, &renamed).unwrap();
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        manifest.verify(&mut [renamed], &ref_manifest).unwrap();
+    }
+
+    #[test]
+    fn test_included_public_key() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        ref_manifest.set_include_public_key(true);
+        _ = ref_manifest
+            .sign(&mut [temp_file.path().to_path_buf()])
+            .unwrap();
+        let json = serde_json::to_string(&ref_manifest).unwrap();
+        let signature = Manifest::from_signature_str(base_path, &json).unwrap();
+        assert_eq!(
+            signature.signatures[0].included_public_key().unwrap(),
+            Some(pub_key.clone())
+        );
+
+        // the included key must match the fingerprint
+        let other = hex::encode(create_test_keypair().public_key());
+        let json = json.replace(&hex::encode(&pub_key), &other);
+        assert!(Manifest::from_signature_str(base_path, &json).is_err());
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let leaves: Vec<Vec<u8>> = (0..3u8).map(|i| merkle_leaf(&[i])).collect();
+
+        assert_eq!(merkle_root(&leaves[..1]), leaves[0]);
+        assert_ne!(merkle_root(&leaves[..2]), merkle_root(&leaves[1..3]));
+        assert_eq!(merkle_root(&[]), merkle_leaf(&[]));
+    }
+
+    #[test]
+    fn test_will_verify_chunked_signature() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("0123456789").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        assert!(ref_manifest.set_chunk_size(0).is_err());
+        ref_manifest.set_chunk_size(4).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        // 4 + 4 + 2 bytes
+        assert_eq!(
+            ref_manifest.chunk_checksums.values().next().unwrap().len(),
+            3
+        );
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+
+        manifest
+            .verify_range(temp_file.path(), 5..10, &ref_manifest)
+            .unwrap();
+        assert!(manifest
+            .verify_range(temp_file.path(), 5..11, &ref_manifest)
+            .is_err());
+    }
+
+    #[test]
+    fn test_wont_verify_a_tampered_chunk() {
+        let keypair = create_test_keypair();
+        let pub_key = keypair.public_key().to_vec();
+        let mut temp_file = create_temp_file_with_content("0123456789").unwrap();
+        let base_path = temp_file.path().parent().unwrap().to_path_buf();
+
+        let mut ref_manifest = Manifest::from_signing_key(&base_path, keypair).unwrap();
+        ref_manifest.set_chunk_size(4).unwrap();
+
+        let mut paths = vec![temp_file.path().to_path_buf()];
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        // tamper with the last chunk
+        temp_file.seek(std::io::SeekFrom::Start(9)).unwrap();
+        temp_file.write_all(b"X").unwrap();
+
+        let mut manifest = Manifest::from_public_key(&base_path, pub_key).unwrap();
+        assert!(manifest
+            .verify_range(temp_file.path(), 0..8, &ref_manifest)
+            .is_ok());
+        assert!(manifest
+            .verify_range(temp_file.path(), 7..9, &ref_manifest)
+            .is_err());
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+
+    #[test]
+    fn test_will_verify_ecdsa_p256_signature() {
+        let keypair = SigningKey::generate(SigningAlgorithm::EcdsaP256).unwrap().0;
+        let pub_key = keypair.public_key().to_vec();
+        let temp_file = create_temp_file_with_content("test").unwrap();
+        let base_path = temp_file.path().parent().unwrap();
+
+        let mut ref_manifest = Manifest::from_signing_key(base_path, keypair).unwrap();
+        let mut paths = vec![temp_file.path().to_path_buf()];
+        _ = ref_manifest.sign(&mut paths).unwrap();
+
+        assert_eq!(
+            ref_manifest.signatures[0].algorithm,
+            SigningAlgorithm::EcdsaP256
+        );
+
+        let mut manifest = Manifest::from_public_key(base_path, pub_key).unwrap();
+        manifest.verify(&mut paths, &ref_manifest).unwrap();
+
+        // an Ed25519 key can't verify it
+        let other_key = create_test_keypair().public_key().to_vec();
+        let mut manifest = Manifest::from_public_key(base_path, other_key).unwrap();
+        assert!(manifest.verify(&mut paths, &ref_manifest).is_err());
+    }
+}
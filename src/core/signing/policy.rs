@@ -0,0 +1,314 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use super::{parse_duration, SigningAlgorithm, Verdict};
+use crate::core::{
+    handlers::{self, onnx, Scope},
+    DetailLevel,
+};
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<chrono::Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|duration| parse_duration(&duration).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Verification policy requiring valid signatures from at least `threshold` of the trusted keys,
+/// and optionally restricting the algorithms, the age of the signatures and the signed files.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Policy {
+    // minimum number of trusted keys that must have signed the manifest
+    pub threshold: usize,
+    // public key files, relative to the policy file folder unless absolute
+    pub trusted_keys: Vec<PathBuf>,
+    // algorithms the trusted signatures can be made with
+    #[serde(default)]
+    pub allowed_signing_algorithms: Option<Vec<SigningAlgorithm>>,
+    // algorithms the files can be hashed with, BLAKE2b512 for manifests and SHA256 for DSSE envelopes
+    #[serde(default)]
+    pub allowed_hash_algorithms: Option<Vec<String>>,
+    // maximum size of each file, in bytes
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    // extensions of the files that can be signed
+    #[serde(default)]
+    pub allowed_file_types: Option<Vec<String>>,
+    // metadata keys every safetensors, GGUF and ONNX model must have
+    #[serde(default)]
+    pub required_metadata_keys: Vec<String>,
+    // operators ONNX models must not use
+    #[serde(default)]
+    pub forbidden_onnx_ops: Vec<String>,
+    // maximum age of the trusted signatures, such as 90d, their date must be authenticated
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub max_signature_age: Option<chrono::Duration>,
+}
+
+impl Policy {
+    /// Parses a YAML policy, or a JSON one since JSON is valid YAML.
+    pub(crate) fn from_str(data: &str, base_path: &Path) -> anyhow::Result<Self> {
+        let mut policy: Self = serde_yaml_ng::from_str(data)?;
+        if policy.trusted_keys.is_empty() {
+            anyhow::bail!("the policy has no trusted keys");
+        }
+        if policy.threshold == 0 || policy.threshold > policy.trusted_keys.len() {
+            anyhow::bail!(
+                "invalid policy threshold {} for {} trusted keys",
+                policy.threshold,
+                policy.trusted_keys.len()
+            );
+        }
+
+        for key_path in policy.trusted_keys.iter_mut() {
+            if key_path.is_relative() {
+                *key_path = base_path.join(&key_path);
+            }
+        }
+
+        Ok(policy)
+    }
+
+    pub(crate) fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("can't read policy {}: {}", path.display(), e))?;
+        Self::from_str(&data, path.parent().unwrap_or(Path::new("")))
+    }
+
+    // violations of the trusted signatures of the verdict
+    fn check_signers(&self, verdict: &Verdict, violations: &mut Vec<String>) {
+        for signer in verdict.signers.iter().filter(|signer| signer.trusted) {
+            let fingerprint = &signer.fingerprint[..16];
+            if let Some(allowed) = &self.allowed_signing_algorithms {
+                if !allowed.contains(&signer.algorithm) {
+                    violations.push(format!(
+                        "signature by {} made with {:?}, which is not allowed",
+                        fingerprint, signer.algorithm
+                    ));
+                }
+            }
+
+            let Some(max_age) = self.max_signature_age else {
+                continue;
+            };
+            // the unsigned signing date of older manifests could have been changed
+            match signer
+                .authenticated_at
+                .as_ref()
+                .and_then(|date| chrono::DateTime::parse_from_rfc3339(date).ok())
+            {
+                Some(signed_at) if chrono::Utc::now() - signed_at.to_utc() > max_age => violations
+                    .push(format!(
+                        "signature by {} made at {} is older than {} days",
+                        fingerprint,
+                        signed_at.to_rfc3339(),
+                        max_age.num_days()
+                    )),
+                Some(_) => {}
+                None => violations.push(format!(
+                    "the signing date of {} can't be trusted, sign with --expires-in or --tsa-url",
+                    fingerprint
+                )),
+            }
+        }
+    }
+
+    // violations of a signed file
+    fn check_file(
+        &self,
+        path: &Path,
+        name: &str,
+        violations: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        if let Some(allowed) = &self.allowed_file_types {
+            let extension = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if !allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&extension))
+            {
+                violations.push(format!("{} is not of an allowed file type", name));
+            }
+        }
+        if let Some(max_file_size) = self.max_file_size {
+            let size = std::fs::metadata(path)?.len();
+            if size > max_file_size {
+                violations.push(format!(
+                    "{} is {} bytes, more than the {} allowed",
+                    name, size, max_file_size
+                ));
+            }
+        }
+
+        let Ok(handler) = handlers::handler_for(None, path, Scope::Inspection) else {
+            return Ok(());
+        };
+        let file_type = handler.file_type();
+        if !(file_type.is_safetensors() || file_type.is_gguf() || file_type.is_onnx()) {
+            return Ok(());
+        }
+
+        if !self.required_metadata_keys.is_empty() {
+            let inspection = handler.inspect(path, DetailLevel::Brief, None)?;
+            for key in &self.required_metadata_keys {
+                if !inspection.metadata.contains_key(key) {
+                    violations.push(format!("{} has no {} metadata", name, key));
+                }
+            }
+        }
+        if file_type.is_onnx() && !self.forbidden_onnx_ops.is_empty() {
+            let operators = onnx::operator_types(path)?;
+            for op in &self.forbidden_onnx_ops {
+                if operators.contains(op) {
+                    violations.push(format!("{} uses the forbidden {} operator", name, op));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates the policy once the signatures have been verified, on the signers and the
+    /// files of the verdict, relative to the base path, returning the requirements not met.
+    pub(crate) fn evaluate(
+        &self,
+        verdict: &Verdict,
+        base_path: &Path,
+        hash_algorithm: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut violations = vec![];
+
+        if let Some(allowed) = &self.allowed_hash_algorithms {
+            if !allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(hash_algorithm))
+            {
+                violations.push(format!(
+                    "files hashed with {}, which is not allowed",
+                    hash_algorithm
+                ));
+            }
+        }
+        self.check_signers(verdict, &mut violations);
+        for file in &verdict.files {
+            self.check_file(&base_path.join(&file.path), &file.path, &mut violations)?;
+        }
+
+        Ok(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::signing::{FileStatus, FileVerdict, SignerVerdict};
+
+    #[test]
+    fn test_policy() {
+        let policy = Policy::from_str(
+            r#"{"threshold": 2, "trusted_keys": ["a.pub", "/keys/b.pub", "c.pub"]}"#,
+            Path::new("/policies"),
+        )
+        .unwrap();
+        assert_eq!(policy.threshold, 2);
+        assert_eq!(
+            policy.trusted_keys,
+            vec![
+                PathBuf::from("/policies/a.pub"),
+                PathBuf::from("/keys/b.pub"),
+                PathBuf::from("/policies/c.pub")
+            ]
+        );
+
+        for invalid in [
+            r#"{"threshold": 0, "trusted_keys": ["a.pub"]}"#,
+            r#"{"threshold": 2, "trusted_keys": ["a.pub"]}"#,
+            r#"{"threshold": 1, "trusted_keys": []}"#,
+            r#"{"trusted_keys": ["a.pub"]}"#,
+            r#"{"threshold": 1, "trusted_keys": ["a.pub"], "max_signature_age": "1y"}"#,
+            r#"{"threshold": 1, "trusted_keys": ["a.pub"], "forbidden_ops": ["Loop"]}"#,
+        ] {
+            assert!(Policy::from_str(invalid, Path::new("")).is_err());
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let policy = Policy::from_str(
+            "threshold: 1
+trusted_keys: [a.pub]
+allowed_signing_algorithms: [Ed25519]
+allowed_hash_algorithms: [blake2b512]
+max_file_size: 16
+allowed_file_types: [safetensors, json]
+required_metadata_keys: [license]
+max_signature_age: 30d
+",
+            Path::new(""),
+        )
+        .unwrap();
+
+        let folder = tempfile::tempdir().unwrap();
+        let header = br#"{"__metadata__":{"author":"me"}}  "#;
+        let mut model = (header.len() as u64).to_le_bytes().to_vec();
+        model.extend(header);
+        std::fs::write(folder.path().join("model.safetensors"), model).unwrap();
+        std::fs::write(folder.path().join("config.json"), "{}").unwrap();
+        std::fs::write(folder.path().join("run.sh"), "").unwrap();
+
+        let signer =
+            |algorithm, timestamped_at: Option<chrono::DateTime<chrono::Utc>>| SignerVerdict {
+                fingerprint: "0123456789abcdef0123".to_string(),
+                algorithm,
+                trusted: true,
+                signed_at: chrono::Utc::now().to_rfc3339(),
+                expires_at: None,
+                timestamped_at: timestamped_at.map(|time| time.to_rfc3339()),
+                authenticated_at: timestamped_at.map(|time| time.to_rfc3339()),
+            };
+        let file = |path: &str| FileVerdict {
+            path: path.to_string(),
+            status: FileStatus::Ok,
+        };
+        let mut verdict = Verdict {
+            files: vec![file("config.json")],
+            signers: vec![signer(SigningAlgorithm::Ed25519, Some(chrono::Utc::now()))],
+            ..Default::default()
+        };
+        assert!(policy
+            .evaluate(&verdict, folder.path(), "BLAKE2b512")
+            .unwrap()
+            .is_empty());
+
+        verdict.files = vec![file("model.safetensors"), file("run.sh")];
+        verdict.signers = vec![
+            signer(
+                SigningAlgorithm::EcdsaP256,
+                Some(chrono::Utc::now() - chrono::Duration::days(31)),
+            ),
+            signer(SigningAlgorithm::Ed25519, None),
+        ];
+        assert_eq!(
+            policy.evaluate(&verdict, folder.path(), "SHA256").unwrap(),
+            vec![
+                "files hashed with SHA256, which is not allowed",
+                "signature by 0123456789abcdef made with EcdsaP256, which is not allowed",
+                format!(
+                    "signature by 0123456789abcdef made at {} is older than 30 days",
+                    verdict.signers[0].timestamped_at.as_ref().unwrap()
+                )
+                .as_str(),
+                "the signing date of 0123456789abcdef can't be trusted, sign with --expires-in or --tsa-url",
+                "model.safetensors is 42 bytes, more than the 16 allowed",
+                "model.safetensors has no license metadata",
+                "run.sh is not of an allowed file type",
+            ]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{fingerprint, SigningAlgorithm, SigningKey, VerifyingKey};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Revocation {
+    // fingerprint of the revoked public key
+    pub fingerprint: String,
+    pub revoked_at: String,
+    pub reason: String,
+}
+
+/// List of revoked public keys, signed by its issuer.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RevocationList {
+    pub issued_at: String,
+    pub revoked: Vec<Revocation>,
+    // fingerprint of the public key of the issuer
+    pub public_key: String,
+    pub algorithm: Option<SigningAlgorithm>,
+    pub signature: String,
+}
+
+impl RevocationList {
+    pub(crate) fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("can't read revocation list {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub(crate) fn to_file(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn data_to_sign(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(&(&self.issued_at, &self.revoked))?)
+    }
+
+    /// Returns the revocation of the public key with this fingerprint, if any.
+    pub(crate) fn find(&self, fingerprint: &str) -> Option<&Revocation> {
+        self.revoked.iter().find(|r| r.fingerprint == fingerprint)
+    }
+
+    pub(crate) fn revoke(
+        &mut self,
+        fingerprint: String,
+        revoked_at: String,
+        reason: String,
+    ) -> anyhow::Result<()> {
+        if self.find(&fingerprint).is_some() {
+            anyhow::bail!("key {} has already been revoked", &fingerprint[..16]);
+        }
+        chrono::DateTime::parse_from_rfc3339(&revoked_at)
+            .map_err(|e| anyhow::anyhow!("invalid revocation date '{}': {}", revoked_at, e))?;
+
+        self.revoked.push(Revocation {
+            fingerprint,
+            revoked_at,
+            reason,
+        });
+        Ok(())
+    }
+
+    /// Signs the list, replacing the previous signature.
+    pub(crate) fn sign(&mut self, signing_key: &SigningKey) -> anyhow::Result<()> {
+        self.issued_at = chrono::Utc::now().to_rfc3339();
+        self.public_key = fingerprint(signing_key.public_key());
+        self.algorithm = Some(signing_key.algorithm());
+        self.signature = hex::encode(signing_key.sign(self.data_to_sign()?.as_bytes())?);
+        Ok(())
+    }
+
+    /// Verifies that the list has been signed by one of the given keys.
+    pub(crate) fn verify(&self, keys: &[VerifyingKey]) -> anyhow::Result<()> {
+        let key = keys
+            .iter()
+            .find(|key| fingerprint(key.as_bytes()) == self.public_key)
+            .ok_or_else(|| anyhow::anyhow!("revocation list issued by an untrusted key"))?;
+
+        key.verify(
+            self.data_to_sign()?.as_bytes(),
+            &hex::decode(&self.signature)?,
+        )
+        .map_err(|e| anyhow::anyhow!("invalid revocation list signature: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revocation_list() {
+        let (issuer, _) = SigningKey::generate(SigningAlgorithm::Ed25519).unwrap();
+        let (other, _) = SigningKey::generate(SigningAlgorithm::Ed25519).unwrap();
+        let issuer_key = VerifyingKey::new(issuer.public_key().to_vec()).unwrap();
+        let other_key = VerifyingKey::new(other.public_key().to_vec()).unwrap();
+        let revoked = fingerprint(other.public_key());
+
+        let mut list = RevocationList::default();
+        list.revoke(
+            revoked.clone(),
+            "2026-01-01T00:00:00Z".to_string(),
+            "compromised".to_string(),
+        )
+        .unwrap();
+        assert!(list
+            .revoke(
+                revoked.clone(),
+                "2026-01-01T00:00:00Z".to_string(),
+                "".to_string()
+            )
+            .is_err());
+        assert!(list
+            .revoke("00".repeat(64), "yesterday".to_string(), "".to_string())
+            .is_err());
+        list.sign(&issuer).unwrap();
+
+        let list: RevocationList =
+            serde_json::from_str(&serde_json::to_string(&list).unwrap()).unwrap();
+        list.verify(&[other_key.clone(), issuer_key.clone()])
+            .unwrap();
+        assert!(list.verify(&[other_key]).is_err());
+        assert_eq!(list.find(&revoked).unwrap().reason, "compromised");
+        assert!(list.find(&fingerprint(issuer.public_key())).is_none());
+
+        // tampered
+        let mut tampered = list;
+        tampered.revoked[0].reason = "nothing to see here".to_string();
+        assert!(tampered.verify(&[issuer_key]).is_err());
+    }
+}
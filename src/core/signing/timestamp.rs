@@ -0,0 +1,627 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ring::{
+    digest,
+    rand::{SecureRandom, SystemRandom},
+    signature,
+};
+
+// DER tags
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OID: u8 = 0x06;
+const GENERALIZED_TIME: u8 = 0x18;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const CONTEXT_0: u8 = 0xa0;
+const CONTEXT_1: u8 = 0xa1;
+
+// object identifiers, DER encoded
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_SHA384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+const OID_TST_INFO: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const OID_SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+const NONCE_SIZE: usize = 8;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Minimal DER reader, enough to walk the structures of RFC 3161 and RFC 5652.
+struct Der<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Reads the next element, returns its tag, its contents and its whole encoding.
+    fn read(&mut self) -> anyhow::Result<(u8, &'a [u8], &'a [u8])> {
+        let invalid = || anyhow::anyhow!("invalid DER encoding");
+
+        let tag = *self.data.first().ok_or_else(invalid)?;
+        let first = *self.data.get(1).ok_or_else(invalid)? as usize;
+        let (header_size, length) = if first < 0x80 {
+            (2, first)
+        } else {
+            let num_bytes = first & 0x7f;
+            if num_bytes == 0 || num_bytes > 4 {
+                return Err(invalid());
+            }
+            let bytes = self.data.get(2..2 + num_bytes).ok_or_else(invalid)?;
+            (
+                2 + num_bytes,
+                bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize),
+            )
+        };
+
+        let end = header_size.checked_add(length).ok_or_else(invalid)?;
+        let encoding = self.data.get(..end).ok_or_else(invalid)?;
+        self.data = &self.data[end..];
+
+        Ok((tag, &encoding[header_size..], encoding))
+    }
+
+    /// Reads the next element and returns its contents, fails if it has a different tag.
+    fn expect(&mut self, tag: u8) -> anyhow::Result<&'a [u8]> {
+        let (actual, contents, _) = self.read()?;
+        if actual != tag {
+            anyhow::bail!(
+                "unexpected DER tag 0x{:02x}, expected 0x{:02x}",
+                actual,
+                tag
+            );
+        }
+        Ok(contents)
+    }
+
+    /// Reads the next element only if it has the given tag.
+    fn optional(&mut self, tag: u8) -> anyhow::Result<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(tag) {
+            self.expect(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn encode(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    if contents.len() < 0x80 {
+        encoded.push(contents.len() as u8);
+    } else {
+        let length = (contents.len() as u32).to_be_bytes();
+        let skip = length.iter().take_while(|b| **b == 0).count();
+        encoded.push(0x80 | (4 - skip) as u8);
+        encoded.extend_from_slice(&length[skip..]);
+    }
+    encoded.extend_from_slice(contents);
+    encoded
+}
+
+fn digest_algorithm(oid: &[u8]) -> anyhow::Result<&'static digest::Algorithm> {
+    match oid {
+        OID_SHA256 => Ok(&digest::SHA256),
+        OID_SHA384 => Ok(&digest::SHA384),
+        OID_SHA512 => Ok(&digest::SHA512),
+        _ => anyhow::bail!("unsupported timestamp digest algorithm"),
+    }
+}
+
+/// Hash of the manifest signature the timestamp is requested for.
+fn message_imprint(signature: &[u8]) -> Vec<u8> {
+    digest::digest(&digest::SHA256, signature).as_ref().to_vec()
+}
+
+fn build_request(imprint: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let algorithm = [encode(OID, OID_SHA256), encode(NULL, &[])].concat();
+    let message_imprint = [encode(SEQUENCE, &algorithm), encode(OCTET_STRING, imprint)].concat();
+
+    encode(
+        SEQUENCE,
+        &[
+            // version
+            encode(INTEGER, &[1]),
+            encode(SEQUENCE, &message_imprint),
+            encode(INTEGER, nonce),
+            // ask for the TSA certificate to be included in the token
+            encode(BOOLEAN, &[0xff]),
+        ]
+        .concat(),
+    )
+}
+
+fn http_post(url: &str, content_type: &str, body: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let Some(location) = url.strip_prefix("http://") else {
+        anyhow::bail!("unsupported TSA url {}, only http:// is supported", url);
+    };
+    let (host, path) = match location.find('/') {
+        Some(index) => (&location[..index], &location[index..]),
+        None => (location, "/"),
+    };
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+
+    let mut stream = TcpStream::connect(&address)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+
+    // HTTP/1.0 so that the response is never chunked
+    write!(
+        stream,
+        "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nUser-Agent: {}/{}\r\n\r\n",
+        path,
+        host,
+        content_type,
+        body.len(),
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    stream.write_all(body)?;
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response)?;
+
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("invalid HTTP response from {}", url))?;
+    let status_line = String::from_utf8_lossy(&response[..header_end])
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        anyhow::bail!("request to {} failed: {}", url, status_line);
+    }
+
+    Ok(response[header_end + 4..].to_vec())
+}
+
+/// Extracts the timestamp token from a TSA response.
+fn parse_response(response: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut response = Der::new(Der::new(response).expect(SEQUENCE)?);
+    let mut status_info = Der::new(response.expect(SEQUENCE)?);
+    let status = status_info.expect(INTEGER)?;
+    // granted or granted with modifications
+    if status != [0] && status != [1] {
+        anyhow::bail!(
+            "timestamp request rejected with status {}",
+            hex::encode(status)
+        );
+    }
+
+    let (tag, _, token) = response.read()?;
+    if tag != SEQUENCE {
+        anyhow::bail!("missing timestamp token in TSA response");
+    }
+
+    Ok(token.to_vec())
+}
+
+struct TstInfo {
+    imprint_algorithm: Vec<u8>,
+    imprint: Vec<u8>,
+    gen_time: DateTime<Utc>,
+    nonce: Option<Vec<u8>>,
+}
+
+fn parse_tst_info(data: &[u8]) -> anyhow::Result<TstInfo> {
+    let mut tst_info = Der::new(Der::new(data).expect(SEQUENCE)?);
+    // version and policy
+    tst_info.expect(INTEGER)?;
+    tst_info.expect(OID)?;
+
+    let mut message_imprint = Der::new(tst_info.expect(SEQUENCE)?);
+    let imprint_algorithm = Der::new(message_imprint.expect(SEQUENCE)?)
+        .expect(OID)?
+        .to_vec();
+    let imprint = message_imprint.expect(OCTET_STRING)?.to_vec();
+
+    // serial number
+    tst_info.expect(INTEGER)?;
+
+    let gen_time = std::str::from_utf8(tst_info.expect(GENERALIZED_TIME)?)?;
+    let gen_time = NaiveDateTime::parse_from_str(gen_time, "%Y%m%d%H%M%S%.fZ")?.and_utc();
+
+    // the nonce is the only optional field encoded as an integer
+    let mut nonce = None;
+    while !tst_info.is_empty() {
+        let (tag, contents, _) = tst_info.read()?;
+        if tag == INTEGER {
+            nonce = Some(contents.to_vec());
+        }
+    }
+
+    Ok(TstInfo {
+        imprint_algorithm,
+        imprint,
+        gen_time,
+        nonce,
+    })
+}
+
+struct PublicKey<'a> {
+    key_type: &'a [u8],
+    // named curve of EC keys
+    curve: Option<&'a [u8]>,
+    key: &'a [u8],
+}
+
+/// Returns the subject public key of a certificate.
+fn certificate_public_key(certificate: &[u8]) -> anyhow::Result<PublicKey<'_>> {
+    let mut certificate = Der::new(Der::new(certificate).expect(SEQUENCE)?);
+    let mut tbs = Der::new(certificate.expect(SEQUENCE)?);
+    tbs.optional(CONTEXT_0)?;
+    // serial number, signature algorithm, issuer, validity and subject
+    tbs.expect(INTEGER)?;
+    for _ in 0..4 {
+        tbs.expect(SEQUENCE)?;
+    }
+
+    let mut spki = Der::new(tbs.expect(SEQUENCE)?);
+    let mut algorithm = Der::new(spki.expect(SEQUENCE)?);
+    let key_type = algorithm.expect(OID)?;
+    let curve = algorithm.optional(OID)?;
+    // skip the number of unused bits
+    let key = spki
+        .expect(BIT_STRING)?
+        .get(1..)
+        .ok_or_else(|| anyhow::anyhow!("invalid certificate public key"))?;
+
+    Ok(PublicKey {
+        key_type,
+        curve,
+        key,
+    })
+}
+
+fn verification_algorithm(
+    signature_algorithm: &[u8],
+    digest_algorithm: &[u8],
+    key_type: &[u8],
+    curve: Option<&[u8]>,
+) -> anyhow::Result<&'static dyn signature::VerificationAlgorithm> {
+    let algorithm: &'static dyn signature::VerificationAlgorithm =
+        match (signature_algorithm, key_type, curve) {
+            (OID_RSA_ENCRYPTION, OID_RSA_ENCRYPTION, _) => match digest_algorithm {
+                OID_SHA256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+                OID_SHA384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+                OID_SHA512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+                _ => anyhow::bail!("unsupported timestamp digest algorithm"),
+            },
+            (OID_SHA256_WITH_RSA, OID_RSA_ENCRYPTION, _) => &signature::RSA_PKCS1_2048_8192_SHA256,
+            (OID_SHA384_WITH_RSA, OID_RSA_ENCRYPTION, _) => &signature::RSA_PKCS1_2048_8192_SHA384,
+            (OID_SHA512_WITH_RSA, OID_RSA_ENCRYPTION, _) => &signature::RSA_PKCS1_2048_8192_SHA512,
+            (OID_ECDSA_WITH_SHA256, _, Some(OID_P256)) => &signature::ECDSA_P256_SHA256_ASN1,
+            (OID_ECDSA_WITH_SHA384, _, Some(OID_P256)) => &signature::ECDSA_P256_SHA384_ASN1,
+            (OID_ECDSA_WITH_SHA256, _, Some(OID_P384)) => &signature::ECDSA_P384_SHA256_ASN1,
+            (OID_ECDSA_WITH_SHA384, _, Some(OID_P384)) => &signature::ECDSA_P384_SHA384_ASN1,
+            _ => anyhow::bail!("unsupported timestamp signature algorithm"),
+        };
+    Ok(algorithm)
+}
+
+/// Verifies the CMS signature of a timestamp token and returns the signed TSTInfo. If a TSA
+/// certificate is given the token must have been signed with it, otherwise the certificates
+/// included in the token are used.
+fn verify_token(token: &[u8], tsa_certificate: Option<&[u8]>) -> anyhow::Result<TstInfo> {
+    let mut content_info = Der::new(Der::new(token).expect(SEQUENCE)?);
+    if content_info.expect(OID)? != OID_SIGNED_DATA {
+        anyhow::bail!("the timestamp token is not a CMS signed data structure");
+    }
+
+    let mut signed_data = Der::new(Der::new(content_info.expect(CONTEXT_0)?).expect(SEQUENCE)?);
+    // version and digest algorithms
+    signed_data.expect(INTEGER)?;
+    signed_data.expect(SET)?;
+
+    let mut encapsulated = Der::new(signed_data.expect(SEQUENCE)?);
+    if encapsulated.expect(OID)? != OID_TST_INFO {
+        anyhow::bail!("the timestamp token doesn't contain a TSTInfo");
+    }
+    let tst_info = Der::new(encapsulated.expect(CONTEXT_0)?).expect(OCTET_STRING)?;
+
+    let mut certificates = vec![];
+    if let Some(encoded) = signed_data.optional(CONTEXT_0)? {
+        let mut encoded = Der::new(encoded);
+        while !encoded.is_empty() {
+            certificates.push(encoded.read()?.2);
+        }
+    }
+    // CRLs
+    signed_data.optional(CONTEXT_1)?;
+
+    let mut signer_infos = Der::new(signed_data.expect(SET)?);
+    let mut signer_info = Der::new(signer_infos.expect(SEQUENCE)?);
+    // version and signer identifier
+    signer_info.expect(INTEGER)?;
+    signer_info.read()?;
+    let digest_oid = Der::new(signer_info.expect(SEQUENCE)?).expect(OID)?;
+    let (tag, attributes, encoded_attributes) = signer_info.read()?;
+    if tag != CONTEXT_0 {
+        anyhow::bail!("the timestamp token has no signed attributes");
+    }
+    let signature_oid = Der::new(signer_info.expect(SEQUENCE)?).expect(OID)?;
+    let signature = signer_info.expect(OCTET_STRING)?;
+
+    // the signed attributes must include the digest of the TSTInfo
+    let mut message_digest = None;
+    let mut attributes = Der::new(attributes);
+    while !attributes.is_empty() {
+        let mut attribute = Der::new(attributes.expect(SEQUENCE)?);
+        if attribute.expect(OID)? == OID_MESSAGE_DIGEST {
+            message_digest = Some(Der::new(attribute.expect(SET)?).expect(OCTET_STRING)?);
+        }
+    }
+    let expected_digest = digest::digest(digest_algorithm(digest_oid)?, tst_info);
+    if message_digest != Some(expected_digest.as_ref()) {
+        anyhow::bail!("the timestamp token digest doesn't match its content");
+    }
+
+    // the signature is computed on the attributes encoded as a SET
+    let mut signed_attributes = encoded_attributes.to_vec();
+    signed_attributes[0] = SET;
+
+    let candidates = match tsa_certificate {
+        Some(certificate) => vec![certificate],
+        None => certificates,
+    };
+    let verified = candidates.into_iter().any(|certificate| {
+        let Ok(public_key) = certificate_public_key(certificate) else {
+            return false;
+        };
+        verification_algorithm(
+            signature_oid,
+            digest_oid,
+            public_key.key_type,
+            public_key.curve,
+        )
+        .map(|algorithm| {
+            signature::UnparsedPublicKey::new(algorithm, public_key.key)
+                .verify(&signed_attributes, signature)
+                .is_ok()
+        })
+        .unwrap_or(false)
+    });
+    if !verified {
+        anyhow::bail!("invalid timestamp token signature");
+    }
+
+    parse_tst_info(tst_info)
+}
+
+fn request_token(url: &str, imprint: &[u8], nonce: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let response = http_post(
+        url,
+        "application/timestamp-query",
+        &build_request(imprint, nonce),
+    )?;
+    let token = parse_response(&response)?;
+
+    let tst_info = verify_token(&token, None)?;
+    if tst_info.imprint_algorithm != OID_SHA256 || tst_info.imprint != imprint {
+        anyhow::bail!("the timestamp token is for a different message");
+    }
+    if tst_info.nonce.as_deref() != Some(nonce) {
+        anyhow::bail!("the timestamp token nonce doesn't match the request");
+    }
+
+    Ok(token)
+}
+
+/// Requests to the TSA a timestamp token of the signature.
+pub(crate) fn request_timestamp(url: &str, signature: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_SIZE];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow::anyhow!("failed to generate nonce"))?;
+    // keep it positive and minimally encoded
+    nonce[0] = (nonce[0] & 0x7f) | 0x40;
+
+    request_token(url, &message_imprint(signature), &nonce)
+}
+
+/// Verifies that the token is a timestamp of the signature and returns its time.
+pub(crate) fn verify_timestamp(
+    token: &[u8],
+    signature: &[u8],
+    tsa_certificate: Option<&[u8]>,
+) -> anyhow::Result<DateTime<Utc>> {
+    let tst_info = verify_token(token, tsa_certificate)?;
+    let expected = digest::digest(digest_algorithm(&tst_info.imprint_algorithm)?, signature);
+    if tst_info.imprint != expected.as_ref() {
+        anyhow::bail!("the timestamp token is not for this signature");
+    }
+
+    Ok(tst_info.gen_time)
+}
+
+/// Loads a certificate either PEM or DER encoded.
+pub(crate) fn load_certificate(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return Ok(data.to_vec());
+    };
+    if !text.contains("-----BEGIN CERTIFICATE-----") {
+        return Ok(data.to_vec());
+    }
+
+    let encoded: String = text
+        .lines()
+        .skip_while(|line| !line.starts_with("-----BEGIN CERTIFICATE-----"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END CERTIFICATE-----"))
+        .collect();
+
+    Ok(STANDARD.decode(encoded)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // responses of an openssl TSA to requests for the SHA-256 of "tensor-man"
+    const RSA_RESPONSE: &str = concat!(
+        "MIIF0zADAgEAMIIFygYJKoZIhvcNAQcCoIIFuzCCBbcCAQMxDzANBglghkgBZQMEAgEFADByBgsqhkiG9w0BCRAB",
+        "BKBjBGEwXwIBAQYEKgMEATAxMA0GCWCGSAFlAwQCAQUABCD0GsS1EOJnOTJjBXPJbyBrZLi2hkbThUL8WghEsVcO",
+        "mAIBAhgPMjAyNjEwMTYxMzQ3MDZaMAMCAQECCCmvxTjOSspLoIIDLDCCAygwggIQoAMCAQICFCgP40/32wmeEz+/",
+        "ORgO1UpGZoxnMA0GCSqGSIb3DQEBCwUAMBExDzANBgNVBAMMBlRlc3RDQTAgFw0yNjEwMTYxMzQ3MDZaGA8yMTI2",
+        "MDkyMjEzNDcwNlowEjEQMA4GA1UEAwwHVGVzdFRTQTCCASIwDQYJKoZIhvcNAQEBBQADggEPADCCAQoCggEBAMB+",
+        "mnqAjSPSTe/k3Tsg2EN2jk7YpRb4fvVNs1on16UrVmRZ7oDRTRh6QO4jbljJNLyfLetmsK1U5XgpeOmjSVxO1HTC",
+        "pmQoE6MTF87V3MYZP3OnAFNmlpdJktWHLVJFXFOonqv5hukGWNCCNr0xM2f75QvFJuupvI1iBnZydv7Fwhb/uwf3",
+        "/h97/T+xTjq2nByCl+gp4ANo8SHJA/d/Fdw8wt0GY3ubOwp8KX/2BitAc5BgWc5dasxglJr4D+EIwEktGfGw+F/K",
+        "G/Vr9cyJ3ww2I1nSEfcAr3xdeftLhLF03UyXJPanQ6zz8ULfyt9Hvypgq7jg6IHwEBxbm4q0DqsCAwEAAaN1MHMw",
+        "CQYDVR0TBAIwADAWBgNVHSUBAf8EDDAKBggrBgEFBQcDCDAOBgNVHQ8BAf8EBAMCB4AwHQYDVR0OBBYEFGByXUcZ",
+        "JFhyczJZKrQ1b3bpxUd0MB8GA1UdIwQYMBaAFHbhue5YbbE6vHg+Ep+G7vm2N4Y6MA0GCSqGSIb3DQEBCwUAA4IB",
+        "AQAucQJ1u+uzP3RBBqM3Ff2dwyAdt7uTQopFrz8wrjCbAK8HVh0SQ93tXPO4q9Xk0O4y1W4XEP8cDV48Jl+rodSo",
+        "CvTXHUG05Uqpn1KSQu9cSIyZbrpn+ft+2AUzZU+WC04ZYqb4+R7EPmUASDt+9CbTBy0YQOI2Q5nhqAjogfqLVsQk",
+        "BaZ4/BYgJwXXPfOKAim0JmHm9HvCoezpJmRzqum4uc9Y0MIEzFpP+Y3HchP+9fdsWrvEkQQHPCnFuYGuq5X0rsq7",
+        "4U+6HxdeajQXC7eqP0efbXZCZGXJZXAeO29DkcsXru7i73ZHPu7EXHL9YpEg3tcxGYWkQ9a82DuNhH9EMYIB+zCC",
+        "AfcCAQEwKTARMQ8wDQYDVQQDDAZUZXN0Q0ECFCgP40/32wmeEz+/ORgO1UpGZoxnMA0GCWCGSAFlAwQCAQUAoIGk",
+        "MBoGCSqGSIb3DQEJAzENBgsqhkiG9w0BCRABBDAcBgkqhkiG9w0BCQUxDxcNMjYxMDE2MTM0NzA2WjAvBgkqhkiG",
+        "9w0BCQQxIgQge8W7y/WWqkNDycpBssWRox0zILw/cjluGkjRvWKUb5AwNwYLKoZIhvcNAQkQAi8xKDAmMCQwIgQg",
+        "K/2vXurxqEZgKkGE8LEHCyvKgNXhf5Qjvh7NaIlc43EwDQYJKoZIhvcNAQEBBQAEggEAGVfVC9HpDVH4mymKHU8z",
+        "ivYkTo3iqDTOcAYLxr0bImQr1u5aZO4oCXVi1jyn7S0JWUnNQiLRrHk2z3Je8QTrNnOT/Ff1T4GDT52YL9Fu9MK2",
+        "FYFcugjwm6hoiH1QK2pm6ODvuZcN5S1t+FQKwjf4vsabDbQtApVvMqUR3PgTWboteXweXin0E5ksjzoBQwOUVknK",
+        "VR41fuS9A4ZesseWpZKpfa+UanW5ToGUWjlZYMCDutH4TpePqsfx1Y1wEnKI87u5wXvJ8Yubthn4KLZmAv3a+HYe",
+        "B40PqdoS5qvWHpd3EEyCg7CxirLqQ9VoMTLW5bk24mhBjvNiVjVM/qODdg==",
+    );
+    const RSA_NONCE: &[u8] = &[0x29, 0xaf, 0xc5, 0x38, 0xce, 0x4a, 0xca, 0x4b];
+    const EC_RESPONSE: &str = concat!(
+        "MIIESzADAgEAMIIEQgYJKoZIhvcNAQcCoIIEMzCCBC8CAQMxDzANBglghkgBZQMEAgEFADBzBgsqhkiG9w0BCRAB",
+        "BKBkBGIwYAIBAQYEKgMEATAxMA0GCWCGSAFlAwQCAQUABCD0GsS1EOJnOTJjBXPJbyBrZLi2hkbThUL8WghEsVcO",
+        "mAIBAxgPMjAyNjEwMTYxMzQ3MDdaMAMCAQECCQDte8NgkOWANqCCAmEwggJdMIIBRaADAgECAhQoD+NP99sJnhM/",
+        "vzkYDtVKRmaMaDANBgkqhkiG9w0BAQsFADARMQ8wDQYDVQQDDAZUZXN0Q0EwIBcNMjYxMDE2MTM0NzA3WhgPMjEy",
+        "NjA5MjIxMzQ3MDdaMBIxEDAOBgNVBAMMB1Rlc3RUU0EwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAATLV1E/JC17",
+        "BAGktmE+yz34X6cPi08ePD3RlgbGFPr3UZBwzJS58bMT3FsjAZjNPwGnddjicuRg0Cg+NyRbLxvNo3UwczAJBgNV",
+        "HRMEAjAAMBYGA1UdJQEB/wQMMAoGCCsGAQUFBwMIMA4GA1UdDwEB/wQEAwIHgDAdBgNVHQ4EFgQUFuEdr81lOJnN",
+        "lHeQoX3bw+I6QMMwHwYDVR0jBBgwFoAUduG57lhtsTq8eD4Sn4bu+bY3hjowDQYJKoZIhvcNAQELBQADggEBABSC",
+        "9YwoYSSufKocyKOE4x/zdLzKEi5Jrtu0kfhL9PGqa4a2ie3O3biOKMTRsunH0XcI4s4pmTRMxZYA38eRHfRN3Ywf",
+        "QYJKEPUmX/TW0Rt9aP63xnQfsppH5tbIJm9wZNBxub3BvSik4QUVnUYGyxo/IkZodwQ/CaUjiHfa3QlqzOMYld07",
+        "NP+civQPXKxAGCZ/eg/LLBCd/rrn+CugWIasQDSAZoPlLp6JBABtx1GEuXZPppzgFt4rwPWFQlda7ut/j/8/Fcsm",
+        "PAXz+2qig2I4eBogtSYd1W4oJiLbgzGcLWGiou2O4V3J3ukwkGb7IqnDnun9bThG5xrXS4DuooMxggE9MIIBOQIB",
+        "ATApMBExDzANBgNVBAMMBlRlc3RDQQIUKA/jT/fbCZ4TP785GA7VSkZmjGgwDQYJYIZIAWUDBAIBBQCggaQwGgYJ",
+        "KoZIhvcNAQkDMQ0GCyqGSIb3DQEJEAEEMBwGCSqGSIb3DQEJBTEPFw0yNjEwMTYxMzQ3MDdaMC8GCSqGSIb3DQEJ",
+        "BDEiBCDf1VR0nCLM2p4k3v1GQ3Np/LOCn10udtICN1zQxgrHjjA3BgsqhkiG9w0BCRACLzEoMCYwJDAiBCAh0vXE",
+        "mQdkGMthtCQUPxFRFWVM9MzxQu6l6ErDFqL77DAKBggqhkjOPQQDAgRHMEUCIE2nqdJzCfLRuO44WloURSzdAvC4",
+        "qHWFuJCUCETJHWc0AiEAtAhHdXjwSu+izmH5W4ZihSmhpgU8BZLWSQA/DT0CJ34=",
+    );
+
+    const RSA_TSA_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----\nMIIDKDCCAhCgAwIBAgIUKA/jT/fbCZ4TP785GA7VSkZmjGcwDQYJKoZIhvcNAQEL\nBQAwETEPMA0GA1UEAwwGVGVzdENBMCAXDTI2MTAxNjEzNDcwNloYDzIxMjYwOTIy\nMTM0NzA2WjASMRAwDgYDVQQDDAdUZXN0VFNBMIIBIjANBgkqhkiG9w0BAQEFAAOC\nAQ8AMIIBCgKCAQEAwH6aeoCNI9JN7+TdOyDYQ3aOTtilFvh+9U2zWifXpStWZFnu\ngNFNGHpA7iNuWMk0vJ8t62awrVTleCl46aNJXE7UdMKmZCgToxMXztXcxhk/c6cA\nU2aWl0mS1YctUkVcU6ieq/mG6QZY0II2vTEzZ/vlC8Um66m8jWIGdnJ2/sXCFv+7\nB/f+H3v9P7FOOracHIKX6CngA2jxIckD938V3DzC3QZje5s7Cnwpf/YGK0BzkGBZ\nzl1qzGCUmvgP4QjASS0Z8bD4X8ob9Wv1zInfDDYjWdIR9wCvfF15+0uEsXTdTJck\n9qdDrPPxQt/K30e/KmCruODogfAQHFubirQOqwIDAQABo3UwczAJBgNVHRMEAjAA\nMBYGA1UdJQEB/wQMMAoGCCsGAQUFBwMIMA4GA1UdDwEB/wQEAwIHgDAdBgNVHQ4E\nFgQUYHJdRxkkWHJzMlkqtDVvdunFR3QwHwYDVR0jBBgwFoAUduG57lhtsTq8eD4S\nn4bu+bY3hjowDQYJKoZIhvcNAQELBQADggEBAC5xAnW767M/dEEGozcV/Z3DIB23\nu5NCikWvPzCuMJsArwdWHRJD3e1c87ir1eTQ7jLVbhcQ/xwNXjwmX6uh1KgK9Ncd\nQbTlSqmfUpJC71xIjJluumf5+37YBTNlT5YLThlipvj5HsQ+ZQBIO370JtMHLRhA\n4jZDmeGoCOiB+otWxCQFpnj8FiAnBdc984oCKbQmYeb0e8Kh7OkmZHOq6bi5z1jQ\nwgTMWk/5jcdyE/7192xau8SRBAc8KcW5ga6rlfSuyrvhT7ofF15qNBcLt6o/R59t\ndkJkZcllcB47b0ORyxeu7uLvdkc+7sRccv1ikSDe1zEZhaRD1rzYO42Ef0Q=\n-----END CERTIFICATE-----\n";
+    const EC_TSA_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----\nMIICXTCCAUWgAwIBAgIUKA/jT/fbCZ4TP785GA7VSkZmjGgwDQYJKoZIhvcNAQEL\nBQAwETEPMA0GA1UEAwwGVGVzdENBMCAXDTI2MTAxNjEzNDcwN1oYDzIxMjYwOTIy\nMTM0NzA3WjASMRAwDgYDVQQDDAdUZXN0VFNBMFkwEwYHKoZIzj0CAQYIKoZIzj0D\nAQcDQgAEy1dRPyQtewQBpLZhPss9+F+nD4tPHjw90ZYGxhT691GQcMyUufGzE9xb\nIwGYzT8Bp3XY4nLkYNAoPjckWy8bzaN1MHMwCQYDVR0TBAIwADAWBgNVHSUBAf8E\nDDAKBggrBgEFBQcDCDAOBgNVHQ8BAf8EBAMCB4AwHQYDVR0OBBYEFBbhHa/NZTiZ\nzZR3kKF928PiOkDDMB8GA1UdIwQYMBaAFHbhue5YbbE6vHg+Ep+G7vm2N4Y6MA0G\nCSqGSIb3DQEBCwUAA4IBAQAUgvWMKGEkrnyqHMijhOMf83S8yhIuSa7btJH4S/Tx\nqmuGtontzt24jijE0bLpx9F3COLOKZk0TMWWAN/HkR30Td2MH0GCShD1Jl/01tEb\nfWj+t8Z0H7KaR+bWyCZvcGTQcbm9wb0opOEFFZ1GBssaPyJGaHcEPwmlI4h32t0J\naszjGJXdOzT/nIr0D1ysQBgmf3oPyywQnf665/groFiGrEA0gGaD5S6eiQQAbcdR\nhLl2T6ac4BbeK8D1hUJXWu7rf4//PxXLJjwF8/tqooNiOHgaILUmHdVuKCYi24Mx\nnC1hoqLtjuFdyd7pMJBm+yKpw57p/W04Ruca10uA7qKD\n-----END CERTIFICATE-----\n";
+
+    const SIGNATURE: &[u8] = b"tensor-man";
+
+    fn token(response: &str) -> Vec<u8> {
+        let response = STANDARD.decode(response).unwrap();
+        parse_response(&response).unwrap()
+    }
+
+    #[test]
+    fn test_der() {
+        let contents = vec![7u8; 300];
+        let encoded = encode(SEQUENCE, &contents);
+        assert_eq!(&encoded[..4], &[SEQUENCE, 0x82, 0x01, 0x2c]);
+
+        let mut der = Der::new(&encoded);
+        assert_eq!(der.expect(SEQUENCE).unwrap(), &contents[..]);
+        assert!(der.is_empty());
+
+        // truncated
+        assert!(Der::new(&encoded[..100]).read().is_err());
+    }
+
+    #[test]
+    fn test_verify_timestamp() {
+        let rsa_certificate = load_certificate(RSA_TSA_CERTIFICATE.as_bytes()).unwrap();
+        let ec_certificate = load_certificate(EC_TSA_CERTIFICATE.as_bytes()).unwrap();
+
+        for (response, certificate, other_certificate) in [
+            (RSA_RESPONSE, &rsa_certificate, &ec_certificate),
+            (EC_RESPONSE, &ec_certificate, &rsa_certificate),
+        ] {
+            let token = token(response);
+
+            let time = verify_timestamp(&token, SIGNATURE, None).unwrap();
+            assert_eq!(time.format("%Y-%m-%d").to_string(), "2026-10-16");
+            assert!(verify_timestamp(&token, SIGNATURE, Some(certificate)).is_ok());
+
+            // signed by a different TSA
+            assert!(verify_timestamp(&token, SIGNATURE, Some(other_certificate)).is_err());
+            // timestamp of a different signature
+            assert!(verify_timestamp(&token, b"other", None).is_err());
+        }
+
+        // tampered token
+        let mut token = token(RSA_RESPONSE);
+        let index = token
+            .windows(4)
+            .position(|window| window == b"2026")
+            .unwrap();
+        token[index + 3] = b'5';
+        assert!(verify_timestamp(&token, SIGNATURE, None).is_err());
+    }
+
+    #[test]
+    fn test_request_token() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tsr", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![0u8; 4096];
+                let _ = stream.read(&mut request).unwrap();
+
+                let response = STANDARD.decode(RSA_RESPONSE).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/timestamp-reply\r\nContent-Length: {}\r\n\r\n",
+                    response.len()
+                )
+                .unwrap();
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        let imprint = message_imprint(SIGNATURE);
+        assert!(request_token(&url, &imprint, RSA_NONCE).is_ok());
+        // replayed response
+        assert!(request_token(&url, &imprint, &[1, 2, 3]).is_err());
+
+        server.join().unwrap();
+        assert!(request_token("https://localhost/", &imprint, RSA_NONCE).is_err());
+    }
+}
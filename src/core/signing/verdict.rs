@@ -0,0 +1,334 @@
+use std::{fmt, path::Path};
+
+use serde::Serialize;
+
+use super::{FileStatus, SigningAlgorithm};
+use crate::core::sarif::{artifact_uri, Finding, Rule};
+
+/// Rules of the SARIF log of a verification.
+pub(crate) const RULES: &[Rule] = &[
+    Rule {
+        id: "invalid-signature",
+        description: "The signature is invalid, or not enough signatures are valid.",
+        level: "error",
+    },
+    Rule {
+        id: "key-mismatch",
+        description: "The model has not been signed with the verifying keys, or they are revoked.",
+        level: "error",
+    },
+    Rule {
+        id: "modified-file",
+        description: "A signed file has been modified.",
+        level: "error",
+    },
+    Rule {
+        id: "missing-file",
+        description: "A signed file is missing.",
+        level: "error",
+    },
+    Rule {
+        id: "unsigned-file",
+        description: "A file of the model has not been signed.",
+        level: "error",
+    },
+    Rule {
+        id: "expired-signature",
+        description: "The signature expired, is too old or lacks the required timestamp.",
+        level: "error",
+    },
+    Rule {
+        id: "missing-signature",
+        description: "No signature found for the model.",
+        level: "error",
+    },
+    Rule {
+        id: "policy-violation",
+        description:
+            "The model or its signatures violate a requirement of the verification policy.",
+        level: "error",
+    },
+];
+
+/// Cause of a failed verification, each one exits with its own code.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    // a signature doesn't match the manifest, or not enough signatures are valid
+    InvalidSignature,
+    // the signatures have not been made with the verifying keys, or their keys are revoked
+    KeyMismatch,
+    // the content or the size of a signed file changed
+    ModifiedFile,
+    // a signed file is missing, or a file has not been signed
+    MissingFile,
+    // the signature expired, is older than allowed or lacks the required timestamp
+    Expired,
+    // no signature found for the model
+    MissingSignature,
+    // the model or its signatures violate the verification policy
+    PolicyViolation,
+}
+
+impl Failure {
+    /// Exit code of the process, 1 being used for any other error and 2 for invalid arguments.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Failure::InvalidSignature => 3,
+            Failure::KeyMismatch => 4,
+            Failure::ModifiedFile => 5,
+            Failure::MissingFile => 6,
+            Failure::Expired => 7,
+            Failure::MissingSignature => 8,
+            Failure::PolicyViolation => 9,
+        }
+    }
+
+    /// Id of the SARIF rule.
+    pub(crate) fn rule_id(&self) -> &'static str {
+        match self {
+            Failure::InvalidSignature => "invalid-signature",
+            Failure::KeyMismatch => "key-mismatch",
+            Failure::ModifiedFile => "modified-file",
+            Failure::MissingFile => "missing-file",
+            Failure::Expired => "expired-signature",
+            Failure::MissingSignature => "missing-signature",
+            Failure::PolicyViolation => "policy-violation",
+        }
+    }
+
+    /// Returns an error with the message, tagged with this cause.
+    pub(crate) fn error(self, message: impl Into<String>) -> anyhow::Error {
+        anyhow::Error::new(VerificationError {
+            failure: self,
+            message: message.into(),
+        })
+    }
+
+    /// Returns the cause of the error if it is a verification failure.
+    pub fn of(error: &anyhow::Error) -> Option<Self> {
+        error
+            .downcast_ref::<VerificationError>()
+            .map(|error| error.failure)
+    }
+}
+
+#[derive(Debug)]
+struct VerificationError {
+    failure: Failure,
+    message: String,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FileVerdict {
+    pub path: String,
+    pub status: FileStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SignerVerdict {
+    // fingerprint of the public key
+    pub fingerprint: String,
+    pub algorithm: SigningAlgorithm,
+    // whether the signature has been made with a verifying key and is valid
+    pub trusted: bool,
+    pub signed_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    // RFC 3161 timestamp, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamped_at: Option<String>,
+    // signing date if it can be trusted, timestamped or signed along with the checksums
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authenticated_at: Option<String>,
+}
+
+/// Machine readable result of a verification.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Verdict {
+    // verified or failed
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<Failure>,
+    pub exit_code: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub files: Vec<FileVerdict>,
+    pub signers: Vec<SignerVerdict>,
+    // requirements of the verification policy not met
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub policy_violations: Vec<String>,
+    pub verified_at: String,
+}
+
+impl Verdict {
+    /// Sets the status from the result of the verification.
+    pub(crate) fn set_result(&mut self, result: &anyhow::Result<()>) {
+        self.verified_at = chrono::Utc::now().to_rfc3339();
+        match result {
+            Ok(()) => {
+                self.status = "verified";
+                self.exit_code = 0;
+            }
+            Err(e) => {
+                let failure = Failure::of(e);
+                self.status = "failed";
+                self.failure = failure;
+                self.exit_code = failure.map(|f| f.exit_code()).unwrap_or(1);
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Returns the SARIF findings: one for each file that failed verification and each policy
+    /// violation, or one for the signature if the failure is not about them.
+    pub(crate) fn findings(&self) -> Vec<Finding> {
+        let model_path = Path::new(&self.model);
+        // the file paths are relative to the model folder
+        let base_path = if model_path.is_dir() {
+            model_path
+        } else {
+            model_path.parent().unwrap_or(Path::new(""))
+        };
+
+        let mut findings: Vec<Finding> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let (rule_id, message) = match file.status {
+                    FileStatus::Ok => return None,
+                    FileStatus::Modified => ("modified-file", "has been modified"),
+                    FileStatus::Missing => ("missing-file", "is missing"),
+                    FileStatus::Extra => ("unsigned-file", "has not been signed"),
+                };
+                Some(Finding {
+                    rule_id,
+                    message: format!("{} {}", file.path, message),
+                    uri: artifact_uri(&base_path.join(&file.path)),
+                    byte_offset: None,
+                })
+            })
+            .collect();
+        let signature_uri = artifact_uri(Path::new(self.signature.as_ref().unwrap_or(&self.model)));
+        findings.extend(self.policy_violations.iter().map(|violation| Finding {
+            rule_id: "policy-violation",
+            message: violation.clone(),
+            uri: signature_uri.clone(),
+            byte_offset: None,
+        }));
+
+        if let Some(failure) = self.failure {
+            let is_about_files = matches!(
+                failure,
+                Failure::ModifiedFile | Failure::MissingFile | Failure::PolicyViolation
+            );
+            if findings.is_empty() || !is_about_files {
+                findings.push(Finding {
+                    rule_id: failure.rule_id(),
+                    message: self.error.clone().unwrap_or_default(),
+                    uri: signature_uri,
+                    byte_offset: None,
+                });
+            }
+        }
+
+        findings
+    }
+
+    /// Error of the execution, if it failed for a reason other than the verification itself.
+    pub(crate) fn execution_error(&self) -> Option<String> {
+        match self.failure {
+            None => self.error.clone(),
+            Some(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_of_error() {
+        let error = Failure::KeyMismatch.error("public key fingerprint mismatch");
+        assert_eq!(Failure::of(&error), Some(Failure::KeyMismatch));
+        assert_eq!(error.to_string(), "public key fingerprint mismatch");
+        // the cause is preserved through the context
+        assert_eq!(
+            Failure::of(&error.context("verifying model")),
+            Some(Failure::KeyMismatch)
+        );
+        assert_eq!(Failure::of(&anyhow::anyhow!("i/o error")), None);
+
+        let mut verdict = Verdict::default();
+        verdict.set_result(&Err(Failure::ModifiedFile.error("invalid size for a")));
+        assert_eq!(verdict.status, "failed");
+        assert_eq!(verdict.exit_code, 5);
+        assert_eq!(
+            serde_json::to_value(&verdict).unwrap()["failure"],
+            "modified_file"
+        );
+    }
+
+    #[test]
+    fn test_findings() {
+        let mut verdict = Verdict {
+            model: "models/llama/model.safetensors".to_string(),
+            signature: Some("models/llama/model.signature".to_string()),
+            files: vec![
+                FileVerdict {
+                    path: "model.safetensors".to_string(),
+                    status: FileStatus::Modified,
+                },
+                FileVerdict {
+                    path: "config.json".to_string(),
+                    status: FileStatus::Ok,
+                },
+            ],
+            ..Default::default()
+        };
+        verdict.set_result(&Err(Failure::ModifiedFile.error("invalid size")));
+
+        assert_eq!(
+            verdict.findings(),
+            vec![Finding {
+                rule_id: "modified-file",
+                message: "model.safetensors has been modified".to_string(),
+                uri: "models/llama/model.safetensors".to_string(),
+                byte_offset: None,
+            }]
+        );
+        assert_eq!(verdict.execution_error(), None);
+
+        verdict.files.clear();
+        verdict.set_result(&Err(Failure::KeyMismatch.error("fingerprint mismatch")));
+        let findings = verdict.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule_id, "key-mismatch");
+        assert_eq!(findings[0].uri, "models/llama/model.signature");
+
+        // every failure has a rule
+        for failure in [
+            Failure::InvalidSignature,
+            Failure::KeyMismatch,
+            Failure::ModifiedFile,
+            Failure::MissingFile,
+            Failure::Expired,
+            Failure::MissingSignature,
+            Failure::PolicyViolation,
+        ] {
+            assert!(RULES.iter().any(|rule| rule.id == failure.rule_id()));
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::data::TensorData;
+
+/// Value statistics of a single tensor, min/max/mean/std only account for finite values.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct TensorStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std: f64,
+    pub zero_fraction: f64,
+    pub nan_count: usize,
+    pub inf_count: usize,
+}
+
+impl TensorStats {
+    pub fn from_values(values: impl Iterator<Item = f64>) -> Self {
+        let mut count = 0usize;
+        let mut finite = 0usize;
+        let mut zeros = 0usize;
+        let mut nan_count = 0usize;
+        let mut inf_count = 0usize;
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        // Welford's online algorithm, numerically stable on large tensors
+        let mut mean = 0.0f64;
+        let mut m2 = 0.0f64;
+
+        for value in values {
+            count += 1;
+            if value.is_nan() {
+                nan_count += 1;
+                continue;
+            } else if value.is_infinite() {
+                inf_count += 1;
+                continue;
+            }
+
+            if value == 0.0 {
+                zeros += 1;
+            }
+
+            finite += 1;
+            min = min.min(value);
+            max = max.max(value);
+            let delta = value - mean;
+            mean += delta / finite as f64;
+            m2 += delta * (value - mean);
+        }
+
+        if finite == 0 {
+            min = f64::NAN;
+            max = f64::NAN;
+            mean = f64::NAN;
+        }
+
+        Self {
+            count,
+            min,
+            max,
+            mean,
+            std: if finite > 0 {
+                (m2 / finite as f64).sqrt()
+            } else {
+                f64::NAN
+            },
+            zero_fraction: if count > 0 {
+                zeros as f64 / count as f64
+            } else {
+                0.0
+            },
+            nan_count,
+            inf_count,
+        }
+    }
+
+    pub fn from_data(tensor: &TensorData) -> Self {
+        Self::from_values(tensor.values())
+    }
+}
+
+/// Computes in parallel the statistics of each tensor, by tensor name.
+pub(crate) fn compute(tensors: &[TensorData]) -> BTreeMap<String, TensorStats> {
+    tensors
+        .par_iter()
+        .map(|tensor| (tensor.id.clone(), TensorStats::from_data(tensor)))
+        .collect()
+}
+
+/// NaN and infinite values found in a tensor.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct NonFinite {
+    pub id: String,
+    pub nan_count: usize,
+    pub inf_count: usize,
+    /// Index of the first non finite element.
+    pub first_index: usize,
+    /// File offset of the first non finite element, or of the quantization block containing it.
+    pub first_offset: Option<u64>,
+}
+
+/// Scans the floating point tensors in parallel and returns the ones containing NaN or Inf values.
+pub(crate) fn find_non_finite(tensors: &[TensorData]) -> Vec<NonFinite> {
+    let mut found: Vec<NonFinite> = tensors
+        .par_iter()
+        .filter(|tensor| tensor.encoding.is_float())
+        .filter_map(|tensor| {
+            let mut nan_count = 0;
+            let mut inf_count = 0;
+            let mut first_index = None;
+            for (index, value) in tensor.values().enumerate() {
+                if value.is_nan() {
+                    nan_count += 1;
+                } else if value.is_infinite() {
+                    inf_count += 1;
+                } else {
+                    continue;
+                }
+                first_index.get_or_insert(index);
+            }
+
+            first_index.map(|first_index| NonFinite {
+                id: tensor.id.clone(),
+                nan_count,
+                inf_count,
+                first_index,
+                first_offset: tensor.element_file_offset(first_index),
+            })
+        })
+        .collect();
+
+    found.sort_by(|a, b| a.id.cmp(&b.id));
+    found
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct Bin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+/// Distribution of the finite values of a tensor over equal width bins.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct Histogram {
+    pub id: String,
+    pub min: f64,
+    pub max: f64,
+    pub bins: Vec<Bin>,
+    pub nan_count: usize,
+    pub inf_count: usize,
+}
+
+impl Histogram {
+    pub fn from_data(tensor: &TensorData, num_bins: usize) -> anyhow::Result<Self> {
+        if num_bins == 0 {
+            anyhow::bail!("the number of bins must be greater than zero");
+        }
+
+        // first pass for the range, second one to fill the bins
+        let stats = TensorStats::from_data(tensor);
+        let (min, max) = if stats.min.is_nan() {
+            (0.0, 0.0)
+        } else {
+            (stats.min, stats.max)
+        };
+        let width = (max - min) / num_bins as f64;
+
+        let mut bins: Vec<Bin> = (0..num_bins)
+            .map(|i| Bin {
+                start: min + width * i as f64,
+                end: min + width * (i + 1) as f64,
+                count: 0,
+            })
+            .collect();
+
+        for value in tensor.values().filter(|v| v.is_finite()) {
+            let index = if width > 0.0 {
+                (((value - min) / width) as usize).min(num_bins - 1)
+            } else {
+                0
+            };
+            bins[index].count += 1;
+        }
+
+        Ok(Self {
+            id: tensor.id.clone(),
+            min,
+            max,
+            bins,
+            nan_count: stats.nan_count,
+            inf_count: stats.inf_count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::core::data::{ElementType, Encoding};
+
+    #[test]
+    fn test_from_values() {
+        let stats =
+            TensorStats::from_values([1.0, 2.0, 3.0, 0.0, f64::NAN, f64::INFINITY].into_iter());
+
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.min, 0.0);
+        assert_eq!(stats.max, 3.0);
+        assert_eq!(stats.mean, 1.5);
+        assert!((stats.std - 1.25f64.sqrt()).abs() < 1e-12);
+        assert!((stats.zero_fraction - 1.0 / 6.0).abs() < 1e-12);
+        assert_eq!(stats.nan_count, 1);
+        assert_eq!(stats.inf_count, 1);
+    }
+
+    #[test]
+    fn test_from_values_without_finite_values() {
+        let stats = TensorStats::from_values([f64::NAN, f64::NAN].into_iter());
+        assert_eq!(stats.nan_count, 2);
+        assert!(stats.mean.is_nan());
+
+        let stats = TensorStats::from_values(std::iter::empty());
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.zero_fraction, 0.0);
+    }
+
+    #[test]
+    fn test_compute() {
+        let data: Vec<u8> = [-1.0f32, 1.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let tensors = vec![TensorData::owned(
+            "t".to_string(),
+            Encoding::Plain(ElementType::F32),
+            data,
+        )];
+
+        let stats = compute(&tensors);
+        let stats = stats.get("t").unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.mean, 0.0);
+        assert_eq!(stats.std, 1.0);
+    }
+
+    #[test]
+    fn test_find_non_finite() {
+        let floats: Vec<u8> = [0.0f32, f32::INFINITY, f32::NAN, f32::NAN]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let buffer: Arc<dyn AsRef<[u8]> + Send + Sync> = Arc::new(floats);
+        let tensors = vec![
+            TensorData::new(
+                "bad".to_string(),
+                Encoding::Plain(ElementType::F32),
+                buffer,
+                0..16,
+                Some(100),
+            )
+            .unwrap(),
+            TensorData::owned(
+                "good".to_string(),
+                Encoding::Plain(ElementType::F32),
+                1.0f32.to_le_bytes().to_vec(),
+            ),
+            // integers can't hold non finite values
+            TensorData::owned(
+                "ints".to_string(),
+                Encoding::Plain(ElementType::I32),
+                vec![0xff; 4],
+            ),
+        ];
+
+        let found = find_non_finite(&tensors);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "bad");
+        assert_eq!(found[0].nan_count, 2);
+        assert_eq!(found[0].inf_count, 1);
+        assert_eq!(found[0].first_index, 1);
+        assert_eq!(found[0].first_offset, Some(104));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let data: Vec<u8> = [0.0f32, 1.0, 2.0, 3.0, 4.0, f32::NAN]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let tensor = TensorData::owned("t".to_string(), Encoding::Plain(ElementType::F32), data);
+
+        let histogram = Histogram::from_data(&tensor, 4).unwrap();
+        assert_eq!(histogram.min, 0.0);
+        assert_eq!(histogram.max, 4.0);
+        assert_eq!(histogram.nan_count, 1);
+        assert_eq!(
+            histogram.bins.iter().map(|b| b.count).collect::<Vec<_>>(),
+            vec![1, 1, 1, 2]
+        );
+        assert_eq!(histogram.bins[1].start, 1.0);
+
+        // constant tensors end up in a single bin
+        let tensor = TensorData::owned(
+            "c".to_string(),
+            Encoding::Plain(ElementType::U8),
+            vec![7; 10],
+        );
+        let histogram = Histogram::from_data(&tensor, 3).unwrap();
+        assert_eq!(histogram.bins[0].count, 10);
+
+        assert!(Histogram::from_data(&tensor, 0).is_err());
+    }
+}
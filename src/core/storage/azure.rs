@@ -0,0 +1,273 @@
+use std::io::Read;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ureq::http::{header, request::Builder, Request};
+
+use super::{check, hmac_sha256, percent_encode, xml_texts, Object, Storage};
+use crate::core::remote;
+
+const API_VERSION: &str = "2021-08-06";
+
+enum Credentials {
+    // AZURE_STORAGE_KEY, base64-decoded
+    SharedKey(Vec<u8>),
+    // AZURE_STORAGE_SAS_TOKEN, appended to the query
+    Sas(String),
+}
+
+fn env(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+}
+
+// Shared Key string to sign, only x-ms-* headers and the content length are set
+fn string_to_sign(
+    method: &str,
+    content_length: usize,
+    ms_headers: &[(&str, String)],
+    resource: &str,
+    query: &[(&str, &str)],
+) -> String {
+    let content_length = if content_length > 0 {
+        content_length.to_string()
+    } else {
+        String::new()
+    };
+    let mut ms_headers = ms_headers.to_vec();
+    ms_headers.sort();
+    let mut query = query.to_vec();
+    query.sort();
+
+    // verb, content encoding, language, length, MD5, type, date, if-modified-since,
+    // if-match, if-none-match, if-unmodified-since and range
+    let mut data = format!("{}\n\n\n{}\n\n\n\n\n\n\n\n\n", method, content_length);
+    for (name, value) in ms_headers {
+        data.push_str(&format!("{}:{}\n", name, value));
+    }
+    data.push_str(resource);
+    for (name, value) in query {
+        data.push_str(&format!("\n{}:{}", name, value));
+    }
+    data
+}
+
+/// Azure Blob Storage.
+pub(super) struct Azure {
+    agent: ureq::Agent,
+    account: String,
+    container: String,
+    // AZURE_STORAGE_ENDPOINT, for instance http://127.0.0.1:10000/devstoreaccount1 for Azurite
+    endpoint: String,
+    // anonymous requests if not set, for public containers
+    credentials: Option<Credentials>,
+}
+
+impl Azure {
+    pub(super) fn new(container: &str) -> anyhow::Result<Self> {
+        let account = env(&["AZURE_STORAGE_ACCOUNT", "AZURE_STORAGE_ACCOUNT_NAME"])
+            .ok_or_else(|| anyhow::anyhow!("AZURE_STORAGE_ACCOUNT is required for az:// URIs"))?;
+        let endpoint = env(&["AZURE_STORAGE_ENDPOINT"])
+            .unwrap_or_else(|| format!("https://{}.blob.core.windows.net", account));
+        let credentials = match (
+            env(&["AZURE_STORAGE_KEY", "AZURE_STORAGE_ACCOUNT_KEY"]),
+            env(&["AZURE_STORAGE_SAS_TOKEN"]),
+        ) {
+            (Some(key), _) => {
+                Some(Credentials::SharedKey(STANDARD.decode(key).map_err(
+                    |e| anyhow::anyhow!("invalid AZURE_STORAGE_KEY: {}", e),
+                )?))
+            }
+            (None, Some(sas)) => Some(Credentials::Sas(sas.trim_start_matches('?').to_string())),
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            agent: super::agent(),
+            account,
+            container: container.to_string(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            credentials,
+        })
+    }
+
+    // returns the URL and the authorized request for the blob, or the container if the
+    // key is empty, the query values are not encoded
+    fn request(
+        &self,
+        method: &str,
+        key: &str,
+        query: &[(&str, &str)],
+        ms_headers: Vec<(&str, String)>,
+        content_length: usize,
+    ) -> (String, Builder) {
+        let mut url = format!("{}/{}", self.endpoint, self.container);
+        if !key.is_empty() {
+            url.push('/');
+            url.push_str(&percent_encode(key, true));
+        }
+        let mut parameters: Vec<String> = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, percent_encode(value, false)))
+            .collect();
+        if let Some(Credentials::Sas(sas)) = &self.credentials {
+            parameters.push(sas.clone());
+        }
+        if !parameters.is_empty() {
+            url.push('?');
+            url.push_str(&parameters.join("&"));
+        }
+
+        let mut ms_headers = ms_headers;
+        ms_headers.push((
+            "x-ms-date",
+            chrono::Utc::now()
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        ));
+        ms_headers.push(("x-ms-version", API_VERSION.to_string()));
+
+        let mut builder = Request::builder().method(method).uri(&url);
+        if let Some(Credentials::SharedKey(account_key)) = &self.credentials {
+            // the account followed by the path of the URL
+            let path = url
+                .split_once("://")
+                .and_then(|(_, rest)| rest.find('/').map(|index| &rest[index..]))
+                .unwrap_or("/");
+            let path = path.split('?').next().unwrap_or(path);
+            let resource = format!("/{}{}", self.account, path);
+
+            let signature = hmac_sha256(
+                account_key,
+                string_to_sign(method, content_length, &ms_headers, &resource, query).as_bytes(),
+            );
+            builder = builder.header(
+                header::AUTHORIZATION,
+                format!("SharedKey {}:{}", self.account, STANDARD.encode(signature)),
+            );
+        }
+        for (name, value) in ms_headers {
+            builder = builder.header(name, value);
+        }
+
+        (url, builder)
+    }
+}
+
+impl Storage for Azure {
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<Object>> {
+        let mut objects: Vec<Object> = vec![];
+        let mut marker = String::new();
+
+        loop {
+            let mut query = vec![
+                ("comp", "list"),
+                ("prefix", prefix),
+                ("restype", "container"),
+            ];
+            if !marker.is_empty() {
+                query.push(("marker", &marker));
+            }
+            let (url, builder) = self.request("GET", "", &query, vec![], 0);
+            let response = self
+                .agent
+                .run(builder.body(())?)
+                .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+            let xml = check(response, "GET", &url)?
+                .body_mut()
+                .with_config()
+                .limit(64 * 1024 * 1024)
+                .read_to_string()?;
+
+            let mut next_marker = String::new();
+            for (path, text) in xml_texts(&xml)? {
+                if path.ends_with("Blob/Name") {
+                    objects.push(Object { key: text, size: 0 });
+                } else if path.ends_with("Blob/Properties/Content-Length") {
+                    if let Some(object) = objects.last_mut() {
+                        object.size = text.parse()?;
+                    }
+                } else if path.ends_with("NextMarker") {
+                    next_marker = text;
+                }
+            }
+            if next_marker.is_empty() {
+                break;
+            }
+            marker = next_marker;
+        }
+
+        Ok(objects)
+    }
+
+    fn read_range(&self, key: &str, start: u64, end: u64) -> anyhow::Result<(Vec<u8>, u64)> {
+        let (url, builder) = self.request(
+            "GET",
+            key,
+            &[],
+            vec![("x-ms-range", format!("bytes={}-{}", start, end))],
+            0,
+        );
+        let response = self
+            .agent
+            .run(builder.body(())?)
+            .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+        remote::range_of(response, &url, start, end)
+    }
+
+    fn reader(&self, key: &str) -> anyhow::Result<Box<dyn Read>> {
+        let (url, builder) = self.request("GET", key, &[], vec![], 0);
+        let response = self
+            .agent
+            .run(builder.body(())?)
+            .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+        Ok(Box::new(
+            check(response, "GET", &url)?.into_body().into_reader(),
+        ))
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let (url, builder) = self.request(
+            "PUT",
+            key,
+            &[],
+            vec![("x-ms-blob-type", "BlockBlob".to_string())],
+            data.len(),
+        );
+        let response = self
+            .agent
+            .run(builder.body(data)?)
+            .map_err(|e| anyhow::anyhow!("PUT {} failed: {}", url, e))?;
+        check(response, "PUT", &url)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_to_sign() {
+        assert_eq!(
+            string_to_sign(
+                "GET",
+                0,
+                &[
+                    ("x-ms-version", API_VERSION.to_string()),
+                    ("x-ms-date", "Mon, 01 Jan 2024 00:00:00 GMT".to_string()),
+                ],
+                "/account/models",
+                &[
+                    ("restype", "container"),
+                    ("comp", "list"),
+                    ("prefix", "a/b")
+                ],
+            ),
+            "GET\n\n\n\n\n\n\n\n\n\n\n\n\
+             x-ms-date:Mon, 01 Jan 2024 00:00:00 GMT\n\
+             x-ms-version:2021-08-06\n\
+             /account/models\ncomp:list\nprefix:a/b\nrestype:container"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::io::Read;
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use ring::{rand::SystemRandom, signature::RsaKeyPair};
+use serde::Deserialize;
+use ureq::http::{header, request::Builder, Request};
+
+use super::{check, percent_encode, Object, Storage};
+use crate::core::remote;
+
+const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// Service account key file, as referenced by GOOGLE_APPLICATION_CREDENTIALS.
+#[derive(Debug, Deserialize)]
+struct ServiceAccount {
+    #[serde(rename = "type")]
+    account_type: String,
+    client_email: String,
+    // PKCS#8 PEM RSA key
+    private_key: String,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectList {
+    #[serde(default)]
+    items: Vec<ListedObject>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListedObject {
+    name: String,
+    // 64 bit integers are strings in the JSON API
+    size: String,
+}
+
+fn pem_to_der(pem: &str) -> anyhow::Result<Vec<u8>> {
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .map(str::trim)
+        .collect();
+    Ok(STANDARD.decode(body)?)
+}
+
+// signed JWT asserting the identity of the service account
+fn jwt_assertion(account: &ServiceAccount, token_uri: &str, now: i64) -> anyhow::Result<String> {
+    let header = serde_json::json!({"alg": "RS256", "typ": "JWT"});
+    let claims = serde_json::json!({
+        "iss": account.client_email,
+        "scope": SCOPE,
+        "aud": token_uri,
+        "iat": now,
+        "exp": now + 3600,
+    });
+    let data = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+
+    let key_pair = RsaKeyPair::from_pkcs8(&pem_to_der(&account.private_key)?)
+        .map_err(|e| anyhow::anyhow!("invalid service account key: {}", e))?;
+    let mut signature = vec![0u8; key_pair.public().modulus_len()];
+    key_pair
+        .sign(
+            &ring::signature::RSA_PKCS1_SHA256,
+            &SystemRandom::new(),
+            data.as_bytes(),
+            &mut signature,
+        )
+        .map_err(|e| anyhow::anyhow!("can't sign the token request: {}", e))?;
+
+    Ok(format!("{}.{}", data, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+/// Google Cloud Storage, through its JSON API.
+pub(super) struct Gcs {
+    agent: ureq::Agent,
+    bucket: String,
+    // STORAGE_EMULATOR_HOST for local emulators
+    endpoint: String,
+    // OAuth 2.0 access token, anonymous requests if none
+    token: Option<String>,
+}
+
+impl Gcs {
+    pub(super) fn new(bucket: &str) -> anyhow::Result<Self> {
+        let endpoint = match std::env::var("STORAGE_EMULATOR_HOST") {
+            Ok(host) if host.starts_with("http://") || host.starts_with("https://") => host,
+            Ok(host) if !host.is_empty() => format!("http://{}", host),
+            _ => DEFAULT_ENDPOINT.to_string(),
+        };
+
+        let mut this = Self {
+            agent: super::agent(),
+            bucket: bucket.to_string(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            token: None,
+        };
+        this.token = match std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+            Ok(token) if !token.is_empty() => Some(token),
+            _ => match std::env::var("GOOGLE_APPLICATION_CREDENTIALS") {
+                Ok(path) if !path.is_empty() => Some(this.service_account_token(&path)?),
+                _ => None,
+            },
+        };
+
+        Ok(this)
+    }
+
+    // exchanges a JWT signed with the service account key for an access token
+    fn service_account_token(&self, path: &str) -> anyhow::Result<String> {
+        let account: ServiceAccount = serde_json::from_str(
+            &std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("can't read credentials {}: {}", path, e))?,
+        )?;
+        if account.account_type != "service_account" {
+            anyhow::bail!(
+                "unsupported {} credentials in {}, set GOOGLE_OAUTH_ACCESS_TOKEN instead",
+                account.account_type,
+                path
+            );
+        }
+
+        let token_uri = account.token_uri.as_deref().unwrap_or(DEFAULT_TOKEN_URI);
+        let assertion = jwt_assertion(&account, token_uri, chrono::Utc::now().timestamp())?;
+        let body = format!(
+            "grant_type={}&assertion={}",
+            percent_encode("urn:ietf:params:oauth:grant-type:jwt-bearer", false),
+            assertion
+        );
+        let response = self
+            .agent
+            .post(token_uri)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .send(body)
+            .map_err(|e| anyhow::anyhow!("POST {} failed: {}", token_uri, e))?;
+        let token: Token = serde_json::from_slice(
+            &check(response, "POST", token_uri)?
+                .body_mut()
+                .with_config()
+                .limit(1024 * 1024)
+                .read_to_vec()?,
+        )?;
+
+        Ok(token.access_token)
+    }
+
+    fn request(&self, method: &str, url: &str) -> Builder {
+        let builder = Request::builder().method(method).uri(url);
+        match &self.token {
+            Some(token) => builder.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+            None => builder,
+        }
+    }
+
+    fn media_url(&self, key: &str) -> String {
+        format!(
+            "{}/storage/v1/b/{}/o/{}?alt=media",
+            self.endpoint,
+            self.bucket,
+            percent_encode(key, false)
+        )
+    }
+}
+
+impl Storage for Gcs {
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<Object>> {
+        let mut objects = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut url = format!(
+                "{}/storage/v1/b/{}/o?prefix={}&fields=items(name,size),nextPageToken",
+                self.endpoint,
+                self.bucket,
+                percent_encode(prefix, false)
+            );
+            if let Some(token) = &page_token {
+                url.push_str(&format!("&pageToken={}", percent_encode(token, false)));
+            }
+
+            let response = self
+                .agent
+                .run(self.request("GET", &url).body(())?)
+                .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+            let list: ObjectList = serde_json::from_slice(
+                &check(response, "GET", &url)?
+                    .body_mut()
+                    .with_config()
+                    .limit(64 * 1024 * 1024)
+                    .read_to_vec()?,
+            )?;
+
+            for object in list.items {
+                objects.push(Object {
+                    key: object.name,
+                    size: object.size.parse()?,
+                });
+            }
+            page_token = list.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(objects)
+    }
+
+    fn read_range(&self, key: &str, start: u64, end: u64) -> anyhow::Result<(Vec<u8>, u64)> {
+        let url = self.media_url(key);
+        let response = self
+            .agent
+            .run(
+                self.request("GET", &url)
+                    .header(header::RANGE, format!("bytes={}-{}", start, end))
+                    .body(())?,
+            )
+            .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+        remote::range_of(response, &url, start, end)
+    }
+
+    fn reader(&self, key: &str) -> anyhow::Result<Box<dyn Read>> {
+        let url = self.media_url(key);
+        let response = self
+            .agent
+            .run(self.request("GET", &url).body(())?)
+            .map_err(|e| anyhow::anyhow!("GET {} failed: {}", url, e))?;
+        Ok(Box::new(
+            check(response, "GET", &url)?.into_body().into_reader(),
+        ))
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let url = format!(
+            "{}/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+            self.endpoint,
+            self.bucket,
+            percent_encode(key, false)
+        );
+        let response = self
+            .agent
+            .run(
+                self.request("POST", &url)
+                    .header(header::CONTENT_TYPE, "application/octet-stream")
+                    .body(data)?,
+            )
+            .map_err(|e| anyhow::anyhow!("POST {} failed: {}", url, e))?;
+        check(response, "POST", &url)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jwt_assertion() {
+        use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+
+        let private_key = rsa::RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 2048).unwrap();
+        let account = ServiceAccount {
+            account_type: "service_account".to_string(),
+            client_email: "tman@project.iam.gserviceaccount.com".to_string(),
+            private_key: private_key
+                .to_pkcs8_pem(LineEnding::LF)
+                .unwrap()
+                .to_string(),
+            token_uri: None,
+        };
+
+        let jwt = jwt_assertion(&account, DEFAULT_TOKEN_URI, 1700000000).unwrap();
+        let parts: Vec<&str> = jwt.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        let claims: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[1]).unwrap()).unwrap();
+        assert_eq!(claims["iss"], "tman@project.iam.gserviceaccount.com");
+        assert_eq!(claims["aud"], DEFAULT_TOKEN_URI);
+        assert_eq!(claims["exp"], 1700003600);
+    }
+}
@@ -0,0 +1,286 @@
+use std::{fmt, io::Read, path::Path, time::Duration};
+
+use quick_xml::{events::Event, Reader};
+use ring::{digest, hmac};
+use ureq::{
+    http::{Response, StatusCode},
+    Body,
+};
+
+use super::remote::RangeReader;
+
+mod azure;
+mod gcs;
+mod s3;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Backend {
+    S3,
+    Gcs,
+    Azure,
+}
+
+impl Backend {
+    fn scheme(&self) -> &'static str {
+        match self {
+            Backend::S3 => "s3://",
+            Backend::Gcs => "gs://",
+            Backend::Azure => "az://",
+        }
+    }
+}
+
+/// An object, or the objects under a prefix, of an object store: `s3://bucket/key`,
+/// `gs://bucket/key` or `az://container/key`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ObjectUri {
+    pub backend: Backend,
+    // bucket, or container for Azure
+    pub bucket: String,
+    // key of the object or prefix, empty for the whole bucket
+    pub key: String,
+}
+
+impl ObjectUri {
+    pub(crate) fn is_object_uri(path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        [Backend::S3, Backend::Gcs, Backend::Azure]
+            .iter()
+            .any(|backend| path.starts_with(backend.scheme()))
+    }
+
+    pub(crate) fn parse(uri: &str) -> anyhow::Result<Self> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "invalid object store URI '{}', expected s3://bucket/key, gs://bucket/key or az://container/key",
+                uri
+            )
+        };
+        let (backend, rest) = [Backend::S3, Backend::Gcs, Backend::Azure]
+            .into_iter()
+            .find_map(|backend| {
+                uri.strip_prefix(backend.scheme())
+                    .map(|rest| (backend, rest))
+            })
+            .ok_or_else(invalid)?;
+
+        let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty()
+            || !bucket
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c))
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            backend,
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    pub(crate) fn with_key(&self, key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for ObjectUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}/{}", self.backend.scheme(), self.bucket, self.key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Object {
+    pub key: String,
+    pub size: u64,
+}
+
+/// Operations of the object stores on the objects of a bucket.
+pub(crate) trait Storage {
+    /// Lists the objects with keys starting with the prefix.
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<Object>>;
+    /// Returns the bytes of the object from start to end included, along with its size.
+    fn read_range(&self, key: &str, start: u64, end: u64) -> anyhow::Result<(Vec<u8>, u64)>;
+    /// Streams the whole object.
+    fn reader(&self, key: &str) -> anyhow::Result<Box<dyn Read>>;
+    /// Creates or replaces a small object.
+    fn write(&self, key: &str, data: &[u8]) -> anyhow::Result<()>;
+}
+
+/// Returns the client of the object store, configured from the environment.
+pub(crate) fn storage_for(uri: &ObjectUri) -> anyhow::Result<Box<dyn Storage>> {
+    Ok(match uri.backend {
+        Backend::S3 => Box::new(s3::S3::new(&uri.bucket)),
+        Backend::Gcs => Box::new(gcs::Gcs::new(&uri.bucket)?),
+        Backend::Azure => Box::new(azure::Azure::new(&uri.bucket)?),
+    })
+}
+
+/// An object read with range requests.
+pub(crate) struct ObjectRange {
+    storage: Box<dyn Storage>,
+    key: String,
+}
+
+impl ObjectRange {
+    pub(crate) fn new(uri: &ObjectUri) -> anyhow::Result<Self> {
+        Ok(Self {
+            storage: storage_for(uri)?,
+            key: uri.key.clone(),
+        })
+    }
+}
+
+impl RangeReader for ObjectRange {
+    fn read_range(&self, start: u64, end: u64) -> anyhow::Result<(Vec<u8>, u64)> {
+        self.storage.read_range(&self.key, start, end)
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_connect(Some(HTTP_TIMEOUT))
+        .build()
+        .into()
+}
+
+// returns the successful responses, and an error with the body of the others
+fn check(mut response: Response<Body>, method: &str, url: &str) -> anyhow::Result<Response<Body>> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let details = response
+        .body_mut()
+        .with_config()
+        .limit(64 * 1024)
+        .read_to_string()
+        .unwrap_or_default();
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => anyhow::bail!(
+            "{} {}: access denied, check the credentials: {}",
+            method,
+            url,
+            details.trim()
+        ),
+        _ => anyhow::bail!("{} {} failed: {} {}", method, url, status, details.trim()),
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters, and the slashes if requested.
+fn percent_encode(value: &str, keep_slashes: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) || (keep_slashes && byte == b'/')
+        {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data)
+        .as_ref()
+        .to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(digest::digest(&digest::SHA256, data))
+}
+
+/// Returns the text of the XML elements along with their path, for instance
+/// `ListBucketResult/Contents/Key`.
+fn xml_texts(xml: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let mut reader = Reader::from_str(xml);
+    let mut path: Vec<String> = vec![];
+    let mut texts = vec![];
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Eof => break,
+            Event::Start(element) => {
+                path.push(element.local_name().as_ref().to_string());
+                text.clear();
+            }
+            Event::Text(content) => text.push_str(&content.xml10_content()),
+            Event::CData(content) => text.push_str(&content.xml10_content()),
+            Event::GeneralRef(reference) => text.push_str(&quick_xml::escape::unescape(&format!(
+                "&{};",
+                reference.xml10_content()
+            ))?),
+            Event::End(_) => {
+                texts.push((path.join("/"), std::mem::take(&mut text)));
+                path.pop();
+            }
+            _ => {}
+        }
+    }
+
+    Ok(texts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uri() {
+        let uri = ObjectUri::parse("s3://models/llama/model.safetensors").unwrap();
+        assert_eq!(uri.backend, Backend::S3);
+        assert_eq!(uri.bucket, "models");
+        assert_eq!(uri.key, "llama/model.safetensors");
+        assert_eq!(uri.to_string(), "s3://models/llama/model.safetensors");
+
+        let uri = ObjectUri::parse("gs://my-bucket").unwrap();
+        assert_eq!(uri.backend, Backend::Gcs);
+        assert_eq!(uri.key, "");
+        assert_eq!(uri.with_key("a/b").to_string(), "gs://my-bucket/a/b");
+
+        let uri = ObjectUri::parse("az://container/model.onnx").unwrap();
+        assert_eq!(uri.backend, Backend::Azure);
+        assert_eq!(uri.bucket, "container");
+
+        assert!(ObjectUri::is_object_uri(Path::new("s3://bucket/key")));
+        assert!(!ObjectUri::is_object_uri(Path::new("hf://org/model")));
+        for invalid in ["s3://", "s3:///key", "gs://Bucket/key", "ftp://bucket/key"] {
+            assert!(ObjectUri::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("a b/c~d", true), "a%20b/c~d");
+        assert_eq!(percent_encode("a b/c~d", false), "a%20b%2Fc~d");
+        assert_eq!(percent_encode("ü", true), "%C3%BC");
+    }
+
+    #[test]
+    fn test_xml_texts() {
+        let texts = xml_texts(
+            "<?xml version=\"1.0\"?><R><Contents><Key>a &amp; b</Key><Size>3</Size></Contents></R>",
+        )
+        .unwrap();
+        assert_eq!(
+            texts,
+            vec![
+                ("R/Contents/Key".to_string(), "a & b".to_string()),
+                ("R/Contents/Size".to_string(), "3".to_string()),
+                ("R/Contents".to_string(), "".to_string()),
+                ("R".to_string(), "".to_string()),
+            ]
+        );
+    }
+}
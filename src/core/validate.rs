@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::{
+    handlers::{gguf, onnx, safetensors},
+    sarif::Rule,
+};
+
+/// Kinds of violations, as rules of the SARIF log.
+pub(crate) const RULES: &[Rule] = &[
+    Rule {
+        id: "invalid-header",
+        description: "The header exceeds the file or can't be parsed.",
+        level: "error",
+    },
+    Rule {
+        id: "invalid-metadata",
+        description: "The safetensors __metadata__ block is not a map of strings, or the GGUF alignment is not a power of two.",
+        level: "error",
+    },
+    Rule {
+        id: "invalid-tensor",
+        description: "A tensor has an unknown type, invalid offsets or dims, or a size not matching its shape.",
+        level: "error",
+    },
+    Rule {
+        id: "duplicate-tensor",
+        description: "A tensor name is declared more than once, only one of them is loaded.",
+        level: "error",
+    },
+    Rule {
+        id: "offset-out-of-bounds",
+        description: "The data of a tensor exceeds the file, which is truncated.",
+        level: "error",
+    },
+    Rule {
+        id: "overlapping-data",
+        description: "The data of a tensor overlaps the data of another tensor.",
+        level: "error",
+    },
+    Rule {
+        id: "data-gap",
+        description: "Bytes between the data of two tensors are not part of any tensor.",
+        level: "error",
+    },
+    Rule {
+        id: "non-monotonic-offsets",
+        description: "The offsets of the tensors decrease in the order they are declared.",
+        level: "warning",
+    },
+    Rule {
+        id: "trailing-data",
+        description: "Bytes following the data of the last tensor are not part of any tensor.",
+        level: "error",
+    },
+    Rule {
+        id: "misaligned-tensor",
+        description: "The data of a GGUF tensor is not aligned.",
+        level: "error",
+    },
+    Rule {
+        id: "unexpected-offset",
+        description: "The offset of a GGUF tensor is not the one computed from the previous tensor.",
+        level: "error",
+    },
+    Rule {
+        id: "invalid-ir-version",
+        description: "The ONNX IR version is missing or too old for the opset of the model.",
+        level: "error",
+    },
+    Rule {
+        id: "invalid-opset",
+        description: "An ONNX domain used by a node is not imported, or is imported more than once.",
+        level: "error",
+    },
+    Rule {
+        id: "dangling-input",
+        description: "An ONNX node input or graph output is not a graph input, an initializer or the output of a previous node.",
+        level: "error",
+    },
+    Rule {
+        id: "duplicate-name",
+        description: "An ONNX value is assigned by more than one node.",
+        level: "error",
+    },
+    Rule {
+        id: "type-mismatch",
+        description: "The type or dims of an ONNX initializer don't match the graph input it initializes.",
+        level: "error",
+    },
+];
+
+/// A structural violation of the format of a file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Violation {
+    pub rule_id: &'static str,
+    pub message: String,
+    // offset in the file, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+pub(crate) fn violation(rule_id: &'static str, message: String, offset: Option<u64>) -> Violation {
+    Violation {
+        rule_id,
+        message,
+        offset,
+    }
+}
+
+/// Result of the validation of a file.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Validation {
+    pub file_path: PathBuf,
+    pub violations: Vec<Violation>,
+}
+
+fn has_extension(file_path: &Path, extension: &str) -> bool {
+    file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Whether the file is a model that can be validated.
+pub(crate) fn is_validatable(file_path: &Path) -> bool {
+    has_extension(file_path, "safetensors")
+        || has_extension(file_path, "gguf")
+        || has_extension(file_path, "onnx")
+}
+
+/// Strictly validates the structure of a safetensors, GGUF or ONNX file.
+pub(crate) fn validate(file_path: &Path) -> anyhow::Result<Validation> {
+    let violations = if has_extension(file_path, "gguf") {
+        gguf::validate(file_path)?
+    } else if has_extension(file_path, "onnx") {
+        onnx::validate(file_path)?
+    } else {
+        safetensors::validate(file_path)?
+    };
+
+    Ok(Validation {
+        file_path: file_path.to_path_buf(),
+        violations,
+    })
+}
@@ -0,0 +1,89 @@
+//! Inspect, sign and verify machine learning models, the library behind the `tman` command line
+//! tool.
+//!
+//! ```no_run
+//! use tensor_man::DetailLevel;
+//!
+//! let inspection = tensor_man::inspect("model.safetensors", None, DetailLevel::Brief, None)?;
+//! println!("{} tensors", inspection.num_tensors);
+//!
+//! tensor_man::sign("model.safetensors", "private.key")?;
+//! let signers = tensor_man::verify("model.safetensors", &["public.key"])?;
+//! println!("signed by {}", signers[0]);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Signing and verifying don't print anything, they report their progress as
+//! [`tracing`](https://docs.rs/tracing) events, for the subscriber of the application to log.
+//!
+//! When built for WebAssembly only inspection is available, the `wasm` feature exposing it to
+//! JavaScript.
+// most of the crate is only used by the command line tool, not built for WebAssembly
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+use std::path::{Path, PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod core;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+// entry point of tman, not part of the library
+#[cfg(not(target_arch = "wasm32"))]
+#[doc(hidden)]
+pub use crate::cli::run;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::core::signing::Failure;
+pub use crate::core::{
+    data::TensorData,
+    handlers::{handler_for, EmbeddedSignature, Handler, ReadSeek, Scope},
+    DetailLevel, FileType, Inspection, Metadata, TensorDescriptor,
+};
+
+/// Inspects the model file or folder, its format detected from its name unless given. With
+/// [`DetailLevel::Full`] the tensors are listed, only those whose name contains the filter if any.
+pub fn inspect(
+    path: impl AsRef<Path>,
+    format: Option<FileType>,
+    detail: DetailLevel,
+    filter: Option<String>,
+) -> anyhow::Result<Inspection> {
+    let path = path.as_ref();
+    handler_for(format, path, Scope::Inspection)?.inspect(path, detail, filter)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Signs the model file or folder with the private key, writing the signature manifest next to
+/// the file or in the folder. Returns the path of the signature.
+pub fn sign(path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> anyhow::Result<PathBuf> {
+    let path = path.as_ref();
+    cli::sign(cli::SignArgs::new(
+        path.to_path_buf(),
+        key_path.as_ref().to_path_buf(),
+    ))?;
+    Ok(cli::signature_path(path, None))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Verifies the model file or folder against its signature with the public keys, any of them
+/// being enough. Returns the fingerprints of the keys of the valid signatures, the cause of a
+/// failed verification is given by [`Failure::of`].
+pub fn verify(
+    path: impl AsRef<Path>,
+    key_paths: &[impl AsRef<Path>],
+) -> anyhow::Result<Vec<String>> {
+    let (verdict, result) = cli::verdict_of(cli::VerifyArgs::new(
+        path.as_ref().to_path_buf(),
+        key_paths
+            .iter()
+            .map(|key_path| key_path.as_ref().to_path_buf())
+            .collect(),
+    ));
+    result?;
+    Ok(verdict
+        .signers
+        .into_iter()
+        .filter(|signer| signer.trusted)
+        .map(|signer| signer.fingerprint)
+        .collect())
+}
@@ -0,0 +1,85 @@
+//! JavaScript bindings of the SafeTensors, GGUF and ONNX header parsers, for models to be inspected
+//! in the browser without being uploaded anywhere.
+use std::path::{Path, PathBuf};
+
+use wasm_bindgen::prelude::*;
+
+use crate::core::{
+    handlers::{gguf, onnx, safetensors},
+    pipe, DetailLevel, Inspection,
+};
+
+// inspects the content of the file, its format detected from its name or else its first bytes
+fn inspect_content(
+    content: &[u8],
+    file_name: &str,
+    detail: DetailLevel,
+    filter: Option<String>,
+) -> anyhow::Result<Inspection> {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let extension = match extension.as_deref() {
+        Some(ext @ ("safetensors" | "gguf" | "onnx")) => ext,
+        _ => pipe::sniff_extension(content).ok_or_else(|| {
+            anyhow::anyhow!("{} is not a SafeTensors, GGUF or ONNX file", file_name)
+        })?,
+    };
+
+    let mut inspection = match extension {
+        "safetensors" => safetensors::inspect_buffer(content, detail, filter)?,
+        "gguf" => gguf::inspect_buffer(content, detail, filter)?,
+        _ => onnx::inspect_buffer(content, detail, filter)?,
+    };
+    inspection.file_path = PathBuf::from(file_name);
+
+    Ok(inspection)
+}
+
+/// Inspects a SafeTensors, GGUF or ONNX file read in the browser, for instance with
+/// `new Uint8Array(await file.arrayBuffer())`, its format detected from its name or else its first
+/// bytes. Returns the inspection as JSON, with the tensors if full is set, only those whose name
+/// contains the filter if any.
+#[wasm_bindgen]
+pub fn inspect(
+    content: &[u8],
+    file_name: &str,
+    full: bool,
+    filter: Option<String>,
+) -> Result<String, JsError> {
+    let detail = if full {
+        DetailLevel::Full
+    } else {
+        DetailLevel::Brief
+    };
+    let inspection = inspect_content(content, file_name, detail, filter)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+
+    serde_json::to_string(&inspection).map_err(|e| JsError::new(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_content() {
+        let header = br#"{"weight":{"dtype":"F32","shape":[2,2],"data_offsets":[0,16]}}"#;
+        let mut content = (header.len() as u64).to_le_bytes().to_vec();
+        content.extend_from_slice(header);
+        content.extend_from_slice(&[0; 16]);
+
+        // the format is sniffed when the name doesn't tell
+        for file_name in ["model.safetensors", "blob"] {
+            let inspection = inspect_content(&content, file_name, DetailLevel::Full, None).unwrap();
+            assert_eq!(inspection.file_path, PathBuf::from(file_name));
+            assert_eq!(inspection.file_size, content.len() as u64);
+            assert_eq!(inspection.num_tensors, 1);
+            assert_eq!(inspection.data_size, 16);
+            assert_eq!(inspection.tensors.unwrap()[0].shape, vec![2, 2]);
+        }
+
+        assert!(inspect_content(b"PK\x03\x04", "model.zip", DetailLevel::Brief, None).is_err());
+    }
+}